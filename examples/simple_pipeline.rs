@@ -2,7 +2,7 @@
 // Author: Gabriel Demetrios Lafis
 
 use rust_data_processing_engine::{
-    data::{DataSet, DataType, Field, Row, Schema, Value},
+    data::{DataSet, DataType, Field, Row, Schema, Value, format_uuid},
     processing::{FilterProcessor, Pipeline, SelectTransform, AddColumnTransform},
 };
 
@@ -111,8 +111,14 @@ fn print_dataset(dataset: &DataSet) {
                 Value::Boolean(b) => print!("{}", b),
                 Value::Integer(n) => print!("{}", n),
                 Value::Float(f) => print!("{:.1}", f),
+                Value::Decimal(d) => print!("{}", d),
                 Value::String(s) => print!("{}", s),
                 Value::Binary(_) => print!("[binary]"),
+                Value::Point(lat, lon) => print!("({}, {})", lat, lon),
+                Value::Uuid(u) => print!("{}", format_uuid(*u)),
+                Value::Date(d) => print!("{}", d),
+                Value::Timestamp(t) => print!("{}", t),
+                Value::Time(t) => print!("{}", t),
                 Value::Array(_) => print!("[array]"),
                 Value::Map(_) => print!("[map]"),
             }
@@ -0,0 +1,214 @@
+// Chart data and rendering
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::data::{DataSet, Value};
+use crate::processing::{AggregateOptions, DataProcessor, GroupByProcessor};
+use super::{ApiError, ChartRequest};
+
+/// Width of rendered charts in pixels
+const WIDTH: f64 = 640.0;
+
+/// Height of rendered charts in pixels
+const HEIGHT: f64 = 360.0;
+
+/// Space around the plot area for axis labels
+const MARGIN: f64 = 48.0;
+
+/// Colors of the series, reused in order
+const PALETTE: [&str; 6] = ["#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948"];
+
+/// Aggregated data of a chart
+///
+/// Every series has one value per x value, in the same order, with nulls
+/// where a series has no rows at that x value.
+#[derive(Debug, Clone)]
+pub struct Chart {
+    pub x: Vec<Value>,
+    pub series: Vec<ChartSeries>,
+}
+
+/// Values of one group of rows
+#[derive(Debug, Clone)]
+pub struct ChartSeries {
+    pub name: String,
+    pub values: Vec<Value>,
+}
+
+impl Chart {
+    /// Aggregate a dataset into chart data
+    ///
+    /// Rows are grouped by the x column, and by the group column if any, and
+    /// the y column aggregated in each group. Without a y column the rows of
+    /// each group are counted.
+    pub fn build(data: &DataSet, request: &ChartRequest) -> Result<Self, ApiError> {
+        let y = request.y.as_deref().unwrap_or(&request.x);
+        let aggregation = match (&request.aggregation, &request.y) {
+            (Some(aggregation), _) => aggregation.as_str(),
+            (None, Some(_)) => "sum",
+            (None, None) => "count",
+        };
+        
+        let output = format!("{}_{}", aggregation, y);
+        let mut group_by = GroupByProcessor::new().group_by(&request.x);
+        
+        if let Some(group) = &request.group {
+            group_by = group_by.group_by(group);
+        }
+        
+        group_by = group_by.aggregate_named(&output, y, aggregation, &AggregateOptions::default())
+            .map_err(|_| ApiError::ValidationError(format!(
+                "Unknown chart aggregation: {}", aggregation
+            )))?;
+        
+        let result = group_by.process(data)?;
+        
+        // Rows are (x, value) or (x, group, value)
+        let x: Vec<Value> = result.data.iter()
+            .map(|row| row.values[0].clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        
+        let positions: BTreeMap<&Value, usize> = x.iter().enumerate()
+            .map(|(i, value)| (value, i))
+            .collect();
+        
+        let mut series: Vec<ChartSeries> = Vec::new();
+        let mut series_index: BTreeMap<String, usize> = BTreeMap::new();
+        
+        for row in &result.data {
+            let (name, value) = match &request.group {
+                Some(_) => (label(&row.values[1]), &row.values[2]),
+                None => (output.clone(), &row.values[1]),
+            };
+            
+            let index = *series_index.entry(name.clone()).or_insert_with(|| {
+                series.push(ChartSeries { name, values: vec![Value::Null; x.len()] });
+                series.len() - 1
+            });
+            
+            series[index].values[positions[&row.values[0]]] = value.clone();
+        }
+        
+        Ok(Chart { x, series })
+    }
+    
+    /// Render the chart as SVG, as a `bar` or `line` chart
+    pub fn render_svg(&self, kind: &str) -> Result<String, ApiError> {
+        if kind != "bar" && kind != "line" {
+            return Err(ApiError::ValidationError(format!("Unknown chart kind: {}", kind)));
+        }
+        
+        let numbers: Vec<Vec<Option<f64>>> = self.series.iter()
+            .map(|series| series.values.iter().map(number).collect())
+            .collect();
+        
+        // Scale from zero to the largest value, keeping negative values in view
+        let all = numbers.iter().flatten().flatten();
+        let max = all.clone().fold(0.0_f64, |a, &b| a.max(b));
+        let min = all.fold(0.0_f64, |a, &b| a.min(b));
+        let span = if max > min { max - min } else { 1.0 };
+        
+        let plot_width = WIDTH - 2.0 * MARGIN;
+        let plot_height = HEIGHT - 2.0 * MARGIN;
+        let slot = plot_width / self.x.len().max(1) as f64;
+        let y_at = |v: f64| MARGIN + plot_height * (max - v) / span;
+        
+        let mut svg = String::new();
+        let _ = write!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="11">"#,
+            w = WIDTH, h = HEIGHT,
+        );
+        
+        // Axes, with the zero line as the x axis
+        let _ = write!(
+            svg,
+            r##"<line x1="{m}" y1="{m}" x2="{m}" y2="{b}" stroke="#333"/><line x1="{m}" y1="{z:.1}" x2="{r}" y2="{z:.1}" stroke="#333"/>"##,
+            m = MARGIN, b = HEIGHT - MARGIN, r = WIDTH - MARGIN, z = y_at(0.0),
+        );
+        let _ = write!(
+            svg,
+            r#"<text x="{x}" y="{y:.1}" text-anchor="end">{max}</text><text x="{x}" y="{b:.1}" text-anchor="end">{min}</text>"#,
+            x = MARGIN - 4.0, y = MARGIN + 4.0, b = HEIGHT - MARGIN, max = max, min = min,
+        );
+        
+        for (i, value) in self.x.iter().enumerate() {
+            let _ = write!(
+                svg,
+                r#"<text x="{x:.1}" y="{y}" text-anchor="middle">{label}</text>"#,
+                x = MARGIN + slot * (i as f64 + 0.5), y = HEIGHT - MARGIN + 16.0, label = escape(&label(value)),
+            );
+        }
+        
+        for (s, values) in numbers.iter().enumerate() {
+            let color = PALETTE[s % PALETTE.len()];
+            
+            if kind == "bar" {
+                // Bars of a slot sit side by side, one per series
+                let bar = slot * 0.8 / numbers.len() as f64;
+                
+                for (i, value) in values.iter().enumerate() {
+                    if let Some(v) = value {
+                        let (top, bottom) = (y_at(v.max(0.0)), y_at(v.min(0.0)));
+                        let _ = write!(
+                            svg,
+                            r#"<rect x="{x:.1}" y="{y:.1}" width="{w:.1}" height="{h:.1}" fill="{c}"/>"#,
+                            x = MARGIN + slot * (i as f64 + 0.1) + bar * s as f64, y = top, w = bar, h = bottom - top, c = color,
+                        );
+                    }
+                }
+            } else {
+                let points: Vec<String> = values.iter().enumerate()
+                    .filter_map(|(i, value)| value.map(|v| format!("{:.1},{:.1}", MARGIN + slot * (i as f64 + 0.5), y_at(v))))
+                    .collect();
+                
+                let _ = write!(
+                    svg,
+                    r#"<polyline points="{p}" fill="none" stroke="{c}" stroke-width="2"/>"#,
+                    p = points.join(" "), c = color,
+                );
+            }
+            
+            // Legend
+            let _ = write!(
+                svg,
+                r#"<rect x="{x}" y="{y}" width="10" height="10" fill="{c}"/><text x="{tx}" y="{ty}">{name}</text>"#,
+                x = WIDTH - MARGIN + 4.0, y = MARGIN + 16.0 * s as f64, c = color,
+                tx = WIDTH - MARGIN + 18.0, ty = MARGIN + 16.0 * s as f64 + 9.0, name = escape(&self.series[s].name),
+            );
+        }
+        
+        svg.push_str("</svg>");
+        Ok(svg)
+    }
+}
+
+/// Get the label of an axis value or series
+fn label(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Float(f) => f.to_string(),
+        Value::String(s) => s.clone(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Get the numeric value of an aggregate, if it has one
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(i) => Some(*i as f64),
+        Value::Float(f) if f.is_finite() => Some(*f),
+        _ => None,
+    }
+}
+
+/// Escape text for SVG
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -1,101 +1,1543 @@
 // API request handlers
 // Author: Gabriel Demetrios Lafis
 
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder};
+use actix_web::http::header::{self, HeaderName, HeaderValue};
 use serde_json::json;
 use std::sync::Arc;
 
-use crate::data::{DataSet, DataType, Field, Row, Schema, Value};
+use crate::data::{ColumnDrift, ColumnRange, CsvSource, DataSet, DataType, Field, FieldDefault, JsonSource, LineageStep, MsgPackSink, MsgPackSource, NonFiniteFloats, Row, Schema, SchemaValidator, Value, format_date, format_time, format_timestamp, format_uuid, parse_decimal_value, parse_temporal_value, parse_uuid, MSGPACK_CONTENT_TYPE};
 use crate::processing::{
     FilterProcessor, GroupByProcessor, JoinProcessor, JoinType,
     SelectTransform, AddColumnTransform, CastTransform, StatsProcessor, StatsType,
+    MultiCastTransform, CastErrorPolicy, CastFormat, JsonPathTransform,
+    EncodeTransform, DecodeTransform, Encoding,
+    MakePointTransform, GeoDistanceTransform, GeohashTransform, BoundingBox,
+    CurrencyConversionTransform, BinTransform, BinStrategy,
+    ScaleTransform, ScalingMethod, SortProcessor, SortKey,
+    ResampleProcessor, ResampleAggregation, FillMethod,
+    MovingAverageTransform, EwmaTransform,
+    ChangeApplier, ChangeEvent,
+    EncryptColumnTransform, DecryptColumnTransform,
+    FilterExpression, NanPolicy, OverflowPolicy, AggregateOptions,
+    ResultCache, ResultKey, CancellationToken, ProcessingError, DatasetProfile,
+    Diagnostics, Warning, PipelineTemplate, PipelinePlan, TableEstimate, DRY_RUN_SAMPLE_ROWS,
 };
-use crate::storage::DataStorage;
-use super::{ApiError, models::*};
+use crate::storage::{backup_storage, describe_drift, restore_storage, run_maintenance, storage_status, DriftPolicy, ExportOutcome, IncrementalExporter, IngestionService, KeyRing, SessionStorage, StorageError, StorageStatus, ViewDefinition};
+use crate::utils::render_html_report;
+use super::{AccessControl, ApiError, Chart, ExportRoot, Principal, ProcessingTimeout, ResponseLimit, ResponsePolicies, ResponsePolicy, ScopedStorage, models::*};
 
-/// List all datasets
+/// List datasets with summary info, filtered by name prefix or tag, sorted and paginated
 pub async fn list_datasets(
-    storage: web::Data<Arc<dyn DataStorage + Send + Sync>>,
+    storage: ScopedStorage,
+    query: web::Query<ListQuery>,
 ) -> Result<impl Responder, ApiError> {
-    let datasets = storage.list()?;
+    let query = query.into_inner();
+    
+    let sort = query.sort.as_deref().unwrap_or("name");
+    if !["name", "size", "modified", "rows"].contains(&sort) {
+        return Err(ApiError::ValidationError(format!(
+            "Invalid sort key: {}. Use name, size, modified or rows", sort
+        )));
+    }
+    
+    let descending = match query.order.as_deref() {
+        None | Some("asc") => false,
+        Some("desc") => true,
+        Some(order) => return Err(ApiError::ValidationError(format!(
+            "Invalid sort order: {}. Use asc or desc", order
+        ))),
+    };
+    
+    // Filter by name before reading any summary info
+    let mut names = storage.list()?;
+    if let Some(prefix) = &query.prefix {
+        names.retain(|name| name.starts_with(prefix.as_str()));
+    }
+    
+    // Summary info is only read up front when sorting needs it, since it
+    // may mean reading the whole dataset
+    let sort_by_info = sort != "name";
+    
+    let mut datasets = Vec::with_capacity(names.len());
+    for name in names {
+        let tags = storage.tags(&name)?;
+        
+        if let Some(tag) = &query.tag {
+            if !tags.contains(tag) {
+                continue;
+            }
+        }
+        
+        let info = if sort_by_info { Some(storage.info(&name)?) } else { None };
+        datasets.push((name, info, tags));
+    }
+    
+    datasets.sort_by(|(a_name, a, _), (b_name, b, _)| {
+        let ordering = match (sort, a, b) {
+            ("size", Some(a), Some(b)) => a.size_bytes.cmp(&b.size_bytes),
+            ("modified", Some(a), Some(b)) => a.last_modified.cmp(&b.last_modified),
+            ("rows", Some(a), Some(b)) => a.rows.cmp(&b.rows),
+            _ => std::cmp::Ordering::Equal,
+        };
+        
+        let ordering = ordering.then_with(|| a_name.cmp(b_name));
+        if descending { ordering.reverse() } else { ordering }
+    });
+    
+    let total = datasets.len();
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(total);
+    
+    // Read the info of the returned page only when sorting by name
+    let datasets = datasets.into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(name, info, tags)| {
+            let info = match info {
+                Some(info) => info,
+                None => storage.info(&name)?,
+            };
+            
+            Ok(json!({
+                "name": name,
+                "rows": info.rows,
+                "columns": info.columns,
+                "size_bytes": info.size_bytes,
+                "format": info.format,
+                "last_modified": info.last_modified
+                    .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339()),
+                "tags": tags,
+            }))
+        })
+        .collect::<Result<Vec<serde_json::Value>, ApiError>>()?;
     
     Ok(HttpResponse::Ok().json(json!({
         "datasets": datasets,
+        "total": total,
+        "offset": offset,
+        "limit": limit,
     })))
 }
 
 /// Create a new dataset
+///
+/// Accepts the JSON request shape, or a CSV (`text/csv`), NDJSON
+/// (`application/x-ndjson`) or MessagePack (`application/msgpack`) body
+/// with the name and tags given as query parameters. Column types of CSV
+/// bodies are inferred from their values.
 pub async fn create_dataset(
-    storage: web::Data<Arc<dyn DataStorage + Send + Sync>>,
-    payload: web::Json<CreateDatasetRequest>,
+    storage: ScopedStorage,
+    request: HttpRequest,
+    query: web::Query<CreateQuery>,
+    body: web::Bytes,
 ) -> Result<impl Responder, ApiError> {
-    let req = payload.into_inner();
+    let query = query.into_inner();
+    
+    let (name, tags, dataset) = match request.content_type() {
+        "text/csv" => {
+            let name = query.name.ok_or_else(|| ApiError::ValidationError(
+                "Missing 'name' query parameter".to_string()
+            ))?;
+            let delimiter = query.delimiter.unwrap_or(',');
+            
+            let dataset = CsvSource::new(&name, query.header.unwrap_or(true), delimiter)
+                .with_type_inference()
+                .read_from(body.as_ref())?;
+            
+            (name, split_tags(query.tags), dataset)
+        },
+        "application/x-ndjson" | "application/ndjson" => {
+            let name = query.name.ok_or_else(|| ApiError::ValidationError(
+                "Missing 'name' query parameter".to_string()
+            ))?;
+            
+            let dataset = JsonSource::new(&name).ndjson().read_from(body.as_ref())?;
+            
+            (name, split_tags(query.tags), dataset)
+        },
+        MSGPACK_CONTENT_TYPE | "application/x-msgpack" => {
+            let name = query.name.ok_or_else(|| ApiError::ValidationError(
+                "Missing 'name' query parameter".to_string()
+            ))?;
+            
+            let dataset = MsgPackSource::new(&name).read_from(body.as_ref())?;
+            
+            (name, split_tags(query.tags), dataset)
+        },
+        "application/json" | "" => {
+            let req: CreateDatasetRequest = serde_json::from_slice(&body)
+                .map_err(|e| ApiError::ValidationError(format!("Invalid request body: {}", e)))?;
+            let dataset = dataset_from_request(&req)?;
+            
+            (req.name, req.tags, dataset)
+        },
+        content_type => return Err(ApiError::ValidationError(format!(
+            "Unsupported content type: {}. Use application/json, text/csv, application/x-ndjson or application/msgpack",
+            content_type
+        ))),
+    };
     
     // Check if dataset already exists
-    if storage.exists(&req.name)? {
+    if storage.exists(&name)? {
         return Err(ApiError::Conflict(format!(
-            "Dataset '{}' already exists", req.name
+            "Dataset '{}' already exists", name
         )));
     }
     
-    // Create schema
-    let fields = req.schema.iter()
-        .map(|field| {
-            let data_type = match field.data_type.as_str() {
-                "boolean" => DataType::Boolean,
-                "integer" => DataType::Integer,
-                "float" => DataType::Float,
-                "string" => DataType::String,
-                "binary" => DataType::Binary,
-                _ => return Err(ApiError::ValidationError(format!(
-                    "Invalid data type: {}", field.data_type
-                ))),
-            };
-            
-            Ok(Field::new(field.name.clone(), data_type, field.nullable))
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+    // Store dataset
+    storage.store(&name, &dataset)?;
+    
+    if !tags.is_empty() {
+        storage.set_tags(&name, &tags)?;
+    }
     
-    let schema = Schema::new(fields);
+    Ok(HttpResponse::Created().json(json!({
+        "name": name,
+        "rows": dataset.len(),
+        "tags": storage.tags(&name)?,
+    })))
+}
+
+/// Split comma-separated tags from a query parameter
+fn split_tags(tags: Option<String>) -> Vec<String> {
+    tags.map(|tags| tags.split(',').map(|tag| tag.trim().to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Build a dataset from the JSON create request shape
+fn dataset_from_request(req: &CreateDatasetRequest) -> Result<DataSet, ApiError> {
+    let schema = schema_from_request(&req.schema)?;
     let mut dataset = DataSet::new(schema);
     
     // Add rows
     for row_data in &req.data {
-        let values = row_data.iter()
-            .map(|value| match value {
-                serde_json::Value::Null => Value::Null,
-                serde_json::Value::Bool(b) => Value::Boolean(*b),
-                serde_json::Value::Number(n) => {
-                    if n.is_i64() {
-                        Value::Integer(n.as_i64().unwrap())
-                    } else {
-                        Value::Float(n.as_f64().unwrap())
-                    }
-                },
-                serde_json::Value::String(s) => Value::String(s.clone()),
-                _ => Value::Null,
-            })
-            .collect();
+        let row = typed_row(Row::new(row_data.iter().map(json_to_value).collect()), &dataset.schema)?;
+        dataset.insert_row(row).map_err(|e| ApiError::from(e).in_dataset(&req.name))?;
+    }
+    
+    Ok(dataset)
+}
+
+/// Build a schema from its field definitions
+fn schema_from_request(fields: &[SchemaField]) -> Result<Schema, ApiError> {
+    let fields = fields.iter()
+        .map(|field| {
+            let data_type = DataType::from_str(&field.data_type).map_err(ApiError::from)?;
+            let mut field_def = Field::new(field.name.clone(), data_type, field.nullable);
+            
+            if let Some(generator) = &field.generate {
+                field_def = field_def.with_default(
+                    FieldDefault::from_generator(generator).map_err(ApiError::from)?
+                );
+            } else if let Some(default) = &field.default {
+                let default = parse_temporal_value(json_to_value(default), &field_def.data_type)
+                    .and_then(|default| parse_decimal_value(default, &field_def.data_type))?;
+                field_def = field_def.with_default(FieldDefault::Value(default));
+            }
+            
+            Ok(field_def)
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+    
+    Ok(Schema::new(fields))
+}
+
+
+/// Get a dataset
+///
+/// With a response limit, or `offset` and `limit` query parameters, one page
+/// of rows is returned along with the offset of the next page, if any.
+/// Clients accepting `application/msgpack` get the rows MessagePack encoded,
+/// with the next offset in the `X-Next-Offset` header.
+pub async fn get_dataset(
+    storage: ScopedStorage,
+    request: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<DatasetQuery>,
+    response_limit: Option<web::Data<ResponseLimit>>,
+    response_policies: Option<web::Data<ResponsePolicies>>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    let limit = response_limit.as_deref().cloned().unwrap_or_default();
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    // Page through the rows if asked to, or if the response is limited
+    let offset = query.offset.unwrap_or(0);
+    let page = match (query.limit, limit.max_rows()) {
+        (Some(rows), Some(max_rows)) => Some(rows.min(max_rows)),
+        (rows, max_rows) => rows.or(max_rows),
+    };
+    
+    let mut dataset = if offset == 0 && page.is_none() {
+        storage.load(&name)?
+    } else {
+        storage.load_range(&name, offset, page.unwrap_or(usize::MAX))?
+    };
+    
+    // A full page may be followed by more rows
+    let next_offset = page.filter(|&page| page > 0 && dataset.len() == page)
+        .map(|page| offset + page);
+    
+    apply_response_policy(&response_policies, &name, &mut dataset);
+    
+    if accepts_msgpack(&request) {
+        let hint = "page with the 'offset' and 'limit' query parameters";
+        let mut response = limit.body(MsgPackSink::to_bytes(&dataset), MSGPACK_CONTENT_TYPE, hint)?;
+        
+        if let Some(next_offset) = next_offset {
+            response.headers_mut().insert(HeaderName::from_static("x-next-offset"), HeaderValue::from(next_offset));
+        }
+        
+        return Ok(response);
+    }
+    
+    // Convert to response
+    let schema = dataset.schema.fields.iter()
+        .map(schema_field)
+        .collect::<Vec<_>>();
+    
+    let data = dataset.data.iter()
+        .map(|row| row.values.iter().map(value_to_json).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    
+    limit.json(&json!({
+        "name": name,
+        "schema": schema,
+        "data": data,
+        "rows": dataset.len(),
+        "offset": offset,
+        "next_offset": next_offset,
+    }), "page with the 'offset' and 'limit' query parameters")
+}
+
+/// Check whether a request accepts MessagePack responses
+fn accepts_msgpack(request: &HttpRequest) -> bool {
+    request.headers().get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .map_or(false, |accept| accept.split(',').any(|media_type| {
+            let media_type = media_type.split(';').next().unwrap_or_default().trim();
+            media_type == MSGPACK_CONTENT_TYPE || media_type == "application/x-msgpack"
+        }))
+}
+
+/// Get row count, column count and size of a dataset without loading it
+pub async fn get_dataset_info(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    let info = storage.info(&name)?;
+    
+    let last_modified = info.last_modified
+        .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339());
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "name": name,
+        "rows": info.rows,
+        "columns": info.columns,
+        "size_bytes": info.size_bytes,
+        "format": info.format,
+        "last_modified": last_modified,
+        "immutable": storage.is_immutable(&name)?,
+        "tags": storage.tags(&name)?,
+    })))
+}
+
+/// Replace the tags of a dataset
+pub async fn put_tags(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+    payload: web::Json<TagsRequest>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    storage.set_tags(&name, &payload.tags)?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "name": name,
+        "tags": storage.tags(&name)?,
+    })))
+}
+
+/// Get the history of a dataset's columns
+///
+/// Each column lists how it came to be, oldest step first: renames, the
+/// expressions of added columns and casts. Asking for a column also returns
+/// the columns renamed from it, for impact analysis.
+pub async fn get_lineage(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+    query: web::Query<LineageQuery>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    let mut lineage = storage.lineage(&name)?;
+    
+    if let Some(column) = &query.column {
+        lineage.retain(|current, steps| {
+            current == column || steps.iter().any(|step| matches!(step, LineageStep::Renamed { from } if from == column))
+        });
+    }
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "name": name,
+        "columns": lineage,
+    })))
+}
+
+/// Check a dataset exists and report its size in headers
+pub async fn head_dataset(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    let info = storage.info(&name)?;
+    
+    let mut response = HttpResponse::Ok();
+    response.insert_header(("X-Dataset-Rows", info.rows.to_string()));
+    response.insert_header(("X-Dataset-Columns", info.columns.to_string()));
+    
+    if let Some(last_modified) = info.last_modified {
+        response.insert_header((
+            "Last-Modified",
+            chrono::DateTime::<chrono::Utc>::from(last_modified)
+                .format("%a, %d %b %Y %H:%M:%S GMT")
+                .to_string(),
+        ));
+    }
+    
+    Ok(response.finish())
+}
+
+/// Preview the first rows of a dataset
+pub async fn preview_dataset(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+    query: web::Query<PreviewQuery>,
+    response_policies: Option<web::Data<ResponsePolicies>>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    let rows = query.rows.unwrap_or(20).min(1000);
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    let mut dataset = storage.load_head(&name, rows)?;
+    apply_response_policy(&response_policies, &name, &mut dataset);
+    
+    // Count every row from headers or footers rather than loading them
+    let total_rows = storage.info(&name)?.rows;
+    
+    let schema = dataset.schema.fields.iter()
+        .map(schema_field)
+        .collect::<Vec<_>>();
+    
+    let data = dataset.data.iter()
+        .map(|row| row.values.iter().map(value_to_json).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "name": name,
+        "schema": schema,
+        "data": data,
+        "rows": dataset.len(),
+        "total_rows": total_rows,
+    })))
+}
+
+/// Update a dataset
+pub async fn update_dataset(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+    query: web::Query<DryRunQuery>,
+    payload: web::Json<UpdateDatasetRequest>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    let req = payload.into_inner();
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    // Load dataset
+    let mut dataset = storage.load(&name)?;
+    
+    // Update rows if provided
+    if let Some(data) = req.data {
+        // Clear existing data
+        dataset.data.clear();
+        
+        // Add new rows
+        for row_data in data {
+            let row = typed_row(Row::new(row_data.iter().map(json_to_value).collect()), &dataset.schema)?;
+            dataset.insert_row(row).map_err(|e| ApiError::from(e).in_dataset(&name))?;
+        }
+    }
+    
+    // Store updated dataset
+    if !query.dry_run {
+        storage.store(&name, &dataset)?;
+    }
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "name": name,
+        "rows": dataset.len(),
+        "dry_run": query.dry_run,
+    })))
+}
+
+/// Append rows to an existing dataset
+pub async fn append_rows(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+    query: web::Query<DryRunQuery>,
+    payload: web::Json<AppendRowsRequest>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    let req = payload.into_inner();
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    let (rows, drift) = append_request_rows(&storage, &name, &req)?;
+    
+    // A dry run checks the rows against the schema alone, as a real append does
+    if query.dry_run {
+        let total = storage.info(&name)?.rows;
+        let mut dataset = storage.load_head(&name, 0)?;
+        insert_typed_rows(&mut dataset, rows, &name)?;
+        
+        return Ok(HttpResponse::Ok().json(json!({
+            "name": name,
+            "appended": req.data.len(),
+            "rows": total + req.data.len(),
+            "drift": drift,
+            "dry_run": true,
+        })));
+    }
+    
+    // Load dataset
+    let mut dataset = storage.load(&name)?;
+    insert_typed_rows(&mut dataset, rows, &name)?;
+    
+    // Store updated dataset
+    storage.store(&name, &dataset)?;
+    
+    if !drift.is_empty() {
+        log::warn!("Schema drift in rows appended to '{}': {}", name, describe_drift(&drift));
+    }
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "name": name,
+        "appended": req.data.len(),
+        "rows": dataset.len(),
+        "drift": drift,
+    })))
+}
+
+/// Type and insert the rows of an append request, filling field defaults
+fn insert_typed_rows(dataset: &mut DataSet, rows: Vec<Row>, name: &str) -> Result<(), ApiError> {
+    for row in rows {
+        let row = typed_row(row, &dataset.schema)?;
+        dataset.insert_row(row).map_err(|e| ApiError::from(e).in_dataset(name))?;
+    }
+    
+    Ok(())
+}
+
+/// Get the rows of an append request in the dataset's column order, with the
+/// drift of their schema from the dataset's
+fn append_request_rows(
+    storage: &ScopedStorage,
+    name: &str,
+    req: &AppendRowsRequest,
+) -> Result<(Vec<Row>, Vec<ColumnDrift>), ApiError> {
+    let rows: Vec<Row> = req.data.iter()
+        .map(|row_data| Row::new(row_data.iter().map(json_to_value).collect()))
+        .collect();
+    
+    let batch = match &req.schema {
+        Some(fields) => schema_from_request(fields)?,
+        None => return Ok((rows, Vec::new())),
+    };
+    
+    let policy = DriftPolicy::from_str(req.on_drift.as_deref().unwrap_or("block"))?;
+    let schema = storage.load_head(name, 0)?.schema;
+    let drift = schema.drift(&batch);
+    policy.check(&drift)?;
+    
+    Ok((schema.align_rows(&batch, rows), drift))
+}
+
+/// Compare the schema of a batch with a dataset's
+///
+/// Reports the columns the batch adds, removes or retypes, and whether any
+/// of them keeps its rows from being appended as they are.
+pub async fn check_drift(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+    payload: web::Json<DriftRequest>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    let batch = schema_from_request(&payload.schema)?;
+    let drift = storage.load_head(&name, 0)?.schema.drift(&batch);
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "name": name,
+        "breaking": drift.iter().any(|column| column.breaking),
+        "drift": drift,
+    })))
+}
+
+/// Apply change data capture events to a dataset
+pub async fn apply_changes(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+    payload: web::Json<ApplyChangesRequest>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    let req = payload.into_inner();
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    let events = req.events.iter()
+        .map(ChangeEvent::from_json)
+        .collect::<Result<Vec<_>, _>>()?;
+    
+    // Apply events to the current state and store it
+    let mut dataset = storage.load(&name)?;
+    let summary = ChangeApplier::new(req.key_columns).apply(&mut dataset, &events)?;
+    
+    storage.store(&name, &dataset)?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "name": name,
+        "inserted": summary.inserted,
+        "updated": summary.updated,
+        "deleted": summary.deleted,
+        "rows": dataset.len(),
+    })))
+}
+
+/// Export a dataset as a snapshot or an incremental delta
+///
+/// The export directory is resolved under the export root of the server.
+pub async fn export_dataset(
+    principal: Principal,
+    storage: ScopedStorage,
+    export_root: Option<web::Data<ExportRoot>>,
+    path: web::Path<String>,
+    payload: web::Json<ExportRequest>,
+) -> Result<impl Responder, ApiError> {
+    require_admin(&principal, "export datasets")?;
+    
+    let export_root = export_root.ok_or_else(|| ApiError::ValidationError(
+        "Export is not configured".to_string()
+    ))?;
+    
+    let name = path.into_inner();
+    let req = payload.into_inner();
+    let directory = export_root.resolve(&req.directory)?;
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    let dataset = storage.load(&name)?;
+    let exporter = IncrementalExporter::new(&directory, req.key_columns)?;
+    
+    let response = match exporter.export(&name, &dataset)? {
+        ExportOutcome::Snapshot { file, rows } => json!({
+            "mode": "snapshot",
+            "file": file,
+            "rows": rows,
+        }),
+        ExportOutcome::Delta { file, inserted, updated, deleted } => json!({
+            "mode": "delta",
+            "file": file,
+            "inserted": inserted,
+            "updated": updated,
+            "deleted": deleted,
+        }),
+        ExportOutcome::Unchanged => json!({
+            "mode": "unchanged",
+        }),
+    };
+    
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Delete a dataset
+pub async fn delete_dataset(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+    query: web::Query<DryRunQuery>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    // A dry run reports the dataset that would be deleted
+    if query.dry_run {
+        let info = storage.info(&name)?;
+        
+        return Ok(HttpResponse::Ok().json(json!({
+            "name": name,
+            "rows": info.rows,
+            "columns": info.columns,
+            "size_bytes": info.size_bytes,
+            "dry_run": true,
+        })));
+    }
+    
+    // Delete dataset
+    storage.delete(&name)?;
+    
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Copy a dataset to a new name
+pub async fn copy_dataset(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+    payload: web::Json<DatasetTargetRequest>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    let req = payload.into_inner();
+    
+    storage.copy(&name, &req.target).map_err(move_error)?;
+    
+    Ok(HttpResponse::Created().json(json!({
+        "source": name,
+        "name": req.target,
+    })))
+}
+
+/// Rename a dataset
+pub async fn rename_dataset(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+    payload: web::Json<DatasetTargetRequest>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    let req = payload.into_inner();
+    
+    storage.rename(&name, &req.target).map_err(move_error)?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "source": name,
+        "name": req.target,
+    })))
+}
+
+/// Move a dataset to another namespace
+///
+/// Namespaces are the dotted prefix of a dataset name, so moving `raw.sales`
+/// to the `curated` namespace renames it to `curated.sales`. Namespaces may
+/// be nested: the name keeps only its last segment, so `raw.eu.sales` moves
+/// to `curated.sales`, or to `curated.eu.sales` with namespace `curated.eu`.
+/// An empty namespace moves the dataset to the top level.
+pub async fn move_dataset(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+    payload: web::Json<MoveDatasetRequest>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    let req = payload.into_inner();
+    
+    let base = name.rsplit_once('.').map_or(name.as_str(), |(_, base)| base);
+    let target = if req.namespace.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}.{}", req.namespace, base)
+    };
+    
+    storage.rename(&name, &target).map_err(move_error)?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "source": name,
+        "name": target,
+    })))
+}
+
+/// Map storage errors of copy, rename and move to API errors
+fn move_error(err: StorageError) -> ApiError {
+    match err {
+        StorageError::NotFound(name) => ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )),
+        StorageError::AlreadyExists(name) => ApiError::Conflict(format!(
+            "Dataset '{}' already exists", name
+        )),
+        err => ApiError::from(err),
+    }
+}
+
+/// Mark a dataset immutable so it cannot be overwritten or deleted
+pub async fn publish_dataset(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    storage.set_immutable(&name, true)?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "name": name,
+        "immutable": true,
+    })))
+}
+
+/// Unlock an immutable dataset so it can be modified again
+pub async fn unlock_dataset(
+    principal: Principal,
+    storage: ScopedStorage,
+    path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+    require_admin(&principal, "unlock datasets")?;
+    
+    let name = path.into_inner();
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    storage.set_immutable(&name, false)?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "name": name,
+        "immutable": false,
+    })))
+}
+
+/// List dataset aliases
+pub async fn list_aliases(
+    storage: ScopedStorage,
+) -> Result<impl Responder, ApiError> {
+    let aliases: Vec<serde_json::Value> = storage.aliases()?
+        .into_iter()
+        .map(|(alias, target)| json!({
+            "alias": alias,
+            "target": target,
+        }))
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "aliases": aliases,
+    })))
+}
+
+/// Create or repoint an alias to a dataset
+pub async fn put_alias(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+    payload: web::Json<AliasRequest>,
+) -> Result<impl Responder, ApiError> {
+    let alias = path.into_inner();
+    let req = payload.into_inner();
+    
+    storage.create_alias(&alias, &req.target).map_err(|err| match err {
+        StorageError::NotFound(name) => ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )),
+        StorageError::AlreadyExists(name) => ApiError::Conflict(format!(
+            "Dataset '{}' already exists", name
+        )),
+        err => ApiError::from(err),
+    })?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "alias": alias,
+        "target": req.target,
+    })))
+}
+
+/// Remove an alias, leaving its dataset alone
+pub async fn delete_alias(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+    let alias = path.into_inner();
+    
+    storage.remove_alias(&alias).map_err(|err| match err {
+        StorageError::NotFound(name) => ApiError::NotFound(format!(
+            "Alias '{}' not found", name
+        )),
+        err => ApiError::from(err),
+    })?;
+    
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// List logical views with their definitions
+pub async fn list_views(
+    storage: ScopedStorage,
+) -> Result<impl Responder, ApiError> {
+    let views: Vec<serde_json::Value> = storage.views()?
+        .into_iter()
+        .map(|(name, view)| json!({
+            "name": name,
+            "view": view,
+        }))
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "views": views,
+    })))
+}
+
+/// Create or redefine a view over other datasets
+pub async fn put_view(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+    payload: web::Json<ViewDefinition>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    let view = payload.into_inner();
+    
+    storage.create_view(&name, &view).map_err(|err| match err {
+        StorageError::NotFound(name) => ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )),
+        StorageError::AlreadyExists(name) => ApiError::Conflict(format!(
+            "Dataset '{}' already exists", name
+        )),
+        err => ApiError::from(err),
+    })?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "name": name,
+        "view": view,
+    })))
+}
+
+/// Remove a view, leaving the datasets it reads alone
+pub async fn delete_view(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    
+    storage.remove_view(&name).map_err(|err| match err {
+        StorageError::NotFound(name) => ApiError::NotFound(format!(
+            "View '{}' not found", name
+        )),
+        err => ApiError::from(err),
+    })?;
+    
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// List deleted datasets that can still be restored
+pub async fn list_trash(
+    storage: ScopedStorage,
+) -> Result<impl Responder, ApiError> {
+    let to_rfc3339 = |time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339();
+    
+    let entries: Vec<serde_json::Value> = storage.trash()?
+        .into_iter()
+        .map(|entry| json!({
+            "name": entry.name,
+            "deleted_at": to_rfc3339(entry.deleted_at),
+            "expires_at": entry.expires_at.map(to_rfc3339),
+        }))
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "trash": entries,
+    })))
+}
+
+/// Restore a deleted dataset from the trash
+pub async fn restore_dataset(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    
+    storage.restore(&name).map_err(|err| match err {
+        StorageError::NotFound(name) => ApiError::NotFound(format!(
+            "Dataset '{}' not found in trash", name
+        )),
+        StorageError::AlreadyExists(name) => ApiError::Conflict(format!(
+            "Dataset '{}' already exists", name
+        )),
+        err => ApiError::from(err),
+    })?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "name": name,
+        "restored": true,
+    })))
+}
+
+/// Permanently remove a dataset from the trash
+pub async fn purge_dataset(
+    storage: ScopedStorage,
+    path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+    let name = path.into_inner();
+    
+    storage.purge(Some(&name)).map_err(|err| match err {
+        StorageError::NotFound(name) => ApiError::NotFound(format!(
+            "Dataset '{}' not found in trash", name
+        )),
+        err => ApiError::from(err),
+    })?;
+    
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Permanently remove all datasets from the trash
+pub async fn purge_trash(
+    principal: Principal,
+    storage: ScopedStorage,
+) -> Result<impl Responder, ApiError> {
+    require_admin(&principal, "purge the trash")?;
+    
+    let purged = storage.purge(None)?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "purged": purged,
+    })))
+}
+
+/// Run storage maintenance and report the space reclaimed
+pub async fn maintain_storage(
+    principal: Principal,
+    storage: ScopedStorage,
+) -> Result<impl Responder, ApiError> {
+    require_admin(&principal, "run maintenance")?;
+    
+    let report = run_maintenance(&*storage)?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "datasets": report.datasets,
+        "total_bytes": report.total_bytes,
+        "trash_purged": report.trash_purged,
+        "aliases_removed": report.aliases_removed,
+        "cache_evicted": report.cache_evicted,
+        "files_reencrypted": report.files_reencrypted,
+        "sessions_expired": report.sessions_expired,
+        "bytes_reclaimed": report.bytes_reclaimed,
+        "writes_replayed": report.writes_replayed,
+    })))
+}
+
+/// Download a backup of every dataset as a tar archive
+pub async fn create_backup(
+    storage: ScopedStorage,
+    principal: Principal,
+) -> Result<impl Responder, ApiError> {
+    require_admin(&principal, "back up storage")?;
+    
+    let mut archive = Vec::new();
+    backup_storage(&*storage, &mut archive)?;
+    
+    let file_name = format!("backup-{}.tar", chrono::Utc::now().format("%Y%m%dT%H%M%SZ"));
+    
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-tar")
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", file_name)))
+        .body(archive))
+}
+
+/// Restore the datasets of a backup uploaded as a tar archive
+pub async fn restore_backup(
+    storage: ScopedStorage,
+    principal: Principal,
+    query: web::Query<RestoreQuery>,
+    body: web::Bytes,
+) -> Result<impl Responder, ApiError> {
+    require_admin(&principal, "restore storage")?;
+    
+    let manifest = restore_storage(&*storage, body.as_ref(), query.overwrite)?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "created_at": manifest.created_at,
+        "datasets": manifest.datasets.iter().map(|dataset| &dataset.name).collect::<Vec<_>>(),
+        "aliases": manifest.aliases.len(),
+        "views": manifest.views.len(),
+    })))
+}
+
+/// Describe the result cache and the datasets cached by the storage
+pub async fn admin_cache(
+    storage: ScopedStorage,
+    principal: Principal,
+    result_cache: Option<web::Data<ResultCache>>,
+) -> Result<impl Responder, ApiError> {
+    require_admin(&principal, "inspect caches")?;
+    
+    let results = match &result_cache {
+        Some(cache) => {
+            let stats = cache.stats()?;
+            let entries: Vec<_> = cache.entries()?.into_iter()
+                .map(|entry| json!({
+                    "config": entry.config,
+                    "inputs": entry.inputs,
+                    "rows": entry.rows,
+                    "columns": entry.columns,
+                }))
+                .collect();
+            
+            json!({
+                "capacity": stats.capacity,
+                "hits": stats.hits,
+                "misses": stats.misses,
+                "entries": entries,
+            })
+        },
+        None => serde_json::Value::Null,
+    };
+    
+    let mut status = StorageStatus::default();
+    storage.describe(&mut status)?;
+    
+    let datasets: Vec<_> = status.cached.iter()
+        .map(|cached| json!({
+            "name": cached.name,
+            "rows": cached.rows,
+            "columns": cached.columns,
+            "ttl_seconds": cached.expires_in.map(|ttl| ttl.as_secs_f64()),
+        }))
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "results": results,
+        "datasets": datasets,
+    })))
+}
+
+/// Drop every cached processing result and cached dataset
+pub async fn clear_admin_cache(
+    storage: ScopedStorage,
+    principal: Principal,
+    result_cache: Option<web::Data<ResultCache>>,
+) -> Result<impl Responder, ApiError> {
+    require_admin(&principal, "clear caches")?;
+    
+    let results_cleared = match &result_cache {
+        Some(cache) => {
+            let cleared = cache.stats()?.entries;
+            cache.clear()?;
+            cleared
+        },
+        None => 0,
+    };
+    
+    let datasets_cleared = storage.clear_cache()?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "results_cleared": results_cleared,
+        "datasets_cleared": datasets_cleared,
+    })))
+}
+
+/// Describe the storage backend and its layers, and check its health
+///
+/// Responds with 503 Service Unavailable when the storage cannot list its
+/// datasets, so the endpoint can serve as a readiness probe.
+pub async fn admin_storage(
+    storage: ScopedStorage,
+    principal: Principal,
+) -> Result<impl Responder, ApiError> {
+    require_admin(&principal, "inspect storage")?;
+    
+    let status = storage_status(&*storage);
+    
+    let mut response = if status.healthy {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::ServiceUnavailable()
+    };
+    
+    Ok(response.json(json!({
+        "backend": status.backend,
+        "layers": status.layers,
+        "location": status.location,
+        "format": status.format,
+        "datasets": status.datasets,
+        "total_bytes": status.total_bytes,
+        "cached_datasets": status.cached.len(),
+        "memory": status.memory.map(|memory| json!({
+            "used_bytes": memory.used_bytes,
+            "max_bytes": memory.max_bytes,
+            "datasets": memory.datasets,
+            "evicted": memory.evicted,
+            "spilled": memory.spilled,
+            "rejected": memory.rejected,
+        })),
+        "healthy": status.healthy,
+        "error": status.error,
+    })))
+}
+
+/// Check that the caller is an administrator before a storage-wide action
+fn require_admin(principal: &Principal, action: &str) -> Result<(), ApiError> {
+    if !principal.is_admin() {
+        return Err(ApiError::Forbidden(format!("Only administrators can {}", action)));
+    }
+    
+    Ok(())
+}
+
+/// Report the ingestion results of every landing directory
+pub async fn ingestion_status(
+    _principal: Principal,
+    ingestion: Option<web::Data<IngestionService>>,
+) -> Result<impl Responder, ApiError> {
+    let ingestion = enabled_ingestion(&ingestion)?;
+    
+    let zones = ingestion.statuses()?.into_iter()
+        .zip(ingestion.zones())
+        .map(|((dataset, status), zone)| json!({
+            "dataset": dataset,
+            "landing_dir": zone.landing_dir().display().to_string(),
+            "pending_files": zone.pending_files().map(|files| files.len()).unwrap_or(0),
+            "files_ingested": status.files_ingested,
+            "files_quarantined": status.files_quarantined,
+            "rows_ingested": status.rows_ingested,
+            "last_file": status.last_file,
+            "last_error": status.last_error,
+            "last_ingested_at": status.last_ingested_at.map(|at| at.to_rfc3339()),
+            "last_drift": status.last_drift,
+        }))
+        .collect::<Vec<_>>();
+    
+    Ok(HttpResponse::Ok().json(json!({ "zones": zones })))
+}
+
+/// Ingest the files waiting in the landing directories now
+pub async fn scan_ingestion(
+    _principal: Principal,
+    ingestion: Option<web::Data<IngestionService>>,
+) -> Result<impl Responder, ApiError> {
+    let ingested = enabled_ingestion(&ingestion)?.scan()?;
+    
+    Ok(HttpResponse::Ok().json(json!({ "ingested": ingested })))
+}
+
+/// Get the ingestion service, failing if no landing directories are configured
+fn enabled_ingestion(ingestion: &Option<web::Data<IngestionService>>) -> Result<&IngestionService, ApiError> {
+    ingestion.as_ref().map(|ingestion| ingestion.get_ref()).ok_or_else(|| ApiError::ValidationError(
+        "Ingestion is not configured".to_string()
+    ))
+}
+
+/// Get the access control of the server, checking that the caller is an administrator
+fn admin_access(
+    principal: &Principal,
+    access: Option<web::Data<AccessControl>>,
+) -> Result<web::Data<AccessControl>, ApiError> {
+    let access = access.ok_or_else(|| ApiError::ValidationError(
+        "Access control is not configured".to_string()
+    ))?;
+    
+    if !principal.is_admin() {
+        return Err(ApiError::Forbidden("Only administrators can manage policies".to_string()));
+    }
+    
+    Ok(access)
+}
+
+/// Open a session for temporary datasets
+///
+/// Like every session endpoint, this needs a valid API key when access
+/// control is on.
+pub async fn create_session(
+    _principal: Principal,
+    sessions: Option<web::Data<SessionStorage>>,
+) -> Result<impl Responder, ApiError> {
+    let sessions = enabled_sessions(&sessions)?;
+    let session = sessions.create_session()?;
+    
+    Ok(HttpResponse::Created().json(json!({
+        "session": session,
+        "ttl_secs": sessions.ttl().as_secs(),
+    })))
+}
+
+/// List the temporary datasets of a session
+pub async fn get_session(
+    _principal: Principal,
+    sessions: Option<web::Data<SessionStorage>>,
+    path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+    let session = path.into_inner();
+    let datasets = enabled_sessions(&sessions)?.session_datasets(&session)
+        .map_err(|err| session_error(&session, err))?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "session": session,
+        "datasets": datasets,
+    })))
+}
+
+/// End a session, dropping its temporary datasets
+pub async fn delete_session(
+    _principal: Principal,
+    sessions: Option<web::Data<SessionStorage>>,
+    path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+    let session = path.into_inner();
+    enabled_sessions(&sessions)?.end_session(&session)
+        .map_err(|err| session_error(&session, err))?;
+    
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Get the session storage, if sessions are enabled
+fn enabled_sessions(sessions: &Option<web::Data<SessionStorage>>) -> Result<&SessionStorage, ApiError> {
+    sessions.as_ref().map(|sessions| sessions.get_ref()).ok_or_else(|| ApiError::ValidationError(
+        "Sessions are not enabled".to_string()
+    ))
+}
+
+/// Report a missing or expired session as not found
+fn session_error(session: &str, err: StorageError) -> ApiError {
+    match err {
+        StorageError::NotFound(_) => ApiError::NotFound(format!("Session '{}' not found or expired", session)),
+        err => err.into(),
+    }
+}
+
+/// List row-level security policies by dataset and role
+pub async fn list_policies(
+    principal: Principal,
+    access: Option<web::Data<AccessControl>>,
+) -> Result<impl Responder, ApiError> {
+    let access = admin_access(&principal, access)?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "policies": access.policies()?,
+    })))
+}
+
+/// Attach a row filter to a dataset for a role
+pub async fn put_policy(
+    principal: Principal,
+    access: Option<web::Data<AccessControl>>,
+    result_cache: Option<web::Data<ResultCache>>,
+    path: web::Path<(String, String)>,
+    payload: web::Json<PolicyRequest>,
+) -> Result<impl Responder, ApiError> {
+    let access = admin_access(&principal, access)?;
+    let (name, role) = path.into_inner();
+    let req = payload.into_inner();
+    
+    let filter = FilterExpression::parse(&req.filter)
+        .map_err(|e| ApiError::ValidationError(e.to_string()))?;
+    
+    access.set_policy(&name, &role, filter)?;
+    forget_results(&result_cache)?;
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "dataset": name,
+        "role": role,
+        "filter": req.filter,
+    })))
+}
+
+/// Remove the row filter of a role from a dataset
+pub async fn delete_policy(
+    principal: Principal,
+    access: Option<web::Data<AccessControl>>,
+    result_cache: Option<web::Data<ResultCache>>,
+    path: web::Path<(String, String)>,
+) -> Result<impl Responder, ApiError> {
+    let access = admin_access(&principal, access)?;
+    let (name, role) = path.into_inner();
+    
+    access.remove_policy(&name, &role).map_err(|err| match err {
+        StorageError::NotFound(msg) => ApiError::NotFound(msg),
+        err => ApiError::from(err),
+    })?;
+    forget_results(&result_cache)?;
+    
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Drop cached results after a policy change alters what roles can read
+fn forget_results(cache: &Option<web::Data<ResultCache>>) -> Result<(), ApiError> {
+    if let Some(cache) = cache {
+        cache.clear()?;
+    }
+    
+    Ok(())
+}
+
+/// Get a single column with summary statistics
+pub async fn get_column(
+    storage: ScopedStorage,
+    path: web::Path<(String, String)>,
+    query: web::Query<ColumnQuery>,
+    response_policies: Option<web::Data<ResponsePolicies>>,
+) -> Result<impl Responder, ApiError> {
+    let (name, column) = path.into_inner();
+    
+    // Check if dataset exists
+    if !storage.exists(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "Dataset '{}' not found", name
+        )));
+    }
+    
+    // Load only the requested column, with no statistics of redacted values
+    let mut dataset = match storage.load_columns(&name, &[column.clone()]) {
+        Ok(dataset) => dataset,
+        Err(err) => {
+            // Backends report missing columns differently, so check the schema only once projecting failed
+            if storage.load_head(&name, 0)?.schema.get_field_by_name(&column).is_none() {
+                return Err(ApiError::NotFound(format!(
+                    "Column '{}' not found in dataset '{}'", column, name
+                )));
+            }
+            
+            return Err(err.into());
+        },
+    };
+    apply_response_policy(&response_policies, &name, &mut dataset);
+    let col_idx = 0;
+    
+    let field = &dataset.schema.fields[col_idx];
+    
+    // Page through the column values
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(100);
+    
+    let values = dataset.data.iter()
+        .skip(offset)
+        .take(limit)
+        .map(|row| value_to_json(&row.values[col_idx]))
+        .collect::<Vec<_>>();
+    
+    // Compute summary statistics
+    let null_count = dataset.data.iter()
+        .filter(|row| matches!(row.values[col_idx], Value::Null))
+        .count();
+    
+    let mut stats = json!({
+        "count": dataset.len() - null_count,
+        "null_count": null_count,
+    });
+    
+    if field.data_type.is_numeric() {
+        let numeric_stats = [
+            ("mean", StatsProcessor::mean(&column)),
+            ("min", StatsProcessor::min(&column)),
+            ("max", StatsProcessor::max(&column)),
+            ("sum", StatsProcessor::sum(&column)),
+            ("std_dev", StatsProcessor::std_dev(&column)),
+        ];
         
-        let row = Row::new(values);
-        dataset.add_row(row).map_err(ApiError::from)?;
+        for (key, processor) in numeric_stats {
+            let result = processor.process(&dataset)?;
+            stats[key] = result.data.get(0)
+                .and_then(|row| row.values.get(0))
+                .map(value_to_json)
+                .unwrap_or(serde_json::Value::Null);
+        }
     }
     
-    // Store dataset
-    storage.store(&req.name, &dataset)?;
-    
-    Ok(HttpResponse::Created().json(json!({
-        "name": req.name,
-        "rows": dataset.len(),
+    Ok(HttpResponse::Ok().json(json!({
+        "dataset": name,
+        "column": schema_field(field),
+        "offset": offset,
+        "limit": limit,
+        "total": dataset.len(),
+        "values": values,
+        "stats": stats,
     })))
 }
 
-/// Get a dataset
-pub async fn get_dataset(
-    storage: web::Data<Arc<dyn DataStorage + Send + Sync>>,
+/// Aggregate a dataset into chart-ready series, or render them as SVG
+pub async fn chart_dataset(
+    storage: ScopedStorage,
     path: web::Path<String>,
+    payload: web::Json<ChartRequest>,
 ) -> Result<impl Responder, ApiError> {
     let name = path.into_inner();
+    let req = payload.into_inner();
     
     // Check if dataset exists
     if !storage.exists(&name)? {
@@ -104,62 +1546,42 @@ pub async fn get_dataset(
         )));
     }
     
-    // Load dataset
-    let dataset = storage.load(&name)?;
-    
-    // Convert to response
-    let schema = dataset.schema.fields.iter()
-        .map(|field| SchemaField {
-            name: field.name.clone(),
-            data_type: match field.data_type {
-                DataType::Boolean => "boolean".to_string(),
-                DataType::Integer => "integer".to_string(),
-                DataType::Float => "float".to_string(),
-                DataType::String => "string".to_string(),
-                DataType::Binary => "binary".to_string(),
-                _ => "unknown".to_string(),
-            },
-            nullable: field.nullable,
-        })
-        .collect::<Vec<_>>();
-    
-    let data = dataset.data.iter()
-        .map(|row| {
-            row.values.iter()
-                .map(|value| match value {
-                    Value::Null => serde_json::Value::Null,
-                    Value::Boolean(b) => serde_json::Value::Bool(*b),
-                    Value::Integer(i) => serde_json::Value::Number((*i).into()),
-                    Value::Float(f) => {
-                        serde_json::Number::from_f64(*f)
-                            .map(serde_json::Value::Number)
-                            .unwrap_or(serde_json::Value::Null)
-                    },
-                    Value::String(s) => serde_json::Value::String(s.clone()),
-                    Value::Binary(_) => serde_json::Value::String("[binary data]".to_string()),
-                    Value::Array(_) => serde_json::Value::String("[array]".to_string()),
-                    Value::Map(_) => serde_json::Value::String("[map]".to_string()),
-                })
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<_>>();
+    let chart = Chart::build(&storage.load(&name)?, &req)?;
     
-    Ok(HttpResponse::Ok().json(json!({
-        "name": name,
-        "schema": schema,
-        "data": data,
-        "rows": dataset.len(),
-    })))
+    match req.format.as_deref().unwrap_or("json") {
+        "json" => {
+            let series = chart.series.iter()
+                .map(|series| json!({
+                    "name": series.name,
+                    "values": series.values.iter().map(value_to_json).collect::<Vec<_>>(),
+                }))
+                .collect::<Vec<_>>();
+            
+            Ok(HttpResponse::Ok().json(json!({
+                "name": name,
+                "x": chart.x.iter().map(value_to_json).collect::<Vec<_>>(),
+                "series": series,
+            })))
+        },
+        "svg" => {
+            let svg = chart.render_svg(req.kind.as_deref().unwrap_or("bar"))?;
+            Ok(HttpResponse::Ok().content_type("image/svg+xml").body(svg))
+        },
+        format => Err(ApiError::ValidationError(format!(
+            "Unknown chart format: {}", format
+        ))),
+    }
 }
 
-/// Update a dataset
-pub async fn update_dataset(
-    storage: web::Data<Arc<dyn DataStorage + Send + Sync>>,
+/// Render the profile of a dataset as a standalone HTML report
+pub async fn report_dataset(
+    storage: ScopedStorage,
     path: web::Path<String>,
-    payload: web::Json<UpdateDatasetRequest>,
+    query: web::Query<ReportQuery>,
+    response_policies: Option<web::Data<ResponsePolicies>>,
 ) -> Result<impl Responder, ApiError> {
     let name = path.into_inner();
-    let req = payload.into_inner();
+    let query = query.into_inner();
     
     // Check if dataset exists
     if !storage.exists(&name)? {
@@ -168,52 +1590,26 @@ pub async fn update_dataset(
         )));
     }
     
-    // Load dataset
     let mut dataset = storage.load(&name)?;
+    apply_response_policy(&response_policies, &name, &mut dataset);
     
-    // Update rows if provided
-    if let Some(data) = req.data {
-        // Clear existing data
-        dataset.data.clear();
-        
-        // Add new rows
-        for row_data in data {
-            let values = row_data.iter()
-                .map(|value| match value {
-                    serde_json::Value::Null => Value::Null,
-                    serde_json::Value::Bool(b) => Value::Boolean(*b),
-                    serde_json::Value::Number(n) => {
-                        if n.is_i64() {
-                            Value::Integer(n.as_i64().unwrap())
-                        } else {
-                            Value::Float(n.as_f64().unwrap())
-                        }
-                    },
-                    serde_json::Value::String(s) => Value::String(s.clone()),
-                    _ => Value::Null,
-                })
-                .collect();
-            
-            let row = Row::new(values);
-            dataset.add_row(row).map_err(ApiError::from)?;
-        }
-    }
-    
-    // Store updated dataset
-    storage.store(&name, &dataset)?;
+    let profile = DatasetProfile::compute(&dataset, query.bins.unwrap_or(10))?;
+    dataset.data.truncate(query.sample.unwrap_or(20));
     
-    Ok(HttpResponse::Ok().json(json!({
-        "name": name,
-        "rows": dataset.len(),
-    })))
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render_html_report(&name, &profile, &dataset)))
 }
 
-/// Delete a dataset
-pub async fn delete_dataset(
-    storage: web::Data<Arc<dyn DataStorage + Send + Sync>>,
+/// Search string columns of a dataset
+pub async fn search_dataset(
+    storage: ScopedStorage,
     path: web::Path<String>,
+    query: web::Query<SearchQuery>,
+    response_policies: Option<web::Data<ResponsePolicies>>,
 ) -> Result<impl Responder, ApiError> {
     let name = path.into_inner();
+    let query = query.into_inner();
     
     // Check if dataset exists
     if !storage.exists(&name)? {
@@ -222,18 +1618,91 @@ pub async fn delete_dataset(
         )));
     }
     
-    // Delete dataset
-    storage.delete(&name)?;
+    // Load dataset, searching the values as they are shown so redacted ones cannot be probed
+    let mut dataset = storage.load(&name)?;
+    apply_response_policy(&response_policies, &name, &mut dataset);
     
-    Ok(HttpResponse::NoContent().finish())
+    // Resolve the columns to search, defaulting to all string columns
+    let col_indices = match &query.columns {
+        Some(columns) => columns.split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|col| {
+                dataset.schema.fields.iter()
+                    .position(|field| field.name == col)
+                    .ok_or_else(|| ApiError::ValidationError(format!(
+                        "Column '{}' not found", col
+                    )))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => dataset.schema.fields.iter().enumerate()
+            .filter(|(_, field)| field.data_type == DataType::String)
+            .map(|(i, _)| i)
+            .collect(),
+    };
+    
+    // Build a case-insensitive matcher
+    let pattern = if query.regex.unwrap_or(false) {
+        query.q.clone()
+    } else {
+        regex::escape(&query.q)
+    };
+    
+    let matcher = regex::RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| ApiError::ValidationError(format!(
+            "Invalid search pattern: {}", e
+        )))?;
+    
+    // Find matching rows
+    let matches = dataset.data.iter()
+        .enumerate()
+        .filter(|(_, row)| {
+            col_indices.iter().any(|&i| match &row.values[i] {
+                Value::String(s) => matcher.is_match(s),
+                _ => false,
+            })
+        })
+        .collect::<Vec<_>>();
+    
+    // Page through the matches
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(100);
+    
+    let data = matches.iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(i, row)| json!({
+            "row": i,
+            "values": row.values.iter().map(value_to_json).collect::<Vec<_>>(),
+        }))
+        .collect::<Vec<_>>();
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "dataset": name,
+        "query": query.q,
+        "offset": offset,
+        "limit": limit,
+        "total": matches.len(),
+        "data": data,
+    })))
 }
 
 /// Transform a dataset
 pub async fn transform_dataset(
-    storage: web::Data<Arc<dyn DataStorage + Send + Sync>>,
+    storage: ScopedStorage,
+    principal: Principal,
+    result_cache: Option<web::Data<ResultCache>>,
+    processing_timeout: Option<web::Data<ProcessingTimeout>>,
+    response_limit: Option<web::Data<ResponseLimit>>,
+    sessions: Option<web::Data<SessionStorage>>,
+    query: web::Query<DryRunQuery>,
     payload: web::Json<TransformRequest>,
 ) -> Result<impl Responder, ApiError> {
     let req = payload.into_inner();
+    let token = request_token(&processing_timeout);
+    let target = result_target(&sessions, req.session.as_deref(), req.target.clone())?;
     
     // Check if source dataset exists
     if !storage.exists(&req.source)? {
@@ -242,10 +1711,50 @@ pub async fn transform_dataset(
         )));
     }
     
-    // Load source dataset
-    let source = storage.load(&req.source)?;
+    // Serve unchanged inputs from the result cache, unless only planning
+    let cache_key = result_cache_key(&result_cache, &principal, "transform", &req, &[&req.source])?;
+    if let Some(result) = cached_result(&result_cache, &cache_key)?.filter(|_| !query.dry_run) {
+        return result_response(&storage, &response_limit, target, &result, &[]);
+    }
+    
+    // Load source dataset, or only a sample of it for a dry run
+    let (source, input_rows) = load_source(&storage, &req.source, &[], query.dry_run)?;
     
     // Apply transformation
+    let (result, warnings) = collect_warnings(|| apply_transform(&storage, &req, &source, query.dry_run, &token));
+    let result = match result {
+        Ok(result) => Ok(result),
+        // Processing errors, cancellation included, go through the timeout check
+        Err(ApiError::ProcessingError(err)) => Err(err),
+        Err(err) => return Err(err.in_dataset(&req.source)),
+    };
+    let result = check_timeout(&processing_timeout, "transform", &token, result)
+        .map_err(|e| e.in_dataset(&req.source))?;
+    
+    if query.dry_run {
+        let plan = PipelinePlan::from_step("transform", &source, input_rows, &result);
+        return plan_response(&storage, target, &req.source, &plan, &warnings);
+    }
+    
+    remember_result(&result_cache, cache_key, &result, &warnings)?;
+    
+    result_response(&storage, &response_limit, target, &result, &warnings)
+}
+
+/// Apply the transformation of a transform request to its source dataset
+///
+/// Dry runs skip writing the rejected rows of casts. Casts, encryption and
+/// time-series transforms stop once the token is cancelled; the others are
+/// only started if it is not.
+fn apply_transform(
+    storage: &ScopedStorage,
+    req: &TransformRequest,
+    source: &DataSet,
+    dry_run: bool,
+    token: &CancellationToken,
+) -> Result<DataSet, ApiError> {
+    token.check()?;
+    
     let result = match req.transform_type.as_str() {
         "select" => {
             let columns = req.params.get("columns")
@@ -258,7 +1767,7 @@ pub async fn transform_dataset(
                 .collect::<Vec<_>>();
             
             let transform = SelectTransform::new(columns);
-            transform.process(&source)?
+            transform.process(source)?
         },
         "add_column" => {
             let name = req.params.get("name")
@@ -302,84 +1811,517 @@ pub async fn transform_dataset(
                 _ => Value::Null,
             };
             
-            let transform = AddColumnTransform::with_constant(name, data_type, true, value);
-            transform.process(&source)?
+            let transform = AddColumnTransform::with_constant(name, data_type, true, value);
+            transform.process(source)?
+        },
+        "cast" => {
+            let column = req.params.get("column")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'column' parameter".to_string()
+                ))?;
+            
+            let target_type = req.params.get("target_type")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'target_type' parameter".to_string()
+                ))?;
+            
+            let data_type = match target_type {
+                "boolean" => DataType::Boolean,
+                "integer" => DataType::Integer,
+                "float" => DataType::Float,
+                "string" => DataType::String,
+                _ => return Err(ApiError::ValidationError(format!(
+                    "Invalid target type: {}", target_type
+                ))),
+            };
+            
+            let transform = CastTransform::new(column, data_type)
+                .with_format(parse_cast_format(&req.params)?)
+                .with_cancellation(token.clone());
+            transform.process(source)?
+        },
+        "multi_cast" => {
+            let casts = req.params.get("casts")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'casts' parameter".to_string()
+                ))?;
+            
+            let policy = match req.params.get("on_error").and_then(|v| v.as_str()).unwrap_or("fail") {
+                "fail" => CastErrorPolicy::Fail,
+                "null" => CastErrorPolicy::Null,
+                "default" => CastErrorPolicy::Default,
+                "reject" => CastErrorPolicy::Reject,
+                other => return Err(ApiError::ValidationError(format!(
+                    "Invalid error policy: {}", other
+                ))),
+            };
+            
+            let mut transform = MultiCastTransform::new(policy)
+                .with_format(parse_cast_format(&req.params)?)
+                .with_cancellation(token.clone());
+            
+            for cast in casts {
+                let column = cast.get("column")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| ApiError::ValidationError(
+                        "Missing or invalid 'column' in cast".to_string()
+                    ))?;
+                
+                let target_type = cast.get("target_type")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| ApiError::ValidationError(
+                        "Missing or invalid 'target_type' in cast".to_string()
+                    ))?;
+                
+                let default = cast.get("default")
+                    .map(json_to_value)
+                    .unwrap_or(Value::Null);
+                
+                transform = transform.cast_with_default(column, parse_data_type(target_type)?, default);
+                
+                if let Some(date_format) = cast.get("date_format").and_then(|v| v.as_str()) {
+                    transform = transform.with_date_format(column, date_format);
+                }
+            }
+            
+            // Only the reject policy produces rejected rows, and they never
+            // replace an existing dataset unless asked to
+            let rejects_target = req.params.get("rejects_target").and_then(|v| v.as_str());
+            if let Some(rejects_target) = rejects_target {
+                if policy != CastErrorPolicy::Reject {
+                    return Err(ApiError::ValidationError(
+                        "'rejects_target' requires the 'reject' error policy".to_string()
+                    ));
+                }
+                
+                let overwrite = req.params.get("overwrite_rejects").and_then(|v| v.as_bool()).unwrap_or(false);
+                if !overwrite && storage.exists(rejects_target)? {
+                    return Err(ApiError::Conflict(format!(
+                        "Dataset '{}' already exists; set 'overwrite_rejects' to replace it", rejects_target
+                    )));
+                }
+            }
+            
+            let (result, rejects) = transform.process_with_rejects(source)?;
+            
+            // Store rejected rows if requested
+            if let Some(rejects_target) = rejects_target.filter(|_| !dry_run) {
+                storage.store(rejects_target, &rejects)?;
+            }
+            
+            result
+        },
+        "json_path" => {
+            let column = req.params.get("column")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'column' parameter".to_string()
+                ))?;
+            
+            let extractions = req.params.get("extractions")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'extractions' parameter".to_string()
+                ))?;
+            
+            let mut transform = JsonPathTransform::new(column);
+            
+            for extraction in extractions {
+                let path = extraction.get("path").and_then(|v| v.as_str());
+                let output = extraction.get("output").and_then(|v| v.as_str());
+                let data_type = extraction.get("data_type").and_then(|v| v.as_str());
+                
+                match (path, output, data_type) {
+                    (Some(path), Some(output), Some(data_type)) => {
+                        transform = transform.extract(path, output, parse_data_type(data_type)?);
+                    },
+                    _ => return Err(ApiError::ValidationError(
+                        "Each extraction requires 'path', 'output' and 'data_type'".to_string()
+                    )),
+                }
+            }
+            
+            transform.process(source)?
+        },
+        "make_point" => {
+            let lat_column = req.params.get("lat_column")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'lat_column' parameter".to_string()
+                ))?;
+            
+            let lon_column = req.params.get("lon_column")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'lon_column' parameter".to_string()
+                ))?;
+            
+            let output = req.params.get("output")
+                .and_then(|v| v.as_str())
+                .unwrap_or("location");
+            
+            MakePointTransform::new(lat_column, lon_column, output).process(source)?
+        },
+        "geo_distance" | "geohash" => {
+            let column = req.params.get("column")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'column' parameter".to_string()
+                ))?;
+            
+            let output = req.params.get("output")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'output' parameter".to_string()
+                ))?;
+            
+            if req.transform_type == "geo_distance" {
+                let lat = float_param(&req.params, "lat")?;
+                let lon = float_param(&req.params, "lon")?;
+                
+                GeoDistanceTransform::new(column, lat, lon, output).process(source)?
+            } else {
+                let precision = req.params.get("precision")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(6) as usize;
+                
+                GeohashTransform::new(column, precision, output).process(source)?
+            }
+        },
+        "bin" => {
+            let column = req.params.get("column")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'column' parameter".to_string()
+                ))?;
+            
+            let strategy = match (req.params.get("edges"), req.params.get("bins").and_then(|v| v.as_u64())) {
+                (Some(edges), _) => {
+                    let edges = edges.as_array()
+                        .ok_or_else(|| ApiError::ValidationError(
+                            "Invalid 'edges' parameter".to_string()
+                        ))?
+                        .iter()
+                        .map(|v| v.as_f64().ok_or_else(|| ApiError::ValidationError(
+                            "Bin edges must be numbers".to_string()
+                        )))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    BinStrategy::Edges(edges)
+                },
+                (None, Some(bins)) => match req.params.get("strategy").and_then(|v| v.as_str()) {
+                    Some("equal_frequency") | Some("quantile") => BinStrategy::EqualFrequency(bins as usize),
+                    Some("equal_width") | None => BinStrategy::EqualWidth(bins as usize),
+                    Some(other) => return Err(ApiError::ValidationError(format!(
+                        "Unknown bin strategy: {}", other
+                    ))),
+                },
+                (None, None) => return Err(ApiError::ValidationError(
+                    "Missing 'bins' or 'edges' parameter".to_string()
+                )),
+            };
+            
+            let mut transform = BinTransform::new(column, strategy);
+            
+            if let Some(output) = req.params.get("output").and_then(|v| v.as_str()) {
+                transform = transform.with_output(output);
+            }
+            
+            if let Some(labels) = req.params.get("labels").and_then(|v| v.as_array()) {
+                transform = transform.with_labels(labels.iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect());
+            }
+            
+            transform.process(source)?
+        },
+        "scale" => {
+            // Reapply the scaling fitted on another dataset if given
+            let transform = match req.params.get("fitted_on").and_then(|v| v.as_str()) {
+                Some(fitted_on) => {
+                    if !storage.exists(fitted_on)? {
+                        return Err(ApiError::NotFound(format!(
+                            "Dataset '{}' not found", fitted_on
+                        )));
+                    }
+                    
+                    ScaleTransform::from_metadata(&storage.load(fitted_on)?.metadata)?
+                },
+                None => {
+                    let columns = req.params.get("columns")
+                        .and_then(|v| v.as_array())
+                        .ok_or_else(|| ApiError::ValidationError(
+                            "Missing or invalid 'columns' parameter".to_string()
+                        ))?
+                        .iter()
+                        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                        .collect::<Vec<_>>();
+                    
+                    let method = req.params.get("method")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("min_max");
+                    
+                    ScaleTransform::new(columns, ScalingMethod::from_str(method)?)
+                },
+            };
+            
+            transform.process(source)?
+        },
+        "resample" => {
+            let time_column = req.params.get("time_column")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'time_column' parameter".to_string()
+                ))?;
+            
+            let frequency = req.params.get("frequency")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'frequency' parameter".to_string()
+                ))?;
+            
+            let mut processor = ResampleProcessor::new(
+                time_column,
+                ResampleProcessor::parse_frequency(frequency)?,
+            )
+            .with_cancellation(token.clone());
+            
+            if let Some(aggregations) = req.params.get("aggregations").and_then(|v| v.as_array()) {
+                for aggregation in aggregations {
+                    let column = aggregation.get("column")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| ApiError::ValidationError(
+                            "Missing or invalid aggregation 'column'".to_string()
+                        ))?;
+                    
+                    let function = aggregation.get("function")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("mean");
+                    
+                    processor = processor.aggregate(column, ResampleAggregation::from_str(function)?);
+                }
+            }
+            
+            if let Some(fill) = req.params.get("fill").and_then(|v| v.as_str()) {
+                processor = processor.with_fill(FillMethod::from_str(fill)?);
+            }
+            
+            processor.process(source)?
+        },
+        "moving_average" | "ewma" => {
+            let column = req.params.get("column")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'column' parameter".to_string()
+                ))?;
+            
+            let output = req.params.get("output")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'output' parameter".to_string()
+                ))?;
+            
+            let partition_by = req.params.get("partition_by")
+                .and_then(|v| v.as_array())
+                .map(|columns| columns.iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect::<Vec<_>>())
+                .unwrap_or_default();
+            
+            let order_by = req.params.get("order_by").and_then(|v| v.as_str());
+            
+            if req.transform_type == "moving_average" {
+                let window = req.params.get("window")
+                    .and_then(|v| v.as_u64())
+                    .ok_or_else(|| ApiError::ValidationError(
+                        "Missing or invalid 'window' parameter".to_string()
+                    ))?;
+                
+                let mut transform = MovingAverageTransform::new(column, window as usize, output)
+                    .partition_by(partition_by)
+                    .with_cancellation(token.clone());
+                
+                if let Some(min_periods) = req.params.get("min_periods").and_then(|v| v.as_u64()) {
+                    transform = transform.with_min_periods(min_periods as usize);
+                }
+                
+                if let Some(order_by) = order_by {
+                    transform = transform.order_by(order_by);
+                }
+                
+                transform.process(source)?
+            } else {
+                let mut transform = match req.params.get("alpha").and_then(|v| v.as_f64()) {
+                    Some(alpha) => EwmaTransform::with_alpha(column, alpha, output),
+                    None => EwmaTransform::new(column, float_param(&req.params, "span")?, output),
+                }
+                .partition_by(partition_by)
+                .with_cancellation(token.clone());
+                
+                if let Some(order_by) = order_by {
+                    transform = transform.order_by(order_by);
+                }
+                
+                transform.process(source)?
+            }
+        },
+        "convert_currency" => {
+            let amount_column = req.params.get("amount_column")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'amount_column' parameter".to_string()
+                ))?;
+            
+            let currency_column = req.params.get("currency_column")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'currency_column' parameter".to_string()
+                ))?;
+            
+            let target_currency = req.params.get("target_currency")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'target_currency' parameter".to_string()
+                ))?;
+            
+            let rates_name = req.params.get("rates")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'rates' parameter".to_string()
+                ))?;
+            
+            if !storage.exists(rates_name)? {
+                return Err(ApiError::NotFound(format!(
+                    "Rates dataset '{}' not found", rates_name
+                )));
+            }
+            
+            let output = req.params.get("output")
+                .and_then(|v| v.as_str())
+                .unwrap_or("converted_amount");
+            
+            let mut transform = CurrencyConversionTransform::new(
+                amount_column,
+                currency_column,
+                target_currency,
+                storage.load(rates_name)?,
+                output,
+            );
+            
+            if let Some(date_column) = req.params.get("date_column").and_then(|v| v.as_str()) {
+                let rate_date_column = req.params.get("rate_date_column")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("date");
+                transform = transform.with_dates(date_column, rate_date_column);
+            }
+            
+            if req.params.get("missing_as_null").and_then(|v| v.as_bool()).unwrap_or(false) {
+                transform = transform.with_missing_as_null();
+            }
+            
+            transform.process(source)?
+        },
+        "encode" | "decode" => {
+            let column = req.params.get("column")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'column' parameter".to_string()
+                ))?;
+            
+            let encoding = req.params.get("encoding")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'encoding' parameter".to_string()
+                ))?;
+            
+            let encoding = Encoding::from_str(encoding)?;
+            
+            if req.transform_type == "encode" {
+                EncodeTransform::new(column, encoding).process(source)?
+            } else if req.params.get("target_type").and_then(|v| v.as_str()) == Some("string") {
+                DecodeTransform::utf8(column, encoding).process(source)?
+            } else {
+                DecodeTransform::new(column, encoding).process(source)?
+            }
+        },
+        "sort" => {
+            // Keys are column names, sorted ascending, or objects with a
+            // column, direction and null placement
+            let keys = req.params.get("columns")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'columns' parameter".to_string()
+                ))?
+                .iter()
+                .map(|v| match v {
+                    serde_json::Value::String(column) => Ok(SortKey::asc(column)),
+                    v => serde_json::from_value::<SortKey>(v.clone()).map_err(|e| ApiError::ValidationError(
+                        format!("Invalid sort key: {}", e)
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            
+            SortProcessor::new(keys).process(source)?
         },
-        "cast" => {
-            let column = req.params.get("column")
-                .and_then(|v| v.as_str())
+        "encrypt" | "decrypt" => {
+            let columns = req.params.get("columns")
+                .and_then(|v| v.as_array())
                 .ok_or_else(|| ApiError::ValidationError(
-                    "Missing or invalid 'column' parameter".to_string()
-                ))?;
+                    "Missing or invalid 'columns' parameter".to_string()
+                ))?
+                .iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect::<Vec<_>>();
             
-            let target_type = req.params.get("target_type")
+            // Keys are given as `id:base64key` entries; the first encrypts
+            let keys = req.params.get("keys")
                 .and_then(|v| v.as_str())
                 .ok_or_else(|| ApiError::ValidationError(
-                    "Missing or invalid 'target_type' parameter".to_string()
+                    "Missing or invalid 'keys' parameter".to_string()
                 ))?;
             
-            let data_type = match target_type {
-                "boolean" => DataType::Boolean,
-                "integer" => DataType::Integer,
-                "float" => DataType::Float,
-                "string" => DataType::String,
-                _ => return Err(ApiError::ValidationError(format!(
-                    "Invalid target type: {}", target_type
-                ))),
-            };
+            let keys = KeyRing::parse(keys)
+                .map_err(|e| ApiError::ValidationError(e.to_string()))?;
             
-            let transform = CastTransform::new(column, data_type);
-            transform.process(&source)?
+            if req.transform_type == "encrypt" {
+                let mut transform = EncryptColumnTransform::new(columns, Arc::new(keys))
+                    .with_cancellation(token.clone());
+                
+                if req.params.get("as_text").and_then(|v| v.as_bool()).unwrap_or(false) {
+                    transform = transform.as_text();
+                }
+                
+                transform.process(source)?
+            } else {
+                DecryptColumnTransform::new(columns, Arc::new(keys))
+                    .with_cancellation(token.clone())
+                    .process(source)?
+            }
         },
         _ => return Err(ApiError::ValidationError(format!(
             "Unknown transform type: {}", req.transform_type
         ))),
     };
     
-    // Store result dataset if target is specified
-    if let Some(target) = req.target {
-        storage.store(&target, &result)?;
-        
-        Ok(HttpResponse::Ok().json(json!({
-            "target": target,
-            "rows": result.len(),
-        })))
-    } else {
-        // Return result directly
-        let data = result.data.iter()
-            .map(|row| {
-                row.values.iter()
-                    .map(|value| match value {
-                        Value::Null => serde_json::Value::Null,
-                        Value::Boolean(b) => serde_json::Value::Bool(*b),
-                        Value::Integer(i) => serde_json::Value::Number((*i).into()),
-                        Value::Float(f) => {
-                            serde_json::Number::from_f64(*f)
-                                .map(serde_json::Value::Number)
-                                .unwrap_or(serde_json::Value::Null)
-                        },
-                        Value::String(s) => serde_json::Value::String(s.clone()),
-                        Value::Binary(_) => serde_json::Value::String("[binary data]".to_string()),
-                        Value::Array(_) => serde_json::Value::String("[array]".to_string()),
-                        Value::Map(_) => serde_json::Value::String("[map]".to_string()),
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .collect::<Vec<_>>();
-        
-        Ok(HttpResponse::Ok().json(json!({
-            "data": data,
-            "rows": result.len(),
-        })))
-    }
+    Ok(result)
 }
 
 /// Filter a dataset
 pub async fn filter_dataset(
-    storage: web::Data<Arc<dyn DataStorage + Send + Sync>>,
+    storage: ScopedStorage,
+    principal: Principal,
+    result_cache: Option<web::Data<ResultCache>>,
+    processing_timeout: Option<web::Data<ProcessingTimeout>>,
+    response_limit: Option<web::Data<ResponseLimit>>,
+    sessions: Option<web::Data<SessionStorage>>,
+    query: web::Query<DryRunQuery>,
     payload: web::Json<FilterRequest>,
 ) -> Result<impl Responder, ApiError> {
     let req = payload.into_inner();
+    let token = request_token(&processing_timeout);
+    let target = result_target(&sessions, req.session.as_deref(), req.target.clone())?;
     
     // Check if source dataset exists
     if !storage.exists(&req.source)? {
@@ -388,8 +2330,14 @@ pub async fn filter_dataset(
         )));
     }
     
-    // Load source dataset
-    let source = storage.load(&req.source)?;
+    // Serve unchanged inputs from the result cache, unless only planning
+    let cache_key = result_cache_key(&result_cache, &principal, "filter", &req, &[&req.source])?;
+    if let Some(result) = cached_result(&result_cache, &cache_key)?.filter(|_| !query.dry_run) {
+        return result_response(&storage, &response_limit, target, &result, &[]);
+    }
+    
+    // Load source dataset, or only a sample of it for a dry run
+    let (source, input_rows) = load_source(&storage, &req.source, &[], query.dry_run)?;
     
     // Apply filter
     let filter = match req.filter_type.as_str() {
@@ -501,57 +2449,130 @@ pub async fn filter_dataset(
             
             FilterProcessor::contains(column, substring)
         },
+        "within_bounds" => {
+            let column = req.params.get("column")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'column' parameter".to_string()
+                ))?;
+            
+            let bounds = BoundingBox::new(
+                float_param(&req.params, "min_lat")?,
+                float_param(&req.params, "min_lon")?,
+                float_param(&req.params, "max_lat")?,
+                float_param(&req.params, "max_lon")?,
+            );
+            
+            FilterProcessor::within_bounds(column, bounds)
+        },
+        "within_radius" => {
+            let column = req.params.get("column")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::ValidationError(
+                    "Missing or invalid 'column' parameter".to_string()
+                ))?;
+            
+            FilterProcessor::within_radius(
+                column,
+                float_param(&req.params, "lat")?,
+                float_param(&req.params, "lon")?,
+                float_param(&req.params, "radius_km")?,
+            )
+        },
         _ => return Err(ApiError::ValidationError(format!(
             "Unknown filter type: {}", req.filter_type
         ))),
     };
     
-    let result = filter.process(&source)?;
+    let filter = filter.with_cancellation(token.clone());
+    let (result, warnings) = collect_warnings(|| filter.process(&source));
+    let result = check_timeout(&processing_timeout, "filter", &token, result)
+        .map_err(|e| e.in_dataset(&req.source))?;
     
-    // Store result dataset if target is specified
-    if let Some(target) = req.target {
-        storage.store(&target, &result)?;
-        
-        Ok(HttpResponse::Ok().json(json!({
-            "target": target,
-            "rows": result.len(),
-        })))
-    } else {
-        // Return result directly
-        let data = result.data.iter()
-            .map(|row| {
-                row.values.iter()
-                    .map(|value| match value {
-                        Value::Null => serde_json::Value::Null,
-                        Value::Boolean(b) => serde_json::Value::Bool(*b),
-                        Value::Integer(i) => serde_json::Value::Number((*i).into()),
-                        Value::Float(f) => {
-                            serde_json::Number::from_f64(*f)
-                                .map(serde_json::Value::Number)
-                                .unwrap_or(serde_json::Value::Null)
-                        },
-                        Value::String(s) => serde_json::Value::String(s.clone()),
-                        Value::Binary(_) => serde_json::Value::String("[binary data]".to_string()),
-                        Value::Array(_) => serde_json::Value::String("[array]".to_string()),
-                        Value::Map(_) => serde_json::Value::String("[map]".to_string()),
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .collect::<Vec<_>>();
-        
-        Ok(HttpResponse::Ok().json(json!({
-            "data": data,
-            "rows": result.len(),
-        })))
+    if query.dry_run {
+        let plan = PipelinePlan::from_step("filter", &source, input_rows, &result);
+        return plan_response(&storage, target, &req.source, &plan, &warnings);
+    }
+    
+    remember_result(&result_cache, cache_key, &result, &warnings)?;
+    
+    result_response(&storage, &response_limit, target, &result, &warnings)
+}
+
+/// Run a pipeline specification on a dataset, resolving its parameters first
+pub async fn run_pipeline(
+    storage: ScopedStorage,
+    principal: Principal,
+    result_cache: Option<web::Data<ResultCache>>,
+    processing_timeout: Option<web::Data<ProcessingTimeout>>,
+    response_limit: Option<web::Data<ResponseLimit>>,
+    sessions: Option<web::Data<SessionStorage>>,
+    query: web::Query<DryRunQuery>,
+    payload: web::Json<PipelineRequest>,
+) -> Result<impl Responder, ApiError> {
+    let req = payload.into_inner();
+    let token = request_token(&processing_timeout);
+    let target = result_target(&sessions, req.session.as_deref(), req.target.clone())?;
+    
+    // Resolve and check the pipeline before touching the data
+    let spec = PipelineTemplate::from_config(req.pipeline.clone())
+        .and_then(|template| template.resolve(&req.params))
+        .map_err(|e| ApiError::ValidationError(e.to_string()))?;
+    let pipeline = spec.build().map_err(|e| ApiError::ValidationError(e.to_string()))?;
+    let ranges = spec.input_ranges().map_err(|e| ApiError::ValidationError(e.to_string()))?;
+    
+    // Check if source dataset exists
+    if !storage.exists(&req.source)? {
+        return Err(ApiError::NotFound(format!(
+            "Source dataset '{}' not found", req.source
+        )));
+    }
+    
+    // Serve unchanged inputs from the result cache, unless only planning
+    let cache_key = result_cache_key(&result_cache, &principal, "pipeline", &req, &[&req.source])?;
+    if let Some(result) = cached_result(&result_cache, &cache_key)?.filter(|_| !query.dry_run) {
+        return result_response(&storage, &response_limit, target, &result, &[]);
+    }
+    let statistics_key = statistics_key(&result_cache, &principal, &req.source)?;
+    
+    // Load source dataset, or only a sample of it for a dry run, skipping
+    // the blocks of rows the leading filters rule out
+    let (source, input_rows) = load_source(&storage, &req.source, &ranges, query.dry_run)?;
+    
+    // Run the most selective filters first
+    let complete = if query.dry_run { source.len() >= input_rows } else { ranges.is_empty() };
+    let statistics = input_statistics(&result_cache, statistics_key, &source, input_rows, complete)?;
+    let pipeline = pipeline.order_filters(&statistics);
+    
+    if query.dry_run {
+        let (plan, warnings) = collect_warnings(|| pipeline.dry_run_with_statistics(&source, &statistics));
+        let plan = plan.map_err(|e| ApiError::from(e).in_dataset(&req.source))?;
+        return plan_response(&storage, target, &req.source, &plan, &warnings);
     }
+    
+    let (result, warnings) = collect_warnings(|| pipeline.execute_owned(source));
+    let result = check_timeout(&processing_timeout, "pipeline", &token, result)
+        .map_err(|e| e.in_dataset(&req.source))?;
+    
+    remember_result(&result_cache, cache_key, &result, &warnings)?;
+    
+    result_response(&storage, &response_limit, target, &result, &warnings)
 }
 
 /// Aggregate a dataset
 pub async fn aggregate_dataset(
-    storage: web::Data<Arc<dyn DataStorage + Send + Sync>>,
+    storage: ScopedStorage,
+    principal: Principal,
+    result_cache: Option<web::Data<ResultCache>>,
+    processing_timeout: Option<web::Data<ProcessingTimeout>>,
+    response_limit: Option<web::Data<ResponseLimit>>,
+    sessions: Option<web::Data<SessionStorage>>,
+    query: web::Query<DryRunQuery>,
     payload: web::Json<AggregateRequest>,
 ) -> Result<impl Responder, ApiError> {
     let req = payload.into_inner();
+    let token = request_token(&processing_timeout);
+    let target = result_target(&sessions, req.session.as_deref(), req.target.clone())?;
     
     // Check if source dataset exists
     if !storage.exists(&req.source)? {
@@ -560,8 +2581,14 @@ pub async fn aggregate_dataset(
         )));
     }
     
-    // Load source dataset
-    let source = storage.load(&req.source)?;
+    // Serve unchanged inputs from the result cache, unless only planning
+    let cache_key = result_cache_key(&result_cache, &principal, "aggregate", &req, &[&req.source])?;
+    if let Some(result) = cached_result(&result_cache, &cache_key)?.filter(|_| !query.dry_run) {
+        return result_response(&storage, &response_limit, target, &result, &[]);
+    }
+    
+    // Load source dataset, or only a sample of it for a dry run
+    let (source, input_rows) = load_source(&storage, &req.source, &[], query.dry_run)?;
     
     // Create group by processor
     let mut group_by = GroupByProcessor::new();
@@ -573,77 +2600,58 @@ pub async fn aggregate_dataset(
         }
     }
     
-    // Add aggregations
+    let overflow = match &req.overflow {
+        Some(policy) => OverflowPolicy::from_str(policy)?,
+        None => OverflowPolicy::Error,
+    };
+    
+    if let Some(policy) = &req.nan_policy {
+        group_by = group_by.with_nan_policy(NanPolicy::from_str(policy)?);
+    }
+    
+    if let Some(having) = &req.having {
+        let having = FilterExpression::parse(having)
+            .map_err(|e| ApiError::ValidationError(e.to_string()))?;
+        group_by = group_by.having(having);
+    }
+    
+    // Add aggregations, built-in or registered
+    let options = AggregateOptions { overflow };
     for agg in req.aggregations {
-        match agg.function.as_str() {
-            "count" => {
-                group_by = group_by.count(&agg.output_name, &agg.input_column);
-            },
-            "sum" => {
-                group_by = group_by.sum(&agg.output_name, &agg.input_column);
-            },
-            "avg" => {
-                group_by = group_by.avg(&agg.output_name, &agg.input_column);
-            },
-            "min" => {
-                group_by = group_by.min(&agg.output_name, &agg.input_column);
-            },
-            "max" => {
-                group_by = group_by.max(&agg.output_name, &agg.input_column);
-            },
-            _ => return Err(ApiError::ValidationError(format!(
-                "Unknown aggregation function: {}", agg.function
-            ))),
-        }
+        group_by = group_by.aggregate_named(&agg.output_name, &agg.input_column, &agg.function, &options)
+            .map_err(|e| ApiError::ValidationError(e.to_string()))?;
     }
     
     // Apply aggregation
-    let result = group_by.process(&source)?;
+    let group_by = group_by.with_cancellation(token.clone());
+    let (result, warnings) = collect_warnings(|| group_by.process(&source));
+    let result = check_timeout(&processing_timeout, "aggregate", &token, result)
+        .map_err(|e| e.in_dataset(&req.source))?;
     
-    // Store result dataset if target is specified
-    if let Some(target) = req.target {
-        storage.store(&target, &result)?;
-        
-        Ok(HttpResponse::Ok().json(json!({
-            "target": target,
-            "rows": result.len(),
-        })))
-    } else {
-        // Return result directly
-        let data = result.data.iter()
-            .map(|row| {
-                row.values.iter()
-                    .map(|value| match value {
-                        Value::Null => serde_json::Value::Null,
-                        Value::Boolean(b) => serde_json::Value::Bool(*b),
-                        Value::Integer(i) => serde_json::Value::Number((*i).into()),
-                        Value::Float(f) => {
-                            serde_json::Number::from_f64(*f)
-                                .map(serde_json::Value::Number)
-                                .unwrap_or(serde_json::Value::Null)
-                        },
-                        Value::String(s) => serde_json::Value::String(s.clone()),
-                        Value::Binary(_) => serde_json::Value::String("[binary data]".to_string()),
-                        Value::Array(_) => serde_json::Value::String("[array]".to_string()),
-                        Value::Map(_) => serde_json::Value::String("[map]".to_string()),
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .collect::<Vec<_>>();
-        
-        Ok(HttpResponse::Ok().json(json!({
-            "data": data,
-            "rows": result.len(),
-        })))
+    if query.dry_run {
+        let plan = PipelinePlan::from_step("aggregate", &source, input_rows, &result);
+        return plan_response(&storage, target, &req.source, &plan, &warnings);
     }
+    
+    remember_result(&result_cache, cache_key, &result, &warnings)?;
+    
+    result_response(&storage, &response_limit, target, &result, &warnings)
 }
 
 /// Join datasets
 pub async fn join_datasets(
-    storage: web::Data<Arc<dyn DataStorage + Send + Sync>>,
+    storage: ScopedStorage,
+    principal: Principal,
+    result_cache: Option<web::Data<ResultCache>>,
+    processing_timeout: Option<web::Data<ProcessingTimeout>>,
+    response_limit: Option<web::Data<ResponseLimit>>,
+    sessions: Option<web::Data<SessionStorage>>,
+    query: web::Query<DryRunQuery>,
     payload: web::Json<JoinRequest>,
 ) -> Result<impl Responder, ApiError> {
     let req = payload.into_inner();
+    let token = request_token(&processing_timeout);
+    let target = result_target(&sessions, req.session.as_deref(), req.target.clone())?;
     
     // Check if left dataset exists
     if !storage.exists(&req.left)? {
@@ -659,9 +2667,17 @@ pub async fn join_datasets(
         )));
     }
     
-    // Load datasets
-    let left = storage.load(&req.left)?;
-    let right = storage.load(&req.right)?;
+    // Serve unchanged inputs from the result cache, unless only planning
+    let cache_key = result_cache_key(&result_cache, &principal, "join", &req, &[&req.left, &req.right])?;
+    if let Some(result) = cached_result(&result_cache, &cache_key)?.filter(|_| !query.dry_run) {
+        return result_response(&storage, &response_limit, target, &result, &[]);
+    }
+    let left_key = statistics_key(&result_cache, &principal, &req.left)?;
+    let right_key = statistics_key(&result_cache, &principal, &req.right)?;
+    
+    // Load datasets, or only samples of them for a dry run
+    let (left, left_rows) = load_source(&storage, &req.left, &[], query.dry_run)?;
+    let (right, right_rows) = load_source(&storage, &req.right, &[], query.dry_run)?;
     
     // Create join processor
     let join_type = match req.join_type.as_str() {
@@ -682,52 +2698,50 @@ pub async fn join_datasets(
     };
     
     // Apply join
-    let result = join.process_join(&left, &right)?;
+    let join = join.with_cancellation(token.clone());
+    let (result, warnings) = collect_warnings(|| join.process_join(&left, &right));
+    let result = check_timeout(&processing_timeout, "join", &token, result)?;
     
-    // Store result dataset if target is specified
-    if let Some(target) = req.target {
-        storage.store(&target, &result)?;
+    if query.dry_run {
+        let left_statistics = input_statistics(&result_cache, left_key, &left, left_rows, left.len() >= left_rows)?;
+        let right_statistics = input_statistics(&result_cache, right_key, &right, right_rows, right.len() >= right_rows)?;
+        let overwrites_target = match &target {
+            Some(target) => storage.exists(target)?,
+            None => false,
+        };
         
-        Ok(HttpResponse::Ok().json(json!({
+        return Ok(HttpResponse::Ok().json(json!({
+            "dry_run": true,
+            "left": req.left,
+            "right": req.right,
             "target": target,
-            "rows": result.len(),
-        })))
-    } else {
-        // Return result directly
-        let data = result.data.iter()
-            .map(|row| {
-                row.values.iter()
-                    .map(|value| match value {
-                        Value::Null => serde_json::Value::Null,
-                        Value::Boolean(b) => serde_json::Value::Bool(*b),
-                        Value::Integer(i) => serde_json::Value::Number((*i).into()),
-                        Value::Float(f) => {
-                            serde_json::Number::from_f64(*f)
-                                .map(serde_json::Value::Number)
-                                .unwrap_or(serde_json::Value::Null)
-                        },
-                        Value::String(s) => serde_json::Value::String(s.clone()),
-                        Value::Binary(_) => serde_json::Value::String("[binary data]".to_string()),
-                        Value::Array(_) => serde_json::Value::String("[array]".to_string()),
-                        Value::Map(_) => serde_json::Value::String("[map]".to_string()),
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .collect::<Vec<_>>();
-        
-        Ok(HttpResponse::Ok().json(json!({
-            "data": data,
-            "rows": result.len(),
-        })))
+            "overwrites_target": overwrites_target,
+            "left_rows": left_rows,
+            "right_rows": right_rows,
+            "build_side": join.build_side(left_rows, right_rows),
+            "estimated_rows": join.estimate_rows(&left_statistics, &right_statistics),
+            "exact": false,
+            "schema": result.schema.fields.iter().map(schema_field).collect::<Vec<_>>(),
+            "warnings": warnings,
+        })));
     }
+    
+    remember_result(&result_cache, cache_key, &result, &warnings)?;
+    
+    result_response(&storage, &response_limit, target, &result, &warnings)
 }
 
 /// Compute statistics on a dataset
 pub async fn compute_stats(
-    storage: web::Data<Arc<dyn DataStorage + Send + Sync>>,
+    storage: ScopedStorage,
+    principal: Principal,
+    result_cache: Option<web::Data<ResultCache>>,
+    processing_timeout: Option<web::Data<ProcessingTimeout>>,
+    response_limit: Option<web::Data<ResponseLimit>>,
     payload: web::Json<StatsRequest>,
 ) -> Result<impl Responder, ApiError> {
     let req = payload.into_inner();
+    let token = request_token(&processing_timeout);
     
     // Check if source dataset exists
     if !storage.exists(&req.source)? {
@@ -736,6 +2750,12 @@ pub async fn compute_stats(
         )));
     }
     
+    // Serve unchanged inputs from the result cache
+    let cache_key = result_cache_key(&result_cache, &principal, "stats", &req, &[&req.source])?;
+    if let Some(result) = cached_result(&result_cache, &cache_key)? {
+        return stats_response(&response_limit, req.output_name, &result, &[]);
+    }
+    
     // Load source dataset
     let source = storage.load(&req.source)?;
     
@@ -761,19 +2781,30 @@ pub async fn compute_stats(
     let stats = StatsProcessor::new(&req.output_name, req.columns, stats_type);
     
     // Apply stats
-    let result = stats.process(&source)?;
+    let (result, warnings) = collect_warnings(|| stats.process(&source));
+    let result = check_timeout(&processing_timeout, "stats", &token, result)
+        .map_err(|e| e.in_dataset(&req.source))?;
+    remember_result(&result_cache, cache_key, &result, &warnings)?;
     
+    stats_response(&response_limit, req.output_name, &result, &warnings)
+}
+
+/// Build the response of a statistics request from its result dataset
+///
+/// Modes of long strings can be large, so the body is held to the byte cap.
+fn stats_response(
+    limit: &Option<web::Data<ResponseLimit>>,
+    name: String,
+    result: &DataSet,
+    warnings: &[Warning],
+) -> Result<HttpResponse, ApiError> {
     // Get the result value
     let value = if !result.data.is_empty() && !result.data[0].values.is_empty() {
         match &result.data[0].values[0] {
             Value::Null => serde_json::Value::Null,
             Value::Boolean(b) => serde_json::Value::Bool(*b),
             Value::Integer(i) => serde_json::Value::Number((*i).into()),
-            Value::Float(f) => {
-                serde_json::Number::from_f64(*f)
-                    .map(serde_json::Value::Number)
-                    .unwrap_or(serde_json::Value::Null)
-            },
+            Value::Float(f) => float_to_json(*f),
             Value::String(s) => serde_json::Value::String(s.clone()),
             _ => serde_json::Value::Null,
         }
@@ -781,9 +2812,434 @@ pub async fn compute_stats(
         serde_json::Value::Null
     };
     
-    Ok(HttpResponse::Ok().json(json!({
-        "name": req.output_name,
+    let limit = limit.as_deref().cloned().unwrap_or_default();
+    limit.json(&json!({
+        "name": name,
         "value": value,
+        "warnings": warnings,
+    }), "run the statistic in a pipeline with a 'target' dataset instead")
+}
+
+/// Store a processing result in the target dataset, or return its rows
+///
+/// Results larger than the response limit must be stored in a target.
+fn result_response(
+    storage: &ScopedStorage,
+    limit: &Option<web::Data<ResponseLimit>>,
+    target: Option<String>,
+    result: &DataSet,
+    warnings: &[Warning],
+) -> Result<HttpResponse, ApiError> {
+    if let Some(target) = target {
+        storage.store(&target, result)?;
+        
+        Ok(HttpResponse::Ok().json(json!({
+            "target": target,
+            "rows": result.len(),
+            "warnings": warnings,
+        })))
+    } else {
+        let hint = "set a 'target' dataset to store the result instead";
+        let limit = limit.as_deref().cloned().unwrap_or_default();
+        limit.check_rows(result.len(), hint)?;
+        
+        let data = result.data.iter()
+            .map(|row| row.values.iter().map(value_to_json).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        
+        limit.json(&json!({
+            "data": data,
+            "rows": result.len(),
+            "warnings": warnings,
+        }), hint)
+    }
+}
+
+/// Load the source dataset of a processing request with its row count
+///
+/// Dry runs load only a sample, taking the row count from the storage.
+/// Otherwise blocks of rows outside the ranges may be skipped, and the
+/// row count is of the rows loaded.
+fn load_source(
+    storage: &ScopedStorage,
+    name: &str,
+    ranges: &[ColumnRange],
+    dry_run: bool,
+) -> Result<(DataSet, usize), ApiError> {
+    if dry_run {
+        let rows = storage.info(name)?.rows;
+        Ok((storage.load_head(name, DRY_RUN_SAMPLE_ROWS)?, rows))
+    } else {
+        let source = storage.load_pruned(name, ranges)?;
+        let rows = source.len();
+        Ok((source, rows))
+    }
+}
+
+/// Report what a processing request would do, for a dry run
+fn plan_response(
+    storage: &ScopedStorage,
+    target: Option<String>,
+    source: &str,
+    plan: &PipelinePlan,
+    warnings: &[Warning],
+) -> Result<HttpResponse, ApiError> {
+    let overwrites_target = match &target {
+        Some(target) => storage.exists(target)?,
+        None => false,
+    };
+    
+    let steps = plan.steps.iter()
+        .map(|step| json!({
+            "name": step.name,
+            "schema": step.schema.fields.iter().map(schema_field).collect::<Vec<_>>(),
+            "estimated_rows": step.estimated_rows,
+        }))
+        .collect::<Vec<_>>();
+    
+    Ok(HttpResponse::Ok().json(json!({
+        "dry_run": true,
+        "source": source,
+        "target": target,
+        "overwrites_target": overwrites_target,
+        "input_rows": plan.input_rows,
+        "sample_rows": plan.sample_rows,
+        "estimated_rows": plan.estimated_rows,
+        "exact": plan.exact,
+        "schema": plan.schema.fields.iter().map(schema_field).collect::<Vec<_>>(),
+        "steps": steps,
+        "warnings": warnings,
     })))
 }
 
+/// Build the result cache key of a processing request
+///
+/// The key covers the endpoint, the caller's role, since row-level security
+/// changes what the inputs contain, and the request without its target.
+fn result_cache_key<T: serde::Serialize>(
+    cache: &Option<web::Data<ResultCache>>,
+    principal: &Principal,
+    endpoint: &str,
+    request: &T,
+    inputs: &[&str],
+) -> Result<Option<ResultKey>, ApiError> {
+    let cache = match cache {
+        Some(cache) => cache,
+        None => return Ok(None),
+    };
+    
+    let mut request = serde_json::to_value(request)
+        .map_err(|e| ApiError::InternalError(e.to_string()))?;
+    if let Some(fields) = request.as_object_mut() {
+        fields.remove("target");
+        fields.remove("session");
+    }
+    
+    let config = json!({
+        "endpoint": endpoint,
+        "role": principal.role(),
+        "admin": principal.is_admin(),
+        "request": request,
+    });
+    
+    Ok(Some(cache.key(&config.to_string(), inputs)?))
+}
+
+/// Build the cache key of the column statistics of an input dataset
+///
+/// Like results, statistics are kept per role, since row-level security
+/// changes what the dataset contains. The key must be taken before the
+/// dataset is loaded.
+fn statistics_key(
+    cache: &Option<web::Data<ResultCache>>,
+    principal: &Principal,
+    name: &str,
+) -> Result<Option<ResultKey>, ApiError> {
+    let cache = match cache {
+        Some(cache) => cache,
+        None => return Ok(None),
+    };
+    
+    let config = json!({
+        "statistics": true,
+        "role": principal.role(),
+        "admin": principal.is_admin(),
+    });
+    
+    Ok(Some(cache.key(&config.to_string(), &[name])?))
+}
+
+/// Get the column statistics of an input dataset for planning
+///
+/// Cached statistics are used when present. Otherwise they are computed
+/// from the rows loaded and scaled to `input_rows`, and cached only when
+/// the rows loaded are the complete dataset.
+fn input_statistics(
+    cache: &Option<web::Data<ResultCache>>,
+    key: Option<ResultKey>,
+    loaded: &DataSet,
+    input_rows: usize,
+    complete: bool,
+) -> Result<TableEstimate, ApiError> {
+    if let (Some(cache), Some(key)) = (cache, &key) {
+        if let Some(statistics) = cache.get_statistics(key)? {
+            return Ok(statistics);
+        }
+    }
+    
+    let statistics = TableEstimate::compute(loaded).scaled(input_rows.max(loaded.len()));
+    
+    if let (Some(cache), Some(key), true) = (cache, key, complete) {
+        cache.insert_statistics(key, statistics.clone())?;
+    }
+    
+    Ok(statistics)
+}
+
+/// Run the processing of a request, collecting the warnings its processors emit
+fn collect_warnings<T, F: FnOnce() -> T>(process: F) -> (T, Vec<Warning>) {
+    let diagnostics = Diagnostics::new();
+    let result = diagnostics.collect(process);
+    
+    (result, diagnostics.warnings())
+}
+
+/// Create the cancellation token of a processing request, with the configured time limit
+fn request_token(timeout: &Option<web::Data<ProcessingTimeout>>) -> CancellationToken {
+    timeout.as_ref().map_or_else(CancellationToken::new, |timeout| timeout.token())
+}
+
+/// Turn the result of a processing request that ran out of time into a timeout error
+///
+/// Processors that do not check the token run to completion, so the token
+/// is checked again once they return.
+fn check_timeout(
+    timeout: &Option<web::Data<ProcessingTimeout>>,
+    endpoint: &str,
+    token: &CancellationToken,
+    result: Result<DataSet, ProcessingError>,
+) -> Result<DataSet, ApiError> {
+    match result.and_then(|data| token.check().map(|_| data)) {
+        Err(ProcessingError::Cancelled(msg)) => {
+            if let Some(timeout) = timeout {
+                timeout.record_timeout(endpoint);
+            }
+            
+            Err(ApiError::Timeout(msg))
+        },
+        result => Ok(result?),
+    }
+}
+
+/// Get the dataset a processing result is stored in, if any
+///
+/// Requests naming a session but no target store their result in a new
+/// temporary dataset of the session.
+fn result_target(
+    sessions: &Option<web::Data<SessionStorage>>,
+    session: Option<&str>,
+    target: Option<String>,
+) -> Result<Option<String>, ApiError> {
+    match (target, session) {
+        (Some(target), _) => Ok(Some(target)),
+        (None, Some(session)) => {
+            let name = enabled_sessions(sessions)?.next_temporary_name(session)
+                .map_err(|err| session_error(session, err))?;
+            Ok(Some(name))
+        },
+        (None, None) => Ok(None),
+    }
+}
+
+/// Get a cached processing result
+fn cached_result(cache: &Option<web::Data<ResultCache>>, key: &Option<ResultKey>) -> Result<Option<DataSet>, ApiError> {
+    match (cache, key) {
+        (Some(cache), Some(key)) => Ok(cache.get(key)?),
+        _ => Ok(None),
+    }
+}
+
+/// Keep a processing result for later requests with the same key
+///
+/// Results that came with warnings are not cached, so the warnings are
+/// reported again rather than lost on the next request.
+fn remember_result(
+    cache: &Option<web::Data<ResultCache>>,
+    key: Option<ResultKey>,
+    result: &DataSet,
+    warnings: &[Warning],
+) -> Result<(), ApiError> {
+    if !warnings.is_empty() {
+        return Ok(());
+    }
+    
+    if let (Some(cache), Some(key)) = (cache, key) {
+        cache.insert(key, result.clone())?;
+    }
+    
+    Ok(())
+}
+
+/// Apply the response policy of a dataset, or the default policy if none are configured
+fn apply_response_policy(policies: &Option<web::Data<ResponsePolicies>>, name: &str, dataset: &mut DataSet) {
+    match policies {
+        Some(policies) => policies.apply(name, dataset),
+        None => ResponsePolicy::default().apply(dataset),
+    }
+}
+
+/// Convert a data value to a JSON value for responses
+fn value_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Boolean(b) => serde_json::Value::Bool(*b),
+        Value::Integer(i) => serde_json::Value::Number((*i).into()),
+        Value::Float(f) => float_to_json(*f),
+        // Decimals are given as text so clients do not round them to floats
+        Value::Decimal(d) => serde_json::Value::String(d.to_string()),
+        Value::String(s) => serde_json::Value::String(s.clone()),
+        Value::Binary(_) => serde_json::Value::String("[binary data]".to_string()),
+        Value::Point(lat, lon) => serde_json::json!({"lat": lat, "lon": lon}),
+        Value::Uuid(uuid) => serde_json::Value::String(format_uuid(*uuid)),
+        Value::Date(date) => serde_json::Value::String(format_date(date)),
+        Value::Timestamp(timestamp) => serde_json::Value::String(format_timestamp(timestamp)),
+        Value::Time(time) => serde_json::Value::String(format_time(time)),
+        Value::Array(_) => serde_json::Value::String("[array]".to_string()),
+        Value::Map(_) => serde_json::Value::String("[map]".to_string()),
+    }
+}
+
+/// Convert a float for responses, keeping NaN and infinities as strings
+fn float_to_json(f: f64) -> serde_json::Value {
+    NonFiniteFloats::String.float_to_json(f).unwrap_or(serde_json::Value::Null)
+}
+
+/// Get the API name of a data type
+fn data_type_name(data_type: &DataType) -> String {
+    let name = match data_type {
+        DataType::Boolean => "boolean",
+        DataType::Int8 => "int8",
+        DataType::Int16 => "int16",
+        DataType::Int32 => "int32",
+        DataType::Integer => "integer",
+        DataType::Float32 => "float32",
+        DataType::Float => "float",
+        DataType::String => "string",
+        DataType::Binary => "binary",
+        DataType::Point => "point",
+        DataType::Uuid => "uuid",
+        DataType::Date => "date",
+        DataType::Timestamp => "timestamp",
+        DataType::Time => "time",
+        DataType::Decimal(_, _) => return data_type.name(),
+        _ => "unknown",
+    };
+    
+    name.to_string()
+}
+
+/// Describe a schema field for responses
+fn schema_field(field: &Field) -> SchemaField {
+    SchemaField {
+        name: field.name.clone(),
+        data_type: data_type_name(&field.data_type),
+        nullable: field.nullable,
+        default: match &field.default {
+            Some(FieldDefault::Value(value)) => Some(value_to_json(value)),
+            _ => None,
+        },
+        generate: field.default.as_ref()
+            .and_then(|default| default.generator())
+            .map(str::to_string),
+    }
+}
+
+/// Convert a JSON value from a request to a data value
+fn json_to_value(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Boolean(*b),
+        serde_json::Value::Number(n) => {
+            if n.is_i64() {
+                Value::Integer(n.as_i64().unwrap())
+            } else {
+                Value::Float(n.as_f64().unwrap())
+            }
+        },
+        serde_json::Value::String(s) => Value::String(s.clone()),
+        serde_json::Value::Object(obj) => {
+            // Points are given as {"lat": .., "lon": ..}
+            match (obj.get("lat").and_then(|v| v.as_f64()), obj.get("lon").and_then(|v| v.as_f64())) {
+                (Some(lat), Some(lon)) => Value::Point(lat, lon),
+                _ => Value::Null,
+            }
+        },
+        _ => Value::Null,
+    }
+}
+
+/// Parse the text of date, timestamp, time and UUID values given as JSON
+/// strings, and decimals given as strings or numbers, then check each value
+/// against the type of its column
+fn typed_row(mut row: Row, schema: &Schema) -> Result<Row, ApiError> {
+    for (value, field) in row.values.iter_mut().zip(&schema.fields) {
+        let text = std::mem::replace(value, Value::Null);
+        *value = parse_temporal_value(text, &field.data_type)
+            .and_then(|value| parse_decimal_value(value, &field.data_type))
+            .and_then(|value| match (value, &field.data_type) {
+                (Value::String(s), DataType::Uuid) => parse_uuid(&s).map(Value::Uuid),
+                // JSON numbers without a fraction arrive as integers
+                (Value::Integer(i), t) if t.is_float() => Ok(Value::Float(i as f64)),
+                (value, _) => Ok(value),
+            })
+            .and_then(|value| SchemaValidator::validate_value(&value, &field.data_type).map(|_| value))
+            .map_err(|e| ApiError::ValidationError(format!("Column '{}': {}", field.name, e)))?;
+    }
+    
+    Ok(row)
+}
+
+/// Parse a data type from its API name
+fn parse_data_type(name: &str) -> Result<DataType, ApiError> {
+    match name {
+        "boolean" => Ok(DataType::Boolean),
+        "int8" => Ok(DataType::Int8),
+        "int16" => Ok(DataType::Int16),
+        "int32" => Ok(DataType::Int32),
+        "integer" | "int64" => Ok(DataType::Integer),
+        "float32" => Ok(DataType::Float32),
+        "float" | "float64" => Ok(DataType::Float),
+        "string" => Ok(DataType::String),
+        "binary" => Ok(DataType::Binary),
+        "point" => Ok(DataType::Point),
+        "uuid" => Ok(DataType::Uuid),
+        "date" => Ok(DataType::Date),
+        "timestamp" | "datetime" => Ok(DataType::Timestamp),
+        "time" => Ok(DataType::Time),
+        name if name.starts_with("decimal") => DataType::from_str(name)
+            .map_err(|e| ApiError::ValidationError(e.to_string())),
+        _ => Err(ApiError::ValidationError(format!(
+            "Invalid data type: {}", name
+        ))),
+    }
+}
+
+/// Parse optional cast format hints from request parameters
+fn parse_cast_format(params: &serde_json::Value) -> Result<CastFormat, ApiError> {
+    match params.get("format") {
+        Some(format) => serde_json::from_value(format.clone())
+            .map_err(|e| ApiError::ValidationError(format!(
+                "Invalid 'format' parameter: {}", e
+            ))),
+        None => Ok(CastFormat::default()),
+    }
+}
+
+/// Get a required numeric parameter
+fn float_param(params: &serde_json::Value, name: &str) -> Result<f64, ApiError> {
+    params.get(name)
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| ApiError::ValidationError(format!(
+            "Missing or invalid '{}' parameter", name
+        )))
+}
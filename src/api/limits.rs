@@ -0,0 +1,170 @@
+// Execution limits for processing requests
+// Author: Gabriel Demetrios Lafis
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use actix_web::HttpResponse;
+
+use crate::processing::CancellationToken;
+use super::ApiError;
+
+/// Time limit on processing requests
+///
+/// Each request gets a cancellation token with the timeout as its deadline;
+/// processors stop when it passes and the request fails with
+/// `ApiError::Timeout`.
+#[derive(Debug)]
+pub struct ProcessingTimeout {
+    timeout: Duration,
+    timeouts: AtomicU64,
+}
+
+impl ProcessingTimeout {
+    /// Create a new processing time limit
+    pub fn new(timeout: Duration) -> Self {
+        ProcessingTimeout {
+            timeout,
+            timeouts: AtomicU64::new(0),
+        }
+    }
+    
+    /// Get the time limit
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+    
+    /// Create a token for a request starting now
+    pub fn token(&self) -> CancellationToken {
+        CancellationToken::new().with_timeout(self.timeout)
+    }
+    
+    /// Record a request that ran out of time
+    pub fn record_timeout(&self, endpoint: &str) {
+        self.timeouts.fetch_add(1, Ordering::Relaxed);
+        log::warn!("Processing request to '{}' timed out after {:?}", endpoint, self.timeout);
+    }
+    
+    /// Get the number of requests that ran out of time
+    pub fn timeouts(&self) -> u64 {
+        self.timeouts.load(Ordering::Relaxed)
+    }
+}
+
+/// Cap on the rows and bytes a response returns inline
+///
+/// Larger results have to be paged through or stored in a target dataset,
+/// so a single request cannot serialize a whole large dataset into one
+/// JSON body.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseLimit {
+    max_rows: Option<usize>,
+    max_bytes: Option<usize>,
+}
+
+impl ResponseLimit {
+    /// Create a limit that allows any response
+    pub fn new() -> Self {
+        ResponseLimit::default()
+    }
+    
+    /// Limit the number of rows returned inline
+    pub fn with_max_rows(mut self, rows: usize) -> Self {
+        self.max_rows = Some(rows);
+        self
+    }
+    
+    /// Limit the size of response bodies in bytes
+    pub fn with_max_bytes(mut self, bytes: usize) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+    
+    /// Get the maximum number of rows returned inline
+    pub fn max_rows(&self) -> Option<usize> {
+        self.max_rows
+    }
+    
+    /// Fail if more rows than allowed would be returned inline
+    ///
+    /// The hint tells the caller how to get the rows instead.
+    pub fn check_rows(&self, rows: usize, hint: &str) -> Result<(), ApiError> {
+        match self.max_rows {
+            Some(max_rows) if rows > max_rows => Err(ApiError::ValidationError(format!(
+                "Result has {} rows, more than the {} returned inline; {}", rows, max_rows, hint
+            ))),
+            _ => Ok(()),
+        }
+    }
+    
+    /// Serialize a JSON response body, failing if it is larger than allowed
+    pub fn json(&self, body: &serde_json::Value, hint: &str) -> Result<HttpResponse, ApiError> {
+        let bytes = serde_json::to_vec(body)
+            .map_err(|e| ApiError::InternalError(e.to_string()))?;
+        
+        self.body(bytes, "application/json", hint)
+    }
+    
+    /// Respond with an encoded body, failing if it is larger than allowed
+    pub fn body(&self, bytes: Vec<u8>, content_type: &str, hint: &str) -> Result<HttpResponse, ApiError> {
+        if let Some(max_bytes) = self.max_bytes {
+            if bytes.len() > max_bytes {
+                return Err(ApiError::ValidationError(format!(
+                    "Response of {} bytes is larger than the {} returned inline; {}", bytes.len(), max_bytes, hint
+                )));
+            }
+        }
+        
+        Ok(HttpResponse::Ok().content_type(content_type).body(bytes))
+    }
+}
+
+/// Directory that export requests write under
+///
+/// Clients name export directories relative to the root, so a request
+/// cannot write anywhere else on the server's filesystem.
+#[derive(Debug, Clone)]
+pub struct ExportRoot {
+    root: PathBuf,
+}
+
+impl ExportRoot {
+    /// Create a new export root
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        ExportRoot {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+    
+    /// Get the root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+    
+    /// Resolve a client's export directory under the root
+    ///
+    /// Absolute paths and `..` components are rejected.
+    pub fn resolve(&self, directory: &str) -> Result<PathBuf, ApiError> {
+        let relative = Path::new(directory);
+        let mut normal = false;
+        
+        for component in relative.components() {
+            match component {
+                Component::Normal(_) => normal = true,
+                Component::CurDir => {},
+                _ => return Err(ApiError::ValidationError(format!(
+                    "Export directory '{}' must be a relative path without '..'", directory
+                ))),
+            }
+        }
+        
+        if !normal {
+            return Err(ApiError::ValidationError(
+                "Export directory must name a directory under the export root".to_string()
+            ));
+        }
+        
+        Ok(self.root.join(relative))
+    }
+}
@@ -5,16 +5,30 @@ mod server;
 mod routes;
 mod handlers;
 mod models;
+mod security;
+mod limits;
+mod redaction;
+mod chart;
+mod request_id;
 
 pub use server::*;
 pub use routes::*;
 pub use handlers::*;
 pub use models::*;
+pub use security::*;
+pub use limits::*;
+pub use redaction::*;
+pub use chart::*;
+pub use request_id::*;
 
 use std::error::Error;
 use std::fmt;
 
-use crate::data::DataError;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::json;
+
+use crate::data::{DataError, ErrorContext};
 use crate::processing::ProcessingError;
 use crate::storage::StorageError;
 
@@ -29,6 +43,7 @@ pub enum ApiError {
     Unauthorized(String),
     Forbidden(String),
     Conflict(String),
+    Timeout(String),
     InternalError(String),
 }
 
@@ -43,6 +58,7 @@ impl fmt::Display for ApiError {
             ApiError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
             ApiError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
             ApiError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            ApiError::Timeout(msg) => write!(f, "Timeout: {}", msg),
             ApiError::InternalError(msg) => write!(f, "Internal error: {}", msg),
         }
     }
@@ -50,6 +66,104 @@ impl fmt::Display for ApiError {
 
 impl Error for ApiError {}
 
+impl ApiError {
+    /// Record the dataset a data or processing error happened in
+    pub fn in_dataset(self, name: &str) -> Self {
+        let context = ErrorContext::new().dataset(name);
+        
+        match self {
+            ApiError::DataError(err) => ApiError::DataError(err.with_context(context)),
+            ApiError::ProcessingError(err) => ApiError::ProcessingError(err.with_context(context)),
+            err => err,
+        }
+    }
+    
+    /// Get the kind of the error, as reported in error bodies
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ApiError::DataError(_) => "data_error",
+            ApiError::ProcessingError(_) => "processing_error",
+            ApiError::StorageError(_) => "storage_error",
+            ApiError::ValidationError(_) => "validation_error",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::Timeout(_) => "timeout",
+            ApiError::InternalError(_) => "internal_error",
+        }
+    }
+    
+    /// Get where in the data the error happened, if known
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            ApiError::DataError(err) => err.context(),
+            ApiError::ProcessingError(err) => err.context(),
+            _ => None,
+        }
+    }
+    
+    /// Respond with the error body, naming the request that failed
+    pub fn response_for_request(&self, request_id: &RequestId) -> HttpResponse {
+        let mut body = self.body();
+        body["request_id"] = json!(request_id.as_str());
+        
+        HttpResponse::build(self.status_code()).json(body)
+    }
+    
+    /// Build the JSON body of the error
+    fn body(&self) -> serde_json::Value {
+        let mut body = json!({
+            "error": self.kind(),
+            "message": self.to_string(),
+        });
+        
+        if let Some(context) = self.context() {
+            body["context"] = json!(context);
+        }
+        
+        body
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::DataError(err) => match err.without_context() {
+                DataError::IoError(_) | DataError::Other(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                _ => StatusCode::BAD_REQUEST,
+            },
+            ApiError::ProcessingError(err) => match err.without_context() {
+                ProcessingError::Cancelled(_) => StatusCode::SERVICE_UNAVAILABLE,
+                ProcessingError::Other(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                _ => StatusCode::BAD_REQUEST,
+            },
+            ApiError::StorageError(err) => match err {
+                StorageError::NotFound(_) => StatusCode::NOT_FOUND,
+                StorageError::AlreadyExists(_) => StatusCode::CONFLICT,
+                StorageError::InvalidFormat(_) => StatusCode::BAD_REQUEST,
+                StorageError::Full(_) => StatusCode::INSUFFICIENT_STORAGE,
+                StorageError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+                StorageError::Unsupported(_) => StatusCode::NOT_IMPLEMENTED,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            ApiError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::Timeout(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+    
+    /// Respond with the kind and message of the error, and the dataset,
+    /// column, row and value it happened at when known
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(self.body())
+    }
+}
+
 impl From<DataError> for ApiError {
     fn from(err: DataError) -> Self {
         ApiError::DataError(err)
@@ -64,7 +178,10 @@ impl From<ProcessingError> for ApiError {
 
 impl From<StorageError> for ApiError {
     fn from(err: StorageError) -> Self {
-        ApiError::StorageError(err)
+        match err {
+            StorageError::ReadOnly(msg) => ApiError::Forbidden(msg),
+            err => ApiError::StorageError(err),
+        }
     }
 }
 
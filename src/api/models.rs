@@ -1,6 +1,8 @@
 // API request and response models
 // Author: Gabriel Demetrios Lafis
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
@@ -9,15 +11,135 @@ use serde_json::Value as JsonValue;
 pub struct SchemaField {
     pub name: String,
     pub data_type: String,
+    #[serde(default = "default_nullable")]
     pub nullable: bool,
+    /// Value used when a row leaves the field null or omits it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<JsonValue>,
+    /// Generated value instead of a fixed default: `autoincrement`, `uuid` or `created_at`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generate: Option<String>,
+}
+
+fn default_nullable() -> bool {
+    true
 }
 
 /// Request to create a new dataset
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateDatasetRequest {
     pub name: String,
     pub schema: Vec<SchemaField>,
+    /// Initial rows; a dataset created with only a schema is filled later by appending rows
+    #[serde(default)]
     pub data: Vec<Vec<JsonValue>>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Query parameters for creating a dataset from a CSV or NDJSON body
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateQuery {
+    pub name: Option<String>,
+    pub tags: Option<String>,
+    pub delimiter: Option<char>,
+    pub header: Option<bool>,
+}
+
+/// Query parameters for listing datasets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListQuery {
+    pub prefix: Option<String>,
+    pub tag: Option<String>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+/// Query parameters for restoring a backup
+#[derive(Debug, Clone, Deserialize)]
+pub struct RestoreQuery {
+    /// Replace datasets and aliases that already exist instead of failing
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Query parameters of requests that can be dry runs
+#[derive(Debug, Clone, Deserialize)]
+pub struct DryRunQuery {
+    /// Check the request and report what it would do, without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Request to replace the tags of a dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagsRequest {
+    pub tags: Vec<String>,
+}
+
+/// Query parameters for the lineage of a dataset's columns
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LineageQuery {
+    /// Only columns with this name or renamed from it
+    pub column: Option<String>,
+}
+
+/// Query parameters for reading a page of a dataset
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatasetQuery {
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+/// Query parameters for the profile report of a dataset
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReportQuery {
+    /// Number of sample rows, 20 by default
+    pub sample: Option<usize>,
+    /// Number of histogram bins of numeric columns, 10 by default
+    pub bins: Option<usize>,
+}
+
+/// Request for chart data of a dataset
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChartRequest {
+    /// Column on the x axis
+    pub x: String,
+    /// Column aggregated on the y axis; rows are counted if unset
+    pub y: Option<String>,
+    /// Aggregation of the y column: `count`, `sum` (default), `avg`, `min` or `max`
+    pub aggregation: Option<String>,
+    /// Column splitting the rows into one series per value
+    pub group: Option<String>,
+    /// Response format: `json` (default) or `svg`
+    pub format: Option<String>,
+    /// Chart drawn by the SVG format: `bar` (default) or `line`
+    pub kind: Option<String>,
+}
+
+/// Query parameters for previewing a dataset
+#[derive(Debug, Clone, Deserialize)]
+pub struct PreviewQuery {
+    pub rows: Option<usize>,
+}
+
+/// Query parameters for reading a single column
+#[derive(Debug, Clone, Deserialize)]
+pub struct ColumnQuery {
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+/// Query parameters for searching a dataset
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub columns: Option<String>,
+    pub regex: Option<bool>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
 }
 
 /// Request to update an existing dataset
@@ -26,26 +148,88 @@ pub struct UpdateDatasetRequest {
     pub data: Option<Vec<Vec<JsonValue>>>,
 }
 
-/// Request to transform a dataset
+/// Request to append rows to an existing dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppendRowsRequest {
+    pub data: Vec<Vec<JsonValue>>,
+    /// Schema of the rows, checked for drift and matched to the dataset's
+    /// columns by name; rows are in the dataset's column order if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Vec<SchemaField>>,
+    /// `block` (default) rejects rows whose schema drifts from the
+    /// dataset's, `notify` appends them unless the drift is breaking
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_drift: Option<String>,
+}
+
+/// Request to compare the schema of a batch with a dataset's
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftRequest {
+    pub schema: Vec<SchemaField>,
+}
+
+/// Request to apply change events to a dataset
 #[derive(Debug, Clone, Deserialize)]
+pub struct ApplyChangesRequest {
+    pub key_columns: Vec<String>,
+    pub events: Vec<JsonValue>,
+}
+
+/// Request to export a dataset as a snapshot plus incremental deltas
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExportRequest {
+    pub directory: String,
+    pub key_columns: Vec<String>,
+}
+
+/// Request to copy or rename a dataset
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatasetTargetRequest {
+    pub target: String,
+}
+
+/// Request to move a dataset to another namespace
+#[derive(Debug, Clone, Deserialize)]
+pub struct MoveDatasetRequest {
+    pub namespace: String,
+}
+
+/// Request to create or repoint an alias
+#[derive(Debug, Clone, Deserialize)]
+pub struct AliasRequest {
+    pub target: String,
+}
+
+/// Request to attach a row filter to a dataset for a role
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolicyRequest {
+    pub filter: String,
+}
+
+/// Request to transform a dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransformRequest {
     pub source: String,
     pub target: Option<String>,
+    /// Session to keep the result in under an automatic name, when no target is given
+    pub session: Option<String>,
     pub transform_type: String,
     pub params: JsonValue,
 }
 
 /// Request to filter a dataset
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FilterRequest {
     pub source: String,
     pub target: Option<String>,
+    /// Session to keep the result in under an automatic name, when no target is given
+    pub session: Option<String>,
     pub filter_type: String,
     pub params: JsonValue,
 }
 
 /// Aggregation definition
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Aggregation {
     pub function: String,
     pub input_column: String,
@@ -53,27 +237,51 @@ pub struct Aggregation {
 }
 
 /// Request to aggregate a dataset
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AggregateRequest {
     pub source: String,
     pub target: Option<String>,
+    /// Session to keep the result in under an automatic name, when no target is given
+    pub session: Option<String>,
     pub group_by: Option<Vec<String>>,
     pub aggregations: Vec<Aggregation>,
+    /// Overflow policy for sums: `error` (default), `saturate` or `promote`
+    pub overflow: Option<String>,
+    /// NaN and infinity handling: `propagate` (default), `skip` or `error`
+    pub nan_policy: Option<String>,
+    /// Filter expression on the aggregated rows, such as `count > 10`
+    pub having: Option<String>,
 }
 
 /// Request to join datasets
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JoinRequest {
     pub left: String,
     pub right: String,
     pub target: Option<String>,
+    /// Session to keep the result in under an automatic name, when no target is given
+    pub session: Option<String>,
     pub join_type: String,
     pub left_columns: Vec<String>,
     pub right_columns: Vec<String>,
 }
 
+/// Request to run a pipeline specification on a dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineRequest {
+    pub source: String,
+    pub target: Option<String>,
+    /// Session to keep the result in under an automatic name, when no target is given
+    pub session: Option<String>,
+    /// Pipeline specification, possibly a template with `${name}` parameters
+    pub pipeline: JsonValue,
+    /// Values of the template's parameters
+    #[serde(default)]
+    pub params: BTreeMap<String, JsonValue>,
+}
+
 /// Request to compute statistics on a dataset
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StatsRequest {
     pub source: String,
     pub stats_type: String,
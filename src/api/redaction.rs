@@ -0,0 +1,139 @@
+// Truncation and redaction of values in dataset responses
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::{HashMap, HashSet};
+
+use crate::data::{DataSet, DataType, Value};
+
+/// Dataset name whose policy applies to datasets without a policy of their own
+pub const DEFAULT_DATASET: &str = "*";
+
+/// Text replacing the values of redacted columns
+pub const REDACTED: &str = "[redacted]";
+
+/// Text replacing hidden binary values
+pub const HIDDEN_BINARY: &str = "[binary data]";
+
+/// How values of a dataset are shown in responses
+///
+/// Redacted columns keep their nulls but every other value is replaced, long
+/// strings are cut to a number of characters, and binary values are either
+/// hidden or sent base64 encoded. Columns whose values are replaced are
+/// reported as string columns.
+#[derive(Debug, Clone)]
+pub struct ResponsePolicy {
+    max_string_length: Option<usize>,
+    redacted: HashSet<String>,
+    hide_binary: bool,
+}
+
+impl ResponsePolicy {
+    /// Create a policy that only hides binary values
+    pub fn new() -> Self {
+        ResponsePolicy::default()
+    }
+    
+    /// Cut strings longer than a number of characters, marking them with an ellipsis
+    pub fn with_max_string_length(mut self, length: usize) -> Self {
+        self.max_string_length = Some(length);
+        self
+    }
+    
+    /// Replace every value of a column
+    pub fn with_redacted_column<S: Into<String>>(mut self, column: S) -> Self {
+        self.redacted.insert(column.into());
+        self
+    }
+    
+    /// Hide binary values, or send them base64 encoded
+    pub fn with_hide_binary(mut self, hide: bool) -> Self {
+        self.hide_binary = hide;
+        self
+    }
+    
+    /// Apply the policy to a dataset about to be sent
+    pub fn apply(&self, dataset: &mut DataSet) {
+        for (index, field) in dataset.schema.fields.iter_mut().enumerate() {
+            let redacted = self.redacted.contains(&field.name);
+            
+            if redacted || field.data_type == DataType::Binary {
+                field.data_type = DataType::String;
+                field.default = None;
+            }
+            
+            for row in &mut dataset.data {
+                if let Some(value) = row.values.get_mut(index) {
+                    self.apply_value(value, redacted);
+                }
+            }
+        }
+    }
+    
+    /// Apply the policy to one value of a column
+    fn apply_value(&self, value: &mut Value, redacted: bool) {
+        match value {
+            Value::Null => {},
+            _ if redacted => *value = Value::String(REDACTED.to_string()),
+            Value::Binary(_) if self.hide_binary => *value = Value::String(HIDDEN_BINARY.to_string()),
+            Value::Binary(bytes) => {
+                let encoded = base64::encode(bytes);
+                *value = Value::String(encoded);
+                self.apply_value(value, false);
+            },
+            Value::String(s) => {
+                if let Some(max_length) = self.max_string_length {
+                    if let Some((end, _)) = s.char_indices().nth(max_length) {
+                        s.truncate(end);
+                        s.push('…');
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+impl Default for ResponsePolicy {
+    fn default() -> Self {
+        ResponsePolicy {
+            max_string_length: None,
+            redacted: HashSet::new(),
+            hide_binary: true,
+        }
+    }
+}
+
+/// Response policies by dataset name
+///
+/// Datasets without a policy of their own get the policy of
+/// `DEFAULT_DATASET`, or the default policy if there is none.
+#[derive(Debug, Clone, Default)]
+pub struct ResponsePolicies {
+    policies: HashMap<String, ResponsePolicy>,
+    fallback: ResponsePolicy,
+}
+
+impl ResponsePolicies {
+    /// Create a set of policies applying the default policy to every dataset
+    pub fn new() -> Self {
+        ResponsePolicies::default()
+    }
+    
+    /// Apply a policy to a dataset, or to every other dataset with `DEFAULT_DATASET`
+    pub fn with_policy<S: Into<String>>(mut self, dataset: S, policy: ResponsePolicy) -> Self {
+        self.policies.insert(dataset.into(), policy);
+        self
+    }
+    
+    /// Get the policy applied to a dataset
+    pub fn policy_for(&self, dataset: &str) -> &ResponsePolicy {
+        self.policies.get(dataset)
+            .or_else(|| self.policies.get(DEFAULT_DATASET))
+            .unwrap_or(&self.fallback)
+    }
+    
+    /// Apply the policy of a dataset to it before it is sent
+    pub fn apply(&self, name: &str, dataset: &mut DataSet) {
+        self.policy_for(name).apply(dataset);
+    }
+}
@@ -0,0 +1,164 @@
+// Request correlation IDs
+// Author: Gabriel Demetrios Lafis
+
+use std::fmt;
+use std::future::{ready, Ready};
+use std::time::Instant;
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, FromRequest, HttpMessage, HttpRequest};
+
+use crate::data::{format_uuid, generate_uuid};
+use crate::utils::{parse_traceparent, Span, SpanKind, Telemetry, DURATION_BUCKETS};
+use super::ApiError;
+
+/// W3C trace context header joining a request to its caller's trace
+const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Header carrying the correlation ID of a request and its response
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest correlation ID accepted from a client
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Correlation ID of a request
+///
+/// Taken from the `X-Request-Id` header when the client sends a usable one,
+/// otherwise generated, so calls made on behalf of an upstream request keep
+/// its ID. The ID is echoed in the response header, in error bodies and in
+/// the request log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Generate a new random ID
+    pub fn generate() -> Self {
+        RequestId(format_uuid(generate_uuid()))
+    }
+    
+    /// Use the ID a client sent, or generate one if it is missing or unusable
+    ///
+    /// IDs are accepted up to 128 characters of letters, digits, `-`, `_`,
+    /// `.` and `:`, so they can be logged and echoed safely.
+    pub fn from_header(value: Option<&HeaderValue>) -> Self {
+        let id = value.and_then(|value| value.to_str().ok()).map(str::trim);
+        
+        match id {
+            Some(id) if Self::is_valid(id) => RequestId(id.to_string()),
+            _ => Self::generate(),
+        }
+    }
+    
+    /// Check if a client-supplied ID can be used as is
+    fn is_valid(id: &str) -> bool {
+        !id.is_empty()
+            && id.len() <= MAX_REQUEST_ID_LEN
+            && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+    }
+    
+    /// Get the ID as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromRequest for RequestId {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+    
+    /// Get the ID assigned by `track_requests`, or a new one when the
+    /// middleware is not installed
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let id = req.extensions().get::<RequestId>().cloned().unwrap_or_else(RequestId::generate);
+        ready(Ok(id))
+    }
+}
+
+/// Middleware assigning a correlation ID to every request
+///
+/// The ID is stored in the request extensions for handlers, set on the
+/// response's `X-Request-Id` header and added to JSON error bodies, and each
+/// request is logged with it once answered. With telemetry configured, each
+/// request is also recorded as a server span, joining the caller's trace
+/// when a `traceparent` header is sent, and in a duration histogram.
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let id = RequestId::from_header(req.headers().get(REQUEST_ID_HEADER));
+    req.extensions_mut().insert(id.clone());
+    
+    let telemetry = req.app_data::<web::Data<Telemetry>>().cloned();
+    let span = telemetry.as_ref().map(|_| {
+        let mut span = Span::start(&format!("{} {}", req.method(), req.path()), SpanKind::Server);
+        
+        let parent = req.headers().get(TRACEPARENT_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_traceparent);
+        if let Some((trace_id, parent_span_id)) = parent {
+            span = span.with_parent(trace_id, parent_span_id);
+        }
+        
+        span.set_attribute("http.request.method", req.method().as_str());
+        span.set_attribute("url.path", req.path());
+        span.set_attribute("request.id", id.as_str());
+        span
+    });
+    
+    // The request must not be cloned before routing, which needs sole access to it
+    let started = Instant::now();
+    let mut res = next.call(req).await?.map_into_boxed_body();
+    
+    // Rebuild API error bodies to name the request
+    let error_response = res.response().error()
+        .and_then(|err| err.as_error::<ApiError>())
+        .map(|err| err.response_for_request(&id));
+    if let Some(error_response) = error_response {
+        res = res.into_response(error_response);
+    }
+    
+    if let Ok(value) = HeaderValue::from_str(id.as_str()) {
+        res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    
+    let status = res.status();
+    let request = res.request();
+    if status.is_server_error() {
+        log::error!("[{}] {} {} -> {} in {:?}", id, request.method(), request.path(), status.as_u16(), started.elapsed());
+    } else {
+        log::info!("[{}] {} {} -> {} in {:?}", id, request.method(), request.path(), status.as_u16(), started.elapsed());
+    }
+    
+    if let (Some(telemetry), Some(mut span)) = (telemetry, span) {
+        // Name spans and series by route, so paths with dataset names group together
+        let route = request.match_pattern().unwrap_or_else(|| "unmatched".to_string());
+        let status_code = status.as_u16().to_string();
+        
+        span.set_name(&format!("{} {}", request.method(), route));
+        span.set_attribute("http.route", route.as_str());
+        span.set_attribute("http.response.status_code", status.as_u16() as i64);
+        if status.is_server_error() {
+            span.set_error(status.canonical_reason().unwrap_or("Server error"));
+        }
+        telemetry.record_span(span);
+        
+        telemetry.record_histogram(
+            "http.server.request.duration",
+            "s",
+            &DURATION_BUCKETS,
+            &[("http.request.method", request.method().as_str()), ("http.route", &route), ("http.response.status_code", &status_code)],
+            started.elapsed().as_secs_f64(),
+        );
+    }
+    
+    Ok(res)
+}
@@ -3,7 +3,10 @@
 
 use actix_web::{web, HttpResponse, Responder};
 
-use super::handlers;
+use super::{handlers, ProcessingTimeout};
+
+/// Largest backup archive accepted for restoring
+const MAX_RESTORE_BYTES: usize = 1 << 30;
 
 /// Configure API routes
 pub fn configure(cfg: &mut web::ServiceConfig) {
@@ -18,8 +21,93 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
                     .route("", web::get().to(handlers::list_datasets))
                     .route("", web::post().to(handlers::create_dataset))
                     .route("/{name}", web::get().to(handlers::get_dataset))
+                    .route("/{name}", web::head().to(handlers::head_dataset))
                     .route("/{name}", web::put().to(handlers::update_dataset))
                     .route("/{name}", web::delete().to(handlers::delete_dataset))
+                    .route("/{name}/info", web::get().to(handlers::get_dataset_info))
+                    .route("/{name}/tags", web::put().to(handlers::put_tags))
+                    .route("/{name}/lineage", web::get().to(handlers::get_lineage))
+                    .route("/{name}/preview", web::get().to(handlers::preview_dataset))
+                    .route("/{name}/columns/{column}", web::get().to(handlers::get_column))
+                    .route("/{name}/search", web::get().to(handlers::search_dataset))
+                    .route("/{name}/chart", web::post().to(handlers::chart_dataset))
+                    .route("/{name}/report", web::get().to(handlers::report_dataset))
+                    .route("/{name}/rows", web::post().to(handlers::append_rows))
+                    .route("/{name}/drift", web::post().to(handlers::check_drift))
+                    .route("/{name}/changes", web::post().to(handlers::apply_changes))
+                    .route("/{name}/export", web::post().to(handlers::export_dataset))
+                    .route("/{name}/copy", web::post().to(handlers::copy_dataset))
+                    .route("/{name}/rename", web::post().to(handlers::rename_dataset))
+                    .route("/{name}/move", web::post().to(handlers::move_dataset))
+                    .route("/{name}/publish", web::post().to(handlers::publish_dataset))
+                    .route("/{name}/unlock", web::post().to(handlers::unlock_dataset))
+            )
+            
+            // Aliases
+            .service(
+                web::scope("/aliases")
+                    .route("", web::get().to(handlers::list_aliases))
+                    .route("/{alias}", web::put().to(handlers::put_alias))
+                    .route("/{alias}", web::delete().to(handlers::delete_alias))
+            )
+            
+            // Views
+            .service(
+                web::scope("/views")
+                    .route("", web::get().to(handlers::list_views))
+                    .route("/{name}", web::put().to(handlers::put_view))
+                    .route("/{name}", web::delete().to(handlers::delete_view))
+            )
+            
+            // Trash
+            .service(
+                web::scope("/trash")
+                    .route("", web::get().to(handlers::list_trash))
+                    .route("", web::delete().to(handlers::purge_trash))
+                    .route("/{name}", web::delete().to(handlers::purge_dataset))
+                    .route("/{name}/restore", web::post().to(handlers::restore_dataset))
+            )
+            
+            // Sessions of temporary datasets
+            .service(
+                web::scope("/sessions")
+                    .route("", web::post().to(handlers::create_session))
+                    .route("/{session}", web::get().to(handlers::get_session))
+                    .route("/{session}", web::delete().to(handlers::delete_session))
+            )
+            
+            // Row-level security policies
+            .service(
+                web::scope("/policies")
+                    .route("", web::get().to(handlers::list_policies))
+                    .route("/{name}/{role}", web::put().to(handlers::put_policy))
+                    .route("/{name}/{role}", web::delete().to(handlers::delete_policy))
+            )
+            
+            // Ingestion of landing directories
+            .service(
+                web::scope("/ingestion")
+                    .route("", web::get().to(handlers::ingestion_status))
+                    .route("/scan", web::post().to(handlers::scan_ingestion))
+            )
+            
+            // Maintenance
+            .route("/maintenance", web::post().to(handlers::maintain_storage))
+            
+            // Backup and restore of the whole storage
+            .route("/backup", web::get().to(handlers::create_backup))
+            .service(
+                web::resource("/restore")
+                    .app_data(web::PayloadConfig::new(MAX_RESTORE_BYTES))
+                    .route(web::post().to(handlers::restore_backup))
+            )
+            
+            // Administration
+            .service(
+                web::scope("/admin")
+                    .route("/cache", web::get().to(handlers::admin_cache))
+                    .route("/cache", web::delete().to(handlers::clear_admin_cache))
+                    .route("/storage", web::get().to(handlers::admin_storage))
             )
             
             // Processing
@@ -30,15 +118,17 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
                     .route("/aggregate", web::post().to(handlers::aggregate_dataset))
                     .route("/join", web::post().to(handlers::join_datasets))
                     .route("/stats", web::post().to(handlers::compute_stats))
+                    .route("/pipeline", web::post().to(handlers::run_pipeline))
             )
     );
 }
 
 /// Health check handler
-async fn health_check() -> impl Responder {
+async fn health_check(processing_timeout: Option<web::Data<ProcessingTimeout>>) -> impl Responder {
     HttpResponse::Ok().json(serde_json::json!({
         "status": "ok",
         "version": env!("CARGO_PKG_VERSION"),
+        "processing_timeouts": processing_timeout.map(|timeout| timeout.timeouts()),
     }))
 }
 
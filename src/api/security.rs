@@ -0,0 +1,566 @@
+// API principals and row-level security
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::future::{ready, Ready};
+use std::io::{BufReader, BufWriter};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use actix_web::dev::Payload;
+use actix_web::{error, web, FromRequest, HttpRequest};
+
+use crate::data::{ColumnRange, DataSet, LineageStep};
+use crate::processing::FilterExpression;
+use crate::storage::{
+    project_columns, DatasetInfo, DataStorage, MaintenanceReport, StorageError, StorageStatus, TrashEntry, ViewDefinition,
+};
+use super::ApiError;
+
+/// Header carrying the API key of a request
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+/// Role whose policy applies to roles without a policy of their own
+pub const DEFAULT_ROLE: &str = "*";
+
+/// API keys, roles and row-level security policies
+///
+/// Each API key maps to a role. Administrators attach a filter expression to
+/// a dataset per role, and every read through the API by that role only sees
+/// the matching rows. Admin roles always see all rows. Policies are keyed by
+/// dataset name, so they stay with a name across deletes and restores.
+pub struct AccessControl {
+    api_keys: HashMap<String, String>,
+    admin_roles: HashSet<String>,
+    policies: RwLock<BTreeMap<String, BTreeMap<String, FilterExpression>>>,
+    policy_file: Option<PathBuf>,
+}
+
+impl AccessControl {
+    /// Create a new access control without keys or policies
+    pub fn new() -> Self {
+        AccessControl {
+            api_keys: HashMap::new(),
+            admin_roles: HashSet::new(),
+            policies: RwLock::new(BTreeMap::new()),
+            policy_file: None,
+        }
+    }
+    
+    /// Accept an API key for a role
+    pub fn with_api_key<K: Into<String>, R: Into<String>>(mut self, key: K, role: R) -> Self {
+        self.api_keys.insert(key.into(), role.into());
+        self
+    }
+    
+    /// Let a role manage policies and bypass row filtering
+    pub fn with_admin_role<R: Into<String>>(mut self, role: R) -> Self {
+        self.admin_roles.insert(role.into());
+        self
+    }
+    
+    /// Persist policies to a JSON file, loading any existing policies
+    pub fn with_policy_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self, StorageError> {
+        let path = path.as_ref().to_path_buf();
+        
+        if path.exists() {
+            let reader = BufReader::new(File::open(&path)?);
+            let stored: BTreeMap<String, BTreeMap<String, String>> = serde_json::from_reader(reader)
+                .map_err(|e| StorageError::InvalidFormat(e.to_string()))?;
+            
+            let mut policies = BTreeMap::new();
+            for (dataset, roles) in stored {
+                let mut parsed = BTreeMap::new();
+                
+                for (role, filter) in roles {
+                    let expression = FilterExpression::parse(&filter).map_err(|e| StorageError::InvalidFormat(
+                        format!("Invalid policy for '{}' on dataset '{}': {}", role, dataset, e)
+                    ))?;
+                    parsed.insert(role, expression);
+                }
+                
+                policies.insert(dataset, parsed);
+            }
+            
+            self.policies = RwLock::new(policies);
+        }
+        
+        self.policy_file = Some(path);
+        Ok(self)
+    }
+    
+    /// Identify the principal making a request from its API key
+    pub fn authenticate(&self, req: &HttpRequest) -> Result<Principal, ApiError> {
+        let key = req.headers()
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| ApiError::Unauthorized(format!("Missing {} header", API_KEY_HEADER)))?;
+        
+        let role = self.api_keys.get(key)
+            .ok_or_else(|| ApiError::Unauthorized("Invalid API key".to_string()))?;
+        
+        Ok(Principal {
+            role: Some(role.clone()),
+            admin: self.admin_roles.contains(role),
+        })
+    }
+    
+    /// Get the policies of all datasets as filter text by role
+    pub fn policies(&self) -> Result<BTreeMap<String, BTreeMap<String, String>>, StorageError> {
+        let policies = self.policies.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        Ok(policies.iter()
+            .map(|(dataset, roles)| {
+                let roles = roles.iter()
+                    .map(|(role, expression)| (role.clone(), expression.to_string()))
+                    .collect();
+                (dataset.clone(), roles)
+            })
+            .collect())
+    }
+    
+    /// Get the filter applied to a role reading a dataset, if any
+    pub fn policy_for(&self, dataset: &str, role: &str) -> Result<Option<FilterExpression>, StorageError> {
+        let policies = self.policies.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        Ok(policies.get(dataset).and_then(|roles| {
+            roles.get(role).or_else(|| roles.get(DEFAULT_ROLE)).cloned()
+        }))
+    }
+    
+    /// Attach a filter to a dataset for a role, replacing any existing one
+    pub fn set_policy(&self, dataset: &str, role: &str, filter: FilterExpression) -> Result<(), StorageError> {
+        let mut policies = self.policies.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        policies.entry(dataset.to_string())
+            .or_insert_with(BTreeMap::new)
+            .insert(role.to_string(), filter);
+        
+        self.save_policies(&policies)
+    }
+    
+    /// Remove the filter of a role from a dataset
+    pub fn remove_policy(&self, dataset: &str, role: &str) -> Result<(), StorageError> {
+        let mut policies = self.policies.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        let roles = policies.get_mut(dataset)
+            .ok_or_else(|| StorageError::NotFound(format!("No policies for dataset '{}'", dataset)))?;
+        
+        if roles.remove(role).is_none() {
+            return Err(StorageError::NotFound(
+                format!("No policy for role '{}' on dataset '{}'", role, dataset)
+            ));
+        }
+        
+        if roles.is_empty() {
+            policies.remove(dataset);
+        }
+        
+        self.save_policies(&policies)
+    }
+    
+    /// Write the policies to the policy file, if any
+    fn save_policies(&self, policies: &BTreeMap<String, BTreeMap<String, FilterExpression>>) -> Result<(), StorageError> {
+        if let Some(path) = &self.policy_file {
+            let stored: BTreeMap<&String, BTreeMap<&String, String>> = policies.iter()
+                .map(|(dataset, roles)| {
+                    (dataset, roles.iter().map(|(role, expression)| (role, expression.to_string())).collect())
+                })
+                .collect();
+            
+            let writer = BufWriter::new(File::create(path)?);
+            serde_json::to_writer_pretty(writer, &stored)
+                .map_err(|e| StorageError::Other(e.to_string()))?;
+        }
+        
+        Ok(())
+    }
+}
+
+impl Default for AccessControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The caller of an API request
+///
+/// Without access control every caller is an anonymous administrator.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    role: Option<String>,
+    admin: bool,
+}
+
+impl Principal {
+    /// Get the role of the principal
+    pub fn role(&self) -> Option<&str> {
+        self.role.as_deref()
+    }
+    
+    /// Check if the principal may manage policies and see all rows
+    pub fn is_admin(&self) -> bool {
+        self.admin
+    }
+    
+    /// Identify the principal of a request
+    fn from_http_request(req: &HttpRequest) -> Result<Self, ApiError> {
+        match req.app_data::<web::Data<AccessControl>>() {
+            Some(access) => access.authenticate(req),
+            None => Ok(Principal { role: None, admin: true }),
+        }
+    }
+}
+
+impl FromRequest for Principal {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+    
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Self::from_http_request(req).map_err(|e| error::ErrorUnauthorized(e.to_string())))
+    }
+}
+
+/// Storage as seen by the principal of a request
+///
+/// Extracted in handlers in place of the shared storage, so reads of
+/// datasets with a policy for the principal's role only return matching rows.
+pub struct ScopedStorage(Arc<dyn DataStorage + Send + Sync>);
+
+impl Deref for ScopedStorage {
+    type Target = dyn DataStorage + Send + Sync;
+    
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl FromRequest for ScopedStorage {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+    
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let storage = match req.app_data::<web::Data<Arc<dyn DataStorage + Send + Sync>>>() {
+            Some(storage) => storage.get_ref().clone(),
+            None => return ready(Err(error::ErrorInternalServerError("Storage is not configured"))),
+        };
+        
+        let access = match req.app_data::<web::Data<AccessControl>>() {
+            Some(access) => access.clone().into_inner(),
+            None => return ready(Ok(ScopedStorage(storage))),
+        };
+        
+        let principal = match access.authenticate(req) {
+            Ok(principal) => principal,
+            Err(err) => return ready(Err(error::ErrorUnauthorized(err.to_string()))),
+        };
+        
+        if principal.is_admin() {
+            return ready(Ok(ScopedStorage(storage)));
+        }
+        
+        ready(Ok(ScopedStorage(Arc::new(PolicyStorage {
+            backend: storage,
+            access,
+            role: principal.role.unwrap_or_default(),
+        }))))
+    }
+}
+
+/// Storage wrapper applying the row policies of one role
+///
+/// Datasets restricted for the role are read through their filter and
+/// cannot be modified, since writing back a filtered view would drop rows.
+/// Views reading restricted datasets are computed from the filtered rows.
+struct PolicyStorage {
+    backend: Arc<dyn DataStorage + Send + Sync>,
+    access: Arc<AccessControl>,
+    role: String,
+}
+
+impl PolicyStorage {
+    /// Get the filter for a dataset, resolving aliases of the backend
+    fn policy(&self, name: &str) -> Result<Option<FilterExpression>, StorageError> {
+        let name = self.backend.aliases()?
+            .into_iter()
+            .find(|(alias, _)| alias == name)
+            .map_or_else(|| name.to_string(), |(_, target)| target);
+        
+        self.access.policy_for(&name, &self.role)
+    }
+    
+    /// Check that a dataset is not restricted for the role
+    fn check_unrestricted(&self, name: &str) -> Result<(), StorageError> {
+        if self.policy(name)?.is_some() {
+            return Err(StorageError::ReadOnly(format!(
+                "Dataset '{}' is restricted by a row policy for role '{}'", name, self.role
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    /// Compute a view through the role's filters, if it reads a restricted dataset
+    ///
+    /// Views reading only unrestricted datasets are left to the backend.
+    fn restricted_view(&self, name: &str) -> Result<Option<DataSet>, StorageError> {
+        let views: BTreeMap<String, ViewDefinition> = self.backend.views()?.into_iter().collect();
+        
+        match views.get(name) {
+            Some(view) if self.reads_restricted(view, &views)? => Ok(Some(view.evaluate(name, &|input| self.load(input))?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Check whether a view reads a restricted dataset, directly or through other views
+    fn reads_restricted(&self, view: &ViewDefinition, views: &BTreeMap<String, ViewDefinition>) -> Result<bool, StorageError> {
+        for input in view.inputs() {
+            let restricted = match views.get(input) {
+                Some(other) => self.reads_restricted(other, views)?,
+                None => self.policy(input)?.is_some(),
+            };
+            
+            if restricted {
+                return Ok(true);
+            }
+        }
+        
+        Ok(false)
+    }
+    
+    /// Load a dataset with the role's filter applied
+    fn load_filtered(&self, name: &str, filter: &FilterExpression) -> Result<DataSet, StorageError> {
+        let mut data = self.backend.load(name)?;
+        
+        let (schema, rows) = (&data.schema, &mut data.data);
+        rows.retain(|row| filter.matches(row, schema));
+        
+        Ok(data)
+    }
+}
+
+impl DataStorage for PolicyStorage {
+    fn store(&self, name: &str, data: &DataSet) -> Result<(), StorageError> {
+        self.check_unrestricted(name)?;
+        self.backend.store(name, data)
+    }
+    
+    fn load(&self, name: &str) -> Result<DataSet, StorageError> {
+        if let Some(data) = self.restricted_view(name)? {
+            return Ok(data);
+        }
+        
+        match self.policy(name)? {
+            Some(filter) => self.load_filtered(name, &filter),
+            None => self.backend.load(name),
+        }
+    }
+    
+    fn exists(&self, name: &str) -> Result<bool, StorageError> {
+        self.backend.exists(name)
+    }
+    
+    fn delete(&self, name: &str) -> Result<(), StorageError> {
+        self.check_unrestricted(name)?;
+        self.backend.delete(name)
+    }
+    
+    fn list(&self) -> Result<Vec<String>, StorageError> {
+        self.backend.list()
+    }
+    
+    fn copy(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.check_unrestricted(to)?;
+        
+        if let Some(data) = self.restricted_view(from)? {
+            if self.backend.exists(to)? {
+                return Err(StorageError::AlreadyExists(to.to_string()));
+            }
+            
+            return self.backend.store(to, &data);
+        }
+        
+        match self.policy(from)? {
+            // Copies of a restricted dataset only hold the visible rows
+            Some(filter) => {
+                if self.backend.exists(to)? {
+                    return Err(StorageError::AlreadyExists(to.to_string()));
+                }
+                
+                self.backend.store(to, &self.load_filtered(from, &filter)?)
+            },
+            None => self.backend.copy(from, to),
+        }
+    }
+    
+    fn rename(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.check_unrestricted(from)?;
+        self.check_unrestricted(to)?;
+        self.backend.rename(from, to)
+    }
+    
+    fn load_head(&self, name: &str, rows: usize) -> Result<DataSet, StorageError> {
+        if let Some(mut data) = self.restricted_view(name)? {
+            data.data.truncate(rows);
+            return Ok(data);
+        }
+        
+        match self.policy(name)? {
+            Some(filter) => {
+                let mut data = self.load_filtered(name, &filter)?;
+                data.data.truncate(rows);
+                Ok(data)
+            },
+            None => self.backend.load_head(name, rows),
+        }
+    }
+    
+    fn load_range(&self, name: &str, offset: usize, limit: usize) -> Result<DataSet, StorageError> {
+        if let Some(mut data) = self.restricted_view(name)? {
+            data.data = data.data.into_iter().skip(offset).take(limit).collect();
+            return Ok(data);
+        }
+        
+        match self.policy(name)? {
+            Some(filter) => {
+                let mut data = self.load_filtered(name, &filter)?;
+                data.data = data.data.into_iter().skip(offset).take(limit).collect();
+                Ok(data)
+            },
+            None => self.backend.load_range(name, offset, limit),
+        }
+    }
+    
+    fn load_columns(&self, name: &str, columns: &[String]) -> Result<DataSet, StorageError> {
+        if let Some(data) = self.restricted_view(name)? {
+            return project_columns(&data, columns);
+        }
+        
+        // The filter may use columns outside the projection
+        match self.policy(name)? {
+            Some(filter) => project_columns(&self.load_filtered(name, &filter)?, columns),
+            None => self.backend.load_columns(name, columns),
+        }
+    }
+    
+    fn load_pruned(&self, name: &str, ranges: &[ColumnRange]) -> Result<DataSet, StorageError> {
+        if let Some(data) = self.restricted_view(name)? {
+            return Ok(data);
+        }
+        
+        let mut data = self.backend.load_pruned(name, ranges)?;
+        
+        if let Some(filter) = self.policy(name)? {
+            let (schema, rows) = (&data.schema, &mut data.data);
+            rows.retain(|row| filter.matches(row, schema));
+        }
+        
+        Ok(data)
+    }
+    
+    fn info(&self, name: &str) -> Result<DatasetInfo, StorageError> {
+        let mut info = self.backend.info(name)?;
+        
+        if let Some(data) = self.restricted_view(name)? {
+            info.rows = data.len();
+        } else if let Some(filter) = self.policy(name)? {
+            info.rows = self.load_filtered(name, &filter)?.len();
+        }
+        
+        Ok(info)
+    }
+    
+    fn is_immutable(&self, name: &str) -> Result<bool, StorageError> {
+        self.backend.is_immutable(name)
+    }
+    
+    fn set_immutable(&self, name: &str, immutable: bool) -> Result<(), StorageError> {
+        self.check_unrestricted(name)?;
+        self.backend.set_immutable(name, immutable)
+    }
+    
+    fn aliases(&self) -> Result<Vec<(String, String)>, StorageError> {
+        self.backend.aliases()
+    }
+    
+    fn create_alias(&self, alias: &str, target: &str) -> Result<(), StorageError> {
+        self.check_unrestricted(alias)?;
+        self.backend.create_alias(alias, target)
+    }
+    
+    fn remove_alias(&self, alias: &str) -> Result<(), StorageError> {
+        self.backend.remove_alias(alias)
+    }
+    
+    fn views(&self) -> Result<Vec<(String, ViewDefinition)>, StorageError> {
+        self.backend.views()
+    }
+    
+    fn create_view(&self, name: &str, view: &ViewDefinition) -> Result<(), StorageError> {
+        self.check_unrestricted(name)?;
+        self.backend.create_view(name, view)
+    }
+    
+    fn remove_view(&self, name: &str) -> Result<(), StorageError> {
+        self.check_unrestricted(name)?;
+        self.backend.remove_view(name)
+    }
+    
+    fn tags(&self, name: &str) -> Result<Vec<String>, StorageError> {
+        self.backend.tags(name)
+    }
+    
+    fn set_tags(&self, name: &str, tags: &[String]) -> Result<(), StorageError> {
+        self.check_unrestricted(name)?;
+        self.backend.set_tags(name, tags)
+    }
+    
+    fn lineage(&self, name: &str) -> Result<BTreeMap<String, Vec<LineageStep>>, StorageError> {
+        self.backend.lineage(name)
+    }
+    
+    fn trash(&self) -> Result<Vec<TrashEntry>, StorageError> {
+        self.backend.trash()
+    }
+    
+    fn restore(&self, name: &str) -> Result<(), StorageError> {
+        self.check_unrestricted(name)?;
+        self.backend.restore(name)
+    }
+    
+    fn purge(&self, name: Option<&str>) -> Result<usize, StorageError> {
+        match name {
+            Some(name) => self.check_unrestricted(name)?,
+            None => {
+                for entry in self.backend.trash()? {
+                    self.check_unrestricted(&entry.name)?;
+                }
+            },
+        }
+        
+        self.backend.purge(name)
+    }
+    
+    fn maintain(&self, report: &mut MaintenanceReport) -> Result<(), StorageError> {
+        self.backend.maintain(report)
+    }
+    
+    fn describe(&self, status: &mut StorageStatus) -> Result<(), StorageError> {
+        status.layers.push("policy".to_string());
+        self.backend.describe(status)
+    }
+    
+    fn clear_cache(&self) -> Result<usize, StorageError> {
+        self.backend.clear_cache()
+    }
+}
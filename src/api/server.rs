@@ -4,11 +4,13 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
-use actix_web::{web, App, HttpServer};
+use actix_web::{middleware, web, App, HttpServer};
 use actix_cors::Cors;
 
-use crate::storage::DataStorage;
-use super::routes;
+use crate::processing::ResultCache;
+use crate::storage::{DataStorage, IngestionService, SessionStorage};
+use crate::utils::Telemetry;
+use super::{routes, track_requests, AccessControl, ProcessingTimeout, ResponseLimit, ResponsePolicies, ExportRoot, REQUEST_ID_HEADER};
 
 /// API server configuration
 pub struct ServerConfig {
@@ -33,6 +35,15 @@ impl Default for ServerConfig {
 pub struct Server {
     config: ServerConfig,
     storage: Arc<dyn DataStorage + Send + Sync>,
+    access: Option<web::Data<AccessControl>>,
+    result_cache: Option<web::Data<ResultCache>>,
+    processing_timeout: Option<web::Data<ProcessingTimeout>>,
+    response_limit: Option<web::Data<ResponseLimit>>,
+    response_policies: Option<web::Data<ResponsePolicies>>,
+    sessions: Option<web::Data<SessionStorage>>,
+    ingestion: Option<web::Data<IngestionService>>,
+    telemetry: Option<web::Data<Telemetry>>,
+    export_root: Option<web::Data<ExportRoot>>,
 }
 
 impl Server {
@@ -44,39 +55,167 @@ impl Server {
         Server {
             config,
             storage: Arc::new(storage),
+            access: None,
+            result_cache: None,
+            processing_timeout: None,
+            response_limit: None,
+            response_policies: None,
+            sessions: None,
+            ingestion: None,
+            telemetry: None,
+            export_root: None,
         }
     }
     
+    /// Require API keys and apply row-level security policies
+    pub fn with_access_control(mut self, access: AccessControl) -> Self {
+        self.access = Some(web::Data::new(access));
+        self
+    }
+    
+    /// Serve repeated processing requests on unchanged inputs from a cache
+    ///
+    /// The storage must report its writes to the same cache, for example by
+    /// being wrapped in an `InvalidatingStorage`.
+    pub fn with_result_cache(mut self, cache: Arc<ResultCache>) -> Self {
+        self.result_cache = Some(web::Data::from(cache));
+        self
+    }
+    
+    /// Cancel processing requests that run longer than the timeout
+    pub fn with_processing_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.processing_timeout = Some(web::Data::new(ProcessingTimeout::new(timeout)));
+        self
+    }
+    
+    /// Cap the rows and bytes returned inline by dataset and processing responses
+    pub fn with_response_limit(mut self, limit: ResponseLimit) -> Self {
+        self.response_limit = Some(web::Data::new(limit));
+        self
+    }
+    
+    /// Truncate and redact the values of datasets returned by read endpoints
+    pub fn with_response_policies(mut self, policies: ResponsePolicies) -> Self {
+        self.response_policies = Some(web::Data::new(policies));
+        self
+    }
+    
+    /// Let clients open sessions that keep temporary datasets
+    ///
+    /// The storage must be, or wrap, the same session storage, so temporary
+    /// datasets written by requests are kept in their session.
+    pub fn with_sessions(mut self, sessions: Arc<SessionStorage>) -> Self {
+        self.sessions = Some(web::Data::from(sessions));
+        self
+    }
+    
+    /// Report on and trigger the ingestion of landing directories
+    pub fn with_ingestion(mut self, ingestion: Arc<IngestionService>) -> Self {
+        self.ingestion = Some(web::Data::from(ingestion));
+        self
+    }
+    
+    /// Record a trace span and duration metrics for every request
+    pub fn with_telemetry(mut self, telemetry: Arc<Telemetry>) -> Self {
+        self.telemetry = Some(web::Data::from(telemetry));
+        self
+    }
+    
+    /// Let administrators export datasets to directories under a root
+    pub fn with_export_root<P: AsRef<std::path::Path>>(mut self, root: P) -> Self {
+        self.export_root = Some(web::Data::new(ExportRoot::new(root)));
+        self
+    }
+    
     /// Run the API server
     pub async fn run(&self) -> std::io::Result<()> {
+        let (server, addr) = self.bind()?;
+        
+        println!("Starting server at http://{}", addr);
+        
+        server.await
+    }
+    
+    /// Bind the API server without running it
+    ///
+    /// Returns the server, to be awaited or spawned, and the address it
+    /// listens on, which gives the port picked when the configured port is 0.
+    pub fn bind(&self) -> std::io::Result<(actix_web::dev::Server, SocketAddr)> {
         let addr = format!("{}:{}", self.config.host, self.config.port);
         let addr = addr.parse::<SocketAddr>().unwrap();
         
         let storage = self.storage.clone();
+        let access = self.access.clone();
+        let result_cache = self.result_cache.clone();
+        let processing_timeout = self.processing_timeout.clone();
+        let response_limit = self.response_limit.clone();
+        let response_policies = self.response_policies.clone();
+        let sessions = self.sessions.clone();
+        let ingestion = self.ingestion.clone();
+        let telemetry = self.telemetry.clone();
+        let export_root = self.export_root.clone();
         let enable_cors = self.config.enable_cors;
         
-        println!("Starting server at http://{}", addr);
-        
-        HttpServer::new(move || {
+        let server = HttpServer::new(move || {
             let mut app = App::new()
                 .app_data(web::Data::new(storage.clone()));
             
+            if let Some(access) = &access {
+                app = app.app_data(access.clone());
+            }
+            
+            if let Some(result_cache) = &result_cache {
+                app = app.app_data(result_cache.clone());
+            }
+            
+            if let Some(processing_timeout) = &processing_timeout {
+                app = app.app_data(processing_timeout.clone());
+            }
+            
+            if let Some(response_limit) = &response_limit {
+                app = app.app_data(response_limit.clone());
+            }
+            
+            if let Some(response_policies) = &response_policies {
+                app = app.app_data(response_policies.clone());
+            }
+            
+            if let Some(sessions) = &sessions {
+                app = app.app_data(sessions.clone());
+            }
+            
+            if let Some(ingestion) = &ingestion {
+                app = app.app_data(ingestion.clone());
+            }
+            
+            if let Some(telemetry) = &telemetry {
+                app = app.app_data(telemetry.clone());
+            }
+            
+            if let Some(export_root) = &export_root {
+                app = app.app_data(export_root.clone());
+            }
+            
             if enable_cors {
                 app = app.wrap(
                     Cors::default()
                         .allow_any_origin()
                         .allow_any_method()
                         .allow_any_header()
+                        .expose_headers([REQUEST_ID_HEADER])
                         .max_age(3600)
                 );
             }
             
-            app.configure(routes::configure)
+            app.wrap(middleware::from_fn(track_requests))
+                .configure(routes::configure)
         })
         .workers(self.config.workers)
-        .bind(addr)?
-        .run()
-        .await
+        .bind(addr)?;
+        
+        let addr = server.addrs().first().copied().unwrap_or(addr);
+        
+        Ok((server.run(), addr))
     }
 }
 
@@ -0,0 +1,272 @@
+// REST API client implementation
+// Author: Gabriel Demetrios Lafis
+
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::{Client, RequestBuilder, Response, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::api::{
+    AggregateRequest, AppendRowsRequest, CreateDatasetRequest, DriftRequest, FilterRequest, JoinRequest, ListQuery,
+    PipelineRequest, StatsRequest, TagsRequest, TransformRequest, API_KEY_HEADER, REQUEST_ID_HEADER,
+};
+use crate::data::{DataSet, MsgPackSink, MsgPackSource, MSGPACK_CONTENT_TYPE};
+
+use super::{
+    AppendedRows, ClientError, CreatedDataset, DatasetDetails, DatasetLineage, DatasetList, Health, ProcessPlan,
+    ProcessResult, SchemaDrift, StatsResult,
+};
+
+/// Async client for the engine's REST API
+///
+/// Datasets are uploaded and downloaded MessagePack encoded, so values keep
+/// their exact types, and downloads follow the server's pages until the
+/// whole dataset is read. Processing requests take the same models the
+/// server accepts.
+///
+/// ```no_run
+/// # async fn example() -> Result<(), rust_data_processing_engine::client::ClientError> {
+/// use rust_data_processing_engine::api::FilterRequest;
+/// use rust_data_processing_engine::client::EngineClient;
+///
+/// let client = EngineClient::new("http://localhost:8080").with_api_key("secret");
+///
+/// let result = client.filter(&FilterRequest {
+///     source: "sales".to_string(),
+///     target: Some("large_sales".to_string()),
+///     session: None,
+///     filter_type: "greater_than".to_string(),
+///     params: serde_json::json!({"column": "amount", "value": 1000}),
+/// }).await?;
+///
+/// let large_sales = client.get_dataset("large_sales").await?;
+/// assert_eq!(large_sales.len(), result.rows());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct EngineClient {
+    base_url: String,
+    api_key: Option<String>,
+    http: Client,
+}
+
+impl EngineClient {
+    /// Create a new client for a server, such as `http://localhost:8080`
+    pub fn new(base_url: &str) -> Self {
+        EngineClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: None,
+            http: Client::new(),
+        }
+    }
+    
+    /// Send an API key with every request
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+    
+    /// Use a configured HTTP client, for example one with timeouts or a proxy
+    pub fn with_http_client(mut self, http: Client) -> Self {
+        self.http = http;
+        self
+    }
+    
+    /// Check that the server is up
+    pub async fn health(&self) -> Result<Health, ClientError> {
+        let url = self.url(&["health"])?;
+        self.json(self.http.get(url)).await
+    }
+    
+    /// List datasets
+    pub async fn list_datasets(&self, query: &ListQuery) -> Result<DatasetList, ClientError> {
+        let url = self.url(&["datasets"])?;
+        self.json(self.http.get(url).query(query)).await
+    }
+    
+    /// Create a dataset from the JSON request shape
+    pub async fn create_dataset(&self, request: &CreateDatasetRequest) -> Result<CreatedDataset, ClientError> {
+        let url = self.url(&["datasets"])?;
+        self.json(self.http.post(url).json(request)).await
+    }
+    
+    /// Create a dataset from a local dataset, keeping its schema and values
+    pub async fn upload_dataset(&self, name: &str, data: &DataSet, tags: &[String]) -> Result<CreatedDataset, ClientError> {
+        let url = self.url(&["datasets"])?;
+        let mut query = vec![("name", name.to_string())];
+        if !tags.is_empty() {
+            query.push(("tags", tags.join(",")));
+        }
+        
+        let request = self.http.post(url)
+            .query(&query)
+            .header(CONTENT_TYPE, MSGPACK_CONTENT_TYPE)
+            .body(MsgPackSink::to_bytes(data));
+        
+        self.json(request).await
+    }
+    
+    /// Download a whole dataset, following pages
+    pub async fn get_dataset(&self, name: &str) -> Result<DataSet, ClientError> {
+        let url = self.url(&["datasets", name])?;
+        let mut dataset: Option<DataSet> = None;
+        let mut offset = 0;
+        
+        loop {
+            let request = self.http.get(url.clone())
+                .query(&[("offset", offset)])
+                .header(ACCEPT, MSGPACK_CONTENT_TYPE);
+            let response = self.send(request).await?;
+            
+            let next_offset = response.headers().get("x-next-offset")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<usize>().ok());
+            
+            let bytes = response.bytes().await?;
+            let page = MsgPackSource::new(name).read_from(bytes.as_ref())?;
+            
+            match dataset.as_mut() {
+                Some(dataset) => dataset.data.extend(page.data),
+                None => dataset = Some(page),
+            }
+            
+            match next_offset {
+                Some(next_offset) => offset = next_offset,
+                None => break,
+            }
+        }
+        
+        Ok(dataset.expect("at least one page is read"))
+    }
+    
+    /// Get the size, format and tags of a dataset without downloading it
+    pub async fn dataset_info(&self, name: &str) -> Result<DatasetDetails, ClientError> {
+        let url = self.url(&["datasets", name, "info"])?;
+        self.json(self.http.get(url)).await
+    }
+    
+    /// Append rows to a dataset
+    pub async fn append_rows(&self, name: &str, request: &AppendRowsRequest) -> Result<AppendedRows, ClientError> {
+        let url = self.url(&["datasets", name, "rows"])?;
+        self.json(self.http.post(url).json(request)).await
+    }
+    
+    /// Compare the schema of a batch with a dataset's
+    pub async fn check_drift(&self, name: &str, request: &DriftRequest) -> Result<SchemaDrift, ClientError> {
+        let url = self.url(&["datasets", name, "drift"])?;
+        self.json(self.http.post(url).json(request)).await
+    }
+    
+    /// Replace the tags of a dataset
+    pub async fn set_tags(&self, name: &str, request: &TagsRequest) -> Result<(), ClientError> {
+        let url = self.url(&["datasets", name, "tags"])?;
+        self.send(self.http.put(url).json(request)).await?;
+        Ok(())
+    }
+    
+    /// Get the history of a dataset's columns
+    pub async fn lineage(&self, name: &str) -> Result<DatasetLineage, ClientError> {
+        let url = self.url(&["datasets", name, "lineage"])?;
+        self.json(self.http.get(url)).await
+    }
+    
+    /// Delete a dataset
+    pub async fn delete_dataset(&self, name: &str) -> Result<(), ClientError> {
+        let url = self.url(&["datasets", name])?;
+        self.send(self.http.delete(url)).await?;
+        Ok(())
+    }
+    
+    /// Transform a dataset
+    pub async fn transform(&self, request: &TransformRequest) -> Result<ProcessResult, ClientError> {
+        self.process("transform", request).await
+    }
+    
+    /// Filter a dataset
+    pub async fn filter(&self, request: &FilterRequest) -> Result<ProcessResult, ClientError> {
+        self.process("filter", request).await
+    }
+    
+    /// Aggregate a dataset
+    pub async fn aggregate(&self, request: &AggregateRequest) -> Result<ProcessResult, ClientError> {
+        self.process("aggregate", request).await
+    }
+    
+    /// Join two datasets
+    pub async fn join(&self, request: &JoinRequest) -> Result<ProcessResult, ClientError> {
+        self.process("join", request).await
+    }
+    
+    /// Run a pipeline specification or template on a dataset
+    pub async fn run_pipeline(&self, request: &PipelineRequest) -> Result<ProcessResult, ClientError> {
+        self.process("pipeline", request).await
+    }
+    
+    /// Check a pipeline on a sample of its source and get its plan, without storing anything
+    pub async fn plan_pipeline(&self, request: &PipelineRequest) -> Result<ProcessPlan, ClientError> {
+        let url = self.url(&["process", "pipeline"])?;
+        self.json(self.http.post(url).query(&[("dry_run", true)]).json(request)).await
+    }
+    
+    /// Compute a statistic on a dataset
+    pub async fn stats(&self, request: &StatsRequest) -> Result<StatsResult, ClientError> {
+        self.process("stats", request).await
+    }
+    
+    /// Post a processing request
+    async fn process<B: Serialize, T: DeserializeOwned>(&self, endpoint: &str, request: &B) -> Result<T, ClientError> {
+        let url = self.url(&["process", endpoint])?;
+        self.json(self.http.post(url).json(request)).await
+    }
+    
+    /// Build the URL of an API path, escaping each segment
+    fn url(&self, segments: &[&str]) -> Result<Url, ClientError> {
+        let mut url = Url::parse(&self.base_url)
+            .map_err(|e| ClientError::InvalidUrl(format!("{}: {}", self.base_url, e)))?;
+        
+        url.path_segments_mut()
+            .map_err(|_| ClientError::InvalidUrl(format!("{} cannot have a path", self.base_url)))?
+            .pop_if_empty()
+            .extend(["api", "v1"].iter().chain(segments));
+        
+        Ok(url)
+    }
+    
+    /// Send a request and decode its JSON response
+    async fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ClientError> {
+        Ok(self.send(request).await?.json().await?)
+    }
+    
+    /// Send a request, turning error statuses into errors
+    async fn send(&self, request: RequestBuilder) -> Result<Response, ClientError> {
+        let request = match &self.api_key {
+            Some(api_key) => request.header(API_KEY_HEADER, api_key),
+            None => request,
+        };
+        
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        
+        let request_id = response.headers().get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        
+        let body = response.text().await.unwrap_or_default();
+        
+        // Error bodies are JSON with a message, but proxies may answer in plain text
+        let message = serde_json::from_str::<serde_json::Value>(&body).ok()
+            .and_then(|json| json.get("message").and_then(|m| m.as_str()).map(str::to_string))
+            .unwrap_or(body);
+        
+        let message = match message.trim() {
+            "" => status.canonical_reason().unwrap_or("Request failed").to_string(),
+            message => message.to_string(),
+        };
+        
+        Err(ClientError::Api { status: status.as_u16(), message, request_id })
+    }
+}
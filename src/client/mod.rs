@@ -0,0 +1,92 @@
+// Client module for calling the REST API
+// Author: Gabriel Demetrios Lafis
+
+//! Typed async client for the engine's REST API
+//!
+//! Requests reuse the `api` request models, so a client built against the
+//! same version of the crate sends exactly what the server expects.
+//! Requires the `client` feature.
+
+mod engine;
+mod models;
+
+pub use engine::*;
+pub use models::*;
+
+use std::error::Error;
+use std::fmt;
+
+use crate::data::DataError;
+
+/// Represents an error in the client module
+#[derive(Debug)]
+pub enum ClientError {
+    /// The request could not be sent or its response could not be read
+    Http(reqwest::Error),
+    /// The server answered with an error status
+    ///
+    /// The request ID is the one the server logged the request under, to
+    /// look the failure up in its logs.
+    Api { status: u16, message: String, request_id: Option<String> },
+    /// A dataset in a response could not be decoded
+    DataError(DataError),
+    /// The server URL is not valid
+    InvalidUrl(String),
+}
+
+impl ClientError {
+    /// Get the HTTP status of an error response, if the server answered
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ClientError::Api { status, .. } => Some(*status),
+            ClientError::Http(err) => err.status().map(|status| status.as_u16()),
+            ClientError::DataError(_) | ClientError::InvalidUrl(_) => None,
+        }
+    }
+    
+    /// Get the ID the server assigned to a failed request, if it answered with one
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            ClientError::Api { request_id, .. } => request_id.as_deref(),
+            _ => None,
+        }
+    }
+    
+    /// Check whether the server reported the resource as missing
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(404)
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientError::Http(err) => write!(f, "HTTP error: {}", err),
+            ClientError::Api { status, message, request_id } => {
+                write!(f, "API error ({}): {}", status, message)?;
+                
+                if let Some(request_id) = request_id {
+                    write!(f, " [request {}]", request_id)?;
+                }
+                
+                Ok(())
+            },
+            ClientError::DataError(err) => write!(f, "Data error: {}", err),
+            ClientError::InvalidUrl(msg) => write!(f, "Invalid URL: {}", msg),
+        }
+    }
+}
+
+impl Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(err: reqwest::Error) -> Self {
+        ClientError::Http(err)
+    }
+}
+
+impl From<DataError> for ClientError {
+    fn from(err: DataError) -> Self {
+        ClientError::DataError(err)
+    }
+}
@@ -0,0 +1,167 @@
+// Client response models
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::api::SchemaField;
+use crate::data::{ColumnDrift, LineageStep};
+use crate::processing::Warning;
+
+/// Server health
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Health {
+    pub status: String,
+    pub version: String,
+}
+
+/// Dataset entry of a listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetSummary {
+    pub name: String,
+    pub rows: usize,
+    pub columns: usize,
+    pub size_bytes: Option<u64>,
+    pub format: String,
+    /// RFC 3339 modification time
+    pub last_modified: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Page of the dataset listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetList {
+    pub datasets: Vec<DatasetSummary>,
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// Dataset details, as returned by the info endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetDetails {
+    pub name: String,
+    pub rows: usize,
+    pub columns: usize,
+    pub size_bytes: Option<u64>,
+    pub format: String,
+    /// RFC 3339 modification time
+    pub last_modified: Option<String>,
+    #[serde(default)]
+    pub immutable: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// History of a dataset's columns, as returned by the lineage endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetLineage {
+    pub name: String,
+    /// Steps of each column with a history, oldest first
+    pub columns: BTreeMap<String, Vec<LineageStep>>,
+}
+
+/// Dataset created on the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedDataset {
+    pub name: String,
+    pub rows: usize,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Rows appended to a dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppendedRows {
+    pub name: String,
+    pub appended: usize,
+    /// Rows in the dataset after appending
+    pub rows: usize,
+    /// Drift of the rows' schema let through
+    #[serde(default)]
+    pub drift: Vec<ColumnDrift>,
+}
+
+/// Drift of a batch's schema from a dataset's, as returned by the drift endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaDrift {
+    pub name: String,
+    /// Whether any drift keeps the batch's rows from being appended as they are
+    pub breaking: bool,
+    pub drift: Vec<ColumnDrift>,
+}
+
+/// Result of a processing request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ProcessResult {
+    /// Result stored in the target dataset
+    Stored {
+        target: String,
+        rows: usize,
+        #[serde(default)]
+        warnings: Vec<Warning>,
+    },
+    /// Result rows, when no target was given
+    Rows {
+        data: Vec<Vec<JsonValue>>,
+        rows: usize,
+        #[serde(default)]
+        warnings: Vec<Warning>,
+    },
+}
+
+impl ProcessResult {
+    /// Get the number of rows in the result
+    pub fn rows(&self) -> usize {
+        match self {
+            ProcessResult::Stored { rows, .. } | ProcessResult::Rows { rows, .. } => *rows,
+        }
+    }
+    
+    /// Get the warnings the processing emitted
+    pub fn warnings(&self) -> &[Warning] {
+        match self {
+            ProcessResult::Stored { warnings, .. } | ProcessResult::Rows { warnings, .. } => warnings,
+        }
+    }
+}
+
+/// Plan of a processing request, as reported by a dry run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessPlan {
+    pub source: String,
+    pub target: Option<String>,
+    /// Whether the target dataset already exists and would be replaced
+    pub overwrites_target: bool,
+    pub input_rows: usize,
+    /// Rows of the source the dry run processed
+    pub sample_rows: usize,
+    pub estimated_rows: usize,
+    /// Whether the sample was the whole source, making the estimates exact
+    pub exact: bool,
+    pub schema: Vec<SchemaField>,
+    pub steps: Vec<PlanStep>,
+    #[serde(default)]
+    pub warnings: Vec<Warning>,
+}
+
+/// Step of a processing plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub name: String,
+    pub schema: Vec<SchemaField>,
+    pub estimated_rows: usize,
+}
+
+/// Result of a statistics request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsResult {
+    pub name: String,
+    pub value: JsonValue,
+    #[serde(default)]
+    pub warnings: Vec<Warning>,
+}
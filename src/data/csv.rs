@@ -2,16 +2,22 @@
 // Author: Gabriel Demetrios Lafis
 
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::iter::Peekable;
 use std::path::Path;
 
-use super::{DataError, DataSet, DataSink, DataSource, Field, Row, Schema, SinkType, SourceType, Value};
+use super::{
+    DataError, DataSet, DataSink, DataSource, Field, Row, Schema, SinkType, SourceType, Value, format_date, format_time,
+    format_timestamp, format_uuid, parse_date, parse_time, parse_timestamp,
+};
 
 /// CSV data source
 pub struct CsvSource {
     path: String,
     has_header: bool,
     delimiter: char,
+    limit: Option<usize>,
+    infer_types: bool,
 }
 
 impl CsvSource {
@@ -21,31 +27,139 @@ impl CsvSource {
             path: path.as_ref().to_string_lossy().to_string(),
             has_header,
             delimiter,
+            limit: None,
+            infer_types: false,
         }
     }
-}
-
-impl DataSource for CsvSource {
-    fn read(&self) -> Result<DataSet, DataError> {
+    
+    /// Stop reading after the given number of rows
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+    
+    /// Infer boolean, integer, float, date, timestamp and time columns instead of
+    /// reading all values as strings
+    pub fn with_type_inference(mut self) -> Self {
+        self.infer_types = true;
+        self
+    }
+    
+    /// Infer the type of a column from its string values
+    ///
+    /// A column takes the narrowest type every non-empty value parses as.
+    fn infer_type(dataset: &DataSet, col_idx: usize) -> super::DataType {
+        let values = || dataset.data.iter().filter_map(|row| match &row.values[col_idx] {
+            Value::String(s) => Some(s.as_str()),
+            _ => None,
+        });
+        
+        if values().next().is_none() {
+            super::DataType::String
+        } else if values().all(|s| s.parse::<i64>().is_ok()) {
+            super::DataType::Integer
+        } else if values().all(|s| s.parse::<f64>().is_ok()) {
+            super::DataType::Float
+        } else if values().all(|s| s == "true" || s == "false") {
+            super::DataType::Boolean
+        } else if values().all(|s| parse_date(s).is_ok()) {
+            super::DataType::Date
+        } else if values().all(|s| parse_timestamp(s).is_ok()) {
+            super::DataType::Timestamp
+        } else if values().all(|s| parse_time(s).is_ok()) {
+            super::DataType::Time
+        } else {
+            super::DataType::String
+        }
+    }
+    
+    /// Convert string values to their inferred column types
+    fn apply_inferred_types(dataset: &mut DataSet) {
+        for col_idx in 0..dataset.schema.fields.len() {
+            let data_type = Self::infer_type(dataset, col_idx);
+            
+            for row in &mut dataset.data {
+                if let Value::String(s) = &row.values[col_idx] {
+                    let value = match data_type {
+                        ref t if t.is_integer() => s.parse().ok().map(Value::Integer),
+                        ref t if t.is_float() => s.parse().ok().map(Value::Float),
+                        super::DataType::Boolean => Some(Value::Boolean(s == "true")),
+                        super::DataType::Date => parse_date(s).ok().map(Value::Date),
+                        super::DataType::Timestamp => parse_timestamp(s).ok().map(Value::Timestamp),
+                        super::DataType::Time => parse_time(s).ok().map(Value::Time),
+                        _ => None,
+                    };
+                    
+                    if let Some(value) = value {
+                        row.values[col_idx] = value;
+                    }
+                }
+            }
+            
+            dataset.schema.fields[col_idx].data_type = data_type;
+        }
+    }
+    
+    /// Count rows and columns without parsing values
+    pub fn count(&self) -> Result<(usize, usize), DataError> {
         let file = File::open(&self.path).map_err(DataError::IoError)?;
         let reader = BufReader::new(file);
         
+        let mut csv_reader = csv::ReaderBuilder::new()
+            .delimiter(self.delimiter as u8)
+            .has_headers(self.has_header)
+            .from_reader(reader);
+        
+        let mut record = csv::ByteRecord::new();
+        let mut rows = 0;
+        let mut columns = 0;
+        
+        while csv_reader.read_byte_record(&mut record)
+            .map_err(|e| DataError::ParseError(e.to_string()))?
+        {
+            columns = columns.max(record.len());
+            rows += 1;
+        }
+        
+        if self.has_header {
+            columns = csv_reader.byte_headers()
+                .map_err(|e| DataError::ParseError(e.to_string()))?
+                .len();
+        }
+        
+        Ok((rows, columns))
+    }
+    
+    /// Open CSV records over a reader, with a schema of string fields
+    ///
+    /// Without a header, columns are named after their position in the first
+    /// record, which is kept as data.
+    fn open_records<R: Read>(&self, reader: R) -> Result<(Schema, Peekable<csv::StringRecordsIntoIter<R>>), DataError> {
         let mut csv_reader = csv::ReaderBuilder::new()
             .delimiter(self.delimiter as u8)
             .has_headers(self.has_header)
             .from_reader(reader);
         
         // Read headers to create schema
-        let headers: Vec<String> = if self.has_header {
-            csv_reader.headers()
+        let header_names: Option<Vec<String>> = if self.has_header {
+            Some(csv_reader.headers()
                 .map_err(|e| DataError::ParseError(e.to_string()))?
                 .iter()
                 .map(|s| s.to_string())
-                .collect()
+                .collect())
         } else {
-            // Generate column names if no header
-            let record = csv_reader.records().next()
+            None
+        };
+        
+        let mut records = csv_reader.into_records().peekable();
+        
+        let headers: Vec<String> = if let Some(headers) = header_names {
+            headers
+        } else {
+            // Generate column names if no header, keeping the first record as data
+            let record = records.peek()
                 .ok_or_else(|| DataError::ParseError("Empty CSV file".to_string()))?
+                .as_ref()
                 .map_err(|e| DataError::ParseError(e.to_string()))?;
             
             (0..record.len())
@@ -58,35 +172,37 @@ impl DataSource for CsvSource {
             .map(|name| Field::new(name.clone(), super::DataType::String, true))
             .collect();
         
-        let schema = Schema::new(fields);
-        let mut dataset = DataSet::new(schema);
+        Ok((Schema::new(fields), records))
+    }
+    
+    /// Convert a CSV record into a row of strings, with empty fields as nulls
+    fn record_row(record: &csv::StringRecord) -> Row {
+        let values: Vec<Value> = record.iter()
+            .map(|field| {
+                if field.is_empty() {
+                    Value::Null
+                } else {
+                    Value::String(field.to_string())
+                }
+            })
+            .collect();
         
-        // Reset reader if we've already read a record
-        if !self.has_header {
-            let file = File::open(&self.path).map_err(DataError::IoError)?;
-            let reader = BufReader::new(file);
-            csv_reader = csv::ReaderBuilder::new()
-                .delimiter(self.delimiter as u8)
-                .has_headers(self.has_header)
-                .from_reader(reader);
-        }
+        Row::new(values)
+    }
+    
+    /// Read a dataset from any reader, such as decrypted file contents
+    pub fn read_from<R: Read>(&self, reader: R) -> Result<DataSet, DataError> {
+        let (schema, records) = self.open_records(reader)?;
+        let mut dataset = DataSet::new(schema);
         
         // Read data
-        for result in csv_reader.records() {
+        for result in records.take(self.limit.unwrap_or(usize::MAX)) {
             let record = result.map_err(|e| DataError::ParseError(e.to_string()))?;
-            
-            let values: Vec<Value> = record.iter()
-                .map(|field| {
-                    if field.is_empty() {
-                        Value::Null
-                    } else {
-                        Value::String(field.to_string())
-                    }
-                })
-                .collect();
-            
-            let row = Row::new(values);
-            dataset.add_row(row)?;
+            dataset.add_row(Self::record_row(&record))?;
+        }
+        
+        if self.infer_types {
+            Self::apply_inferred_types(&mut dataset);
         }
         
         // Add metadata
@@ -96,6 +212,72 @@ impl DataSource for CsvSource {
         Ok(dataset)
     }
     
+    /// Read datasets of up to `batch_size` rows from any reader, parsing each batch on demand
+    ///
+    /// Only one batch is held in memory at a time. Types are not inferred,
+    /// since a batch cannot tell what the rest of a column holds.
+    pub fn read_batches_from<R: Read>(&self, reader: R, batch_size: usize) -> Result<CsvBatches<R>, DataError> {
+        let (schema, records) = self.open_records(reader)?;
+        
+        Ok(CsvBatches {
+            records,
+            schema,
+            path: self.path.clone(),
+            batch_size: batch_size.max(1),
+            remaining: self.limit.unwrap_or(usize::MAX),
+        })
+    }
+}
+
+/// Batches of rows read lazily from CSV
+pub struct CsvBatches<R: Read> {
+    records: Peekable<csv::StringRecordsIntoIter<R>>,
+    schema: Schema,
+    path: String,
+    batch_size: usize,
+    remaining: usize,
+}
+
+impl<R: Read> Iterator for CsvBatches<R> {
+    type Item = Result<DataSet, DataError>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        
+        let mut dataset = DataSet::new(self.schema.clone());
+        
+        for result in self.records.by_ref().take(self.batch_size.min(self.remaining)) {
+            let row = match result {
+                Ok(record) => CsvSource::record_row(&record),
+                Err(e) => return Some(Err(DataError::ParseError(e.to_string()))),
+            };
+            
+            if let Err(e) = dataset.add_row(row) {
+                return Some(Err(e));
+            }
+        }
+        
+        if dataset.is_empty() {
+            return None;
+        }
+        
+        self.remaining -= dataset.len();
+        
+        dataset.metadata.add("source".to_string(), "csv".to_string());
+        dataset.metadata.add("path".to_string(), self.path.clone());
+        
+        Some(Ok(dataset))
+    }
+}
+
+impl DataSource for CsvSource {
+    fn read(&self) -> Result<DataSet, DataError> {
+        let file = File::open(&self.path).map_err(DataError::IoError)?;
+        self.read_from(BufReader::new(file))
+    }
+    
     fn name(&self) -> &str {
         &self.path
     }
@@ -119,13 +301,9 @@ impl CsvSink {
             delimiter,
         }
     }
-}
-
-impl DataSink for CsvSink {
-    fn write(&self, data: &DataSet) -> Result<(), DataError> {
-        let file = File::create(&self.path).map_err(DataError::IoError)?;
-        let writer = BufWriter::new(file);
-        
+    
+    /// Write a dataset to any writer, such as a buffer to be encrypted
+    pub fn write_to<W: Write>(&self, data: &DataSet, writer: W) -> Result<(), DataError> {
         let mut csv_writer = csv::WriterBuilder::new()
             .delimiter(self.delimiter as u8)
             .from_writer(writer);
@@ -146,8 +324,14 @@ impl DataSink for CsvSink {
                     Value::Boolean(b) => b.to_string(),
                     Value::Integer(i) => i.to_string(),
                     Value::Float(f) => f.to_string(),
+                    Value::Decimal(d) => d.to_string(),
                     Value::String(s) => s.clone(),
-                    Value::Binary(_) => "[binary data]".to_string(),
+                    Value::Binary(b) => base64::encode(b),
+                    Value::Point(lat, lon) => format!("{},{}", lat, lon),
+                    Value::Uuid(uuid) => format_uuid(*uuid),
+                    Value::Date(date) => format_date(date),
+                    Value::Timestamp(timestamp) => format_timestamp(timestamp),
+                    Value::Time(time) => format_time(time),
                     Value::Array(_) => "[array]".to_string(),
                     Value::Map(_) => "[map]".to_string(),
                 })
@@ -162,6 +346,13 @@ impl DataSink for CsvSink {
         
         Ok(())
     }
+}
+
+impl DataSink for CsvSink {
+    fn write(&self, data: &DataSet) -> Result<(), DataError> {
+        let file = File::create(&self.path).map_err(DataError::IoError)?;
+        self.write_to(data, BufWriter::new(file))
+    }
     
     fn name(&self) -> &str {
         &self.path
@@ -0,0 +1,267 @@
+// Exact decimal numbers
+// Author: Gabriel Demetrios Lafis
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use super::{DataError, DataType, Value};
+
+/// Most digits a decimal holds
+pub const MAX_DECIMAL_PRECISION: u8 = 38;
+
+/// Exact decimal number, held as an integer of its digits and the number
+/// of those digits after the decimal point
+///
+/// Decimals compare and hash by their numeric value, so `1.50` equals `1.5`.
+#[derive(Debug, Clone, Copy)]
+pub struct Decimal {
+    unscaled: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Create a decimal from its digits and scale, e.g. 1250 and 2 for 12.50
+    pub fn new(unscaled: i128, scale: u32) -> Self {
+        Decimal { unscaled, scale }
+    }
+    
+    /// Parse a decimal such as `-12.50`, keeping the fraction digits given
+    pub fn from_str(s: &str) -> Result<Self, DataError> {
+        let invalid = || DataError::ParseError(format!("Invalid decimal: {}", s));
+        let trimmed = s.trim();
+        
+        let (negative, digits) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        
+        if whole.is_empty() && fraction.is_empty()
+            || !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit())
+            || whole.len() + fraction.len() > MAX_DECIMAL_PRECISION as usize + 1
+        {
+            return Err(invalid());
+        }
+        
+        let unscaled: i128 = format!("{}{}", whole, fraction).parse().map_err(|_| invalid())?;
+        
+        Ok(Decimal {
+            unscaled: if negative { -unscaled } else { unscaled },
+            scale: fraction.len() as u32,
+        })
+    }
+    
+    /// Convert a float to the shortest decimal that reads back as it
+    pub fn from_f64(f: f64) -> Option<Self> {
+        if !f.is_finite() {
+            return None;
+        }
+        
+        Self::from_str(&f.to_string()).ok()
+    }
+    
+    /// Get the digits of the decimal
+    pub fn unscaled(&self) -> i128 {
+        self.unscaled
+    }
+    
+    /// Get the number of digits after the decimal point
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+    
+    /// Get the number of digits, at least one more than the scale for a leading zero
+    pub fn precision(&self) -> u32 {
+        let digits = self.unscaled.unsigned_abs().checked_ilog10().map_or(1, |log| log + 1);
+        digits.max(self.scale + 1)
+    }
+    
+    /// Change the number of fraction digits, rounding half to even when dropping digits
+    ///
+    /// Returns None if the decimal does not fit in the new scale.
+    pub fn rescale(&self, scale: u32) -> Option<Self> {
+        let unscaled = if scale >= self.scale {
+            self.unscaled.checked_mul(10i128.checked_pow(scale - self.scale)?)?
+        } else {
+            divide_rounded(self.unscaled, 10i128.checked_pow(self.scale - scale)?)
+        };
+        
+        Some(Decimal { unscaled, scale })
+    }
+    
+    /// Add two decimals exactly, returning None on overflow
+    pub fn checked_add(&self, other: &Decimal) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let unscaled = self.rescale(scale)?.unscaled.checked_add(other.rescale(scale)?.unscaled)?;
+        Some(Decimal { unscaled, scale })
+    }
+    
+    /// Divide by an integer, rounding half to even at the given scale
+    pub fn checked_div(&self, divisor: i64, scale: u32) -> Option<Self> {
+        if divisor == 0 {
+            return None;
+        }
+        
+        let dividend = self.rescale(scale)?.unscaled;
+        Some(Decimal { unscaled: divide_rounded(dividend, divisor as i128), scale })
+    }
+    
+    /// Get the widest decimal type at the decimal's scale, to hold other values of its column
+    pub fn data_type(&self) -> DataType {
+        DataType::Decimal(MAX_DECIMAL_PRECISION, self.scale.min(MAX_DECIMAL_PRECISION as u32) as u8)
+    }
+    
+    /// Convert to the nearest float
+    pub fn to_f64(&self) -> f64 {
+        // Parsing the text rounds once, where dividing would round twice
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+    
+    /// Get the decimal without trailing zeros after the point
+    fn normalized(&self) -> Self {
+        let mut decimal = *self;
+        
+        while decimal.scale > 0 && decimal.unscaled % 10 == 0 {
+            decimal.unscaled /= 10;
+            decimal.scale -= 1;
+        }
+        
+        decimal
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(i: i64) -> Self {
+        Decimal { unscaled: i as i128, scale: 0 }
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        
+        match (self.rescale(scale), other.rescale(scale)) {
+            (Some(a), Some(b)) => a.unscaled.cmp(&b.unscaled),
+            // A decimal too large to rescale is larger in magnitude than the other
+            (None, _) => if self.unscaled < 0 { Ordering::Less } else { Ordering::Greater },
+            (_, None) => if other.unscaled < 0 { Ordering::Greater } else { Ordering::Less },
+        }
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let normalized = self.normalized();
+        normalized.unscaled.hash(state);
+        normalized.scale.hash(state);
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.unscaled.unsigned_abs().to_string();
+        let sign = if self.unscaled < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, whole, fraction)
+    }
+}
+
+/// Divide, rounding half to even so repeated rounding does not bias sums
+fn divide_rounded(dividend: i128, divisor: i128) -> i128 {
+    let quotient = dividend / divisor;
+    let remainder = dividend % divisor;
+    
+    let twice = remainder.unsigned_abs() * 2;
+    let away = match twice.cmp(&divisor.unsigned_abs()) {
+        Ordering::Greater => true,
+        Ordering::Equal => quotient % 2 != 0,
+        Ordering::Less => false,
+    };
+    
+    if !away {
+        quotient
+    } else if (dividend < 0) != (divisor < 0) {
+        quotient - 1
+    } else {
+        quotient + 1
+    }
+}
+
+/// Convert a decimal to a column's precision and scale
+///
+/// Fraction digits past the scale are rounded half to even; decimals with
+/// more whole digits than the column holds are out of range.
+pub fn fit_decimal(decimal: &Decimal, precision: u8, scale: u8) -> Result<Decimal, DataError> {
+    let out_of_range = || DataError::ValidationError(format!(
+        "Value {} out of range for decimal({},{})", decimal, precision, scale
+    ));
+    
+    let fitted = decimal.rescale(scale as u32).ok_or_else(out_of_range)?;
+    
+    if fitted.precision() > (precision as u32).max(scale as u32 + 1) {
+        return Err(out_of_range());
+    }
+    
+    Ok(fitted)
+}
+
+/// Parse a string or number value into a decimal value of a decimal type
+///
+/// Values of other types, and values that are neither strings nor numbers,
+/// are returned unchanged.
+pub fn parse_decimal_value(value: Value, data_type: &DataType) -> Result<Value, DataError> {
+    let (precision, scale) = match data_type {
+        DataType::Decimal(precision, scale) => (*precision, *scale),
+        _ => return Ok(value),
+    };
+    
+    let decimal = match &value {
+        Value::String(s) => Decimal::from_str(s)?,
+        Value::Integer(i) => Decimal::from(*i),
+        Value::Float(f) => Decimal::from_f64(*f)
+            .ok_or_else(|| DataError::ParseError(format!("Invalid decimal: {}", f)))?,
+        Value::Decimal(decimal) => *decimal,
+        _ => return Ok(value),
+    };
+    
+    fit_decimal(&decimal, precision, scale).map(Value::Decimal)
+}
+
+/// Compare a decimal with another decimal, an integer, a float or a numeric string
+///
+/// Returns None when neither value is a decimal or they are not comparable.
+pub fn compare_decimal(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Decimal(a), Value::Decimal(b)) => Some(a.cmp(b)),
+        (Value::Decimal(a), Value::Integer(b)) => Some(a.cmp(&Decimal::from(*b))),
+        (Value::Decimal(a), Value::Float(b)) => a.to_f64().partial_cmp(b),
+        (Value::Decimal(a), Value::String(b)) => Decimal::from_str(b).ok().map(|b| a.cmp(&b)),
+        (Value::Integer(_) | Value::Float(_) | Value::String(_), Value::Decimal(_)) => {
+            compare_decimal(b, a).map(Ordering::reverse)
+        },
+        _ => None,
+    }
+}
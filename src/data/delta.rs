@@ -0,0 +1,505 @@
+// Delta Lake table implementation
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde_json::{json, Value as JsonValue};
+
+use super::{
+    DataError, DataSet, DataSink, DataSource, DataType, Field, ParquetCompression, ParquetSink,
+    ParquetSource, Row, Schema, SinkType, SourceType, Value, format_uuid, generate_uuid,
+    parse_decimal_value,
+};
+
+/// Directory of a Delta table holding its transaction log
+const LOG_DIR: &str = "_delta_log";
+
+/// Reader and writer protocol versions of the tables this engine writes
+const MIN_READER_VERSION: u64 = 1;
+const MIN_WRITER_VERSION: u64 = 2;
+
+/// Data file of a Delta table version
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeltaFile {
+    /// Path relative to the table directory
+    pub path: String,
+    pub partition_values: HashMap<String, Option<String>>,
+    pub size: u64,
+}
+
+/// State of a Delta table at one version
+#[derive(Debug, Clone)]
+pub struct DeltaSnapshot {
+    pub version: u64,
+    pub schema: Schema,
+    pub partition_columns: Vec<String>,
+    pub files: Vec<DeltaFile>,
+}
+
+/// How a write is committed to a Delta table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeltaWriteMode {
+    /// Add the rows to the table
+    Append,
+    /// Replace every row of the table
+    Overwrite,
+}
+
+/// Delta Lake table
+///
+/// Replays the JSON commits of the `_delta_log` directory to find the data
+/// files of a version, so tables written by Spark or other Delta writers can
+/// be read, including older versions. Partition values are added back as
+/// columns. Writes add an unpartitioned Parquet file and commit it as the
+/// next version; a commit fails instead of overwriting one made concurrently.
+/// Tables whose log has been compacted into checkpoints, or that need reader
+/// features such as deletion vectors, are not supported. Data files are read
+/// and written through `ParquetSource` and `ParquetSink`, so this requires
+/// the `parquet` feature.
+pub struct DeltaTable {
+    name: String,
+    path: PathBuf,
+    version: Option<u64>,
+    mode: DeltaWriteMode,
+}
+
+impl DeltaTable {
+    /// Open the table in a directory; it is created by the first write if it does not exist
+    pub fn open<P: AsRef<Path>>(path: P) -> Self {
+        DeltaTable {
+            name: path.as_ref().to_string_lossy().to_string(),
+            path: path.as_ref().to_path_buf(),
+            version: None,
+            mode: DeltaWriteMode::Append,
+        }
+    }
+    
+    /// Read the table as of a version instead of the latest one
+    pub fn with_version(mut self, version: u64) -> Self {
+        self.version = Some(version);
+        self
+    }
+    
+    /// Set how writes are committed
+    pub fn with_mode(mut self, mode: DeltaWriteMode) -> Self {
+        self.mode = mode;
+        self
+    }
+    
+    /// Get the latest committed version, or `None` if the table has no commits
+    pub fn latest_version(&self) -> Result<Option<u64>, DataError> {
+        Ok(self.commit_versions()?.last().copied())
+    }
+    
+    /// Get the state of the table at a version, or the latest one
+    pub fn snapshot(&self, version: Option<u64>) -> Result<DeltaSnapshot, DataError> {
+        let versions = self.commit_versions()?;
+        
+        let latest = *versions.last().ok_or_else(|| DataError::Other(
+            format!("{} is not a Delta table", self.name)
+        ))?;
+        let version = version.unwrap_or(latest);
+        
+        if version > latest {
+            return Err(DataError::ValidationError(format!(
+                "Version {} does not exist; the latest version of {} is {}", version, self.name, latest
+            )));
+        }
+        
+        // Every commit from the first is needed to replay the log
+        if versions.first() != Some(&0) || versions.len() as u64 != latest + 1 {
+            return Err(DataError::NotSupported(format!(
+                "{} has checkpointed or missing commits, which are not supported", self.name
+            )));
+        }
+        
+        let mut schema = None;
+        let mut partition_columns = Vec::new();
+        let mut files: Vec<DeltaFile> = Vec::new();
+        
+        for v in 0..=version {
+            for action in self.read_commit(v)? {
+                if let Some(protocol) = action.get("protocol") {
+                    let reader_version = protocol["minReaderVersion"].as_u64().unwrap_or(1);
+                    if reader_version > MIN_READER_VERSION {
+                        return Err(DataError::NotSupported(format!(
+                            "{} needs Delta reader version {}", self.name, reader_version
+                        )));
+                    }
+                } else if let Some(metadata) = action.get("metaData") {
+                    let schema_string = metadata["schemaString"].as_str().ok_or_else(|| {
+                        DataError::ParseError(format!("Commit {} has metadata without a schema", v))
+                    })?;
+                    schema = Some(parse_schema(schema_string)?);
+                    partition_columns = string_array(&metadata["partitionColumns"]);
+                } else if let Some(add) = action.get("add") {
+                    let path = add["path"].as_str().unwrap_or_default().to_string();
+                    let partition_values = add["partitionValues"].as_object()
+                        .map(|values| values.iter()
+                            .map(|(k, v)| (k.clone(), v.as_str().map(|s| s.to_string())))
+                            .collect())
+                        .unwrap_or_default();
+                    
+                    files.retain(|file| file.path != path);
+                    files.push(DeltaFile { path, partition_values, size: add["size"].as_u64().unwrap_or(0) });
+                } else if let Some(remove) = action.get("remove") {
+                    let path = remove["path"].as_str().unwrap_or_default();
+                    files.retain(|file| file.path != path);
+                }
+            }
+        }
+        
+        let schema = schema.ok_or_else(|| DataError::ParseError(
+            format!("{} has no metadata in its log", self.name)
+        ))?;
+        
+        Ok(DeltaSnapshot { version, schema, partition_columns, files })
+    }
+    
+    /// Read the rows of a version, or the latest one
+    pub fn read_version(&self, version: Option<u64>) -> Result<DataSet, DataError> {
+        let snapshot = self.snapshot(version)?;
+        let mut combined = DataSet::new(snapshot.schema.clone());
+        
+        for file in &snapshot.files {
+            let data = ParquetSource::new(self.path.join(percent_decode(&file.path))).read()?;
+            
+            // Data files hold every column except the partition columns
+            let positions = snapshot.schema.fields.iter()
+                .map(|field| {
+                    if snapshot.partition_columns.contains(&field.name) {
+                        return Ok(None);
+                    }
+                    
+                    data.schema.fields.iter()
+                        .position(|f| f.name == field.name)
+                        .map(Some)
+                        .ok_or_else(|| DataError::ValidationError(format!(
+                            "{} has no column '{}'", file.path, field.name
+                        )))
+                })
+                .collect::<Result<Vec<_>, DataError>>()?;
+            
+            let partition_values = snapshot.schema.fields.iter()
+                .map(|field| match file.partition_values.get(&field.name) {
+                    Some(Some(value)) => parse_partition_value(value, &field.data_type),
+                    _ => Value::Null,
+                })
+                .collect::<Vec<_>>();
+            
+            for row in data.data {
+                let values = positions.iter().zip(&partition_values)
+                    .map(|(position, partition_value)| match position {
+                        Some(i) => row.values[*i].clone(),
+                        None => partition_value.clone(),
+                    })
+                    .collect();
+                
+                combined.data.push(Row::new(values));
+            }
+        }
+        
+        combined.metadata.add("delta_version".to_string(), snapshot.version.to_string());
+        Ok(combined)
+    }
+    
+    /// Commit rows as the next version, returning it
+    pub fn commit(&self, data: &DataSet, mode: DeltaWriteMode) -> Result<u64, DataError> {
+        let latest = self.latest_version()?;
+        let mut actions = Vec::new();
+        let now = Utc::now().timestamp_millis();
+        
+        match latest {
+            None => {
+                actions.push(json!({ "protocol": {
+                    "minReaderVersion": MIN_READER_VERSION,
+                    "minWriterVersion": MIN_WRITER_VERSION,
+                }}));
+                actions.push(metadata_action(&data.schema, now)?);
+            },
+            Some(version) => {
+                let snapshot = self.snapshot(Some(version))?;
+                
+                if !snapshot.partition_columns.is_empty() {
+                    return Err(DataError::NotSupported(format!(
+                        "Writing to partitioned table {} is not supported", self.name
+                    )));
+                }
+                
+                if mode == DeltaWriteMode::Overwrite {
+                    // Overwrites may change the schema
+                    let changed = !same_columns(&snapshot.schema, &data.schema)
+                        || snapshot.schema.fields.iter().zip(&data.schema.fields).any(|(a, b)| a.data_type != b.data_type);
+                    if changed {
+                        actions.push(metadata_action(&data.schema, now)?);
+                    }
+                    
+                    for file in &snapshot.files {
+                        actions.push(json!({ "remove": {
+                            "path": file.path,
+                            "deletionTimestamp": now,
+                            "dataChange": true,
+                        }}));
+                    }
+                } else if !same_columns(&snapshot.schema, &data.schema) {
+                    return Err(DataError::ValidationError(format!(
+                        "Data does not have the columns of {}", self.name
+                    )));
+                }
+            },
+        }
+        
+        // Check every type has a Delta equivalent before writing anything
+        schema_string(&data.schema)?;
+        
+        let file_name = format!("part-00000-{}-c000.snappy.parquet", format_uuid(generate_uuid()));
+        let file_path = self.path.join(&file_name);
+        
+        fs::create_dir_all(self.path.join(LOG_DIR)).map_err(DataError::IoError)?;
+        ParquetSink::new(&file_path, ParquetCompression::Snappy).write(data)?;
+        
+        let size = fs::metadata(&file_path).map_err(DataError::IoError)?.len();
+        actions.push(json!({ "add": {
+            "path": file_name,
+            "partitionValues": {},
+            "size": size,
+            "modificationTime": now,
+            "dataChange": true,
+        }}));
+        actions.push(json!({ "commitInfo": {
+            "timestamp": now,
+            "operation": "WRITE",
+            "operationParameters": {
+                "mode": if mode == DeltaWriteMode::Overwrite { "Overwrite" } else { "Append" },
+            },
+            "engineInfo": concat!("rust-data-processing-engine/", env!("CARGO_PKG_VERSION")),
+        }}));
+        
+        let version = latest.map_or(0, |v| v + 1);
+        let mut body = String::new();
+        for action in &actions {
+            body.push_str(&action.to_string());
+            body.push('\n');
+        }
+        
+        // Creating the commit file fails if another writer took the version first
+        let commit_path = self.commit_path(version);
+        let mut commit = match fs::OpenOptions::new().write(true).create_new(true).open(&commit_path) {
+            Ok(commit) => commit,
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                let _ = fs::remove_file(&file_path);
+                return Err(DataError::Other(format!(
+                    "Version {} of {} was committed concurrently", version, self.name
+                )));
+            },
+            Err(err) => return Err(DataError::IoError(err)),
+        };
+        
+        commit.write_all(body.as_bytes()).map_err(DataError::IoError)?;
+        Ok(version)
+    }
+    
+    /// Get the versions with a commit file in the log, in order
+    fn commit_versions(&self) -> Result<Vec<u64>, DataError> {
+        let log_dir = self.path.join(LOG_DIR);
+        if !log_dir.exists() {
+            return Ok(Vec::new());
+        }
+        
+        let mut versions: Vec<u64> = fs::read_dir(&log_dir)
+            .map_err(DataError::IoError)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name.strip_suffix(".json")
+                    .filter(|stem| stem.len() == 20)
+                    .and_then(|stem| stem.parse().ok())
+            })
+            .collect();
+        
+        versions.sort_unstable();
+        Ok(versions)
+    }
+    
+    /// Get the path of a version's commit file
+    fn commit_path(&self, version: u64) -> PathBuf {
+        self.path.join(LOG_DIR).join(format!("{:020}.json", version))
+    }
+    
+    /// Read the actions of a commit, one JSON object per line
+    fn read_commit(&self, version: u64) -> Result<Vec<JsonValue>, DataError> {
+        let text = fs::read_to_string(self.commit_path(version)).map_err(DataError::IoError)?;
+        
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(|e| DataError::ParseError(
+                format!("Invalid action in commit {}: {}", version, e)
+            )))
+            .collect()
+    }
+}
+
+impl DataSource for DeltaTable {
+    fn read(&self) -> Result<DataSet, DataError> {
+        self.read_version(self.version)
+    }
+    
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn source_type(&self) -> SourceType {
+        SourceType::Custom("delta".to_string())
+    }
+}
+
+impl DataSink for DeltaTable {
+    fn write(&self, data: &DataSet) -> Result<(), DataError> {
+        self.commit(data, self.mode).map(|_| ())
+    }
+    
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn sink_type(&self) -> SinkType {
+        SinkType::Custom("delta".to_string())
+    }
+}
+
+/// Build the metadata action describing a schema
+fn metadata_action(schema: &Schema, created_time: i64) -> Result<JsonValue, DataError> {
+    Ok(json!({ "metaData": {
+        "id": format_uuid(generate_uuid()),
+        "format": { "provider": "parquet", "options": {} },
+        "schemaString": schema_string(schema)?,
+        "partitionColumns": [],
+        "configuration": {},
+        "createdTime": created_time,
+    }}))
+}
+
+/// Serialize a schema into a Delta schema string
+fn schema_string(schema: &Schema) -> Result<String, DataError> {
+    let fields = schema.fields.iter()
+        .map(|field| {
+            let delta_type = match &field.data_type {
+                DataType::Boolean => "boolean".to_string(),
+                DataType::Int8 => "byte".to_string(),
+                DataType::Int16 => "short".to_string(),
+                DataType::Int32 => "integer".to_string(),
+                DataType::Integer => "long".to_string(),
+                DataType::Float32 => "float".to_string(),
+                DataType::Float => "double".to_string(),
+                // Points are written to Parquet as text
+                DataType::String | DataType::Point => "string".to_string(),
+                DataType::Binary | DataType::Uuid => "binary".to_string(),
+                DataType::Date => "date".to_string(),
+                DataType::Timestamp => "timestamp".to_string(),
+                DataType::Decimal(precision, scale) => format!("decimal({},{})", precision, scale),
+                other => return Err(DataError::NotSupported(format!(
+                    "Column '{}' has type {:?}, which cannot be written to a Delta table", field.name, other
+                ))),
+            };
+            
+            Ok(json!({ "name": field.name, "type": delta_type, "nullable": field.nullable, "metadata": {} }))
+        })
+        .collect::<Result<Vec<_>, DataError>>()?;
+    
+    Ok(json!({ "type": "struct", "fields": fields }).to_string())
+}
+
+/// Parse a Delta schema string
+fn parse_schema(schema_string: &str) -> Result<Schema, DataError> {
+    let schema: JsonValue = serde_json::from_str(schema_string)
+        .map_err(|e| DataError::ParseError(format!("Invalid Delta schema: {}", e)))?;
+    
+    let fields = schema["fields"].as_array()
+        .ok_or_else(|| DataError::ParseError("Delta schema has no fields".to_string()))?
+        .iter()
+        .map(|field| {
+            let name = field["name"].as_str()
+                .ok_or_else(|| DataError::ParseError("Delta schema field has no name".to_string()))?;
+            let nullable = field["nullable"].as_bool().unwrap_or(true);
+            Ok(Field::new(name.to_string(), delta_data_type(&field["type"]), nullable))
+        })
+        .collect::<Result<Vec<_>, DataError>>()?;
+    
+    Ok(Schema::new(fields))
+}
+
+/// Convert a Delta field type into our data type
+fn delta_data_type(delta_type: &JsonValue) -> DataType {
+    match delta_type {
+        JsonValue::String(name) => match name.as_str() {
+            "boolean" => DataType::Boolean,
+            "byte" => DataType::Int8,
+            "short" => DataType::Int16,
+            "integer" => DataType::Int32,
+            "long" => DataType::Integer,
+            "float" => DataType::Float32,
+            "double" => DataType::Float,
+            "binary" => DataType::Binary,
+            name if name.starts_with("decimal(") => DataType::from_str(name).unwrap_or(DataType::String),
+            // Dates and timestamps are read as text
+            _ => DataType::String,
+        },
+        JsonValue::Object(complex) => match complex.get("type").and_then(|t| t.as_str()) {
+            Some("array") => DataType::Array(Box::new(DataType::String)), // Simplified
+            _ => DataType::Map(Box::new(DataType::String)), // Simplified
+        },
+        _ => DataType::String,
+    }
+}
+
+/// Parse a partition value, kept as text in the log
+fn parse_partition_value(value: &str, data_type: &DataType) -> Value {
+    let parsed = match data_type {
+        DataType::Boolean => value.parse().ok().map(Value::Boolean),
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Integer => value.parse().ok().map(Value::Integer),
+        DataType::Float32 | DataType::Float => value.parse().ok().map(Value::Float),
+        DataType::Decimal(_, _) => parse_decimal_value(Value::String(value.to_string()), data_type).ok(),
+        _ => None,
+    };
+    
+    parsed.unwrap_or_else(|| Value::String(value.to_string()))
+}
+
+/// Check two schemas have the same column names in the same order
+fn same_columns(a: &Schema, b: &Schema) -> bool {
+    a.fields.len() == b.fields.len() && a.fields.iter().zip(&b.fields).all(|(a, b)| a.name == b.name)
+}
+
+/// Get the strings of a JSON array
+fn string_array(value: &JsonValue) -> Vec<String> {
+    value.as_array()
+        .map(|values| values.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+        .unwrap_or_default()
+}
+
+/// Decode `%XX` escapes of a data file path
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    
+    String::from_utf8_lossy(&decoded).to_string()
+}
@@ -0,0 +1,131 @@
+// Dictionary encoding for categorical string columns
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::HashMap;
+
+use super::{DataError, DataSet, Value};
+
+/// Distinct strings, each identified by the position it was first seen at
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    values: Vec<String>,
+    ids: HashMap<String, u32>,
+}
+
+impl Dictionary {
+    /// Create a new empty dictionary
+    pub fn new() -> Self {
+        Dictionary::default()
+    }
+    
+    /// Get the id of a string, adding it if it is new
+    pub fn intern(&mut self, value: &str) -> u32 {
+        if let Some(&id) = self.ids.get(value) {
+            return id;
+        }
+        
+        let id = self.values.len() as u32;
+        self.values.push(value.to_string());
+        self.ids.insert(value.to_string(), id);
+        id
+    }
+    
+    /// Get the id of a string, if it is in the dictionary
+    pub fn id(&self, value: &str) -> Option<u32> {
+        self.ids.get(value).copied()
+    }
+    
+    /// Get the string with an id
+    pub fn value(&self, id: u32) -> Option<&str> {
+        self.values.get(id as usize).map(|s| s.as_str())
+    }
+    
+    /// Get the strings in id order
+    pub fn values(&self) -> &[String] {
+        &self.values
+    }
+    
+    /// Get the number of distinct strings
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+    
+    /// Check if the dictionary is empty
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+    
+    /// Encode a column of strings and nulls into ids, adding new strings
+    ///
+    /// Nulls have no id. Returns `None` if the column holds other values.
+    pub fn encode(&mut self, data: &DataSet, column: usize) -> Option<Vec<Option<u32>>> {
+        data.data.iter()
+            .map(|row| match &row.values[column] {
+                Value::String(s) => Some(Some(self.intern(s))),
+                Value::Null => Some(None),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// String column stored as ids into a dictionary of its distinct values
+///
+/// Low-cardinality columns take one id per cell instead of one string, and
+/// compare and hash ids instead of strings.
+#[derive(Debug, Clone)]
+pub struct DictionaryColumn {
+    dictionary: Dictionary,
+    ids: Vec<Option<u32>>,
+}
+
+impl DictionaryColumn {
+    /// Encode a column of a dataset holding only strings and nulls
+    pub fn encode(data: &DataSet, column: &str) -> Result<Self, DataError> {
+        let index = data.schema.fields.iter()
+            .position(|field| field.name == column)
+            .ok_or_else(|| DataError::ValidationError(format!("Column '{}' not found", column)))?;
+        
+        let mut dictionary = Dictionary::new();
+        let ids = dictionary.encode(data, index).ok_or_else(|| DataError::ValidationError(
+            format!("Column '{}' holds values other than strings", column)
+        ))?;
+        
+        Ok(DictionaryColumn { dictionary, ids })
+    }
+    
+    /// Get the dictionary of distinct values
+    pub fn dictionary(&self) -> &Dictionary {
+        &self.dictionary
+    }
+    
+    /// Get the id of every row, with no id for nulls
+    pub fn ids(&self) -> &[Option<u32>] {
+        &self.ids
+    }
+    
+    /// Get the string of a row, or `None` for nulls
+    pub fn get(&self, row: usize) -> Option<&str> {
+        self.ids.get(row).copied().flatten().and_then(|id| self.dictionary.value(id))
+    }
+    
+    /// Get the number of rows
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+    
+    /// Check if the column has no rows
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+    
+    /// Decode the column back into values
+    pub fn decode(&self) -> Vec<Value> {
+        self.ids.iter()
+            .map(|id| match id.and_then(|id| self.dictionary.value(id)) {
+                Some(s) => Value::String(s.to_string()),
+                None => Value::Null,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,283 @@
+// Elasticsearch data sink implementation
+// Author: Gabriel Demetrios Lafis
+
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::storage::RetryPolicy;
+use super::{DataError, DataSet, DataSink, DataType, Schema, SinkType, Value, format_date, format_time, format_timestamp, format_uuid};
+
+/// Rows sent per `_bulk` request by default
+const DEFAULT_BATCH_SIZE: usize = 1000;
+
+/// Elasticsearch or OpenSearch data sink
+///
+/// Creates the index with a mapping derived from the schema if it does not
+/// exist, then indexes rows through batched `_bulk` requests. Batches that
+/// fail with a connection error, 429 or 5xx status are retried with backoff,
+/// as are rows the cluster rejected with 429. Rows are indexed under the
+/// value of the id column if one is set, so re-running a pipeline replaces
+/// its documents instead of duplicating them. Requires the `elasticsearch`
+/// feature.
+pub struct ElasticsearchSink {
+    url: String,
+    index: String,
+    id_column: Option<String>,
+    batch_size: usize,
+    credentials: Option<(String, String)>,
+    retry: RetryPolicy,
+}
+
+impl ElasticsearchSink {
+    /// Create a new sink indexing into an index of the cluster at a URL
+    pub fn new(url: &str, index: &str) -> Self {
+        ElasticsearchSink {
+            url: url.trim_end_matches('/').to_string(),
+            index: index.to_string(),
+            id_column: None,
+            batch_size: DEFAULT_BATCH_SIZE,
+            credentials: None,
+            retry: RetryPolicy::default(),
+        }
+    }
+    
+    /// Use the values of a column as document ids
+    pub fn with_id_column(mut self, column: &str) -> Self {
+        self.id_column = Some(column.to_string());
+        self
+    }
+    
+    /// Set the number of rows sent per `_bulk` request
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+    
+    /// Authenticate with HTTP basic auth
+    pub fn with_credentials(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+    
+    /// Set how failed requests are retried
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+    
+    /// Get the index mapping of a schema
+    pub fn mapping(schema: &Schema) -> JsonValue {
+        let properties: Map<String, JsonValue> = schema.fields.iter()
+            .map(|field| (field.name.clone(), field_mapping(&field.data_type)))
+            .collect();
+        
+        json!({ "mappings": { "properties": properties } })
+    }
+    
+    /// Build the NDJSON body of a `_bulk` request for some rows
+    fn bulk_body(&self, data: &DataSet, rows: &[usize], id_index: Option<usize>) -> Result<String, DataError> {
+        let mut body = String::new();
+        
+        for &i in rows {
+            let row = &data.data[i];
+            let mut action = json!({ "_index": self.index });
+            
+            if let Some(id_index) = id_index {
+                let id = match &row.values[id_index] {
+                    Value::Null => return Err(DataError::ValidationError(format!(
+                        "Row {} has no value in the id column", i
+                    ))),
+                    Value::String(s) => s.clone(),
+                    Value::Uuid(uuid) => format_uuid(*uuid),
+                    other => document_value(other).to_string(),
+                };
+                action["_id"] = JsonValue::String(id);
+            }
+            
+            let document: Map<String, JsonValue> = data.schema.fields.iter().zip(&row.values)
+                .map(|(field, value)| (field.name.clone(), document_value(value)))
+                .collect();
+            
+            body.push_str(&json!({ "index": action }).to_string());
+            body.push('\n');
+            body.push_str(&JsonValue::Object(document).to_string());
+            body.push('\n');
+        }
+        
+        Ok(body)
+    }
+    
+    /// Send a request, returning the status and parsed body
+    #[cfg(feature = "elasticsearch")]
+    fn send(&self, method: &str, path: &str, content_type: &str, body: &str) -> Result<(u16, JsonValue), DataError> {
+        let mut request = ureq::request(method, &format!("{}/{}", self.url, path))
+            .set("Content-Type", content_type);
+        
+        if let Some((username, password)) = &self.credentials {
+            let token = base64::encode(format!("{}:{}", username, password));
+            request = request.set("Authorization", &format!("Basic {}", token));
+        }
+        
+        let response = match request.send_string(body) {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(err) => return Err(DataError::IoError(std::io::Error::new(std::io::ErrorKind::Other, err.to_string()))),
+        };
+        
+        let status = response.status();
+        let body = response.into_json().unwrap_or(JsonValue::Null);
+        Ok((status, body))
+    }
+    
+    /// Create the index with the schema's mapping unless it already exists
+    #[cfg(feature = "elasticsearch")]
+    fn ensure_index(&self, schema: &Schema) -> Result<(), DataError> {
+        let (status, body) = self.send("PUT", &self.index, "application/json", &Self::mapping(schema).to_string())?;
+        
+        let exists = body["error"]["type"] == "resource_already_exists_exception";
+        if status < 300 || exists {
+            return Ok(());
+        }
+        
+        Err(DataError::Other(format!("Cannot create index '{}': {}", self.index, body["error"])))
+    }
+    
+    /// Index rows in one batch, retrying what the cluster could not take yet
+    #[cfg(feature = "elasticsearch")]
+    fn index_batch(&self, data: &DataSet, rows: &[usize], id_index: Option<usize>) -> Result<(), DataError> {
+        let mut pending = rows.to_vec();
+        let mut attempt = 0;
+        
+        loop {
+            let body = self.bulk_body(data, &pending, id_index)?;
+            let retryable = match self.send("POST", "_bulk", "application/x-ndjson", &body) {
+                Ok((status, response)) if status < 300 => {
+                    if response["errors"] != JsonValue::Bool(true) {
+                        return Ok(());
+                    }
+                    
+                    // Keep only rows rejected for load; any other rejection fails the write
+                    let items = response["items"].as_array().cloned().unwrap_or_default();
+                    let mut rejected = Vec::new();
+                    
+                    for (item, &row) in items.iter().zip(&pending) {
+                        let result = &item["index"];
+                        match result["status"].as_u64() {
+                            Some(status) if status < 300 => {},
+                            Some(429) => rejected.push(row),
+                            _ => return Err(DataError::Other(format!(
+                                "Row {} was rejected by index '{}': {}", row, self.index, result["error"]
+                            ))),
+                        }
+                    }
+                    
+                    pending = rejected;
+                    format!("{} rows rejected with 429", pending.len())
+                },
+                Ok((status, response)) if status == 429 || status >= 500 => {
+                    format!("status {}: {}", status, response["error"])
+                },
+                Ok((status, response)) => return Err(DataError::Other(format!(
+                    "Bulk request to index '{}' failed with status {}: {}", self.index, status, response["error"]
+                ))),
+                Err(err) => err.to_string(),
+            };
+            
+            if attempt >= self.retry.max_retries {
+                return Err(DataError::Other(format!(
+                    "Bulk request to index '{}' failed after {} attempts: {}", self.index, attempt + 1, retryable
+                )));
+            }
+            
+            std::thread::sleep(self.retry.backoff(attempt));
+            attempt += 1;
+        }
+    }
+}
+
+impl DataSink for ElasticsearchSink {
+    fn write(&self, data: &DataSet) -> Result<(), DataError> {
+        let id_index = match &self.id_column {
+            Some(column) => Some(data.schema.fields.iter()
+                .position(|field| &field.name == column)
+                .ok_or_else(|| DataError::ValidationError(format!("Id column '{}' not found", column)))?),
+            None => None,
+        };
+        
+        #[cfg(feature = "elasticsearch")]
+        {
+            self.ensure_index(&data.schema)?;
+            
+            let rows: Vec<usize> = (0..data.len()).collect();
+            for batch in rows.chunks(self.batch_size) {
+                self.index_batch(data, batch, id_index)?;
+            }
+            
+            Ok(())
+        }
+        
+        #[cfg(not(feature = "elasticsearch"))]
+        {
+            let _ = id_index;
+            Err(DataError::NotSupported("Elasticsearch support not enabled".to_string()))
+        }
+    }
+    
+    fn name(&self) -> &str {
+        &self.index
+    }
+    
+    fn sink_type(&self) -> SinkType {
+        SinkType::Custom("elasticsearch".to_string())
+    }
+}
+
+/// Get the field mapping of a data type
+fn field_mapping(data_type: &DataType) -> JsonValue {
+    match data_type {
+        DataType::Boolean => json!({ "type": "boolean" }),
+        DataType::Int8 => json!({ "type": "byte" }),
+        DataType::Int16 => json!({ "type": "short" }),
+        DataType::Int32 => json!({ "type": "integer" }),
+        DataType::Integer => json!({ "type": "long" }),
+        DataType::Float32 => json!({ "type": "float" }),
+        DataType::Float => json!({ "type": "double" }),
+        // Stored as a long of the digits, so values keep their fraction digits exactly
+        DataType::Decimal(_, scale) => json!({ "type": "scaled_float", "scaling_factor": 10f64.powi(*scale as i32) }),
+        // Full-text search plus exact matches and aggregations on the keyword
+        DataType::String => json!({
+            "type": "text",
+            "fields": { "keyword": { "type": "keyword", "ignore_above": 256 } },
+        }),
+        DataType::Binary => json!({ "type": "binary" }),
+        DataType::Point => json!({ "type": "geo_point" }),
+        DataType::Uuid => json!({ "type": "keyword" }),
+        DataType::Date => json!({ "type": "date", "format": "strict_date" }),
+        DataType::Timestamp => json!({ "type": "date", "format": "strict_date_optional_time_nanos" }),
+        // Elasticsearch has no time of day type
+        DataType::Time => json!({ "type": "keyword" }),
+        // Any field holds arrays of its type
+        DataType::Array(inner) => field_mapping(inner),
+        DataType::Map(_) => json!({ "type": "object" }),
+    }
+}
+
+/// Convert a value to its representation in a document
+fn document_value(value: &Value) -> JsonValue {
+    match value {
+        Value::Null => JsonValue::Null,
+        Value::Boolean(b) => JsonValue::Bool(*b),
+        Value::Integer(i) => JsonValue::from(*i),
+        Value::Float(f) => serde_json::Number::from_f64(*f).map_or(JsonValue::Null, JsonValue::Number),
+        // Elasticsearch parses numeric strings, which keeps every digit on the way
+        Value::Decimal(d) => JsonValue::String(d.to_string()),
+        Value::String(s) => JsonValue::String(s.clone()),
+        Value::Binary(b) => JsonValue::String(base64::encode(b)),
+        Value::Point(lat, lon) => json!({ "lat": lat, "lon": lon }),
+        Value::Uuid(uuid) => JsonValue::String(format_uuid(*uuid)),
+        Value::Date(date) => JsonValue::String(format_date(date)),
+        Value::Timestamp(timestamp) => JsonValue::String(format_timestamp(timestamp)),
+        Value::Time(time) => JsonValue::String(format_time(time)),
+        Value::Array(values) => JsonValue::Array(values.iter().map(document_value).collect()),
+        Value::Map(map) => JsonValue::Object(map.iter().map(|(k, v)| (k.clone(), document_value(v))).collect()),
+    }
+}
@@ -0,0 +1,261 @@
+// Fixed-width text data source implementation
+// Author: Gabriel Demetrios Lafis
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::{DataError, DataSet, DataSource, DataType, Field, Row, Schema, SourceType, Value, parse_date, parse_decimal_value, parse_time, parse_timestamp, parse_uuid};
+
+/// Column of a fixed-width record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixedWidthColumn {
+    pub name: String,
+    /// Offset of the first character in the record, starting at 0
+    pub start: usize,
+    /// Number of characters
+    pub length: usize,
+    /// Data type name, as accepted by `DataType::from_str`
+    #[serde(rename = "type", default = "default_type")]
+    pub data_type: String,
+    /// Strip surrounding spaces before casting
+    #[serde(default = "default_trim")]
+    pub trim: bool,
+}
+
+fn default_type() -> String {
+    "string".to_string()
+}
+
+fn default_trim() -> bool {
+    true
+}
+
+impl FixedWidthColumn {
+    /// Create a new trimmed column
+    pub fn new(name: &str, start: usize, length: usize, data_type: DataType) -> Self {
+        FixedWidthColumn {
+            name: name.to_string(),
+            start,
+            length,
+            data_type: data_type.name(),
+            trim: true,
+        }
+    }
+    
+    /// Keep surrounding spaces, which only applies to string columns
+    pub fn untrimmed(mut self) -> Self {
+        self.trim = false;
+        self
+    }
+}
+
+/// Layout of a fixed-width file, as kept in a JSON spec file
+///
+/// ```json
+/// {
+///   "skip_lines": 1,
+///   "columns": [
+///     { "name": "account", "start": 0, "length": 10 },
+///     { "name": "balance", "start": 10, "length": 12, "type": "float" }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FixedWidthSpec {
+    /// Header lines to skip before the first record
+    #[serde(default)]
+    pub skip_lines: usize,
+    pub columns: Vec<FixedWidthColumn>,
+}
+
+impl FixedWidthSpec {
+    /// Read a layout from a JSON spec file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, DataError> {
+        let text = fs::read_to_string(path.as_ref()).map_err(DataError::IoError)?;
+        
+        serde_json::from_str(&text).map_err(|e| DataError::ParseError(
+            format!("Invalid fixed-width spec {}: {}", path.as_ref().display(), e)
+        ))
+    }
+}
+
+/// Fixed-width text data source
+///
+/// Each line is a record cut into columns by character position. Values
+/// are trimmed unless the column says otherwise, empty values are null, and
+/// the rest are cast to the column type; a value that does not parse fails
+/// the read with its line number. Lines shorter than the layout leave the
+/// missing columns null, and blank lines are skipped.
+pub struct FixedWidthSource {
+    path: String,
+    spec: FixedWidthSpec,
+    limit: Option<usize>,
+}
+
+impl FixedWidthSource {
+    /// Create a new fixed-width data source with no columns
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        FixedWidthSource {
+            path: path.as_ref().to_string_lossy().to_string(),
+            spec: FixedWidthSpec::default(),
+            limit: None,
+        }
+    }
+    
+    /// Create a new fixed-width data source with the layout of a spec file
+    pub fn from_spec_file<P: AsRef<Path>, S: AsRef<Path>>(path: P, spec_path: S) -> Result<Self, DataError> {
+        Ok(Self::new(path).with_spec(FixedWidthSpec::from_file(spec_path)?))
+    }
+    
+    /// Add a column
+    pub fn with_column(mut self, column: FixedWidthColumn) -> Self {
+        self.spec.columns.push(column);
+        self
+    }
+    
+    /// Replace the layout
+    pub fn with_spec(mut self, spec: FixedWidthSpec) -> Self {
+        self.spec = spec;
+        self
+    }
+    
+    /// Skip header lines before the first record
+    pub fn with_skip_lines(mut self, lines: usize) -> Self {
+        self.spec.skip_lines = lines;
+        self
+    }
+    
+    /// Stop reading after the given number of rows
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+    
+    /// Get the schema of the layout, checking column types
+    pub fn schema(&self) -> Result<Schema, DataError> {
+        if self.spec.columns.is_empty() {
+            return Err(DataError::ValidationError("Fixed-width source has no columns".to_string()));
+        }
+        
+        let fields = self.spec.columns.iter()
+            .map(|column| {
+                let data_type = DataType::from_str(&column.data_type)?;
+                
+                if matches!(data_type, DataType::Binary | DataType::Array(_) | DataType::Map(_)) {
+                    return Err(DataError::NotSupported(format!(
+                        "Column '{}' has type {}, which cannot be read from fixed-width text", column.name, column.data_type
+                    )));
+                }
+                
+                if column.length == 0 {
+                    return Err(DataError::ValidationError(format!("Column '{}' has no length", column.name)));
+                }
+                
+                Ok(Field::new(column.name.clone(), data_type, true))
+            })
+            .collect::<Result<Vec<_>, DataError>>()?;
+        
+        Ok(Schema::new(fields))
+    }
+    
+    /// Read a dataset from any reader
+    pub fn read_from<R: Read>(&self, reader: R) -> Result<DataSet, DataError> {
+        let schema = self.schema()?;
+        let mut dataset = DataSet::new(schema.clone());
+        
+        let lines = BufReader::new(reader).lines().enumerate().skip(self.spec.skip_lines);
+        
+        for (line_idx, line) in lines {
+            if self.limit.map_or(false, |limit| dataset.len() >= limit) {
+                break;
+            }
+            
+            let line = line.map_err(DataError::IoError)?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            
+            let values = self.spec.columns.iter().zip(&schema.fields)
+                .map(|(column, field)| {
+                    let raw = slice_chars(line, column.start, column.length);
+                    let text = if column.trim || field.data_type != DataType::String { raw.trim() } else { raw };
+                    
+                    parse_value(text, &field.data_type).ok_or_else(|| DataError::ParseError(format!(
+                        "Line {}: cannot read '{}' as {} for column '{}'", line_idx + 1, text, column.data_type, column.name
+                    )))
+                })
+                .collect::<Result<Vec<_>, DataError>>()?;
+            
+            dataset.add_row(Row::new(values))?;
+        }
+        
+        // Add metadata
+        dataset.metadata.add("source".to_string(), "fixed_width".to_string());
+        dataset.metadata.add("path".to_string(), self.path.clone());
+        
+        Ok(dataset)
+    }
+}
+
+impl DataSource for FixedWidthSource {
+    fn read(&self) -> Result<DataSet, DataError> {
+        let file = File::open(&self.path).map_err(DataError::IoError)?;
+        self.read_from(file)
+    }
+    
+    fn name(&self) -> &str {
+        &self.path
+    }
+    
+    fn source_type(&self) -> SourceType {
+        SourceType::File
+    }
+}
+
+/// Get the characters of a line at a position, or fewer past its end
+fn slice_chars(line: &str, start: usize, length: usize) -> &str {
+    let mut indices = line.char_indices().map(|(i, _)| i).chain(std::iter::once(line.len()));
+    
+    let from = match indices.nth(start) {
+        Some(from) => from,
+        None => return "",
+    };
+    let to = indices.nth(length - 1).unwrap_or(line.len());
+    
+    &line[from..to]
+}
+
+/// Cast a value of a fixed-width column, with empty values as null
+fn parse_value(text: &str, data_type: &DataType) -> Option<Value> {
+    if text.is_empty() {
+        return Some(Value::Null);
+    }
+    
+    match data_type {
+        DataType::Boolean => match text.to_lowercase().as_str() {
+            "true" | "t" | "yes" | "y" | "1" => Some(Value::Boolean(true)),
+            "false" | "f" | "no" | "n" | "0" => Some(Value::Boolean(false)),
+            _ => None,
+        },
+        t if t.is_integer() => {
+            let n: i64 = text.parse().ok()?;
+            let (min, max) = t.integer_range().unwrap_or((i64::MIN, i64::MAX));
+            (min..=max).contains(&n).then(|| Value::Integer(n))
+        },
+        t if t.is_float() => text.parse().ok().map(Value::Float),
+        DataType::Uuid => parse_uuid(text).ok().map(Value::Uuid),
+        DataType::Date => parse_date(text).ok().map(Value::Date),
+        DataType::Timestamp => parse_timestamp(text).ok().map(Value::Timestamp),
+        DataType::Time => parse_time(text).ok().map(Value::Time),
+        DataType::Decimal(_, _) => parse_decimal_value(Value::String(text.to_string()), data_type).ok(),
+        DataType::Point => {
+            let (lat, lon) = text.split_once(',')?;
+            Some(Value::Point(lat.trim().parse().ok()?, lon.trim().parse().ok()?))
+        },
+        _ => Some(Value::String(text.to_string())),
+    }
+}
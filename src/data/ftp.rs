@@ -0,0 +1,282 @@
+// FTP data source implementation
+// Author: Gabriel Demetrios Lafis
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use super::{combine_files, glob_regex, parse_remote_file, split_pattern, DataError, DataSet, DataSource, RemoteFile, SourceType};
+
+/// Default FTP control port
+const DEFAULT_PORT: u16 = 21;
+
+/// FTP data source
+///
+/// Reads files like `SftpSource`, for partners that still deliver over
+/// plain FTP. Files are listed and downloaded over one login, in passive
+/// binary mode. FTP has no encryption, so the password and the files cross
+/// the network in clear text; prefer SFTP wherever the server offers it.
+pub struct FtpSource {
+    name: String,
+    host: String,
+    port: u16,
+    username: String,
+    password: String,
+    dir: String,
+    pattern: String,
+    modified_since: Option<DateTime<Utc>>,
+}
+
+impl FtpSource {
+    /// Create a new FTP source reading every file of the login directory
+    pub fn new(host: &str, username: &str, password: &str) -> Self {
+        FtpSource {
+            name: format!("ftp://{}@{}", username, host),
+            host: host.to_string(),
+            port: DEFAULT_PORT,
+            username: username.to_string(),
+            password: password.to_string(),
+            dir: ".".to_string(),
+            pattern: "*".to_string(),
+            modified_since: None,
+        }
+    }
+    
+    /// Connect to another port than 21
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+    
+    /// Read the files matching a glob pattern, where `*` and `?` match within a file name
+    pub fn with_pattern(mut self, pattern: &str) -> Self {
+        let (dir, pattern) = split_pattern(pattern);
+        self.dir = dir;
+        self.pattern = pattern;
+        self
+    }
+    
+    /// Read only files modified after the given time
+    pub fn with_modified_since(mut self, since: DateTime<Utc>) -> Self {
+        self.modified_since = Some(since);
+        self
+    }
+    
+    /// List the remote files to read, in name order
+    pub fn list(&self) -> Result<Vec<RemoteFile>, DataError> {
+        let mut ftp = self.connect()?;
+        let files = self.list_files(&mut ftp)?;
+        ftp.quit();
+        
+        Ok(files)
+    }
+    
+    /// Download and parse one remote file
+    pub fn read_file(&self, file: &RemoteFile) -> Result<DataSet, DataError> {
+        let mut ftp = self.connect()?;
+        let data = self.download(&mut ftp, file)?;
+        ftp.quit();
+        
+        Ok(data)
+    }
+    
+    /// List the files matching the pattern over an open login
+    ///
+    /// Names without a size, such as directories, are skipped.
+    fn list_files(&self, ftp: &mut FtpConnection) -> Result<Vec<RemoteFile>, DataError> {
+        let pattern = glob_regex(&self.pattern)?;
+        let listing = String::from_utf8_lossy(&ftp.transfer(&format!("NLST {}", self.dir))?).to_string();
+        
+        let mut files = Vec::new();
+        
+        for entry in listing.lines().map(str::trim).filter(|entry| !entry.is_empty()) {
+            let name = entry.rsplit('/').next().unwrap_or(entry);
+            if !pattern.is_match(name) {
+                continue;
+            }
+            
+            let path = match self.dir.as_str() {
+                "." => name.to_string(),
+                dir => format!("{}/{}", dir.trim_end_matches('/'), name),
+            };
+            
+            let size = match ftp.command(&format!("SIZE {}", path))? {
+                (213, text) => text.trim().parse::<u64>().ok(),
+                _ => continue,
+            };
+            
+            let modified = match ftp.command(&format!("MDTM {}", path))? {
+                (213, text) => parse_mdtm(text.trim()),
+                _ => None,
+            };
+            
+            files.push(RemoteFile { path, size, modified });
+        }
+        
+        files.retain(|file| match (self.modified_since, file.modified) {
+            (Some(since), Some(modified)) => modified > since,
+            _ => true,
+        });
+        
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+    
+    /// Download and parse one file over an open login
+    fn download(&self, ftp: &mut FtpConnection, file: &RemoteFile) -> Result<DataSet, DataError> {
+        let bytes = ftp.transfer(&format!("RETR {}", file.path))?;
+        let mut data = parse_remote_file(file, bytes.as_slice(), "FTP")?;
+        
+        data.metadata.add("remote".to_string(), format!("{}/{}", self.name, file.path.trim_start_matches('/')));
+        Ok(data)
+    }
+    
+    /// Log in and switch to binary transfers
+    fn connect(&self) -> Result<FtpConnection, DataError> {
+        let stream = TcpStream::connect((self.host.as_str(), self.port)).map_err(DataError::IoError)?;
+        let mut ftp = FtpConnection {
+            reader: BufReader::new(stream.try_clone().map_err(DataError::IoError)?),
+            stream,
+        };
+        
+        expect_reply(ftp.reply()?, &[220])?;
+        
+        match ftp.command(&format!("USER {}", self.username))? {
+            (230, _) => {},
+            (331, _) => {
+                let reply = ftp.command(&format!("PASS {}", self.password))?;
+                expect_reply(reply, &[230, 202])?;
+            },
+            reply => expect_reply(reply, &[230, 331])?,
+        }
+        
+        let reply = ftp.command("TYPE I")?;
+        expect_reply(reply, &[200])?;
+        
+        Ok(ftp)
+    }
+}
+
+impl DataSource for FtpSource {
+    fn read(&self) -> Result<DataSet, DataError> {
+        let mut ftp = self.connect()?;
+        let mut combined = None;
+        
+        for file in self.list_files(&mut ftp)? {
+            let data = self.download(&mut ftp, &file)?;
+            combined = Some(combine_files(combined, data, &file)?);
+        }
+        
+        ftp.quit();
+        
+        combined.ok_or_else(|| DataError::Other(format!(
+            "No files match {}/{} on {}", self.dir, self.pattern, self.name
+        )))
+    }
+    
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn source_type(&self) -> SourceType {
+        SourceType::Custom("ftp".to_string())
+    }
+}
+
+/// Control connection of a logged in FTP client
+struct FtpConnection {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl FtpConnection {
+    /// Send a command and read its reply
+    fn command(&mut self, command: &str) -> Result<(u32, String), DataError> {
+        self.stream.write_all(format!("{}\r\n", command).as_bytes()).map_err(DataError::IoError)?;
+        self.reply()
+    }
+    
+    /// Read a reply, joining the lines of multi-line replies
+    fn reply(&mut self) -> Result<(u32, String), DataError> {
+        let mut text = String::new();
+        let mut line = String::new();
+        
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line).map_err(DataError::IoError)? == 0 {
+                return Err(DataError::Other("FTP server closed the connection".to_string()));
+            }
+            
+            text.push_str(line.get(4..).unwrap_or("").trim_end());
+            
+            let code = line.get(..3).and_then(|code| code.parse::<u32>().ok());
+            match (code, line.as_bytes().get(3)) {
+                // The last line of a reply has a space after its code
+                (Some(code), Some(b' ')) => return Ok((code, text)),
+                _ => text.push('\n'),
+            }
+        }
+    }
+    
+    /// Run a command whose output comes over a passive data connection
+    fn transfer(&mut self, command: &str) -> Result<Vec<u8>, DataError> {
+        let reply = self.command("PASV")?;
+        let port = match &reply {
+            (227, text) => parse_pasv_port(text),
+            _ => None,
+        };
+        let port = port.ok_or_else(|| DataError::Other(format!("FTP server replied {} {}", reply.0, reply.1)))?;
+        
+        // Connect to the control host rather than the address in the reply,
+        // which may be internal or point somewhere else entirely
+        let host = self.stream.peer_addr().map_err(DataError::IoError)?.ip();
+        let mut data = TcpStream::connect(SocketAddr::new(host, port)).map_err(DataError::IoError)?;
+        
+        let reply = self.command(command)?;
+        expect_reply(reply, &[125, 150])?;
+        
+        let mut bytes = Vec::new();
+        data.read_to_end(&mut bytes).map_err(DataError::IoError)?;
+        drop(data);
+        
+        let reply = self.reply()?;
+        expect_reply(reply, &[226, 250])?;
+        
+        Ok(bytes)
+    }
+    
+    /// Log out, ignoring errors since the work is done
+    fn quit(mut self) {
+        let _ = self.command("QUIT");
+    }
+}
+
+/// Fail unless a reply has one of the expected codes
+fn expect_reply(reply: (u32, String), codes: &[u32]) -> Result<(), DataError> {
+    if codes.contains(&reply.0) {
+        Ok(())
+    } else {
+        Err(DataError::Other(format!("FTP server replied {} {}", reply.0, reply.1)))
+    }
+}
+
+/// Get the data port of a `227 Entering Passive Mode (h1,h2,h3,h4,p1,p2)` reply
+fn parse_pasv_port(text: &str) -> Option<u16> {
+    let start = text.find('(')?;
+    let end = text[start..].find(')')? + start;
+    let numbers: Vec<u16> = text[start + 1..end].split(',')
+        .map(|n| n.trim().parse::<u16>().ok())
+        .collect::<Option<_>>()?;
+    
+    match numbers.as_slice() {
+        [_, _, _, _, high, low] if *high < 256 && *low < 256 => Some(high * 256 + low),
+        _ => None,
+    }
+}
+
+/// Parse the `YYYYMMDDhhmmss` time of an MDTM reply
+fn parse_mdtm(text: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(text.get(..14)?, "%Y%m%d%H%M%S").ok()
+        .map(|time| time.and_utc())
+}
@@ -2,18 +2,22 @@
 // Author: Gabriel Demetrios Lafis
 
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 use std::path::Path;
 use std::collections::HashMap;
 
 use serde_json::{Value as JsonValue, Map};
 
-use super::{DataError, DataSet, DataSink, DataSource, Field, Row, Schema, SinkType, SourceType, Value, DataType};
+use super::{
+    DataError, DataSet, DataSink, DataSource, Decimal, Field, FieldDefault, Row, Schema, SinkType, SourceType, Value,
+    DataType, format_date, format_time, format_timestamp, format_uuid, parse_date, parse_time, parse_timestamp, parse_uuid,
+};
 
 /// JSON data source
 pub struct JsonSource {
     path: String,
     array_path: Option<String>,
+    ndjson: bool,
 }
 
 impl JsonSource {
@@ -22,6 +26,7 @@ impl JsonSource {
         JsonSource {
             path: path.as_ref().to_string_lossy().to_string(),
             array_path: None,
+            ndjson: false,
         }
     }
     
@@ -30,11 +35,48 @@ impl JsonSource {
         JsonSource {
             path: path.as_ref().to_string_lossy().to_string(),
             array_path: Some(array_path.into()),
+            ndjson: false,
         }
     }
     
+    /// Read newline-delimited JSON, one object per line, instead of an array
+    pub fn ndjson(mut self) -> Self {
+        self.ndjson = true;
+        self
+    }
+    
+    /// Count rows and columns without converting values
+    pub fn count(&self) -> Result<(usize, usize), DataError> {
+        let file = File::open(&self.path).map_err(DataError::IoError)?;
+        let reader = BufReader::new(file);
+        
+        let json: JsonValue = serde_json::from_reader(reader)
+            .map_err(|e| DataError::ParseError(e.to_string()))?;
+        
+        if let Some(schema) = Self::empty_schema(&json)? {
+            return Ok((0, schema.fields.len()));
+        }
+        
+        let mut current = &json;
+        if let Some(ref array_path) = self.array_path {
+            for part in array_path.split('.') {
+                current = current.get(part)
+                    .ok_or_else(|| DataError::ParseError(format!("Path '{}' not found", array_path)))?;
+            }
+        }
+        
+        let array = current.as_array()
+            .ok_or_else(|| DataError::ParseError("Expected a JSON array".to_string()))?;
+        
+        let columns = array.first()
+            .and_then(|v| v.as_object())
+            .map_or(0, |obj| obj.len());
+        
+        Ok((array.len(), columns))
+    }
+    
     /// Convert a JSON value to a data value
-    fn json_to_value(json: &JsonValue) -> Value {
+    pub(crate) fn json_to_value(json: &JsonValue) -> Value {
         match json {
             JsonValue::Null => Value::Null,
             JsonValue::Bool(b) => Value::Boolean(*b),
@@ -53,6 +95,31 @@ impl JsonSource {
                 Value::Array(values)
             },
             JsonValue::Object(obj) => {
+                // Binary values are written as {"$binary": "<base64>"}
+                if let Some(bytes) = Self::json_to_binary(obj) {
+                    return Value::Binary(bytes);
+                }
+                
+                // Points are written as {"$point": [lat, lon]}
+                if let Some((lat, lon)) = Self::json_to_point(obj) {
+                    return Value::Point(lat, lon);
+                }
+                
+                // UUIDs are written as {"$uuid": "<hyphenated>"}
+                if let Some(uuid) = Self::json_to_uuid(obj) {
+                    return Value::Uuid(uuid);
+                }
+                
+                // Dates, timestamps and times are written as {"$date": "<ISO 8601>"} and so on
+                if let Some(value) = Self::json_to_temporal(obj) {
+                    return value;
+                }
+                
+                // Decimals are written as {"$decimal": "<digits>"} so no digits are lost
+                if let Some(decimal) = Self::json_to_decimal(obj) {
+                    return Value::Decimal(decimal);
+                }
+                
                 let mut map = HashMap::new();
                 for (k, v) in obj {
                     map.insert(k.clone(), Self::json_to_value(v));
@@ -62,6 +129,98 @@ impl JsonSource {
         }
     }
     
+    /// Decode a {"$uuid": "<hyphenated>"} object to a UUID
+    fn json_to_uuid(obj: &Map<String, JsonValue>) -> Option<u128> {
+        if obj.len() != 1 {
+            return None;
+        }
+        
+        obj.get("$uuid")
+            .and_then(|v| v.as_str())
+            .and_then(|s| parse_uuid(s).ok())
+    }
+    
+    /// Decode a {"$date"}, {"$timestamp"} or {"$time"} object to its value
+    fn json_to_temporal(obj: &Map<String, JsonValue>) -> Option<Value> {
+        if obj.len() != 1 {
+            return None;
+        }
+        
+        let (key, text) = obj.iter().next()?;
+        let text = text.as_str()?;
+        
+        match key.as_str() {
+            "$date" => parse_date(text).ok().map(Value::Date),
+            "$timestamp" => parse_timestamp(text).ok().map(Value::Timestamp),
+            "$time" => parse_time(text).ok().map(Value::Time),
+            _ => None,
+        }
+    }
+    
+    /// Decode a {"$decimal": "<digits>"} object to a decimal
+    fn json_to_decimal(obj: &Map<String, JsonValue>) -> Option<Decimal> {
+        if obj.len() != 1 {
+            return None;
+        }
+        
+        obj.get("$decimal")
+            .and_then(|v| v.as_str())
+            .and_then(|s| Decimal::from_str(s).ok())
+    }
+    
+    /// Decode a {"$binary": "<base64>"} object to bytes
+    fn json_to_binary(obj: &Map<String, JsonValue>) -> Option<Vec<u8>> {
+        if obj.len() != 1 {
+            return None;
+        }
+        
+        obj.get("$binary")
+            .and_then(|v| v.as_str())
+            .and_then(|s| base64::decode(s).ok())
+    }
+    
+    /// Decode a {"$point": [lat, lon]} object to coordinates
+    fn json_to_point(obj: &Map<String, JsonValue>) -> Option<(f64, f64)> {
+        if obj.len() != 1 {
+            return None;
+        }
+        
+        match obj.get("$point").and_then(|v| v.as_array()) {
+            Some(coords) if coords.len() == 2 => {
+                Some((coords[0].as_f64()?, coords[1].as_f64()?))
+            },
+            _ => None,
+        }
+    }
+    
+    /// Read the schema of an empty dataset written as {"$schema": [fields]}
+    fn empty_schema(json: &JsonValue) -> Result<Option<Schema>, DataError> {
+        let fields = match json.get("$schema").and_then(|v| v.as_array()) {
+            Some(fields) => fields,
+            None => return Ok(None),
+        };
+        
+        let fields = fields.iter()
+            .map(|field| {
+                let name = field.get("name").and_then(|v| v.as_str())
+                    .ok_or_else(|| DataError::ParseError("Schema field without a name".to_string()))?;
+                let data_type = DataType::from_str(field.get("type").and_then(|v| v.as_str()).unwrap_or("string"))?;
+                let nullable = field.get("nullable").and_then(|v| v.as_bool()).unwrap_or(true);
+                
+                let mut field_def = Field::new(name.to_string(), data_type, nullable);
+                if let Some(generator) = field.get("generate").and_then(|v| v.as_str()) {
+                    field_def = field_def.with_default(FieldDefault::from_generator(generator)?);
+                } else if let Some(default) = field.get("default") {
+                    field_def = field_def.with_default(FieldDefault::Value(Self::json_to_value(default)));
+                }
+                
+                Ok(field_def)
+            })
+            .collect::<Result<Vec<_>, DataError>>()?;
+        
+        Ok(Some(Schema::new(fields)))
+    }
+    
     /// Infer schema from a JSON object
     fn infer_schema(obj: &Map<String, JsonValue>) -> Schema {
         let fields: Vec<Field> = obj.iter()
@@ -78,7 +237,16 @@ impl JsonSource {
                     },
                     JsonValue::String(_) => DataType::String,
                     JsonValue::Array(_) => DataType::Array(Box::new(DataType::String)), // Simplified
-                    JsonValue::Object(_) => DataType::Map(Box::new(DataType::String)), // Simplified
+                    JsonValue::Object(obj) if Self::json_to_binary(obj).is_some() => DataType::Binary,
+                    JsonValue::Object(obj) if Self::json_to_point(obj).is_some() => DataType::Point,
+                    JsonValue::Object(obj) if Self::json_to_uuid(obj).is_some() => DataType::Uuid,
+                    JsonValue::Object(obj) => match Self::json_to_temporal(obj) {
+                        Some(Value::Date(_)) => DataType::Date,
+                        Some(Value::Timestamp(_)) => DataType::Timestamp,
+                        Some(Value::Time(_)) => DataType::Time,
+                        _ => Self::json_to_decimal(obj)
+                            .map_or(DataType::Map(Box::new(DataType::String)), |decimal| decimal.data_type()), // Simplified
+                    },
                 };
                 
                 Field::new(key.clone(), data_type, true)
@@ -89,14 +257,28 @@ impl JsonSource {
     }
 }
 
-impl DataSource for JsonSource {
-    fn read(&self) -> Result<DataSet, DataError> {
-        let file = File::open(&self.path).map_err(DataError::IoError)?;
-        let reader = BufReader::new(file);
+impl JsonSource {
+    /// Read a dataset from any reader, such as decrypted file contents
+    pub fn read_from<R: Read>(&self, reader: R) -> Result<DataSet, DataError> {
+        if self.ndjson {
+            let lines = BufReader::new(reader).lines()
+                .filter(|line| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+                .map(|line| {
+                    let line = line.map_err(DataError::IoError)?;
+                    serde_json::from_str(&line).map_err(|e| DataError::ParseError(e.to_string()))
+                })
+                .collect::<Result<Vec<JsonValue>, DataError>>()?;
+            
+            return self.read_array(&lines);
+        }
         
         let json: JsonValue = serde_json::from_reader(reader)
             .map_err(|e| DataError::ParseError(e.to_string()))?;
         
+        if let Some(schema) = Self::empty_schema(&json)? {
+            return Ok(DataSet::new(schema));
+        }
+        
         // Get the array of objects
         let array = if let Some(ref array_path) = self.array_path {
             let parts: Vec<&str> = array_path.split('.').collect();
@@ -115,6 +297,11 @@ impl DataSource for JsonSource {
             return Err(DataError::ParseError("JSON root is not an array and no array path provided".to_string()));
         };
         
+        self.read_array(array)
+    }
+    
+    /// Convert an array of JSON objects to a dataset
+    pub(crate) fn read_array(&self, array: &[JsonValue]) -> Result<DataSet, DataError> {
         if array.is_empty() {
             return Err(DataError::ParseError("Empty JSON array".to_string()));
         }
@@ -149,6 +336,13 @@ impl DataSource for JsonSource {
         
         Ok(dataset)
     }
+}
+
+impl DataSource for JsonSource {
+    fn read(&self) -> Result<DataSet, DataError> {
+        let file = File::open(&self.path).map_err(DataError::IoError)?;
+        self.read_from(BufReader::new(file))
+    }
     
     fn name(&self) -> &str {
         &self.path
@@ -163,6 +357,37 @@ impl DataSource for JsonSource {
 pub struct JsonSink {
     path: String,
     pretty: bool,
+    non_finite: NonFiniteFloats,
+}
+
+/// How NaN and infinite floats, which JSON numbers cannot hold, are written
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NonFiniteFloats {
+    /// Write null, losing the value
+    Null,
+    /// Write the strings "NaN", "Infinity" and "-Infinity"
+    String,
+    /// Fail the write
+    Error,
+}
+
+impl NonFiniteFloats {
+    /// Convert a float to a JSON value
+    pub fn float_to_json(&self, f: f64) -> Result<JsonValue, DataError> {
+        if let Some(n) = serde_json::Number::from_f64(f) {
+            return Ok(JsonValue::Number(n));
+        }
+        
+        match self {
+            NonFiniteFloats::Null => Ok(JsonValue::Null),
+            NonFiniteFloats::String if f.is_nan() => Ok(JsonValue::from("NaN")),
+            NonFiniteFloats::String if f > 0.0 => Ok(JsonValue::from("Infinity")),
+            NonFiniteFloats::String => Ok(JsonValue::from("-Infinity")),
+            NonFiniteFloats::Error => Err(DataError::ValidationError(
+                format!("{} cannot be written as JSON", f)
+            )),
+        }
+    }
 }
 
 impl JsonSink {
@@ -171,75 +396,144 @@ impl JsonSink {
         JsonSink {
             path: path.as_ref().to_string_lossy().to_string(),
             pretty,
+            non_finite: NonFiniteFloats::Null,
         }
     }
     
+    /// Set how NaN and infinite floats are written; null by default
+    pub fn with_non_finite(mut self, non_finite: NonFiniteFloats) -> Self {
+        self.non_finite = non_finite;
+        self
+    }
+    
+    /// Convert a data value to a JSON value, writing NaN and infinities as null
+    pub(crate) fn value_to_json(value: &Value) -> JsonValue {
+        Self::value_to_json_as(value, NonFiniteFloats::Null).unwrap_or(JsonValue::Null)
+    }
+    
     /// Convert a data value to a JSON value
-    fn value_to_json(value: &Value) -> JsonValue {
-        match value {
+    fn value_to_json_as(value: &Value, non_finite: NonFiniteFloats) -> Result<JsonValue, DataError> {
+        Ok(match value {
             Value::Null => JsonValue::Null,
             Value::Boolean(b) => JsonValue::Bool(*b),
             Value::Integer(i) => JsonValue::Number((*i).into()),
-            Value::Float(f) => {
-                let n = serde_json::Number::from_f64(*f);
-                match n {
-                    Some(num) => JsonValue::Number(num),
-                    None => JsonValue::Null,
-                }
+            Value::Float(f) => non_finite.float_to_json(*f)?,
+            Value::Decimal(d) => {
+                let mut obj = Map::new();
+                obj.insert("$decimal".to_string(), JsonValue::String(d.to_string()));
+                JsonValue::Object(obj)
             },
             Value::String(s) => JsonValue::String(s.clone()),
             Value::Binary(b) => {
-                // Convert binary to base64 string
-                let base64 = base64::encode(b);
-                JsonValue::String(base64)
+                // Wrap base64 so the source can tell binary from string values
+                let mut obj = Map::new();
+                obj.insert("$binary".to_string(), JsonValue::String(base64::encode(b)));
+                JsonValue::Object(obj)
+            },
+            Value::Point(lat, lon) => {
+                let mut obj = Map::new();
+                obj.insert("$point".to_string(), serde_json::json!([lat, lon]));
+                JsonValue::Object(obj)
+            },
+            Value::Uuid(uuid) => {
+                let mut obj = Map::new();
+                obj.insert("$uuid".to_string(), JsonValue::String(format_uuid(*uuid)));
+                JsonValue::Object(obj)
+            },
+            Value::Date(date) => {
+                let mut obj = Map::new();
+                obj.insert("$date".to_string(), JsonValue::String(format_date(date)));
+                JsonValue::Object(obj)
+            },
+            Value::Timestamp(timestamp) => {
+                let mut obj = Map::new();
+                obj.insert("$timestamp".to_string(), JsonValue::String(format_timestamp(timestamp)));
+                JsonValue::Object(obj)
+            },
+            Value::Time(time) => {
+                let mut obj = Map::new();
+                obj.insert("$time".to_string(), JsonValue::String(format_time(time)));
+                JsonValue::Object(obj)
             },
             Value::Array(arr) => {
-                let values: Vec<JsonValue> = arr.iter()
-                    .map(|v| Self::value_to_json(v))
-                    .collect();
+                let values = arr.iter()
+                    .map(|v| Self::value_to_json_as(v, non_finite))
+                    .collect::<Result<Vec<JsonValue>, DataError>>()?;
                 JsonValue::Array(values)
             },
             Value::Map(map) => {
                 let mut obj = Map::new();
                 for (k, v) in map {
-                    obj.insert(k.clone(), Self::value_to_json(v));
+                    obj.insert(k.clone(), Self::value_to_json_as(v, non_finite)?);
                 }
                 JsonValue::Object(obj)
             },
-        }
+        })
     }
 }
 
-impl DataSink for JsonSink {
-    fn write(&self, data: &DataSet) -> Result<(), DataError> {
-        let file = File::create(&self.path).map_err(DataError::IoError)?;
-        let writer = BufWriter::new(file);
-        
+impl JsonSink {
+    /// Write a dataset to any writer, such as a buffer to be encrypted
+    pub fn write_to<W: Write>(&self, data: &DataSet, writer: W) -> Result<(), DataError> {
         let mut array = Vec::new();
         
+        // An empty array has no objects to infer the schema from, so empty
+        // datasets are written as their schema
+        if data.is_empty() && !data.schema.fields.is_empty() {
+            let fields: Vec<JsonValue> = data.schema.fields.iter()
+                .map(|field| {
+                    let mut json = serde_json::json!({
+                        "name": field.name,
+                        "type": field.data_type.name(),
+                        "nullable": field.nullable,
+                    });
+                    
+                    match &field.default {
+                        Some(FieldDefault::Value(value)) => json["default"] = Self::value_to_json(value),
+                        Some(default) => json["generate"] = JsonValue::from(default.generator()),
+                        None => {},
+                    }
+                    
+                    json
+                })
+                .collect();
+            
+            return self.write_json(&serde_json::json!({ "$schema": fields }), writer);
+        }
+        
         for row in &data.data {
             let mut obj = Map::new();
             
             for (i, field) in data.schema.fields.iter().enumerate() {
                 let value = row.values.get(i).unwrap_or(&Value::Null);
-                obj.insert(field.name.clone(), Self::value_to_json(value));
+                obj.insert(field.name.clone(), Self::value_to_json_as(value, self.non_finite)?);
             }
             
             array.push(JsonValue::Object(obj));
         }
         
-        let json = JsonValue::Array(array);
-        
+        self.write_json(&JsonValue::Array(array), writer)
+    }
+    
+    /// Write a JSON value, pretty-printed if configured
+    fn write_json<W: Write>(&self, json: &JsonValue, writer: W) -> Result<(), DataError> {
         if self.pretty {
-            serde_json::to_writer_pretty(writer, &json)
+            serde_json::to_writer_pretty(writer, json)
                 .map_err(|e| DataError::IoError(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
         } else {
-            serde_json::to_writer(writer, &json)
+            serde_json::to_writer(writer, json)
                 .map_err(|e| DataError::IoError(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
         }
         
         Ok(())
     }
+}
+
+impl DataSink for JsonSink {
+    fn write(&self, data: &DataSet) -> Result<(), DataError> {
+        let file = File::create(&self.path).map_err(DataError::IoError)?;
+        self.write_to(data, BufWriter::new(file))
+    }
     
     fn name(&self) -> &str {
         &self.path
@@ -0,0 +1,82 @@
+// Column lineage kept in dataset metadata
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{DataType, Metadata, Schema};
+
+/// Prefix of metadata keys holding the lineage of a column
+const LINEAGE_PREFIX: &str = "lineage.";
+
+/// Step in the history of a column
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LineageStep {
+    /// The column was renamed from another
+    Renamed { from: String },
+    /// The column was added, computed by an expression if known
+    Derived { expression: Option<String> },
+    /// The column was cast from one type to another
+    Cast { from: String, to: String },
+}
+
+impl LineageStep {
+    /// Create a cast step between two types
+    pub fn cast(from: &DataType, to: &DataType) -> Self {
+        LineageStep::Cast { from: from.name(), to: to.name() }
+    }
+}
+
+impl Metadata {
+    /// Get the history of a column, oldest step first
+    pub fn lineage(&self, column: &str) -> Vec<LineageStep> {
+        self.get(&format!("{}{}", LINEAGE_PREFIX, column))
+            .and_then(|steps| serde_json::from_str(steps).ok())
+            .unwrap_or_default()
+    }
+    
+    /// Add a step to the history of a column
+    pub fn add_lineage(&mut self, column: &str, step: LineageStep) {
+        let mut steps = self.lineage(column);
+        steps.push(step);
+        self.set_lineage(column, &steps);
+    }
+    
+    /// Move the history of a renamed column to its new name and record the rename
+    pub fn rename_lineage(&mut self, from: &str, to: &str) {
+        let steps = self.lineage(from);
+        self.properties.remove(&format!("{}{}", LINEAGE_PREFIX, from));
+        self.set_lineage(to, &steps);
+        self.add_lineage(to, LineageStep::Renamed { from: from.to_string() });
+    }
+    
+    /// Get the history of every column of the schema that has one
+    pub fn column_lineage(&self, schema: &Schema) -> BTreeMap<String, Vec<LineageStep>> {
+        schema.fields.iter()
+            .map(|field| (field.name.clone(), self.lineage(&field.name)))
+            .filter(|(_, steps)| !steps.is_empty())
+            .collect()
+    }
+    
+    /// Replace the history of every column with the given one
+    pub fn set_column_lineage(&mut self, lineage: &BTreeMap<String, Vec<LineageStep>>) {
+        self.properties.retain(|key, _| !key.starts_with(LINEAGE_PREFIX));
+        
+        for (column, steps) in lineage {
+            self.set_lineage(column, steps);
+        }
+    }
+    
+    /// Replace the history of a column
+    fn set_lineage(&mut self, column: &str, steps: &[LineageStep]) {
+        let key = format!("{}{}", LINEAGE_PREFIX, column);
+        
+        if steps.is_empty() {
+            self.properties.remove(&key);
+        } else if let Ok(steps) = serde_json::to_string(steps) {
+            self.add(key, steps);
+        }
+    }
+}
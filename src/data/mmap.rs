@@ -0,0 +1,110 @@
+// Memory-mapped file reading
+// Author: Gabriel Demetrios Lafis
+
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+use super::DataError;
+
+/// Read-only memory map of a file
+///
+/// The OS pages the file in as it is read instead of copying it through a
+/// buffer, so large files are parsed straight from the page cache. A mapped
+/// file must not be truncated while it is read; `FileStorage` replaces files
+/// by renaming a new file over them when mapping is enabled.
+#[derive(Clone)]
+pub struct MappedFile {
+    #[cfg(feature = "mmap")]
+    map: Arc<memmap2::Mmap>,
+    #[cfg(not(feature = "mmap"))]
+    map: Arc<Vec<u8>>,
+}
+
+impl MappedFile {
+    /// Map a file into memory
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, DataError> {
+        #[cfg(feature = "mmap")]
+        {
+            let file = std::fs::File::open(path).map_err(DataError::IoError)?;
+            
+            // Safety: the map is read-only, and files are replaced rather than
+            // truncated while they may be mapped
+            let map = unsafe { memmap2::Mmap::map(&file) }.map_err(DataError::IoError)?;
+            
+            Ok(MappedFile { map: Arc::new(map) })
+        }
+        
+        #[cfg(not(feature = "mmap"))]
+        {
+            let _ = path;
+            Err(DataError::NotSupported("Memory-mapped reading not enabled".to_string()))
+        }
+    }
+    
+    /// Get the contents of the file
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map[..]
+    }
+    
+    /// Get the length of the file in bytes
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+    
+    /// Check if the file is empty
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+    
+    /// Get a reader over the whole file
+    pub fn reader(&self) -> MappedReader {
+        self.range_reader(0, self.len())
+    }
+    
+    /// Get a reader over a byte range of the file, clamped to its length
+    pub fn range_reader(&self, start: usize, length: usize) -> MappedReader {
+        let end = start.saturating_add(length).min(self.len());
+        
+        MappedReader {
+            file: self.clone(),
+            pos: start.min(end),
+            end,
+        }
+    }
+}
+
+/// Reader over a byte range of a mapped file
+pub struct MappedReader {
+    file: MappedFile,
+    pos: usize,
+    end: usize,
+}
+
+impl Read for MappedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes = &self.file.as_bytes()[self.pos..self.end];
+        let n = bytes.len().min(buf.len());
+        
+        buf[..n].copy_from_slice(&bytes[..n]);
+        self.pos += n;
+        
+        Ok(n)
+    }
+}
+
+#[cfg(all(feature = "mmap", feature = "parquet"))]
+impl parquet::file::reader::Length for MappedFile {
+    fn len(&self) -> u64 {
+        self.map.len() as u64
+    }
+}
+
+#[cfg(all(feature = "mmap", feature = "parquet"))]
+impl parquet::file::reader::ChunkReader for MappedFile {
+    type T = MappedReader;
+    
+    fn get_read(&self, start: u64, length: usize) -> parquet::errors::Result<Self::T> {
+        Ok(self.range_reader(start as usize, length))
+    }
+}
@@ -5,14 +5,56 @@ mod csv;
 mod json;
 mod parquet;
 mod schema;
+mod uuid;
+mod temporal;
+mod decimal;
+mod dictionary;
+mod mmap;
+mod remote;
+mod sftp;
+mod ftp;
+mod elasticsearch;
+mod mqtt;
+mod delta;
+mod orc;
+mod fixed_width;
+mod xml;
+mod msgpack;
+mod statistics;
+mod record;
+mod lineage;
+mod schema_registry;
 
 pub use csv::*;
 pub use json::*;
 pub use parquet::*;
 pub use schema::*;
+pub use uuid::*;
+pub use temporal::*;
+pub use decimal::*;
+pub use dictionary::*;
+pub use mmap::*;
+pub use remote::*;
+pub use sftp::*;
+pub use ftp::*;
+pub use elasticsearch::*;
+pub use mqtt::*;
+pub use delta::*;
+pub use orc::*;
+pub use fixed_width::*;
+pub use xml::*;
+pub use msgpack::*;
+pub use statistics::*;
+pub use record::*;
+pub use lineage::*;
+pub use schema_registry::*;
 
 use std::error::Error;
 use std::fmt;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use serde::Serialize;
 
 /// Represents a generic data source
 pub trait DataSource {
@@ -66,6 +108,78 @@ impl DataSet {
         Ok(())
     }
     
+    /// Insert a row, filling missing values from field defaults
+    ///
+    /// Rows may omit trailing fields. Null values are replaced by the field's
+    /// default, any null left in a non-nullable field is rejected, and every
+    /// value must match its field's type.
+    pub fn insert_row(&mut self, mut row: Row) -> Result<(), DataError> {
+        if row.values.len() > self.schema.fields.len() {
+            return Err(DataError::SchemaMismatch);
+        }
+        
+        row.values.resize(self.schema.fields.len(), Value::Null);
+        
+        for (index, field) in self.schema.fields.iter().enumerate() {
+            if !matches!(row.values[index], Value::Null) {
+                continue;
+            }
+            
+            if let Some(default) = &field.default {
+                row.values[index] = self.generate(index, field, default);
+            }
+            
+            if !field.nullable && matches!(row.values[index], Value::Null) {
+                return Err(DataError::ValidationError(format!(
+                    "Field '{}' is not nullable", field.name
+                )).with_context(ErrorContext::new().column(&field.name).row(self.data.len())));
+            }
+        }
+        
+        for (value, field) in row.values.iter().zip(&self.schema.fields) {
+            SchemaValidator::validate_value(value, &field.data_type)
+                .map_err(|err| err.with_context(ErrorContext::new().column(&field.name).row(self.data.len()).value(value)))?;
+        }
+        
+        self.data.push(row);
+        Ok(())
+    }
+    
+    /// Generate the default value of a field for a new row
+    fn generate(&self, index: usize, field: &Field, default: &FieldDefault) -> Value {
+        match default {
+            FieldDefault::Value(value) => value.clone(),
+            FieldDefault::AutoIncrement => {
+                let max = self.data.iter()
+                    .filter_map(|row| match row.values.get(index) {
+                        Some(Value::Integer(i)) => Some(*i),
+                        _ => None,
+                    })
+                    .max();
+                
+                Value::Integer(max.map_or(1, |max| max + 1))
+            },
+            FieldDefault::Uuid => {
+                let uuid = generate_uuid();
+                
+                match field.data_type {
+                    DataType::Uuid => Value::Uuid(uuid),
+                    _ => Value::String(format_uuid(uuid)),
+                }
+            },
+            FieldDefault::CreatedAt => {
+                let now = chrono::Utc::now();
+                
+                match field.data_type {
+                    DataType::Integer => Value::Integer(now.timestamp_millis()),
+                    DataType::Timestamp => Value::Timestamp(now),
+                    DataType::Date => Value::Date(now.date_naive()),
+                    _ => Value::String(now.to_rfc3339()),
+                }
+            },
+        }
+    }
+    
     /// Get the number of rows in the dataset
     pub fn len(&self) -> usize {
         self.data.len()
@@ -117,12 +231,133 @@ pub enum Value {
     Boolean(bool),
     Integer(i64),
     Float(f64),
+    /// An exact decimal number
+    Decimal(Decimal),
     String(String),
     Binary(Vec<u8>),
+    Point(f64, f64),
+    Uuid(u128),
+    Date(chrono::NaiveDate),
+    /// A point in time, kept in UTC
+    Timestamp(chrono::DateTime<chrono::Utc>),
+    /// A time of day
+    Time(chrono::NaiveTime),
     Array(Vec<Value>),
     Map(std::collections::HashMap<String, Value>),
 }
 
+// Floats compare by their bits so values can key joins and groups
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (Value::Decimal(a), Value::Decimal(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Binary(a), Value::Binary(b)) => a == b,
+            (Value::Point(a_lat, a_lon), Value::Point(b_lat, b_lon)) => {
+                a_lat.to_bits() == b_lat.to_bits() && a_lon.to_bits() == b_lon.to_bits()
+            },
+            (Value::Uuid(a), Value::Uuid(b)) => a == b,
+            (Value::Date(a), Value::Date(b)) => a == b,
+            (Value::Timestamp(a), Value::Timestamp(b)) => a == b,
+            (Value::Time(a), Value::Time(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+// A total order for sorting keys deterministically: values of different
+// types order by variant, nulls first, and floats by `f64::total_cmp`
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let rank = |value: &Value| match value {
+            Value::Null => 0,
+            Value::Boolean(_) => 1,
+            Value::Integer(_) => 2,
+            Value::Float(_) => 3,
+            Value::Decimal(_) => 4,
+            Value::String(_) => 5,
+            Value::Binary(_) => 6,
+            Value::Point(_, _) => 7,
+            Value::Uuid(_) => 8,
+            Value::Date(_) => 9,
+            Value::Timestamp(_) => 10,
+            Value::Time(_) => 11,
+            Value::Array(_) => 12,
+            Value::Map(_) => 13,
+        };
+        
+        match (self, other) {
+            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+            (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+            (Value::Float(a), Value::Float(b)) => a.total_cmp(b),
+            (Value::Decimal(a), Value::Decimal(b)) => a.cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Binary(a), Value::Binary(b)) => a.cmp(b),
+            (Value::Point(a_lat, a_lon), Value::Point(b_lat, b_lon)) => {
+                a_lat.total_cmp(b_lat).then(a_lon.total_cmp(b_lon))
+            },
+            (Value::Uuid(a), Value::Uuid(b)) => a.cmp(b),
+            (Value::Date(a), Value::Date(b)) => a.cmp(b),
+            (Value::Timestamp(a), Value::Timestamp(b)) => a.cmp(b),
+            (Value::Time(a), Value::Time(b)) => a.cmp(b),
+            (Value::Array(a), Value::Array(b)) => a.cmp(b),
+            (Value::Map(a), Value::Map(b)) => {
+                let mut a: Vec<_> = a.iter().collect();
+                let mut b: Vec<_> = b.iter().collect();
+                a.sort();
+                b.sort();
+                a.cmp(&b)
+            },
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        
+        match self {
+            Value::Null => {},
+            Value::Boolean(b) => b.hash(state),
+            Value::Integer(i) => i.hash(state),
+            Value::Float(f) => f.to_bits().hash(state),
+            Value::Decimal(d) => d.hash(state),
+            Value::String(s) => s.hash(state),
+            Value::Binary(b) => b.hash(state),
+            Value::Point(lat, lon) => {
+                lat.to_bits().hash(state);
+                lon.to_bits().hash(state);
+            },
+            Value::Uuid(u) => u.hash(state),
+            Value::Date(d) => d.hash(state),
+            Value::Timestamp(t) => t.hash(state),
+            Value::Time(t) => t.hash(state),
+            Value::Array(values) => values.hash(state),
+            Value::Map(map) => {
+                // Hash entries in key order so equal maps hash alike
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                entries.hash(state);
+            },
+        }
+    }
+}
+
 /// Represents a schema for a dataset
 #[derive(Debug, Clone)]
 pub struct Schema {
@@ -152,6 +387,7 @@ pub struct Field {
     pub name: String,
     pub data_type: DataType,
     pub nullable: bool,
+    pub default: Option<FieldDefault>,
 }
 
 impl Field {
@@ -161,22 +397,237 @@ impl Field {
             name,
             data_type,
             nullable,
+            default: None,
+        }
+    }
+    
+    /// Fill missing values of this field on insertion
+    pub fn with_default(mut self, default: FieldDefault) -> Self {
+        self.default = Some(default);
+        self
+    }
+}
+
+/// How a missing field value is filled when a row is inserted
+#[derive(Debug, Clone)]
+pub enum FieldDefault {
+    /// A fixed value
+    Value(Value),
+    /// One more than the largest integer already in the column
+    AutoIncrement,
+    /// A random (version 4) UUID
+    Uuid,
+    /// The insertion time, as epoch milliseconds for integer fields, a timestamp or date for
+    /// temporal fields and RFC 3339 otherwise
+    CreatedAt,
+}
+
+impl FieldDefault {
+    /// Parse a generator name: `autoincrement`, `uuid` or `created_at`
+    pub fn from_generator(name: &str) -> Result<Self, DataError> {
+        match name.trim().to_lowercase().as_str() {
+            "autoincrement" | "auto_increment" => Ok(FieldDefault::AutoIncrement),
+            "uuid" => Ok(FieldDefault::Uuid),
+            "created_at" => Ok(FieldDefault::CreatedAt),
+            _ => Err(DataError::ValidationError(format!("Invalid generator: {}", name))),
+        }
+    }
+    
+    /// Get the generator name, or None for a fixed value
+    pub fn generator(&self) -> Option<&'static str> {
+        match self {
+            FieldDefault::Value(_) => None,
+            FieldDefault::AutoIncrement => Some("autoincrement"),
+            FieldDefault::Uuid => Some("uuid"),
+            FieldDefault::CreatedAt => Some("created_at"),
         }
     }
 }
 
 /// Represents a data type for a field
+///
+/// Narrow numeric types hold `Value::Integer` and `Value::Float` values
+/// restricted to their range, so they survive Parquet round trips.
 #[derive(Debug, Clone, PartialEq)]
 pub enum DataType {
     Boolean,
+    Int8,
+    Int16,
+    Int32,
     Integer,
+    Float32,
     Float,
+    /// Exact decimal numbers of a precision (total digits) and scale (digits after the point)
+    Decimal(u8, u8),
     String,
     Binary,
+    Point,
+    Uuid,
+    Date,
+    Timestamp,
+    Time,
     Array(Box<DataType>),
     Map(Box<DataType>),
 }
 
+impl DataType {
+    /// Parse a data type from a name such as `integer` or `array<string>`
+    pub fn from_str(s: &str) -> Result<Self, DataError> {
+        let s = s.trim().to_lowercase();
+        
+        let inner = |prefix: &str| s.strip_prefix(prefix).and_then(|rest| rest.strip_suffix('>'));
+        
+        if let Some(elem) = inner("array<") {
+            return Ok(DataType::Array(Box::new(Self::from_str(elem)?)));
+        }
+        
+        if let Some(value) = inner("map<") {
+            return Ok(DataType::Map(Box::new(Self::from_str(value)?)));
+        }
+        
+        if s.starts_with("decimal") {
+            return Self::decimal_from_str(&s);
+        }
+        
+        match s.as_str() {
+            "boolean" => Ok(DataType::Boolean),
+            "int8" => Ok(DataType::Int8),
+            "int16" => Ok(DataType::Int16),
+            "int32" => Ok(DataType::Int32),
+            "integer" | "int64" => Ok(DataType::Integer),
+            "float32" => Ok(DataType::Float32),
+            "float" | "float64" => Ok(DataType::Float),
+            "string" => Ok(DataType::String),
+            "binary" => Ok(DataType::Binary),
+            "point" => Ok(DataType::Point),
+            "uuid" => Ok(DataType::Uuid),
+            "date" => Ok(DataType::Date),
+            "timestamp" | "datetime" => Ok(DataType::Timestamp),
+            "time" => Ok(DataType::Time),
+            _ => Err(DataError::ValidationError(format!("Invalid data type: {}", s))),
+        }
+    }
+    
+    /// Get the name of the data type, as accepted by `from_str`
+    pub fn name(&self) -> String {
+        match self {
+            DataType::Boolean => "boolean".to_string(),
+            DataType::Int8 => "int8".to_string(),
+            DataType::Int16 => "int16".to_string(),
+            DataType::Int32 => "int32".to_string(),
+            DataType::Integer => "integer".to_string(),
+            DataType::Float32 => "float32".to_string(),
+            DataType::Float => "float".to_string(),
+            DataType::Decimal(precision, scale) => format!("decimal({},{})", precision, scale),
+            DataType::String => "string".to_string(),
+            DataType::Binary => "binary".to_string(),
+            DataType::Point => "point".to_string(),
+            DataType::Uuid => "uuid".to_string(),
+            DataType::Date => "date".to_string(),
+            DataType::Timestamp => "timestamp".to_string(),
+            DataType::Time => "time".to_string(),
+            DataType::Array(elem) => format!("array<{}>", elem.name()),
+            DataType::Map(value) => format!("map<{}>", value.name()),
+        }
+    }
+    
+    /// Parse a decimal type such as `decimal(10,2)`
+    fn decimal_from_str(s: &str) -> Result<Self, DataError> {
+        let invalid = || DataError::ValidationError(format!(
+            "Invalid data type: {}; decimals need a precision and scale, such as decimal(10,2)", s
+        ));
+        
+        let (precision, scale) = s.strip_prefix("decimal(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .and_then(|args| args.split_once(','))
+            .ok_or_else(invalid)?;
+        
+        let precision: u8 = precision.trim().parse().map_err(|_| invalid())?;
+        let scale: u8 = scale.trim().parse().map_err(|_| invalid())?;
+        
+        if precision == 0 || precision > MAX_DECIMAL_PRECISION || scale > precision {
+            return Err(invalid());
+        }
+        
+        Ok(DataType::Decimal(precision, scale))
+    }
+    
+    /// Check if the type holds integers of any width
+    pub fn is_integer(&self) -> bool {
+        matches!(self, DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Integer)
+    }
+    
+    /// Check if the type holds floats of any width
+    pub fn is_float(&self) -> bool {
+        matches!(self, DataType::Float32 | DataType::Float)
+    }
+    
+    /// Check if the type holds numbers
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+    
+    /// Get the smallest and largest values of an integer type
+    pub fn integer_range(&self) -> Option<(i64, i64)> {
+        match self {
+            DataType::Int8 => Some((i8::MIN as i64, i8::MAX as i64)),
+            DataType::Int16 => Some((i16::MIN as i64, i16::MAX as i64)),
+            DataType::Int32 => Some((i32::MIN as i64, i32::MAX as i64)),
+            DataType::Integer => Some((i64::MIN, i64::MAX)),
+            _ => None,
+        }
+    }
+    
+    /// Get the narrowest type holding every value of both types
+    ///
+    /// Integers widened with floats become Float32 only when the integers are
+    /// exactly representable in it (up to 16 bits); otherwise Float. Returns
+    /// None for types with no common numeric representation.
+    pub fn widen(&self, other: &DataType) -> Option<DataType> {
+        if self == other {
+            return Some(self.clone());
+        }
+        
+        // Decimals widen to hold the whole and fraction digits of both types
+        match (self, other) {
+            (DataType::Decimal(p1, s1), DataType::Decimal(p2, s2)) => {
+                let scale = *s1.max(s2);
+                let whole = (p1 - s1).max(p2 - s2);
+                return Some(DataType::Decimal((whole + scale).min(MAX_DECIMAL_PRECISION), scale));
+            },
+            (DataType::Decimal(precision, scale), t) | (t, DataType::Decimal(precision, scale)) if t.is_integer() => {
+                return Some(DataType::Decimal((scale + 19).max(*precision).min(MAX_DECIMAL_PRECISION), *scale));
+            },
+            (DataType::Decimal(_, _), t) | (t, DataType::Decimal(_, _)) if t.is_float() => {
+                return Some(DataType::Float);
+            },
+            _ => {},
+        }
+        
+        let width = |t: &DataType| match t {
+            DataType::Int8 => 8,
+            DataType::Int16 => 16,
+            DataType::Int32 | DataType::Float32 => 32,
+            _ => 64,
+        };
+        
+        match (self.is_integer(), other.is_integer(), self.is_float(), other.is_float()) {
+            (true, true, _, _) => Some(if width(self) >= width(other) { self.clone() } else { other.clone() }),
+            (_, _, true, true) => Some(DataType::Float),
+            (true, _, _, true) | (_, true, true, _) => {
+                let (int, float) = if self.is_integer() { (self, other) } else { (other, self) };
+                
+                if *float == DataType::Float32 && width(int) <= 16 {
+                    Some(DataType::Float32)
+                } else {
+                    Some(DataType::Float)
+                }
+            },
+            _ => None,
+        }
+    }
+}
+
 /// Represents metadata for a dataset
 #[derive(Debug, Clone)]
 pub struct Metadata {
@@ -222,6 +673,97 @@ pub enum SinkType {
     Custom(String),
 }
 
+/// Longest rendering of an offending value kept in an error context
+const CONTEXT_VALUE_LIMIT: usize = 100;
+
+/// Where in the data an error happened
+///
+/// Every part is optional; layers that know more, such as the API knowing
+/// the dataset name, fill in the parts still missing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ErrorContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dataset: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    /// Index of the row in its dataset, from zero
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub row: Option<usize>,
+    /// Rendering of the offending value, truncated if long
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+impl ErrorContext {
+    /// Create an empty context
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Set the dataset name
+    pub fn dataset(mut self, name: &str) -> Self {
+        self.dataset = Some(name.to_string());
+        self
+    }
+    
+    /// Set the column name
+    pub fn column(mut self, name: &str) -> Self {
+        self.column = Some(name.to_string());
+        self
+    }
+    
+    /// Set the row index
+    pub fn row(mut self, index: usize) -> Self {
+        self.row = Some(index);
+        self
+    }
+    
+    /// Set the offending value
+    pub fn value(mut self, value: &Value) -> Self {
+        let rendered = match JsonSink::value_to_json(value) {
+            serde_json::Value::String(s) => s,
+            json => json.to_string(),
+        };
+        
+        self.value = Some(match rendered.char_indices().nth(CONTEXT_VALUE_LIMIT) {
+            Some((end, _)) => format!("{}...", &rendered[..end]),
+            None => rendered,
+        });
+        self
+    }
+    
+    /// Fill the parts missing here from another context
+    pub(crate) fn or(self, other: ErrorContext) -> Self {
+        ErrorContext {
+            dataset: self.dataset.or(other.dataset),
+            column: self.column.or(other.column),
+            row: self.row.or(other.row),
+            value: self.value.or(other.value),
+        }
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        
+        if let Some(dataset) = &self.dataset {
+            parts.push(format!("dataset '{}'", dataset));
+        }
+        if let Some(column) = &self.column {
+            parts.push(format!("column '{}'", column));
+        }
+        if let Some(row) = self.row {
+            parts.push(format!("row {}", row));
+        }
+        if let Some(value) = &self.value {
+            parts.push(format!("value '{}'", value));
+        }
+        
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
 /// Represents an error in the data module
 #[derive(Debug)]
 pub enum DataError {
@@ -231,6 +773,34 @@ pub enum DataError {
     ValidationError(String),
     NotSupported(String),
     Other(String),
+    /// Another error with where in the data it happened
+    InContext(Box<DataError>, ErrorContext),
+}
+
+impl DataError {
+    /// Add context to the error, keeping the parts it already has
+    pub fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            DataError::InContext(err, existing) => DataError::InContext(err, existing.or(context)),
+            err => DataError::InContext(Box::new(err), context),
+        }
+    }
+    
+    /// Get the context of the error, if any
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            DataError::InContext(_, context) => Some(context),
+            _ => None,
+        }
+    }
+    
+    /// Get the error without its context
+    pub fn without_context(&self) -> &DataError {
+        match self {
+            DataError::InContext(err, _) => err,
+            err => err,
+        }
+    }
 }
 
 impl fmt::Display for DataError {
@@ -242,6 +812,7 @@ impl fmt::Display for DataError {
             DataError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
             DataError::NotSupported(msg) => write!(f, "Not supported: {}", msg),
             DataError::Other(msg) => write!(f, "Error: {}", msg),
+            DataError::InContext(err, context) => write!(f, "{} ({})", err, context),
         }
     }
 }
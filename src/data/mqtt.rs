@@ -0,0 +1,265 @@
+// MQTT streaming source implementation
+// Author: Gabriel Demetrios Lafis
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value as JsonValue;
+
+use super::{Compatibility, DataError, DataSet, DataType, JsonSource, Row, Schema, SchemaRegistry, SchemaValidator, Value};
+
+/// Messages per micro-batch by default
+const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// Time a micro-batch stays open after its first message by default
+const DEFAULT_BATCH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// MQTT source subscribing to topics of JSON messages
+///
+/// Each message is a JSON object decoded against the schema: fields are
+/// looked up by name, missing ones are null, and messages that are not
+/// objects or do not fit the schema are counted as rejected and skipped.
+/// Messages are gathered into micro-batches and acknowledged only when the
+/// batch is committed, after it has been processed, so messages of a batch
+/// that was not committed are delivered again after a reconnect
+/// (at-least-once). Requires the `mqtt` feature.
+///
+/// With a schema registry, messages are instead Avro records in the
+/// Confluent wire format. Batches then follow the writer's schema of their
+/// messages as far as the compatibility level allows, and later batches
+/// start from the evolved schema.
+pub struct MqttSource {
+    host: String,
+    port: u16,
+    client_id: String,
+    topics: Vec<String>,
+    schema: Schema,
+    batch_size: usize,
+    batch_timeout: Duration,
+    credentials: Option<(String, String)>,
+    registry: Option<(Arc<SchemaRegistry>, Compatibility)>,
+}
+
+/// Micro-batch of decoded messages
+pub struct MqttBatch {
+    pub data: DataSet,
+    /// Messages skipped because they did not fit the schema
+    pub rejected: usize,
+    #[cfg(feature = "mqtt")]
+    messages: Vec<rumqttc::Publish>,
+}
+
+/// Subscription delivering micro-batches
+pub struct MqttStream {
+    schema: Schema,
+    batch_size: usize,
+    batch_timeout: Duration,
+    registry: Option<(Arc<SchemaRegistry>, Compatibility)>,
+    #[cfg(feature = "mqtt")]
+    client: rumqttc::Client,
+    #[cfg(feature = "mqtt")]
+    connection: rumqttc::Connection,
+}
+
+impl MqttSource {
+    /// Create a new source for a broker, with a client id the broker keeps the session under
+    pub fn new(host: &str, port: u16, client_id: &str, schema: Schema) -> Self {
+        MqttSource {
+            host: host.to_string(),
+            port,
+            client_id: client_id.to_string(),
+            topics: Vec::new(),
+            schema,
+            batch_size: DEFAULT_BATCH_SIZE,
+            batch_timeout: DEFAULT_BATCH_TIMEOUT,
+            credentials: None,
+            registry: None,
+        }
+    }
+    
+    /// Subscribe to a topic filter, such as `sensors/+/temperature`
+    pub fn with_topic(mut self, topic: &str) -> Self {
+        self.topics.push(topic.to_string());
+        self
+    }
+    
+    /// Close micro-batches once they hold this many messages
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+    
+    /// Close micro-batches this long after their first message
+    pub fn with_batch_timeout(mut self, timeout: Duration) -> Self {
+        self.batch_timeout = timeout;
+        self
+    }
+    
+    /// Log in with a username and password
+    pub fn with_credentials(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+    
+    /// Decode messages as Avro records with schemas from a registry
+    pub fn with_schema_registry(mut self, registry: Arc<SchemaRegistry>, compatibility: Compatibility) -> Self {
+        self.registry = Some((registry, compatibility));
+        self
+    }
+    
+    /// Connect and subscribe to the topics with QoS 1
+    pub fn subscribe(&self) -> Result<MqttStream, DataError> {
+        if self.topics.is_empty() {
+            return Err(DataError::ValidationError("MQTT source has no topics".to_string()));
+        }
+        
+        #[cfg(feature = "mqtt")]
+        {
+            use rumqttc::{Client, MqttOptions, QoS};
+            
+            let mut options = MqttOptions::new(&self.client_id, &self.host, self.port);
+            options.set_keep_alive(Duration::from_secs(30));
+            // Keep the session, so unacknowledged messages are delivered again
+            options.set_clean_session(false);
+            options.set_manual_acks(true);
+            
+            if let Some((username, password)) = &self.credentials {
+                options.set_credentials(username, password);
+            }
+            
+            let (client, connection) = Client::new(options, self.batch_size.max(10));
+            
+            for topic in &self.topics {
+                client.subscribe(topic, QoS::AtLeastOnce).map_err(mqtt_error)?;
+            }
+            
+            Ok(MqttStream {
+                schema: self.schema.clone(),
+                batch_size: self.batch_size,
+                batch_timeout: self.batch_timeout,
+                registry: self.registry.clone(),
+                client,
+                connection,
+            })
+        }
+        
+        #[cfg(not(feature = "mqtt"))]
+        {
+            Err(DataError::NotSupported("MQTT support not enabled".to_string()))
+        }
+    }
+}
+
+impl MqttStream {
+    /// Wait for the next micro-batch
+    ///
+    /// Blocks until a message arrives, then gathers messages until the batch
+    /// is full or its timeout has passed.
+    pub fn next_batch(&mut self) -> Result<MqttBatch, DataError> {
+        #[cfg(feature = "mqtt")]
+        {
+            use rumqttc::{Event, Packet, RecvTimeoutError};
+            use std::time::Instant;
+            
+            let mut batch = MqttBatch { data: DataSet::new(self.schema.clone()), rejected: 0, messages: Vec::new() };
+            let mut deadline: Option<Instant> = None;
+            
+            while batch.messages.len() < self.batch_size {
+                let wait = match deadline {
+                    Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                    None => self.batch_timeout,
+                };
+                
+                let publish = match self.connection.recv_timeout(wait) {
+                    Ok(Ok(Event::Incoming(Packet::Publish(publish)))) => publish,
+                    Ok(Ok(_)) => continue,
+                    Ok(Err(err)) => return Err(mqtt_error(err)),
+                    Err(RecvTimeoutError::Timeout) if deadline.is_some() => break,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => return Err(DataError::Other(
+                        "MQTT connection closed".to_string()
+                    )),
+                };
+                
+                deadline.get_or_insert_with(|| Instant::now() + self.batch_timeout);
+                
+                let decoded = match &self.registry {
+                    Some((registry, compatibility)) => registry.decode_into(&publish.payload, &mut batch.data, *compatibility),
+                    None => decode_message(&publish.payload, &self.schema).and_then(|row| batch.data.add_row(row)),
+                };
+                
+                if let Err(err) = decoded {
+                    log::warn!("Rejected MQTT message on '{}': {}", publish.topic, err);
+                    batch.rejected += 1;
+                }
+                
+                batch.messages.push(publish);
+            }
+            
+            // Schema changes carry over to later batches
+            self.schema = batch.data.schema.clone();
+            Ok(batch)
+        }
+        
+        #[cfg(not(feature = "mqtt"))]
+        {
+            let _ = (&self.schema, self.batch_size, self.batch_timeout, &self.registry);
+            Err(DataError::NotSupported("MQTT support not enabled".to_string()))
+        }
+    }
+    
+    /// Acknowledge the messages of a processed batch, including rejected ones
+    ///
+    /// Acknowledgements are sent as the stream waits for the next batch.
+    pub fn commit(&mut self, batch: MqttBatch) -> Result<(), DataError> {
+        #[cfg(feature = "mqtt")]
+        {
+            for message in &batch.messages {
+                self.client.ack(message).map_err(mqtt_error)?;
+            }
+            
+            Ok(())
+        }
+        
+        #[cfg(not(feature = "mqtt"))]
+        {
+            let _ = batch;
+            Err(DataError::NotSupported("MQTT support not enabled".to_string()))
+        }
+    }
+}
+
+/// Decode a JSON object message into a row of the schema
+fn decode_message(payload: &[u8], schema: &Schema) -> Result<Row, DataError> {
+    let json: JsonValue = serde_json::from_slice(payload)
+        .map_err(|e| DataError::ParseError(e.to_string()))?;
+    
+    let obj = json.as_object()
+        .ok_or_else(|| DataError::ParseError("Message is not a JSON object".to_string()))?;
+    
+    let values = schema.fields.iter()
+        .map(|field| {
+            let value = match (obj.get(&field.name).map(JsonSource::json_to_value), &field.data_type) {
+                (None, _) => Value::Null,
+                // Whole numbers are valid floats
+                (Some(Value::Integer(i)), DataType::Float | DataType::Float32) => Value::Float(i as f64),
+                (Some(value), _) => value,
+            };
+            
+            if !field.nullable && matches!(value, Value::Null) {
+                return Err(DataError::ValidationError(format!("Field '{}' is not nullable", field.name)));
+            }
+            
+            SchemaValidator::validate_value(&value, &field.data_type)?;
+            Ok(value)
+        })
+        .collect::<Result<Vec<_>, DataError>>()?;
+    
+    Ok(Row::new(values))
+}
+
+/// Convert an MQTT client error into a data error
+#[cfg(feature = "mqtt")]
+fn mqtt_error<E: std::fmt::Display>(err: E) -> DataError {
+    DataError::IoError(std::io::Error::new(std::io::ErrorKind::Other, err.to_string()))
+}
@@ -0,0 +1,462 @@
+// MessagePack data source and sink implementation
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use super::{
+    DataError, DataSet, DataSink, DataSource, DataType, Field, Row, Schema, SinkType, SourceType, Value, format_date,
+    format_time, format_timestamp, parse_decimal_value, parse_temporal_value,
+};
+
+/// Content type of MessagePack bodies
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// MessagePack data source
+///
+/// Reads datasets written by `MsgPackSink`: a map holding the `schema` as
+/// an array of `{name, type, nullable}` maps and the `data` as an array of
+/// rows, each an array of values in column order. Values are decoded by
+/// their column type, so points are `[lat, lon]` arrays and UUIDs 16-byte
+/// binaries.
+pub struct MsgPackSource {
+    path: String,
+}
+
+impl MsgPackSource {
+    /// Create a new MessagePack data source
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        MsgPackSource {
+            path: path.as_ref().to_string_lossy().to_string(),
+        }
+    }
+    
+    /// Read a dataset from any reader, such as a request body
+    pub fn read_from<R: Read>(&self, mut reader: R) -> Result<DataSet, DataError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).map_err(DataError::IoError)?;
+        
+        let mut decoder = Decoder { bytes: &bytes, pos: 0 };
+        let root = decoder.read()?;
+        
+        if decoder.pos != bytes.len() {
+            return Err(DataError::ParseError("Trailing bytes after MessagePack dataset".to_string()));
+        }
+        
+        let schema_value = root.get("schema")
+            .ok_or_else(|| DataError::ParseError("MessagePack dataset has no schema".to_string()))?;
+        let rows = match root.get("data") {
+            Some(Packed::Array(rows)) => rows.as_slice(),
+            Some(_) => return Err(DataError::ParseError("MessagePack dataset data is not an array".to_string())),
+            None => &[],
+        };
+        
+        let fields = match schema_value {
+            Packed::Array(fields) => fields.iter()
+                .map(|field| {
+                    let name = match field.get("name") {
+                        Some(Packed::Str(name)) => name.clone(),
+                        _ => return Err(DataError::ParseError("Schema field without a name".to_string())),
+                    };
+                    let data_type = match field.get("type") {
+                        Some(Packed::Str(data_type)) => DataType::from_str(data_type)?,
+                        _ => DataType::String,
+                    };
+                    let nullable = !matches!(field.get("nullable"), Some(Packed::Bool(false)));
+                    
+                    Ok(Field::new(name, data_type, nullable))
+                })
+                .collect::<Result<Vec<_>, DataError>>()?,
+            _ => return Err(DataError::ParseError("MessagePack dataset schema is not an array".to_string())),
+        };
+        
+        let mut dataset = DataSet::new(Schema::new(fields));
+        
+        for (row_idx, row) in rows.iter().enumerate() {
+            let values = match row {
+                Packed::Array(values) if values.len() == dataset.schema.fields.len() => values,
+                _ => return Err(DataError::ParseError(format!(
+                    "Row {} is not an array of {} values", row_idx, dataset.schema.fields.len()
+                ))),
+            };
+            
+            let values = values.iter().zip(&dataset.schema.fields)
+                .map(|(value, field)| value.to_value(&field.data_type).map_err(|e| DataError::ParseError(
+                    format!("Row {}, column '{}': {}", row_idx, field.name, e)
+                )))
+                .collect::<Result<Vec<_>, DataError>>()?;
+            
+            dataset.add_row(Row::new(values))?;
+        }
+        
+        // Add metadata
+        dataset.metadata.add("source".to_string(), "msgpack".to_string());
+        dataset.metadata.add("path".to_string(), self.path.clone());
+        
+        Ok(dataset)
+    }
+}
+
+impl DataSource for MsgPackSource {
+    fn read(&self) -> Result<DataSet, DataError> {
+        let file = File::open(&self.path).map_err(DataError::IoError)?;
+        self.read_from(BufReader::new(file))
+    }
+    
+    fn name(&self) -> &str {
+        &self.path
+    }
+    
+    fn source_type(&self) -> SourceType {
+        SourceType::File
+    }
+}
+
+/// MessagePack data sink
+///
+/// Writes the layout `MsgPackSource` reads. Column names are written once
+/// and integers take the fewest bytes that hold them, so bodies are a
+/// fraction of the size of the JSON row format.
+pub struct MsgPackSink {
+    path: String,
+}
+
+impl MsgPackSink {
+    /// Create a new MessagePack data sink
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        MsgPackSink {
+            path: path.as_ref().to_string_lossy().to_string(),
+        }
+    }
+    
+    /// Encode a dataset into bytes
+    pub fn to_bytes(data: &DataSet) -> Vec<u8> {
+        let mut out = Vec::new();
+        
+        write_map_len(&mut out, 2);
+        write_str(&mut out, "schema");
+        write_array_len(&mut out, data.schema.fields.len());
+        
+        for field in &data.schema.fields {
+            write_map_len(&mut out, 3);
+            write_str(&mut out, "name");
+            write_str(&mut out, &field.name);
+            write_str(&mut out, "type");
+            write_str(&mut out, &field.data_type.name());
+            write_str(&mut out, "nullable");
+            write_bool(&mut out, field.nullable);
+        }
+        
+        write_str(&mut out, "data");
+        write_array_len(&mut out, data.data.len());
+        
+        for row in &data.data {
+            write_array_len(&mut out, row.values.len());
+            for value in &row.values {
+                write_value(&mut out, value);
+            }
+        }
+        
+        out
+    }
+    
+    /// Write a dataset to any writer
+    pub fn write_to<W: Write>(&self, data: &DataSet, mut writer: W) -> Result<(), DataError> {
+        writer.write_all(&Self::to_bytes(data)).map_err(DataError::IoError)?;
+        writer.flush().map_err(DataError::IoError)
+    }
+}
+
+impl DataSink for MsgPackSink {
+    fn write(&self, data: &DataSet) -> Result<(), DataError> {
+        let file = File::create(&self.path).map_err(DataError::IoError)?;
+        self.write_to(data, BufWriter::new(file))
+    }
+    
+    fn name(&self) -> &str {
+        &self.path
+    }
+    
+    fn sink_type(&self) -> SinkType {
+        SinkType::File
+    }
+}
+
+/// Write a value
+fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => out.push(0xc0),
+        Value::Boolean(b) => write_bool(out, *b),
+        Value::Integer(i) => write_int(out, *i),
+        Value::Float(f) => {
+            out.push(0xcb);
+            out.extend_from_slice(&f.to_be_bytes());
+        },
+        Value::String(s) => write_str(out, s),
+        Value::Binary(b) => write_bin(out, b),
+        Value::Point(lat, lon) => {
+            write_array_len(out, 2);
+            write_value(out, &Value::Float(*lat));
+            write_value(out, &Value::Float(*lon));
+        },
+        Value::Uuid(uuid) => write_bin(out, &uuid.to_be_bytes()),
+        // Temporal values are written as their ISO 8601 text
+        Value::Date(date) => write_str(out, &format_date(date)),
+        Value::Timestamp(timestamp) => write_str(out, &format_timestamp(timestamp)),
+        Value::Time(time) => write_str(out, &format_time(time)),
+        // Decimals are written as their text so no digits are lost
+        Value::Decimal(d) => write_str(out, &d.to_string()),
+        Value::Array(values) => {
+            write_array_len(out, values.len());
+            for value in values {
+                write_value(out, value);
+            }
+        },
+        Value::Map(map) => {
+            write_map_len(out, map.len());
+            for (key, value) in map {
+                write_str(out, key);
+                write_value(out, value);
+            }
+        },
+    }
+}
+
+/// Write a boolean
+fn write_bool(out: &mut Vec<u8>, b: bool) {
+    out.push(if b { 0xc3 } else { 0xc2 });
+}
+
+/// Write an integer in the smallest encoding that holds it
+fn write_int(out: &mut Vec<u8>, i: i64) {
+    if (0..=0x7f).contains(&i) || (-32..0).contains(&i) {
+        // Positive and negative fixints
+        out.push(i as i8 as u8);
+    } else if let Ok(i) = i8::try_from(i) {
+        out.push(0xd0);
+        out.push(i as u8);
+    } else if let Ok(i) = i16::try_from(i) {
+        out.push(0xd1);
+        out.extend_from_slice(&i.to_be_bytes());
+    } else if let Ok(i) = i32::try_from(i) {
+        out.push(0xd2);
+        out.extend_from_slice(&i.to_be_bytes());
+    } else {
+        out.push(0xd3);
+        out.extend_from_slice(&i.to_be_bytes());
+    }
+}
+
+/// Write a length prefix, picking the fix, 8, 16 or 32-bit form
+fn write_len(out: &mut Vec<u8>, len: usize, fix: Option<(u8, usize)>, markers: [Option<u8>; 3]) {
+    match fix {
+        Some((prefix, max)) if len <= max => out.push(prefix | len as u8),
+        _ => match markers {
+            [Some(marker), _, _] if len <= u8::MAX as usize => {
+                out.push(marker);
+                out.push(len as u8);
+            },
+            [_, Some(marker), _] if len <= u16::MAX as usize => {
+                out.push(marker);
+                out.extend_from_slice(&(len as u16).to_be_bytes());
+            },
+            [_, _, Some(marker)] => {
+                out.push(marker);
+                out.extend_from_slice(&(len as u32).to_be_bytes());
+            },
+            _ => unreachable!(),
+        },
+    }
+}
+
+/// Write a string with its length
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    write_len(out, s.len(), Some((0xa0, 31)), [Some(0xd9), Some(0xda), Some(0xdb)]);
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// Write bytes with their length
+fn write_bin(out: &mut Vec<u8>, b: &[u8]) {
+    write_len(out, b.len(), None, [Some(0xc4), Some(0xc5), Some(0xc6)]);
+    out.extend_from_slice(b);
+}
+
+/// Write the header of an array
+fn write_array_len(out: &mut Vec<u8>, len: usize) {
+    write_len(out, len, Some((0x90, 15)), [None, Some(0xdc), Some(0xdd)]);
+}
+
+/// Write the header of a map
+fn write_map_len(out: &mut Vec<u8>, len: usize) {
+    write_len(out, len, Some((0x80, 15)), [None, Some(0xde), Some(0xdf)]);
+}
+
+/// Decoded MessagePack value, before it is converted by column type
+#[derive(Debug, Clone, PartialEq)]
+enum Packed {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Str(String),
+    Bin(Vec<u8>),
+    Array(Vec<Packed>),
+    Map(Vec<(Packed, Packed)>),
+}
+
+impl Packed {
+    /// Look up a string key of a map
+    fn get(&self, key: &str) -> Option<&Packed> {
+        match self {
+            Packed::Map(entries) => entries.iter()
+                .find(|(k, _)| matches!(k, Packed::Str(k) if k == key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+    
+    /// Convert to a value of a column type
+    fn to_value(&self, data_type: &DataType) -> Result<Value, String> {
+        let value = match (self, data_type) {
+            (Packed::Nil, _) => Value::Null,
+            (Packed::Bool(b), DataType::Boolean) => Value::Boolean(*b),
+            (Packed::Int(i), t) if t.is_integer() => Value::Integer(*i),
+            (Packed::UInt(u), t) if t.is_integer() => Value::Integer(
+                i64::try_from(*u).map_err(|_| format!("{} is out of range", u))?
+            ),
+            (Packed::Float(f), t) if t.is_float() => Value::Float(*f),
+            (Packed::Int(i), t) if t.is_float() => Value::Float(*i as f64),
+            (Packed::UInt(u), t) if t.is_float() => Value::Float(*u as f64),
+            (Packed::Str(s), DataType::String) => Value::String(s.clone()),
+            (Packed::Str(s), DataType::Date | DataType::Timestamp | DataType::Time) => {
+                parse_temporal_value(Value::String(s.clone()), data_type).map_err(|e| e.to_string())?
+            },
+            (Packed::Str(s), DataType::Decimal(_, _)) => {
+                parse_decimal_value(Value::String(s.clone()), data_type).map_err(|e| e.to_string())?
+            },
+            (Packed::Bin(b), DataType::Binary) => Value::Binary(b.clone()),
+            (Packed::Array(coords), DataType::Point) if coords.len() == 2 => {
+                match (coords[0].to_value(&DataType::Float)?, coords[1].to_value(&DataType::Float)?) {
+                    (Value::Float(lat), Value::Float(lon)) => Value::Point(lat, lon),
+                    _ => return Err("point coordinates are not numbers".to_string()),
+                }
+            },
+            (Packed::Bin(b), DataType::Uuid) if b.len() == 16 => {
+                let bytes: [u8; 16] = b.as_slice().try_into().unwrap();
+                Value::Uuid(u128::from_be_bytes(bytes))
+            },
+            (Packed::Array(values), DataType::Array(inner)) => Value::Array(
+                values.iter().map(|value| value.to_value(inner)).collect::<Result<Vec<_>, String>>()?
+            ),
+            (Packed::Map(entries), DataType::Map(inner)) => Value::Map(
+                entries.iter()
+                    .map(|(key, value)| match key {
+                        Packed::Str(key) => Ok((key.clone(), value.to_value(inner)?)),
+                        _ => Err("map key is not a string".to_string()),
+                    })
+                    .collect::<Result<HashMap<_, _>, String>>()?
+            ),
+            (packed, data_type) => return Err(format!("{:?} is not a {} value", packed, data_type.name())),
+        };
+        
+        Ok(value)
+    }
+}
+
+/// Reader of MessagePack values from a byte slice
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    /// Take the next bytes
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DataError> {
+        let end = self.pos.checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| DataError::ParseError("Truncated MessagePack data".to_string()))?;
+        
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+    
+    /// Take a big-endian unsigned integer of 1, 2, 4 or 8 bytes
+    fn uint(&mut self, len: usize) -> Result<u64, DataError> {
+        Ok(self.take(len)?.iter().fold(0u64, |n, &b| (n << 8) | b as u64))
+    }
+    
+    /// Read the next value
+    fn read(&mut self) -> Result<Packed, DataError> {
+        let marker = self.take(1)?[0];
+        
+        let value = match marker {
+            0x00..=0x7f => Packed::Int(marker as i64),
+            0x80..=0x8f => self.map((marker & 0x0f) as usize)?,
+            0x90..=0x9f => self.array((marker & 0x0f) as usize)?,
+            0xa0..=0xbf => self.string((marker & 0x1f) as usize)?,
+            0xc0 => Packed::Nil,
+            0xc2 => Packed::Bool(false),
+            0xc3 => Packed::Bool(true),
+            0xc4 | 0xc5 | 0xc6 => {
+                let len = self.uint(1 << (marker - 0xc4))? as usize;
+                Packed::Bin(self.take(len)?.to_vec())
+            },
+            0xca => Packed::Float(f32::from_bits(self.uint(4)? as u32) as f64),
+            0xcb => Packed::Float(f64::from_bits(self.uint(8)?)),
+            0xcc..=0xcf => Packed::UInt(self.uint(1 << (marker - 0xcc))?),
+            0xd0 => Packed::Int(self.uint(1)? as u8 as i8 as i64),
+            0xd1 => Packed::Int(self.uint(2)? as u16 as i16 as i64),
+            0xd2 => Packed::Int(self.uint(4)? as u32 as i32 as i64),
+            0xd3 => Packed::Int(self.uint(8)? as i64),
+            0xd9 | 0xda | 0xdb => {
+                let len = self.uint(1 << (marker - 0xd9))? as usize;
+                self.string(len)?
+            },
+            0xdc | 0xdd => {
+                let len = self.uint(2 << (marker - 0xdc))? as usize;
+                self.array(len)?
+            },
+            0xde | 0xdf => {
+                let len = self.uint(2 << (marker - 0xde))? as usize;
+                self.map(len)?
+            },
+            0xe0..=0xff => Packed::Int(marker as i8 as i64),
+            _ => return Err(DataError::NotSupported(format!(
+                "MessagePack extension type 0x{:02x} is not supported", marker
+            ))),
+        };
+        
+        Ok(value)
+    }
+    
+    /// Read a string of a given length
+    fn string(&mut self, len: usize) -> Result<Packed, DataError> {
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec())
+            .map(Packed::Str)
+            .map_err(|e| DataError::ParseError(e.to_string()))
+    }
+    
+    /// Read an array of a given length
+    fn array(&mut self, len: usize) -> Result<Packed, DataError> {
+        // Every value takes at least a byte, which bounds what a bad length can allocate
+        let mut values = Vec::with_capacity(len.min(self.bytes.len() - self.pos));
+        for _ in 0..len {
+            values.push(self.read()?);
+        }
+        Ok(Packed::Array(values))
+    }
+    
+    /// Read a map of a given length
+    fn map(&mut self, len: usize) -> Result<Packed, DataError> {
+        let mut entries = Vec::with_capacity(len.min(self.bytes.len() - self.pos));
+        for _ in 0..len {
+            entries.push((self.read()?, self.read()?));
+        }
+        Ok(Packed::Map(entries))
+    }
+}
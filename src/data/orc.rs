@@ -0,0 +1,354 @@
+// ORC data source and sink implementation
+// Author: Gabriel Demetrios Lafis
+
+use std::path::Path;
+
+use super::{DataError, DataSet, DataSink, DataSource, SinkType, SourceType};
+#[cfg(feature = "orc")]
+use super::{DataType, Field, Row, Schema, Value, format_date, format_time, format_timestamp};
+
+/// ORC data source
+///
+/// Columns are converted the way `ParquetSource` converts them; dates,
+/// timestamps, decimals and other types without an equivalent are read as
+/// text. Requires the `orc` feature.
+pub struct OrcSource {
+    path: String,
+    limit: Option<usize>,
+}
+
+impl OrcSource {
+    /// Create a new ORC data source
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        OrcSource {
+            path: path.as_ref().to_string_lossy().to_string(),
+            limit: None,
+        }
+    }
+    
+    /// Stop reading after the given number of rows
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+    
+    /// Convert Arrow data type to our data type
+    #[cfg(feature = "orc")]
+    fn convert_arrow_type(arrow_type: &orc_arrow::datatypes::DataType) -> DataType {
+        use orc_arrow::datatypes::DataType as ArrowType;
+        
+        match arrow_type {
+            ArrowType::Boolean => DataType::Boolean,
+            ArrowType::Int8 => DataType::Int8,
+            ArrowType::Int16 => DataType::Int16,
+            ArrowType::Int32 => DataType::Int32,
+            ArrowType::Int64 => DataType::Integer,
+            ArrowType::Float32 => DataType::Float32,
+            ArrowType::Float64 => DataType::Float,
+            ArrowType::Binary | ArrowType::LargeBinary => DataType::Binary,
+            _ => DataType::String, // Read as text
+        }
+    }
+    
+    /// Convert one column of a batch into values
+    #[cfg(feature = "orc")]
+    fn column_values(array: &orc_arrow::array::ArrayRef) -> Result<Vec<Value>, DataError> {
+        use orc_arrow::array::{
+            Array, BinaryArray, BooleanArray, Float32Array, Float64Array, Int16Array, Int32Array,
+            Int64Array, Int8Array, LargeBinaryArray,
+        };
+        use orc_arrow::datatypes::DataType as ArrowType;
+        use orc_arrow::util::display::array_value_to_string;
+        
+        let any = array.as_any();
+        
+        (0..array.len())
+            .map(|i| {
+                if array.is_null(i) {
+                    return Ok(Value::Null);
+                }
+                
+                let value = match array.data_type() {
+                    ArrowType::Boolean => Value::Boolean(any.downcast_ref::<BooleanArray>().unwrap().value(i)),
+                    ArrowType::Int8 => Value::Integer(any.downcast_ref::<Int8Array>().unwrap().value(i) as i64),
+                    ArrowType::Int16 => Value::Integer(any.downcast_ref::<Int16Array>().unwrap().value(i) as i64),
+                    ArrowType::Int32 => Value::Integer(any.downcast_ref::<Int32Array>().unwrap().value(i) as i64),
+                    ArrowType::Int64 => Value::Integer(any.downcast_ref::<Int64Array>().unwrap().value(i)),
+                    ArrowType::Float32 => Value::Float(any.downcast_ref::<Float32Array>().unwrap().value(i) as f64),
+                    ArrowType::Float64 => Value::Float(any.downcast_ref::<Float64Array>().unwrap().value(i)),
+                    ArrowType::Binary => Value::Binary(any.downcast_ref::<BinaryArray>().unwrap().value(i).to_vec()),
+                    ArrowType::LargeBinary => Value::Binary(any.downcast_ref::<LargeBinaryArray>().unwrap().value(i).to_vec()),
+                    _ => Value::String(array_value_to_string(array, i).map_err(|e| DataError::ParseError(e.to_string()))?),
+                };
+                
+                Ok(value)
+            })
+            .collect()
+    }
+}
+
+impl DataSource for OrcSource {
+    fn read(&self) -> Result<DataSet, DataError> {
+        #[cfg(feature = "orc")]
+        {
+            use orc_rust::ArrowReaderBuilder;
+            use std::fs::File;
+            
+            let file = File::open(&self.path).map_err(DataError::IoError)?;
+            let reader = ArrowReaderBuilder::try_new(file)
+                .map_err(|e| DataError::ParseError(e.to_string()))?
+                .build();
+            
+            // Convert Arrow schema to our schema
+            let arrow_schema = reader.schema();
+            let fields: Vec<Field> = arrow_schema.fields().iter()
+                .map(|field| {
+                    Field::new(
+                        field.name().clone(),
+                        Self::convert_arrow_type(field.data_type()),
+                        field.is_nullable(),
+                    )
+                })
+                .collect();
+            
+            let mut dataset = DataSet::new(Schema::new(fields));
+            
+            for batch_result in reader {
+                if self.limit.map_or(false, |limit| dataset.len() >= limit) {
+                    break;
+                }
+                
+                let batch = batch_result.map_err(|e| DataError::ParseError(e.to_string()))?;
+                let columns = batch.columns().iter()
+                    .map(Self::column_values)
+                    .collect::<Result<Vec<_>, DataError>>()?;
+                
+                for row_idx in 0..batch.num_rows() {
+                    let values = columns.iter().map(|column| column[row_idx].clone()).collect();
+                    dataset.add_row(Row::new(values))?;
+                }
+            }
+            
+            // Drop rows read past the limit in the last batch
+            if let Some(limit) = self.limit {
+                dataset.data.truncate(limit);
+            }
+            
+            // Add metadata
+            dataset.metadata.add("source".to_string(), "orc".to_string());
+            dataset.metadata.add("path".to_string(), self.path.clone());
+            
+            Ok(dataset)
+        }
+        
+        #[cfg(not(feature = "orc"))]
+        {
+            let _ = self.limit;
+            Err(DataError::NotSupported("ORC support not enabled".to_string()))
+        }
+    }
+    
+    fn name(&self) -> &str {
+        &self.path
+    }
+    
+    fn source_type(&self) -> SourceType {
+        SourceType::File
+    }
+}
+
+/// ORC data sink
+///
+/// Columns are written with the Arrow types `ParquetSink` uses, except that
+/// binary columns keep their bytes, UUIDs are written as 16-byte binary,
+/// which reads back as binary, dates, timestamps and times are written as
+/// ISO 8601 text and decimals as their digits in text. Array and map columns
+/// are not supported.
+/// Requires the `orc` feature.
+pub struct OrcSink {
+    path: String,
+}
+
+impl OrcSink {
+    /// Create a new ORC data sink
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        OrcSink {
+            path: path.as_ref().to_string_lossy().to_string(),
+        }
+    }
+    
+    /// Convert our data type to Arrow data type
+    #[cfg(feature = "orc")]
+    fn convert_to_arrow_type(field: &Field) -> Result<orc_arrow::datatypes::DataType, DataError> {
+        use orc_arrow::datatypes::DataType as ArrowType;
+        
+        Ok(match &field.data_type {
+            DataType::Boolean => ArrowType::Boolean,
+            DataType::Int8 => ArrowType::Int8,
+            DataType::Int16 => ArrowType::Int16,
+            DataType::Int32 => ArrowType::Int32,
+            DataType::Integer => ArrowType::Int64,
+            DataType::Float32 => ArrowType::Float32,
+            DataType::Float => ArrowType::Float64,
+            DataType::String | DataType::Point | DataType::Date | DataType::Timestamp | DataType::Time | DataType::Decimal(_, _) => ArrowType::Utf8,
+            DataType::Binary | DataType::Uuid => ArrowType::Binary,
+            DataType::Array(_) | DataType::Map(_) => return Err(DataError::NotSupported(format!(
+                "Column '{}' has type {}, which cannot be written to ORC", field.name, field.data_type.name()
+            ))),
+        })
+    }
+    
+    /// Build the Arrow array of one column
+    #[cfg(feature = "orc")]
+    fn column_array(data: &DataSet, col_idx: usize) -> Result<orc_arrow::array::ArrayRef, DataError> {
+        use orc_arrow::array::{
+            BinaryArray, BooleanArray, Float32Array, Float64Array, Int16Array, Int32Array, Int64Array,
+            Int8Array, StringArray,
+        };
+        use std::sync::Arc;
+        
+        let field = &data.schema.fields[col_idx];
+        let values = data.data.iter().map(|row| &row.values[col_idx]);
+        
+        // Narrow integers must fit rather than be truncated
+        let out_of_range = |n: &i64| DataError::ValidationError(format!(
+            "Value {} out of range for {} column '{}'", n, field.data_type.name(), field.name
+        ));
+        let mismatch = |value: &Value| DataError::ValidationError(format!(
+            "Column '{}' holds {:?}, which is not a {} value", field.name, value, field.data_type.name()
+        ));
+        
+        let array: orc_arrow::array::ArrayRef = match &field.data_type {
+            DataType::Boolean => Arc::new(values
+                .map(|value| match value {
+                    Value::Null => Ok(None),
+                    Value::Boolean(b) => Ok(Some(*b)),
+                    other => Err(mismatch(other)),
+                })
+                .collect::<Result<BooleanArray, DataError>>()?),
+            DataType::Int8 => Arc::new(values
+                .map(|value| match value {
+                    Value::Null => Ok(None),
+                    Value::Integer(n) => i8::try_from(*n).map(Some).map_err(|_| out_of_range(n)),
+                    other => Err(mismatch(other)),
+                })
+                .collect::<Result<Int8Array, DataError>>()?),
+            DataType::Int16 => Arc::new(values
+                .map(|value| match value {
+                    Value::Null => Ok(None),
+                    Value::Integer(n) => i16::try_from(*n).map(Some).map_err(|_| out_of_range(n)),
+                    other => Err(mismatch(other)),
+                })
+                .collect::<Result<Int16Array, DataError>>()?),
+            DataType::Int32 => Arc::new(values
+                .map(|value| match value {
+                    Value::Null => Ok(None),
+                    Value::Integer(n) => i32::try_from(*n).map(Some).map_err(|_| out_of_range(n)),
+                    other => Err(mismatch(other)),
+                })
+                .collect::<Result<Int32Array, DataError>>()?),
+            DataType::Integer => Arc::new(values
+                .map(|value| match value {
+                    Value::Null => Ok(None),
+                    Value::Integer(n) => Ok(Some(*n)),
+                    other => Err(mismatch(other)),
+                })
+                .collect::<Result<Int64Array, DataError>>()?),
+            DataType::Float32 => Arc::new(values
+                .map(|value| match value {
+                    Value::Null => Ok(None),
+                    Value::Float(f) => Ok(Some(*f as f32)),
+                    Value::Integer(n) => Ok(Some(*n as f32)),
+                    other => Err(mismatch(other)),
+                })
+                .collect::<Result<Float32Array, DataError>>()?),
+            DataType::Float => Arc::new(values
+                .map(|value| match value {
+                    Value::Null => Ok(None),
+                    Value::Float(f) => Ok(Some(*f)),
+                    Value::Integer(n) => Ok(Some(*n as f64)),
+                    other => Err(mismatch(other)),
+                })
+                .collect::<Result<Float64Array, DataError>>()?),
+            DataType::String | DataType::Point | DataType::Date | DataType::Timestamp | DataType::Time | DataType::Decimal(_, _) => Arc::new(values
+                .map(|value| match value {
+                    Value::Null => Ok(None),
+                    Value::String(s) => Ok(Some(s.clone())),
+                    Value::Point(lat, lon) => Ok(Some(format!("{},{}", lat, lon))),
+                    Value::Date(date) => Ok(Some(format_date(date))),
+                    Value::Timestamp(timestamp) => Ok(Some(format_timestamp(timestamp))),
+                    Value::Time(time) => Ok(Some(format_time(time))),
+                    Value::Decimal(decimal) => Ok(Some(decimal.to_string())),
+                    other => Err(mismatch(other)),
+                })
+                .collect::<Result<StringArray, DataError>>()?),
+            DataType::Binary | DataType::Uuid => Arc::new(values
+                .map(|value| match value {
+                    Value::Null => Ok(None),
+                    Value::Binary(b) => Ok(Some(b.clone())),
+                    Value::Uuid(uuid) => Ok(Some(uuid.to_be_bytes().to_vec())),
+                    other => Err(mismatch(other)),
+                })
+                .collect::<Result<Vec<_>, DataError>>()?
+                .iter()
+                .map(|bytes| bytes.as_deref())
+                .collect::<BinaryArray>()),
+            DataType::Array(_) | DataType::Map(_) => unreachable!("rejected by convert_to_arrow_type"),
+        };
+        
+        Ok(array)
+    }
+}
+
+impl DataSink for OrcSink {
+    fn write(&self, data: &DataSet) -> Result<(), DataError> {
+        #[cfg(feature = "orc")]
+        {
+            use orc_arrow::datatypes::{Field as ArrowField, Schema as ArrowSchema};
+            use orc_arrow::record_batch::RecordBatch;
+            use orc_rust::ArrowWriterBuilder;
+            use std::fs::File;
+            use std::sync::Arc;
+            
+            // Convert our schema to Arrow schema
+            let arrow_fields = data.schema.fields.iter()
+                .map(|field| Ok(ArrowField::new(&field.name, Self::convert_to_arrow_type(field)?, field.nullable)))
+                .collect::<Result<Vec<_>, DataError>>()?;
+            
+            let arrow_schema = Arc::new(ArrowSchema::new(arrow_fields));
+            
+            let arrays = (0..data.schema.fields.len())
+                .map(|i| Self::column_array(data, i))
+                .collect::<Result<Vec<_>, DataError>>()?;
+            
+            let batch = RecordBatch::try_new(arrow_schema.clone(), arrays)
+                .map_err(|e| DataError::Other(e.to_string()))?;
+            
+            // Write to ORC file
+            let file = File::create(&self.path).map_err(DataError::IoError)?;
+            
+            let mut writer = ArrowWriterBuilder::new(file, arrow_schema)
+                .try_build()
+                .map_err(|e| DataError::Other(e.to_string()))?;
+            
+            writer.write(&batch).map_err(|e| DataError::Other(e.to_string()))?;
+            writer.close().map_err(|e| DataError::Other(e.to_string()))?;
+            
+            Ok(())
+        }
+        
+        #[cfg(not(feature = "orc"))]
+        {
+            let _ = data;
+            Err(DataError::NotSupported("ORC support not enabled".to_string()))
+        }
+    }
+    
+    fn name(&self) -> &str {
+        &self.path
+    }
+    
+    fn sink_type(&self) -> SinkType {
+        SinkType::File
+    }
+}
@@ -1,14 +1,32 @@
 // Parquet data source and sink implementation
 // Author: Gabriel Demetrios Lafis
 
+use std::collections::HashSet;
 use std::path::Path;
 use std::sync::Arc;
 
-use super::{DataError, DataSet, DataSink, DataSource, Field, Row, Schema, SinkType, SourceType, Value, DataType};
+use super::{
+    ColumnRange, ColumnStatistics, DataError, DataSet, DataSink, DataSource, Field, Row, RowGroupStatistics, Schema,
+    SinkType, SourceType, Value, DataType,
+};
+
+/// Arrow field metadata key holding the type of a column written in another one
+const LOGICAL_TYPE_KEY: &str = "engine.logical_type";
+
+/// Largest share of distinct values for which a string column is dictionary encoded
+const DICTIONARY_MAX_DISTINCT_RATIO: f64 = 0.5;
+
+/// Rows per row group written by default, small enough for range queries to skip most of a large file
+const DEFAULT_ROW_GROUP_SIZE: usize = 64 * 1024;
 
 /// Parquet data source
 pub struct ParquetSource {
     path: String,
+    offset: usize,
+    limit: Option<usize>,
+    columns: Option<Vec<String>>,
+    ranges: Vec<ColumnRange>,
+    mmap: bool,
 }
 
 impl ParquetSource {
@@ -16,9 +34,220 @@ impl ParquetSource {
     pub fn new<P: AsRef<Path>>(path: P) -> Self {
         ParquetSource {
             path: path.as_ref().to_string_lossy().to_string(),
+            offset: 0,
+            limit: None,
+            columns: None,
+            ranges: Vec::new(),
+            mmap: false,
+        }
+    }
+    
+    /// Skip the given number of rows before reading
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+    
+    /// Stop reading after the given number of rows
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+    
+    /// Read only the given columns
+    pub fn with_columns(mut self, columns: Vec<String>) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+    
+    /// Read column chunks from a memory map of the file instead of seeking and buffering
+    pub fn with_mmap(mut self) -> Self {
+        self.mmap = true;
+        self
+    }
+    
+    /// Skip row groups whose statistics show no rows in the given ranges
+    ///
+    /// Rows of the row groups read are all returned, including those outside
+    /// the ranges, so the filter the ranges come from must still be applied.
+    /// Offsets and limits count only the rows of the row groups read.
+    pub fn with_ranges(mut self, ranges: Vec<ColumnRange>) -> Self {
+        self.ranges = ranges;
+        self
+    }
+    
+    /// Get the statistics of every row group from the file footer
+    pub fn row_group_statistics(&self) -> Result<Vec<RowGroupStatistics>, DataError> {
+        #[cfg(feature = "parquet")]
+        {
+            let file_reader = self.open_reader(&[])?;
+            
+            Ok(file_reader.metadata().row_groups().iter().map(Self::convert_statistics).collect())
+        }
+        
+        #[cfg(not(feature = "parquet"))]
+        {
+            Err(DataError::NotSupported("Parquet support not enabled".to_string()))
+        }
+    }
+    
+    /// Convert the statistics of a row group to ours
+    #[cfg(feature = "parquet")]
+    fn convert_statistics(row_group: &parquet::file::metadata::RowGroupMetaData) -> RowGroupStatistics {
+        use parquet::file::statistics::Statistics;
+        
+        let columns = row_group.columns().iter()
+            .map(|column| {
+                let statistics = column.statistics();
+                
+                let (min, max) = match statistics.filter(|statistics| statistics.has_min_max_set()) {
+                    Some(Statistics::Boolean(s)) => (Value::Boolean(*s.min()), Value::Boolean(*s.max())),
+                    Some(Statistics::Int32(s)) => (Value::Integer(*s.min() as i64), Value::Integer(*s.max() as i64)),
+                    Some(Statistics::Int64(s)) => (Value::Integer(*s.min()), Value::Integer(*s.max())),
+                    Some(Statistics::Float(s)) => (Value::Float(*s.min() as f64), Value::Float(*s.max() as f64)),
+                    Some(Statistics::Double(s)) => (Value::Float(*s.min()), Value::Float(*s.max())),
+                    Some(Statistics::ByteArray(s)) => match (s.min().as_utf8(), s.max().as_utf8()) {
+                        (Ok(min), Ok(max)) => (Value::String(min.to_string()), Value::String(max.to_string())),
+                        _ => (Value::Null, Value::Null),
+                    },
+                    Some(Statistics::FixedLenByteArray(s)) => {
+                        match (<[u8; 16]>::try_from(s.min().data()), <[u8; 16]>::try_from(s.max().data())) {
+                            (Ok(min), Ok(max)) => (Value::Uuid(u128::from_be_bytes(min)), Value::Uuid(u128::from_be_bytes(max))),
+                            _ => (Value::Null, Value::Null),
+                        }
+                    },
+                    _ => (Value::Null, Value::Null),
+                };
+                
+                let known = |value: Value| Some(value).filter(|value| !matches!(value, Value::Null));
+                
+                ColumnStatistics {
+                    column: column.column_path().string(),
+                    min: known(min),
+                    max: known(max),
+                    nulls: statistics.map(|statistics| statistics.null_count()),
+                }
+            })
+            .collect();
+        
+        RowGroupStatistics {
+            rows: row_group.num_rows() as usize,
+            columns,
         }
     }
     
+    /// Open a reader over the file, memory mapped if enabled, reading only
+    /// row groups that may hold rows in the ranges
+    #[cfg(feature = "parquet")]
+    fn open_reader(&self, ranges: &[ColumnRange]) -> Result<Arc<dyn parquet::file::reader::FileReader>, DataError> {
+        use parquet::file::reader::SerializedFileReader;
+        use parquet::file::serialized_reader::{ReadOptions, ReadOptionsBuilder};
+        use std::fs::File;
+        
+        let options = || -> ReadOptions {
+            let ranges = ranges.to_vec();
+            
+            ReadOptionsBuilder::new()
+                .with_predicate(Box::new(move |row_group, _| {
+                    ranges.is_empty() || Self::convert_statistics(row_group).may_match(&ranges)
+                }))
+                .build()
+        };
+        
+        #[cfg(feature = "mmap")]
+        {
+            if self.mmap {
+                let file = super::MappedFile::open(&self.path)?;
+                let file_reader = SerializedFileReader::new_with_options(file, options())
+                    .map_err(|e| DataError::ParseError(e.to_string()))?;
+                
+                return Ok(Arc::new(file_reader));
+            }
+        }
+        
+        #[cfg(not(feature = "mmap"))]
+        {
+            if self.mmap {
+                return Err(DataError::NotSupported("Memory-mapped reading not enabled".to_string()));
+            }
+        }
+        
+        let file = File::open(&self.path).map_err(DataError::IoError)?;
+        let file_reader = SerializedFileReader::new_with_options(file, options())
+            .map_err(|e| DataError::ParseError(e.to_string()))?;
+        
+        Ok(Arc::new(file_reader))
+    }
+    
+    /// Count rows and columns from the file footer
+    pub fn count(&self) -> Result<(usize, usize), DataError> {
+        #[cfg(feature = "parquet")]
+        {
+            let file_reader = self.open_reader(&[])?;
+            let metadata = file_reader.metadata().file_metadata();
+            
+            // Count top-level fields, since nested columns span several leaf columns
+            let columns = metadata.schema_descr().root_schema().get_fields().len();
+            
+            Ok((metadata.num_rows() as usize, columns))
+        }
+        
+        #[cfg(not(feature = "parquet"))]
+        {
+            Err(DataError::NotSupported("Parquet support not enabled".to_string()))
+        }
+    }
+    
+    /// Read the schema from the file footer, without reading any rows
+    pub fn schema(&self) -> Result<Schema, DataError> {
+        #[cfg(feature = "parquet")]
+        {
+            let file_reader = self.open_reader(&[])?;
+            let metadata = file_reader.metadata().file_metadata();
+            let arrow_schema = parquet::arrow::parquet_to_arrow_schema(metadata.schema_descr(), metadata.key_value_metadata())
+                .map_err(|e| DataError::ParseError(e.to_string()))?;
+            
+            let (_, fields) = self.convert_schema(&arrow_schema)?;
+            Ok(Schema::new(fields))
+        }
+        
+        #[cfg(not(feature = "parquet"))]
+        {
+            Err(DataError::NotSupported("Parquet support not enabled".to_string()))
+        }
+    }
+    
+    /// Convert the projected fields of an Arrow schema, with their indices
+    #[cfg(feature = "parquet")]
+    fn convert_schema(&self, arrow_schema: &arrow::datatypes::Schema) -> Result<(Vec<usize>, Vec<Field>), DataError> {
+        // Resolve the projected column indices
+        let selected: Vec<usize> = match &self.columns {
+            Some(columns) => columns.iter()
+                .map(|column| arrow_schema.fields().iter()
+                    .position(|field| field.name() == column)
+                    .ok_or_else(|| DataError::ValidationError(
+                        format!("Column '{}' not found", column)
+                    )))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => (0..arrow_schema.fields().len()).collect(),
+        };
+        
+        // Convert Arrow schema to our schema, restoring the types recorded by the writer
+        let fields = selected.iter()
+            .map(|&i| &arrow_schema.fields()[i])
+            .map(|field| {
+                let data_type = field.metadata().as_ref()
+                    .and_then(|metadata| metadata.get(LOGICAL_TYPE_KEY))
+                    .and_then(|name| DataType::from_str(name).ok())
+                    .unwrap_or_else(|| Self::convert_arrow_type(field.data_type()));
+                
+                Field::new(field.name().clone(), data_type, field.is_nullable())
+            })
+            .collect();
+        
+        Ok((selected, fields))
+    }
+    
     /// Convert Arrow data type to our data type
     #[cfg(feature = "parquet")]
     fn convert_arrow_type(arrow_type: &arrow::datatypes::DataType) -> DataType {
@@ -26,18 +255,178 @@ impl ParquetSource {
         
         match arrow_type {
             ArrowType::Boolean => DataType::Boolean,
-            ArrowType::Int8 | ArrowType::Int16 | ArrowType::Int32 | ArrowType::Int64 |
-            ArrowType::UInt8 | ArrowType::UInt16 | ArrowType::UInt32 | ArrowType::UInt64 => DataType::Integer,
-            ArrowType::Float16 | ArrowType::Float32 | ArrowType::Float64 => DataType::Float,
+            // Unsigned types take the next signed width that holds them
+            ArrowType::Int8 => DataType::Int8,
+            ArrowType::Int16 | ArrowType::UInt8 => DataType::Int16,
+            ArrowType::Int32 | ArrowType::UInt16 => DataType::Int32,
+            ArrowType::Int64 | ArrowType::UInt32 | ArrowType::UInt64 => DataType::Integer,
+            ArrowType::Float16 | ArrowType::Float32 => DataType::Float32,
+            ArrowType::Float64 => DataType::Float,
             ArrowType::Utf8 | ArrowType::LargeUtf8 => DataType::String,
             ArrowType::Binary | ArrowType::LargeBinary => DataType::Binary,
-            ArrowType::List(_) | ArrowType::LargeList(_) | ArrowType::FixedSizeList(_, _) => {
-                DataType::Array(Box::new(DataType::String)) // Simplified
+            ArrowType::FixedSizeBinary(16) => DataType::Uuid,
+            ArrowType::FixedSizeBinary(_) => DataType::Binary,
+            ArrowType::Date32 | ArrowType::Date64 => DataType::Date,
+            // Timestamps without a zone are taken as UTC
+            ArrowType::Timestamp(_, _) => DataType::Timestamp,
+            ArrowType::Time32(_) | ArrowType::Time64(_) => DataType::Time,
+            ArrowType::Decimal(precision, scale) => DataType::Decimal(*precision as u8, *scale as u8),
+            ArrowType::List(item) | ArrowType::LargeList(item) | ArrowType::FixedSizeList(item, _) => {
+                DataType::Array(Box::new(Self::convert_arrow_type(item.data_type())))
             },
-            ArrowType::Struct(_) | ArrowType::Map(_, _) => {
-                DataType::Map(Box::new(DataType::String)) // Simplified
+            // Structs become maps of their fields, typed if every field has the same type
+            ArrowType::Struct(fields) => {
+                let mut types = fields.iter().map(|field| Self::convert_arrow_type(field.data_type()));
+                let first = types.next().unwrap_or(DataType::String);
+                let value = if types.all(|data_type| data_type == first) { first } else { DataType::String };
+                DataType::Map(Box::new(value))
             },
-            _ => DataType::String, // Default for other types
+            ArrowType::Map(entries, _) => match entries.data_type() {
+                ArrowType::Struct(fields) if fields.len() == 2 => {
+                    DataType::Map(Box::new(Self::convert_arrow_type(fields[1].data_type())))
+                },
+                _ => DataType::Map(Box::new(DataType::String)),
+            },
+            // Other types are read as their text
+            _ => DataType::String,
+        }
+    }
+    
+    /// Convert a value of an Arrow array to our value of the given type
+    ///
+    /// The type guides values written in a different representation, such as
+    /// points written as structs of their coordinates.
+    #[cfg(feature = "parquet")]
+    fn convert_arrow_value(array: &dyn arrow::array::Array, row: usize, data_type: &DataType) -> Value {
+        use arrow::array::{
+            BinaryArray, BooleanArray, Date32Array, Date64Array, DecimalArray, FixedSizeBinaryArray,
+            FixedSizeListArray, Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array,
+            LargeBinaryArray, LargeListArray, LargeStringArray, ListArray, MapArray, StringArray, StructArray,
+            Time32MillisecondArray, Time32SecondArray, Time64MicrosecondArray, Time64NanosecondArray,
+            TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray, TimestampSecondArray,
+            UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+        };
+        use arrow::datatypes::{DataType as ArrowType, TimeUnit};
+        
+        if array.is_null(row) {
+            return Value::Null;
+        }
+        
+        macro_rules! value {
+            ($array:ty) => {
+                array.as_any().downcast_ref::<$array>().unwrap().value(row)
+            };
+        }
+        
+        // Convert the elements of a nested array, typed by the element type if known
+        let elements = |values: arrow::array::ArrayRef, item: &arrow::datatypes::Field| {
+            let element = match data_type {
+                DataType::Array(element) => (**element).clone(),
+                _ => Self::convert_arrow_type(item.data_type()),
+            };
+            
+            Value::Array((0..values.len())
+                .map(|i| Self::convert_arrow_value(values.as_ref(), i, &element))
+                .collect())
+        };
+        
+        let map_value = |field: &arrow::datatypes::Field| match data_type {
+            DataType::Map(value) => (**value).clone(),
+            _ => Self::convert_arrow_type(field.data_type()),
+        };
+        
+        match array.data_type() {
+            ArrowType::Boolean => Value::Boolean(value!(BooleanArray)),
+            ArrowType::Int8 => Value::Integer(value!(Int8Array) as i64),
+            ArrowType::Int16 => Value::Integer(value!(Int16Array) as i64),
+            ArrowType::Int32 => Value::Integer(value!(Int32Array) as i64),
+            ArrowType::Int64 => Value::Integer(value!(Int64Array)),
+            ArrowType::UInt8 => Value::Integer(value!(UInt8Array) as i64),
+            ArrowType::UInt16 => Value::Integer(value!(UInt16Array) as i64),
+            ArrowType::UInt32 => Value::Integer(value!(UInt32Array) as i64),
+            // Values past i64::MAX have no exact integer representation
+            ArrowType::UInt64 => i64::try_from(value!(UInt64Array)).map(Value::Integer).unwrap_or(Value::Null),
+            ArrowType::Float32 => Value::Float(value!(Float32Array) as f64),
+            ArrowType::Float64 => Value::Float(value!(Float64Array)),
+            ArrowType::Utf8 => Value::String(value!(StringArray).to_string()),
+            ArrowType::LargeUtf8 => Value::String(value!(LargeStringArray).to_string()),
+            ArrowType::Binary => Value::Binary(value!(BinaryArray).to_vec()),
+            ArrowType::LargeBinary => Value::Binary(value!(LargeBinaryArray).to_vec()),
+            ArrowType::FixedSizeBinary(16) if *data_type != DataType::Binary => {
+                let bytes: [u8; 16] = value!(FixedSizeBinaryArray).try_into().unwrap();
+                Value::Uuid(u128::from_be_bytes(bytes))
+            },
+            ArrowType::FixedSizeBinary(_) => Value::Binary(value!(FixedSizeBinaryArray).to_vec()),
+            ArrowType::Date32 => array.as_any().downcast_ref::<Date32Array>().unwrap().value_as_date(row)
+                .map_or(Value::Null, Value::Date),
+            ArrowType::Date64 => array.as_any().downcast_ref::<Date64Array>().unwrap().value_as_date(row)
+                .map_or(Value::Null, Value::Date),
+            ArrowType::Timestamp(unit, _) => {
+                let datetime = match unit {
+                    TimeUnit::Second => array.as_any().downcast_ref::<TimestampSecondArray>().unwrap().value_as_datetime(row),
+                    TimeUnit::Millisecond => array.as_any().downcast_ref::<TimestampMillisecondArray>().unwrap().value_as_datetime(row),
+                    TimeUnit::Microsecond => array.as_any().downcast_ref::<TimestampMicrosecondArray>().unwrap().value_as_datetime(row),
+                    TimeUnit::Nanosecond => array.as_any().downcast_ref::<TimestampNanosecondArray>().unwrap().value_as_datetime(row),
+                };
+                
+                // Zoned timestamps are stored in UTC
+                datetime.map_or(Value::Null, |datetime| Value::Timestamp(datetime.and_utc()))
+            },
+            ArrowType::Time32(unit) | ArrowType::Time64(unit) => {
+                let time = match unit {
+                    TimeUnit::Second => array.as_any().downcast_ref::<Time32SecondArray>().unwrap().value_as_time(row),
+                    TimeUnit::Millisecond => array.as_any().downcast_ref::<Time32MillisecondArray>().unwrap().value_as_time(row),
+                    TimeUnit::Microsecond => array.as_any().downcast_ref::<Time64MicrosecondArray>().unwrap().value_as_time(row),
+                    TimeUnit::Nanosecond => array.as_any().downcast_ref::<Time64NanosecondArray>().unwrap().value_as_time(row),
+                };
+                
+                time.map_or(Value::Null, Value::Time)
+            },
+            ArrowType::Decimal(_, scale) => Value::Decimal(super::Decimal::new(value!(DecimalArray), *scale as u32)),
+            ArrowType::List(item) => elements(value!(ListArray), item),
+            ArrowType::LargeList(item) => elements(value!(LargeListArray), item),
+            ArrowType::FixedSizeList(item, _) => elements(value!(FixedSizeListArray), item),
+            ArrowType::Struct(fields) => {
+                let array = array.as_any().downcast_ref::<StructArray>().unwrap();
+                
+                if *data_type == DataType::Point {
+                    let coordinate = |i: usize| match Self::convert_arrow_value(array.column(i).as_ref(), row, &DataType::Float) {
+                        Value::Float(f) => Some(f),
+                        _ => None,
+                    };
+                    
+                    return match (coordinate(0), coordinate(1)) {
+                        (Some(lat), Some(lon)) => Value::Point(lat, lon),
+                        _ => Value::Null,
+                    };
+                }
+                
+                Value::Map(fields.iter().enumerate()
+                    .map(|(i, field)| {
+                        let value = Self::convert_arrow_value(array.column(i).as_ref(), row, &map_value(field));
+                        (field.name().clone(), value)
+                    })
+                    .collect())
+            },
+            ArrowType::Map(_, _) => {
+                let entries = value!(MapArray);
+                let entries = entries.as_any().downcast_ref::<StructArray>().unwrap();
+                let (keys, values) = (entries.column(0), entries.column(1));
+                let value_type = map_value(&entries.fields()[1]);
+                
+                Value::Map((0..entries.len())
+                    .filter_map(|i| {
+                        let key = match Self::convert_arrow_value(keys.as_ref(), i, &DataType::String) {
+                            Value::String(key) => key,
+                            Value::Null => return None,
+                            other => super::JsonSink::value_to_json(&other).to_string(),
+                        };
+                        
+                        Some((key, Self::convert_arrow_value(values.as_ref(), i, &value_type)))
+                    })
+                    .collect())
+            },
+            _ => Value::Null,
         }
     }
 }
@@ -46,89 +435,31 @@ impl DataSource for ParquetSource {
     fn read(&self) -> Result<DataSet, DataError> {
         #[cfg(feature = "parquet")]
         {
-            use arrow::array::{Array, BooleanArray, Float64Array, Int64Array, StringArray};
+            use arrow::record_batch::RecordBatchReader;
             use parquet::arrow::arrow_reader::ParquetRecordBatchReader;
-            use parquet::file::reader::SerializedFileReader;
-            use std::fs::File;
             
-            let file = File::open(&self.path).map_err(DataError::IoError)?;
-            let file_reader = SerializedFileReader::new(file)
+            let arrow_reader = ParquetRecordBatchReader::try_new(self.open_reader(&self.ranges)?, 1024)
                 .map_err(|e| DataError::ParseError(e.to_string()))?;
             
-            let mut arrow_reader = ParquetRecordBatchReader::try_new(Arc::new(file_reader), 1024)
-                .map_err(|e| DataError::ParseError(e.to_string()))?;
+            // Get the schema from the reader rather than a batch, since the file
+            // may be empty and every row group may be skipped
+            let arrow_schema = arrow_reader.schema();
             
-            // Get schema from the first batch
-            let first_batch = arrow_reader.next()
-                .ok_or_else(|| DataError::ParseError("Empty Parquet file".to_string()))?
-                .map_err(|e| DataError::ParseError(e.to_string()))?;
-            
-            let arrow_schema = first_batch.schema();
-            
-            // Convert Arrow schema to our schema
-            let fields: Vec<Field> = arrow_schema.fields().iter()
-                .map(|field| {
-                    Field::new(
-                        field.name().clone(),
-                        Self::convert_arrow_type(field.data_type()),
-                        field.is_nullable(),
-                    )
-                })
-                .collect();
+            let (selected, fields) = self.convert_schema(&arrow_schema)?;
             
+            let types: Vec<DataType> = fields.iter().map(|field| field.data_type.clone()).collect();
             let schema = Schema::new(fields);
             let mut dataset = DataSet::new(schema);
             
-            // Process the first batch
-            let mut process_batch = |batch: &arrow::record_batch::RecordBatch| -> Result<(), DataError> {
+            // Convert the rows of a batch, starting at the given row
+            let process_batch = |batch: &arrow::record_batch::RecordBatch, start: usize, dataset: &mut DataSet| -> Result<(), DataError> {
                 let num_rows = batch.num_rows();
                 
-                for row_idx in 0..num_rows {
+                for row_idx in start..num_rows {
                     let mut values = Vec::new();
                     
-                    for (col_idx, field) in batch.schema().fields().iter().enumerate() {
-                        let array = batch.column(col_idx);
-                        
-                        let value = match field.data_type() {
-                            arrow::datatypes::DataType::Boolean => {
-                                let array = array.as_any().downcast_ref::<BooleanArray>().unwrap();
-                                if array.is_null(row_idx) {
-                                    Value::Null
-                                } else {
-                                    Value::Boolean(array.value(row_idx))
-                                }
-                            },
-                            arrow::datatypes::DataType::Int8 | arrow::datatypes::DataType::Int16 |
-                            arrow::datatypes::DataType::Int32 | arrow::datatypes::DataType::Int64 |
-                            arrow::datatypes::DataType::UInt8 | arrow::datatypes::DataType::UInt16 |
-                            arrow::datatypes::DataType::UInt32 | arrow::datatypes::DataType::UInt64 => {
-                                let array = array.as_any().downcast_ref::<Int64Array>().unwrap();
-                                if array.is_null(row_idx) {
-                                    Value::Null
-                                } else {
-                                    Value::Integer(array.value(row_idx))
-                                }
-                            },
-                            arrow::datatypes::DataType::Float32 | arrow::datatypes::DataType::Float64 => {
-                                let array = array.as_any().downcast_ref::<Float64Array>().unwrap();
-                                if array.is_null(row_idx) {
-                                    Value::Null
-                                } else {
-                                    Value::Float(array.value(row_idx))
-                                }
-                            },
-                            arrow::datatypes::DataType::Utf8 | arrow::datatypes::DataType::LargeUtf8 => {
-                                let array = array.as_any().downcast_ref::<StringArray>().unwrap();
-                                if array.is_null(row_idx) {
-                                    Value::Null
-                                } else {
-                                    Value::String(array.value(row_idx).to_string())
-                                }
-                            },
-                            _ => Value::Null, // Simplified for other types
-                        };
-                        
-                        values.push(value);
+                    for (&col_idx, data_type) in selected.iter().zip(&types) {
+                        values.push(Self::convert_arrow_value(batch.column(col_idx).as_ref(), row_idx, data_type));
                     }
                     
                     let row = Row::new(values);
@@ -138,12 +469,28 @@ impl DataSource for ParquetSource {
                 Ok(())
             };
             
-            process_batch(&first_batch)?;
+            let mut to_skip = self.offset;
             
-            // Process remaining batches
-            while let Some(batch_result) = arrow_reader.next() {
+            for batch_result in arrow_reader {
+                if self.limit.map_or(false, |limit| dataset.len() >= limit) {
+                    break;
+                }
+                
                 let batch = batch_result.map_err(|e| DataError::ParseError(e.to_string()))?;
-                process_batch(&batch)?;
+                
+                // Skip whole batches before the offset without converting them
+                if to_skip >= batch.num_rows() {
+                    to_skip -= batch.num_rows();
+                    continue;
+                }
+                
+                process_batch(&batch, to_skip, &mut dataset)?;
+                to_skip = 0;
+            }
+            
+            // Drop rows read past the limit in the last batch
+            if let Some(limit) = self.limit {
+                dataset.data.truncate(limit);
             }
             
             // Add metadata
@@ -172,6 +519,8 @@ impl DataSource for ParquetSource {
 pub struct ParquetSink {
     path: String,
     compression: ParquetCompression,
+    downcast: bool,
+    row_group_size: usize,
 }
 
 /// Parquet compression options
@@ -185,42 +534,374 @@ pub enum ParquetCompression {
     Zstd,
 }
 
+/// How a column is written, chosen from its values
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnEncoding {
+    /// Type the values are written as, never wider than the column's type
+    pub physical_type: DataType,
+    /// Whether the column is dictionary encoded
+    pub dictionary: bool,
+}
+
+impl ColumnEncoding {
+    /// Choose the narrowest encoding holding every value of a column
+    ///
+    /// Integers take the narrowest width holding their range, floats are
+    /// written as 32-bit floats when every value survives the round trip,
+    /// and strings are dictionary encoded only when at most half of them are
+    /// distinct, since larger dictionaries cost more than they save. Other
+    /// columns keep their type and the writer's default dictionary encoding.
+    pub fn analyze(data: &DataSet, column: usize) -> Self {
+        let data_type = &data.schema.fields[column].data_type;
+        let mut values = data.data.iter()
+            .filter_map(|row| row.values.get(column))
+            .filter(|value| !matches!(value, Value::Null));
+        
+        let keep = ColumnEncoding { physical_type: data_type.clone(), dictionary: true };
+        
+        match data_type {
+            _ if data_type.is_integer() => {
+                let mut range: Option<(i64, i64)> = None;
+                
+                for value in values {
+                    match value {
+                        Value::Integer(n) => range = Some(range.map_or((*n, *n), |(min, max)| (min.min(*n), max.max(*n)))),
+                        _ => return keep,
+                    }
+                }
+                
+                let (min, max) = range.unwrap_or((0, 0));
+                let (type_min, type_max) = data_type.integer_range().expect("integer types have a range");
+                
+                let physical_type = [DataType::Int8, DataType::Int16, DataType::Int32, DataType::Integer].into_iter()
+                    .find(|candidate| {
+                        let (candidate_min, candidate_max) = candidate.integer_range().expect("integer types have a range");
+                        candidate_min >= type_min && candidate_max <= type_max && candidate_min <= min && max <= candidate_max
+                    })
+                    .unwrap_or_else(|| data_type.clone());
+                
+                ColumnEncoding { physical_type, ..keep }
+            },
+            DataType::Float => {
+                let exact = values.all(|value| match value {
+                    Value::Float(f) => f.is_nan() || (*f as f32) as f64 == *f,
+                    _ => false,
+                });
+                
+                if exact {
+                    ColumnEncoding { physical_type: DataType::Float32, ..keep }
+                } else {
+                    keep
+                }
+            },
+            DataType::String => {
+                let mut distinct = HashSet::new();
+                let mut count = 0;
+                
+                for value in values {
+                    if let Value::String(s) = value {
+                        distinct.insert(s.as_str());
+                    }
+                    count += 1;
+                }
+                
+                let dictionary = distinct.len() as f64 <= count as f64 * DICTIONARY_MAX_DISTINCT_RATIO;
+                ColumnEncoding { dictionary, ..keep }
+            },
+            _ => keep,
+        }
+    }
+}
+
 impl ParquetSink {
     /// Create a new Parquet data sink
     pub fn new<P: AsRef<Path>>(path: P, compression: ParquetCompression) -> Self {
         ParquetSink {
             path: path.as_ref().to_string_lossy().to_string(),
             compression,
+            downcast: false,
+            row_group_size: DEFAULT_ROW_GROUP_SIZE,
         }
     }
     
+    /// Set the most rows written per row group, 65536 by default
+    ///
+    /// Each row group records the minimum, maximum and null count of every
+    /// column, which readers use to skip row groups outside a range; smaller
+    /// groups skip more precisely on sorted data at some cost in file size.
+    pub fn with_row_group_size(mut self, rows: usize) -> Self {
+        self.row_group_size = rows.max(1);
+        self
+    }
+    
+    /// Write each column in the narrowest type holding its values
+    ///
+    /// Columns are analyzed before writing, as by `ColumnEncoding::analyze`.
+    /// Their own types are kept in the file's schema, so reading the file
+    /// back gives the dataset's schema unchanged.
+    pub fn with_downcasting(mut self) -> Self {
+        self.downcast = true;
+        self
+    }
+    
     /// Convert our data type to Arrow data type
     #[cfg(feature = "parquet")]
     fn convert_to_arrow_type(data_type: &DataType) -> arrow::datatypes::DataType {
-        use arrow::datatypes::DataType as ArrowType;
+        use arrow::datatypes::{DataType as ArrowType, Field as ArrowField, TimeUnit};
         
         match data_type {
             DataType::Boolean => ArrowType::Boolean,
+            DataType::Int8 => ArrowType::Int8,
+            DataType::Int16 => ArrowType::Int16,
+            DataType::Int32 => ArrowType::Int32,
             DataType::Integer => ArrowType::Int64,
+            DataType::Float32 => ArrowType::Float32,
             DataType::Float => ArrowType::Float64,
             DataType::String => ArrowType::Utf8,
             DataType::Binary => ArrowType::Binary,
-            DataType::Array(_) => {
-                ArrowType::List(Arc::new(arrow::datatypes::Field::new(
-                    "item",
-                    ArrowType::Utf8,
-                    true,
-                )))
+            DataType::Point => ArrowType::Struct(vec![
+                ArrowField::new("lat", ArrowType::Float64, false),
+                ArrowField::new("lon", ArrowType::Float64, false),
+            ]),
+            // UUIDs are stored as 16 raw bytes rather than 36-character strings
+            DataType::Uuid => ArrowType::FixedSizeBinary(16),
+            DataType::Date => ArrowType::Date32,
+            DataType::Timestamp => ArrowType::Timestamp(TimeUnit::Microsecond, Some("UTC".to_string())),
+            DataType::Time => ArrowType::Time64(TimeUnit::Microsecond),
+            DataType::Decimal(precision, scale) => ArrowType::Decimal(*precision as usize, *scale as usize),
+            DataType::Array(element) => {
+                ArrowType::List(Box::new(ArrowField::new("item", Self::convert_to_arrow_type(element), true)))
             },
-            DataType::Map(_) => {
-                ArrowType::Struct(vec![
-                    arrow::datatypes::Field::new("key", ArrowType::Utf8, false),
-                    arrow::datatypes::Field::new("value", ArrowType::Utf8, true),
-                ])
+            DataType::Map(value) => {
+                let entries = ArrowType::Struct(vec![
+                    ArrowField::new("key", ArrowType::Utf8, false),
+                    ArrowField::new("value", Self::convert_to_arrow_type(value), true),
+                ]);
+                
+                ArrowType::Map(Box::new(ArrowField::new("entries", entries, false)), false)
             },
         }
     }
     
+    /// Build the Arrow array of a column's values
+    ///
+    /// Arrays and maps are written as Arrow lists and maps of their element
+    /// types, so nested values round trip instead of being flattened to text.
+    #[cfg(feature = "parquet")]
+    fn build_array(values: &[&Value], data_type: &DataType, column: &str) -> Result<arrow::array::ArrayRef, DataError> {
+        use arrow::array::{
+            make_array, ArrayData, BinaryArray, BooleanArray, BooleanBufferBuilder, Date32Array, DecimalBuilder, FixedSizeBinaryArray,
+            Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array, StringArray, StructArray,
+            Time64MicrosecondArray, TimestampMicrosecondArray,
+        };
+        use arrow::datatypes::Field as ArrowField;
+        
+        let mismatch = || DataError::ValidationError(format!(
+            "Column '{}' holds a value that is not of type {}", column, data_type.name()
+        ));
+        
+        // Narrow integers must fit rather than be truncated
+        macro_rules! integers {
+            ($array:ty, $native:ty) => {
+                Arc::new(values.iter()
+                    .map(|value| match value {
+                        Value::Null => Ok(None),
+                        Value::Integer(n) => <$native>::try_from(*n).map(Some).map_err(|_| DataError::ValidationError(format!(
+                            "Value {} out of range for {} column '{}'", n, data_type.name(), column
+                        ))),
+                        _ => Err(mismatch()),
+                    })
+                    .collect::<Result<$array, _>>()?)
+            };
+        }
+        
+        let array: arrow::array::ArrayRef = match data_type {
+            DataType::Boolean => Arc::new(values.iter()
+                .map(|value| match value {
+                    Value::Null => Ok(None),
+                    Value::Boolean(b) => Ok(Some(*b)),
+                    _ => Err(mismatch()),
+                })
+                .collect::<Result<BooleanArray, _>>()?),
+            DataType::Int8 => integers!(Int8Array, i8),
+            DataType::Int16 => integers!(Int16Array, i16),
+            DataType::Int32 => integers!(Int32Array, i32),
+            DataType::Integer => integers!(Int64Array, i64),
+            DataType::Float32 | DataType::Float => {
+                let floats = values.iter()
+                    .map(|value| match value {
+                        Value::Null => Ok(None),
+                        Value::Float(f) => Ok(Some(*f)),
+                        Value::Integer(n) => Ok(Some(*n as f64)),
+                        _ => Err(mismatch()),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                
+                if *data_type == DataType::Float32 {
+                    Arc::new(floats.into_iter().map(|f| f.map(|f| f as f32)).collect::<Float32Array>())
+                } else {
+                    Arc::new(floats.into_iter().collect::<Float64Array>())
+                }
+            },
+            // Other values in string columns are written as their JSON text
+            DataType::String => Arc::new(values.iter()
+                .map(|value| match value {
+                    Value::Null => None,
+                    Value::String(s) => Some(s.clone()),
+                    value => Some(super::JsonSink::value_to_json(value).to_string()),
+                })
+                .collect::<StringArray>()),
+            DataType::Binary => Arc::new(values.iter()
+                .map(|value| match value {
+                    Value::Null => Ok(None),
+                    Value::Binary(bytes) => Ok(Some(bytes.as_slice())),
+                    _ => Err(mismatch()),
+                })
+                .collect::<Result<BinaryArray, _>>()?),
+            DataType::Uuid => {
+                let uuids = values.iter()
+                    .map(|value| match value {
+                        Value::Null => Ok(None),
+                        Value::Uuid(uuid) => Ok(Some(uuid.to_be_bytes())),
+                        _ => Err(DataError::ValidationError(format!("Column '{}' holds a value that is not a UUID", column))),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                
+                Arc::new(FixedSizeBinaryArray::try_from_sparse_iter(uuids.into_iter())
+                    .map_err(|e| DataError::Other(e.to_string()))?)
+            },
+            // Dates are stored as days since the Unix epoch, timestamps and times in microseconds
+            DataType::Date => {
+                let epoch = chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+                
+                Arc::new(values.iter()
+                    .map(|value| match value {
+                        Value::Null => Ok(None),
+                        Value::Date(date) => Ok(Some(date.signed_duration_since(epoch).num_days() as i32)),
+                        _ => Err(mismatch()),
+                    })
+                    .collect::<Result<Date32Array, _>>()?)
+            },
+            DataType::Timestamp => {
+                let timestamps = values.iter()
+                    .map(|value| match value {
+                        Value::Null => Ok(None),
+                        Value::Timestamp(timestamp) => Ok(Some(timestamp.timestamp_micros())),
+                        _ => Err(mismatch()),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                
+                Arc::new(TimestampMicrosecondArray::from_opt_vec(timestamps, Some("UTC".to_string())))
+            },
+            DataType::Time => Arc::new(values.iter()
+                .map(|value| match value {
+                    Value::Null => Ok(None),
+                    Value::Time(time) => Ok((*time - chrono::NaiveTime::MIN).num_microseconds()),
+                    _ => Err(mismatch()),
+                })
+                .collect::<Result<Time64MicrosecondArray, _>>()?),
+            // Decimals are stored as their digits at the column's scale
+            DataType::Decimal(precision, scale) => {
+                let arrow_error = |e: arrow::error::ArrowError| DataError::Other(e.to_string());
+                let mut builder = DecimalBuilder::new(values.len(), *precision as usize, *scale as usize);
+                
+                for value in values {
+                    match value {
+                        Value::Null => builder.append_null().map_err(arrow_error)?,
+                        Value::Decimal(decimal) => {
+                            let fitted = super::fit_decimal(decimal, *precision, *scale)?;
+                            builder.append_value(fitted.unscaled()).map_err(arrow_error)?;
+                        },
+                        _ => return Err(mismatch()),
+                    }
+                }
+                
+                Arc::new(builder.finish())
+            },
+            DataType::Point => {
+                let mut validity = BooleanBufferBuilder::new(values.len());
+                let mut lats = Vec::with_capacity(values.len());
+                let mut lons = Vec::with_capacity(values.len());
+                
+                for value in values {
+                    let (lat, lon) = match value {
+                        Value::Null => (0.0, 0.0),
+                        Value::Point(lat, lon) => (*lat, *lon),
+                        _ => return Err(mismatch()),
+                    };
+                    
+                    validity.append(!matches!(value, Value::Null));
+                    lats.push(lat);
+                    lons.push(lon);
+                }
+                
+                let columns = vec![
+                    (ArrowField::new("lat", arrow::datatypes::DataType::Float64, false), Arc::new(Float64Array::from(lats)) as arrow::array::ArrayRef),
+                    (ArrowField::new("lon", arrow::datatypes::DataType::Float64, false), Arc::new(Float64Array::from(lons)) as arrow::array::ArrayRef),
+                ];
+                
+                Arc::new(StructArray::from((columns, validity.finish())))
+            },
+            DataType::Array(element) => {
+                let (offsets, validity, items) = nested_values(values, |value| match value {
+                    Value::Array(items) => Some(items.iter().collect()),
+                    _ => None,
+                }).ok_or_else(mismatch)?;
+                
+                let items = Self::build_array(&items, element, column)?;
+                
+                let data = ArrayData::builder(Self::convert_to_arrow_type(data_type))
+                    .len(values.len())
+                    .add_buffer(offsets)
+                    .add_child_data(items.data().clone())
+                    .null_bit_buffer(validity)
+                    .build()
+                    .map_err(|e| DataError::Other(e.to_string()))?;
+                
+                make_array(data)
+            },
+            DataType::Map(value_type) => {
+                // Entries are sorted by key so maps are written deterministically
+                let sorted = |entries: &std::collections::HashMap<String, Value>| {
+                    let mut entries: Vec<_> = entries.iter().collect();
+                    entries.sort_by(|a, b| a.0.cmp(b.0));
+                    entries
+                };
+                
+                let (offsets, validity, entry_values) = nested_values(values, |value| match value {
+                    Value::Map(entries) => Some(sorted(entries).into_iter().map(|(_, value)| value).collect()),
+                    _ => None,
+                }).ok_or_else(mismatch)?;
+                
+                let keys: StringArray = values.iter()
+                    .filter_map(|value| match value {
+                        Value::Map(entries) => Some(sorted(entries)),
+                        _ => None,
+                    })
+                    .flatten()
+                    .map(|(key, _)| Some(key.as_str()))
+                    .collect();
+                
+                let entries = StructArray::from(vec![
+                    (ArrowField::new("key", arrow::datatypes::DataType::Utf8, false), Arc::new(keys) as arrow::array::ArrayRef),
+                    (ArrowField::new("value", Self::convert_to_arrow_type(value_type), true), Self::build_array(&entry_values, value_type, column)?),
+                ]);
+                
+                let data = ArrayData::builder(Self::convert_to_arrow_type(data_type))
+                    .len(values.len())
+                    .add_buffer(offsets)
+                    .add_child_data(entries.data().clone())
+                    .null_bit_buffer(validity)
+                    .build()
+                    .map_err(|e| DataError::Other(e.to_string()))?;
+                
+                make_array(data)
+            },
+        };
+        
+        Ok(array)
+    }
+    
     /// Convert compression enum to parquet compression
     #[cfg(feature = "parquet")]
     fn get_compression(&self) -> parquet::basic::Compression {
@@ -241,103 +922,55 @@ impl DataSink for ParquetSink {
     fn write(&self, data: &DataSet) -> Result<(), DataError> {
         #[cfg(feature = "parquet")]
         {
-            use arrow::array::{ArrayRef, BooleanBuilder, Float64Builder, Int64Builder, StringBuilder};
+            use arrow::array::ArrayRef;
             use arrow::datatypes::{Field as ArrowField, Schema as ArrowSchema};
             use arrow::record_batch::RecordBatch;
             use parquet::arrow::ArrowWriter;
             use std::fs::File;
             use std::sync::Arc;
             
-            // Convert our schema to Arrow schema
-            let arrow_fields: Vec<ArrowField> = data.schema.fields.iter()
-                .map(|field| {
-                    ArrowField::new(
+            // Choose the narrowest encoding of each column if enabled
+            let encodings: Option<Vec<ColumnEncoding>> = self.downcast.then(|| {
+                (0..data.schema.fields.len()).map(|i| ColumnEncoding::analyze(data, i)).collect()
+            });
+            
+            let schema = match &encodings {
+                Some(encodings) => Schema::new(data.schema.fields.iter().zip(encodings)
+                    .map(|(field, encoding)| Field { data_type: encoding.physical_type.clone(), ..field.clone() })
+                    .collect()),
+                None => data.schema.clone(),
+            };
+            
+            // Convert our schema to Arrow schema, recording the types of columns that do not read back as written
+            let arrow_fields: Vec<ArrowField> = schema.fields.iter().zip(&data.schema.fields)
+                .map(|(field, logical)| {
+                    let arrow_field = ArrowField::new(
                         &field.name,
                         Self::convert_to_arrow_type(&field.data_type),
                         field.nullable,
-                    )
+                    );
+                    
+                    if ParquetSource::convert_arrow_type(arrow_field.data_type()) == logical.data_type {
+                        arrow_field
+                    } else {
+                        let metadata = [(LOGICAL_TYPE_KEY.to_string(), logical.data_type.name())].into_iter().collect();
+                        arrow_field.with_metadata(Some(metadata))
+                    }
                 })
                 .collect();
             
             let arrow_schema = Arc::new(ArrowSchema::new(arrow_fields));
             
-            // Create array builders for each column
-            let mut builders: Vec<Box<dyn arrow::array::ArrayBuilder>> = data.schema.fields.iter()
-                .map(|field| {
-                    match field.data_type {
-                        DataType::Boolean => Box::new(BooleanBuilder::new()) as Box<dyn arrow::array::ArrayBuilder>,
-                        DataType::Integer => Box::new(Int64Builder::new()) as Box<dyn arrow::array::ArrayBuilder>,
-                        DataType::Float => Box::new(Float64Builder::new()) as Box<dyn arrow::array::ArrayBuilder>,
-                        DataType::String | DataType::Binary | DataType::Array(_) | DataType::Map(_) => {
-                            Box::new(StringBuilder::new()) as Box<dyn arrow::array::ArrayBuilder>
-                        },
-                    }
+            // Build the array of each column
+            let arrays: Vec<ArrayRef> = schema.fields.iter().enumerate()
+                .map(|(i, field)| {
+                    let values: Vec<&Value> = data.data.iter()
+                        .map(|row| row.values.get(i).unwrap_or(&Value::Null))
+                        .collect();
+                    
+                    Self::build_array(&values, &field.data_type, &field.name)
                 })
-                .collect();
-            
-            // Fill builders with data
-            for row in &data.data {
-                for (i, value) in row.values.iter().enumerate() {
-                    match (value, &data.schema.fields[i].data_type) {
-                        (Value::Null, _) => {
-                            match &data.schema.fields[i].data_type {
-                                DataType::Boolean => {
-                                    let builder = builders[i].as_any_mut().downcast_mut::<BooleanBuilder>().unwrap();
-                                    builder.append_null();
-                                },
-                                DataType::Integer => {
-                                    let builder = builders[i].as_any_mut().downcast_mut::<Int64Builder>().unwrap();
-                                    builder.append_null();
-                                },
-                                DataType::Float => {
-                                    let builder = builders[i].as_any_mut().downcast_mut::<Float64Builder>().unwrap();
-                                    builder.append_null();
-                                },
-                                _ => {
-                                    let builder = builders[i].as_any_mut().downcast_mut::<StringBuilder>().unwrap();
-                                    builder.append_null();
-                                },
-                            }
-                        },
-                        (Value::Boolean(b), DataType::Boolean) => {
-                            let builder = builders[i].as_any_mut().downcast_mut::<BooleanBuilder>().unwrap();
-                            builder.append_value(*b);
-                        },
-                        (Value::Integer(n), DataType::Integer) => {
-                            let builder = builders[i].as_any_mut().downcast_mut::<Int64Builder>().unwrap();
-                            builder.append_value(*n);
-                        },
-                        (Value::Float(f), DataType::Float) => {
-                            let builder = builders[i].as_any_mut().downcast_mut::<Float64Builder>().unwrap();
-                            builder.append_value(*f);
-                        },
-                        (Value::String(s), DataType::String) => {
-                            let builder = builders[i].as_any_mut().downcast_mut::<StringBuilder>().unwrap();
-                            builder.append_value(s);
-                        },
-                        // Convert other types to string
-                        (value, _) => {
-                            let builder = builders[i].as_any_mut().downcast_mut::<StringBuilder>().unwrap();
-                            let s = match value {
-                                Value::Boolean(b) => b.to_string(),
-                                Value::Integer(n) => n.to_string(),
-                                Value::Float(f) => f.to_string(),
-                                Value::String(s) => s.clone(),
-                                Value::Binary(_) => "[binary data]".to_string(),
-                                Value::Array(_) => "[array]".to_string(),
-                                Value::Map(_) => "[map]".to_string(),
-                                Value::Null => unreachable!(),
-                            };
-                            builder.append_value(&s);
-                        },
-                    }
-                }
-            }
-            
-            // Finish arrays
-            let arrays: Vec<ArrayRef> = builders.iter_mut()
-                .map(|builder| builder.finish())
-                .collect();
+                .collect::<Result<_, _>>()?;
             
             // Create record batch
             let batch = RecordBatch::try_new(arrow_schema.clone(), arrays)
@@ -346,12 +979,21 @@ impl DataSink for ParquetSink {
             // Write to Parquet file
             let file = File::create(&self.path).map_err(DataError::IoError)?;
             
+            // Record column statistics for each row group and page
+            let mut properties = parquet::file::properties::WriterProperties::builder()
+                .set_compression(self.get_compression())
+                .set_statistics_enabled(true)
+                .set_max_row_group_size(self.row_group_size);
+            
+            for (field, encoding) in schema.fields.iter().zip(encodings.iter().flatten()) {
+                let column = parquet::schema::types::ColumnPath::from(field.name.as_str());
+                properties = properties.set_column_dictionary_enabled(column, encoding.dictionary);
+            }
+            
             let mut writer = ArrowWriter::try_new(
                 file,
                 arrow_schema,
-                Some(parquet::file::properties::WriterProperties::builder()
-                    .set_compression(self.get_compression())
-                    .build()),
+                Some(properties.build()),
             ).map_err(|e| DataError::Other(e.to_string()))?;
             
             writer.write(&batch).map_err(|e| DataError::Other(e.to_string()))?;
@@ -375,3 +1017,27 @@ impl DataSink for ParquetSink {
     }
 }
 
+/// Build the offsets and validity of a list column, and the values of its lists
+///
+/// Returns None if a value is neither null nor a list, as told by `children`.
+#[cfg(feature = "parquet")]
+fn nested_values<'a>(
+    values: &[&'a Value],
+    children: impl Fn(&'a Value) -> Option<Vec<&'a Value>>,
+) -> Option<(arrow::buffer::Buffer, arrow::buffer::Buffer, Vec<&'a Value>)> {
+    let mut offsets = Vec::with_capacity(values.len() + 1);
+    let mut validity = arrow::array::BooleanBufferBuilder::new(values.len());
+    let mut items = Vec::new();
+    offsets.push(0i32);
+    
+    for &value in values {
+        if !matches!(value, Value::Null) {
+            items.extend(children(value)?);
+        }
+        
+        validity.append(!matches!(value, Value::Null));
+        offsets.push(items.len() as i32);
+    }
+    
+    Some((arrow::buffer::Buffer::from_slice_ref(&offsets), validity.finish(), items))
+}
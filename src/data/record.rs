@@ -0,0 +1,193 @@
+// Record-oriented access to rows
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::HashMap;
+
+use super::{DataError, DataSet, DataType, ErrorContext, Field, Row, Schema, Value};
+
+/// A row as a map of field names to values
+pub type Record = HashMap<String, Value>;
+
+/// What to do with record fields the schema does not have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtraFields {
+    /// Fail on the first unknown field
+    Reject,
+    /// Drop unknown fields
+    Ignore,
+    /// Add unknown fields to the schema as nullable fields, typed by their
+    /// first value, with nulls in the rows before them
+    Extend,
+}
+
+impl ExtraFields {
+    /// Parse a policy name: `reject`, `ignore` or `extend`
+    pub fn from_str(s: &str) -> Result<Self, DataError> {
+        match s.trim().to_lowercase().as_str() {
+            "reject" => Ok(ExtraFields::Reject),
+            "ignore" => Ok(ExtraFields::Ignore),
+            "extend" => Ok(ExtraFields::Extend),
+            _ => Err(DataError::ValidationError(format!("Invalid extra fields policy: {}", s))),
+        }
+    }
+}
+
+impl DataSet {
+    /// Build a dataset from records
+    ///
+    /// Missing fields are filled as by `insert_row`, and unknown fields are
+    /// handled by the policy; with `ExtraFields::Extend` the schema may start
+    /// empty and be built from the records.
+    pub fn try_from_records<I, R, K, V>(schema: Schema, records: I, extra: ExtraFields) -> Result<Self, DataError>
+    where
+        I: IntoIterator<Item = R>,
+        R: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        let mut dataset = DataSet::new(schema);
+        
+        for record in records {
+            dataset.push_record_with(record, extra)?;
+        }
+        
+        Ok(dataset)
+    }
+    
+    /// Add a record, rejecting fields the schema does not have
+    pub fn push_record<R, K, V>(&mut self, record: R) -> Result<(), DataError>
+    where
+        R: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        self.push_record_with(record, ExtraFields::Reject)
+    }
+    
+    /// Add a record, handling fields the schema does not have by the policy
+    ///
+    /// Missing fields are filled from field defaults, and rejected if left
+    /// null in a non-nullable field.
+    pub fn push_record_with<R, K, V>(&mut self, record: R, extra: ExtraFields) -> Result<(), DataError>
+    where
+        R: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        let mut values = vec![Value::Null; self.schema.fields.len()];
+        
+        for (name, value) in record {
+            let name = name.into();
+            let value = value.into();
+            
+            let index = match self.schema.fields.iter().position(|field| field.name == name) {
+                Some(index) => index,
+                None => match extra {
+                    ExtraFields::Reject => {
+                        return Err(DataError::ValidationError(format!("Unknown field '{}'", name))
+                            .with_context(ErrorContext::new().column(&name).row(self.data.len())));
+                    },
+                    ExtraFields::Ignore => continue,
+                    ExtraFields::Extend => {
+                        self.schema.fields.push(Field::new(name, value_type(&value), true));
+                        
+                        for row in &mut self.data {
+                            row.values.push(Value::Null);
+                        }
+                        
+                        values.push(Value::Null);
+                        values.len() - 1
+                    },
+                },
+            };
+            
+            values[index] = value;
+        }
+        
+        self.insert_row(Row::new(values))
+    }
+    
+    /// Iterate over the rows as records
+    pub fn records(&self) -> impl Iterator<Item = Record> + '_ {
+        self.data.iter().map(move |row| row.as_record(&self.schema))
+    }
+}
+
+impl Row {
+    /// Get the row as a record of the schema's field names
+    ///
+    /// Null values are kept, so every field of the schema is in the record.
+    pub fn as_record(&self, schema: &Schema) -> Record {
+        schema.fields.iter()
+            .zip(&self.values)
+            .map(|(field, value)| (field.name.clone(), value.clone()))
+            .collect()
+    }
+}
+
+/// Infer the type of a field from its first value
+fn value_type(value: &Value) -> DataType {
+    match value {
+        Value::Null | Value::String(_) => DataType::String,
+        Value::Boolean(_) => DataType::Boolean,
+        Value::Integer(_) => DataType::Integer,
+        Value::Float(_) => DataType::Float,
+        Value::Decimal(d) => d.data_type(),
+        Value::Binary(_) => DataType::Binary,
+        Value::Point(_, _) => DataType::Point,
+        Value::Uuid(_) => DataType::Uuid,
+        Value::Date(_) => DataType::Date,
+        Value::Timestamp(_) => DataType::Timestamp,
+        Value::Time(_) => DataType::Time,
+        Value::Array(values) => {
+            let element = values.iter().find(|value| !matches!(value, Value::Null));
+            DataType::Array(Box::new(element.map_or(DataType::String, value_type)))
+        },
+        Value::Map(entries) => {
+            let value = entries.values().find(|value| !matches!(value, Value::Null));
+            DataType::Map(Box::new(value.map_or(DataType::String, value_type)))
+        },
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Boolean(b)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(n: i32) -> Self {
+        Value::Integer(n as i64)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Integer(n)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Value::Float(f)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
@@ -0,0 +1,74 @@
+// Files downloaded from remote servers
+// Author: Gabriel Demetrios Lafis
+
+use std::io::Read;
+
+use chrono::{DateTime, Utc};
+
+use super::{CsvSource, DataError, DataSet, JsonSource};
+
+/// File on a remote server
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteFile {
+    pub path: String,
+    pub size: Option<u64>,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+/// Split a remote glob pattern such as `/outgoing/orders_*.csv` into its directory and file pattern
+pub(crate) fn split_pattern(pattern: &str) -> (String, String) {
+    match pattern.rsplit_once('/') {
+        Some((dir, file)) => {
+            let dir = if dir.is_empty() { "/".to_string() } else { dir.to_string() };
+            (dir, file.to_string())
+        },
+        None => (".".to_string(), pattern.to_string()),
+    }
+}
+
+/// Convert a file name glob pattern into an anchored regex
+pub(crate) fn glob_regex(pattern: &str) -> Result<regex::Regex, DataError> {
+    let mut regex = String::from("^");
+    
+    for c in pattern.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    
+    regex.push('$');
+    regex::Regex::new(&regex).map_err(|e| DataError::ParseError(e.to_string()))
+}
+
+/// Parse a downloaded file as CSV or JSON by its extension
+pub(crate) fn parse_remote_file<R: Read>(file: &RemoteFile, reader: R, protocol: &str) -> Result<DataSet, DataError> {
+    match file.path.rsplit('.').next().map(|ext| ext.to_lowercase()).as_deref() {
+        Some("csv") => CsvSource::new(&file.path, true, ',').read_from(reader),
+        Some("json") => JsonSource::new(&file.path).read_from(reader),
+        _ => Err(DataError::NotSupported(format!(
+            "Cannot parse {}: only CSV and JSON files are read over {}", file.path, protocol
+        ))),
+    }
+}
+
+/// Append the rows of a downloaded file to those of the files before it
+pub(crate) fn combine_files(combined: Option<DataSet>, data: DataSet, file: &RemoteFile) -> Result<DataSet, DataError> {
+    let mut combined = match combined {
+        Some(combined) => combined,
+        None => return Ok(data),
+    };
+    
+    let same_columns = combined.schema.fields.len() == data.schema.fields.len()
+        && combined.schema.fields.iter().zip(&data.schema.fields).all(|(a, b)| a.name == b.name);
+    
+    if !same_columns {
+        return Err(DataError::ValidationError(format!(
+            "{} does not have the columns of the files before it", file.path
+        )));
+    }
+    
+    combined.data.extend(data.data);
+    Ok(combined)
+}
@@ -1,7 +1,9 @@
 // Schema definition and validation
 // Author: Gabriel Demetrios Lafis
 
-use super::{DataError, DataType, Field, Schema, Value};
+use serde::{Deserialize, Serialize};
+
+use super::{DataError, DataType, ErrorContext, Field, Row, Schema, Value, fit_decimal};
 
 /// Schema validator for ensuring data conforms to a schema
 pub struct SchemaValidator;
@@ -12,10 +14,48 @@ impl SchemaValidator {
         match (value, data_type) {
             (Value::Null, _) => Ok(()), // Null is valid for any type
             (Value::Boolean(_), DataType::Boolean) => Ok(()),
-            (Value::Integer(_), DataType::Integer) => Ok(()),
+            (Value::Integer(i), t) if t.is_integer() => {
+                let (min, max) = t.integer_range().unwrap();
+                if *i < min || *i > max {
+                    return Err(DataError::ValidationError(format!(
+                        "Value {} out of range for {}", i, t.name()
+                    )));
+                }
+                Ok(())
+            },
+            (Value::Float(f), DataType::Float32) => {
+                if f.is_finite() && f.abs() > f32::MAX as f64 {
+                    return Err(DataError::ValidationError(format!(
+                        "Value {} out of range for float32", f
+                    )));
+                }
+                Ok(())
+            },
             (Value::Float(_), DataType::Float) => Ok(()),
+            (Value::Decimal(d), DataType::Decimal(precision, scale)) => {
+                // Decimals must not need rounding to fit
+                if d.scale() > *scale as u32 || fit_decimal(d, *precision, *scale).is_err() {
+                    return Err(DataError::ValidationError(format!(
+                        "Value {} out of range for {}", d, data_type.name()
+                    )));
+                }
+                Ok(())
+            },
             (Value::String(_), DataType::String) => Ok(()),
             (Value::Binary(_), DataType::Binary) => Ok(()),
+            (Value::Uuid(_), DataType::Uuid) => Ok(()),
+            (Value::Date(_), DataType::Date) => Ok(()),
+            (Value::Timestamp(_), DataType::Timestamp) => Ok(()),
+            (Value::Time(_), DataType::Time) => Ok(()),
+            (Value::Point(lat, lon), DataType::Point) => {
+                // Reject coordinates outside the WGS84 ranges
+                if lat.abs() > 90.0 || lon.abs() > 180.0 {
+                    return Err(DataError::ValidationError(format!(
+                        "Invalid point ({}, {})", lat, lon
+                    )));
+                }
+                Ok(())
+            },
             (Value::Array(arr), DataType::Array(elem_type)) => {
                 // Validate each element in the array
                 for elem in arr {
@@ -55,11 +95,12 @@ impl SchemaValidator {
             if !field.nullable && matches!(value, Value::Null) {
                 return Err(DataError::ValidationError(format!(
                     "Field '{}' cannot be null", field.name
-                )));
+                )).with_context(ErrorContext::new().column(&field.name)));
             }
             
             // Validate type
-            Self::validate_value(value, &field.data_type)?;
+            Self::validate_value(value, &field.data_type)
+                .map_err(|err| err.with_context(ErrorContext::new().column(&field.name).value(value)))?;
         }
         
         Ok(())
@@ -100,6 +141,11 @@ impl SchemaBuilder {
         self.add_field(name, DataType::Float, nullable)
     }
     
+    /// Add a decimal field of a precision and scale
+    pub fn add_decimal(self, name: &str, precision: u8, scale: u8, nullable: bool) -> Self {
+        self.add_field(name, DataType::Decimal(precision, scale), nullable)
+    }
+    
     /// Add a string field
     pub fn add_string(self, name: &str, nullable: bool) -> Self {
         self.add_field(name, DataType::String, nullable)
@@ -110,6 +156,31 @@ impl SchemaBuilder {
         self.add_field(name, DataType::Binary, nullable)
     }
     
+    /// Add a geographic point field
+    pub fn add_point(self, name: &str, nullable: bool) -> Self {
+        self.add_field(name, DataType::Point, nullable)
+    }
+    
+    /// Add a UUID field
+    pub fn add_uuid(self, name: &str, nullable: bool) -> Self {
+        self.add_field(name, DataType::Uuid, nullable)
+    }
+    
+    /// Add a date field
+    pub fn add_date(self, name: &str, nullable: bool) -> Self {
+        self.add_field(name, DataType::Date, nullable)
+    }
+    
+    /// Add a timestamp field
+    pub fn add_timestamp(self, name: &str, nullable: bool) -> Self {
+        self.add_field(name, DataType::Timestamp, nullable)
+    }
+    
+    /// Add a time of day field
+    pub fn add_time(self, name: &str, nullable: bool) -> Self {
+        self.add_field(name, DataType::Time, nullable)
+    }
+    
     /// Add an array field
     pub fn add_array(self, name: &str, element_type: DataType, nullable: bool) -> Self {
         self.add_field(name, DataType::Array(Box::new(element_type)), nullable)
@@ -132,3 +203,107 @@ impl Default for SchemaBuilder {
     }
 }
 
+/// How a column differs between a dataset's schema and a batch's
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftKind {
+    /// The batch has a column the dataset does not
+    Added,
+    /// The batch lacks a column of the dataset
+    Removed,
+    /// The batch has the column with another type
+    Retyped,
+}
+
+/// Column that differs between a dataset's schema and a batch's
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnDrift {
+    pub column: String,
+    pub kind: DriftKind,
+    /// Type in the dataset, unless the column was added
+    pub from: Option<String>,
+    /// Type in the batch, unless the column was removed
+    pub to: Option<String>,
+    /// Whether rows of the batch cannot be added to the dataset as they are
+    pub breaking: bool,
+}
+
+impl ColumnDrift {
+    /// Describe the change in words
+    pub fn describe(&self) -> String {
+        let type_name = |data_type: &Option<String>| data_type.clone().unwrap_or_default();
+        
+        match self.kind {
+            DriftKind::Added => format!("added column '{}' ({})", self.column, type_name(&self.to)),
+            DriftKind::Removed => format!("removed column '{}' ({})", self.column, type_name(&self.from)),
+            DriftKind::Retyped => format!(
+                "column '{}' changed from {} to {}", self.column, type_name(&self.from), type_name(&self.to)
+            ),
+        }
+    }
+}
+
+impl Schema {
+    /// Compare the schema of a dataset with the schema of a batch of new rows
+    ///
+    /// Columns are matched by name, so reordered columns are not drift.
+    /// Added columns are breaking, since the dataset has nowhere to keep
+    /// them; removed columns only if the dataset cannot fill them with null
+    /// or a default; and retyped columns unless the dataset's type holds the
+    /// batch's values, as integers hold narrower integers.
+    pub fn drift(&self, batch: &Schema) -> Vec<ColumnDrift> {
+        let mut drift = Vec::new();
+        
+        for field in &self.fields {
+            match batch.get_field_by_name(&field.name) {
+                None => drift.push(ColumnDrift {
+                    column: field.name.clone(),
+                    kind: DriftKind::Removed,
+                    from: Some(field.data_type.name()),
+                    to: None,
+                    breaking: !field.nullable && field.default.is_none(),
+                }),
+                Some(other) if other.data_type != field.data_type => drift.push(ColumnDrift {
+                    column: field.name.clone(),
+                    kind: DriftKind::Retyped,
+                    from: Some(field.data_type.name()),
+                    to: Some(other.data_type.name()),
+                    breaking: field.data_type.widen(&other.data_type).as_ref() != Some(&field.data_type),
+                }),
+                Some(_) => {},
+            }
+        }
+        
+        for field in &batch.fields {
+            if self.get_field_by_name(&field.name).is_none() {
+                drift.push(ColumnDrift {
+                    column: field.name.clone(),
+                    kind: DriftKind::Added,
+                    from: None,
+                    to: Some(field.data_type.name()),
+                    breaking: true,
+                });
+            }
+        }
+        
+        drift
+    }
+    
+    /// Reorder rows of a batch's schema to this schema, matching columns by
+    /// name and leaving columns the batch lacks null
+    pub fn align_rows(&self, batch: &Schema, rows: Vec<Row>) -> Vec<Row> {
+        let indices: Vec<Option<usize>> = self.fields.iter()
+            .map(|field| batch.fields.iter().position(|other| other.name == field.name))
+            .collect();
+        
+        rows.into_iter()
+            .map(|row| {
+                let mut values: Vec<Option<Value>> = row.values.into_iter().map(Some).collect();
+                
+                Row::new(indices.iter()
+                    .map(|index| index.and_then(|index| values.get_mut(index)?.take()).unwrap_or(Value::Null))
+                    .collect())
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,725 @@
+// Schema registry support for Avro messages of streaming sources
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use serde_json::Value as JsonValue;
+
+use super::{
+    DataError, DataSet, DataType, Decimal, DriftKind, Field, FieldDefault, JsonSink, JsonSource, Row, Schema,
+    SchemaValidator, Value, MAX_DECIMAL_PRECISION,
+};
+
+/// First byte of messages in the Confluent wire format
+const MAGIC_BYTE: u8 = 0;
+
+/// Days from the common era to the Unix epoch
+const EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+/// Annotation giving an Avro type a meaning beyond its encoding
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogicalType {
+    Date,
+    TimeMillis,
+    TimeMicros,
+    TimestampMillis,
+    TimestampMicros,
+    Uuid,
+    Decimal { precision: u8, scale: u8 },
+}
+
+/// Type of an Avro schema
+#[derive(Debug, Clone, PartialEq)]
+pub enum AvroType {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    Record { name: String, fields: Vec<AvroField> },
+    Enum { name: String, symbols: Vec<String> },
+    Array(Box<AvroType>),
+    Map(Box<AvroType>),
+    Union(Vec<AvroType>),
+    Fixed { name: String, size: usize },
+    Logical(LogicalType, Box<AvroType>),
+}
+
+/// Field of an Avro record
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvroField {
+    pub name: String,
+    pub avro_type: AvroType,
+    pub default: Option<JsonValue>,
+}
+
+/// Avro record schema, with the dataset schema its records decode to
+///
+/// Records decode to one column per field. Nullable unions become nullable
+/// columns, dates, times, timestamps and decimals become their column types,
+/// and nested records become maps.
+#[derive(Debug, Clone)]
+pub struct AvroSchema {
+    fields: Vec<AvroField>,
+    schema: Schema,
+}
+
+impl AvroSchema {
+    /// Parse the JSON text of an Avro record schema
+    pub fn parse(text: &str) -> Result<Self, DataError> {
+        let json: JsonValue = serde_json::from_str(text)
+            .map_err(|e| DataError::ParseError(format!("Invalid Avro schema: {}", e)))?;
+        
+        let fields = match parse_type(&json, &mut HashMap::new())? {
+            AvroType::Record { fields, .. } => fields,
+            _ => return Err(DataError::NotSupported("Avro schemas must be records".to_string())),
+        };
+        
+        let schema = Schema::new(fields.iter()
+            .map(|field| {
+                let (data_type, nullable) = column_type(&field.avro_type);
+                let mut column = Field::new(field.name.clone(), data_type, nullable);
+                
+                if let Some(default) = field.default.as_ref().filter(|default| !default.is_null()) {
+                    column = column.with_default(FieldDefault::Value(JsonSource::json_to_value(default)));
+                }
+                
+                column
+            })
+            .collect());
+        
+        Ok(AvroSchema { fields, schema })
+    }
+    
+    /// Get the dataset schema records decode to
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+    
+    /// Decode an Avro binary record into a row of the schema
+    pub fn decode(&self, bytes: &[u8]) -> Result<Row, DataError> {
+        let mut reader = AvroReader { bytes, pos: 0 };
+        
+        let values = self.fields.iter().zip(&self.schema.fields)
+            .map(|(field, column)| {
+                let value = coerce(reader.value(&field.avro_type)?, &column.data_type);
+                SchemaValidator::validate_value(&value, &column.data_type)?;
+                Ok(value)
+            })
+            .collect::<Result<Vec<_>, DataError>>()?;
+        
+        Ok(Row::new(values))
+    }
+}
+
+/// Rules a new schema must follow to be added to a dataset
+///
+/// These follow the compatibility levels of the Confluent Schema Registry,
+/// checked against the dataset's current schema: backward compatible
+/// schemas may only add columns that are nullable or have a default and may
+/// only widen types; forward compatible schemas may only remove such
+/// columns and may only narrow types; full compatibility requires both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    None,
+    Backward,
+    Forward,
+    Full,
+}
+
+impl Compatibility {
+    /// Parse a compatibility level, accepting the registry's transitive variants
+    pub fn from_str(s: &str) -> Result<Self, DataError> {
+        match s.trim().to_lowercase().trim_end_matches("_transitive") {
+            "none" => Ok(Compatibility::None),
+            "backward" => Ok(Compatibility::Backward),
+            "forward" => Ok(Compatibility::Forward),
+            "full" => Ok(Compatibility::Full),
+            _ => Err(DataError::ValidationError(format!("Invalid compatibility level: {}", s))),
+        }
+    }
+    
+    /// Get the name of the compatibility level
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compatibility::None => "none",
+            Compatibility::Backward => "backward",
+            Compatibility::Forward => "forward",
+            Compatibility::Full => "full",
+        }
+    }
+    
+    /// Check a new schema against a dataset's schema and get the schema
+    /// holding rows of both
+    ///
+    /// Added columns are appended as nullable, removed columns become
+    /// nullable, and retyped columns take the wider of both types.
+    pub fn evolve(&self, current: &Schema, incoming: &Schema) -> Result<Schema, DataError> {
+        let backward = matches!(self, Compatibility::Backward | Compatibility::Full);
+        let forward = matches!(self, Compatibility::Forward | Compatibility::Full);
+        let optional = |field: &Field| field.nullable || field.default.is_some();
+        let incompatible = |reason: String| DataError::ValidationError(format!(
+            "Schema breaks {} compatibility: {}", self.name(), reason
+        ));
+        
+        let mut evolved = current.clone();
+        
+        for drift in current.drift(incoming) {
+            let old = current.get_field_by_name(&drift.column);
+            let new = incoming.get_field_by_name(&drift.column);
+            
+            match (drift.kind, old, new) {
+                (DriftKind::Added, _, Some(new)) => {
+                    if backward && !optional(new) {
+                        return Err(incompatible(format!("added column '{}' has no default", new.name)));
+                    }
+                    
+                    let mut field = new.clone();
+                    field.nullable = true;
+                    evolved.fields.push(field);
+                },
+                (DriftKind::Removed, Some(old), _) => {
+                    if forward && !optional(old) {
+                        return Err(incompatible(format!("removed column '{}' has no default", old.name)));
+                    }
+                    
+                    if let Some(field) = evolved.fields.iter_mut().find(|field| field.name == old.name) {
+                        field.nullable = true;
+                    }
+                },
+                (DriftKind::Retyped, Some(old), Some(new)) => {
+                    let wider = old.data_type.widen(&new.data_type);
+                    
+                    if (backward && wider.as_ref() != Some(&new.data_type))
+                        || (forward && wider.as_ref() != Some(&old.data_type)) {
+                        return Err(incompatible(format!(
+                            "column '{}' changed from {} to {}", old.name, old.data_type.name(), new.data_type.name()
+                        )));
+                    }
+                    
+                    let wider = wider.ok_or_else(|| DataError::ValidationError(format!(
+                        "Column '{}' cannot hold both {} and {}", old.name, old.data_type.name(), new.data_type.name()
+                    )))?;
+                    
+                    if let Some(field) = evolved.fields.iter_mut().find(|field| field.name == old.name) {
+                        field.data_type = wider;
+                    }
+                },
+                _ => {},
+            }
+        }
+        
+        Ok(evolved)
+    }
+    
+    /// Check a new schema against a dataset and rewrite the dataset's rows
+    /// to the evolved schema
+    ///
+    /// Added columns are filled with their default or null, and values of
+    /// widened columns are converted.
+    pub fn evolve_dataset(&self, dataset: &DataSet, incoming: &Schema) -> Result<DataSet, DataError> {
+        let schema = self.evolve(&dataset.schema, incoming)?;
+        let mut evolved = DataSet::new(schema.clone());
+        evolved.metadata = dataset.metadata.clone();
+        
+        for row in schema.align_rows(&dataset.schema, dataset.data.clone()) {
+            let values = row.values.into_iter().zip(&schema.fields)
+                .map(|(value, field)| coerce(value, &field.data_type))
+                .collect();
+            
+            evolved.insert_row(Row::new(values))?;
+        }
+        
+        Ok(evolved)
+    }
+}
+
+/// Client of a Confluent Schema Registry
+///
+/// Decodes messages in the Confluent wire format: a zero byte, the id of the
+/// writer's schema as a 4-byte big-endian integer, then the Avro binary
+/// record. Schemas are fetched by id from `GET /schemas/ids/{id}` once and
+/// cached, since registered schemas never change. Fetching requires the
+/// `schema-registry` feature; schemas can also be registered by hand.
+pub struct SchemaRegistry {
+    url: String,
+    credentials: Option<(String, String)>,
+    schemas: RwLock<HashMap<u32, Arc<AvroSchema>>>,
+}
+
+impl SchemaRegistry {
+    /// Create a client for the registry at a URL
+    pub fn new(url: &str) -> Self {
+        SchemaRegistry {
+            url: url.trim_end_matches('/').to_string(),
+            credentials: None,
+            schemas: RwLock::new(HashMap::new()),
+        }
+    }
+    
+    /// Authenticate with HTTP basic auth
+    pub fn with_credentials(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+    
+    /// Add a schema to the cache under an id, so it is not fetched
+    pub fn with_schema(self, id: u32, schema: AvroSchema) -> Self {
+        if let Ok(mut schemas) = self.schemas.write() {
+            schemas.insert(id, Arc::new(schema));
+        }
+        
+        self
+    }
+    
+    /// Get the schema with an id, fetching it unless cached
+    pub fn schema(&self, id: u32) -> Result<Arc<AvroSchema>, DataError> {
+        let cached = self.schemas.read()
+            .map_err(|_| DataError::Other("Failed to acquire read lock".to_string()))?
+            .get(&id)
+            .cloned();
+        
+        if let Some(schema) = cached {
+            return Ok(schema);
+        }
+        
+        let schema = Arc::new(AvroSchema::parse(&self.fetch(id)?)?);
+        
+        self.schemas.write()
+            .map_err(|_| DataError::Other("Failed to acquire write lock".to_string()))?
+            .insert(id, schema.clone());
+        
+        Ok(schema)
+    }
+    
+    /// Decode a message into a row of a schema, matching columns by name
+    ///
+    /// Columns the writer's schema lacks are left null, to be filled by
+    /// their defaults when the row is inserted.
+    pub fn decode(&self, payload: &[u8], schema: &Schema) -> Result<Row, DataError> {
+        let (id, body) = split_message(payload)?;
+        let writer = self.schema(id)?;
+        let row = writer.decode(body)?;
+        
+        let mut rows = schema.align_rows(writer.schema(), vec![row]);
+        let values = rows.remove(0).values.into_iter().zip(&schema.fields)
+            .map(|(value, field)| {
+                let value = coerce(value, &field.data_type);
+                SchemaValidator::validate_value(&value, &field.data_type)?;
+                Ok(value)
+            })
+            .collect::<Result<Vec<_>, DataError>>()?;
+        
+        Ok(Row::new(values))
+    }
+    
+    /// Decode a message and add it to a dataset, first evolving the dataset
+    /// to the writer's schema under the compatibility rules
+    ///
+    /// Messages whose schema breaks the rules are rejected and leave the
+    /// dataset unchanged.
+    pub fn decode_into(&self, payload: &[u8], dataset: &mut DataSet, compatibility: Compatibility) -> Result<(), DataError> {
+        let (id, _) = split_message(payload)?;
+        let writer = self.schema(id)?;
+        
+        let schema = compatibility.evolve(&dataset.schema, writer.schema())?;
+        if !same_fields(&schema, &dataset.schema) {
+            log::info!("Evolving schema to version {} of the registry", id);
+            *dataset = compatibility.evolve_dataset(dataset, writer.schema())?;
+        }
+        
+        let row = self.decode(payload, &dataset.schema)?;
+        dataset.insert_row(row)
+    }
+    
+    /// Fetch the text of a schema from the registry
+    fn fetch(&self, id: u32) -> Result<String, DataError> {
+        #[cfg(feature = "schema-registry")]
+        {
+            let mut request = ureq::get(&format!("{}/schemas/ids/{}", self.url, id))
+                .set("Accept", "application/vnd.schemaregistry.v1+json");
+            
+            if let Some((username, password)) = &self.credentials {
+                let token = base64::encode(format!("{}:{}", username, password));
+                request = request.set("Authorization", &format!("Basic {}", token));
+            }
+            
+            let body: JsonValue = match request.call() {
+                Ok(response) => response.into_json()?,
+                Err(ureq::Error::Status(status, response)) => return Err(DataError::Other(format!(
+                    "Schema registry returned status {} for schema {}: {}",
+                    status, id, response.into_string().unwrap_or_default()
+                ))),
+                Err(err) => return Err(DataError::IoError(std::io::Error::new(std::io::ErrorKind::Other, err.to_string()))),
+            };
+            
+            // Schemas without a type are Avro
+            if let Some(schema_type) = body["schemaType"].as_str().filter(|schema_type| *schema_type != "AVRO") {
+                return Err(DataError::NotSupported(format!("Schema {} is a {} schema, not Avro", id, schema_type)));
+            }
+            
+            body["schema"].as_str()
+                .map(str::to_string)
+                .ok_or_else(|| DataError::ParseError(format!("Schema registry response for schema {} has no schema", id)))
+        }
+        
+        #[cfg(not(feature = "schema-registry"))]
+        {
+            let _ = &self.credentials;
+            Err(DataError::NotSupported(format!(
+                "Schema {} is not registered and fetching it from {} needs schema registry support", id, self.url
+            )))
+        }
+    }
+}
+
+/// Split a Confluent wire format message into its schema id and Avro body
+fn split_message(payload: &[u8]) -> Result<(u32, &[u8]), DataError> {
+    match payload {
+        [MAGIC_BYTE, a, b, c, d, body @ ..] => Ok((u32::from_be_bytes([*a, *b, *c, *d]), body)),
+        _ => Err(DataError::ParseError("Message is not in the schema registry wire format".to_string())),
+    }
+}
+
+/// Check if two schemas have the same columns, types and nullability
+fn same_fields(a: &Schema, b: &Schema) -> bool {
+    a.fields.len() == b.fields.len() && a.fields.iter().zip(&b.fields).all(|(a, b)| {
+        a.name == b.name && a.data_type == b.data_type && a.nullable == b.nullable
+    })
+}
+
+/// Parse an Avro type, resolving references to named types defined before it
+fn parse_type(json: &JsonValue, named: &mut HashMap<String, AvroType>) -> Result<AvroType, DataError> {
+    let invalid = |message: &str| DataError::ParseError(format!("Invalid Avro schema: {}", message));
+    
+    let object = match json {
+        JsonValue::String(name) => return match name.as_str() {
+            "null" => Ok(AvroType::Null),
+            "boolean" => Ok(AvroType::Boolean),
+            "int" => Ok(AvroType::Int),
+            "long" => Ok(AvroType::Long),
+            "float" => Ok(AvroType::Float),
+            "double" => Ok(AvroType::Double),
+            "bytes" => Ok(AvroType::Bytes),
+            "string" => Ok(AvroType::String),
+            name => named.get(name).cloned().ok_or_else(|| DataError::NotSupported(format!(
+                "Unknown or recursive Avro type '{}'", name
+            ))),
+        },
+        JsonValue::Array(branches) => return Ok(AvroType::Union(branches.iter()
+            .map(|branch| parse_type(branch, named))
+            .collect::<Result<_, _>>()?)),
+        JsonValue::Object(object) => object,
+        _ => return Err(invalid("types are names, unions or objects")),
+    };
+    
+    let name = || object.get("name").and_then(JsonValue::as_str).map(str::to_string)
+        .ok_or_else(|| invalid("named types need a name"));
+    
+    let avro_type = match object.get("type").ok_or_else(|| invalid("type objects need a type"))? {
+        JsonValue::String(kind) if kind == "record" || kind == "error" => {
+            let name = name()?;
+            let fields = object.get("fields").and_then(JsonValue::as_array)
+                .ok_or_else(|| invalid("records need fields"))?
+                .iter()
+                .map(|field| Ok(AvroField {
+                    name: field["name"].as_str().ok_or_else(|| invalid("fields need a name"))?.to_string(),
+                    avro_type: parse_type(&field["type"], named)?,
+                    default: field.get("default").cloned(),
+                }))
+                .collect::<Result<Vec<_>, DataError>>()?;
+            
+            AvroType::Record { name, fields }
+        },
+        JsonValue::String(kind) if kind == "enum" => AvroType::Enum {
+            name: name()?,
+            symbols: object.get("symbols").and_then(JsonValue::as_array)
+                .ok_or_else(|| invalid("enums need symbols"))?
+                .iter()
+                .map(|symbol| symbol.as_str().map(str::to_string).ok_or_else(|| invalid("symbols are strings")))
+                .collect::<Result<_, _>>()?,
+        },
+        JsonValue::String(kind) if kind == "fixed" => AvroType::Fixed {
+            name: name()?,
+            size: object.get("size").and_then(JsonValue::as_u64).ok_or_else(|| invalid("fixed types need a size"))? as usize,
+        },
+        JsonValue::String(kind) if kind == "array" => AvroType::Array(Box::new(
+            parse_type(object.get("items").ok_or_else(|| invalid("arrays need items"))?, named)?
+        )),
+        JsonValue::String(kind) if kind == "map" => AvroType::Map(Box::new(
+            parse_type(object.get("values").ok_or_else(|| invalid("maps need values"))?, named)?
+        )),
+        other => parse_type(other, named)?,
+    };
+    
+    // Register named types under their name and full name for later references
+    if let AvroType::Record { name, .. } | AvroType::Enum { name, .. } | AvroType::Fixed { name, .. } = &avro_type {
+        if let Some(namespace) = object.get("namespace").and_then(JsonValue::as_str) {
+            named.insert(format!("{}.{}", namespace, name), avro_type.clone());
+        }
+        named.insert(name.clone(), avro_type.clone());
+    }
+    
+    // Unknown logical types are ignored, as the specification requires
+    let logical = match object.get("logicalType").and_then(JsonValue::as_str) {
+        Some("date") => Some(LogicalType::Date),
+        Some("time-millis") => Some(LogicalType::TimeMillis),
+        Some("time-micros") => Some(LogicalType::TimeMicros),
+        Some("timestamp-millis") => Some(LogicalType::TimestampMillis),
+        Some("timestamp-micros") => Some(LogicalType::TimestampMicros),
+        Some("uuid") => Some(LogicalType::Uuid),
+        Some("decimal") => {
+            let precision = object.get("precision").and_then(JsonValue::as_u64).unwrap_or(0);
+            let scale = object.get("scale").and_then(JsonValue::as_u64).unwrap_or(0);
+            
+            // Decimals wider than ours are invalid here, and so ignored
+            (precision > 0 && precision <= MAX_DECIMAL_PRECISION as u64 && scale <= precision)
+                .then(|| LogicalType::Decimal { precision: precision as u8, scale: scale as u8 })
+        },
+        _ => None,
+    };
+    
+    Ok(match logical {
+        Some(logical) => AvroType::Logical(logical, Box::new(avro_type)),
+        None => avro_type,
+    })
+}
+
+/// Get the column type of an Avro type and whether it is nullable
+fn column_type(avro_type: &AvroType) -> (DataType, bool) {
+    let data_type = match avro_type {
+        AvroType::Null | AvroType::String | AvroType::Enum { .. } => DataType::String,
+        AvroType::Boolean => DataType::Boolean,
+        AvroType::Int => DataType::Int32,
+        AvroType::Long => DataType::Integer,
+        AvroType::Float => DataType::Float32,
+        AvroType::Double => DataType::Float,
+        AvroType::Bytes | AvroType::Fixed { .. } => DataType::Binary,
+        AvroType::Array(items) => DataType::Array(Box::new(column_type(items).0)),
+        AvroType::Map(values) => DataType::Map(Box::new(column_type(values).0)),
+        AvroType::Record { fields, .. } => {
+            let mut types = fields.iter().map(|field| column_type(&field.avro_type).0);
+            let first = types.next().unwrap_or(DataType::String);
+            
+            // Fields of one type keep it, others are kept as text
+            DataType::Map(Box::new(if types.all(|data_type| data_type == first) { first } else { DataType::String }))
+        },
+        AvroType::Union(branches) => {
+            let nullable = branches.contains(&AvroType::Null);
+            let data_type = branches.iter()
+                .filter(|branch| **branch != AvroType::Null)
+                .map(|branch| Some(column_type(branch).0))
+                .reduce(|a, b| a.zip(b).and_then(|(a, b)| a.widen(&b)))
+                .flatten()
+                .unwrap_or(DataType::String);
+            
+            return (data_type, nullable);
+        },
+        AvroType::Logical(LogicalType::Uuid, _) => DataType::Uuid,
+        AvroType::Logical(LogicalType::Date, _) => DataType::Date,
+        AvroType::Logical(LogicalType::TimeMillis | LogicalType::TimeMicros, _) => DataType::Time,
+        AvroType::Logical(LogicalType::TimestampMillis | LogicalType::TimestampMicros, _) => DataType::Timestamp,
+        AvroType::Logical(LogicalType::Decimal { precision, scale }, _) => DataType::Decimal(*precision, *scale),
+    };
+    
+    (data_type, matches!(avro_type, AvroType::Null))
+}
+
+/// Convert a decoded value to a column type where it does not fit as it is
+fn coerce(value: Value, data_type: &DataType) -> Value {
+    match (value, data_type) {
+        (Value::Integer(i), DataType::Float | DataType::Float32) => Value::Float(i as f64),
+        (Value::Null, _) => Value::Null,
+        (Value::String(s), DataType::String) => Value::String(s),
+        (Value::Array(values), DataType::Array(item_type)) => {
+            Value::Array(values.into_iter().map(|value| coerce(value, item_type)).collect())
+        },
+        (Value::Map(entries), DataType::Map(value_type)) => {
+            Value::Map(entries.into_iter().map(|(key, value)| (key, coerce(value, value_type))).collect())
+        },
+        // Values of mixed unions and records are kept as their JSON text
+        (value, DataType::String) => Value::String(JsonSink::value_to_json(&value).to_string()),
+        (value, _) => value,
+    }
+}
+
+/// Reader of Avro binary data
+struct AvroReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> AvroReader<'a> {
+    /// Read a number of bytes
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DataError> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| DataError::ParseError("Avro record ends early".to_string()))?;
+        
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+    
+    /// Read a zigzag encoded variable-length integer, as ints and longs are written
+    fn long(&mut self) -> Result<i64, DataError> {
+        let mut value: u64 = 0;
+        
+        for shift in (0..70).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7f) as u64) << shift;
+            
+            if byte & 0x80 == 0 {
+                return Ok((value >> 1) as i64 ^ -((value & 1) as i64));
+            }
+        }
+        
+        Err(DataError::ParseError("Avro integer is too long".to_string()))
+    }
+    
+    /// Read a length-prefixed byte string
+    fn bytes(&mut self) -> Result<&'a [u8], DataError> {
+        let len = self.long()?;
+        
+        if len < 0 {
+            return Err(DataError::ParseError(format!("Negative Avro length {}", len)));
+        }
+        
+        self.take(len as usize)
+    }
+    
+    /// Read the item count of the next block of an array or map, zero at the end
+    fn block_len(&mut self) -> Result<usize, DataError> {
+        let len = self.long()?;
+        
+        // Negative counts are followed by the size of the block in bytes
+        if len < 0 {
+            self.long()?;
+        }
+        
+        Ok(len.unsigned_abs() as usize)
+    }
+    
+    /// Read a value of a type
+    fn value(&mut self, avro_type: &AvroType) -> Result<Value, DataError> {
+        Ok(match avro_type {
+            AvroType::Null => Value::Null,
+            AvroType::Boolean => Value::Boolean(self.take(1)?[0] != 0),
+            AvroType::Int | AvroType::Long => Value::Integer(self.long()?),
+            AvroType::Float => {
+                let bytes = self.take(4)?;
+                Value::Float(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64)
+            },
+            AvroType::Double => {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(self.take(8)?);
+                Value::Float(f64::from_le_bytes(bytes))
+            },
+            AvroType::Bytes => Value::Binary(self.bytes()?.to_vec()),
+            AvroType::String => Value::String(utf8(self.bytes()?)?),
+            AvroType::Record { fields, .. } => Value::Map(fields.iter()
+                .map(|field| Ok((field.name.clone(), self.value(&field.avro_type)?)))
+                .collect::<Result<_, DataError>>()?),
+            AvroType::Enum { symbols, .. } => {
+                let index = self.long()?;
+                
+                let symbol = usize::try_from(index).ok().and_then(|index| symbols.get(index))
+                    .ok_or_else(|| DataError::ParseError(format!("Avro enum index {} out of range", index)))?;
+                Value::String(symbol.clone())
+            },
+            AvroType::Array(items) => {
+                let mut values = Vec::new();
+                
+                loop {
+                    let len = self.block_len()?;
+                    if len == 0 {
+                        break;
+                    }
+                    
+                    for _ in 0..len {
+                        values.push(self.value(items)?);
+                    }
+                }
+                
+                Value::Array(values)
+            },
+            AvroType::Map(value_type) => {
+                let mut entries = HashMap::new();
+                
+                loop {
+                    let len = self.block_len()?;
+                    if len == 0 {
+                        break;
+                    }
+                    
+                    for _ in 0..len {
+                        let key = utf8(self.bytes()?)?;
+                        entries.insert(key, self.value(value_type)?);
+                    }
+                }
+                
+                Value::Map(entries)
+            },
+            AvroType::Union(branches) => {
+                let index = self.long()?;
+                
+                let branch = usize::try_from(index).ok().and_then(|index| branches.get(index))
+                    .ok_or_else(|| DataError::ParseError(format!("Avro union index {} out of range", index)))?;
+                self.value(branch)?
+            },
+            AvroType::Fixed { size, .. } => Value::Binary(self.take(*size)?.to_vec()),
+            AvroType::Logical(logical, base) => logical_value(logical, self.value(base)?)?,
+        })
+    }
+}
+
+/// Decode UTF-8 text of an Avro string
+fn utf8(bytes: &[u8]) -> Result<String, DataError> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| DataError::ParseError("Avro string is not UTF-8".to_string()))
+}
+
+/// Convert the encoded value of a logical type to the value of its column
+fn logical_value(logical: &LogicalType, value: Value) -> Result<Value, DataError> {
+    let out_of_range = || DataError::ParseError(format!("Avro {:?} value out of range", logical));
+    
+    Ok(match (logical, value) {
+        (LogicalType::Date, Value::Integer(days)) => {
+            let date = i32::try_from(days).ok()
+                .and_then(|days| days.checked_add(EPOCH_DAYS_FROM_CE))
+                .and_then(NaiveDate::from_num_days_from_ce_opt)
+                .ok_or_else(out_of_range)?;
+            Value::Date(date)
+        },
+        (LogicalType::TimeMillis | LogicalType::TimeMicros, Value::Integer(time)) => {
+            let micros = if *logical == LogicalType::TimeMillis { time * 1000 } else { time };
+            let time = u32::try_from(micros / 1_000_000).ok()
+                .and_then(|seconds| NaiveTime::from_num_seconds_from_midnight_opt(seconds, (micros % 1_000_000) as u32 * 1000))
+                .ok_or_else(out_of_range)?;
+            Value::Time(time)
+        },
+        (LogicalType::TimestampMillis, Value::Integer(millis)) => {
+            Value::Timestamp(DateTime::from_timestamp_millis(millis).ok_or_else(out_of_range)?)
+        },
+        (LogicalType::TimestampMicros, Value::Integer(micros)) => {
+            Value::Timestamp(DateTime::from_timestamp_micros(micros).ok_or_else(out_of_range)?)
+        },
+        (LogicalType::Uuid, Value::String(s)) => Value::Uuid(super::parse_uuid(&s)?),
+        (LogicalType::Uuid, Value::Binary(bytes)) if bytes.len() == 16 => {
+            let mut uuid = [0; 16];
+            uuid.copy_from_slice(&bytes);
+            Value::Uuid(u128::from_be_bytes(uuid))
+        },
+        (LogicalType::Decimal { scale, .. }, Value::Binary(bytes)) if !bytes.is_empty() && bytes.len() <= 16 => {
+            // Big-endian two's complement, sign extended to 128 bits
+            let fill = if bytes[0] & 0x80 != 0 { 0xff } else { 0 };
+            let mut unscaled = [fill; 16];
+            unscaled[16 - bytes.len()..].copy_from_slice(&bytes);
+            Value::Decimal(Decimal::new(i128::from_be_bytes(unscaled), *scale as u32))
+        },
+        (_, value) => value,
+    })
+}
@@ -0,0 +1,271 @@
+// SFTP data source implementation
+// Author: Gabriel Demetrios Lafis
+
+use chrono::{DateTime, Utc};
+
+use super::{DataError, DataSet, DataSource, RemoteFile, SourceType, split_pattern};
+
+/// Default SSH port
+const DEFAULT_PORT: u16 = 22;
+
+/// How an SFTP source logs in
+#[derive(Debug, Clone)]
+pub enum SftpAuth {
+    Password(String),
+    PrivateKey { path: String, passphrase: Option<String> },
+    /// Keys held by a running SSH agent
+    Agent,
+}
+
+/// How an SFTP source checks the identity of the server
+///
+/// Connections to servers whose key cannot be checked are refused before
+/// any credentials are sent.
+#[derive(Debug, Clone)]
+pub enum HostKeyCheck {
+    /// Accept the keys listed for the host in an OpenSSH known_hosts file
+    KnownHosts(String),
+    /// Accept only the key with this SHA-256 fingerprint, as printed by `ssh-keygen -l` (`SHA256:...`)
+    Fingerprint(String),
+}
+
+impl Default for HostKeyCheck {
+    fn default() -> Self {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        HostKeyCheck::KnownHosts(format!("{}/.ssh/known_hosts", home))
+    }
+}
+
+/// SFTP data source
+///
+/// Downloads the files of a remote directory matching a glob pattern such
+/// as `/outgoing/orders_*.csv`, optionally only those modified after a given
+/// time, and parses them as CSV or JSON by extension. Files are read in name
+/// order over one connection and must share their columns. The server's key
+/// is checked against `~/.ssh/known_hosts` unless another check is set.
+/// Requires the `sftp` feature.
+pub struct SftpSource {
+    name: String,
+    host: String,
+    port: u16,
+    username: String,
+    auth: SftpAuth,
+    host_key: HostKeyCheck,
+    dir: String,
+    pattern: String,
+    modified_since: Option<DateTime<Utc>>,
+}
+
+impl SftpSource {
+    /// Create a new SFTP source reading every file of the home directory
+    pub fn new(host: &str, username: &str, auth: SftpAuth) -> Self {
+        SftpSource {
+            name: format!("sftp://{}@{}", username, host),
+            host: host.to_string(),
+            port: DEFAULT_PORT,
+            username: username.to_string(),
+            auth,
+            host_key: HostKeyCheck::default(),
+            dir: ".".to_string(),
+            pattern: "*".to_string(),
+            modified_since: None,
+        }
+    }
+    
+    /// Connect to another port than 22
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+    
+    /// Check the server's key against another known_hosts file
+    pub fn with_known_hosts(mut self, path: &str) -> Self {
+        self.host_key = HostKeyCheck::KnownHosts(path.to_string());
+        self
+    }
+    
+    /// Accept only a server key with the given SHA-256 fingerprint
+    pub fn with_host_fingerprint(mut self, fingerprint: &str) -> Self {
+        self.host_key = HostKeyCheck::Fingerprint(fingerprint.to_string());
+        self
+    }
+    
+    /// Read the files matching a glob pattern, where `*` and `?` match within a file name
+    pub fn with_pattern(mut self, pattern: &str) -> Self {
+        let (dir, pattern) = split_pattern(pattern);
+        self.dir = dir;
+        self.pattern = pattern;
+        self
+    }
+    
+    /// Read only files modified after the given time
+    pub fn with_modified_since(mut self, since: DateTime<Utc>) -> Self {
+        self.modified_since = Some(since);
+        self
+    }
+    
+    /// List the remote files to read, in name order
+    pub fn list(&self) -> Result<Vec<RemoteFile>, DataError> {
+        #[cfg(feature = "sftp")]
+        {
+            self.list_files(&self.connect()?)
+        }
+        
+        #[cfg(not(feature = "sftp"))]
+        {
+            Err(DataError::NotSupported("SFTP support not enabled".to_string()))
+        }
+    }
+    
+    /// Download and parse one remote file
+    pub fn read_file(&self, file: &RemoteFile) -> Result<DataSet, DataError> {
+        #[cfg(feature = "sftp")]
+        {
+            self.download(&self.connect()?, file)
+        }
+        
+        #[cfg(not(feature = "sftp"))]
+        {
+            let _ = file;
+            Err(DataError::NotSupported("SFTP support not enabled".to_string()))
+        }
+    }
+    
+    /// List the files matching the pattern over an open channel
+    #[cfg(feature = "sftp")]
+    fn list_files(&self, sftp: &ssh2::Sftp) -> Result<Vec<RemoteFile>, DataError> {
+        use chrono::TimeZone;
+        
+        let pattern = super::glob_regex(&self.pattern)?;
+        
+        let mut files: Vec<RemoteFile> = sftp.readdir(std::path::Path::new(&self.dir))
+            .map_err(ssh_error)?
+            .into_iter()
+            .filter(|(_, stat)| stat.is_file())
+            .filter(|(path, _)| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .map_or(false, |name| pattern.is_match(name))
+            })
+            .map(|(path, stat)| RemoteFile {
+                path: path.to_string_lossy().to_string(),
+                size: stat.size,
+                modified: stat.mtime.and_then(|mtime| Utc.timestamp_opt(mtime as i64, 0).single()),
+            })
+            .filter(|file| match (self.modified_since, file.modified) {
+                (Some(since), Some(modified)) => modified > since,
+                _ => true,
+            })
+            .collect();
+        
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+    
+    /// Download and parse one file over an open channel
+    #[cfg(feature = "sftp")]
+    fn download(&self, sftp: &ssh2::Sftp, file: &RemoteFile) -> Result<DataSet, DataError> {
+        let remote = sftp.open(std::path::Path::new(&file.path)).map_err(ssh_error)?;
+        let mut data = super::parse_remote_file(file, std::io::BufReader::new(remote), "SFTP")?;
+        
+        data.metadata.add("remote".to_string(), format!("{}{}", self.name, file.path));
+        Ok(data)
+    }
+    
+    /// Open an SFTP channel, checking the server's key before logging in
+    #[cfg(feature = "sftp")]
+    fn connect(&self) -> Result<ssh2::Sftp, DataError> {
+        let tcp = std::net::TcpStream::connect((self.host.as_str(), self.port))
+            .map_err(DataError::IoError)?;
+        
+        let mut session = ssh2::Session::new().map_err(ssh_error)?;
+        session.set_tcp_stream(tcp);
+        session.handshake().map_err(ssh_error)?;
+        self.verify_host_key(&session)?;
+        
+        match &self.auth {
+            SftpAuth::Password(password) => session.userauth_password(&self.username, password),
+            SftpAuth::PrivateKey { path, passphrase } => session.userauth_pubkey_file(
+                &self.username, None, std::path::Path::new(path), passphrase.as_deref(),
+            ),
+            SftpAuth::Agent => session.userauth_agent(&self.username),
+        }
+        .map_err(ssh_error)?;
+        
+        session.sftp().map_err(ssh_error)
+    }
+    
+    /// Check the key the server presented in the handshake
+    #[cfg(feature = "sftp")]
+    fn verify_host_key(&self, session: &ssh2::Session) -> Result<(), DataError> {
+        let rejected = |reason: &str| DataError::Other(format!(
+            "Host key of {}:{} rejected: {}", self.host, self.port, reason
+        ));
+        
+        match &self.host_key {
+            HostKeyCheck::Fingerprint(expected) => {
+                let hash = session.host_key_hash(ssh2::HashType::Sha256)
+                    .ok_or_else(|| rejected("server sent no key"))?;
+                let actual = format!("SHA256:{}", base64::encode_config(hash, base64::STANDARD_NO_PAD));
+                
+                if actual != expected.trim_end_matches('=') {
+                    return Err(rejected(&format!("fingerprint is {}", actual)));
+                }
+                
+                Ok(())
+            },
+            HostKeyCheck::KnownHosts(path) => {
+                let (key, _) = session.host_key().ok_or_else(|| rejected("server sent no key"))?;
+                
+                let mut known_hosts = session.known_hosts().map_err(ssh_error)?;
+                known_hosts.read_file(std::path::Path::new(path), ssh2::KnownHostFileKind::OpenSSH)
+                    .map_err(|e| rejected(&format!("cannot read {}: {}", path, e)))?;
+                
+                match known_hosts.check_port(&self.host, self.port, key) {
+                    ssh2::CheckResult::Match => Ok(()),
+                    ssh2::CheckResult::NotFound => Err(rejected(&format!("host is not in {}", path))),
+                    ssh2::CheckResult::Mismatch => Err(rejected(&format!("key differs from the one in {}", path))),
+                    ssh2::CheckResult::Failure => Err(rejected("key could not be checked")),
+                }
+            },
+        }
+    }
+}
+
+impl DataSource for SftpSource {
+    fn read(&self) -> Result<DataSet, DataError> {
+        #[cfg(feature = "sftp")]
+        {
+            let sftp = self.connect()?;
+            let mut combined = None;
+            
+            for file in self.list_files(&sftp)? {
+                let data = self.download(&sftp, &file)?;
+                combined = Some(super::combine_files(combined, data, &file)?);
+            }
+            
+            combined.ok_or_else(|| DataError::Other(format!(
+                "No files match {}/{} on {}", self.dir, self.pattern, self.name
+            )))
+        }
+        
+        #[cfg(not(feature = "sftp"))]
+        {
+            Err(DataError::NotSupported("SFTP support not enabled".to_string()))
+        }
+    }
+    
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn source_type(&self) -> SourceType {
+        SourceType::Custom("sftp".to_string())
+    }
+}
+
+/// Convert an SSH error into a data error
+#[cfg(feature = "sftp")]
+fn ssh_error(err: ssh2::Error) -> DataError {
+    DataError::IoError(err.into())
+}
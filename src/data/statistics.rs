@@ -0,0 +1,104 @@
+// Column statistics for skipping data on read
+// Author: Gabriel Demetrios Lafis
+
+use std::cmp::Ordering;
+use std::ops::Bound;
+
+use super::{Value, compare_decimal, compare_temporal};
+
+/// Minimum, maximum and null count of a column in a block of rows
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStatistics {
+    pub column: String,
+    /// Smallest value, if recorded
+    pub min: Option<Value>,
+    /// Largest value, if recorded
+    pub max: Option<Value>,
+    /// Number of nulls, if recorded
+    pub nulls: Option<u64>,
+}
+
+/// Statistics of a block of rows, such as a Parquet row group
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowGroupStatistics {
+    pub rows: usize,
+    pub columns: Vec<ColumnStatistics>,
+}
+
+impl RowGroupStatistics {
+    /// Get the statistics of a column
+    pub fn column(&self, name: &str) -> Option<&ColumnStatistics> {
+        self.columns.iter().find(|column| column.column == name)
+    }
+    
+    /// Check if the block may hold rows in every range
+    ///
+    /// Columns without statistics, and values that cannot be compared with
+    /// the bounds, never rule a block out.
+    pub fn may_match(&self, ranges: &[ColumnRange]) -> bool {
+        ranges.iter().all(|range| self.column(&range.column).map_or(true, |column| range.may_match(column)))
+    }
+}
+
+/// Range a column's values must fall in for a row to be wanted
+///
+/// Ranges are hints for readers: blocks of rows whose statistics show that
+/// no value falls in the range can be skipped, but rows outside the range
+/// may still be returned, so the filter the range comes from must still be
+/// applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnRange {
+    pub column: String,
+    pub lower: Bound<Value>,
+    pub upper: Bound<Value>,
+}
+
+impl ColumnRange {
+    /// Create a range of a column between two bounds
+    pub fn new(column: &str, lower: Bound<Value>, upper: Bound<Value>) -> Self {
+        ColumnRange {
+            column: column.to_string(),
+            lower,
+            upper,
+        }
+    }
+    
+    /// Check if a block with the given statistics may hold values in the range
+    pub fn may_match(&self, statistics: &ColumnStatistics) -> bool {
+        let (min, max) = match (&statistics.min, &statistics.max) {
+            (Some(min), Some(max)) => (min, max),
+            _ => return true,
+        };
+        
+        let above_lower = match &self.lower {
+            Bound::Included(lower) => compare(max, lower).map_or(true, |ordering| ordering != Ordering::Less),
+            Bound::Excluded(lower) => compare(max, lower).map_or(true, |ordering| ordering == Ordering::Greater),
+            Bound::Unbounded => true,
+        };
+        
+        let below_upper = match &self.upper {
+            Bound::Included(upper) => compare(min, upper).map_or(true, |ordering| ordering != Ordering::Greater),
+            Bound::Excluded(upper) => compare(min, upper).map_or(true, |ordering| ordering == Ordering::Less),
+            Bound::Unbounded => true,
+        };
+        
+        above_lower && below_upper
+    }
+}
+
+/// Compare a statistic with a bound, if they are comparable
+///
+/// Integers, floats and decimals compare as numbers, and dates with
+/// timestamps; other values only with values of their own type.
+fn compare(statistic: &Value, bound: &Value) -> Option<Ordering> {
+    match (statistic, bound) {
+        (Value::Integer(a), Value::Integer(b)) => Some(a.cmp(b)),
+        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+        (Value::Integer(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+        (Value::Float(a), Value::Integer(b)) => a.partial_cmp(&(*b as f64)),
+        (Value::Boolean(a), Value::Boolean(b)) => Some(a.cmp(b)),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Uuid(a), Value::Uuid(b)) => Some(a.cmp(b)),
+        (a, b) => compare_temporal(a, b).or_else(|| compare_decimal(a, b)),
+    }
+}
@@ -0,0 +1,100 @@
+// Date, timestamp and time parsing and formatting
+// Author: Gabriel Demetrios Lafis
+
+use std::cmp::Ordering;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, Utc};
+
+use super::{DataError, DataType, Value};
+
+/// Parse a date in ISO 8601 form, such as `2024-03-01`
+pub fn parse_date(s: &str) -> Result<NaiveDate, DataError> {
+    NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+        .map_err(|_| DataError::ParseError(format!("Invalid date: {}", s)))
+}
+
+/// Parse a timestamp in RFC 3339 or ISO 8601 form
+///
+/// Timestamps with an offset are converted to UTC; timestamps without one
+/// are taken as UTC, and plain dates as their midnight.
+pub fn parse_timestamp(s: &str) -> Result<DateTime<Utc>, DataError> {
+    let s = s.trim();
+    
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(s) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"].iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .or_else(|| parse_date(s).ok().map(|date| date.and_time(NaiveTime::MIN)))
+        .map(|datetime| datetime.and_utc())
+        .ok_or_else(|| DataError::ParseError(format!("Invalid timestamp: {}", s)))
+}
+
+/// Parse a time of day, such as `14:30` or `14:30:05.250`
+pub fn parse_time(s: &str) -> Result<NaiveTime, DataError> {
+    let s = s.trim();
+    
+    NaiveTime::parse_from_str(s, "%H:%M:%S%.f")
+        .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M"))
+        .map_err(|_| DataError::ParseError(format!("Invalid time: {}", s)))
+}
+
+/// Format a date in ISO 8601 form
+pub fn format_date(date: &NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Format a timestamp in RFC 3339 form, in UTC with as many fraction digits as needed
+pub fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Format a time of day, with as many fraction digits as needed
+pub fn format_time(time: &NaiveTime) -> String {
+    time.format("%H:%M:%S%.f").to_string()
+}
+
+/// Parse a string value into the date, timestamp or time value of a temporal type
+///
+/// Values of other types, and values that are not strings, are returned unchanged.
+pub fn parse_temporal_value(value: Value, data_type: &DataType) -> Result<Value, DataError> {
+    let s = match &value {
+        Value::String(s) => s,
+        _ => return Ok(value),
+    };
+    
+    match data_type {
+        DataType::Date => parse_date(s).map(Value::Date),
+        DataType::Timestamp => parse_timestamp(s).map(Value::Timestamp),
+        DataType::Time => parse_time(s).map(Value::Time),
+        _ => Ok(value),
+    }
+}
+
+/// Compare two values where either is a date, timestamp or time
+///
+/// Dates compare with timestamps as their midnight in UTC, and strings are
+/// parsed as the temporal type they are compared with. Returns None for
+/// values that are not temporal or not comparable.
+pub fn compare_temporal(a: &Value, b: &Value) -> Option<Ordering> {
+    let midnight = |date: &NaiveDate| date.and_time(NaiveTime::MIN).and_utc();
+    
+    match (a, b) {
+        (Value::Date(a), Value::Date(b)) => Some(a.cmp(b)),
+        (Value::Timestamp(a), Value::Timestamp(b)) => Some(a.cmp(b)),
+        (Value::Time(a), Value::Time(b)) => Some(a.cmp(b)),
+        (Value::Date(a), Value::Timestamp(b)) => Some(midnight(a).cmp(b)),
+        (Value::Timestamp(a), Value::Date(b)) => Some(a.cmp(&midnight(b))),
+        (Value::Date(a), Value::String(b)) => match parse_date(b) {
+            Ok(b) => Some(a.cmp(&b)),
+            Err(_) => parse_timestamp(b).ok().map(|b| midnight(a).cmp(&b)),
+        },
+        (Value::Timestamp(a), Value::String(b)) => parse_timestamp(b).ok().map(|b| a.cmp(&b)),
+        (Value::Time(a), Value::String(b)) => parse_time(b).ok().map(|b| a.cmp(&b)),
+        (Value::String(_), Value::Date(_) | Value::Timestamp(_) | Value::Time(_)) => {
+            compare_temporal(b, a).map(Ordering::reverse)
+        },
+        _ => None,
+    }
+}
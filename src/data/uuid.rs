@@ -0,0 +1,51 @@
+// UUID parsing, formatting and generation
+// Author: Gabriel Demetrios Lafis
+
+use super::DataError;
+
+/// Parse a UUID in its hyphenated, simple or braced text form
+pub fn parse_uuid(s: &str) -> Result<u128, DataError> {
+    let s = s.trim();
+    let s = s.strip_prefix('{').and_then(|s| s.strip_suffix('}')).unwrap_or(s);
+    
+    let hex: String = if s.len() == 36 {
+        // Hyphens are only allowed between the five groups
+        for (i, c) in s.char_indices() {
+            if (i == 8 || i == 13 || i == 18 || i == 23) != (c == '-') {
+                return Err(DataError::ParseError(format!("Invalid UUID: {}", s)));
+            }
+        }
+        s.chars().filter(|c| *c != '-').collect()
+    } else {
+        s.to_string()
+    };
+    
+    if hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(DataError::ParseError(format!("Invalid UUID: {}", s)));
+    }
+    
+    u128::from_str_radix(&hex, 16)
+        .map_err(|_| DataError::ParseError(format!("Invalid UUID: {}", s)))
+}
+
+/// Format a UUID in its lowercase hyphenated form
+pub fn format_uuid(uuid: u128) -> String {
+    let hex = format!("{:032x}", uuid);
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+/// Generate a random (version 4) UUID
+pub fn generate_uuid() -> u128 {
+    generate_uuid_with(&mut rand::thread_rng())
+}
+
+/// Generate a random (version 4) UUID from a given random number generator
+pub fn generate_uuid_with<R: rand::Rng>(rng: &mut R) -> u128 {
+    let mut bytes: [u8; 16] = rng.gen();
+    
+    // Version 4, RFC 4122 variant
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    
+    u128::from_be_bytes(bytes)
+}
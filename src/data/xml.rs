@@ -0,0 +1,249 @@
+// XML data source implementation
+// Author: Gabriel Demetrios Lafis
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+#[cfg(feature = "xml")]
+use serde_json::{Map, Value as JsonValue};
+
+use super::{DataError, DataSet, DataSource, SourceType};
+
+/// XML data source
+///
+/// Every element at the record path becomes a row. The record's attributes
+/// and the text of its child elements become columns, with nested elements
+/// and their attributes named by their dotted path, such as
+/// `customer.address.city` or `customer.id`; repeated elements become
+/// arrays. Text that reads as a number or boolean is converted, except
+/// numbers with leading zeros, which are usually codes. Records then go
+/// through the same schema inference as `JsonSource`. Namespace prefixes are
+/// ignored. Requires the `xml` feature.
+pub struct XmlSource {
+    path: String,
+    record_path: Vec<String>,
+}
+
+impl XmlSource {
+    /// Create a new XML data source reading the elements at a path
+    ///
+    /// The path is a `/`-separated list of element names, such as
+    /// `orders/order`. A path starting with `/` is matched from the root
+    /// element; otherwise it matches at any depth.
+    pub fn new<P: AsRef<Path>>(path: P, record_path: &str) -> Self {
+        XmlSource {
+            path: path.as_ref().to_string_lossy().to_string(),
+            record_path: record_path.split('/').map(|s| s.to_string()).collect(),
+        }
+    }
+    
+    /// Check whether the open elements are at the record path
+    #[cfg(feature = "xml")]
+    fn is_record(&self, stack: &[String]) -> bool {
+        match self.record_path.first().map(String::as_str) {
+            // A leading slash leaves an empty first part
+            Some("") => stack.len() + 1 == self.record_path.len() && stack == &self.record_path[1..],
+            _ => stack.ends_with(&self.record_path),
+        }
+    }
+    
+    /// Read a dataset from any reader
+    pub fn read_from<R: Read>(&self, reader: R) -> Result<DataSet, DataError> {
+        #[cfg(feature = "xml")]
+        {
+            use quick_xml::events::{BytesStart, Event};
+            use quick_xml::reader::Reader;
+            use std::io::BufReader;
+            use super::JsonSource;
+            
+            /// Element open inside a record
+            struct Open {
+                path: String,
+                text: String,
+                has_children: bool,
+            }
+            
+            let name = |e: &BytesStart| String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+            
+            let attributes = |e: &BytesStart| -> Result<Vec<(String, String)>, DataError> {
+                let mut attrs = Vec::new();
+                
+                for attr in e.attributes() {
+                    let attr = attr.map_err(xml_error)?;
+                    
+                    // Namespace declarations are not data
+                    if attr.key.as_namespace_binding().is_some() {
+                        continue;
+                    }
+                    
+                    let value = attr.unescape_value().map_err(xml_error)?.into_owned();
+                    attrs.push((String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string(), value));
+                }
+                
+                Ok(attrs)
+            };
+            
+            let mut xml = Reader::from_reader(BufReader::new(reader));
+            xml.trim_text(true);
+            
+            let mut buf = Vec::new();
+            let mut stack: Vec<String> = Vec::new();
+            let mut records: Vec<JsonValue> = Vec::new();
+            
+            // Columns of the current record, and the elements open inside it
+            let mut record: Option<Map<String, JsonValue>> = None;
+            let mut open: Vec<Open> = Vec::new();
+            
+            loop {
+                let event = xml.read_event_into(&mut buf).map_err(xml_error)?;
+                
+                let (start, end) = match &event {
+                    Event::Start(e) => (Some(e.clone()), false),
+                    Event::Empty(e) => (Some(e.clone()), true),
+                    Event::End(_) => (None, true),
+                    Event::Text(text) => {
+                        if let Some(element) = open.last_mut() {
+                            element.text.push_str(&text.unescape().map_err(xml_error)?);
+                        }
+                        (None, false)
+                    },
+                    Event::CData(data) => {
+                        if let Some(element) = open.last_mut() {
+                            element.text.push_str(&String::from_utf8_lossy(data));
+                        }
+                        (None, false)
+                    },
+                    Event::Eof => break,
+                    _ => (None, false),
+                };
+                
+                if let Some(e) = start {
+                    stack.push(name(&e));
+                    
+                    match record.as_mut() {
+                        None if self.is_record(&stack) => {
+                            let mut columns = Map::new();
+                            for (attr, value) in attributes(&e)? {
+                                add_column(&mut columns, attr, text_to_json(&value));
+                            }
+                            record = Some(columns);
+                        },
+                        None => {},
+                        Some(columns) => {
+                            let path = match open.last_mut() {
+                                Some(parent) => {
+                                    parent.has_children = true;
+                                    format!("{}.{}", parent.path, name(&e))
+                                },
+                                None => name(&e),
+                            };
+                            
+                            for (attr, value) in attributes(&e)? {
+                                add_column(columns, format!("{}.{}", path, attr), text_to_json(&value));
+                            }
+                            
+                            open.push(Open { path, text: String::new(), has_children: false });
+                        },
+                    }
+                }
+                
+                if end {
+                    if let Some(columns) = record.as_mut() {
+                        match open.pop() {
+                            // Elements holding other elements are columns through their children
+                            Some(element) if element.has_children => {},
+                            Some(element) => add_column(columns, element.path, text_to_json(element.text.trim())),
+                            None => records.push(JsonValue::Object(record.take().unwrap())),
+                        }
+                    }
+                    
+                    stack.pop();
+                }
+                
+                buf.clear();
+            }
+            
+            if records.is_empty() {
+                return Err(DataError::ParseError(format!(
+                    "No '{}' elements found in {}", self.record_path.join("/"), self.path
+                )));
+            }
+            
+            let mut dataset = JsonSource::new(&self.path).read_array(&records)?;
+            dataset.metadata.add("source".to_string(), "xml".to_string());
+            
+            Ok(dataset)
+        }
+        
+        #[cfg(not(feature = "xml"))]
+        {
+            let _ = reader;
+            Err(DataError::NotSupported("XML support not enabled".to_string()))
+        }
+    }
+}
+
+impl DataSource for XmlSource {
+    fn read(&self) -> Result<DataSet, DataError> {
+        let file = File::open(&self.path).map_err(DataError::IoError)?;
+        self.read_from(file)
+    }
+    
+    fn name(&self) -> &str {
+        &self.path
+    }
+    
+    fn source_type(&self) -> SourceType {
+        SourceType::File
+    }
+}
+
+/// Add a value to a record, turning the column into an array if it repeats
+#[cfg(feature = "xml")]
+fn add_column(columns: &mut Map<String, JsonValue>, name: String, value: JsonValue) {
+    match columns.get_mut(&name) {
+        Some(JsonValue::Array(values)) => values.push(value),
+        Some(existing) => *existing = JsonValue::Array(vec![existing.take(), value]),
+        None => {
+            columns.insert(name, value);
+        },
+    }
+}
+
+/// Convert element or attribute text to the JSON value it reads as
+#[cfg(feature = "xml")]
+fn text_to_json(text: &str) -> JsonValue {
+    if text.is_empty() {
+        return JsonValue::Null;
+    }
+    
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    let numeric = digits.chars().next().map_or(false, |c| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'));
+    
+    // Keep codes such as "00123" as text
+    let leading_zero = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+    
+    if numeric && !leading_zero {
+        if let Ok(i) = text.parse::<i64>() {
+            return JsonValue::from(i);
+        }
+        
+        if let Some(n) = text.parse::<f64>().ok().and_then(serde_json::Number::from_f64) {
+            return JsonValue::Number(n);
+        }
+    }
+    
+    match text {
+        "true" => JsonValue::Bool(true),
+        "false" => JsonValue::Bool(false),
+        _ => JsonValue::String(text.to_string()),
+    }
+}
+
+/// Convert an XML parser error into a data error
+#[cfg(feature = "xml")]
+fn xml_error<E: std::fmt::Display>(err: E) -> DataError {
+    DataError::ParseError(err.to_string())
+}
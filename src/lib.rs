@@ -13,6 +13,7 @@
 //! - Joining datasets
 //! - Statistical analysis
 //! - REST API for remote access
+//! - Typed async client for the REST API (`client` feature)
 //!
 //! ## Example
 //!
@@ -64,6 +65,8 @@ pub mod processing;
 pub mod storage;
 pub mod api;
 pub mod utils;
+#[cfg(feature = "client")]
+pub mod client;
 
 // Re-export main types
 pub use data::{DataSet, DataType, Field, Row, Schema, Value};
@@ -8,11 +8,25 @@ use clap::{App, Arg, SubCommand};
 use log::{info, error};
 
 use rust_data_processing_engine::{
-    api::Server,
-    storage::{FileStorage, FileFormat, MemoryStorage, CacheStorage},
-    utils::{Config, init_logging},
+    api::{AccessControl, ResponseLimit, ResponsePolicies, ResponsePolicy, Server},
+    processing::{DataProcessor, DatasetProfile, FilterProcessor, PipelineTemplate, ResultCache, parse_parameter, set_global_seed},
+    data::{DataSet, DataSource, FtpSource, SftpAuth, SftpSource},
+    storage::{FileStorage, FileFormat, MemoryPolicy, MemoryStorage, CacheStorage, CatalogStorage, DataStorage, DriftPolicy, FailoverStorage, IngestMode, IngestionService, InvalidatingStorage, KeyRing, LandingZone, ProtectedStorage, RetryPolicy, RetryStorage, SessionStorage, StorageError, backup_storage, read_data_file, restore_storage, run_maintenance},
+    utils::{Config, StorageConfig, Telemetry, TelemetryConfig, init_logging, render_html_report, watch_paths, OutputFormat},
 };
 
+/// Number of sample rows in profile reports
+const REPORT_SAMPLE_ROWS: usize = 20;
+
+/// Number of histogram bins of numeric columns in profile reports
+const REPORT_HISTOGRAM_BINS: usize = 10;
+
+/// Time to wait for a burst of file changes to settle before re-running
+const WATCH_DEBOUNCE_MS: u64 = 300;
+
+/// Time to wait for files to settle in landing directories before ingesting them
+const INGEST_DEBOUNCE_MS: u64 = 1_000;
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // Parse command line arguments
@@ -48,6 +62,156 @@ async fn main() -> std::io::Result<()> {
                         .takes_value(true),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("maintain")
+                .about("Run storage maintenance and report the space reclaimed"),
+        )
+        .subcommand(
+            SubCommand::with_name("backup")
+                .about("Archive every dataset, with its tags and flags, into a tar file")
+                .arg(
+                    Arg::with_name("file")
+                        .help("Archive to write")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("restore")
+                .about("Restore the datasets of a backup archive into the configured storage")
+                .arg(
+                    Arg::with_name("file")
+                        .help("Archive to read")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("overwrite")
+                        .long("overwrite")
+                        .help("Replaces datasets and aliases that already exist"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Print the rows of a dataset, optionally filtered")
+                .arg(
+                    Arg::with_name("dataset")
+                        .help("Name of the dataset to print")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("filter")
+                        .long("filter")
+                        .value_name("EXPR")
+                        .help("Keeps only rows matching a filter expression")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .short("n")
+                        .long("limit")
+                        .value_name("ROWS")
+                        .help("Sets the maximum number of rows printed")
+                        .takes_value(true),
+                )
+                .arg(output_format_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("watch")
+                .about("Re-run a pipeline spec whenever its input files change")
+                .arg(
+                    Arg::with_name("pipeline")
+                        .help("JSON pipeline spec, reloaded when it changes")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .value_name("PATH")
+                        .help("Sets the input file, or a directory whose files are read together")
+                        .required(true)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("DATASET")
+                        .help("Sets the dataset the results are stored in")
+                        .required(true)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("param")
+                        .short("p")
+                        .long("param")
+                        .value_name("NAME=VALUE")
+                        .help("Sets a parameter of the pipeline spec, replacing ${NAME}")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("fetch")
+                .about("Download files from a configured SFTP or FTP server into a dataset")
+                .arg(
+                    Arg::with_name("remote")
+                        .help("Name of the SFTP or FTP server in the config")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("pattern")
+                        .help("Remote files to read, such as /outgoing/orders_*.csv")
+                        .required(true)
+                        .index(2),
+                )
+                .arg(
+                    Arg::with_name("since")
+                        .long("since")
+                        .value_name("TIME")
+                        .help("Reads only files modified after an RFC 3339 time")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("DATASET")
+                        .help("Sets the dataset the files are stored in")
+                        .required(true)
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("report")
+                .about("Write an HTML profile report of a dataset")
+                .arg(
+                    Arg::with_name("dataset")
+                        .help("Name of the dataset to profile")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Sets the report file, <dataset>.html by default")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("sample")
+                        .long("sample")
+                        .value_name("ROWS")
+                        .help("Sets the number of sample rows in the report")
+                        .takes_value(true),
+                ),
+        )
         .get_matches();
     
     // Load configuration
@@ -68,6 +232,17 @@ async fn main() -> std::io::Result<()> {
         eprintln!("Error initializing logger: {}", err);
     }
     
+    // Make randomized processors reproducible, if configured
+    if config.seed.is_some() {
+        set_global_seed(config.seed);
+    }
+    
+    // Memory storage snapshotted to disk, if configured
+    let mut memory_snapshot: Option<(Arc<MemoryStorage>, FileStorage)> = None;
+    
+    // Memory storage with a memory limit, whose occupancy is reported
+    let mut limited_memory: Option<Arc<MemoryStorage>> = None;
+    
     // Create storage
     let storage: Arc<dyn rust_data_processing_engine::storage::DataStorage + Send + Sync> = match config.storage.type_.as_str() {
         "file" => {
@@ -76,10 +251,14 @@ async fn main() -> std::io::Result<()> {
                 Some("csv") => FileFormat::Csv,
                 Some("json") => FileFormat::Json,
                 Some("parquet") => FileFormat::Parquet,
+                Some("orc") => FileFormat::Orc,
                 _ => FileFormat::Csv,
             };
             
-            match FileStorage::new(path, format) {
+            match FileStorage::new(path, format)
+                .and_then(|storage| encrypted(storage, &config.storage))
+                .and_then(|storage| mapped(storage, &config.storage))
+                .map(|storage| downcasting(storage, &config.storage)) {
                 Ok(storage) => Arc::new(storage),
                 Err(err) => {
                     error!("Error creating file storage: {:?}", err);
@@ -93,10 +272,14 @@ async fn main() -> std::io::Result<()> {
                 Some("csv") => FileFormat::Csv,
                 Some("json") => FileFormat::Json,
                 Some("parquet") => FileFormat::Parquet,
+                Some("orc") => FileFormat::Orc,
                 _ => FileFormat::Csv,
             };
             
-            let file_storage = match FileStorage::new(path, format) {
+            let file_storage = match FileStorage::new(path, format)
+                .and_then(|storage| encrypted(storage, &config.storage))
+                .and_then(|storage| mapped(storage, &config.storage))
+                .map(|storage| downcasting(storage, &config.storage)) {
                 Ok(storage) => storage,
                 Err(err) => {
                     error!("Error creating file storage for cache: {:?}", err);
@@ -112,7 +295,165 @@ async fn main() -> std::io::Result<()> {
             
             Arc::new(cache_storage)
         },
-        _ => Arc::new(MemoryStorage::new()),
+        _ => {
+            let mut memory_storage = MemoryStorage::new();
+            
+            if let Some(limit) = &config.storage.memory_limit {
+                let policy = match limit.on_full.as_str() {
+                    "reject" => MemoryPolicy::Reject,
+                    "evict" => MemoryPolicy::EvictLru,
+                    "spill" => {
+                        let path = limit.spill_path.clone().unwrap_or_else(|| "./spill".to_string());
+                        
+                        match FileStorage::new(path, FileFormat::Json).and_then(|storage| encrypted(storage, &config.storage)) {
+                            Ok(spill_storage) => MemoryPolicy::Spill(Arc::new(spill_storage)),
+                            Err(err) => {
+                                error!("Error creating spill storage: {:?}", err);
+                                return Ok(());
+                            }
+                        }
+                    },
+                    policy => {
+                        error!("Invalid memory limit policy: {}", policy);
+                        return Ok(());
+                    }
+                };
+                
+                memory_storage = memory_storage.with_memory_limit(limit.max_bytes, policy);
+            }
+            
+            let memory_storage = Arc::new(memory_storage);
+            
+            if config.storage.memory_limit.is_some() {
+                limited_memory = Some(memory_storage.clone());
+            }
+            
+            if let Some(path) = config.storage.snapshot_path.clone() {
+                let format = match config.storage.format.as_deref() {
+                    Some("csv") => FileFormat::Csv,
+                    Some("parquet") => FileFormat::Parquet,
+                    Some("orc") => FileFormat::Orc,
+                    _ => FileFormat::Json,
+                };
+                
+                match FileStorage::new(path, format)
+                    .and_then(|storage| encrypted(storage, &config.storage))
+                    .and_then(|storage| mapped(storage, &config.storage))
+                    .map(|storage| downcasting(storage, &config.storage)) {
+                    Ok(snapshot_storage) => {
+                        match memory_storage.restore(&snapshot_storage) {
+                            Ok(count) => info!("Restored {} datasets from snapshot", count),
+                            Err(err) => error!("Error restoring snapshot: {:?}", err),
+                        }
+                        
+                        memory_snapshot = Some((memory_storage.clone(), snapshot_storage));
+                    },
+                    Err(err) => error!("Error creating snapshot storage: {:?}", err),
+                }
+            }
+            
+            memory_storage
+        },
+    };
+    
+    // Wrap storage with retries and circuit breaking if configured
+    let storage: Arc<dyn DataStorage + Send + Sync> = match &config.storage.retry {
+        Some(retry) => {
+            let policy = RetryPolicy {
+                max_retries: retry.max_retries,
+                initial_backoff: std::time::Duration::from_millis(retry.initial_backoff_ms),
+                max_backoff: std::time::Duration::from_millis(retry.max_backoff_ms),
+                multiplier: 2.0,
+                jitter: retry.jitter,
+                timeout: retry.timeout_ms.map(std::time::Duration::from_millis),
+            };
+            
+            let mut retry_storage = RetryStorage::new(storage, policy);
+            
+            if let Some(threshold) = retry.failure_threshold {
+                retry_storage = retry_storage.with_circuit_breaker(
+                    threshold,
+                    std::time::Duration::from_millis(retry.reset_timeout_ms),
+                );
+            }
+            
+            Arc::new(retry_storage)
+        },
+        None => storage,
+    };
+    
+    // Fail over to a secondary file storage if configured
+    let storage: Arc<dyn DataStorage + Send + Sync> = match &config.storage.failover {
+        Some(failover) => {
+            let format = match failover.format.as_deref() {
+                Some("json") => FileFormat::Json,
+                Some("parquet") => FileFormat::Parquet,
+                Some("orc") => FileFormat::Orc,
+                _ => FileFormat::Csv,
+            };
+            
+            let secondary = match FileStorage::new(failover.path.clone(), format)
+                .and_then(|storage| encrypted(storage, &config.storage)) {
+                Ok(storage) => storage,
+                Err(err) => {
+                    error!("Error creating failover storage: {:?}", err);
+                    return Ok(());
+                }
+            };
+            
+            Arc::new(FailoverStorage::new(storage, Arc::new(secondary))
+                .with_check_interval(std::time::Duration::from_millis(failover.check_interval_ms))
+                .with_max_pending_writes(failover.max_pending_writes))
+        },
+        None => storage,
+    };
+    
+    // Add aliases and soft deletion
+    let mut catalog_storage = CatalogStorage::new(storage);
+    
+    if let Some(retention) = config.storage.trash_retention {
+        catalog_storage = catalog_storage.with_retention(std::time::Duration::from_secs(retention));
+    }
+    
+    if let Some(catalog_file) = &config.storage.catalog_file {
+        catalog_storage = match catalog_storage.with_catalog_file(catalog_file) {
+            Ok(storage) => storage,
+            Err(err) => {
+                error!("Error loading catalog file: {:?}", err);
+                return Ok(());
+            }
+        };
+    }
+    
+    // Protect read-only storage and immutable datasets
+    let mut protected_storage = ProtectedStorage::new(Arc::new(catalog_storage))
+        .with_read_only(config.storage.read_only);
+    
+    if let Some(lock_file) = &config.storage.lock_file {
+        protected_storage = match protected_storage.with_lock_file(lock_file) {
+            Ok(storage) => storage,
+            Err(err) => {
+                error!("Error loading lock file: {:?}", err);
+                return Ok(());
+            }
+        };
+    }
+    
+    let storage: Arc<dyn DataStorage + Send + Sync> = Arc::new(protected_storage);
+    
+    // Keep session-scoped temporary datasets out of the backend
+    let sessions = config.storage.session_ttl
+        .map(|ttl| Arc::new(SessionStorage::new(storage.clone(), std::time::Duration::from_secs(ttl))));
+    let storage: Arc<dyn DataStorage + Send + Sync> = match &sessions {
+        Some(sessions) => sessions.clone(),
+        None => storage,
+    };
+    
+    // Invalidate cached processing results on every write
+    let result_cache = config.storage.result_cache_entries.map(|entries| Arc::new(ResultCache::new(entries)));
+    let storage: Arc<dyn DataStorage + Send + Sync> = match &result_cache {
+        Some(cache) => Arc::new(InvalidatingStorage::new(storage, cache.clone())),
+        None => storage,
     };
     
     // Handle subcommands
@@ -132,10 +473,362 @@ async fn main() -> std::io::Result<()> {
             enable_cors: config.server.enable_cors,
         };
         
+        // Run maintenance in the background if scheduled
+        if let Some(interval) = config.storage.maintenance_interval {
+            let storage = storage.clone();
+            
+            std::thread::spawn(move || loop {
+                std::thread::sleep(std::time::Duration::from_secs(interval));
+                
+                match run_maintenance(storage.as_ref()) {
+                    Ok(report) => info!("Maintenance completed: {:?}", report),
+                    Err(err) => error!("Error running maintenance: {:?}", err),
+                }
+            });
+        }
+        
+        // Ingest files dropped in landing directories
+        let mut ingestion = None;
+        
+        if !config.ingestion.is_empty() {
+            let mut service = IngestionService::new(storage.clone());
+            
+            for zone_config in &config.ingestion {
+                let mode = match IngestMode::from_str(&zone_config.mode, &zone_config.key_columns) {
+                    Ok(mode) => mode,
+                    Err(err) => {
+                        error!("Error configuring ingestion of '{}': {:?}", zone_config.dataset, err);
+                        return Ok(());
+                    }
+                };
+                
+                let drift_policy = match DriftPolicy::from_str(&zone_config.on_drift) {
+                    Ok(policy) => policy,
+                    Err(err) => {
+                        error!("Error configuring ingestion of '{}': {:?}", zone_config.dataset, err);
+                        return Ok(());
+                    }
+                };
+                
+                let mut zone = LandingZone::new(&zone_config.dataset, &zone_config.landing_dir)
+                    .with_mode(mode)
+                    .with_drift_policy(drift_policy);
+                
+                if let Some(dir) = &zone_config.archive_dir {
+                    zone = zone.with_archive_dir(dir);
+                }
+                
+                if let Some(dir) = &zone_config.quarantine_dir {
+                    zone = zone.with_quarantine_dir(dir);
+                }
+                
+                service = service.add(zone);
+            }
+            
+            let service = Arc::new(service);
+            
+            if let Err(err) = service.clone().start(std::time::Duration::from_millis(INGEST_DEBOUNCE_MS)) {
+                error!("Error starting ingestion: {:?}", err);
+                return Ok(());
+            }
+            
+            ingestion = Some(service);
+        }
+        
         // Create and run server
         info!("Starting server at {}:{}", host, port);
-        let server = Server::new(storage, server_config);
+        let mut server = Server::new(storage, server_config);
+        
+        // Require API keys and apply row-level security policies
+        if let Some(security) = &config.server.security {
+            let mut access = AccessControl::new();
+            
+            for (key, role) in &security.api_keys {
+                access = access.with_api_key(key.clone(), role.clone());
+            }
+            
+            for role in &security.admin_roles {
+                access = access.with_admin_role(role.clone());
+            }
+            
+            if let Some(policy_file) = &security.policy_file {
+                access = match access.with_policy_file(policy_file) {
+                    Ok(access) => access,
+                    Err(err) => {
+                        error!("Error loading policy file: {:?}", err);
+                        return Ok(());
+                    }
+                };
+            }
+            
+            server = server.with_access_control(access);
+        }
+        
+        if let Some(cache) = result_cache {
+            server = server.with_result_cache(cache);
+        }
+        
+        if let Some(sessions) = sessions {
+            server = server.with_sessions(sessions);
+        }
+        
+        if let Some(ingestion) = ingestion {
+            server = server.with_ingestion(ingestion);
+        }
+        
+        if let Some(timeout) = config.server.processing_timeout_ms {
+            server = server.with_processing_timeout(std::time::Duration::from_millis(timeout));
+        }
+        
+        // Cap inline responses, so large results are paged or stored instead
+        if config.server.max_response_rows.is_some() || config.server.max_response_bytes.is_some() {
+            let mut limit = ResponseLimit::new();
+            
+            if let Some(rows) = config.server.max_response_rows {
+                limit = limit.with_max_rows(rows);
+            }
+            
+            if let Some(bytes) = config.server.max_response_bytes {
+                limit = limit.with_max_bytes(bytes);
+            }
+            
+            server = server.with_response_limit(limit);
+        }
+        
+        // Truncate and redact values of datasets returned by reads
+        if !config.server.response_policies.is_empty() {
+            let mut policies = ResponsePolicies::new();
+            
+            for (dataset, policy_config) in &config.server.response_policies {
+                let mut policy = ResponsePolicy::new().with_hide_binary(policy_config.hide_binary);
+                
+                if let Some(length) = policy_config.max_string_length {
+                    policy = policy.with_max_string_length(length);
+                }
+                
+                for column in &policy_config.redact {
+                    policy = policy.with_redacted_column(column.clone());
+                }
+                
+                policies = policies.with_policy(dataset.clone(), policy);
+            }
+            
+            server = server.with_response_policies(policies);
+        }
+        
+        // Let administrators export datasets under a root directory
+        if let Some(export_root) = &config.server.export_root {
+            server = server.with_export_root(export_root);
+        }
+        
+        // Export request traces and metrics to an OpenTelemetry collector
+        let telemetry = match &config.telemetry {
+            Some(_) if !cfg!(feature = "otlp") => {
+                error!("Telemetry is configured but OTLP export support is not enabled");
+                None
+            },
+            telemetry_config => telemetry_config.as_ref().map(telemetry),
+        };
+        
+        if let (Some(telemetry), Some(memory_storage)) = (&telemetry, &limited_memory) {
+            let memory_storage = memory_storage.clone();
+            telemetry.observe_gauge("rdpe.storage.memory.used", "By", move || {
+                memory_storage.usage().map_or(0, |usage| usage.used_bytes as i64)
+            });
+        }
+        
+        if let (Some(telemetry), Some(telemetry_config)) = (&telemetry, &config.telemetry) {
+            telemetry.clone().start(std::time::Duration::from_millis(telemetry_config.export_interval_ms));
+            server = server.with_telemetry(telemetry.clone());
+        }
+        
         server.run().await?;
+        
+        // Send what was recorded since the last export
+        if let Some(telemetry) = &telemetry {
+            if let Err(err) = telemetry.export() {
+                error!("Error exporting telemetry: {}", err);
+            }
+        }
+        
+        // Persist memory storage after graceful shutdown
+        if let Some((memory_storage, snapshot_storage)) = &memory_snapshot {
+            match memory_storage.snapshot(snapshot_storage) {
+                Ok(count) => info!("Saved {} datasets to snapshot", count),
+                Err(err) => error!("Error saving snapshot: {:?}", err),
+            }
+        }
+    } else if matches.subcommand_matches("maintain").is_some() {
+        match run_maintenance(storage.as_ref()) {
+            Ok(report) => {
+                println!("Datasets:           {}", report.datasets);
+                println!("Total bytes:        {}", report.total_bytes);
+                println!("Trash purged:       {}", report.trash_purged);
+                println!("Aliases removed:    {}", report.aliases_removed);
+                println!("Cache evicted:      {}", report.cache_evicted);
+                println!("Files re-encrypted: {}", report.files_reencrypted);
+                println!("Sessions expired:   {}", report.sessions_expired);
+                println!("Bytes reclaimed:    {}", report.bytes_reclaimed);
+                println!("Writes replayed:    {}", report.writes_replayed);
+            },
+            Err(err) => error!("Error running maintenance: {:?}", err),
+        }
+    } else if let Some(backup_matches) = matches.subcommand_matches("backup") {
+        let path = backup_matches.value_of("file").unwrap();
+        
+        let result = std::fs::File::create(path)
+            .map_err(StorageError::IoError)
+            .and_then(|file| backup_storage(storage.as_ref(), std::io::BufWriter::new(file)));
+        
+        match result {
+            Ok(manifest) => println!("Backed up {} datasets to {}", manifest.datasets.len(), path),
+            Err(err) => error!("Error backing up storage: {:?}", err),
+        }
+    } else if let Some(restore_matches) = matches.subcommand_matches("restore") {
+        let path = restore_matches.value_of("file").unwrap();
+        
+        let result = std::fs::File::open(path)
+            .map_err(StorageError::IoError)
+            .and_then(|file| restore_storage(storage.as_ref(), std::io::BufReader::new(file), restore_matches.is_present("overwrite")));
+        
+        match result {
+            Ok(manifest) => println!("Restored {} datasets from {}", manifest.datasets.len(), path),
+            Err(err) => error!("Error restoring backup: {:?}", err),
+        }
+    } else if let Some(show_matches) = matches.subcommand_matches("show") {
+        let name = show_matches.value_of("dataset").unwrap();
+        
+        let format = match OutputFormat::from_str(show_matches.value_of("output-format").unwrap_or("table")) {
+            Ok(format) => format,
+            Err(err) => {
+                error!("{}", err);
+                return Ok(());
+            }
+        };
+        
+        let mut data = match storage.load(name) {
+            Ok(data) => data,
+            Err(err) => {
+                error!("Error loading dataset '{}': {:?}", name, err);
+                return Ok(());
+            }
+        };
+        
+        if let Some(filter) = show_matches.value_of("filter") {
+            data = match FilterProcessor::expression(filter).and_then(|filter| filter.process(&data)) {
+                Ok(data) => data,
+                Err(err) => {
+                    error!("Error filtering dataset '{}': {}", name, err);
+                    return Ok(());
+                }
+            };
+        }
+        
+        if let Some(limit) = show_matches.value_of("limit").and_then(|s| s.parse::<usize>().ok()) {
+            data.data.truncate(limit);
+        }
+        
+        let stdout = std::io::stdout();
+        if let Err(err) = format.write(&data, stdout.lock()) {
+            error!("Error printing dataset '{}': {}", name, err);
+        }
+    } else if let Some(watch_matches) = matches.subcommand_matches("watch") {
+        let spec_path = PathBuf::from(watch_matches.value_of("pipeline").unwrap());
+        let input = PathBuf::from(watch_matches.value_of("input").unwrap());
+        let output = watch_matches.value_of("output").unwrap();
+        
+        let params = match watch_matches.values_of("param").into_iter().flatten().map(parse_parameter).collect() {
+            Ok(params) => params,
+            Err(err) => {
+                error!("{}", err);
+                return Ok(());
+            }
+        };
+        
+        // Reload the spec and input on every run, so both can be edited while watching
+        let run = || {
+            let started = std::time::Instant::now();
+            let result = PipelineTemplate::from_file(&spec_path)
+                .and_then(|template| template.resolve(&params))
+                .and_then(|spec| spec.build())
+                .map_err(|err| err.to_string())
+                .and_then(|pipeline| {
+                    let data = load_input(&input).map_err(|err| format!("{:?}", err))?;
+                    pipeline.execute_owned(data).map_err(|err| err.to_string())
+                })
+                .and_then(|result| {
+                    storage.store(output, &result).map_err(|err| format!("{:?}", err))?;
+                    Ok(result.len())
+                });
+            
+            match result {
+                Ok(rows) => info!("Stored {} rows in '{}' in {:?}", rows, output, started.elapsed()),
+                Err(err) => error!("Error running pipeline: {}", err),
+            }
+        };
+        
+        run();
+        info!("Watching {} and {} for changes", spec_path.display(), input.display());
+        
+        let watched = [spec_path.clone(), input.clone()];
+        let result = watch_paths(&watched, std::time::Duration::from_millis(WATCH_DEBOUNCE_MS), |changed| {
+            info!("Changed: {:?}", changed);
+            run();
+            true
+        });
+        
+        if let Err(err) = result {
+            error!("Error watching files: {}", err);
+        }
+    } else if let Some(fetch_matches) = matches.subcommand_matches("fetch") {
+        let remote = fetch_matches.value_of("remote").unwrap();
+        let output = fetch_matches.value_of("output").unwrap();
+        
+        let source = match remote_source(&config, remote, fetch_matches.value_of("pattern").unwrap(), fetch_matches.value_of("since")) {
+            Ok(source) => source,
+            Err(err) => {
+                error!("{}", err);
+                return Ok(());
+            }
+        };
+        
+        match source.read() {
+            Ok(data) => match storage.store(output, &data) {
+                Ok(()) => println!("Stored {} rows from {} in '{}'", data.len(), source.name(), output),
+                Err(err) => error!("Error storing dataset '{}': {:?}", output, err),
+            },
+            Err(err) => error!("Error fetching from {}: {}", source.name(), err),
+        }
+    } else if let Some(report_matches) = matches.subcommand_matches("report") {
+        let name = report_matches.value_of("dataset").unwrap();
+        let output = report_matches.value_of("output")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(format!("{}.html", name)));
+        let sample = report_matches.value_of("sample")
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(REPORT_SAMPLE_ROWS);
+        
+        let data = match storage.load(name) {
+            Ok(data) => data,
+            Err(err) => {
+                error!("Error loading dataset '{}': {:?}", name, err);
+                return Ok(());
+            }
+        };
+        
+        let profile = match DatasetProfile::compute(&data, REPORT_HISTOGRAM_BINS) {
+            Ok(profile) => profile,
+            Err(err) => {
+                error!("Error profiling dataset '{}': {:?}", name, err);
+                return Ok(());
+            }
+        };
+        
+        let mut sample_rows = data.clone();
+        sample_rows.data.truncate(sample);
+        
+        std::fs::write(&output, render_html_report(name, &profile, &sample_rows))?;
+        println!("Wrote report of '{}' to {}", name, output.display());
     } else {
         println!("No subcommand specified. Use --help for usage information.");
     }
@@ -143,3 +836,176 @@ async fn main() -> std::io::Result<()> {
     Ok(())
 }
 
+/// Option selecting how processing subcommands print their results
+fn output_format_arg() -> Arg<'static, 'static> {
+    Arg::with_name("output-format")
+        .long("output-format")
+        .value_name("FORMAT")
+        .help("Sets the output format: table, csv, json or markdown")
+        .possible_values(&["table", "csv", "json", "markdown"])
+        .takes_value(true)
+}
+
+/// Create a source for files on a configured SFTP or FTP server
+fn remote_source(config: &Config, remote: &str, pattern: &str, since: Option<&str>) -> Result<Box<dyn DataSource>, String> {
+    let remote_config = config.sftp.get(remote)
+        .ok_or_else(|| format!("Server '{}' is not configured", remote))?;
+    
+    let secret = |var: &str| std::env::var(var)
+        .map_err(|_| format!("Environment variable {} is not set", var));
+    
+    let since = since
+        .map(|since| chrono::DateTime::parse_from_rfc3339(since)
+            .map(|since| since.with_timezone(&chrono::Utc))
+            .map_err(|err| format!("Invalid time {}: {}", since, err)))
+        .transpose()?;
+    
+    if remote_config.protocol == "ftp" {
+        let password = remote_config.password_env.as_deref()
+            .ok_or_else(|| format!("FTP server '{}' needs a password_env", remote))
+            .and_then(secret)?;
+        
+        let mut source = FtpSource::new(&remote_config.host, &remote_config.username, &password)
+            .with_pattern(pattern);
+        
+        if let Some(port) = remote_config.port {
+            source = source.with_port(port);
+        }
+        
+        if let Some(since) = since {
+            source = source.with_modified_since(since);
+        }
+        
+        return Ok(Box::new(source));
+    }
+    
+    if remote_config.protocol != "sftp" {
+        return Err(format!("Unknown protocol '{}' of server '{}'; use sftp or ftp", remote_config.protocol, remote));
+    }
+    
+    let auth = match (&remote_config.password_env, &remote_config.private_key) {
+        (Some(var), _) => SftpAuth::Password(secret(var)?),
+        (None, Some(path)) => SftpAuth::PrivateKey {
+            path: path.clone(),
+            passphrase: remote_config.passphrase_env.as_deref().map(secret).transpose()?,
+        },
+        (None, None) => SftpAuth::Agent,
+    };
+    
+    let mut source = SftpSource::new(&remote_config.host, &remote_config.username, auth)
+        .with_pattern(pattern);
+    
+    if let Some(port) = remote_config.port {
+        source = source.with_port(port);
+    }
+    
+    match (&remote_config.host_fingerprint, &remote_config.known_hosts) {
+        (Some(fingerprint), _) => source = source.with_host_fingerprint(fingerprint),
+        (None, Some(path)) => source = source.with_known_hosts(path),
+        (None, None) => {},
+    }
+    
+    if let Some(since) = since {
+        source = source.with_modified_since(since);
+    }
+    
+    Ok(Box::new(source))
+}
+
+/// Read a data file, or every data file of a directory as one dataset
+///
+/// Files of a directory are read in name order and must share a schema;
+/// files without a csv, json or parquet extension are ignored.
+fn load_input(path: &std::path::Path) -> Result<DataSet, StorageError> {
+    let files = if path.is_dir() {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+            .map_err(StorageError::IoError)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| file.is_file())
+            .collect();
+        
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+    
+    let mut combined: Option<DataSet> = None;
+    
+    for file in &files {
+        // Skip files of other formats in directories
+        let known = file.extension()
+            .and_then(|ext| ext.to_str())
+            .map_or(false, |ext| FileFormat::from_extension(ext).is_ok());
+        
+        if !known && path.is_dir() {
+            continue;
+        }
+        
+        let data = read_data_file(file)?;
+        
+        combined = match combined {
+            None => Some(data),
+            Some(mut combined) => {
+                let names = |data: &DataSet| data.schema.fields.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+                
+                if names(&combined) != names(&data) {
+                    return Err(StorageError::InvalidFormat(format!(
+                        "{} does not have the columns of the files before it", file.display()
+                    )));
+                }
+                
+                combined.data.extend(data.data);
+                Some(combined)
+            },
+        };
+    }
+    
+    combined.ok_or_else(|| StorageError::NotFound(path.display().to_string()))
+}
+
+/// Enable encryption at rest on file storage if configured
+fn encrypted(storage: FileStorage, config: &StorageConfig) -> Result<FileStorage, StorageError> {
+    match &config.encryption {
+        Some(encryption) => {
+            let keys = match &encryption.keys {
+                Some(keys) => KeyRing::parse(keys)?,
+                None => KeyRing::from_env(&encryption.key_env)?,
+            };
+            
+            storage.with_encryption(Arc::new(keys))
+        },
+        None => Ok(storage),
+    }
+}
+
+/// Read file storage through memory maps if configured
+fn mapped(storage: FileStorage, config: &StorageConfig) -> Result<FileStorage, StorageError> {
+    if config.mmap {
+        storage.with_mmap()
+    } else {
+        Ok(storage)
+    }
+}
+
+/// Write Parquet datasets in the narrowest column types if configured
+fn downcasting(storage: FileStorage, config: &StorageConfig) -> FileStorage {
+    if config.downcast {
+        storage.with_downcasting()
+    } else {
+        storage
+    }
+}
+
+/// Create the telemetry exporter configured for the server
+fn telemetry(config: &TelemetryConfig) -> Arc<Telemetry> {
+    let mut telemetry = Telemetry::new(&config.endpoint, &config.service_name)
+        .with_traces(config.traces)
+        .with_metrics(config.metrics);
+    
+    for (name, value) in &config.headers {
+        telemetry = telemetry.with_header(name, value);
+    }
+    
+    Arc::new(telemetry)
+}
@@ -1,13 +1,19 @@
 // Aggregate operations for data processing
 // Author: Gabriel Demetrios Lafis
 
+use std::any::Any;
 use std::collections::HashMap;
 
-use crate::data::{DataSet, DataType, Field, Row, Schema, Value};
-use super::{DataProcessor, ProcessingError, ProcessorType};
+use serde_json::Value as JsonValue;
+
+use crate::data::{DataSet, DataType, Decimal, Dictionary, Field, MAX_DECIMAL_PRECISION, Row, Schema, Value};
+use super::{AggregateOptions, AggregationSpec, CancellationToken, DataProcessor, FilterExpression, KeyPart, NanPolicy, OverflowPolicy, ProcessingError, ProcessorType, RowKeys, StepSpec, TableEstimate, WarningKind, aggregate_function, emit_warning, partition_count, partition_rows, process_partitions, promoted_type};
 
 /// Represents an aggregation function
 pub trait AggregateFunction: Send + Sync {
+    /// State built up from the values of a group
+    type State: Send + 'static;
+    
     /// Get the name of the aggregation function
     fn name(&self) -> &str;
     
@@ -15,19 +21,105 @@ pub trait AggregateFunction: Send + Sync {
     fn output_type(&self, input_type: &DataType) -> DataType;
     
     /// Initialize the aggregation state
-    fn init(&self) -> Box<dyn std::any::Any + Send>;
+    fn init(&self) -> Self::State;
     
     /// Update the aggregation state with a new value
-    fn update(&self, state: &mut Box<dyn std::any::Any + Send>, value: &Value);
+    fn update(&self, state: &mut Self::State, value: &Value);
+    
+    /// Merge a partial aggregation state into another
+    ///
+    /// States built from separate batches, threads or partitions of the same
+    /// group merge into the state of all their values, so merging and then
+    /// finalizing gives the same result as updating a single state.
+    fn merge(&self, state: &mut Self::State, other: Self::State);
+    
+    /// Finalize the aggregation and return the result
+    fn finalize(&self, state: Self::State) -> Value;
+    
+    /// Check the aggregation state for errors, such as an overflow, before finalizing
+    fn check(&self, _state: &Self::State) -> Result<(), ProcessingError> {
+        Ok(())
+    }
+}
+
+/// Aggregation state of a `DynAggregateFunction`
+pub struct AggregateState(Box<dyn Any + Send>);
+
+/// Aggregation function with its state type erased, so functions with
+/// different states can be held together
+///
+/// Every `AggregateFunction` is one. States are downcast once per call, and
+/// `update_all` takes all the values of a group at once, so the cost of
+/// erasure is paid per group rather than per value.
+pub trait DynAggregateFunction: Send + Sync {
+    /// Get the name of the aggregation function
+    fn name(&self) -> &str;
+    
+    /// Get the output data type of the aggregation function
+    fn output_type(&self, input_type: &DataType) -> DataType;
+    
+    /// Initialize the aggregation state
+    fn init(&self) -> AggregateState;
+    
+    /// Update the aggregation state with every value of an iterator
+    fn update_all<'a>(&self, state: &mut AggregateState, values: &mut dyn Iterator<Item = &'a Value>);
+    
+    /// Merge a partial aggregation state into another
+    fn merge(&self, state: &mut AggregateState, other: AggregateState);
+    
+    /// Check the aggregation state for errors, such as an overflow, before finalizing
+    fn check(&self, state: &AggregateState) -> Result<(), ProcessingError>;
     
     /// Finalize the aggregation and return the result
-    fn finalize(&self, state: Box<dyn std::any::Any + Send>) -> Value;
+    fn finalize(&self, state: AggregateState) -> Value;
+}
+
+/// Panic message for a state passed to a function that did not create it
+const FOREIGN_STATE: &str = "aggregation state was created by another aggregation function";
+
+impl<F: AggregateFunction> DynAggregateFunction for F {
+    fn name(&self) -> &str {
+        AggregateFunction::name(self)
+    }
+    
+    fn output_type(&self, input_type: &DataType) -> DataType {
+        AggregateFunction::output_type(self, input_type)
+    }
+    
+    fn init(&self) -> AggregateState {
+        AggregateState(Box::new(AggregateFunction::init(self)))
+    }
+    
+    fn update_all<'a>(&self, state: &mut AggregateState, values: &mut dyn Iterator<Item = &'a Value>) {
+        let state = state.0.downcast_mut::<F::State>().expect(FOREIGN_STATE);
+        
+        for value in values {
+            AggregateFunction::update(self, state, value);
+        }
+    }
+    
+    fn merge(&self, state: &mut AggregateState, other: AggregateState) {
+        let state = state.0.downcast_mut::<F::State>().expect(FOREIGN_STATE);
+        let other = *other.0.downcast::<F::State>().expect(FOREIGN_STATE);
+        
+        AggregateFunction::merge(self, state, other);
+    }
+    
+    fn check(&self, state: &AggregateState) -> Result<(), ProcessingError> {
+        AggregateFunction::check(self, state.0.downcast_ref::<F::State>().expect(FOREIGN_STATE))
+    }
+    
+    fn finalize(&self, state: AggregateState) -> Value {
+        AggregateFunction::finalize(self, *state.0.downcast::<F::State>().expect(FOREIGN_STATE))
+    }
 }
 
 /// Count aggregation function
 pub struct CountFunction;
 
 impl AggregateFunction for CountFunction {
+    type State = i64;
+    
     fn name(&self) -> &str {
         "count"
     }
@@ -36,112 +128,260 @@ impl AggregateFunction for CountFunction {
         DataType::Integer
     }
     
-    fn init(&self) -> Box<dyn std::any::Any + Send> {
-        Box::new(0i64)
+    fn init(&self) -> i64 {
+        0
     }
     
-    fn update(&self, state: &mut Box<dyn std::any::Any + Send>, value: &Value) {
+    fn update(&self, count: &mut i64, value: &Value) {
         if !matches!(value, Value::Null) {
-            let count = state.downcast_mut::<i64>().unwrap();
             *count += 1;
         }
     }
     
-    fn finalize(&self, state: Box<dyn std::any::Any + Send>) -> Value {
-        let count = *state.downcast::<i64>().unwrap();
+    fn merge(&self, count: &mut i64, other: i64) {
+        *count += other;
+    }
+    
+    fn finalize(&self, count: i64) -> Value {
         Value::Integer(count)
     }
 }
 
 /// Sum aggregation function
-pub struct SumFunction;
+///
+/// Integer sums that overflow i64 follow the overflow policy: an error,
+/// a sum clamped to the i64 range, or a float sum from then on. Decimals
+/// are summed exactly; decimal sums past 38 digits fail unless promoted to
+/// floats.
+#[derive(Default)]
+pub struct SumFunction {
+    overflow: OverflowPolicy,
+}
+
+/// State of a sum
+#[derive(Debug, Clone, Default)]
+pub struct SumState {
+    int_sum: i64,
+    float_sum: f64,
+    decimal_sum: Option<Decimal>,
+    is_float: bool,
+    overflowed: bool,
+}
+
+impl SumFunction {
+    /// Create a new sum function that fails on overflow
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Set the overflow policy
+    pub fn with_overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+}
 
 impl AggregateFunction for SumFunction {
+    type State = SumState;
+    
     fn name(&self) -> &str {
         "sum"
     }
     
     fn output_type(&self, input_type: &DataType) -> DataType {
         match input_type {
-            DataType::Integer => DataType::Integer,
+            t if t.is_integer() => DataType::Integer,
             DataType::Float => DataType::Float,
+            // Sums keep the scale, with room for ten more whole digits
+            DataType::Decimal(precision, scale) => DataType::Decimal((precision + 10).min(MAX_DECIMAL_PRECISION), *scale),
             _ => DataType::Float, // Default to float for other types
         }
     }
     
-    fn init(&self) -> Box<dyn std::any::Any + Send> {
-        Box::new((0i64, 0.0f64, false)) // (int_sum, float_sum, is_float)
+    fn init(&self) -> SumState {
+        SumState::default()
     }
     
-    fn update(&self, state: &mut Box<dyn std::any::Any + Send>, value: &Value) {
-        let (int_sum, float_sum, is_float) = state.downcast_mut::<(i64, f64, bool)>().unwrap();
-        
+    fn update(&self, state: &mut SumState, value: &Value) {
         match value {
             Value::Integer(i) => {
-                if *is_float {
-                    *float_sum += *i as f64;
+                if state.is_float {
+                    state.float_sum += *i as f64;
+                } else if let Some(sum) = state.int_sum.checked_add(*i) {
+                    state.int_sum = sum;
                 } else {
-                    *int_sum += *i;
+                    match self.overflow {
+                        OverflowPolicy::Error => state.overflowed = true,
+                        OverflowPolicy::Saturate => state.int_sum = state.int_sum.saturating_add(*i),
+                        OverflowPolicy::Promote => {
+                            state.float_sum = state.int_sum as f64 + *i as f64;
+                            state.is_float = true;
+                        },
+                    }
                 }
             },
             Value::Float(f) => {
-                if !*is_float {
-                    *float_sum = *int_sum as f64;
-                    *is_float = true;
+                if !state.is_float {
+                    state.float_sum = state.int_sum as f64;
+                    state.is_float = true;
+                }
+                state.float_sum += *f;
+            },
+            Value::Decimal(d) => {
+                if state.is_float {
+                    state.float_sum += d.to_f64();
+                    return;
+                }
+                
+                let sum = match &state.decimal_sum {
+                    Some(sum) => sum.checked_add(d),
+                    None => Some(*d),
+                };
+                
+                match sum {
+                    Some(sum) => state.decimal_sum = Some(sum),
+                    None if self.overflow == OverflowPolicy::Promote => {
+                        state.float_sum = state.int_sum as f64 + state.decimal_sum.take().map_or(0.0, |sum| sum.to_f64()) + d.to_f64();
+                        state.is_float = true;
+                    },
+                    None => state.overflowed = true,
                 }
-                *float_sum += *f;
             },
             _ => {}, // Ignore other types
         }
     }
     
-    fn finalize(&self, state: Box<dyn std::any::Any + Send>) -> Value {
-        let (int_sum, float_sum, is_float) = *state.downcast::<(i64, f64, bool)>().unwrap();
+    fn merge(&self, state: &mut SumState, other: SumState) {
+        // Adding the other sum as a value applies the overflow policy
+        let other_sum = if other.is_float { Value::Float(other.float_sum) } else { Value::Integer(other.int_sum) };
+        self.update(state, &other_sum);
         
-        if is_float {
-            Value::Float(float_sum)
-        } else {
-            Value::Integer(int_sum)
+        if let Some(decimal_sum) = other.decimal_sum {
+            self.update(state, &Value::Decimal(decimal_sum));
+        }
+        
+        state.overflowed |= other.overflowed;
+    }
+    
+    fn finalize(&self, state: SumState) -> Value {
+        if state.is_float {
+            return Value::Float(state.float_sum + state.decimal_sum.map_or(0.0, |sum| sum.to_f64()));
+        }
+        
+        match state.decimal_sum {
+            Some(sum) => sum.checked_add(&Decimal::from(state.int_sum))
+                .map_or_else(|| Value::Float(sum.to_f64() + state.int_sum as f64), Value::Decimal),
+            None => Value::Integer(state.int_sum),
         }
     }
+    
+    fn check(&self, state: &SumState) -> Result<(), ProcessingError> {
+        if state.overflowed {
+            return Err(ProcessingError::InvalidOperation("Sum overflowed".to_string()));
+        }
+        
+        Ok(())
+    }
 }
 
+/// Fraction digits averages of decimals have beyond those of their inputs
+const AVG_EXTRA_SCALE: u8 = 4;
+
 /// Average aggregation function
+///
+/// Averages of decimals are exact decimals with four more fraction digits
+/// than their inputs, rounded half to even.
 pub struct AvgFunction;
 
+/// State of an average
+#[derive(Debug, Clone, Default)]
+pub struct AvgState {
+    sum: f64,
+    count: i64,
+    decimal_sum: Option<Decimal>,
+    decimal_count: i64,
+}
+
 impl AggregateFunction for AvgFunction {
+    type State = AvgState;
+    
     fn name(&self) -> &str {
         "avg"
     }
     
-    fn output_type(&self, _input_type: &DataType) -> DataType {
-        DataType::Float
+    fn output_type(&self, input_type: &DataType) -> DataType {
+        match input_type {
+            DataType::Decimal(precision, scale) => {
+                let scale = (scale + AVG_EXTRA_SCALE).min(MAX_DECIMAL_PRECISION);
+                DataType::Decimal((precision + AVG_EXTRA_SCALE).min(MAX_DECIMAL_PRECISION), scale)
+            },
+            _ => DataType::Float,
+        }
     }
     
-    fn init(&self) -> Box<dyn std::any::Any + Send> {
-        Box::new((0.0f64, 0i64)) // (sum, count)
+    fn init(&self) -> AvgState {
+        AvgState::default()
     }
     
-    fn update(&self, state: &mut Box<dyn std::any::Any + Send>, value: &Value) {
-        let (sum, count) = state.downcast_mut::<(f64, i64)>().unwrap();
-        
+    fn update(&self, state: &mut AvgState, value: &Value) {
         match value {
             Value::Integer(i) => {
-                *sum += *i as f64;
-                *count += 1;
+                state.sum += *i as f64;
+                state.count += 1;
             },
             Value::Float(f) => {
-                *sum += *f;
-                *count += 1;
+                state.sum += *f;
+                state.count += 1;
+            },
+            Value::Decimal(d) => {
+                // Sums too large for a decimal carry on as floats
+                match state.decimal_sum.map_or(Some(*d), |sum| sum.checked_add(d)) {
+                    Some(sum) => {
+                        state.decimal_sum = Some(sum);
+                        state.decimal_count += 1;
+                    },
+                    None => {
+                        state.sum += d.to_f64();
+                        state.count += 1;
+                    },
+                }
             },
             _ => {}, // Ignore other types
         }
     }
     
-    fn finalize(&self, state: Box<dyn std::any::Any + Send>) -> Value {
-        let (sum, count) = *state.downcast::<(f64, i64)>().unwrap();
+    fn merge(&self, state: &mut AvgState, other: AvgState) {
+        state.sum += other.sum;
+        state.count += other.count;
+        
+        if let Some(decimal_sum) = other.decimal_sum {
+            match state.decimal_sum.map_or(Some(decimal_sum), |sum| sum.checked_add(&decimal_sum)) {
+                Some(sum) => state.decimal_sum = Some(sum),
+                None => {
+                    state.sum += decimal_sum.to_f64();
+                    state.count += other.decimal_count;
+                    return;
+                },
+            }
+        }
+        
+        state.decimal_count += other.decimal_count;
+    }
+    
+    fn finalize(&self, state: AvgState) -> Value {
+        // Only averages of decimals alone stay exact
+        if let (Some(sum), 0) = (state.decimal_sum, state.count) {
+            let scale = (sum.scale() + AVG_EXTRA_SCALE as u32).min(MAX_DECIMAL_PRECISION as u32);
+            
+            if let Some(avg) = sum.checked_div(state.decimal_count, scale) {
+                return Value::Decimal(avg);
+            }
+        }
+        
+        let count = state.count + state.decimal_count;
         
         if count > 0 {
+            let sum = state.sum + state.decimal_sum.map_or(0.0, |sum| sum.to_f64());
             Value::Float(sum / count as f64)
         } else {
             Value::Null
@@ -149,69 +389,118 @@ impl AggregateFunction for AvgFunction {
     }
 }
 
-/// Min aggregation function
-pub struct MinFunction;
+/// State of a min or max: the extreme value seen of each type
+///
+/// Integers win over floats, floats over decimals and decimals over strings
+/// when finalizing.
+#[derive(Debug, Clone, Default)]
+pub struct ExtremeState {
+    int: Option<i64>,
+    float: Option<f64>,
+    decimal: Option<Decimal>,
+    string: Option<String>,
+}
 
-impl AggregateFunction for MinFunction {
-    fn name(&self) -> &str {
-        "min"
-    }
-    
-    fn output_type(&self, input_type: &DataType) -> DataType {
-        input_type.clone()
-    }
-    
-    fn init(&self) -> Box<dyn std::any::Any + Send> {
-        Box::new((i64::MAX, f64::MAX, String::new(), false, false, false)) // (int_min, float_min, string_min, has_int, has_float, has_string)
-    }
-    
-    fn update(&self, state: &mut Box<dyn std::any::Any + Send>, value: &Value) {
-        let (int_min, float_min, string_min, has_int, has_float, has_string) = 
-            state.downcast_mut::<(i64, f64, String, bool, bool, bool)>().unwrap();
-        
+impl ExtremeState {
+    /// Keep a value if it is more extreme than the one of its type, where
+    /// `replaces` tells whether a new value replaces the current one
+    fn update(&mut self, value: &Value, replaces: fn(std::cmp::Ordering) -> bool) {
         match value {
             Value::Integer(i) => {
-                if !*has_int || *i < *int_min {
-                    *int_min = *i;
-                    *has_int = true;
+                if self.int.map_or(true, |current| replaces(i.cmp(&current))) {
+                    self.int = Some(*i);
                 }
             },
             Value::Float(f) => {
-                if !*has_float || *f < *float_min {
-                    *float_min = *f;
-                    *has_float = true;
+                let better = match self.float {
+                    Some(current) => f.partial_cmp(&current).map_or(false, replaces),
+                    None => true,
+                };
+                if better {
+                    self.float = Some(*f);
+                }
+            },
+            Value::Decimal(d) => {
+                if self.decimal.map_or(true, |current| replaces(d.cmp(&current))) {
+                    self.decimal = Some(*d);
                 }
             },
             Value::String(s) => {
-                if !*has_string || s < string_min {
-                    *string_min = s.clone();
-                    *has_string = true;
+                if self.string.as_ref().map_or(true, |current| replaces(s.as_str().cmp(current.as_str()))) {
+                    self.string = Some(s.clone());
                 }
             },
             _ => {}, // Ignore other types
         }
     }
     
-    fn finalize(&self, state: Box<dyn std::any::Any + Send>) -> Value {
-        let (int_min, float_min, string_min, has_int, has_float, has_string) = 
-            *state.downcast::<(i64, f64, String, bool, bool, bool)>().unwrap();
+    /// Merge the extremes of another state
+    fn merge(&mut self, other: ExtremeState, replaces: fn(std::cmp::Ordering) -> bool) {
+        let values = [
+            other.int.map(Value::Integer),
+            other.float.map(Value::Float),
+            other.decimal.map(Value::Decimal),
+            other.string.map(Value::String),
+        ];
         
-        if has_int {
-            Value::Integer(int_min)
-        } else if has_float {
-            Value::Float(float_min)
-        } else if has_string {
-            Value::String(string_min)
+        for value in values.iter().flatten() {
+            self.update(value, replaces);
+        }
+    }
+    
+    /// Get the extreme value
+    fn finalize(self) -> Value {
+        if let Some(i) = self.int {
+            Value::Integer(i)
+        } else if let Some(f) = self.float {
+            Value::Float(f)
+        } else if let Some(d) = self.decimal {
+            Value::Decimal(d)
+        } else if let Some(s) = self.string {
+            Value::String(s)
         } else {
             Value::Null
         }
     }
 }
 
+/// Min aggregation function
+pub struct MinFunction;
+
+impl AggregateFunction for MinFunction {
+    type State = ExtremeState;
+    
+    fn name(&self) -> &str {
+        "min"
+    }
+    
+    fn output_type(&self, input_type: &DataType) -> DataType {
+        input_type.clone()
+    }
+    
+    fn init(&self) -> ExtremeState {
+        ExtremeState::default()
+    }
+    
+    fn update(&self, state: &mut ExtremeState, value: &Value) {
+        state.update(value, std::cmp::Ordering::is_lt);
+    }
+    
+    fn merge(&self, state: &mut ExtremeState, other: ExtremeState) {
+        state.merge(other, std::cmp::Ordering::is_lt);
+    }
+    
+    fn finalize(&self, state: ExtremeState) -> Value {
+        state.finalize()
+    }
+}
+
 /// Max aggregation function
 pub struct MaxFunction;
 
 impl AggregateFunction for MaxFunction {
+    type State = ExtremeState;
+    
     fn name(&self) -> &str {
         "max"
     }
@@ -220,57 +509,36 @@ impl AggregateFunction for MaxFunction {
         input_type.clone()
     }
     
-    fn init(&self) -> Box<dyn std::any::Any + Send> {
-        Box::new((i64::MIN, f64::MIN, String::new(), false, false, false)) // (int_max, float_max, string_max, has_int, has_float, has_string)
+    fn init(&self) -> ExtremeState {
+        ExtremeState::default()
     }
     
-    fn update(&self, state: &mut Box<dyn std::any::Any + Send>, value: &Value) {
-        let (int_max, float_max, string_max, has_int, has_float, has_string) = 
-            state.downcast_mut::<(i64, f64, String, bool, bool, bool)>().unwrap();
-        
-        match value {
-            Value::Integer(i) => {
-                if !*has_int || *i > *int_max {
-                    *int_max = *i;
-                    *has_int = true;
-                }
-            },
-            Value::Float(f) => {
-                if !*has_float || *f > *float_max {
-                    *float_max = *f;
-                    *has_float = true;
-                }
-            },
-            Value::String(s) => {
-                if !*has_string || s > string_max {
-                    *string_max = s.clone();
-                    *has_string = true;
-                }
-            },
-            _ => {}, // Ignore other types
-        }
+    fn update(&self, state: &mut ExtremeState, value: &Value) {
+        state.update(value, std::cmp::Ordering::is_gt);
     }
     
-    fn finalize(&self, state: Box<dyn std::any::Any + Send>) -> Value {
-        let (int_max, float_max, string_max, has_int, has_float, has_string) = 
-            *state.downcast::<(i64, f64, String, bool, bool, bool)>().unwrap();
-        
-        if has_int {
-            Value::Integer(int_max)
-        } else if has_float {
-            Value::Float(float_max)
-        } else if has_string {
-            Value::String(string_max)
-        } else {
-            Value::Null
-        }
+    fn merge(&self, state: &mut ExtremeState, other: ExtremeState) {
+        state.merge(other, std::cmp::Ordering::is_gt);
+    }
+    
+    fn finalize(&self, state: ExtremeState) -> Value {
+        state.finalize()
     }
 }
 
 /// Group by processor for aggregating data
+///
+/// Groups are emitted ordered by their key values. Large inputs are split
+/// into partitions by the hash of the group key, one per thread, so every
+/// group is aggregated by a single thread. A having expression drops groups
+/// by their key and aggregate values as they are aggregated.
 pub struct GroupByProcessor {
     group_by_columns: Vec<String>,
-    aggregations: Vec<(String, String, Box<dyn AggregateFunction>)>, // (output_name, input_column, function)
+    aggregations: Vec<(String, String, Box<dyn DynAggregateFunction>)>, // (output_name, input_column, function)
+    having: Option<FilterExpression>,
+    nan_policy: NanPolicy,
+    partitions: usize,
+    cancellation: CancellationToken,
 }
 
 impl GroupByProcessor {
@@ -279,9 +547,38 @@ impl GroupByProcessor {
         GroupByProcessor {
             group_by_columns: Vec::new(),
             aggregations: Vec::new(),
+            having: None,
+            nan_policy: NanPolicy::default(),
+            partitions: num_cpus::get(),
+            cancellation: CancellationToken::new(),
         }
     }
     
+    /// Set the maximum number of partitions processed in parallel
+    pub fn with_partitions(mut self, partitions: usize) -> Self {
+        self.partitions = partitions;
+        self
+    }
+    
+    /// Stop processing once the token is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+    
+    /// Set how NaN and infinite input values are handled
+    pub fn with_nan_policy(mut self, policy: NanPolicy) -> Self {
+        self.nan_policy = policy;
+        self
+    }
+    
+    /// Keep only groups matching an expression over the group by columns
+    /// and aggregation outputs, such as `count > 10`
+    pub fn having(mut self, expression: FilterExpression) -> Self {
+        self.having = Some(expression);
+        self
+    }
+    
     /// Add a column to group by
     pub fn group_by(mut self, column: &str) -> Self {
         self.group_by_columns.push(column.to_string());
@@ -303,6 +600,23 @@ impl GroupByProcessor {
         self
     }
     
+    /// Add an aggregation by the name of its function, such as `sum` or a
+    /// function added with `register_aggregate`
+    pub fn aggregate_named(
+        mut self,
+        output_name: &str,
+        input_column: &str,
+        function: &str,
+        options: &AggregateOptions,
+    ) -> Result<Self, ProcessingError> {
+        self.aggregations.push((
+            output_name.to_string(),
+            input_column.to_string(),
+            aggregate_function(function, options)?,
+        ));
+        Ok(self)
+    }
+    
     /// Add a count aggregation
     pub fn count(self, output_name: &str, input_column: &str) -> Self {
         self.aggregate(output_name, input_column, CountFunction)
@@ -310,7 +624,7 @@ impl GroupByProcessor {
     
     /// Add a sum aggregation
     pub fn sum(self, output_name: &str, input_column: &str) -> Self {
-        self.aggregate(output_name, input_column, SumFunction)
+        self.aggregate(output_name, input_column, SumFunction::new())
     }
     
     /// Add an average aggregation
@@ -327,6 +641,79 @@ impl GroupByProcessor {
     pub fn max(self, output_name: &str, input_column: &str) -> Self {
         self.aggregate(output_name, input_column, MaxFunction)
     }
+    
+    /// Group and aggregate the given rows, returning the output values of
+    /// each group passing the having expression
+    fn aggregate_rows(
+        &self,
+        input: &DataSet,
+        rows: &[usize],
+        keys: &RowKeys,
+        group_by_indices: &[usize],
+        agg_indices: &[usize],
+        output_schema: &Schema,
+    ) -> Result<Vec<Vec<Value>>, ProcessingError> {
+        // Group rows by the group by columns
+        let mut groups: HashMap<Vec<KeyPart>, Vec<&Row>> = HashMap::new();
+        
+        for (n, &r) in rows.iter().enumerate() {
+            self.cancellation.checkpoint(n)?;
+            groups.entry(keys.key(r)).or_default().push(&input.data[r]);
+        }
+        
+        let mut results = Vec::with_capacity(groups.len());
+        let mut skipped = vec![0; self.aggregations.len()];
+        
+        // Process each group
+        for (n, rows) in groups.into_values().enumerate() {
+            self.cancellation.checkpoint(n)?;
+            
+            let mut output_values: Vec<Value> = group_by_indices.iter()
+                .map(|&i| rows[0].values[i].clone())
+                .collect();
+            
+            // Aggregate the column of each aggregation over the group's rows
+            for (i, (_, input_column, function)) in self.aggregations.iter().enumerate() {
+                let col_idx = agg_indices[i];
+                let mut nan_error = None;
+                
+                let mut values = rows.iter().filter_map(|row| {
+                    match self.nan_policy.apply(&row.values[col_idx], input_column) {
+                        Ok(Some(value)) => Some(value),
+                        Ok(None) => {
+                            skipped[i] += 1;
+                            None
+                        },
+                        Err(err) => {
+                            nan_error.get_or_insert(err);
+                            None
+                        },
+                    }
+                });
+                
+                let mut state = function.init();
+                function.update_all(&mut state, &mut values);
+                
+                if let Some(err) = nan_error {
+                    return Err(err);
+                }
+                
+                function.check(&state)?;
+                output_values.push(function.finalize(state));
+            }
+            
+            let row = Row::new(output_values);
+            if self.having.as_ref().map_or(true, |having| having.matches(&row, output_schema)) {
+                results.push(row.values);
+            }
+        }
+        
+        for ((_, input_column, _), &count) in self.aggregations.iter().zip(&skipped) {
+            emit_warning(self.name(), WarningKind::ValuesSkipped, Some(input_column), count, "NaN and infinite values skipped");
+        }
+        
+        Ok(results)
+    }
 }
 
 impl DataProcessor for GroupByProcessor {
@@ -396,46 +783,47 @@ impl DataProcessor for GroupByProcessor {
         output_fields.extend(agg_output_fields);
         let output_schema = Schema::new(output_fields);
         
-        // Group rows by the group by columns
-        let mut groups: HashMap<Vec<Value>, Vec<&Row>> = HashMap::new();
-        
-        for row in &input.data {
-            let key: Vec<Value> = group_by_indices.iter()
-                .map(|&i| row.values[i].clone())
-                .collect();
-            
-            groups.entry(key).or_default().push(row);
+        // The having expression can only read output columns
+        if let Some(having) = &self.having {
+            for column in having.columns() {
+                if !output_schema.fields.iter().any(|field| field.name == column) {
+                    return Err(ProcessingError::InvalidArgument(format!(
+                        "Having column '{}' is not a group by column or aggregation output", column
+                    )));
+                }
+            }
         }
         
-        // Initialize result dataset
+        // Aggregate each partition of groups on its own thread
+        let partitions = partition_rows(
+            &input.data,
+            &group_by_indices,
+            partition_count(input.data.len(), self.partitions),
+        )?;
+        
+        // String key columns are dictionary encoded, so groups compare ids
+        let encode = vec![true; group_by_indices.len()];
+        let keys = RowKeys::new(input, &group_by_indices, &encode, &mut Dictionary::new());
+        
+        let groups = process_partitions(&partitions, |rows| {
+            self.aggregate_rows(input, rows, &keys, &group_by_indices, &agg_indices, &output_schema)
+        })?;
+        
+        // Emit groups ordered by key so results are the same on every run
+        let group_count = self.group_by_columns.len();
+        let mut groups: Vec<_> = groups.into_iter().flatten().collect();
+        groups.sort_by(|a, b| a[..group_count].cmp(&b[..group_count]));
+        
         let mut result = DataSet::new(output_schema);
         
-        // Process each group
-        for (key, rows) in groups {
-            // Initialize aggregation states
-            let mut agg_states: Vec<Box<dyn std::any::Any + Send>> = self.aggregations.iter()
-                .map(|(_, _, function)| function.init())
-                .collect();
-            
-            // Update aggregation states with each row
-            for row in rows {
-                for (i, (_, _, function)) in self.aggregations.iter().enumerate() {
-                    let col_idx = agg_indices[i];
-                    function.update(&mut agg_states[i], &row.values[col_idx]);
-                }
-            }
-            
-            // Finalize aggregations
-            let agg_results: Vec<Value> = self.aggregations.iter().enumerate()
-                .map(|(i, (_, _, function))| function.finalize(std::mem::replace(&mut agg_states[i], function.init())))
-                .collect();
-            
-            // Create output row
-            let mut output_values = key;
-            output_values.extend(agg_results);
-            
-            let output_row = Row::new(output_values);
-            result.add_row(output_row)?;
+        for output_values in groups {
+            result.add_row(Row::new(output_values))?;
+        }
+        
+        // Sums promoted past i64 hold floats
+        for j in 0..self.aggregations.len() {
+            let field = &mut result.schema.fields[group_count + j];
+            field.data_type = promoted_type(&field.data_type, result.data.iter().map(|row| &row.values[group_count + j]));
         }
         
         // Copy metadata
@@ -453,5 +841,31 @@ impl DataProcessor for GroupByProcessor {
     fn processor_type(&self) -> ProcessorType {
         ProcessorType::Aggregate
     }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        let aggregations = self.aggregations.iter()
+            .map(|(output_name, input_column, function)| AggregationSpec {
+                function: function.name().to_string(),
+                input_column: input_column.clone(),
+                output_name: output_name.clone(),
+            })
+            .collect();
+        
+        Ok(StepSpec::Aggregate {
+            group_by: self.group_by_columns.clone(),
+            aggregations,
+            having: self.having.as_ref().map(|having| having.to_string()),
+            nan_policy: self.nan_policy,
+        }.to_config())
+    }
+    
+    fn estimate(&self, input: &TableEstimate) -> Option<TableEstimate> {
+        let groups = input.group_by(&self.group_by_columns);
+        
+        Some(match &self.having {
+            Some(having) => groups.filter(having),
+            None => groups,
+        })
+    }
 }
 
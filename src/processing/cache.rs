@@ -0,0 +1,247 @@
+// Processing result cache
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+use crate::data::DataSet;
+use super::{ProcessingError, TableEstimate};
+
+/// Key of a cached result
+///
+/// A key combines the processing configuration with the write generation of
+/// every input dataset at the time the key was taken, so a write to any input
+/// makes older keys unreachable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResultKey {
+    config: String,
+    inputs: Vec<(String, u64)>,
+}
+
+/// Cached processing result
+struct CachedResult {
+    data: DataSet,
+    last_used: u64,
+}
+
+/// Hit and miss counters of a result cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResultCacheStats {
+    pub entries: usize,
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Cached result as listed for diagnostics
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultCacheEntry {
+    /// Processing configuration the result was computed for
+    pub config: String,
+    /// Input datasets the result was computed from
+    pub inputs: Vec<String>,
+    pub rows: usize,
+    pub columns: usize,
+}
+
+/// Cache of processing results keyed by configuration and input fingerprints
+///
+/// Re-running the same processing on unchanged inputs returns the stored
+/// result without loading the inputs again. Writes to a dataset must be
+/// reported through `invalidate`; `InvalidatingStorage` does this for every
+/// write made through it. When the cache is full the least recently used
+/// result is evicted. Column statistics of input datasets are kept the same
+/// way, for planning.
+pub struct ResultCache {
+    entries: RwLock<HashMap<ResultKey, CachedResult>>,
+    statistics: RwLock<HashMap<ResultKey, TableEstimate>>,
+    generations: RwLock<HashMap<String, u64>>,
+    capacity: usize,
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ResultCache {
+    /// Create a new result cache holding at most `capacity` results
+    pub fn new(capacity: usize) -> Self {
+        ResultCache {
+            entries: RwLock::new(HashMap::new()),
+            statistics: RwLock::new(HashMap::new()),
+            generations: RwLock::new(HashMap::new()),
+            capacity,
+            clock: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+    
+    /// Build the key for a configuration applied to the given inputs
+    ///
+    /// The key must be taken before the inputs are loaded, so that a write
+    /// racing with the computation leaves the result under a stale key.
+    pub fn key(&self, config: &str, inputs: &[&str]) -> Result<ResultKey, ProcessingError> {
+        let generations = self.generations.read().map_err(|_| {
+            ProcessingError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        Ok(ResultKey {
+            config: config.to_string(),
+            inputs: inputs.iter()
+                .map(|name| (name.to_string(), generations.get(*name).copied().unwrap_or(0)))
+                .collect(),
+        })
+    }
+    
+    /// Get a cached result
+    pub fn get(&self, key: &ResultKey) -> Result<Option<DataSet>, ProcessingError> {
+        let mut entries = self.entries.write().map_err(|_| {
+            ProcessingError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        match entries.get_mut(key) {
+            Some(entry) => {
+                entry.last_used = self.clock.fetch_add(1, Ordering::Relaxed);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Ok(Some(entry.data.clone()))
+            },
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Ok(None)
+            }
+        }
+    }
+    
+    /// Store a result
+    pub fn insert(&self, key: ResultKey, data: DataSet) -> Result<(), ProcessingError> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        
+        let mut entries = self.entries.write().map_err(|_| {
+            ProcessingError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        if !entries.contains_key(&key) && entries.len() >= self.capacity {
+            let oldest = entries.iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        
+        let last_used = self.clock.fetch_add(1, Ordering::Relaxed);
+        entries.insert(key, CachedResult { data, last_used });
+        Ok(())
+    }
+    
+    /// Get a cached result, or compute and store it
+    pub fn get_or_insert_with<E, F>(&self, config: &str, inputs: &[&str], compute: F) -> Result<DataSet, E>
+    where
+        F: FnOnce() -> Result<DataSet, E>,
+        E: From<ProcessingError>,
+    {
+        let key = self.key(config, inputs)?;
+        
+        if let Some(data) = self.get(&key)? {
+            return Ok(data);
+        }
+        
+        let data = compute()?;
+        self.insert(key, data.clone())?;
+        Ok(data)
+    }
+    
+    /// Get cached statistics of a dataset, keyed like results
+    pub fn get_statistics(&self, key: &ResultKey) -> Result<Option<TableEstimate>, ProcessingError> {
+        let statistics = self.statistics.read().map_err(|_| {
+            ProcessingError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        Ok(statistics.get(key).cloned())
+    }
+    
+    /// Store statistics of a dataset
+    ///
+    /// Statistics are small and dropped when their dataset is written, so
+    /// they do not count against the capacity.
+    pub fn insert_statistics(&self, key: ResultKey, estimate: TableEstimate) -> Result<(), ProcessingError> {
+        let mut statistics = self.statistics.write().map_err(|_| {
+            ProcessingError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        statistics.insert(key, estimate);
+        Ok(())
+    }
+    
+    /// Drop every result computed from a dataset after it was written
+    pub fn invalidate(&self, dataset: &str) -> Result<(), ProcessingError> {
+        {
+            let mut generations = self.generations.write().map_err(|_| {
+                ProcessingError::Other("Failed to acquire write lock".to_string())
+            })?;
+            *generations.entry(dataset.to_string()).or_insert(0) += 1;
+        }
+        
+        let mut entries = self.entries.write().map_err(|_| {
+            ProcessingError::Other("Failed to acquire write lock".to_string())
+        })?;
+        entries.retain(|key, _| key.inputs.iter().all(|(name, _)| name != dataset));
+        
+        let mut statistics = self.statistics.write().map_err(|_| {
+            ProcessingError::Other("Failed to acquire write lock".to_string())
+        })?;
+        statistics.retain(|key, _| key.inputs.iter().all(|(name, _)| name != dataset));
+        Ok(())
+    }
+    
+    /// Drop all cached results
+    pub fn clear(&self) -> Result<(), ProcessingError> {
+        let mut entries = self.entries.write().map_err(|_| {
+            ProcessingError::Other("Failed to acquire write lock".to_string())
+        })?;
+        entries.clear();
+        
+        let mut statistics = self.statistics.write().map_err(|_| {
+            ProcessingError::Other("Failed to acquire write lock".to_string())
+        })?;
+        statistics.clear();
+        Ok(())
+    }
+    
+    /// Get the number of cached results and the hit and miss counts
+    pub fn stats(&self) -> Result<ResultCacheStats, ProcessingError> {
+        let entries = self.entries.read().map_err(|_| {
+            ProcessingError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        Ok(ResultCacheStats {
+            entries: entries.len(),
+            capacity: self.capacity,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        })
+    }
+    
+    /// List the cached results, most recently used first
+    pub fn entries(&self) -> Result<Vec<ResultCacheEntry>, ProcessingError> {
+        let entries = self.entries.read().map_err(|_| {
+            ProcessingError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        let mut listed: Vec<(&ResultKey, &CachedResult)> = entries.iter().collect();
+        listed.sort_by(|a, b| b.1.last_used.cmp(&a.1.last_used));
+        
+        Ok(listed.into_iter()
+            .map(|(key, entry)| ResultCacheEntry {
+                config: key.config.clone(),
+                inputs: key.inputs.iter().map(|(name, _)| name.clone()).collect(),
+                rows: entry.data.len(),
+                columns: entry.data.schema.fields.len(),
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,77 @@
+// Cooperative cancellation of processing jobs
+// Author: Gabriel Demetrios Lafis
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::ProcessingError;
+
+/// Number of rows processed between cancellation checks
+const CHECK_INTERVAL: usize = 4096;
+
+/// Signal shared between a processing job and whoever may stop it
+///
+/// Processors check the token as they go and stop with
+/// `ProcessingError::Cancelled` once it is cancelled or its deadline has
+/// passed. Clones share the same signal.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    /// Create a token that is only cancelled explicitly
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+    
+    /// Cancel the token automatically once the timeout has passed
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Some(Instant::now() + timeout);
+        self
+    }
+    
+    /// Cancel the token and every clone of it
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+    
+    /// Check if the token was cancelled or its deadline has passed
+    pub fn is_cancelled(&self) -> bool {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return true;
+        }
+        
+        if matches!(self.deadline, Some(deadline) if Instant::now() >= deadline) {
+            self.cancel();
+            return true;
+        }
+        
+        false
+    }
+    
+    /// Fail if the token was cancelled
+    pub fn check(&self) -> Result<(), ProcessingError> {
+        if self.is_cancelled() {
+            let reason = match self.deadline {
+                Some(deadline) if Instant::now() >= deadline => "Processing timed out",
+                _ => "Processing was cancelled",
+            };
+            
+            return Err(ProcessingError::Cancelled(reason.to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    /// Fail if the token was cancelled, checking only every few thousand rows
+    pub(crate) fn checkpoint(&self, row: usize) -> Result<(), ProcessingError> {
+        if row % CHECK_INTERVAL == 0 {
+            self.check()?;
+        }
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+// Change data capture for data processing
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value as JsonValue;
+
+use crate::data::{DataSet, DataType, ErrorContext, JsonSource, Row, Schema, Value};
+use super::{CastFormat, ProcessingError, cast_value};
+
+/// Kind of change carried by a change event
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeOperation {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// A Debezium-style change event with row images before and after the change
+#[derive(Debug, Clone)]
+pub struct ChangeEvent {
+    pub operation: ChangeOperation,
+    pub before: Option<HashMap<String, Value>>,
+    pub after: Option<HashMap<String, Value>>,
+}
+
+impl ChangeEvent {
+    /// Parse a change event from JSON
+    ///
+    /// Accepts both bare events and events wrapped in a `payload` envelope.
+    /// The `op` field uses Debezium codes: `c` (create), `r` (snapshot read),
+    /// `u` (update) and `d` (delete).
+    pub fn from_json(json: &JsonValue) -> Result<Self, ProcessingError> {
+        let event = json.get("payload").unwrap_or(json);
+        
+        let operation = match event.get("op").and_then(|v| v.as_str()) {
+            Some("c") | Some("r") => ChangeOperation::Insert,
+            Some("u") => ChangeOperation::Update,
+            Some("d") => ChangeOperation::Delete,
+            Some(op) => return Err(ProcessingError::InvalidArgument(
+                format!("Unknown change operation: {}", op)
+            )),
+            None => return Err(ProcessingError::InvalidArgument(
+                "Change event is missing 'op'".to_string()
+            )),
+        };
+        
+        let image = |name: &str| match event.get(name) {
+            Some(JsonValue::Object(obj)) => Some(obj.iter()
+                .map(|(k, v)| (k.clone(), JsonSource::json_to_value(v)))
+                .collect::<HashMap<_, _>>()),
+            _ => None,
+        };
+        
+        let change = ChangeEvent {
+            operation,
+            before: image("before"),
+            after: image("after"),
+        };
+        
+        let valid = match operation {
+            ChangeOperation::Insert | ChangeOperation::Update => change.after.is_some(),
+            ChangeOperation::Delete => change.before.is_some(),
+        };
+        
+        if !valid {
+            return Err(ProcessingError::InvalidArgument(
+                format!("Change event for {:?} is missing its row image", operation)
+            ));
+        }
+        
+        Ok(change)
+    }
+}
+
+/// Read change events from a JSON array file or a newline-delimited JSON file
+pub fn read_change_events<P: AsRef<Path>>(path: P) -> Result<Vec<ChangeEvent>, ProcessingError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| ProcessingError::Other(e.to_string()))?;
+    
+    if content.trim_start().starts_with('[') {
+        let events: Vec<JsonValue> = serde_json::from_str(&content)
+            .map_err(|e| ProcessingError::InvalidArgument(e.to_string()))?;
+        
+        return events.iter().map(ChangeEvent::from_json).collect();
+    }
+    
+    content.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let json: JsonValue = serde_json::from_str(line)
+                .map_err(|e| ProcessingError::InvalidArgument(e.to_string()))?;
+            ChangeEvent::from_json(&json)
+        })
+        .collect()
+}
+
+/// Counts of rows changed by applying change events
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChangeSummary {
+    pub inserted: usize,
+    pub updated: usize,
+    pub deleted: usize,
+}
+
+/// Apply change events to a dataset keyed on primary key columns
+pub struct ChangeApplier {
+    key_columns: Vec<String>,
+}
+
+impl ChangeApplier {
+    /// Create a new change applier
+    pub fn new(key_columns: Vec<String>) -> Self {
+        ChangeApplier { key_columns }
+    }
+    
+    /// Apply change events in order, keeping the dataset as the current state
+    ///
+    /// Inserts of an existing key and updates of a missing key are treated as
+    /// upserts; deletes of a missing key are ignored. Event values are cast
+    /// to the column types first, so a key sent as `"42"` or `42.0` matches
+    /// a stored integer 42.
+    pub fn apply(&self, target: &mut DataSet, events: &[ChangeEvent]) -> Result<ChangeSummary, ProcessingError> {
+        // Find key column indices
+        let key_indices = self.key_columns.iter()
+            .map(|column| target.schema.fields.iter()
+                .position(|field| &field.name == column)
+                .ok_or_else(|| ProcessingError::InvalidArgument(
+                    format!("Key column '{}' not found", column)
+                )))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        if key_indices.is_empty() {
+            return Err(ProcessingError::InvalidArgument(
+                "At least one key column is required".to_string()
+            ));
+        }
+        
+        let row_key = |values: &[Value]| key_indices.iter()
+            .map(|&i| values[i].clone())
+            .collect::<Vec<_>>();
+        
+        let image_key = |image: &HashMap<String, Value>| self.key_columns.iter()
+            .map(|column| image.get(column).cloned().unwrap_or(Value::Null))
+            .collect::<Vec<_>>();
+        
+        // Index current rows by key; deleted rows become None until the end
+        let mut rows: Vec<Option<Row>> = target.data.drain(..).map(Some).collect();
+        let mut index: HashMap<Vec<Value>, usize> = rows.iter()
+            .enumerate()
+            .filter_map(|(i, row)| row.as_ref().map(|row| (row_key(&row.values), i)))
+            .collect();
+        
+        let mut summary = ChangeSummary::default();
+        
+        for event in events {
+            match event.operation {
+                ChangeOperation::Insert | ChangeOperation::Update => {
+                    let after = &coerce_image(&target.schema, event.after.as_ref().unwrap())?;
+                    let key = image_key(after);
+                    
+                    // Updates may change the key itself
+                    if let Some(before) = &event.before {
+                        let old_key = image_key(&coerce_image(&target.schema, before)?);
+                        
+                        if old_key != key {
+                            if let Some(i) = index.remove(&old_key) {
+                                rows[i] = None;
+                            }
+                        }
+                    }
+                    
+                    match index.get(&key) {
+                        Some(&i) => {
+                            let row = rows[i].as_mut().unwrap();
+                            
+                            for (col_idx, field) in target.schema.fields.iter().enumerate() {
+                                if let Some(value) = after.get(&field.name) {
+                                    row.values[col_idx] = value.clone();
+                                }
+                            }
+                            
+                            summary.updated += 1;
+                        },
+                        None => {
+                            let values = target.schema.fields.iter()
+                                .map(|field| after.get(&field.name).cloned().unwrap_or(Value::Null))
+                                .collect();
+                            
+                            index.insert(key, rows.len());
+                            rows.push(Some(Row::new(values)));
+                            summary.inserted += 1;
+                        },
+                    }
+                },
+                ChangeOperation::Delete => {
+                    let key = image_key(&coerce_image(&target.schema, event.before.as_ref().unwrap())?);
+                    
+                    if let Some(i) = index.remove(&key) {
+                        rows[i] = None;
+                        summary.deleted += 1;
+                    }
+                },
+            }
+        }
+        
+        for row in rows.into_iter().flatten() {
+            target.add_row(row)?;
+        }
+        
+        Ok(summary)
+    }
+}
+
+/// Cast the values of a row image to the types of the dataset's columns
+///
+/// Values of columns the dataset does not have are left as they are, since
+/// they are never stored.
+fn coerce_image(schema: &Schema, image: &HashMap<String, Value>) -> Result<HashMap<String, Value>, ProcessingError> {
+    let format = CastFormat::default();
+    
+    image.iter()
+        .map(|(column, value)| {
+            let value = match schema.get_field_by_name(column).map(|field| &field.data_type) {
+                Some(DataType::Array(_)) | Some(DataType::Map(_)) | None => value.clone(),
+                Some(data_type) => cast_value(value, data_type, &format)
+                    .map_err(|err| err.with_context(ErrorContext::new().column(column).value(value)))?,
+            };
+            
+            Ok((column.clone(), value))
+        })
+        .collect()
+}
@@ -0,0 +1,243 @@
+// Column encryption operations for data processing
+// Author: Gabriel Demetrios Lafis
+
+use std::sync::Arc;
+
+use crate::data::{DataSet, DataType, JsonSink, JsonSource, Row, Schema, Value};
+use crate::storage::{EncryptionKey, Encryptor, KeyProvider, KeyRing};
+use super::nested::infer_type;
+use super::{CancellationToken, DataProcessor, ProcessingError, ProcessorType};
+
+/// Prefix of the metadata properties recording the type of each encrypted column
+const ENCRYPTED_TYPE_PREFIX: &str = "encrypted_type.";
+
+/// Find the indices of columns to encrypt or decrypt
+fn column_indices(input: &DataSet, columns: &[String]) -> Result<Vec<usize>, ProcessingError> {
+    columns.iter()
+        .map(|column| input.schema.fields.iter()
+            .position(|field| &field.name == column)
+            .ok_or_else(|| ProcessingError::InvalidArgument(
+                format!("Column '{}' not found", column)
+            )))
+        .collect()
+}
+
+/// Encrypt sensitive columns with AES-256-GCM
+///
+/// Values are serialized with their type before encryption so decryption
+/// restores them exactly, and the column type is kept in the dataset
+/// metadata. Each ciphertext records the id of its key and is bound to its
+/// column name, so it cannot be moved to another column. Nulls are left as
+/// nulls.
+pub struct EncryptColumnTransform {
+    columns: Vec<String>,
+    encryptor: Encryptor,
+    as_text: bool,
+    cancellation: CancellationToken,
+}
+
+impl EncryptColumnTransform {
+    /// Create a new encrypt transform using the current key of a provider
+    pub fn new(columns: Vec<String>, provider: Arc<dyn KeyProvider>) -> Self {
+        EncryptColumnTransform {
+            columns,
+            encryptor: Encryptor::new(provider),
+            as_text: false,
+            cancellation: CancellationToken::new(),
+        }
+    }
+    
+    /// Create a new encrypt transform with a single named key
+    pub fn with_key(columns: Vec<String>, key: EncryptionKey) -> Self {
+        Self::new(columns, Arc::new(KeyRing::new(key)))
+    }
+    
+    /// Store ciphertext as base64 strings instead of binary values
+    pub fn as_text(mut self) -> Self {
+        self.as_text = true;
+        self
+    }
+    
+    /// Stop encrypting once the token is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+}
+
+impl DataProcessor for EncryptColumnTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        let indices = column_indices(input, &self.columns)?;
+        let output_type = if self.as_text { DataType::String } else { DataType::Binary };
+        
+        // Create new schema with ciphertext columns
+        let mut fields = input.schema.fields.clone();
+        for &col_idx in &indices {
+            fields[col_idx].data_type = output_type.clone();
+        }
+        
+        let mut result = DataSet::new(Schema::new(fields));
+        
+        for (n, row) in input.data.iter().enumerate() {
+            self.cancellation.checkpoint(n)?;
+            
+            let mut values = row.values.clone();
+            
+            for &col_idx in &indices {
+                if let Value::Null = row.values[col_idx] {
+                    continue;
+                }
+                
+                let plaintext = JsonSink::value_to_json(&row.values[col_idx]).to_string();
+                let column = &input.schema.fields[col_idx].name;
+                let ciphertext = self.encryptor.encrypt_with_context(plaintext.as_bytes(), column.as_bytes())
+                    .map_err(|e| ProcessingError::Other(e.to_string()))?;
+                
+                values[col_idx] = if self.as_text {
+                    Value::String(base64::encode(ciphertext))
+                } else {
+                    Value::Binary(ciphertext)
+                };
+            }
+            
+            result.add_row(Row::new(values))?;
+        }
+        
+        // Copy metadata
+        for (key, value) in &input.metadata.properties {
+            result.metadata.add(key.clone(), value.clone());
+        }
+        
+        for &col_idx in &indices {
+            let field = &input.schema.fields[col_idx];
+            result.metadata.add(format!("{}{}", ENCRYPTED_TYPE_PREFIX, field.name), field.data_type.name());
+        }
+        
+        Ok(result)
+    }
+    
+    fn name(&self) -> &str {
+        "encrypt_columns"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+}
+
+/// Decrypt columns encrypted by `EncryptColumnTransform`
+///
+/// Accepts binary ciphertext or base64 strings. The key for each value is
+/// looked up by the id recorded in its ciphertext, so columns encrypted
+/// before a key rotation decrypt as long as the provider still has the key.
+/// Columns get back the type recorded when they were encrypted, or the type
+/// of their values when the metadata was lost.
+pub struct DecryptColumnTransform {
+    columns: Vec<String>,
+    encryptor: Encryptor,
+    cancellation: CancellationToken,
+}
+
+impl DecryptColumnTransform {
+    /// Create a new decrypt transform using keys from a provider
+    pub fn new(columns: Vec<String>, provider: Arc<dyn KeyProvider>) -> Self {
+        DecryptColumnTransform {
+            columns,
+            encryptor: Encryptor::new(provider),
+            cancellation: CancellationToken::new(),
+        }
+    }
+    
+    /// Create a new decrypt transform with a single named key
+    pub fn with_key(columns: Vec<String>, key: EncryptionKey) -> Self {
+        Self::new(columns, Arc::new(KeyRing::new(key)))
+    }
+    
+    /// Stop decrypting once the token is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+    
+    /// Decrypt a single value
+    fn decrypt_value(&self, value: &Value, column: &str) -> Result<Value, ProcessingError> {
+        let ciphertext = match value {
+            Value::Null => return Ok(Value::Null),
+            Value::Binary(bytes) => bytes.clone(),
+            Value::String(text) => base64::decode(text.trim()).map_err(|_| ProcessingError::InvalidOperation(
+                format!("Value in column '{}' is not base64 ciphertext", column)
+            ))?,
+            _ => return Err(ProcessingError::InvalidOperation(
+                format!("Cannot decrypt {:?} in column '{}'", value, column)
+            )),
+        };
+        
+        // Values encrypted before ciphertexts were bound to their column have no context
+        let plaintext = self.encryptor.decrypt_with_context(&ciphertext, column.as_bytes())
+            .or_else(|_| self.encryptor.decrypt(&ciphertext))
+            .map_err(|e| ProcessingError::InvalidOperation(
+                format!("Cannot decrypt column '{}': {}", column, e)
+            ))?;
+        
+        let json = serde_json::from_slice(&plaintext)
+            .map_err(|e| ProcessingError::InvalidOperation(e.to_string()))?;
+        
+        Ok(JsonSource::json_to_value(&json))
+    }
+}
+
+impl DataProcessor for DecryptColumnTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        let indices = column_indices(input, &self.columns)?;
+        
+        let mut rows = Vec::with_capacity(input.data.len());
+        for (n, row) in input.data.iter().enumerate() {
+            self.cancellation.checkpoint(n)?;
+            
+            let mut values = row.values.clone();
+            
+            for (&col_idx, column) in indices.iter().zip(&self.columns) {
+                values[col_idx] = self.decrypt_value(&row.values[col_idx], column)?;
+            }
+            
+            rows.push(Row::new(values));
+        }
+        
+        // Restore column types as recorded, or from the decrypted values
+        let mut fields = input.schema.fields.clone();
+        for &col_idx in &indices {
+            let recorded = input.metadata.get(&format!("{}{}", ENCRYPTED_TYPE_PREFIX, fields[col_idx].name))
+                .and_then(|name| DataType::from_str(name).ok());
+            
+            fields[col_idx].data_type = match recorded {
+                Some(data_type) => data_type,
+                None => infer_type(rows.iter().map(|row| &row.values[col_idx])),
+            };
+        }
+        
+        let mut result = DataSet::new(Schema::new(fields));
+        for row in rows {
+            result.add_row(row)?;
+        }
+        
+        // Copy metadata, except the types of the columns now in plaintext
+        for (key, value) in &input.metadata.properties {
+            let decrypted = key.strip_prefix(ENCRYPTED_TYPE_PREFIX)
+                .map_or(false, |column| self.columns.iter().any(|c| c == column));
+            
+            if !decrypted {
+                result.metadata.add(key.clone(), value.clone());
+            }
+        }
+        
+        Ok(result)
+    }
+    
+    fn name(&self) -> &str {
+        "decrypt_columns"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+}
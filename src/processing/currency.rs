@@ -0,0 +1,248 @@
+// Currency conversion for data processing
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::data::{DataSet, DataType, Field, Row, Schema, Value};
+use super::{DataProcessor, ProcessingError, ProcessorType, WarningKind, emit_warning};
+
+/// Exchange rates keyed by currency code, optionally by date
+///
+/// Each rate is the value of one unit of the currency in a common base
+/// currency, so any pair can be converted through the base. Dated tables
+/// keep the rate of each currency's latest date as its latest rate, for
+/// rows without a date.
+struct RateTable {
+    latest: HashMap<String, f64>,
+    by_date: HashMap<String, BTreeMap<String, f64>>,
+}
+
+impl RateTable {
+    /// Look up the rate for a currency, using the latest rate on or before the date
+    fn rate(&self, currency: &str, date: Option<&str>) -> Option<f64> {
+        match date {
+            Some(date) => self.by_date.get(currency)
+                .and_then(|rates| rates.range(..=date.to_string()).next_back())
+                .map(|(_, rate)| *rate),
+            None => self.latest.get(currency).copied(),
+        }
+    }
+}
+
+/// Convert a monetary column between currencies using a rates dataset
+pub struct CurrencyConversionTransform {
+    amount_column: String,
+    currency_column: String,
+    target_currency: String,
+    output: String,
+    rates: DataSet,
+    rate_currency_column: String,
+    rate_value_column: String,
+    rate_date_column: Option<String>,
+    date_column: Option<String>,
+    missing_as_null: bool,
+}
+
+impl CurrencyConversionTransform {
+    /// Create a new currency conversion transform
+    ///
+    /// The rates dataset needs a `currency` column and a `rate` column holding
+    /// the value of one unit of that currency in a common base currency.
+    pub fn new(amount_column: &str, currency_column: &str, target_currency: &str, rates: DataSet, output: &str) -> Self {
+        CurrencyConversionTransform {
+            amount_column: amount_column.to_string(),
+            currency_column: currency_column.to_string(),
+            target_currency: target_currency.to_uppercase(),
+            output: output.to_string(),
+            rates,
+            rate_currency_column: "currency".to_string(),
+            rate_value_column: "rate".to_string(),
+            rate_date_column: None,
+            date_column: None,
+            missing_as_null: false,
+        }
+    }
+    
+    /// Set the currency and rate column names of the rates dataset
+    pub fn with_rate_columns(mut self, currency: &str, rate: &str) -> Self {
+        self.rate_currency_column = currency.to_string();
+        self.rate_value_column = rate.to_string();
+        self
+    }
+    
+    /// Match rates by date, using the latest rate on or before each row's date
+    ///
+    /// Rows without a date use the rate of the latest date in the rates dataset.
+    pub fn with_dates(mut self, date_column: &str, rate_date_column: &str) -> Self {
+        self.date_column = Some(date_column.to_string());
+        self.rate_date_column = Some(rate_date_column.to_string());
+        self
+    }
+    
+    /// Produce null instead of failing when a rate is missing
+    pub fn with_missing_as_null(mut self) -> Self {
+        self.missing_as_null = true;
+        self
+    }
+    
+    /// Build the rate lookup table from the rates dataset
+    fn rate_table(&self) -> Result<RateTable, ProcessingError> {
+        let find = |column: &str| self.rates.schema.fields.iter()
+            .position(|field| field.name == column)
+            .ok_or_else(|| ProcessingError::InvalidArgument(
+                format!("Column '{}' not found in rates dataset", column)
+            ));
+        
+        let currency_idx = find(&self.rate_currency_column)?;
+        let rate_idx = find(&self.rate_value_column)?;
+        let date_idx = match &self.rate_date_column {
+            Some(column) => Some(find(column)?),
+            None => None,
+        };
+        
+        let mut table = RateTable {
+            latest: HashMap::new(),
+            by_date: HashMap::new(),
+        };
+        
+        let mut undated = 0;
+        
+        for row in &self.rates.data {
+            let currency = match &row.values[currency_idx] {
+                Value::String(s) => s.to_uppercase(),
+                _ => continue,
+            };
+            
+            let rate = match &row.values[rate_idx] {
+                Value::Integer(i) => *i as f64,
+                Value::Float(f) => *f,
+                _ => continue,
+            };
+            
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err(ProcessingError::InvalidArgument(
+                    format!("Invalid rate {} for currency '{}'", rate, currency)
+                ));
+            }
+            
+            match date_idx {
+                Some(i) => match &row.values[i] {
+                    Value::String(date) => {
+                        table.by_date.entry(currency)
+                            .or_insert_with(BTreeMap::new)
+                            .insert(date.clone(), rate);
+                    },
+                    _ => undated += 1,
+                },
+                None => {
+                    table.latest.insert(currency, rate);
+                },
+            }
+        }
+        
+        if let Some(column) = &self.rate_date_column {
+            emit_warning(self.name(), WarningKind::RowsSkipped, Some(column), undated, "Rates without a date skipped");
+        }
+        
+        for (currency, rates) in &table.by_date {
+            if let Some((_, rate)) = rates.iter().next_back() {
+                table.latest.insert(currency.clone(), *rate);
+            }
+        }
+        
+        Ok(table)
+    }
+}
+
+impl DataProcessor for CurrencyConversionTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        // Find column indices
+        let find = |column: &str| input.schema.fields.iter()
+            .position(|field| field.name == column)
+            .ok_or_else(|| ProcessingError::InvalidArgument(
+                format!("Column '{}' not found", column)
+            ));
+        
+        let amount_idx = find(&self.amount_column)?;
+        let currency_idx = find(&self.currency_column)?;
+        let date_idx = match &self.date_column {
+            Some(column) => Some(find(column)?),
+            None => None,
+        };
+        
+        if input.schema.get_field_by_name(&self.output).is_some() {
+            return Err(ProcessingError::InvalidArgument(
+                format!("Column '{}' already exists", self.output)
+            ));
+        }
+        
+        let table = self.rate_table()?;
+        
+        // Create new schema with added column
+        let mut fields = input.schema.fields.clone();
+        fields.push(Field::new(self.output.clone(), DataType::Float, true));
+        
+        let mut result = DataSet::new(Schema::new(fields));
+        
+        for row in &input.data {
+            let amount = match &row.values[amount_idx] {
+                Value::Integer(i) => Some(*i as f64),
+                Value::Float(f) => Some(*f),
+                _ => None,
+            };
+            
+            let currency = match &row.values[currency_idx] {
+                Value::String(s) => Some(s.to_uppercase()),
+                _ => None,
+            };
+            
+            // Rows without a date are converted at the latest rates
+            let date = match date_idx.map(|i| &row.values[i]) {
+                Some(Value::String(s)) => Some(s.as_str()),
+                _ => None,
+            };
+            
+            let converted = match (amount, currency) {
+                (Some(amount), Some(currency)) if currency == self.target_currency => Value::Float(amount),
+                (Some(amount), Some(currency)) => {
+                    let rates = (
+                        table.rate(&currency, date),
+                        table.rate(&self.target_currency, date),
+                    );
+                    
+                    match rates {
+                        (Some(from), Some(to)) => Value::Float(amount * from / to),
+                        _ if self.missing_as_null => Value::Null,
+                        _ => return Err(ProcessingError::InvalidOperation(format!(
+                            "No rate to convert '{}' to '{}'{}",
+                            currency,
+                            self.target_currency,
+                            date.map(|d| format!(" on {}", d)).unwrap_or_default()
+                        ))),
+                    }
+                },
+                _ => Value::Null,
+            };
+            
+            let mut values = row.values.clone();
+            values.push(converted);
+            
+            result.add_row(Row::new(values))?;
+        }
+        
+        // Copy metadata
+        for (key, value) in &input.metadata.properties {
+            result.metadata.add(key.clone(), value.clone());
+        }
+        
+        Ok(result)
+    }
+    
+    fn name(&self) -> &str {
+        "currency_conversion"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+}
@@ -0,0 +1,138 @@
+// Non-fatal diagnostics of processing runs
+// Author: Gabriel Demetrios Lafis
+
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+/// What a processor did to the data without failing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    /// Rows were left out of the result
+    RowsSkipped,
+    /// Values were left out of a computation
+    ValuesSkipped,
+    /// Values were truncated, saturated or replaced to fit
+    ValuesCoerced,
+    /// Values were replaced with null
+    NullsIntroduced,
+}
+
+/// Warning emitted by a processor, counting the rows or values affected
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Warning {
+    pub kind: WarningKind,
+    /// Name of the processor emitting the warning
+    pub processor: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    pub count: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} ({}", self.processor, self.message, self.count)?;
+        
+        if let Some(column) = &self.column {
+            write!(f, " in column '{}'", column)?;
+        }
+        
+        write!(f, ")")
+    }
+}
+
+thread_local! {
+    /// Diagnostics collecting the warnings of the run on this thread
+    static CURRENT: RefCell<Option<Diagnostics>> = RefCell::new(None);
+}
+
+/// Collector of the warnings of a processing run
+///
+/// Processors emit warnings with `emit_warning` into the diagnostics
+/// collecting on their thread, if any, so the same processors run with or
+/// without collection. Clones share the same warnings.
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    warnings: Arc<Mutex<Vec<Warning>>>,
+}
+
+/// Restores the previous diagnostics of the thread when dropped, even on panic
+struct CollectGuard(Option<Diagnostics>);
+
+impl Drop for CollectGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+impl Diagnostics {
+    /// Create empty diagnostics
+    pub fn new() -> Self {
+        Diagnostics::default()
+    }
+    
+    /// Run a function, collecting the warnings it emits on this thread
+    pub fn collect<T, F: FnOnce() -> T>(&self, f: F) -> T {
+        let previous = CURRENT.with(|current| current.borrow_mut().replace(self.clone()));
+        let _guard = CollectGuard(previous);
+        
+        f()
+    }
+    
+    /// Get the diagnostics collecting on this thread, to carry them to worker threads
+    pub(crate) fn current() -> Option<Diagnostics> {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+    
+    /// Add a warning, counting it with an earlier one of the same kind, processor, column and message
+    pub fn add(&self, warning: Warning) {
+        let mut warnings = self.warnings.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let existing = warnings.iter_mut().find(|w| {
+            w.kind == warning.kind
+                && w.processor == warning.processor
+                && w.column == warning.column
+                && w.message == warning.message
+        });
+        
+        match existing {
+            Some(existing) => existing.count += warning.count,
+            None => warnings.push(warning),
+        }
+    }
+    
+    /// Get the warnings collected so far, in the order first emitted
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.warnings.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+    
+    /// Check if no warning was collected
+    pub fn is_empty(&self) -> bool {
+        self.warnings.lock().unwrap_or_else(|e| e.into_inner()).is_empty()
+    }
+}
+
+/// Emit a warning to the diagnostics collecting on this thread
+///
+/// Does nothing when the count is zero or nothing is collecting, so
+/// processors count what they affect and emit once per column.
+pub fn emit_warning(processor: &str, kind: WarningKind, column: Option<&str>, count: usize, message: &str) {
+    if count == 0 {
+        return;
+    }
+    
+    if let Some(diagnostics) = Diagnostics::current() {
+        diagnostics.add(Warning {
+            kind,
+            processor: processor.to_string(),
+            column: column.map(str::to_string),
+            count,
+            message: message.to_string(),
+        });
+    }
+}
@@ -0,0 +1,264 @@
+// Encoding operations for data processing
+// Author: Gabriel Demetrios Lafis
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::data::{DataSet, DataType, Row, Schema, Value};
+use super::{DataProcessor, ProcessingError, ProcessorType, StepSpec};
+
+/// Text encoding for binary and string values
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    Base64,
+    Hex,
+    Url,
+}
+
+impl Encoding {
+    /// Parse an encoding from a string
+    pub fn from_str(s: &str) -> Result<Self, ProcessingError> {
+        match s.to_lowercase().as_str() {
+            "base64" => Ok(Encoding::Base64),
+            "hex" => Ok(Encoding::Hex),
+            "url" => Ok(Encoding::Url),
+            _ => Err(ProcessingError::InvalidArgument(
+                format!("Unknown encoding: {}", s)
+            )),
+        }
+    }
+    
+    /// Encode bytes to text
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Base64 => base64::encode(bytes),
+            Encoding::Hex => bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+            Encoding::Url => {
+                let mut encoded = String::new();
+                
+                for &b in bytes {
+                    if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+                        encoded.push(b as char);
+                    } else {
+                        encoded.push_str(&format!("%{:02X}", b));
+                    }
+                }
+                
+                encoded
+            },
+        }
+    }
+    
+    /// Decode text to bytes
+    pub fn decode(&self, text: &str) -> Result<Vec<u8>, ProcessingError> {
+        let invalid = || ProcessingError::InvalidOperation(
+            format!("Cannot decode '{}' as {:?}", text, self)
+        );
+        
+        match self {
+            Encoding::Base64 => base64::decode(text.trim()).map_err(|_| invalid()),
+            Encoding::Hex => {
+                let text = text.trim();
+                
+                if text.len() % 2 != 0 {
+                    return Err(invalid());
+                }
+                
+                (0..text.len())
+                    .step_by(2)
+                    .map(|i| text.get(i..i + 2)
+                        .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                        .ok_or_else(invalid))
+                    .collect()
+            },
+            Encoding::Url => {
+                let bytes = text.as_bytes();
+                let mut decoded = Vec::with_capacity(bytes.len());
+                let mut i = 0;
+                
+                while i < bytes.len() {
+                    match bytes[i] {
+                        b'%' => {
+                            let hex = text.get(i + 1..i + 3).ok_or_else(invalid)?;
+                            decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+                            i += 3;
+                        },
+                        b'+' => {
+                            decoded.push(b' ');
+                            i += 1;
+                        },
+                        b => {
+                            decoded.push(b);
+                            i += 1;
+                        },
+                    }
+                }
+                
+                Ok(decoded)
+            },
+        }
+    }
+}
+
+/// Encode a Binary or String column as text
+pub struct EncodeTransform {
+    column: String,
+    encoding: Encoding,
+}
+
+impl EncodeTransform {
+    /// Create a new encode transform
+    pub fn new(column: &str, encoding: Encoding) -> Self {
+        EncodeTransform {
+            column: column.to_string(),
+            encoding,
+        }
+    }
+}
+
+impl DataProcessor for EncodeTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        // Find column index
+        let col_idx = input.schema.fields.iter()
+            .position(|field| field.name == self.column)
+            .ok_or_else(|| ProcessingError::InvalidArgument(
+                format!("Column '{}' not found", self.column)
+            ))?;
+        
+        // Create new schema with a string column
+        let mut fields = input.schema.fields.clone();
+        fields[col_idx].data_type = DataType::String;
+        
+        let mut result = DataSet::new(Schema::new(fields));
+        
+        // Encode each value
+        for row in &input.data {
+            let mut values = row.values.clone();
+            
+            values[col_idx] = match &row.values[col_idx] {
+                Value::Null => Value::Null,
+                Value::Binary(bytes) => Value::String(self.encoding.encode(bytes)),
+                Value::String(s) => Value::String(self.encoding.encode(s.as_bytes())),
+                other => return Err(ProcessingError::InvalidOperation(
+                    format!("Cannot encode {:?}, expected binary or string", other)
+                )),
+            };
+            
+            result.add_row(Row::new(values))?;
+        }
+        
+        // Copy metadata
+        for (key, value) in &input.metadata.properties {
+            result.metadata.add(key.clone(), value.clone());
+        }
+        
+        Ok(result)
+    }
+    
+    fn name(&self) -> &str {
+        "encode"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Encode { column: self.column.clone(), encoding: self.encoding }.to_config())
+    }
+}
+
+/// Decode a text column back to Binary or String values
+pub struct DecodeTransform {
+    column: String,
+    encoding: Encoding,
+    target_type: DataType,
+}
+
+impl DecodeTransform {
+    /// Create a new decode transform producing binary values
+    pub fn new(column: &str, encoding: Encoding) -> Self {
+        DecodeTransform {
+            column: column.to_string(),
+            encoding,
+            target_type: DataType::Binary,
+        }
+    }
+    
+    /// Create a new decode transform producing UTF-8 string values
+    pub fn utf8(column: &str, encoding: Encoding) -> Self {
+        DecodeTransform {
+            column: column.to_string(),
+            encoding,
+            target_type: DataType::String,
+        }
+    }
+}
+
+impl DataProcessor for DecodeTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        // Find column index
+        let col_idx = input.schema.fields.iter()
+            .position(|field| field.name == self.column)
+            .ok_or_else(|| ProcessingError::InvalidArgument(
+                format!("Column '{}' not found", self.column)
+            ))?;
+        
+        // Create new schema with the decoded type
+        let mut fields = input.schema.fields.clone();
+        fields[col_idx].data_type = self.target_type.clone();
+        
+        let mut result = DataSet::new(Schema::new(fields));
+        
+        // Decode each value
+        for row in &input.data {
+            let mut values = row.values.clone();
+            
+            values[col_idx] = match &row.values[col_idx] {
+                Value::Null => Value::Null,
+                Value::String(s) => {
+                    let bytes = self.encoding.decode(s)?;
+                    
+                    if self.target_type == DataType::String {
+                        Value::String(String::from_utf8(bytes).map_err(|_| {
+                            ProcessingError::InvalidOperation(
+                                format!("Decoded value of '{}' is not valid UTF-8", s)
+                            )
+                        })?)
+                    } else {
+                        Value::Binary(bytes)
+                    }
+                },
+                other => return Err(ProcessingError::InvalidOperation(
+                    format!("Cannot decode {:?}, expected string", other)
+                )),
+            };
+            
+            result.add_row(Row::new(values))?;
+        }
+        
+        // Copy metadata
+        for (key, value) in &input.metadata.properties {
+            result.metadata.add(key.clone(), value.clone());
+        }
+        
+        Ok(result)
+    }
+    
+    fn name(&self) -> &str {
+        "decode"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Decode {
+            column: self.column.clone(),
+            encoding: self.encoding,
+            data_type: self.target_type.name(),
+        }.to_config())
+    }
+}
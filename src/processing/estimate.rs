@@ -0,0 +1,307 @@
+// Row count estimation for planning
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::data::{DataSet, Value};
+use super::{CompareOp, FilterExpression, JoinType, parse_event_time};
+
+/// Selectivity of an equality on a column without statistics
+const DEFAULT_EQUALITY_SELECTIVITY: f64 = 0.1;
+
+/// Selectivity of a range comparison that cannot be placed between the column bounds
+const DEFAULT_RANGE_SELECTIVITY: f64 = 1.0 / 3.0;
+
+/// Fraction of nulls of a column without statistics
+const DEFAULT_NULL_FRACTION: f64 = 0.1;
+
+/// Statistics of one column of a dataset
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnEstimate {
+    /// Number of distinct values other than null
+    pub distinct: usize,
+    pub nulls: usize,
+    /// Smallest value other than null
+    pub min: Option<Value>,
+    /// Largest value other than null
+    pub max: Option<Value>,
+}
+
+/// Estimated row count and column statistics of a dataset, for planning
+///
+/// Estimates are computed from the rows of a dataset, or scaled from a
+/// sample of them, and carried through the steps of a plan. Steps that
+/// cannot tell what they do to a column drop its statistics, and the
+/// estimates of later steps fall back to fixed heuristics for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableEstimate {
+    pub rows: usize,
+    pub columns: BTreeMap<String, ColumnEstimate>,
+}
+
+impl TableEstimate {
+    /// Estimate of a dataset with a known row count and no column statistics
+    pub fn unknown(rows: usize) -> Self {
+        TableEstimate {
+            rows,
+            columns: BTreeMap::new(),
+        }
+    }
+    
+    /// Compute the statistics of every column of a dataset
+    pub fn compute(data: &DataSet) -> Self {
+        let columns = data.schema.fields.iter().enumerate()
+            .map(|(i, field)| {
+                let mut distinct = HashSet::new();
+                let mut nulls = 0;
+                
+                for row in &data.data {
+                    match &row.values[i] {
+                        Value::Null => nulls += 1,
+                        value => {
+                            distinct.insert(value);
+                        },
+                    }
+                }
+                
+                let column = ColumnEstimate {
+                    distinct: distinct.len(),
+                    nulls,
+                    min: distinct.iter().min().map(|value| (*value).clone()),
+                    max: distinct.iter().max().map(|value| (*value).clone()),
+                };
+                
+                (field.name.clone(), column)
+            })
+            .collect();
+        
+        TableEstimate { rows: data.len(), columns }
+    }
+    
+    /// Scale statistics computed on a sample to a dataset of `rows` rows
+    ///
+    /// Null counts grow with the rows. Columns that look unique in the sample
+    /// are assumed to stay unique; other columns keep the distinct values
+    /// the sample found.
+    pub fn scaled(&self, rows: usize) -> Self {
+        if self.rows == 0 || self.rows == rows {
+            return TableEstimate { rows, ..self.clone() };
+        }
+        
+        let factor = rows as f64 / self.rows as f64;
+        let columns = self.columns.iter()
+            .map(|(name, column)| {
+                let non_null = self.rows - column.nulls;
+                let distinct = if non_null > 0 && column.distinct * 10 >= non_null * 9 {
+                    (column.distinct as f64 * factor).round() as usize
+                } else {
+                    column.distinct
+                };
+                
+                let column = ColumnEstimate {
+                    distinct,
+                    nulls: (column.nulls as f64 * factor).round() as usize,
+                    ..column.clone()
+                };
+                
+                (name.clone(), column)
+            })
+            .collect();
+        
+        TableEstimate { rows, columns }.capped()
+    }
+    
+    /// Keep the statistics, with fewer rows selected from the dataset
+    pub fn with_rows(&self, rows: usize) -> Self {
+        let rows = rows.min(self.rows);
+        let fraction = if self.rows == 0 { 0.0 } else { rows as f64 / self.rows as f64 };
+        
+        let columns = self.columns.iter()
+            .map(|(name, column)| {
+                let column = ColumnEstimate {
+                    nulls: (column.nulls as f64 * fraction).round() as usize,
+                    ..column.clone()
+                };
+                
+                (name.clone(), column)
+            })
+            .collect();
+        
+        TableEstimate { rows, columns }.capped()
+    }
+    
+    /// Get the statistics of a column
+    pub fn column(&self, name: &str) -> Option<&ColumnEstimate> {
+        self.columns.get(name)
+    }
+    
+    /// Estimate the fraction of rows matching a filter expression
+    ///
+    /// Equalities match one distinct value of their column, and range
+    /// comparisons the part of the range between the column's bounds that
+    /// they keep. Conditions on different columns are taken as independent.
+    pub fn selectivity(&self, expression: &FilterExpression) -> f64 {
+        let selectivity = match expression {
+            FilterExpression::Compare { value: Value::Null, .. } => 0.0,
+            FilterExpression::Compare { column, op, value } => {
+                let column = self.column(column);
+                let non_null = column.map_or(1.0 - DEFAULT_NULL_FRACTION, |column| self.non_null_fraction(column));
+                
+                match op {
+                    CompareOp::Eq => non_null * equality_selectivity(column, value),
+                    CompareOp::Ne => non_null * (1.0 - equality_selectivity(column, value)),
+                    op => non_null * range_selectivity(column, *op, value),
+                }
+            },
+            FilterExpression::IsNull { column, negated } => {
+                let nulls = self.column(column).map_or(DEFAULT_NULL_FRACTION, |column| 1.0 - self.non_null_fraction(column));
+                if *negated { 1.0 - nulls } else { nulls }
+            },
+            FilterExpression::Not(inner) => 1.0 - self.selectivity(inner),
+            FilterExpression::And(left, right) => self.selectivity(left) * self.selectivity(right),
+            FilterExpression::Or(left, right) => {
+                let (left, right) = (self.selectivity(left), self.selectivity(right));
+                left + right - left * right
+            },
+        };
+        
+        selectivity.clamp(0.0, 1.0)
+    }
+    
+    /// Estimate the rows matching a filter expression
+    pub fn filter(&self, expression: &FilterExpression) -> Self {
+        self.with_rows((self.rows as f64 * self.selectivity(expression)).round() as usize)
+    }
+    
+    /// Estimate the groups of the given columns
+    ///
+    /// Groups are bounded by the combinations of the columns' distinct
+    /// values, counting null as a value, and by the rows.
+    pub fn group_by(&self, columns: &[String]) -> Self {
+        if self.rows == 0 {
+            return TableEstimate::unknown(0);
+        }
+        
+        let groups = columns.iter()
+            .map(|name| match self.column(name) {
+                Some(column) => (column.distinct + (column.nulls > 0) as usize).max(1) as f64,
+                None => self.rows as f64,
+            })
+            .product::<f64>()
+            .min(self.rows as f64) as usize;
+        
+        let columns = columns.iter()
+            .filter_map(|name| self.column(name).map(|column| (name.clone(), column.clone())))
+            .collect();
+        
+        TableEstimate { rows: groups, columns }.capped()
+    }
+    
+    /// Estimate the rows of a join of this dataset with another on key columns
+    ///
+    /// Each key value of the side with fewer distinct keys is assumed to
+    /// match one of the other side, so an equi-join keeps a row pair out of
+    /// every `max(distinct)` pairs. Keys without statistics are taken as
+    /// unique on their side.
+    pub fn join_rows(&self, right: &TableEstimate, join_type: JoinType, left_columns: &[String], right_columns: &[String]) -> usize {
+        let pairs = self.rows as f64 * right.rows as f64;
+        
+        if join_type == JoinType::Cross {
+            return pairs as usize;
+        }
+        
+        let divisor = left_columns.iter().zip(right_columns)
+            .map(|(left_column, right_column)| {
+                let left_distinct = self.column(left_column).map_or(self.rows, |column| column.distinct);
+                let right_distinct = right.column(right_column).map_or(right.rows, |column| column.distinct);
+                left_distinct.max(right_distinct).max(1) as f64
+            })
+            .product::<f64>();
+        
+        let inner = (pairs / divisor).min(pairs).round() as usize;
+        
+        match join_type {
+            JoinType::Inner | JoinType::Cross => inner,
+            JoinType::Left => inner.max(self.rows),
+            JoinType::Right => inner.max(right.rows),
+            JoinType::Full => inner.max(self.rows) + inner.max(right.rows) - inner,
+        }
+    }
+    
+    /// Get the fraction of rows with a value in a column
+    fn non_null_fraction(&self, column: &ColumnEstimate) -> f64 {
+        if self.rows == 0 {
+            return 0.0;
+        }
+        
+        1.0 - column.nulls as f64 / self.rows as f64
+    }
+    
+    /// Bound the distinct values and nulls of every column by the rows
+    fn capped(mut self) -> Self {
+        for column in self.columns.values_mut() {
+            column.nulls = column.nulls.min(self.rows);
+            column.distinct = column.distinct.min(self.rows - column.nulls);
+        }
+        
+        self
+    }
+}
+
+/// Estimate the fraction of the values of a column equal to a literal
+fn equality_selectivity(column: Option<&ColumnEstimate>, value: &Value) -> f64 {
+    let column = match column {
+        Some(column) => column,
+        None => return DEFAULT_EQUALITY_SELECTIVITY,
+    };
+    
+    // Values outside the bounds match nothing
+    if let (Some(min), Some(max), Some(value)) = (column.min.as_ref().and_then(position), column.max.as_ref().and_then(position), position(value)) {
+        if value < min || value > max {
+            return 0.0;
+        }
+    }
+    
+    if column.distinct == 0 { 0.0 } else { 1.0 / column.distinct as f64 }
+}
+
+/// Estimate the fraction of the values of a column a range comparison keeps
+fn range_selectivity(column: Option<&ColumnEstimate>, op: CompareOp, value: &Value) -> f64 {
+    let bounds = column.and_then(|column| Some((position(column.min.as_ref()?)?, position(column.max.as_ref()?)?)));
+    
+    let (min, max, value) = match (bounds, position(value)) {
+        (Some((min, max)), Some(value)) => (min, max, value),
+        _ => return DEFAULT_RANGE_SELECTIVITY,
+    };
+    
+    // Share of the column's range below the literal
+    let below = if max > min {
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    } else if value > min {
+        1.0
+    } else if value < min {
+        0.0
+    } else {
+        0.5
+    };
+    
+    match op {
+        CompareOp::Lt | CompareOp::Le => below,
+        _ => 1.0 - below,
+    }
+}
+
+/// Place a value on a number line, for values that have a numeric order
+///
+/// Dates, timestamps and date strings are placed by their Unix time.
+fn position(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(i) => Some(*i as f64),
+        Value::Float(f) if f.is_finite() => Some(*f),
+        Value::Decimal(d) => Some(d.to_f64()),
+        Value::String(s) => s.trim().parse::<f64>().ok().filter(|f| f.is_finite())
+            .or_else(|| parse_event_time(value).map(|time| time as f64)),
+        Value::Date(_) | Value::Timestamp(_) => parse_event_time(value).map(|time| time as f64),
+        _ => None,
+    }
+}
@@ -0,0 +1,482 @@
+// Filter expressions for data processing
+// Author: Gabriel Demetrios Lafis
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Bound;
+
+use crate::data::{ColumnRange, Row, Schema, Value, compare_decimal, compare_temporal, parse_uuid};
+use super::{FilterProcessor, ProcessingError};
+
+/// Comparison operator in a filter expression
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    /// Parse a comparison operator from a string
+    pub fn from_str(s: &str) -> Result<Self, ProcessingError> {
+        match s {
+            "==" | "=" => Ok(CompareOp::Eq),
+            "!=" | "<>" => Ok(CompareOp::Ne),
+            "<" => Ok(CompareOp::Lt),
+            "<=" => Ok(CompareOp::Le),
+            ">" => Ok(CompareOp::Gt),
+            ">=" => Ok(CompareOp::Ge),
+            _ => Err(ProcessingError::InvalidArgument(
+                format!("Unknown comparison operator: {}", s)
+            )),
+        }
+    }
+    
+    /// Get the operator symbol
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+    
+    /// Check if an ordering satisfies the operator
+    fn accepts(&self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Eq => ordering == Ordering::Equal,
+            CompareOp::Ne => ordering != Ordering::Equal,
+            CompareOp::Lt => ordering == Ordering::Less,
+            CompareOp::Le => ordering != Ordering::Greater,
+            CompareOp::Gt => ordering == Ordering::Greater,
+            CompareOp::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+/// Boolean row filter parsed from text such as `region == 'EU' and amount > 100`
+///
+/// Supports comparisons of a column with a string, number or boolean literal,
+/// `is null` / `is not null`, `and`, `or`, `not` and parentheses. Comparisons
+/// with null values are false, and missing columns never match.
+#[derive(Debug, Clone)]
+pub enum FilterExpression {
+    Compare {
+        column: String,
+        op: CompareOp,
+        value: Value,
+    },
+    IsNull {
+        column: String,
+        negated: bool,
+    },
+    Not(Box<FilterExpression>),
+    And(Box<FilterExpression>, Box<FilterExpression>),
+    Or(Box<FilterExpression>, Box<FilterExpression>),
+}
+
+impl FilterExpression {
+    /// Parse a filter expression
+    pub fn parse(text: &str) -> Result<Self, ProcessingError> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        
+        let expression = parser.parse_or()?;
+        
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(ProcessingError::InvalidArgument(
+                format!("Unexpected {} in filter expression", token)
+            ));
+        }
+        
+        Ok(expression)
+    }
+    
+    /// Get the columns the expression reads, in order of first use
+    pub fn columns(&self) -> Vec<&str> {
+        let mut columns = Vec::new();
+        self.collect_columns(&mut columns);
+        columns
+    }
+    
+    fn collect_columns<'a>(&'a self, columns: &mut Vec<&'a str>) {
+        match self {
+            FilterExpression::Compare { column, .. } | FilterExpression::IsNull { column, .. } => {
+                if !columns.contains(&column.as_str()) {
+                    columns.push(column);
+                }
+            },
+            FilterExpression::Not(inner) => inner.collect_columns(columns),
+            FilterExpression::And(left, right) | FilterExpression::Or(left, right) => {
+                left.collect_columns(columns);
+                right.collect_columns(columns);
+            },
+        }
+    }
+    
+    /// Get ranges of columns that every matching row falls in
+    ///
+    /// Only comparisons joined by `and` bound their column; `or`, `not`,
+    /// `!=` and null checks give no range. Readers use the ranges to skip
+    /// blocks of rows, so the expression must still be applied to the rows read.
+    pub fn column_ranges(&self) -> Vec<ColumnRange> {
+        match self {
+            FilterExpression::Compare { column, op, value } if !matches!(value, Value::Null) => {
+                let (lower, upper) = match op {
+                    CompareOp::Eq => (Bound::Included(value.clone()), Bound::Included(value.clone())),
+                    CompareOp::Lt => (Bound::Unbounded, Bound::Excluded(value.clone())),
+                    CompareOp::Le => (Bound::Unbounded, Bound::Included(value.clone())),
+                    CompareOp::Gt => (Bound::Excluded(value.clone()), Bound::Unbounded),
+                    CompareOp::Ge => (Bound::Included(value.clone()), Bound::Unbounded),
+                    CompareOp::Ne => return Vec::new(),
+                };
+                
+                vec![ColumnRange::new(column, lower, upper)]
+            },
+            FilterExpression::And(left, right) => {
+                let mut ranges = left.column_ranges();
+                ranges.extend(right.column_ranges());
+                ranges
+            },
+            _ => Vec::new(),
+        }
+    }
+    
+    /// Check if a row matches the expression
+    pub fn matches(&self, row: &Row, schema: &Schema) -> bool {
+        match self {
+            FilterExpression::Compare { column, op, value } => {
+                match column_value(row, schema, column) {
+                    Some(actual) => compare(actual, value).map_or(false, |ordering| op.accepts(ordering)),
+                    None => false,
+                }
+            },
+            FilterExpression::IsNull { column, negated } => {
+                match column_value(row, schema, column) {
+                    Some(actual) => matches!(actual, Value::Null) != *negated,
+                    None => false,
+                }
+            },
+            FilterExpression::Not(inner) => !inner.matches(row, schema),
+            FilterExpression::And(left, right) => left.matches(row, schema) && right.matches(row, schema),
+            FilterExpression::Or(left, right) => left.matches(row, schema) || right.matches(row, schema),
+        }
+    }
+}
+
+impl fmt::Display for FilterExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FilterExpression::Compare { column, op, value } => {
+                write!(f, "{} {} ", column, op.symbol())?;
+                match value {
+                    Value::String(s) => write!(f, "'{}'", s.replace('\\', "\\\\").replace('\'', "\\'")),
+                    Value::Integer(i) => write!(f, "{}", i),
+                    Value::Float(x) => write!(f, "{:?}", x),
+                    Value::Boolean(b) => write!(f, "{}", b),
+                    _ => write!(f, "null"),
+                }
+            },
+            FilterExpression::IsNull { column, negated: false } => write!(f, "{} is null", column),
+            FilterExpression::IsNull { column, negated: true } => write!(f, "{} is not null", column),
+            FilterExpression::Not(inner) => write!(f, "not ({})", inner),
+            FilterExpression::And(left, right) => write!(f, "({}) and ({})", left, right),
+            FilterExpression::Or(left, right) => write!(f, "({}) or ({})", left, right),
+        }
+    }
+}
+
+impl FilterProcessor {
+    /// Create a filter that keeps rows matching a filter expression
+    pub fn expression(text: &str) -> Result<Self, ProcessingError> {
+        let expression = FilterExpression::parse(text)?;
+        let normalized = expression.to_string();
+        
+        Ok(Self::new(
+            &format!("expression_{}", normalized),
+            move |row, dataset| expression.matches(row, &dataset.schema),
+        ).with_expression(normalized))
+    }
+}
+
+/// Look up the value of a column in a row
+fn column_value<'a>(row: &'a Row, schema: &Schema, column: &str) -> Option<&'a Value> {
+    schema.fields.iter()
+        .position(|field| field.name == column)
+        .and_then(|i| row.values.get(i))
+}
+
+/// Compare a row value with a literal
+///
+/// Numeric literals are compared with numeric strings as numbers, since
+/// CSV-backed datasets load every column as strings.
+fn compare(actual: &Value, literal: &Value) -> Option<Ordering> {
+    match (actual, literal) {
+        (Value::Null, _) | (_, Value::Null) => None,
+        (Value::Integer(a), Value::Integer(b)) => Some(a.cmp(b)),
+        (Value::Boolean(a), Value::Boolean(b)) => Some(a.cmp(b)),
+        (Value::String(a), Value::String(b)) => Some(a.as_str().cmp(b.as_str())),
+        (Value::Uuid(a), Value::Uuid(b)) => Some(a.cmp(b)),
+        (Value::Uuid(a), Value::String(b)) => parse_uuid(b).ok().map(|b| a.cmp(&b)),
+        (Value::String(a), Value::Boolean(b)) => a.trim().parse::<bool>().ok().map(|a| a.cmp(b)),
+        (Value::Integer(a), Value::String(b)) => Some(a.to_string().cmp(b)),
+        (Value::Float(a), Value::String(b)) => Some(a.to_string().cmp(b)),
+        (Value::Boolean(a), Value::String(b)) => Some(a.to_string().as_str().cmp(b.as_str())),
+        (actual, literal) => compare_temporal(actual, literal).or_else(|| compare_decimal(actual, literal)).or_else(|| {
+            let a = as_number(actual)?;
+            let b = as_number(literal)?;
+            a.partial_cmp(&b)
+        }),
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(i) => Some(*i as f64),
+        Value::Float(f) => Some(*f),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Token of a filter expression
+#[derive(Debug, Clone)]
+enum Token {
+    Ident(String),
+    Literal(Value),
+    Op(CompareOp),
+    And,
+    Or,
+    Not,
+    Is,
+    Null,
+    LParen,
+    RParen,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Literal(value) => write!(f, "literal {:?}", value),
+            Token::Op(op) => write!(f, "'{}'", op.symbol()),
+            Token::And => write!(f, "'and'"),
+            Token::Or => write!(f, "'or'"),
+            Token::Not => write!(f, "'not'"),
+            Token::Is => write!(f, "'is'"),
+            Token::Null => write!(f, "'null'"),
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+        }
+    }
+}
+
+/// Split a filter expression into tokens
+fn tokenize(text: &str) -> Result<Vec<Token>, ProcessingError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    
+    while i < chars.len() {
+        let c = chars[i];
+        
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == '\'' || c == '"' {
+            // Quoted string with backslash escapes
+            let mut value = String::new();
+            i += 1;
+            
+            loop {
+                match chars.get(i) {
+                    Some('\\') => {
+                        if let Some(&escaped) = chars.get(i + 1) {
+                            value.push(escaped);
+                        }
+                        i += 2;
+                    },
+                    Some(&ch) if ch == c => {
+                        i += 1;
+                        break;
+                    },
+                    Some(&ch) => {
+                        value.push(ch);
+                        i += 1;
+                    },
+                    None => return Err(ProcessingError::InvalidArgument(
+                        "Unterminated string in filter expression".to_string()
+                    )),
+                }
+            }
+            
+            tokens.push(Token::Literal(Value::String(value)));
+        } else if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).map_or(false, |d| d.is_ascii_digit())) {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == 'e' || chars[i] == 'E') {
+                i += 1;
+            }
+            
+            let number: String = chars[start..i].iter().collect();
+            let value = if let Ok(int) = number.parse::<i64>() {
+                Value::Integer(int)
+            } else {
+                Value::Float(number.parse::<f64>().map_err(|_| ProcessingError::InvalidArgument(
+                    format!("Invalid number in filter expression: {}", number)
+                ))?)
+            };
+            
+            tokens.push(Token::Literal(value));
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            
+            let word: String = chars[start..i].iter().collect();
+            tokens.push(match word.to_lowercase().as_str() {
+                "and" => Token::And,
+                "or" => Token::Or,
+                "not" => Token::Not,
+                "is" => Token::Is,
+                "null" => Token::Null,
+                "true" => Token::Literal(Value::Boolean(true)),
+                "false" => Token::Literal(Value::Boolean(false)),
+                _ => Token::Ident(word),
+            });
+        } else {
+            // Operators of one or two characters
+            let pair: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            
+            let (token, len) = match pair.as_str() {
+                "&&" => (Token::And, 2),
+                "||" => (Token::Or, 2),
+                "==" | "!=" | "<=" | ">=" | "<>" => (Token::Op(CompareOp::from_str(&pair)?), 2),
+                _ => match c {
+                    '!' => (Token::Not, 1),
+                    '=' | '<' | '>' => (Token::Op(CompareOp::from_str(&c.to_string())?), 1),
+                    _ => return Err(ProcessingError::InvalidArgument(
+                        format!("Unexpected character '{}' in filter expression", c)
+                    )),
+                },
+            };
+            
+            tokens.push(token);
+            i += len;
+        }
+    }
+    
+    Ok(tokens)
+}
+
+/// Recursive descent parser over filter expression tokens
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+    
+    fn eat(&mut self, token: &Token) -> bool {
+        // Only called with tokens without data, so the variant is enough
+        let matches = self.tokens.get(self.pos)
+            .map_or(false, |next| std::mem::discriminant(next) == std::mem::discriminant(token));
+        
+        if matches {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+    
+    fn unexpected(token: Option<Token>) -> ProcessingError {
+        ProcessingError::InvalidArgument(match token {
+            Some(token) => format!("Unexpected {} in filter expression", token),
+            None => "Unexpected end of filter expression".to_string(),
+        })
+    }
+    
+    fn parse_or(&mut self) -> Result<FilterExpression, ProcessingError> {
+        let mut left = self.parse_and()?;
+        
+        while self.eat(&Token::Or) {
+            let right = self.parse_and()?;
+            left = FilterExpression::Or(Box::new(left), Box::new(right));
+        }
+        
+        Ok(left)
+    }
+    
+    fn parse_and(&mut self) -> Result<FilterExpression, ProcessingError> {
+        let mut left = self.parse_unary()?;
+        
+        while self.eat(&Token::And) {
+            let right = self.parse_unary()?;
+            left = FilterExpression::And(Box::new(left), Box::new(right));
+        }
+        
+        Ok(left)
+    }
+    
+    fn parse_unary(&mut self) -> Result<FilterExpression, ProcessingError> {
+        if self.eat(&Token::Not) {
+            return Ok(FilterExpression::Not(Box::new(self.parse_unary()?)));
+        }
+        
+        if self.eat(&Token::LParen) {
+            let inner = self.parse_or()?;
+            
+            if !self.eat(&Token::RParen) {
+                return Err(Self::unexpected(self.next()));
+            }
+            
+            return Ok(inner);
+        }
+        
+        self.parse_comparison()
+    }
+    
+    fn parse_comparison(&mut self) -> Result<FilterExpression, ProcessingError> {
+        let column = match self.next() {
+            Some(Token::Ident(column)) => column,
+            token => return Err(Self::unexpected(token)),
+        };
+        
+        match self.next() {
+            Some(Token::Is) => {
+                let negated = self.eat(&Token::Not);
+                
+                match self.next() {
+                    Some(Token::Null) => Ok(FilterExpression::IsNull { column, negated }),
+                    token => Err(Self::unexpected(token)),
+                }
+            },
+            Some(Token::Op(op)) => match self.next() {
+                Some(Token::Literal(value)) => Ok(FilterExpression::Compare { column, op, value }),
+                // Comparing with null means checking for null
+                Some(Token::Null) if op == CompareOp::Eq => Ok(FilterExpression::IsNull { column, negated: false }),
+                Some(Token::Null) if op == CompareOp::Ne => Ok(FilterExpression::IsNull { column, negated: true }),
+                token => Err(Self::unexpected(token)),
+            },
+            token => Err(Self::unexpected(token)),
+        }
+    }
+}
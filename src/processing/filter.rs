@@ -1,27 +1,162 @@
 // Filter operations for data processing
 // Author: Gabriel Demetrios Lafis
 
-use crate::data::{DataSet, Row, Value};
-use super::{DataProcessor, ProcessingError, ProcessorType};
+use std::cmp::Ordering;
+
+use serde_json::Value as JsonValue;
+
+use crate::data::{DataSet, Dictionary, Row, Value, compare_decimal, compare_temporal, parse_uuid};
+use super::{BoundingBox, CancellationToken, haversine_distance, compare_f64, compare_i64, CompareOp, DataProcessor, FilterExpression, InPlaceDataProcessor, ProcessingError, ProcessorType, StepSpec, TableEstimate, seeded_rng};
 
 /// Filter rows based on a predicate
+///
+/// Numeric comparisons and substring matches are evaluated a column at a
+/// time, with vectorized kernels and once per distinct string respectively,
+/// instead of row by row.
 pub struct FilterProcessor {
     name: String,
-    predicate: Box<dyn Fn(&Row, &DataSet) -> bool>,
+    predicate: Box<dyn Fn(&Row, &DataSet) -> bool + Send + Sync>,
+    column_filter: Option<ColumnFilter>,
+    expression: Option<String>,
+    cancellation: CancellationToken,
+}
+
+/// Filter evaluated a column at a time
+enum ColumnFilter {
+    /// Comparison of a numeric column with a constant
+    Compare {
+        column: String,
+        op: CompareOp,
+        value: Value,
+    },
+    /// Substring match on a string column
+    Contains {
+        column: String,
+        substring: String,
+    },
+}
+
+impl ColumnFilter {
+    /// Evaluate the filter on every row, if the column suits it
+    fn evaluate(&self, input: &DataSet) -> Option<Vec<bool>> {
+        match self {
+            ColumnFilter::Compare { column, op, value } => {
+                let col_idx = input.schema.fields.iter().position(|field| &field.name == column)?;
+                
+                // Other columns, such as decimals, compare row by row
+                if !input.schema.fields[col_idx].data_type.is_numeric() {
+                    return None;
+                }
+                
+                compare_column(input, col_idx, *op, value)
+            },
+            ColumnFilter::Contains { column, substring } => {
+                let col_idx = input.schema.fields.iter().position(|field| &field.name == column)?;
+                
+                // Check each distinct string once
+                let mut dictionary = Dictionary::new();
+                let ids = dictionary.encode(input, col_idx)?;
+                let matches: Vec<bool> = dictionary.values().iter()
+                    .map(|value| value.contains(substring.as_str()))
+                    .collect();
+                
+                Some(ids.iter().map(|id| id.map_or(false, |id| matches[id as usize])).collect())
+            },
+        }
+    }
+}
+
+/// Compare a numeric column with a constant using the vectorized kernels
+///
+/// Like the row predicates, integers only match integer constants and
+/// floats only match float constants.
+fn compare_column(input: &DataSet, col_idx: usize, op: CompareOp, value: &Value) -> Option<Vec<bool>> {
+    let mut present = vec![false; input.data.len()];
+    
+    let mut mask = match *value {
+        Value::Integer(threshold) => {
+            let values: Vec<i64> = input.data.iter().zip(present.iter_mut())
+                .map(|(row, present)| match row.values[col_idx] {
+                    Value::Integer(v) => {
+                        *present = true;
+                        v
+                    },
+                    _ => 0,
+                })
+                .collect();
+            
+            compare_i64(&values, op, threshold)
+        },
+        Value::Float(threshold) => {
+            let values: Vec<f64> = input.data.iter().zip(present.iter_mut())
+                .map(|(row, present)| match row.values[col_idx] {
+                    Value::Float(v) => {
+                        *present = true;
+                        v
+                    },
+                    _ => 0.0,
+                })
+                .collect();
+            
+            compare_f64(&values, op, threshold)
+        },
+        _ => return None,
+    };
+    
+    for (keep, present) in mask.iter_mut().zip(present) {
+        *keep &= present;
+    }
+    
+    Some(mask)
 }
 
 impl FilterProcessor {
     /// Create a new filter processor with a predicate function
     pub fn new<F>(name: &str, predicate: F) -> Self
     where
-        F: Fn(&Row, &DataSet) -> bool + 'static,
+        F: Fn(&Row, &DataSet) -> bool + Send + Sync + 'static,
     {
         FilterProcessor {
             name: name.to_string(),
             predicate: Box::new(predicate),
+            column_filter: None,
+            expression: None,
+            cancellation: CancellationToken::new(),
         }
     }
     
+    /// Stop filtering once the token is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+    
+    /// Evaluate the filter a column at a time where the column suits it
+    fn with_column_filter(mut self, column_filter: ColumnFilter) -> Self {
+        self.column_filter = Some(column_filter);
+        self
+    }
+    
+    /// Record the filter expression the predicate evaluates, for its configuration
+    pub(crate) fn with_expression(mut self, expression: String) -> Self {
+        self.expression = Some(expression);
+        self
+    }
+    
+    /// Evaluate the filter on every row
+    fn evaluate(&self, input: &DataSet) -> Result<Vec<bool>, ProcessingError> {
+        if let Some(mask) = self.column_filter.as_ref().and_then(|column_filter| column_filter.evaluate(input)) {
+            return Ok(mask);
+        }
+        
+        input.data.iter().enumerate()
+            .map(|(n, row)| {
+                self.cancellation.checkpoint(n)?;
+                Ok((self.predicate)(row, input))
+            })
+            .collect()
+    }
+    
     /// Create a filter that keeps rows where a column equals a value
     pub fn equals(column: &str, value: Value) -> Self {
         let column = column.to_string();
@@ -44,7 +179,9 @@ impl FilterProcessor {
                         (Value::Integer(a), Value::Integer(b)) => a == b,
                         (Value::Float(a), Value::Float(b)) => (a - b).abs() < f64::EPSILON,
                         (Value::String(a), Value::String(b)) => a == b,
-                        _ => false,
+                        (Value::Uuid(a), Value::Uuid(b)) => a == b,
+                        (Value::Uuid(a), Value::String(b)) => parse_uuid(b).map_or(false, |b| *a == b),
+                        (a, b) => compare_temporal(a, b).or_else(|| compare_decimal(a, b)) == Some(Ordering::Equal),
                     }
                 } else {
                     false
@@ -56,6 +193,7 @@ impl FilterProcessor {
     /// Create a filter that keeps rows where a column is greater than a value
     pub fn greater_than(column: &str, value: Value) -> Self {
         let column = column.to_string();
+        let column_filter = ColumnFilter::Compare { column: column.clone(), op: CompareOp::Gt, value: value.clone() };
         Self::new(
             &format!("greater_than_{}", column),
             move |row, dataset| {
@@ -73,18 +211,20 @@ impl FilterProcessor {
                         (Value::Integer(a), Value::Integer(b)) => a > b,
                         (Value::Float(a), Value::Float(b)) => a > b,
                         (Value::String(a), Value::String(b)) => a > b,
-                        _ => false,
+                        (a, b) => compare_temporal(a, b).or_else(|| compare_decimal(a, b)) == Some(Ordering::Greater),
                     }
                 } else {
                     false
                 }
             },
         )
+        .with_column_filter(column_filter)
     }
     
     /// Create a filter that keeps rows where a column is less than a value
     pub fn less_than(column: &str, value: Value) -> Self {
         let column = column.to_string();
+        let column_filter = ColumnFilter::Compare { column: column.clone(), op: CompareOp::Lt, value: value.clone() };
         Self::new(
             &format!("less_than_{}", column),
             move |row, dataset| {
@@ -102,13 +242,14 @@ impl FilterProcessor {
                         (Value::Integer(a), Value::Integer(b)) => a < b,
                         (Value::Float(a), Value::Float(b)) => a < b,
                         (Value::String(a), Value::String(b)) => a < b,
-                        _ => false,
+                        (a, b) => compare_temporal(a, b).or_else(|| compare_decimal(a, b)) == Some(Ordering::Less),
                     }
                 } else {
                     false
                 }
             },
         )
+        .with_column_filter(column_filter)
     }
     
     /// Create a filter that keeps rows where a column is not null
@@ -139,6 +280,7 @@ impl FilterProcessor {
     pub fn contains(column: &str, substring: &str) -> Self {
         let column = column.to_string();
         let substring = substring.to_string();
+        let column_filter = ColumnFilter::Contains { column: column.clone(), substring: substring.clone() };
         Self::new(
             &format!("contains_{}", column),
             move |row, dataset| {
@@ -161,6 +303,63 @@ impl FilterProcessor {
                 }
             },
         )
+        .with_column_filter(column_filter)
+    }
+    
+    /// Create a filter that keeps rows where a point column lies inside a bounding box
+    pub fn within_bounds(column: &str, bounds: BoundingBox) -> Self {
+        let column = column.to_string();
+        Self::new(
+            &format!("within_bounds_{}", column),
+            move |row, dataset| {
+                // Find column index
+                let mut col_idx = None;
+                for (i, field) in dataset.schema.fields.iter().enumerate() {
+                    if field.name == column {
+                        col_idx = Some(i);
+                        break;
+                    }
+                }
+                
+                if let Some(i) = col_idx {
+                    match &row.values[i] {
+                        Value::Point(lat, lon) => bounds.contains(*lat, *lon),
+                        _ => false,
+                    }
+                } else {
+                    false
+                }
+            },
+        )
+    }
+    
+    /// Create a filter that keeps rows where a point column is within a radius in kilometers of a point
+    pub fn within_radius(column: &str, lat: f64, lon: f64, radius_km: f64) -> Self {
+        let column = column.to_string();
+        Self::new(
+            &format!("within_radius_{}", column),
+            move |row, dataset| {
+                // Find column index
+                let mut col_idx = None;
+                for (i, field) in dataset.schema.fields.iter().enumerate() {
+                    if field.name == column {
+                        col_idx = Some(i);
+                        break;
+                    }
+                }
+                
+                if let Some(i) = col_idx {
+                    match &row.values[i] {
+                        Value::Point(p_lat, p_lon) => {
+                            haversine_distance((*p_lat, *p_lon), (lat, lon)) <= radius_km
+                        },
+                        _ => false,
+                    }
+                } else {
+                    false
+                }
+            },
+        )
     }
 }
 
@@ -170,8 +369,8 @@ impl DataProcessor for FilterProcessor {
         let mut result = DataSet::new(input.schema.clone());
         
         // Filter rows
-        for row in &input.data {
-            if (self.predicate)(row, input) {
+        for (row, keep) in input.data.iter().zip(self.evaluate(input)?) {
+            if keep {
                 result.add_row(row.clone())?;
             }
         }
@@ -191,6 +390,43 @@ impl DataProcessor for FilterProcessor {
     fn processor_type(&self) -> ProcessorType {
         ProcessorType::Filter
     }
+    
+    fn as_in_place(&self) -> Option<&dyn InPlaceDataProcessor> {
+        Some(self)
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        match &self.expression {
+            Some(expression) => Ok(StepSpec::Filter { expression: expression.clone() }.to_config()),
+            None => Err(ProcessingError::NotSupported(
+                format!("Filter '{}' is built from a predicate function and has no configuration", self.name)
+            )),
+        }
+    }
+    
+    fn estimate(&self, input: &TableEstimate) -> Option<TableEstimate> {
+        // Predicate functions cannot be inspected
+        let expression = FilterExpression::parse(self.expression.as_ref()?).ok()?;
+        Some(input.filter(&expression))
+    }
+}
+
+impl InPlaceDataProcessor for FilterProcessor {
+    fn process_in_place(&self, input: &mut DataSet) -> Result<(), ProcessingError> {
+        // Evaluate the filter first, since it needs the whole dataset
+        let mut keep = self.evaluate(input)?.into_iter();
+        input.data.retain(|_| keep.next().unwrap_or(false));
+        
+        Ok(())
+    }
+    
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Filter
+    }
 }
 
 /// Limit the number of rows in a dataset
@@ -234,6 +470,33 @@ impl DataProcessor for LimitProcessor {
     fn processor_type(&self) -> ProcessorType {
         ProcessorType::Filter
     }
+    
+    fn as_in_place(&self) -> Option<&dyn InPlaceDataProcessor> {
+        Some(self)
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Limit { limit: self.limit }.to_config())
+    }
+    
+    fn estimate(&self, input: &TableEstimate) -> Option<TableEstimate> {
+        Some(input.with_rows(self.limit))
+    }
+}
+
+impl InPlaceDataProcessor for LimitProcessor {
+    fn process_in_place(&self, input: &mut DataSet) -> Result<(), ProcessingError> {
+        input.data.truncate(self.limit);
+        Ok(())
+    }
+    
+    fn name(&self) -> &str {
+        "limit"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Filter
+    }
 }
 
 /// Skip a number of rows in a dataset
@@ -277,6 +540,14 @@ impl DataProcessor for SkipProcessor {
     fn processor_type(&self) -> ProcessorType {
         ProcessorType::Filter
     }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Skip { skip: self.skip }.to_config())
+    }
+    
+    fn estimate(&self, input: &TableEstimate) -> Option<TableEstimate> {
+        Some(input.with_rows(input.rows.saturating_sub(self.skip)))
+    }
 }
 
 /// Sample rows from a dataset
@@ -287,6 +558,8 @@ pub struct SampleProcessor {
 
 impl SampleProcessor {
     /// Create a new sample processor
+    ///
+    /// Without a seed, the global seed is used if one is set.
     pub fn new(fraction: f64, seed: Option<u64>) -> Self {
         SampleProcessor { fraction, seed }
     }
@@ -304,10 +577,7 @@ impl DataProcessor for SampleProcessor {
         let mut result = DataSet::new(input.schema.clone());
         
         // Set up random number generator
-        let mut rng = match self.seed {
-            Some(seed) => rand::rngs::StdRng::seed_from_u64(seed),
-            None => rand::rngs::StdRng::from_entropy(),
-        };
+        let mut rng = seeded_rng(self.seed);
         
         use rand::Rng;
         
@@ -333,5 +603,9 @@ impl DataProcessor for SampleProcessor {
     fn processor_type(&self) -> ProcessorType {
         ProcessorType::Filter
     }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Sample { fraction: self.fraction, seed: self.seed }.to_config())
+    }
 }
 
@@ -0,0 +1,354 @@
+// Geospatial operations for data processing
+// Author: Gabriel Demetrios Lafis
+
+use serde_json::Value as JsonValue;
+
+use crate::data::{DataSet, DataType, Field, Row, Schema, Value};
+use super::{DataProcessor, ProcessingError, ProcessorType, StepSpec, WarningKind, emit_warning};
+
+/// Mean Earth radius in kilometers
+pub const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// Base32 alphabet used by geohashes
+const GEOHASH_ALPHABET: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Great-circle distance in kilometers between two (lat, lon) points
+pub fn haversine_distance(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+    
+    let dlat = lat2 - lat1;
+    let dlon = lon2 - lon1;
+    
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// Encode a (lat, lon) point as a geohash with the given number of characters
+pub fn geohash(lat: f64, lon: f64, precision: usize) -> String {
+    let mut lat_range = (-90.0, 90.0);
+    let mut lon_range = (-180.0, 180.0);
+    let mut hash = String::with_capacity(precision);
+    let mut bits = 0u8;
+    let mut bit_count = 0;
+    let mut even = true;
+    
+    while hash.len() < precision {
+        // Even bits refine longitude, odd bits refine latitude
+        let (range, value) = if even {
+            (&mut lon_range, lon)
+        } else {
+            (&mut lat_range, lat)
+        };
+        
+        let mid = (range.0 + range.1) / 2.0;
+        if value >= mid {
+            bits = (bits << 1) | 1;
+            range.0 = mid;
+        } else {
+            bits <<= 1;
+            range.1 = mid;
+        }
+        
+        even = !even;
+        bit_count += 1;
+        
+        if bit_count == 5 {
+            hash.push(GEOHASH_ALPHABET[bits as usize] as char);
+            bits = 0;
+            bit_count = 0;
+        }
+    }
+    
+    hash
+}
+
+/// Rectangular area bounded by latitude and longitude
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
+}
+
+impl BoundingBox {
+    /// Create a new bounding box from its south-west and north-east corners
+    pub fn new(min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> Self {
+        BoundingBox {
+            min_lat,
+            min_lon,
+            max_lat,
+            max_lon,
+        }
+    }
+    
+    /// Check if a point lies inside the box
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        if lat < self.min_lat || lat > self.max_lat {
+            return false;
+        }
+        
+        // Boxes crossing the antimeridian have min_lon > max_lon
+        if self.min_lon <= self.max_lon {
+            lon >= self.min_lon && lon <= self.max_lon
+        } else {
+            lon >= self.min_lon || lon <= self.max_lon
+        }
+    }
+}
+
+/// Find the index of a point column
+fn point_column_index(input: &DataSet, column: &str) -> Result<usize, ProcessingError> {
+    // Find column index
+    let col_idx = input.schema.fields.iter()
+        .position(|field| field.name == column)
+        .ok_or_else(|| ProcessingError::InvalidArgument(
+            format!("Column '{}' not found", column)
+        ))?;
+    
+    if input.schema.fields[col_idx].data_type != DataType::Point {
+        return Err(ProcessingError::InvalidArgument(
+            format!("Column '{}' is not a point column", column)
+        ));
+    }
+    
+    Ok(col_idx)
+}
+
+/// Add a column computed from the points of another column
+fn add_point_column<F>(
+    input: &DataSet,
+    column: &str,
+    output: &str,
+    data_type: DataType,
+    compute: F,
+) -> Result<DataSet, ProcessingError>
+where
+    F: Fn(f64, f64) -> Value,
+{
+    let col_idx = point_column_index(input, column)?;
+    
+    if input.schema.get_field_by_name(output).is_some() {
+        return Err(ProcessingError::InvalidArgument(
+            format!("Column '{}' already exists", output)
+        ));
+    }
+    
+    // Create new schema with added column
+    let mut fields = input.schema.fields.clone();
+    fields.push(Field::new(output.to_string(), data_type, true));
+    
+    let mut result = DataSet::new(Schema::new(fields));
+    
+    for row in &input.data {
+        let mut values = row.values.clone();
+        
+        values.push(match &row.values[col_idx] {
+            Value::Point(lat, lon) => compute(*lat, *lon),
+            _ => Value::Null,
+        });
+        
+        result.add_row(Row::new(values))?;
+    }
+    
+    // Copy metadata
+    for (key, value) in &input.metadata.properties {
+        result.metadata.add(key.clone(), value.clone());
+    }
+    
+    Ok(result)
+}
+
+/// Build a point column from separate latitude and longitude columns
+pub struct MakePointTransform {
+    lat_column: String,
+    lon_column: String,
+    output: String,
+}
+
+impl MakePointTransform {
+    /// Create a new make point transform
+    pub fn new(lat_column: &str, lon_column: &str, output: &str) -> Self {
+        MakePointTransform {
+            lat_column: lat_column.to_string(),
+            lon_column: lon_column.to_string(),
+            output: output.to_string(),
+        }
+    }
+}
+
+impl DataProcessor for MakePointTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        // Find column indices
+        let find = |column: &str| input.schema.fields.iter()
+            .position(|field| field.name == column)
+            .ok_or_else(|| ProcessingError::InvalidArgument(
+                format!("Column '{}' not found", column)
+            ));
+        
+        let lat_idx = find(&self.lat_column)?;
+        let lon_idx = find(&self.lon_column)?;
+        
+        if input.schema.get_field_by_name(&self.output).is_some() {
+            return Err(ProcessingError::InvalidArgument(
+                format!("Column '{}' already exists", self.output)
+            ));
+        }
+        
+        // Create new schema with added column
+        let mut fields = input.schema.fields.clone();
+        fields.push(Field::new(self.output.clone(), DataType::Point, true));
+        
+        let mut result = DataSet::new(Schema::new(fields));
+        let mut unparsed = 0;
+        
+        for row in &input.data {
+            let coordinate = |value: &Value| match value {
+                Value::Integer(i) => Some(*i as f64),
+                Value::Float(f) => Some(*f),
+                _ => None,
+            };
+            
+            let mut values = row.values.clone();
+            
+            values.push(match (coordinate(&row.values[lat_idx]), coordinate(&row.values[lon_idx])) {
+                (Some(lat), Some(lon)) => {
+                    if lat.abs() > 90.0 || lon.abs() > 180.0 {
+                        return Err(ProcessingError::InvalidOperation(
+                            format!("Invalid point ({}, {})", lat, lon)
+                        ));
+                    }
+                    Value::Point(lat, lon)
+                },
+                (lat, lon) => {
+                    // Null coordinates make null points; anything else is not a number
+                    let is_unparsed = |coordinate: Option<f64>, value: &Value| coordinate.is_none() && !matches!(value, Value::Null);
+                    if is_unparsed(lat, &row.values[lat_idx]) || is_unparsed(lon, &row.values[lon_idx]) {
+                        unparsed += 1;
+                    }
+                    Value::Null
+                },
+            });
+            
+            result.add_row(Row::new(values))?;
+        }
+        
+        emit_warning(self.name(), WarningKind::NullsIntroduced, Some(&self.output), unparsed, "Points with non-numeric coordinates set to null");
+        
+        // Copy metadata
+        for (key, value) in &input.metadata.properties {
+            result.metadata.add(key.clone(), value.clone());
+        }
+        
+        Ok(result)
+    }
+    
+    fn name(&self) -> &str {
+        "make_point"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::MakePoint {
+            lat_column: self.lat_column.clone(),
+            lon_column: self.lon_column.clone(),
+            output: self.output.clone(),
+        }.to_config())
+    }
+}
+
+/// Add a column with the distance in kilometers from each point to a fixed point
+pub struct GeoDistanceTransform {
+    column: String,
+    target: (f64, f64),
+    output: String,
+}
+
+impl GeoDistanceTransform {
+    /// Create a new distance transform to the given (lat, lon) point
+    pub fn new(column: &str, lat: f64, lon: f64, output: &str) -> Self {
+        GeoDistanceTransform {
+            column: column.to_string(),
+            target: (lat, lon),
+            output: output.to_string(),
+        }
+    }
+}
+
+impl DataProcessor for GeoDistanceTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        add_point_column(input, &self.column, &self.output, DataType::Float, |lat, lon| {
+            Value::Float(haversine_distance((lat, lon), self.target))
+        })
+    }
+    
+    fn name(&self) -> &str {
+        "geo_distance"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::GeoDistance {
+            column: self.column.clone(),
+            lat: self.target.0,
+            lon: self.target.1,
+            output: self.output.clone(),
+        }.to_config())
+    }
+}
+
+/// Add a geohash column for bucketing points in a group-by
+pub struct GeohashTransform {
+    column: String,
+    precision: usize,
+    output: String,
+}
+
+impl GeohashTransform {
+    /// Create a new geohash transform with the given number of characters
+    pub fn new(column: &str, precision: usize, output: &str) -> Self {
+        GeohashTransform {
+            column: column.to_string(),
+            precision,
+            output: output.to_string(),
+        }
+    }
+}
+
+impl DataProcessor for GeohashTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        if self.precision == 0 || self.precision > 12 {
+            return Err(ProcessingError::InvalidArgument(
+                format!("Geohash precision must be between 1 and 12, got {}", self.precision)
+            ));
+        }
+        
+        add_point_column(input, &self.column, &self.output, DataType::String, |lat, lon| {
+            Value::String(geohash(lat, lon, self.precision))
+        })
+    }
+    
+    fn name(&self) -> &str {
+        "geohash"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Geohash {
+            column: self.column.clone(),
+            precision: self.precision,
+            output: self.output.clone(),
+        }.to_config())
+    }
+}
@@ -1,13 +1,17 @@
 // Join operations for data processing
 // Author: Gabriel Demetrios Lafis
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::data::{DataSet, Field, Row, Schema, Value};
-use super::{DataProcessor, ProcessingError, ProcessorType};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::data::{DataSet, Dictionary, Field, Row, Schema, Value};
+use super::{CancellationToken, DataProcessor, KeyPart, ProcessingError, ProcessorType, RowKeys, StepSpec, TableEstimate, is_string_column, partition_count, partition_rows, process_partitions};
 
 /// Join type for joining datasets
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum JoinType {
     Inner,
     Left,
@@ -16,11 +20,27 @@ pub enum JoinType {
     Cross,
 }
 
+/// Input of a hash join whose rows are put in the hash table
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildSide {
+    Left,
+    Right,
+}
+
 /// Join processor for joining datasets
+///
+/// Output rows follow the left input order, with matches in right input
+/// order; unmatched right rows of right and full joins come last. Large
+/// inputs are split into partitions by the hash of the join key and
+/// joined one partition per thread. Inner joins hash the smaller input,
+/// which gives the same rows in the same order.
 pub struct JoinProcessor {
     join_type: JoinType,
     left_columns: Vec<String>,
     right_columns: Vec<String>,
+    partitions: usize,
+    cancellation: CancellationToken,
 }
 
 impl JoinProcessor {
@@ -30,9 +50,23 @@ impl JoinProcessor {
             join_type,
             left_columns,
             right_columns,
+            partitions: num_cpus::get(),
+            cancellation: CancellationToken::new(),
         }
     }
     
+    /// Set the maximum number of partitions joined in parallel
+    pub fn with_partitions(mut self, partitions: usize) -> Self {
+        self.partitions = partitions;
+        self
+    }
+    
+    /// Stop joining once the token is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+    
     /// Create a new inner join processor
     pub fn inner(left_columns: Vec<String>, right_columns: Vec<String>) -> Self {
         Self::new(JoinType::Inner, left_columns, right_columns)
@@ -58,8 +92,25 @@ impl JoinProcessor {
         Self::new(JoinType::Cross, Vec::new(), Vec::new())
     }
     
+    /// Choose the input to hash, from the rows of both inputs
+    ///
+    /// Outer joins always hash the right input, since they track which of
+    /// its rows matched.
+    pub fn build_side(&self, left_rows: usize, right_rows: usize) -> BuildSide {
+        if self.join_type == JoinType::Inner && left_rows < right_rows {
+            BuildSide::Left
+        } else {
+            BuildSide::Right
+        }
+    }
+    
+    /// Estimate the rows of the join from statistics of both inputs
+    pub fn estimate_rows(&self, left: &TableEstimate, right: &TableEstimate) -> usize {
+        left.join_rows(right, self.join_type, &self.left_columns, &self.right_columns)
+    }
+    
     /// Process a join between two datasets
-    fn process_join(&self, left: &DataSet, right: &DataSet) -> Result<DataSet, ProcessingError> {
+    pub fn process_join(&self, left: &DataSet, right: &DataSet) -> Result<DataSet, ProcessingError> {
         // For cross join, we don't need join columns
         if self.join_type == JoinType::Cross {
             return self.process_cross_join(left, right);
@@ -142,43 +193,118 @@ impl JoinProcessor {
         let output_schema = Schema::new(output_fields);
         let mut result = DataSet::new(output_schema);
         
-        // Build hash map for right dataset
-        let mut right_map: HashMap<Vec<Value>, Vec<&Row>> = HashMap::new();
+        // Join each partition of keys on its own thread
+        let partitions = partition_count(left.data.len().max(right.data.len()), self.partitions);
+        let left_partitions = partition_rows(&left.data, &left_indices, partitions)?;
+        let right_partitions = partition_rows(&right.data, &right_indices, partitions)?;
+        
+        // String key columns on both sides share a dictionary, so keys compare ids
+        let encode: Vec<bool> = left_indices.iter().zip(&right_indices)
+            .map(|(&l, &r)| is_string_column(left, l) && is_string_column(right, r))
+            .collect();
+        let mut dictionary = Dictionary::new();
+        let right_keys = RowKeys::new(right, &right_indices, &encode, &mut dictionary);
+        let left_keys = RowKeys::new(left, &left_indices, &encode, &mut dictionary);
+        
+        let build_side = self.build_side(left.data.len(), right.data.len());
+        let pairs: Vec<_> = left_partitions.iter().zip(right_partitions.iter()).collect();
+        let outputs = process_partitions(&pairs, |(left_rows, right_rows)| match build_side {
+            BuildSide::Left => self.join_partition_build_left(left_rows, right_rows, &left_keys, &right_keys, &right_indices),
+            BuildSide::Right => self.join_partition(left_rows, right_rows, &left_keys, &right_keys, &right_indices),
+        })?;
+        
+        // Emit matches in left input order, then unmatched right rows in right input order
+        let mut left_output: Vec<Vec<Row>> = vec![Vec::new(); left.data.len()];
+        let mut right_unmatched = Vec::new();
         
-        for row in &right.data {
-            let key: Vec<Value> = right_indices.iter()
-                .map(|&i| row.values[i].clone())
-                .collect();
+        for (matches, unmatched) in outputs {
+            for (left_idx, rows) in matches {
+                left_output[left_idx] = rows;
+            }
+            right_unmatched.extend(unmatched);
+        }
+        
+        for rows in left_output {
+            for row in rows {
+                result.add_row(row)?;
+            }
+        }
+        
+        right_unmatched.sort_unstable();
+        for right_idx in right_unmatched {
+            // Create output row with nulls for left values
+            let mut output_values = vec![Value::Null; left.schema.fields.len()];
+            
+            // Add right values except join columns
+            for (i, value) in right.data[right_idx].values.iter().enumerate() {
+                if !right_indices.contains(&i) {
+                    output_values.push(value.clone());
+                }
+            }
             
-            right_map.entry(key).or_default().push(row);
+            result.add_row(Row::new(output_values))?;
+        }
+        
+        // Copy metadata
+        for (key, value) in &left.metadata.properties {
+            result.metadata.add(key.clone(), value.clone());
+        }
+        
+        for (key, value) in &right.metadata.properties {
+            let mut new_key = key.clone();
+            let mut counter = 1;
+            
+            while result.metadata.properties.contains_key(&new_key) {
+                new_key = format!("{}_{}", key, counter);
+                counter += 1;
+            }
+            
+            result.metadata.add(new_key, value.clone());
+        }
+        
+        Ok(result)
+    }
+    
+    /// Join the rows of one partition
+    ///
+    /// Returns the output rows of each left row that produced any, and the
+    /// right rows without a match when unmatched right rows are kept.
+    fn join_partition(
+        &self,
+        left_rows: &[usize],
+        right_rows: &[usize],
+        left_keys: &RowKeys,
+        right_keys: &RowKeys,
+        right_indices: &[usize],
+    ) -> Result<(Vec<(usize, Vec<Row>)>, Vec<usize>), ProcessingError> {
+        let left = left_keys.data();
+        let right = right_keys.data();
+        
+        // Build hash map for right rows
+        let mut right_map: HashMap<Vec<KeyPart>, Vec<&Row>> = HashMap::new();
+        
+        for (n, &r) in right_rows.iter().enumerate() {
+            self.cancellation.checkpoint(n)?;
+            right_map.entry(right_keys.key(r)).or_default().push(&right.data[r]);
         }
         
         // Process left rows
-        let mut left_matched = vec![false; left.data.len()];
+        let mut matches = Vec::new();
+        let mut matched_keys = HashSet::new();
         
-        for (left_idx, left_row) in left.data.iter().enumerate() {
-            let key: Vec<Value> = left_indices.iter()
-                .map(|&i| left_row.values[i].clone())
-                .collect();
+        for (n, &left_idx) in left_rows.iter().enumerate() {
+            self.cancellation.checkpoint(n)?;
+            
+            let left_row = &left.data[left_idx];
+            let key = left_keys.key(left_idx);
             
             if let Some(right_rows) = right_map.get(&key) {
                 // Match found
-                left_matched[left_idx] = true;
+                let output = right_rows.iter()
+                    .map(|right_row| joined_row(left_row, right_row, right_indices))
+                    .collect();
                 
-                for right_row in right_rows {
-                    // Create output row
-                    let mut output_values = left_row.values.clone();
-                    
-                    // Add right values except join columns
-                    for (i, value) in right_row.values.iter().enumerate() {
-                        if !right_indices.contains(&i) {
-                            output_values.push(value.clone());
-                        }
-                    }
-                    
-                    let output_row = Row::new(output_values);
-                    result.add_row(output_row)?;
-                }
+                matches.push((left_idx, output));
             } else if self.join_type == JoinType::Left || self.join_type == JoinType::Full {
                 // No match, but include left row for left and full joins
                 let mut output_values = left_row.values.clone();
@@ -189,65 +315,62 @@ impl JoinProcessor {
                     output_values.push(Value::Null);
                 }
                 
-                let output_row = Row::new(output_values);
-                result.add_row(output_row)?;
+                matches.push((left_idx, vec![Row::new(output_values)]));
             }
+            
+            matched_keys.insert(key);
         }
         
-        // Process unmatched right rows for right and full joins
+        // Find unmatched right rows for right and full joins
+        let mut unmatched = Vec::new();
+        
         if self.join_type == JoinType::Right || self.join_type == JoinType::Full {
-            for (key, right_rows) in &right_map {
-                // Check if this key was matched
-                let mut matched = false;
-                
-                for left_idx in 0..left.data.len() {
-                    let left_key: Vec<Value> = left_indices.iter()
-                        .map(|&i| left.data[left_idx].values[i].clone())
-                        .collect();
-                    
-                    if &left_key == key {
-                        matched = true;
-                        break;
-                    }
-                }
-                
-                if !matched {
-                    for right_row in right_rows {
-                        // Create output row with nulls for left values
-                        let mut output_values = vec![Value::Null; left.schema.fields.len()];
-                        
-                        // Add right values except join columns
-                        for (i, value) in right_row.values.iter().enumerate() {
-                            if !right_indices.contains(&i) {
-                                output_values.push(value.clone());
-                            }
-                        }
-                        
-                        let output_row = Row::new(output_values);
-                        result.add_row(output_row)?;
-                    }
+            for &r in right_rows {
+                if !matched_keys.contains(&right_keys.key(r)) {
+                    unmatched.push(r);
                 }
             }
         }
         
-        // Copy metadata
-        for (key, value) in &left.metadata.properties {
-            result.metadata.add(key.clone(), value.clone());
+        Ok((matches, unmatched))
+    }
+    
+    /// Join the rows of one partition of an inner join, hashing the left rows
+    ///
+    /// Right rows probe in input order, so the matches of each left row come
+    /// in right input order as when hashing the right rows.
+    fn join_partition_build_left(
+        &self,
+        left_rows: &[usize],
+        right_rows: &[usize],
+        left_keys: &RowKeys,
+        right_keys: &RowKeys,
+        right_indices: &[usize],
+    ) -> Result<(Vec<(usize, Vec<Row>)>, Vec<usize>), ProcessingError> {
+        let left = left_keys.data();
+        let right = right_keys.data();
+        
+        let mut left_map: HashMap<Vec<KeyPart>, Vec<usize>> = HashMap::new();
+        
+        for (n, &l) in left_rows.iter().enumerate() {
+            self.cancellation.checkpoint(n)?;
+            left_map.entry(left_keys.key(l)).or_default().push(l);
         }
         
-        for (key, value) in &right.metadata.properties {
-            let mut new_key = key.clone();
-            let mut counter = 1;
+        let mut outputs: HashMap<usize, Vec<Row>> = HashMap::new();
+        
+        for (n, &r) in right_rows.iter().enumerate() {
+            self.cancellation.checkpoint(n)?;
             
-            while result.metadata.properties.contains_key(&new_key) {
-                new_key = format!("{}_{}", key, counter);
-                counter += 1;
+            if let Some(matched) = left_map.get(&right_keys.key(r)) {
+                for &left_idx in matched {
+                    outputs.entry(left_idx).or_default()
+                        .push(joined_row(&left.data[left_idx], &right.data[r], right_indices));
+                }
             }
-            
-            result.metadata.add(new_key, value.clone());
         }
         
-        Ok(result)
+        Ok((outputs.into_iter().collect(), Vec::new()))
     }
     
     /// Process a cross join between two datasets
@@ -279,6 +402,9 @@ impl JoinProcessor {
         
         // Perform cross join
         for left_row in &left.data {
+            // Each left row yields a full pass over the right rows
+            self.cancellation.check()?;
+            
             for right_row in &right.data {
                 // Create output row
                 let mut output_values = left_row.values.clone();
@@ -310,6 +436,19 @@ impl JoinProcessor {
     }
 }
 
+/// Build an output row from a left row and the right row it matched, without the right join columns
+fn joined_row(left_row: &Row, right_row: &Row, right_indices: &[usize]) -> Row {
+    let mut values = left_row.values.clone();
+    
+    for (i, value) in right_row.values.iter().enumerate() {
+        if !right_indices.contains(&i) {
+            values.push(value.clone());
+        }
+    }
+    
+    Row::new(values)
+}
+
 impl DataProcessor for JoinProcessor {
     fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
         // This processor requires a second dataset, which should be provided via a context
@@ -331,5 +470,13 @@ impl DataProcessor for JoinProcessor {
     fn processor_type(&self) -> ProcessorType {
         ProcessorType::Join
     }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Join {
+            join_type: self.join_type,
+            left_columns: self.left_columns.clone(),
+            right_columns: self.right_columns.clone(),
+        }.to_config())
+    }
 }
 
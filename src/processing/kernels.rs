@@ -0,0 +1,176 @@
+// Vectorized numeric kernels
+// Author: Gabriel Demetrios Lafis
+
+use super::CompareOp;
+
+/// Number of values processed per step, as independent accumulators
+const LANES: usize = 8;
+
+// Each kernel is written as a loop over fixed-width lanes that the compiler
+// turns into SIMD instructions. It is compiled twice: for the baseline
+// target, and with AVX2 enabled for CPUs detected to support it at runtime.
+macro_rules! dispatch {
+    ($(#[$doc:meta])* pub fn $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty => $kernel:ident) => {
+        $(#[$doc])*
+        pub fn $name($($arg: $ty),*) -> $ret {
+            #[cfg(target_arch = "x86_64")]
+            {
+                if is_x86_feature_detected!("avx2") {
+                    #[target_feature(enable = "avx2")]
+                    unsafe fn avx2($($arg: $ty),*) -> $ret {
+                        $kernel($($arg),*)
+                    }
+                    
+                    // Safety: the CPU supports AVX2, checked above
+                    return unsafe { avx2($($arg),*) };
+                }
+            }
+            
+            $kernel($($arg),*)
+        }
+    };
+}
+
+dispatch! {
+    /// Sum values, adding lanes separately before combining them
+    pub fn sum_f64(values: &[f64]) -> f64 => sum_lanes
+}
+
+dispatch! {
+    /// Get the smallest value, ignoring NaN; infinity if there are none
+    pub fn min_f64(values: &[f64]) -> f64 => min_lanes
+}
+
+dispatch! {
+    /// Get the largest value, ignoring NaN; negative infinity if there are none
+    pub fn max_f64(values: &[f64]) -> f64 => max_lanes
+}
+
+dispatch! {
+    /// Sum the squared differences of values from a mean
+    pub fn sum_squared_diff_f64(values: &[f64], mean: f64) -> f64 => sum_squared_diff_lanes
+}
+
+dispatch! {
+    /// Compare each value with a threshold, returning a mask of matches
+    pub fn compare_f64(values: &[f64], op: CompareOp, threshold: f64) -> Vec<bool> => compare_f64_lanes
+}
+
+dispatch! {
+    /// Compare each value with a threshold, returning a mask of matches
+    pub fn compare_i64(values: &[i64], op: CompareOp, threshold: i64) -> Vec<bool> => compare_i64_lanes
+}
+
+/// Get the mean of values, or zero if there are none
+pub fn mean_f64(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    
+    sum_f64(values) / values.len() as f64
+}
+
+/// Get the population variance of values, or zero if there are none
+pub fn variance_f64(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    
+    sum_squared_diff_f64(values, mean_f64(values)) / values.len() as f64
+}
+
+#[inline(always)]
+fn sum_lanes(values: &[f64]) -> f64 {
+    let mut acc = [0.0; LANES];
+    let chunks = values.chunks_exact(LANES);
+    let rest = chunks.remainder();
+    
+    for chunk in chunks {
+        for (a, &v) in acc.iter_mut().zip(chunk) {
+            *a += v;
+        }
+    }
+    
+    acc.iter().sum::<f64>() + rest.iter().sum::<f64>()
+}
+
+#[inline(always)]
+fn min_lanes(values: &[f64]) -> f64 {
+    let mut acc = [f64::INFINITY; LANES];
+    let chunks = values.chunks_exact(LANES);
+    let rest = chunks.remainder();
+    
+    for chunk in chunks {
+        for (a, &v) in acc.iter_mut().zip(chunk) {
+            *a = a.min(v);
+        }
+    }
+    
+    acc.iter().chain(rest).fold(f64::INFINITY, |a, &b| a.min(b))
+}
+
+#[inline(always)]
+fn max_lanes(values: &[f64]) -> f64 {
+    let mut acc = [f64::NEG_INFINITY; LANES];
+    let chunks = values.chunks_exact(LANES);
+    let rest = chunks.remainder();
+    
+    for chunk in chunks {
+        for (a, &v) in acc.iter_mut().zip(chunk) {
+            *a = a.max(v);
+        }
+    }
+    
+    acc.iter().chain(rest).fold(f64::NEG_INFINITY, |a, &b| a.max(b))
+}
+
+#[inline(always)]
+fn sum_squared_diff_lanes(values: &[f64], mean: f64) -> f64 {
+    let mut acc = [0.0; LANES];
+    let chunks = values.chunks_exact(LANES);
+    let rest = chunks.remainder();
+    
+    for chunk in chunks {
+        for (a, &v) in acc.iter_mut().zip(chunk) {
+            let diff = v - mean;
+            *a += diff * diff;
+        }
+    }
+    
+    acc.iter().sum::<f64>() + rest.iter().map(|&x| (x - mean) * (x - mean)).sum::<f64>()
+}
+
+#[inline(always)]
+fn compare_f64_lanes(values: &[f64], op: CompareOp, threshold: f64) -> Vec<bool> {
+    let mut mask = vec![false; values.len()];
+    
+    // One loop per operator keeps the comparison out of the inner loop
+    let pairs = values.iter().zip(mask.iter_mut());
+    match op {
+        CompareOp::Eq => pairs.for_each(|(v, m)| *m = *v == threshold),
+        CompareOp::Ne => pairs.for_each(|(v, m)| *m = *v != threshold),
+        CompareOp::Lt => pairs.for_each(|(v, m)| *m = *v < threshold),
+        CompareOp::Le => pairs.for_each(|(v, m)| *m = *v <= threshold),
+        CompareOp::Gt => pairs.for_each(|(v, m)| *m = *v > threshold),
+        CompareOp::Ge => pairs.for_each(|(v, m)| *m = *v >= threshold),
+    }
+    
+    mask
+}
+
+#[inline(always)]
+fn compare_i64_lanes(values: &[i64], op: CompareOp, threshold: i64) -> Vec<bool> {
+    let mut mask = vec![false; values.len()];
+    
+    let pairs = values.iter().zip(mask.iter_mut());
+    match op {
+        CompareOp::Eq => pairs.for_each(|(v, m)| *m = *v == threshold),
+        CompareOp::Ne => pairs.for_each(|(v, m)| *m = *v != threshold),
+        CompareOp::Lt => pairs.for_each(|(v, m)| *m = *v < threshold),
+        CompareOp::Le => pairs.for_each(|(v, m)| *m = *v <= threshold),
+        CompareOp::Gt => pairs.for_each(|(v, m)| *m = *v > threshold),
+        CompareOp::Ge => pairs.for_each(|(v, m)| *m = *v >= threshold),
+    }
+    
+    mask
+}
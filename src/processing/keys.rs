@@ -0,0 +1,64 @@
+// Row keys for grouping and joining
+// Author: Gabriel Demetrios Lafis
+
+use crate::data::{DataSet, Dictionary, Value};
+
+/// Part of a row key
+///
+/// String columns are dictionary encoded, so their keys hold ids that are
+/// cheaper to hash and compare than the strings; other columns borrow the
+/// row's value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum KeyPart<'a> {
+    Id(Option<u32>),
+    Value(&'a Value),
+}
+
+/// Key columns of a dataset, with string columns dictionary encoded
+pub(crate) struct RowKeys<'a> {
+    data: &'a DataSet,
+    columns: Vec<KeyColumn>,
+}
+
+enum KeyColumn {
+    Encoded(Vec<Option<u32>>),
+    Plain(usize),
+}
+
+impl<'a> RowKeys<'a> {
+    /// Build the keys of the given columns
+    ///
+    /// Columns flagged in `encode` that hold only strings and nulls are
+    /// encoded with the dictionary; datasets whose keys are compared with
+    /// each other must share the dictionary and flags.
+    pub(crate) fn new(data: &'a DataSet, indices: &[usize], encode: &[bool], dictionary: &mut Dictionary) -> Self {
+        let columns = indices.iter().zip(encode)
+            .map(|(&i, &encode)| {
+                let ids = if encode { dictionary.encode(data, i) } else { None };
+                ids.map_or(KeyColumn::Plain(i), KeyColumn::Encoded)
+            })
+            .collect();
+        
+        RowKeys { data, columns }
+    }
+    
+    /// Get the dataset the keys belong to
+    pub(crate) fn data(&self) -> &'a DataSet {
+        self.data
+    }
+    
+    /// Get the key of a row
+    pub(crate) fn key(&self, row: usize) -> Vec<KeyPart<'a>> {
+        self.columns.iter()
+            .map(|column| match column {
+                KeyColumn::Encoded(ids) => KeyPart::Id(ids[row]),
+                KeyColumn::Plain(i) => KeyPart::Value(&self.data.data[row].values[*i]),
+            })
+            .collect()
+    }
+}
+
+/// Check if a column holds only strings and nulls, and so can be dictionary encoded
+pub(crate) fn is_string_column(data: &DataSet, column: usize) -> bool {
+    data.data.iter().all(|row| matches!(row.values[column], Value::String(_) | Value::Null))
+}
@@ -7,6 +7,32 @@ mod aggregate;
 mod join;
 mod window;
 mod stats;
+mod nested;
+mod encoding;
+mod geo;
+mod currency;
+mod scaling;
+mod timeseries;
+mod cdc;
+mod crypto;
+mod expression;
+mod orchestrator;
+mod cache;
+mod partition;
+mod kernels;
+mod keys;
+mod cancel;
+mod profile;
+mod spec;
+mod template;
+mod plan;
+mod random;
+mod registry;
+mod diagnostics;
+mod streaming;
+mod watermark;
+mod sort;
+mod estimate;
 
 pub use transform::*;
 pub use filter::*;
@@ -14,14 +40,44 @@ pub use aggregate::*;
 pub use join::*;
 pub use window::*;
 pub use stats::*;
+pub use nested::*;
+pub use encoding::*;
+pub use geo::*;
+pub use currency::*;
+pub use scaling::*;
+pub use timeseries::*;
+pub use cdc::*;
+pub use crypto::*;
+pub use expression::*;
+pub use orchestrator::*;
+pub use cache::*;
+pub use kernels::*;
+pub use cancel::*;
+pub use profile::*;
+pub use spec::*;
+pub use template::*;
+pub use plan::*;
+pub use random::*;
+pub use registry::*;
+pub use diagnostics::*;
+pub use streaming::*;
+pub use watermark::*;
+pub use sort::*;
+pub use estimate::*;
+
+pub(crate) use keys::*;
+pub(crate) use partition::*;
 
 use std::error::Error;
 use std::fmt;
 
-use crate::data::{DataError, DataSet, Row, Schema, Value};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::data::{DataError, DataSet, ErrorContext, Row, Schema, Value};
 
 /// Represents a data processor that transforms data
-pub trait DataProcessor {
+pub trait DataProcessor: Send + Sync {
     /// Process a dataset and return a new dataset
     fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError>;
     
@@ -30,6 +86,29 @@ pub trait DataProcessor {
     
     /// Get the processor type
     fn processor_type(&self) -> ProcessorType;
+    
+    /// Get the in-place variant of this processor, if it has one
+    fn as_in_place(&self) -> Option<&dyn InPlaceDataProcessor> {
+        None
+    }
+    
+    /// Get the configuration of this processor, with all its parameters
+    ///
+    /// The configuration is a pipeline step in JSON, which
+    /// `processor_from_config` builds back into the same processor. Processors
+    /// built from closures, or holding keys or datasets, have none.
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Err(ProcessingError::NotSupported(
+            format!("Processor '{}' has no configuration", self.name())
+        ))
+    }
+    
+    /// Estimate the output of this processor from statistics of its input, for planning
+    ///
+    /// Processors returning `None` are estimated by running them on a sample.
+    fn estimate(&self, _input: &TableEstimate) -> Option<TableEstimate> {
+        None
+    }
 }
 
 /// Represents a data processor that transforms data in place
@@ -56,6 +135,80 @@ pub enum ProcessorType {
     Custom(String),
 }
 
+/// What to do when a number does not fit its target type
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverflowPolicy {
+    /// Fail with an error
+    Error,
+    /// Clamp to the nearest value the type can hold
+    Saturate,
+    /// Keep the value in a wider type: a 64-bit integer, or a float
+    Promote,
+}
+
+impl Default for OverflowPolicy {
+    fn default() -> Self {
+        OverflowPolicy::Error
+    }
+}
+
+impl OverflowPolicy {
+    /// Parse an overflow policy from its name
+    pub fn from_str(s: &str) -> Result<Self, ProcessingError> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(OverflowPolicy::Error),
+            "saturate" => Ok(OverflowPolicy::Saturate),
+            "promote" => Ok(OverflowPolicy::Promote),
+            _ => Err(ProcessingError::InvalidArgument(format!("Invalid overflow policy: {}", s))),
+        }
+    }
+}
+
+/// What statistics and aggregations do with NaN and infinite inputs
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NanPolicy {
+    /// Ignore them like nulls
+    Skip,
+    /// Let them flow into the result
+    Propagate,
+    /// Fail with an error
+    Error,
+}
+
+impl Default for NanPolicy {
+    fn default() -> Self {
+        NanPolicy::Propagate
+    }
+}
+
+impl NanPolicy {
+    /// Parse a NaN policy from its name
+    pub fn from_str(s: &str) -> Result<Self, ProcessingError> {
+        match s.to_lowercase().as_str() {
+            "skip" => Ok(NanPolicy::Skip),
+            "propagate" => Ok(NanPolicy::Propagate),
+            "error" => Ok(NanPolicy::Error),
+            _ => Err(ProcessingError::InvalidArgument(format!("Invalid NaN policy: {}", s))),
+        }
+    }
+    
+    /// Apply the policy to a value, returning None for skipped values
+    pub fn apply<'a>(&self, value: &'a Value, column: &str) -> Result<Option<&'a Value>, ProcessingError> {
+        match value {
+            Value::Float(f) if !f.is_finite() => match self {
+                NanPolicy::Skip => Ok(None),
+                NanPolicy::Propagate => Ok(Some(value)),
+                NanPolicy::Error => Err(ProcessingError::InvalidOperation(
+                    format!("Column '{}' contains {}", column, f)
+                )),
+            },
+            _ => Ok(Some(value)),
+        }
+    }
+}
+
 /// Represents an error in the processing module
 #[derive(Debug)]
 pub enum ProcessingError {
@@ -63,7 +216,38 @@ pub enum ProcessingError {
     InvalidOperation(String),
     InvalidArgument(String),
     NotSupported(String),
+    Cancelled(String),
     Other(String),
+    /// Another error with where in the data it happened
+    InContext(Box<ProcessingError>, ErrorContext),
+}
+
+impl ProcessingError {
+    /// Add context to the error, keeping the parts it already has
+    pub fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            ProcessingError::DataError(err) => ProcessingError::DataError(err.with_context(context)),
+            ProcessingError::InContext(err, existing) => ProcessingError::InContext(err, existing.or(context)),
+            err => ProcessingError::InContext(Box::new(err), context),
+        }
+    }
+    
+    /// Get the context of the error, if any
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            ProcessingError::DataError(err) => err.context(),
+            ProcessingError::InContext(_, context) => Some(context),
+            _ => None,
+        }
+    }
+    
+    /// Get the error without its context
+    pub fn without_context(&self) -> &ProcessingError {
+        match self {
+            ProcessingError::InContext(err, _) => err,
+            err => err,
+        }
+    }
 }
 
 impl fmt::Display for ProcessingError {
@@ -73,7 +257,9 @@ impl fmt::Display for ProcessingError {
             ProcessingError::InvalidOperation(msg) => write!(f, "Invalid operation: {}", msg),
             ProcessingError::InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
             ProcessingError::NotSupported(msg) => write!(f, "Not supported: {}", msg),
+            ProcessingError::Cancelled(msg) => write!(f, "Cancelled: {}", msg),
             ProcessingError::Other(msg) => write!(f, "Error: {}", msg),
+            ProcessingError::InContext(err, context) => write!(f, "{} ({})", err, context),
         }
     }
 }
@@ -107,12 +293,57 @@ impl Pipeline {
         self
     }
     
+    /// Add a boxed processor to the pipeline
+    pub fn add_boxed(mut self, processor: Box<dyn DataProcessor>) -> Self {
+        self.processors.push(processor);
+        self
+    }
+    
+    /// Build a pipeline from the configuration returned by `to_config`
+    pub fn from_config(config: JsonValue) -> Result<Self, ProcessingError> {
+        PipelineSpec::from_config(config)?.build()
+    }
+    
+    /// Get the specification of the pipeline, with the configuration of every processor
+    ///
+    /// Fails if a processor has no configuration.
+    pub fn to_spec(&self) -> Result<PipelineSpec, ProcessingError> {
+        let steps = self.processors.iter()
+            .map(|processor| step_of(processor.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(PipelineSpec {
+            name: self.name.clone(),
+            seed: None,
+            steps,
+        })
+    }
+    
     /// Execute the pipeline on a dataset
     pub fn execute(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
-        let mut current = input.clone();
+        self.execute_owned(input.clone())
+    }
+    
+    /// Execute the pipeline on a dataset, returning the warnings its processors emitted
+    pub fn execute_with_warnings(&self, input: &DataSet) -> Result<(DataSet, Vec<Warning>), ProcessingError> {
+        let diagnostics = Diagnostics::new();
+        let result = diagnostics.collect(|| self.execute(input))?;
+        
+        Ok((result, diagnostics.warnings()))
+    }
+    
+    /// Execute the pipeline on an owned dataset
+    ///
+    /// Processors that support in-place execution modify the dataset directly
+    /// instead of producing an intermediate copy.
+    pub fn execute_owned(&self, input: DataSet) -> Result<DataSet, ProcessingError> {
+        let mut current = input;
         
         for processor in &self.processors {
-            current = processor.process(&current)?;
+            match processor.as_in_place() {
+                Some(in_place) => in_place.process_in_place(&mut current)?,
+                None => current = processor.process(&current)?,
+            }
         }
         
         Ok(current)
@@ -131,5 +362,9 @@ impl DataProcessor for Pipeline {
     fn processor_type(&self) -> ProcessorType {
         ProcessorType::Custom("Pipeline".to_string())
     }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(serde_json::to_value(self.to_spec()?).expect("pipeline specifications serialize to JSON"))
+    }
 }
 
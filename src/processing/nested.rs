@@ -0,0 +1,541 @@
+// Nested data operations for data processing
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::HashMap;
+
+use serde_json::Value as JsonValue;
+
+use crate::data::{DataSet, DataType, Field, JsonSink, Row, Schema, Value};
+use super::{CastFormat, DataProcessor, JsonPathSpec, ProcessingError, ProcessorType, StepSpec, cast_value};
+
+/// Flatten nested map columns into dotted columns
+pub struct FlattenTransform {
+    columns: Option<Vec<String>>,
+    separator: String,
+}
+
+impl FlattenTransform {
+    /// Create a new flatten transform for all map columns
+    pub fn new() -> Self {
+        FlattenTransform {
+            columns: None,
+            separator: ".".to_string(),
+        }
+    }
+    
+    /// Create a new flatten transform for specific columns
+    pub fn columns(columns: Vec<String>) -> Self {
+        FlattenTransform {
+            columns: Some(columns),
+            separator: ".".to_string(),
+        }
+    }
+    
+    /// Set the separator used to join nested keys
+    pub fn with_separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
+    }
+    
+    /// Collect the leaf key paths of a map value
+    ///
+    /// Paths are kept as key segments, since keys may contain the separator.
+    fn collect_paths(&self, prefix: &mut Vec<String>, map: &HashMap<String, Value>, paths: &mut Vec<Vec<String>>) {
+        let mut keys: Vec<&String> = map.keys().collect();
+        keys.sort();
+        
+        for key in keys {
+            prefix.push(key.clone());
+            
+            match &map[key] {
+                Value::Map(nested) if !nested.is_empty() => self.collect_paths(prefix, nested, paths),
+                _ => {
+                    if !paths.contains(prefix) {
+                        paths.push(prefix.clone());
+                    }
+                },
+            }
+            
+            prefix.pop();
+        }
+    }
+    
+    /// Look up a leaf value by its key path
+    fn lookup<'a>(&self, map: &'a HashMap<String, Value>, path: &[String]) -> Option<&'a Value> {
+        let value = map.get(&path[0])?;
+        
+        if path.len() == 1 {
+            return Some(value);
+        }
+        
+        match value {
+            Value::Map(nested) => self.lookup(nested, &path[1..]),
+            _ => None,
+        }
+    }
+}
+
+impl Default for FlattenTransform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataProcessor for FlattenTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        // Check that requested columns exist
+        if let Some(columns) = &self.columns {
+            for col in columns {
+                if input.schema.get_field_by_name(col).is_none() {
+                    return Err(ProcessingError::InvalidArgument(
+                        format!("Column '{}' not found", col)
+                    ));
+                }
+            }
+        }
+        
+        // Work out the output columns for each input column
+        let mut layout = Vec::new(); // (column index, leaf paths and types or None to copy)
+        let mut output_fields = Vec::new();
+        
+        for (i, field) in input.schema.fields.iter().enumerate() {
+            let selected = match &self.columns {
+                Some(columns) => columns.contains(&field.name),
+                None => matches!(field.data_type, DataType::Map(_)),
+            };
+            
+            if !selected {
+                layout.push((i, None));
+                output_fields.push(field.clone());
+                continue;
+            }
+            
+            // Collect the union of leaf paths over all rows
+            let mut paths = Vec::new();
+            for row in &input.data {
+                if let Value::Map(map) = &row.values[i] {
+                    self.collect_paths(&mut Vec::new(), map, &mut paths);
+                }
+            }
+            
+            let mut leaves = Vec::new();
+            for path in paths {
+                let data_type = infer_type(input.data.iter().filter_map(|row| match &row.values[i] {
+                    Value::Map(map) => self.lookup(map, &path),
+                    _ => None,
+                }));
+                
+                let name = format!("{}{}{}", field.name, self.separator, path.join(&self.separator));
+                output_fields.push(Field::new(name, data_type.clone(), true));
+                leaves.push((path, data_type));
+            }
+            
+            layout.push((i, Some(leaves)));
+        }
+        
+        // Check for name conflicts with existing columns
+        for (i, field) in output_fields.iter().enumerate() {
+            if output_fields[..i].iter().any(|f| f.name == field.name) {
+                return Err(ProcessingError::InvalidArgument(
+                    format!("Duplicate column name '{}' after flatten", field.name)
+                ));
+            }
+        }
+        
+        let mut result = DataSet::new(Schema::new(output_fields));
+        
+        // Copy data, expanding flattened columns
+        for row in &input.data {
+            let mut values = Vec::new();
+            
+            for (i, leaves) in &layout {
+                match leaves {
+                    None => values.push(row.values[*i].clone()),
+                    Some(leaves) => {
+                        for (path, data_type) in leaves {
+                            let value = match &row.values[*i] {
+                                Value::Map(map) => self.lookup(map, path)
+                                    .map_or(Value::Null, |value| conform_value(value, data_type)),
+                                _ => Value::Null,
+                            };
+                            
+                            values.push(value);
+                        }
+                    },
+                }
+            }
+            
+            result.add_row(Row::new(values))?;
+        }
+        
+        // Copy metadata
+        for (key, value) in &input.metadata.properties {
+            result.metadata.add(key.clone(), value.clone());
+        }
+        
+        Ok(result)
+    }
+    
+    fn name(&self) -> &str {
+        "flatten"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Flatten {
+            columns: self.columns.clone(),
+            separator: self.separator.clone(),
+        }.to_config())
+    }
+}
+
+/// Unnest an array column into one row per element
+pub struct ExplodeTransform {
+    column: String,
+    outer: bool,
+}
+
+impl ExplodeTransform {
+    /// Create a new explode transform, dropping rows with null or empty arrays
+    pub fn new(column: &str) -> Self {
+        ExplodeTransform {
+            column: column.to_string(),
+            outer: false,
+        }
+    }
+    
+    /// Keep rows with null or empty arrays, with a null element
+    pub fn outer(mut self) -> Self {
+        self.outer = true;
+        self
+    }
+}
+
+impl DataProcessor for ExplodeTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        // Find column index
+        let col_idx = input.schema.fields.iter()
+            .position(|field| field.name == self.column)
+            .ok_or_else(|| ProcessingError::InvalidArgument(
+                format!("Column '{}' not found", self.column)
+            ))?;
+        
+        // Element type comes from the array type, or is inferred from the data
+        let (element_type, inferred) = match &input.schema.fields[col_idx].data_type {
+            DataType::Array(element_type) => match element_type.as_ref() {
+                DataType::String => (infer_type(input.data.iter()
+                    .filter_map(|row| match &row.values[col_idx] {
+                        Value::Array(values) => Some(values.iter()),
+                        _ => None,
+                    })
+                    .flatten()), true),
+                other => (other.clone(), false),
+            },
+            other => (other.clone(), false),
+        };
+        
+        let mut fields = input.schema.fields.clone();
+        fields[col_idx].data_type = element_type.clone();
+        fields[col_idx].nullable = true;
+        
+        let mut result = DataSet::new(Schema::new(fields));
+        
+        // Emit one row per array element
+        for row in &input.data {
+            match &row.values[col_idx] {
+                Value::Array(elements) if !elements.is_empty() => {
+                    for element in elements {
+                        let mut values = row.values.clone();
+                        values[col_idx] = if inferred {
+                            conform_value(element, &element_type)
+                        } else {
+                            element.clone()
+                        };
+                        result.add_row(Row::new(values))?;
+                    }
+                },
+                Value::Array(_) | Value::Null => {
+                    if self.outer {
+                        let mut values = row.values.clone();
+                        values[col_idx] = Value::Null;
+                        result.add_row(Row::new(values))?;
+                    }
+                },
+                _ => {
+                    // Scalars are treated as single-element arrays
+                    result.add_row(row.clone())?;
+                },
+            }
+        }
+        
+        // Copy metadata
+        for (key, value) in &input.metadata.properties {
+            result.metadata.add(key.clone(), value.clone());
+        }
+        
+        Ok(result)
+    }
+    
+    fn name(&self) -> &str {
+        "explode"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Explode { column: self.column.clone(), outer: self.outer }.to_config())
+    }
+}
+
+/// Infer a data type from a set of values
+///
+/// Integers mixed with floats become Float; any other mix becomes String.
+/// Values are left as they are, so convert them with `conform_value`.
+pub(crate) fn infer_type<'a, I: Iterator<Item = &'a Value>>(values: I) -> DataType {
+    let mut inferred: Option<DataType> = None;
+    
+    for value in values {
+        let data_type = match value {
+            Value::Null => continue,
+            Value::Boolean(_) => DataType::Boolean,
+            Value::Integer(_) => DataType::Integer,
+            Value::Float(_) => DataType::Float,
+            Value::Decimal(d) => d.data_type(),
+            Value::String(_) => DataType::String,
+            Value::Binary(_) => DataType::Binary,
+            Value::Point(_, _) => DataType::Point,
+            Value::Uuid(_) => DataType::Uuid,
+            Value::Date(_) => DataType::Date,
+            Value::Timestamp(_) => DataType::Timestamp,
+            Value::Time(_) => DataType::Time,
+            Value::Array(_) => DataType::Array(Box::new(DataType::String)),
+            Value::Map(_) => DataType::Map(Box::new(DataType::String)),
+        };
+        
+        inferred = match inferred {
+            None => Some(data_type),
+            Some(current) => match current.widen(&data_type) {
+                Some(widened) => Some(widened),
+                None => return DataType::String,
+            },
+        };
+    }
+    
+    inferred.unwrap_or(DataType::String)
+}
+
+/// Convert a value to a type inferred by `infer_type`
+///
+/// Integers become floats in a Float column, and values of a mixed column
+/// become strings; nested values and binary are written as JSON text.
+pub(crate) fn conform_value(value: &Value, data_type: &DataType) -> Value {
+    match (value, data_type) {
+        (Value::Array(_), DataType::Array(_)) | (Value::Map(_), DataType::Map(_)) => value.clone(),
+        (Value::Array(_), DataType::String) | (Value::Map(_), DataType::String) | (Value::Binary(_), DataType::String) => {
+            match JsonSink::value_to_json(value) {
+                JsonValue::String(s) => Value::String(s),
+                json => Value::String(json.to_string()),
+            }
+        },
+        _ => cast_value(value, data_type, &CastFormat::default()).unwrap_or_else(|_| value.clone()),
+    }
+}
+
+/// Segment of a JSONPath expression
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+/// Parse a JSONPath-like expression such as `$.payload.items[0]['user id']`
+fn parse_json_path(path: &str) -> Result<Vec<PathSegment>, ProcessingError> {
+    let invalid = || ProcessingError::InvalidArgument(format!("Invalid JSONPath '{}'", path));
+    
+    let rest = path.trim().strip_prefix('$').ok_or_else(invalid)?;
+    let chars: Vec<char> = rest.chars().collect();
+    let mut segments = Vec::new();
+    let mut i = 0;
+    
+    while i < chars.len() {
+        match chars[i] {
+            '.' => {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len() && chars[end] != '.' && chars[end] != '[' {
+                    end += 1;
+                }
+                
+                let key: String = chars[start..end].iter().collect();
+                if key.is_empty() {
+                    return Err(invalid());
+                }
+                
+                segments.push(if key == "*" { PathSegment::Wildcard } else { PathSegment::Key(key) });
+                i = end;
+            },
+            '[' => {
+                let end = chars[i..].iter().position(|&c| c == ']').ok_or_else(invalid)? + i;
+                let inner: String = chars[i + 1..end].iter().collect();
+                let inner = inner.trim();
+                
+                if inner == "*" {
+                    segments.push(PathSegment::Wildcard);
+                } else if (inner.starts_with('\'') && inner.ends_with('\'') && inner.len() >= 2)
+                    || (inner.starts_with('"') && inner.ends_with('"') && inner.len() >= 2) {
+                    segments.push(PathSegment::Key(inner[1..inner.len() - 1].to_string()));
+                } else {
+                    segments.push(PathSegment::Index(inner.parse().map_err(|_| invalid())?));
+                }
+                
+                i = end + 1;
+            },
+            _ => return Err(invalid()),
+        }
+    }
+    
+    Ok(segments)
+}
+
+/// Evaluate parsed JSONPath segments against a value
+fn evaluate_json_path(value: &Value, segments: &[PathSegment]) -> Value {
+    let segment = match segments.first() {
+        Some(segment) => segment,
+        None => return value.clone(),
+    };
+    
+    match (segment, value) {
+        (PathSegment::Key(key), Value::Map(map)) => map.get(key)
+            .map_or(Value::Null, |v| evaluate_json_path(v, &segments[1..])),
+        (PathSegment::Index(index), Value::Array(values)) => values.get(*index)
+            .map_or(Value::Null, |v| evaluate_json_path(v, &segments[1..])),
+        (PathSegment::Wildcard, Value::Array(values)) => Value::Array(
+            values.iter().map(|v| evaluate_json_path(v, &segments[1..])).collect()
+        ),
+        (PathSegment::Wildcard, Value::Map(map)) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            Value::Array(keys.into_iter().map(|k| evaluate_json_path(&map[k], &segments[1..])).collect())
+        },
+        _ => Value::Null,
+    }
+}
+
+/// Extract values from a JSON column into new typed columns
+pub struct JsonPathTransform {
+    column: String,
+    extractions: Vec<(String, String, DataType)>, // (path, output column, data type)
+}
+
+impl JsonPathTransform {
+    /// Create a new JSONPath transform reading from a Map or JSON string column
+    pub fn new(column: &str) -> Self {
+        JsonPathTransform {
+            column: column.to_string(),
+            extractions: Vec::new(),
+        }
+    }
+    
+    /// Add a path to extract into a new column
+    pub fn extract(mut self, path: &str, output_column: &str, data_type: DataType) -> Self {
+        self.extractions.push((path.to_string(), output_column.to_string(), data_type));
+        self
+    }
+}
+
+impl DataProcessor for JsonPathTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        // Find column index
+        let col_idx = input.schema.fields.iter()
+            .position(|field| field.name == self.column)
+            .ok_or_else(|| ProcessingError::InvalidArgument(
+                format!("Column '{}' not found", self.column)
+            ))?;
+        
+        // Parse all paths up front
+        let paths = self.extractions.iter()
+            .map(|(path, _, _)| parse_json_path(path))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        // Create new schema with extracted columns
+        let mut fields = input.schema.fields.clone();
+        
+        for (_, output_column, data_type) in &self.extractions {
+            if fields.iter().any(|f| &f.name == output_column) {
+                return Err(ProcessingError::InvalidArgument(
+                    format!("Column '{}' already exists", output_column)
+                ));
+            }
+            
+            fields.push(Field::new(output_column.clone(), data_type.clone(), true));
+        }
+        
+        let mut result = DataSet::new(Schema::new(fields));
+        let format = super::CastFormat::default();
+        
+        // Extract values from each row
+        for row in &input.data {
+            // JSON strings are parsed, maps are used as-is
+            let parsed;
+            let document = match &row.values[col_idx] {
+                Value::String(s) => {
+                    let json: serde_json::Value = serde_json::from_str(s)
+                        .map_err(|e| ProcessingError::InvalidOperation(
+                            format!("Cannot parse '{}' as JSON: {}", s, e)
+                        ))?;
+                    parsed = crate::data::JsonSource::json_to_value(&json);
+                    &parsed
+                },
+                other => other,
+            };
+            
+            let mut values = row.values.clone();
+            
+            for (segments, (_, _, data_type)) in paths.iter().zip(&self.extractions) {
+                let value = evaluate_json_path(document, segments);
+                
+                let value = match (&value, data_type) {
+                    (Value::Array(_), DataType::Array(_)) | (Value::Map(_), DataType::Map(_)) => value,
+                    _ => super::cast_value(&value, data_type, &format)?,
+                };
+                
+                values.push(value);
+            }
+            
+            result.add_row(Row::new(values))?;
+        }
+        
+        // Copy metadata
+        for (key, value) in &input.metadata.properties {
+            result.metadata.add(key.clone(), value.clone());
+        }
+        
+        Ok(result)
+    }
+    
+    fn name(&self) -> &str {
+        "json_path"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        let extractions = self.extractions.iter()
+            .map(|(path, output, data_type)| JsonPathSpec {
+                path: path.clone(),
+                output: output.clone(),
+                data_type: data_type.name(),
+            })
+            .collect();
+        
+        Ok(StepSpec::JsonPath { column: self.column.clone(), extractions }.to_config())
+    }
+}
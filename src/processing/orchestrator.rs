@@ -0,0 +1,520 @@
+// Dependency-aware orchestration of pipelines over stored datasets
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::data::{DataSet, DataType, Field, Row, Schema, Value};
+use crate::storage::{DataStorage, RetryPolicy, StorageError};
+use super::{Diagnostics, Pipeline, ProcessingError, Warning};
+
+/// A pipeline reading a stored dataset and storing its result
+pub struct PipelineStep {
+    name: String,
+    input: String,
+    output: String,
+    pipeline: Pipeline,
+    retry: Option<RetryPolicy>,
+    dead_letter: Option<String>,
+}
+
+/// Failed attempt of a step
+struct StepFailure {
+    error: String,
+    /// Whether the failure came from storage and may pass on retry
+    transient: bool,
+    /// The input batch, if it was loaded
+    input: Option<DataSet>,
+}
+
+/// Result of running a step with retries
+struct StepRun {
+    result: Result<(usize, Vec<Warning>), String>,
+    attempts: u32,
+    dead_lettered: Option<String>,
+}
+
+impl PipelineStep {
+    /// Create a new step running a pipeline from an input dataset to an output dataset
+    pub fn new(name: &str, input: &str, output: &str, pipeline: Pipeline) -> Self {
+        PipelineStep {
+            name: name.to_string(),
+            input: input.to_string(),
+            output: output.to_string(),
+            pipeline,
+            retry: None,
+            dead_letter: None,
+        }
+    }
+    
+    /// Retry transient storage failures with backoff
+    ///
+    /// Failures of the pipeline itself are not retried, since they would
+    /// fail the same way again.
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+    
+    /// Quarantine the input of a failed run to a dataset, with error context
+    ///
+    /// The input rows are appended to the dataset with `_step`, `_error`,
+    /// `_attempts` and `_failed_at` columns.
+    pub fn with_dead_letter(mut self, dataset: &str) -> Self {
+        self.dead_letter = Some(dataset.to_string());
+        self
+    }
+    
+    /// Get the step name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    
+    /// Get the input dataset name
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+    
+    /// Get the output dataset name
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+    
+    /// Load the input, run the pipeline and store the output, returning its warnings
+    fn execute(&self, storage: &(dyn DataStorage + Send + Sync)) -> Result<(usize, Vec<Warning>), StepFailure> {
+        let input = storage.load(&self.input).map_err(|e| StepFailure {
+            error: format!("Cannot load '{}': {}", self.input, e),
+            transient: e.is_transient(),
+            input: None,
+        })?;
+        
+        // Keep the input for dead-lettering only when it is needed
+        let diagnostics = Diagnostics::new();
+        let (output, input) = if self.dead_letter.is_some() {
+            (diagnostics.collect(|| self.pipeline.execute(&input)), Some(input))
+        } else {
+            (diagnostics.collect(|| self.pipeline.execute_owned(input)), None)
+        };
+        
+        let output = match output {
+            Ok(output) => output,
+            Err(err) => return Err(StepFailure {
+                error: err.to_string(),
+                transient: false,
+                input,
+            }),
+        };
+        let rows = output.len();
+        
+        storage.store(&self.output, &output).map_err(|e| StepFailure {
+            error: format!("Cannot store '{}': {}", self.output, e),
+            transient: e.is_transient(),
+            input,
+        })?;
+        
+        Ok((rows, diagnostics.warnings()))
+    }
+    
+    /// Run the step, retrying transient failures and dead-lettering the input
+    fn run(&self, storage: &(dyn DataStorage + Send + Sync)) -> StepRun {
+        let started = Instant::now();
+        let mut attempts = 0;
+        
+        let failure = loop {
+            attempts += 1;
+            
+            let failure = match self.execute(storage) {
+                Ok(output) => return StepRun { result: Ok(output), attempts, dead_lettered: None },
+                Err(failure) => failure,
+            };
+            
+            let policy = match &self.retry {
+                Some(policy) if failure.transient && attempts <= policy.max_retries => policy,
+                _ => break failure,
+            };
+            
+            let delay = policy.backoff(attempts - 1);
+            if let Some(timeout) = policy.timeout {
+                if started.elapsed() + delay > timeout {
+                    break failure;
+                }
+            }
+            
+            log::warn!("Pipeline step '{}' failed (attempt {}): {}", self.name, attempts, failure.error);
+            std::thread::sleep(delay);
+        };
+        
+        let dead_lettered = match (&self.dead_letter, &failure.input) {
+            (Some(dataset), Some(input)) => {
+                match self.quarantine(storage, dataset, input, &failure.error, attempts) {
+                    Ok(()) => Some(dataset.clone()),
+                    Err(err) => {
+                        log::error!("Cannot dead-letter input of step '{}' to '{}': {}", self.name, dataset, err);
+                        None
+                    },
+                }
+            },
+            _ => None,
+        };
+        
+        StepRun { result: Err(failure.error), attempts, dead_lettered }
+    }
+    
+    /// Append a failed input batch with error context to a dead-letter dataset
+    fn quarantine(
+        &self,
+        storage: &(dyn DataStorage + Send + Sync),
+        dataset: &str,
+        input: &DataSet,
+        error: &str,
+        attempts: u32,
+    ) -> Result<(), StorageError> {
+        let mut fields = input.schema.fields.clone();
+        fields.push(Field::new("_step".to_string(), DataType::String, false));
+        fields.push(Field::new("_error".to_string(), DataType::String, false));
+        fields.push(Field::new("_attempts".to_string(), DataType::Integer, false));
+        fields.push(Field::new("_failed_at".to_string(), DataType::String, false));
+        
+        let mut quarantined = if storage.exists(dataset)? {
+            let existing = storage.load(dataset)?;
+            
+            let names = |fields: &[Field]| fields.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+            if names(&existing.schema.fields) != names(&fields) {
+                return Err(StorageError::InvalidFormat(format!(
+                    "Dead-letter dataset '{}' has different columns than the input of step '{}'",
+                    dataset, self.name
+                )));
+            }
+            
+            existing
+        } else {
+            DataSet::new(Schema::new(fields))
+        };
+        
+        let failed_at = chrono::Utc::now().to_rfc3339();
+        
+        for row in &input.data {
+            let mut values = row.values.clone();
+            values.push(Value::String(self.name.clone()));
+            values.push(Value::String(error.to_string()));
+            values.push(Value::Integer(attempts as i64));
+            values.push(Value::String(failed_at.clone()));
+            
+            quarantined.add_row(Row::new(values))?;
+        }
+        
+        storage.store(dataset, &quarantined)
+    }
+}
+
+/// Outcome of a step in an orchestrated run
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepStatus {
+    /// The pipeline ran and stored this many rows
+    Succeeded(usize),
+    /// The output was newer than its input and no upstream step ran
+    UpToDate,
+    /// The pipeline failed with this error
+    Failed(String),
+    /// Not run because an upstream step failed
+    Skipped,
+}
+
+/// Report of a single step in an orchestrated run
+#[derive(Debug, Clone)]
+pub struct StepReport {
+    pub name: String,
+    pub output: String,
+    pub status: StepStatus,
+    pub duration: Duration,
+    /// Number of times the step ran, including retries
+    pub attempts: u32,
+    /// Dataset the input was quarantined to after a failure
+    pub dead_lettered: Option<String>,
+    /// Warnings the pipeline emitted in its successful run
+    pub warnings: Vec<Warning>,
+}
+
+/// Summary of an orchestrated run, with steps in the order they were added
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    pub steps: Vec<StepReport>,
+    pub duration: Duration,
+}
+
+impl RunSummary {
+    /// Count steps with a status matching a predicate
+    fn count<F: Fn(&StepStatus) -> bool>(&self, predicate: F) -> usize {
+        self.steps.iter().filter(|step| predicate(&step.status)).count()
+    }
+    
+    /// Number of steps that ran successfully
+    pub fn succeeded(&self) -> usize {
+        self.count(|status| matches!(status, StepStatus::Succeeded(_)))
+    }
+    
+    /// Number of steps skipped because their output was up to date
+    pub fn up_to_date(&self) -> usize {
+        self.count(|status| *status == StepStatus::UpToDate)
+    }
+    
+    /// Number of steps that failed
+    pub fn failed(&self) -> usize {
+        self.count(|status| matches!(status, StepStatus::Failed(_)))
+    }
+    
+    /// Number of steps not run because an upstream step failed
+    pub fn skipped(&self) -> usize {
+        self.count(|status| *status == StepStatus::Skipped)
+    }
+    
+    /// Number of failed steps whose input was quarantined
+    pub fn dead_lettered(&self) -> usize {
+        self.steps.iter().filter(|step| step.dead_lettered.is_some()).count()
+    }
+    
+    /// Check if no step failed
+    pub fn is_success(&self) -> bool {
+        self.failed() == 0 && self.skipped() == 0
+    }
+}
+
+/// Runs pipelines that feed each other in dependency order
+///
+/// A step depends on the step whose output is its input. Steps run once all
+/// their upstream steps have finished, with independent branches running in
+/// parallel. A step is skipped when its output is newer than its input and
+/// no upstream step ran; storages that do not report modification times
+/// always run. When a step fails, its downstream steps are skipped while
+/// other branches carry on.
+pub struct Orchestrator {
+    steps: Vec<PipelineStep>,
+    parallelism: usize,
+    force: bool,
+}
+
+impl Orchestrator {
+    /// Create a new orchestrator without steps
+    pub fn new() -> Self {
+        Orchestrator {
+            steps: Vec::new(),
+            parallelism: num_cpus::get(),
+            force: false,
+        }
+    }
+    
+    /// Add a step
+    pub fn add(mut self, step: PipelineStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+    
+    /// Set the maximum number of steps running at once
+    pub fn with_parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+    
+    /// Run every step even if its output is up to date
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+    
+    /// Get the upstream step of each step, checking for duplicates and cycles
+    fn dependencies(&self) -> Result<Vec<Option<usize>>, ProcessingError> {
+        let mut producers = HashMap::new();
+        let mut names = HashMap::new();
+        
+        for (i, step) in self.steps.iter().enumerate() {
+            if names.insert(step.name.as_str(), i).is_some() {
+                return Err(ProcessingError::InvalidArgument(
+                    format!("Duplicate step name '{}'", step.name)
+                ));
+            }
+            
+            if let Some(other) = producers.insert(step.output.as_str(), i) {
+                return Err(ProcessingError::InvalidArgument(format!(
+                    "Steps '{}' and '{}' both write dataset '{}'",
+                    self.steps[other].name, step.name, step.output
+                )));
+            }
+        }
+        
+        let upstream: Vec<Option<usize>> = self.steps.iter()
+            .map(|step| producers.get(step.input.as_str()).copied())
+            .collect();
+        
+        // Each step has at most one upstream step, so following the chain
+        // from every step finds any cycle
+        for start in 0..self.steps.len() {
+            let mut current = upstream[start];
+            let mut hops = 0;
+            
+            while let Some(i) = current {
+                if i == start || hops > self.steps.len() {
+                    return Err(ProcessingError::InvalidArgument(
+                        format!("Pipeline dependency cycle involving step '{}'", self.steps[start].name)
+                    ));
+                }
+                
+                current = upstream[i];
+                hops += 1;
+            }
+        }
+        
+        Ok(upstream)
+    }
+    
+    /// Get the step names in an order where every step follows its upstream step
+    pub fn plan(&self) -> Result<Vec<&str>, ProcessingError> {
+        let upstream = self.dependencies()?;
+        let downstream = downstream_of(&upstream);
+        
+        let mut queue: VecDeque<usize> = (0..self.steps.len())
+            .filter(|&i| upstream[i].is_none())
+            .collect();
+        let mut order = Vec::with_capacity(self.steps.len());
+        
+        while let Some(i) = queue.pop_front() {
+            order.push(self.steps[i].name.as_str());
+            queue.extend(&downstream[i]);
+        }
+        
+        Ok(order)
+    }
+    
+    /// Check if the output of a step is newer than its input
+    fn is_up_to_date(&self, step: &PipelineStep, storage: &(dyn DataStorage + Send + Sync)) -> bool {
+        if !storage.exists(&step.output).unwrap_or(false) {
+            return false;
+        }
+        
+        let modified = |name: &str| storage.info(name).ok().and_then(|info| info.last_modified);
+        
+        match (modified(&step.input), modified(&step.output)) {
+            (Some(input), Some(output)) => output >= input,
+            _ => false,
+        }
+    }
+    
+    /// Run all steps against a storage and report their outcomes
+    pub fn run(&self, storage: &(dyn DataStorage + Send + Sync)) -> Result<RunSummary, ProcessingError> {
+        let start = Instant::now();
+        let upstream = self.dependencies()?;
+        let downstream = downstream_of(&upstream);
+        
+        let mut reports: Vec<Option<StepReport>> = vec![None; self.steps.len()];
+        let mut ready: VecDeque<usize> = (0..self.steps.len())
+            .filter(|&i| upstream[i].is_none())
+            .collect();
+        
+        std::thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel();
+            let mut running = 0;
+            
+            loop {
+                // Start ready steps, settling the ones that need no run
+                while running < self.parallelism {
+                    let i = match ready.pop_front() {
+                        Some(i) => i,
+                        None => break,
+                    };
+                    let step = &self.steps[i];
+                    
+                    let upstream_status = upstream[i]
+                        .and_then(|u| reports[u].as_ref())
+                        .map(|report| &report.status);
+                    
+                    let status = match upstream_status {
+                        Some(StepStatus::Failed(_)) | Some(StepStatus::Skipped) => Some(StepStatus::Skipped),
+                        Some(StepStatus::Succeeded(_)) => None,
+                        _ if !self.force && self.is_up_to_date(step, storage) => Some(StepStatus::UpToDate),
+                        _ => None,
+                    };
+                    
+                    match status {
+                        Some(status) => {
+                            reports[i] = Some(StepReport {
+                                name: step.name.clone(),
+                                output: step.output.clone(),
+                                status,
+                                duration: Duration::from_secs(0),
+                                attempts: 0,
+                                dead_lettered: None,
+                                warnings: Vec::new(),
+                            });
+                            ready.extend(&downstream[i]);
+                        },
+                        None => {
+                            let sender = sender.clone();
+                            running += 1;
+                            
+                            scope.spawn(move || {
+                                let started = Instant::now();
+                                let run = step.run(storage);
+                                let _ = sender.send((i, run, started.elapsed()));
+                            });
+                        },
+                    }
+                }
+                
+                if running == 0 {
+                    break;
+                }
+                
+                // Wait for a running step to finish
+                let (i, run, duration) = match receiver.recv() {
+                    Ok(message) => message,
+                    Err(_) => break,
+                };
+                running -= 1;
+                
+                let step = &self.steps[i];
+                let (status, warnings) = match run.result {
+                    Ok((rows, warnings)) => (StepStatus::Succeeded(rows), warnings),
+                    Err(err) => {
+                        log::warn!("Pipeline step '{}' failed: {}", step.name, err);
+                        (StepStatus::Failed(err), Vec::new())
+                    },
+                };
+                
+                reports[i] = Some(StepReport {
+                    name: step.name.clone(),
+                    output: step.output.clone(),
+                    status,
+                    duration,
+                    attempts: run.attempts,
+                    dead_lettered: run.dead_lettered,
+                    warnings,
+                });
+                ready.extend(&downstream[i]);
+            }
+        });
+        
+        Ok(RunSummary {
+            steps: reports.into_iter().flatten().collect(),
+            duration: start.elapsed(),
+        })
+    }
+}
+
+impl Default for Orchestrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Invert upstream links into the downstream steps of each step
+fn downstream_of(upstream: &[Option<usize>]) -> Vec<Vec<usize>> {
+    let mut downstream = vec![Vec::new(); upstream.len()];
+    
+    for (i, parent) in upstream.iter().enumerate() {
+        if let Some(parent) = parent {
+            downstream[*parent].push(i);
+        }
+    }
+    
+    downstream
+}
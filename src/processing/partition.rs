@@ -0,0 +1,96 @@
+// Hash partitioning for parallel processing
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::data::Row;
+use super::{Diagnostics, ProcessingError};
+
+/// Fewest rows worth handing to a partition of their own
+const MIN_PARTITION_ROWS: usize = 10_000;
+
+/// Get the number of partitions to use for a number of rows
+///
+/// Small inputs are processed in a single partition, as spawning threads
+/// costs more than it saves.
+pub(crate) fn partition_count(rows: usize, max_partitions: usize) -> usize {
+    (rows / MIN_PARTITION_ROWS).clamp(1, max_partitions.max(1))
+}
+
+/// Split row indices into partitions by the hash of their key columns
+///
+/// Rows with equal keys land in the same partition, and every partition
+/// keeps the input order of its rows.
+pub(crate) fn partition_rows(rows: &[Row], key_indices: &[usize], partitions: usize) -> Result<Vec<Vec<usize>>, ProcessingError> {
+    if partitions <= 1 {
+        return Ok(vec![(0..rows.len()).collect()]);
+    }
+    
+    // Hash chunks of rows in parallel, then gather each partition in chunk order
+    let chunk_size = (rows.len() + partitions - 1) / partitions;
+    let chunks: Vec<Vec<Vec<usize>>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = rows.chunks(chunk_size.max(1)).enumerate()
+            .map(|(c, chunk)| scope.spawn(move || {
+                let mut parts = vec![Vec::new(); partitions];
+                
+                for (offset, row) in chunk.iter().enumerate() {
+                    let mut hasher = DefaultHasher::new();
+                    for &i in key_indices {
+                        row.values[i].hash(&mut hasher);
+                    }
+                    
+                    let part = (hasher.finish() % partitions as u64) as usize;
+                    parts[part].push(c * chunk_size + offset);
+                }
+                
+                parts
+            }))
+            .collect();
+        
+        handles.into_iter()
+            .map(|handle| handle.join().map_err(|_| {
+                ProcessingError::Other("Partitioning worker panicked".to_string())
+            }))
+            .collect::<Result<_, _>>()
+    })?;
+    
+    let mut result = vec![Vec::new(); partitions];
+    for chunk in chunks {
+        for (part, indices) in chunk.into_iter().enumerate() {
+            result[part].extend(indices);
+        }
+    }
+    
+    Ok(result)
+}
+
+/// Process partitions on separate threads, returning results in partition order
+pub(crate) fn process_partitions<P, T, F>(partitions: &[P], process: F) -> Result<Vec<T>, ProcessingError>
+where
+    P: Sync,
+    T: Send,
+    F: Fn(&P) -> Result<T, ProcessingError> + Sync,
+{
+    if partitions.len() <= 1 {
+        return partitions.iter().map(&process).collect();
+    }
+    
+    // Workers emit their warnings to the diagnostics of the calling thread
+    let process = &process;
+    let diagnostics = &Diagnostics::current();
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = partitions.iter()
+            .map(|part| scope.spawn(move || match diagnostics {
+                Some(diagnostics) => diagnostics.collect(|| process(part)),
+                None => process(part),
+            }))
+            .collect();
+        
+        handles.into_iter()
+            .map(|handle| handle.join().map_err(|_| {
+                ProcessingError::Other("Partition worker panicked".to_string())
+            })?)
+            .collect()
+    })
+}
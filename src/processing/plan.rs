@@ -0,0 +1,169 @@
+// Dry runs and planning of pipelines
+// Author: Gabriel Demetrios Lafis
+
+use std::cmp::Ordering;
+
+use crate::data::{DataSet, Schema};
+use super::{DataProcessor, Pipeline, ProcessingError, StepSpec, TableEstimate, step_of};
+
+/// Rows of the input a dry run processes by default
+pub const DRY_RUN_SAMPLE_ROWS: usize = 1000;
+
+/// Plan of a pipeline, as found by a dry run
+#[derive(Debug, Clone)]
+pub struct PipelinePlan {
+    pub name: String,
+    /// Rows of the whole input
+    pub input_rows: usize,
+    /// Rows of the input the dry run processed
+    pub sample_rows: usize,
+    pub steps: Vec<PlannedStep>,
+    /// Schema of the output
+    pub schema: Schema,
+    /// Estimated rows of the output
+    pub estimated_rows: usize,
+    /// Whether the sample was the whole input, making the estimates exact
+    pub exact: bool,
+}
+
+/// Step of a pipeline plan
+#[derive(Debug, Clone)]
+pub struct PlannedStep {
+    pub name: String,
+    /// Schema of the step's output
+    pub schema: Schema,
+    /// Estimated rows of the step's output
+    pub estimated_rows: usize,
+}
+
+impl PipelinePlan {
+    /// Plan a single processing step from its output on a sample of the input
+    pub fn from_step(name: &str, sample: &DataSet, input_rows: usize, output: &DataSet) -> Self {
+        let sample_rows = sample.len();
+        let input_rows = input_rows.max(sample_rows);
+        let estimated_rows = estimate_rows(output.len(), sample_rows, input_rows);
+        
+        PipelinePlan {
+            name: name.to_string(),
+            input_rows,
+            sample_rows,
+            steps: vec![PlannedStep {
+                name: name.to_string(),
+                schema: output.schema.clone(),
+                estimated_rows,
+            }],
+            schema: output.schema.clone(),
+            estimated_rows,
+            exact: sample_rows == input_rows,
+        }
+    }
+}
+
+impl Pipeline {
+    /// Plan the pipeline on a sample of its input, without running it on the whole input
+    ///
+    /// Input statistics are computed from the sample; see
+    /// `dry_run_with_statistics`.
+    pub fn dry_run(&self, sample: &DataSet, input_rows: usize) -> Result<PipelinePlan, ProcessingError> {
+        self.dry_run_with_statistics(sample, &TableEstimate::compute(sample).scaled(input_rows.max(sample.len())))
+    }
+    
+    /// Plan the pipeline on a sample of its input, given statistics of the whole input
+    ///
+    /// Every step runs on the sample, so unknown columns, type mismatches and
+    /// invalid parameters fail as they would in a full run. Row counts are
+    /// estimated from the statistics by the steps that can, such as filters
+    /// and aggregations, and scaled from the sample for the others. Counts
+    /// are exact when the sample is the whole input.
+    pub fn dry_run_with_statistics(&self, sample: &DataSet, statistics: &TableEstimate) -> Result<PipelinePlan, ProcessingError> {
+        let sample_rows = sample.len();
+        let input_rows = statistics.rows.max(sample_rows);
+        let exact = sample_rows == input_rows;
+        let mut current = sample.clone();
+        let mut estimate = statistics.clone();
+        let mut steps = Vec::with_capacity(self.processors.len());
+        
+        for processor in &self.processors {
+            let before = current.len();
+            
+            match processor.as_in_place() {
+                Some(in_place) => in_place.process_in_place(&mut current)?,
+                None => current = processor.process(&current)?,
+            }
+            
+            estimate = match processor.estimate(&estimate) {
+                Some(output) if !exact => output,
+                _ => {
+                    let rows = estimate_rows(current.len(), before, estimate.rows);
+                    TableEstimate::compute(&current).scaled(rows)
+                },
+            };
+            
+            steps.push(PlannedStep {
+                name: processor.name().to_string(),
+                schema: current.schema.clone(),
+                estimated_rows: estimate.rows,
+            });
+        }
+        
+        Ok(PipelinePlan {
+            name: self.name.clone(),
+            input_rows,
+            sample_rows,
+            steps,
+            estimated_rows: estimate.rows,
+            schema: current.schema,
+            exact,
+        })
+    }
+    
+    /// Order each run of consecutive filters so the most selective runs first
+    ///
+    /// Filters commute, so reordering them leaves the output unchanged while
+    /// later filters see fewer rows. Selectivities are estimated from
+    /// statistics of the input, carried through the steps before each run;
+    /// filters with equal estimates keep their order.
+    pub fn order_filters(mut self, statistics: &TableEstimate) -> Self {
+        let mut estimate = statistics.clone();
+        let mut start = 0;
+        
+        while start < self.processors.len() {
+            let end = (start..self.processors.len())
+                .find(|&i| !is_filter(self.processors[i].as_ref()))
+                .unwrap_or(self.processors.len());
+            
+            if end - start > 1 {
+                let rows = estimate.rows.max(1) as f64;
+                let mut run: Vec<(f64, Box<dyn DataProcessor>)> = self.processors.drain(start..end)
+                    .map(|processor| (processor.estimate(&estimate).map_or(1.0, |output| output.rows as f64 / rows), processor))
+                    .collect();
+                
+                run.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+                self.processors.splice(start..start, run.into_iter().map(|(_, processor)| processor));
+            }
+            
+            // Carry the estimate past the run and the step after it
+            for processor in &self.processors[start..(end + 1).min(self.processors.len())] {
+                estimate = processor.estimate(&estimate).unwrap_or_else(|| TableEstimate::unknown(estimate.rows));
+            }
+            
+            start = end + 1;
+        }
+        
+        self
+    }
+}
+
+/// Check whether a processor is a filter expression, which commutes with other filters
+fn is_filter(processor: &dyn DataProcessor) -> bool {
+    matches!(step_of(processor), Ok(StepSpec::Filter { .. }))
+}
+
+/// Scale the rows a step produced from a sample to the whole input
+fn estimate_rows(output_rows: usize, sample_rows: usize, input_rows: usize) -> usize {
+    if sample_rows == 0 || sample_rows >= input_rows {
+        return output_rows;
+    }
+    
+    (output_rows as f64 * input_rows as f64 / sample_rows as f64).round() as usize
+}
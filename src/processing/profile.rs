@@ -0,0 +1,143 @@
+// Dataset profiling
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::HashMap;
+
+use crate::data::{DataSet, DataType, Value};
+use super::{max_f64, mean_f64, min_f64, variance_f64, ProcessingError};
+
+/// Number of most frequent values kept per column
+const TOP_VALUES: usize = 5;
+
+/// Summary of a dataset for data quality checks
+#[derive(Debug, Clone)]
+pub struct DatasetProfile {
+    pub rows: usize,
+    pub columns: Vec<ColumnProfile>,
+}
+
+/// Summary of one column
+#[derive(Debug, Clone)]
+pub struct ColumnProfile {
+    pub name: String,
+    pub data_type: DataType,
+    pub nulls: usize,
+    pub distinct: usize,
+    /// Most frequent values with their counts, most frequent first
+    pub top_values: Vec<(Value, usize)>,
+    /// Statistics of numeric columns
+    pub numeric: Option<NumericProfile>,
+}
+
+/// Statistics and histogram of the finite values of a numeric column
+#[derive(Debug, Clone)]
+pub struct NumericProfile {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub histogram: Vec<HistogramBin>,
+}
+
+/// Count of values in a half-open range; the last bin includes its end
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramBin {
+    pub start: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+impl DatasetProfile {
+    /// Profile every column of a dataset, with numeric histograms of the given number of bins
+    pub fn compute(data: &DataSet, bins: usize) -> Result<Self, ProcessingError> {
+        if bins == 0 {
+            return Err(ProcessingError::InvalidArgument(
+                "Histograms need at least one bin".to_string()
+            ));
+        }
+        
+        let columns = data.schema.fields.iter().enumerate()
+            .map(|(i, field)| ColumnProfile::compute(data, i, &field.name, &field.data_type, bins))
+            .collect();
+        
+        Ok(DatasetProfile { rows: data.len(), columns })
+    }
+}
+
+impl ColumnProfile {
+    fn compute(data: &DataSet, column: usize, name: &str, data_type: &DataType, bins: usize) -> Self {
+        let mut counts: HashMap<&Value, usize> = HashMap::new();
+        let mut nulls = 0;
+        let mut numbers = Vec::new();
+        
+        for row in &data.data {
+            let value = &row.values[column];
+            
+            match value {
+                Value::Null => {
+                    nulls += 1;
+                    continue;
+                },
+                Value::Integer(i) => numbers.push(*i as f64),
+                Value::Float(f) if f.is_finite() => numbers.push(*f),
+                _ => {},
+            }
+            
+            *counts.entry(value).or_insert(0) += 1;
+        }
+        
+        // Most frequent first, ties in value order
+        let mut top_values: Vec<(Value, usize)> = counts.iter()
+            .map(|(value, count)| ((*value).clone(), *count))
+            .collect();
+        top_values.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top_values.truncate(TOP_VALUES);
+        
+        let numeric = if data_type.is_numeric() && !numbers.is_empty() {
+            Some(NumericProfile::compute(&numbers, bins))
+        } else {
+            None
+        };
+        
+        ColumnProfile {
+            name: name.to_string(),
+            data_type: data_type.clone(),
+            nulls,
+            distinct: counts.len(),
+            top_values,
+            numeric,
+        }
+    }
+}
+
+impl NumericProfile {
+    fn compute(values: &[f64], bins: usize) -> Self {
+        let min = min_f64(values);
+        let max = max_f64(values);
+        
+        // Equal-width bins; a single value gets a single bin
+        let width = (max - min) / bins as f64;
+        let bins = if width > 0.0 { bins } else { 1 };
+        
+        let mut histogram: Vec<HistogramBin> = (0..bins)
+            .map(|i| HistogramBin {
+                start: min + width * i as f64,
+                end: if i + 1 == bins { max } else { min + width * (i + 1) as f64 },
+                count: 0,
+            })
+            .collect();
+        
+        for &value in values {
+            let bin = if width > 0.0 { ((value - min) / width) as usize } else { 0 };
+            histogram[bin.min(bins - 1)].count += 1;
+        }
+        
+        NumericProfile {
+            min,
+            max,
+            mean: mean_f64(values),
+            std_dev: variance_f64(values).sqrt(),
+            histogram,
+        }
+    }
+}
@@ -0,0 +1,41 @@
+// Seedable randomness for processors
+// Author: Gabriel Demetrios Lafis
+
+use std::sync::Mutex;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Sequence of seeds handed to processors without their own seed
+static GLOBAL_SEEDS: Mutex<Option<StdRng>> = Mutex::new(None);
+
+/// Seed every randomized processor that has no seed of its own
+///
+/// Each processor run then draws its seed from a sequence started by this
+/// one, so a program making the same calls in the same order gets the same
+/// samples and generated values. `None` goes back to seeding from entropy.
+/// Identifiers that must stay unique, such as session IDs and encryption
+/// nonces, are never seeded.
+pub fn set_global_seed(seed: Option<u64>) {
+    *GLOBAL_SEEDS.lock().unwrap_or_else(|e| e.into_inner()) = seed.map(StdRng::seed_from_u64);
+}
+
+/// Check whether a global seed is set
+pub fn has_global_seed() -> bool {
+    GLOBAL_SEEDS.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+/// Get the random number generator of a processor run
+///
+/// A processor's own seed comes first, then the next seed of the global
+/// sequence, if any, and otherwise the generator is seeded from entropy.
+pub fn seeded_rng(seed: Option<u64>) -> StdRng {
+    if let Some(seed) = seed {
+        return StdRng::seed_from_u64(seed);
+    }
+    
+    match GLOBAL_SEEDS.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        Some(seeds) => StdRng::seed_from_u64(seeds.gen()),
+        None => StdRng::from_entropy(),
+    }
+}
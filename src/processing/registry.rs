@@ -0,0 +1,100 @@
+// Registry of aggregation functions by name
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use super::{AvgFunction, CountFunction, DynAggregateFunction, MaxFunction, MinFunction, OverflowPolicy, ProcessingError, SumFunction};
+
+/// Names of the built-in aggregation functions
+pub const BUILTIN_AGGREGATES: [&str; 5] = ["count", "sum", "avg", "min", "max"];
+
+/// Options of an aggregation, passed to every function factory
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AggregateOptions {
+    /// What to do when an integer result overflows
+    pub overflow: OverflowPolicy,
+}
+
+/// Factory creating an aggregation function from the options of an aggregation
+pub type AggregateFactory = Arc<dyn Fn(&AggregateOptions) -> Box<dyn DynAggregateFunction> + Send + Sync>;
+
+/// Aggregation functions registered in addition to the built-in ones
+static AGGREGATES: Mutex<BTreeMap<String, AggregateFactory>> = Mutex::new(BTreeMap::new());
+
+/// Register an aggregation function under a name
+///
+/// The function becomes usable by name everywhere aggregations are named:
+/// the aggregate endpoint's `function` field, pipeline specifications and
+/// `GroupByProcessor::aggregate_named`. Names are case-insensitive, and
+/// neither a built-in name nor an already registered one can be taken.
+pub fn register_aggregate<F>(name: &str, factory: F) -> Result<(), ProcessingError>
+where
+    F: Fn(&AggregateOptions) -> Box<dyn DynAggregateFunction> + Send + Sync + 'static,
+{
+    let name = name.trim().to_lowercase();
+    
+    if name.is_empty() {
+        return Err(ProcessingError::InvalidArgument("Aggregation function name cannot be empty".to_string()));
+    }
+    
+    if BUILTIN_AGGREGATES.contains(&name.as_str()) {
+        return Err(ProcessingError::InvalidArgument(
+            format!("Cannot replace built-in aggregation function: {}", name)
+        ));
+    }
+    
+    let mut aggregates = AGGREGATES.lock().unwrap_or_else(|e| e.into_inner());
+    
+    if aggregates.contains_key(&name) {
+        return Err(ProcessingError::InvalidOperation(
+            format!("Aggregation function already registered: {}", name)
+        ));
+    }
+    
+    aggregates.insert(name, Arc::new(factory));
+    Ok(())
+}
+
+/// Remove a registered aggregation function, returning whether it was registered
+pub fn unregister_aggregate(name: &str) -> bool {
+    AGGREGATES.lock().unwrap_or_else(|e| e.into_inner())
+        .remove(&name.trim().to_lowercase())
+        .is_some()
+}
+
+/// Get the names of all aggregation functions, built-in ones first
+pub fn aggregate_names() -> Vec<String> {
+    let aggregates = AGGREGATES.lock().unwrap_or_else(|e| e.into_inner());
+    
+    BUILTIN_AGGREGATES.iter()
+        .map(|name| name.to_string())
+        .chain(aggregates.keys().cloned())
+        .collect()
+}
+
+/// Create an aggregation function by name
+pub fn aggregate_function(name: &str, options: &AggregateOptions) -> Result<Box<dyn DynAggregateFunction>, ProcessingError> {
+    let name = name.trim().to_lowercase();
+    
+    let function: Box<dyn DynAggregateFunction> = match name.as_str() {
+        "count" => Box::new(CountFunction),
+        "sum" => Box::new(SumFunction::new().with_overflow(options.overflow)),
+        "avg" => Box::new(AvgFunction),
+        "min" => Box::new(MinFunction),
+        "max" => Box::new(MaxFunction),
+        _ => {
+            // Release the lock before calling the factory, which may use the registry
+            let factory = AGGREGATES.lock().unwrap_or_else(|e| e.into_inner()).get(&name).cloned();
+            
+            match factory {
+                Some(factory) => factory(options),
+                None => return Err(ProcessingError::InvalidArgument(
+                    format!("Unknown aggregation function: {}", name)
+                )),
+            }
+        },
+    };
+    
+    Ok(function)
+}
@@ -0,0 +1,278 @@
+// Scaling operations for data processing
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::data::{DataSet, DataType, Metadata, Row, Schema, Value};
+use super::{DataProcessor, ProcessingError, ProcessorType, StepSpec};
+
+/// Metadata key prefix for fitted scaling parameters
+const SCALE_METADATA_PREFIX: &str = "scale.";
+
+/// Method used to rescale a numeric column
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScalingMethod {
+    /// Rescale to [0, 1] using the minimum and maximum
+    MinMax,
+    /// Standardize to zero mean and unit standard deviation
+    ZScore,
+    /// Center on the median and scale by the interquartile range
+    Robust,
+}
+
+impl ScalingMethod {
+    /// Parse a scaling method from a string
+    pub fn from_str(s: &str) -> Result<Self, ProcessingError> {
+        match s.to_lowercase().as_str() {
+            "min_max" | "minmax" => Ok(ScalingMethod::MinMax),
+            "z_score" | "zscore" | "standard" => Ok(ScalingMethod::ZScore),
+            "robust" => Ok(ScalingMethod::Robust),
+            _ => Err(ProcessingError::InvalidArgument(
+                format!("Unknown scaling method: {}", s)
+            )),
+        }
+    }
+    
+    /// Get the name of the scaling method
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScalingMethod::MinMax => "min_max",
+            ScalingMethod::ZScore => "z_score",
+            ScalingMethod::Robust => "robust",
+        }
+    }
+    
+    /// Fit the center and scale for a set of values
+    fn fit(&self, values: &[f64]) -> ScaleParams {
+        if values.is_empty() {
+            return ScaleParams { center: 0.0, scale: 1.0 };
+        }
+        
+        let (center, scale) = match self {
+            ScalingMethod::MinMax => {
+                let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+                let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                (min, max - min)
+            },
+            ScalingMethod::ZScore => {
+                let mean = values.iter().sum::<f64>() / values.len() as f64;
+                let variance = values.iter()
+                    .map(|v| (v - mean).powi(2))
+                    .sum::<f64>() / values.len() as f64;
+                (mean, variance.sqrt())
+            },
+            ScalingMethod::Robust => {
+                let mut sorted = values.to_vec();
+                sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+                (quantile(&sorted, 0.5), quantile(&sorted, 0.75) - quantile(&sorted, 0.25))
+            },
+        };
+        
+        ScaleParams { center, scale }
+    }
+}
+
+/// Compute a quantile of sorted values with linear interpolation
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let idx = pos.floor() as usize;
+    let frac = pos - idx as f64;
+    
+    if idx + 1 < sorted.len() {
+        sorted[idx] + frac * (sorted[idx + 1] - sorted[idx])
+    } else {
+        sorted[idx]
+    }
+}
+
+/// Fitted parameters for scaling a column, applied as `(value - center) / scale`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScaleParams {
+    pub center: f64,
+    pub scale: f64,
+}
+
+/// Rescale numeric columns, fitting parameters on the input unless given
+pub struct ScaleTransform {
+    columns: Vec<String>,
+    method: ScalingMethod,
+    params: HashMap<String, ScaleParams>,
+}
+
+impl ScaleTransform {
+    /// Create a new scale transform
+    pub fn new(columns: Vec<String>, method: ScalingMethod) -> Self {
+        ScaleTransform {
+            columns,
+            method,
+            params: HashMap::new(),
+        }
+    }
+    
+    /// Create a min-max scale transform
+    pub fn min_max(columns: Vec<String>) -> Self {
+        Self::new(columns, ScalingMethod::MinMax)
+    }
+    
+    /// Create a z-score scale transform
+    pub fn z_score(columns: Vec<String>) -> Self {
+        Self::new(columns, ScalingMethod::ZScore)
+    }
+    
+    /// Create a robust scale transform
+    pub fn robust(columns: Vec<String>) -> Self {
+        Self::new(columns, ScalingMethod::Robust)
+    }
+    
+    /// Use fixed parameters for a column instead of fitting them
+    pub fn with_params(mut self, column: &str, params: ScaleParams) -> Self {
+        self.params.insert(column.to_string(), params);
+        self
+    }
+    
+    /// Create a scale transform that reapplies the parameters fitted on another dataset
+    pub fn from_metadata(metadata: &Metadata) -> Result<Self, ProcessingError> {
+        let mut method = None;
+        let mut transform = Self::new(Vec::new(), ScalingMethod::MinMax);
+        
+        let mut keys: Vec<&String> = metadata.properties.keys()
+            .filter(|key| key.starts_with(SCALE_METADATA_PREFIX))
+            .collect();
+        keys.sort();
+        
+        for key in keys {
+            let column = &key[SCALE_METADATA_PREFIX.len()..];
+            let invalid = || ProcessingError::InvalidArgument(
+                format!("Invalid scaling metadata for column '{}'", column)
+            );
+            
+            // Parameters are stored as "<method>,<center>,<scale>"
+            let parts: Vec<&str> = metadata.properties[key].split(',').collect();
+            if parts.len() != 3 {
+                return Err(invalid());
+            }
+            
+            let column_method = ScalingMethod::from_str(parts[0])?;
+            if method.map_or(false, |m| m != column_method) {
+                return Err(ProcessingError::InvalidArgument(
+                    "Scaling metadata mixes different methods".to_string()
+                ));
+            }
+            method = Some(column_method);
+            
+            let params = ScaleParams {
+                center: parts[1].parse().map_err(|_| invalid())?,
+                scale: parts[2].parse().map_err(|_| invalid())?,
+            };
+            
+            transform.columns.push(column.to_string());
+            transform.params.insert(column.to_string(), params);
+        }
+        
+        transform.method = method.ok_or_else(|| ProcessingError::InvalidArgument(
+            "No scaling parameters found in metadata".to_string()
+        ))?;
+        
+        Ok(transform)
+    }
+}
+
+impl DataProcessor for ScaleTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        let numeric = |value: &Value| match value {
+            Value::Integer(i) => Some(*i as f64),
+            Value::Float(f) if !f.is_nan() => Some(*f),
+            _ => None,
+        };
+        
+        // Find column indices and fit parameters
+        let mut scaled = Vec::new();
+        
+        for column in &self.columns {
+            let col_idx = input.schema.fields.iter()
+                .position(|field| &field.name == column)
+                .ok_or_else(|| ProcessingError::InvalidArgument(
+                    format!("Column '{}' not found", column)
+                ))?;
+            
+            let params = match self.params.get(column) {
+                Some(params) => *params,
+                None => {
+                    let values: Vec<f64> = input.data.iter()
+                        .filter_map(|row| numeric(&row.values[col_idx]))
+                        .collect();
+                    self.method.fit(&values)
+                },
+            };
+            
+            scaled.push((col_idx, column, params));
+        }
+        
+        // Create new schema with float columns
+        let mut fields = input.schema.fields.clone();
+        for (col_idx, _, _) in &scaled {
+            fields[*col_idx].data_type = DataType::Float;
+        }
+        
+        let mut result = DataSet::new(Schema::new(fields));
+        
+        for row in &input.data {
+            let mut values = row.values.clone();
+            
+            for (col_idx, column, params) in &scaled {
+                values[*col_idx] = match &row.values[*col_idx] {
+                    Value::Null => Value::Null,
+                    value => {
+                        let v = numeric(value).ok_or_else(|| ProcessingError::InvalidOperation(
+                            format!("Cannot scale non-numeric value {:?} in column '{}'", value, column)
+                        ))?;
+                        
+                        // Constant columns have no spread to scale by
+                        if params.scale == 0.0 {
+                            Value::Float(0.0)
+                        } else {
+                            Value::Float((v - params.center) / params.scale)
+                        }
+                    },
+                };
+            }
+            
+            result.add_row(Row::new(values))?;
+        }
+        
+        // Copy metadata
+        for (key, value) in &input.metadata.properties {
+            result.metadata.add(key.clone(), value.clone());
+        }
+        
+        // Record fitted parameters so they can be reapplied to new data
+        for (_, column, params) in &scaled {
+            result.metadata.add(
+                format!("{}{}", SCALE_METADATA_PREFIX, column),
+                format!("{},{},{}", self.method.as_str(), params.center, params.scale),
+            );
+        }
+        
+        Ok(result)
+    }
+    
+    fn name(&self) -> &str {
+        "scale"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Scale {
+            columns: self.columns.clone(),
+            method: self.method,
+            params: self.params.iter().map(|(column, params)| (column.clone(), *params)).collect(),
+        }.to_config())
+    }
+}
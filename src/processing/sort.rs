@@ -0,0 +1,190 @@
+// Sorting of datasets
+// Author: Gabriel Demetrios Lafis
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::data::{DataSet, Row, Value};
+use super::{DataProcessor, InPlaceDataProcessor, ProcessingError, ProcessorType, StepSpec};
+
+/// Where null values go in a sort
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NullOrder {
+    First,
+    Last,
+}
+
+impl Default for NullOrder {
+    fn default() -> Self {
+        NullOrder::Last
+    }
+}
+
+impl NullOrder {
+    /// Parse a null placement from a string
+    pub fn from_str(s: &str) -> Result<Self, ProcessingError> {
+        match s.to_lowercase().as_str() {
+            "first" => Ok(NullOrder::First),
+            "last" => Ok(NullOrder::Last),
+            _ => Err(ProcessingError::InvalidArgument(
+                format!("Unknown null order: {}", s)
+            )),
+        }
+    }
+}
+
+/// Column to sort by, with its direction and null placement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SortKey {
+    pub column: String,
+    #[serde(default = "default_ascending")]
+    pub ascending: bool,
+    /// Nulls go last unless placed first, whatever the direction
+    #[serde(default)]
+    pub nulls: NullOrder,
+}
+
+fn default_ascending() -> bool {
+    true
+}
+
+impl SortKey {
+    /// Sort by a column in ascending order
+    pub fn asc(column: &str) -> Self {
+        SortKey {
+            column: column.to_string(),
+            ascending: true,
+            nulls: NullOrder::Last,
+        }
+    }
+    
+    /// Sort by a column in descending order
+    pub fn desc(column: &str) -> Self {
+        SortKey {
+            ascending: false,
+            ..SortKey::asc(column)
+        }
+    }
+    
+    /// Put nulls before every other value
+    pub fn nulls_first(mut self) -> Self {
+        self.nulls = NullOrder::First;
+        self
+    }
+    
+    /// Put nulls after every other value
+    pub fn nulls_last(mut self) -> Self {
+        self.nulls = NullOrder::Last;
+        self
+    }
+}
+
+/// Sort rows by several columns
+///
+/// Rows are compared on each key in turn, the next key breaking ties of the
+/// previous ones. The sort is stable, so rows equal on every key keep their
+/// input order. Integers and floats compare by value; other values of
+/// different types order by type.
+pub struct SortProcessor {
+    keys: Vec<SortKey>,
+}
+
+impl SortProcessor {
+    /// Create a new sort processor with the given keys
+    pub fn new(keys: Vec<SortKey>) -> Self {
+        SortProcessor { keys }
+    }
+    
+    /// Find the column of each key
+    fn key_indices(&self, input: &DataSet) -> Result<Vec<(usize, &SortKey)>, ProcessingError> {
+        if self.keys.is_empty() {
+            return Err(ProcessingError::InvalidArgument(
+                "Sort requires at least one column".to_string()
+            ));
+        }
+        
+        self.keys.iter()
+            .map(|key| input.schema.fields.iter()
+                .position(|field| field.name == key.column)
+                .map(|i| (i, key))
+                .ok_or_else(|| ProcessingError::InvalidArgument(
+                    format!("Column '{}' not found", key.column)
+                )))
+            .collect()
+    }
+}
+
+/// Compare two rows by the sort keys
+fn compare_rows(a: &Row, b: &Row, keys: &[(usize, &SortKey)]) -> Ordering {
+    for &(i, key) in keys {
+        let cmp = match (&a.values[i], &b.values[i]) {
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Null, _) => match key.nulls {
+                NullOrder::First => Ordering::Less,
+                NullOrder::Last => Ordering::Greater,
+            },
+            (_, Value::Null) => match key.nulls {
+                NullOrder::First => Ordering::Greater,
+                NullOrder::Last => Ordering::Less,
+            },
+            (a, b) => {
+                let cmp = match (a, b) {
+                    (Value::Integer(a), Value::Float(b)) => (*a as f64).total_cmp(b),
+                    (Value::Float(a), Value::Integer(b)) => a.total_cmp(&(*b as f64)),
+                    (a, b) => a.cmp(b),
+                };
+                
+                if key.ascending { cmp } else { cmp.reverse() }
+            },
+        };
+        
+        if cmp != Ordering::Equal {
+            return cmp;
+        }
+    }
+    
+    Ordering::Equal
+}
+
+impl DataProcessor for SortProcessor {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        let mut result = input.clone();
+        self.process_in_place(&mut result)?;
+        Ok(result)
+    }
+    
+    fn name(&self) -> &str {
+        "sort"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+    
+    fn as_in_place(&self) -> Option<&dyn InPlaceDataProcessor> {
+        Some(self)
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Sort { keys: self.keys.clone() }.to_config())
+    }
+}
+
+impl InPlaceDataProcessor for SortProcessor {
+    fn process_in_place(&self, input: &mut DataSet) -> Result<(), ProcessingError> {
+        let keys = self.key_indices(input)?;
+        input.data.sort_by(|a, b| compare_rows(a, b, &keys));
+        Ok(())
+    }
+    
+    fn name(&self) -> &str {
+        "sort"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+}
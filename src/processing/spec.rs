@@ -0,0 +1,535 @@
+// Pipeline specifications
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::data::{ColumnRange, DataType, JsonSource};
+use super::{
+    AggregateOptions, BinStrategy, BinTransform, CastErrorPolicy, CastFormat, CastTransform, DataProcessor, DecodeTransform,
+    DropColumnsTransform, EncodeTransform, Encoding, EwmaTransform, ExplodeTransform, FillMethod, FilterExpression,
+    FilterProcessor, FlattenTransform, GenerateUuidTransform, GeoDistanceTransform, GeohashTransform, GroupByProcessor,
+    JoinProcessor, JoinType, JsonPathTransform, LimitProcessor, MakePointTransform, MovingAverageTransform,
+    MultiCastTransform, NanPolicy, OverflowPolicy, Pipeline, ProcessingError, RenameTransform, ResampleAggregation,
+    ResampleProcessor, SampleProcessor, ScaleParams, ScaleTransform, ScalingMethod, SelectTransform, SkipProcessor,
+    SortKey, SortProcessor, StatsProcessor, StatsType, WindowFunctionType, WindowProcessor, seeded_rng,
+};
+
+/// Pipeline described in a JSON file, built into a `Pipeline` to run it
+///
+/// ```json
+/// {
+///   "name": "daily_totals",
+///   "seed": 42,
+///   "steps": [
+///     { "type": "sample", "fraction": 0.1 },
+///     { "type": "filter", "expression": "amount > 0" },
+///     { "type": "aggregate", "group_by": ["day"],
+///       "aggregations": [{ "function": "sum", "input_column": "amount", "output_name": "total" }] }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineSpec {
+    pub name: String,
+    /// Seed of the random steps without their own seed, for reproducible runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub steps: Vec<StepSpec>,
+}
+
+/// One processor of a pipeline specification
+///
+/// Steps are also the configurations returned by `DataProcessor::to_config`,
+/// so an executed processor can be recorded and built again from its step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepSpec {
+    /// Keep rows matching a filter expression
+    Filter { expression: String },
+    /// Keep only the given columns, in order
+    Select { columns: Vec<String> },
+    /// Rename columns, as (old name, new name) pairs
+    Rename { renames: Vec<(String, String)> },
+    /// Remove columns
+    DropColumns { columns: Vec<String> },
+    /// Cast a column to another type
+    Cast {
+        column: String,
+        data_type: String,
+        #[serde(default)]
+        format: CastFormat,
+    },
+    /// Cast several columns with a shared error policy
+    MultiCast {
+        casts: Vec<CastSpec>,
+        policy: CastErrorPolicy,
+        #[serde(default)]
+        format: CastFormat,
+    },
+    /// Bucket a numeric column into a categorical column
+    Bin {
+        column: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        output: Option<String>,
+        strategy: BinStrategy,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        labels: Option<Vec<String>>,
+    },
+    /// Group rows and aggregate columns with count, sum, avg, min, max or a
+    /// registered aggregation function
+    Aggregate {
+        #[serde(default)]
+        group_by: Vec<String>,
+        aggregations: Vec<AggregationSpec>,
+        /// Filter expression on the aggregated rows
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        having: Option<String>,
+        #[serde(default)]
+        nan_policy: NanPolicy,
+    },
+    /// Join with a second dataset on key columns
+    Join {
+        join_type: JoinType,
+        #[serde(default)]
+        left_columns: Vec<String>,
+        #[serde(default)]
+        right_columns: Vec<String>,
+    },
+    /// Add a window function column
+    Window {
+        output: String,
+        function: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value_column: Option<String>,
+        #[serde(default)]
+        partition_by: Vec<String>,
+        /// (column, ascending) pairs
+        #[serde(default)]
+        order_by: Vec<(String, bool)>,
+        #[serde(default)]
+        args: Vec<JsonValue>,
+        #[serde(default)]
+        overflow: OverflowPolicy,
+    },
+    /// Compute a statistic over columns
+    Stats {
+        name: String,
+        columns: Vec<String>,
+        stats_type: StatsType,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        quantile: Option<f64>,
+        #[serde(default)]
+        nan_policy: NanPolicy,
+    },
+    /// Keep the first rows
+    Limit { limit: usize },
+    /// Drop the first rows
+    Skip { skip: usize },
+    /// Sort rows by several columns
+    Sort { keys: Vec<SortKey> },
+    /// Keep each row with the given probability
+    Sample {
+        fraction: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seed: Option<u64>,
+    },
+    /// Fill a UUID column with random UUIDs, adding it if missing
+    GenerateUuid {
+        column: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seed: Option<u64>,
+    },
+    /// Build a point column from latitude and longitude columns
+    MakePoint {
+        lat_column: String,
+        lon_column: String,
+        output: String,
+    },
+    /// Add the distance in kilometers from a point column to a fixed point
+    GeoDistance {
+        column: String,
+        lat: f64,
+        lon: f64,
+        output: String,
+    },
+    /// Add a geohash of a point column
+    Geohash {
+        column: String,
+        precision: usize,
+        output: String,
+    },
+    /// Flatten map columns, or all of them, into columns of their keys
+    Flatten {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        columns: Option<Vec<String>>,
+        separator: String,
+    },
+    /// Unnest an array column into one row per element
+    Explode {
+        column: String,
+        #[serde(default)]
+        outer: bool,
+    },
+    /// Extract JSONPath values into new columns
+    JsonPath {
+        column: String,
+        extractions: Vec<JsonPathSpec>,
+    },
+    /// Encode a binary or string column as text
+    Encode { column: String, encoding: Encoding },
+    /// Decode a text column to `binary` or `string` values
+    Decode {
+        column: String,
+        encoding: Encoding,
+        data_type: String,
+    },
+    /// Rescale numeric columns, with fixed parameters for some of them
+    Scale {
+        columns: Vec<String>,
+        method: ScalingMethod,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        params: BTreeMap<String, ScaleParams>,
+    },
+    /// Resample rows onto a regular time grid of `interval` seconds
+    Resample {
+        time_column: String,
+        interval: i64,
+        /// (column, aggregation) pairs
+        #[serde(default)]
+        aggregations: Vec<(String, ResampleAggregation)>,
+        #[serde(default)]
+        fill: FillMethod,
+        /// Most intervals the grid may have; `DEFAULT_MAX_BUCKETS` if unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_buckets: Option<usize>,
+    },
+    /// Add a moving average over a trailing window of rows
+    MovingAverage {
+        column: String,
+        window: usize,
+        output: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_periods: Option<usize>,
+        #[serde(default)]
+        partition_by: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        order_by: Option<String>,
+    },
+    /// Add an exponentially weighted moving average
+    Ewma {
+        column: String,
+        alpha: f64,
+        output: String,
+        #[serde(default)]
+        partition_by: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        order_by: Option<String>,
+    },
+}
+
+/// Aggregation of an aggregate step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregationSpec {
+    pub function: String,
+    pub input_column: String,
+    pub output_name: String,
+}
+
+/// Column cast of a multi-cast step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CastSpec {
+    pub column: String,
+    pub data_type: String,
+    /// Value used by the `default` policy
+    #[serde(default, skip_serializing_if = "JsonValue::is_null")]
+    pub default: JsonValue,
+    /// Date format of this column, overriding the step's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+}
+
+/// Extraction of a JSONPath step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonPathSpec {
+    pub path: String,
+    pub output: String,
+    pub data_type: String,
+}
+
+impl PipelineSpec {
+    /// Read a pipeline specification from a JSON file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ProcessingError> {
+        let text = fs::read_to_string(path.as_ref()).map_err(|e| ProcessingError::InvalidArgument(
+            format!("Cannot read pipeline spec {}: {}", path.as_ref().display(), e)
+        ))?;
+        
+        serde_json::from_str(&text).map_err(|e| ProcessingError::InvalidArgument(
+            format!("Invalid pipeline spec {}: {}", path.as_ref().display(), e)
+        ))
+    }
+    
+    /// Read a pipeline specification from its JSON configuration
+    pub fn from_config(config: JsonValue) -> Result<Self, ProcessingError> {
+        serde_json::from_value(config).map_err(|e| ProcessingError::InvalidArgument(
+            format!("Invalid pipeline configuration: {}", e)
+        ))
+    }
+    
+    /// Build the pipeline, checking every step
+    ///
+    /// Random steps without a seed get one drawn from the pipeline seed, or
+    /// from the global seed when the pipeline has none.
+    pub fn build(&self) -> Result<Pipeline, ProcessingError> {
+        let mut pipeline = Pipeline::new(&self.name);
+        let mut seeds = self.seed.map(|seed| seeded_rng(Some(seed)));
+        
+        for step in &self.steps {
+            let mut step = step.clone();
+            
+            if let Some(seed) = step.seed_mut() {
+                *seed = seed.or_else(|| seeds.as_mut().map(|rng| rng.gen()));
+            }
+            
+            pipeline = pipeline.add_boxed(step.build()?);
+        }
+        
+        Ok(pipeline)
+    }
+    
+    /// Get the column ranges the pipeline's input can be pruned to before it runs
+    ///
+    /// Only the filters the pipeline starts with are pushed down, since later
+    /// steps may change the columns they read. Storages skip blocks of rows
+    /// outside the ranges, such as Parquet row groups, and the filters still
+    /// run on the rows loaded.
+    pub fn input_ranges(&self) -> Result<Vec<ColumnRange>, ProcessingError> {
+        let mut ranges = Vec::new();
+        
+        for step in &self.steps {
+            match step {
+                StepSpec::Filter { expression } => ranges.extend(FilterExpression::parse(expression)?.column_ranges()),
+                _ => break,
+            }
+        }
+        
+        Ok(ranges)
+    }
+}
+
+impl StepSpec {
+    /// Read a step from a processor configuration
+    pub fn from_config(config: JsonValue) -> Result<Self, ProcessingError> {
+        serde_json::from_value(config).map_err(|e| ProcessingError::InvalidArgument(
+            format!("Invalid processor configuration: {}", e)
+        ))
+    }
+    
+    /// Get the step as a processor configuration
+    pub fn to_config(&self) -> JsonValue {
+        // Steps hold only strings, numbers and sequences, which always serialize
+        serde_json::to_value(self).expect("step specifications serialize to JSON")
+    }
+    
+    /// Get the seed of a random step
+    fn seed_mut(&mut self) -> Option<&mut Option<u64>> {
+        match self {
+            StepSpec::Sample { seed, .. } | StepSpec::GenerateUuid { seed, .. } => Some(seed),
+            _ => None,
+        }
+    }
+    
+    /// Build the processor of the step
+    pub fn build(&self) -> Result<Box<dyn DataProcessor>, ProcessingError> {
+        Ok(match self {
+            StepSpec::Filter { expression } => Box::new(FilterProcessor::expression(expression)?),
+            StepSpec::Select { columns } => Box::new(SelectTransform::new(columns.clone())),
+            StepSpec::Rename { renames } => Box::new(RenameTransform::new(renames.clone())),
+            StepSpec::DropColumns { columns } => Box::new(DropColumnsTransform::new(columns.clone())),
+            StepSpec::Cast { column, data_type, format } => {
+                Box::new(CastTransform::new(column, DataType::from_str(data_type)?).with_format(format.clone()))
+            },
+            StepSpec::MultiCast { casts, policy, format } => {
+                let mut transform = MultiCastTransform::new(*policy).with_format(format.clone());
+                
+                for cast in casts {
+                    let data_type = DataType::from_str(&cast.data_type)?;
+                    transform = transform.cast_with_default(&cast.column, data_type, JsonSource::json_to_value(&cast.default));
+                    
+                    if let Some(date_format) = &cast.date_format {
+                        transform = transform.with_date_format(&cast.column, date_format);
+                    }
+                }
+                
+                Box::new(transform)
+            },
+            StepSpec::Bin { column, output, strategy, labels } => {
+                let mut transform = BinTransform::new(column, strategy.clone());
+                
+                if let Some(output) = output {
+                    transform = transform.with_output(output);
+                }
+                
+                if let Some(labels) = labels {
+                    transform = transform.with_labels(labels.clone());
+                }
+                
+                Box::new(transform)
+            },
+            StepSpec::Aggregate { group_by, aggregations, having, nan_policy } => {
+                let mut processor = GroupByProcessor::new().with_nan_policy(*nan_policy);
+                
+                for column in group_by {
+                    processor = processor.group_by(column);
+                }
+                
+                for agg in aggregations {
+                    processor = processor.aggregate_named(
+                        &agg.output_name,
+                        &agg.input_column,
+                        &agg.function,
+                        &AggregateOptions::default(),
+                    )?;
+                }
+                
+                if let Some(having) = having {
+                    processor = processor.having(FilterExpression::parse(having)?);
+                }
+                
+                Box::new(processor)
+            },
+            StepSpec::Join { join_type, left_columns, right_columns } => {
+                Box::new(JoinProcessor::new(*join_type, left_columns.clone(), right_columns.clone()))
+            },
+            StepSpec::Window { output, function, value_column, partition_by, order_by, args, overflow } => {
+                Box::new(WindowProcessor::new(
+                    output,
+                    WindowFunctionType::from_str(function)?,
+                    value_column.clone(),
+                    partition_by.clone(),
+                    order_by.clone(),
+                    args.iter().map(JsonSource::json_to_value).collect(),
+                ).with_overflow(*overflow))
+            },
+            StepSpec::Stats { name, columns, stats_type, quantile, nan_policy } => {
+                let mut processor = StatsProcessor::new(name, columns.clone(), *stats_type).with_nan_policy(*nan_policy);
+                
+                if let Some(quantile) = quantile {
+                    processor = processor.with_quantile(*quantile);
+                }
+                
+                Box::new(processor)
+            },
+            StepSpec::Limit { limit } => Box::new(LimitProcessor::new(*limit)),
+            StepSpec::Skip { skip } => Box::new(SkipProcessor::new(*skip)),
+            StepSpec::Sort { keys } => Box::new(SortProcessor::new(keys.clone())),
+            StepSpec::Sample { fraction, seed } => Box::new(SampleProcessor::new(*fraction, *seed)),
+            StepSpec::GenerateUuid { column, seed } => {
+                let transform = GenerateUuidTransform::new(column);
+                
+                Box::new(match seed {
+                    Some(seed) => transform.with_seed(*seed),
+                    None => transform,
+                })
+            },
+            StepSpec::MakePoint { lat_column, lon_column, output } => {
+                Box::new(MakePointTransform::new(lat_column, lon_column, output))
+            },
+            StepSpec::GeoDistance { column, lat, lon, output } => {
+                Box::new(GeoDistanceTransform::new(column, *lat, *lon, output))
+            },
+            StepSpec::Geohash { column, precision, output } => Box::new(GeohashTransform::new(column, *precision, output)),
+            StepSpec::Flatten { columns, separator } => {
+                let transform = match columns {
+                    Some(columns) => FlattenTransform::columns(columns.clone()),
+                    None => FlattenTransform::new(),
+                };
+                
+                Box::new(transform.with_separator(separator))
+            },
+            StepSpec::Explode { column, outer } => {
+                let transform = ExplodeTransform::new(column);
+                
+                Box::new(if *outer { transform.outer() } else { transform })
+            },
+            StepSpec::JsonPath { column, extractions } => {
+                let mut transform = JsonPathTransform::new(column);
+                
+                for extraction in extractions {
+                    transform = transform.extract(&extraction.path, &extraction.output, DataType::from_str(&extraction.data_type)?);
+                }
+                
+                Box::new(transform)
+            },
+            StepSpec::Encode { column, encoding } => Box::new(EncodeTransform::new(column, *encoding)),
+            StepSpec::Decode { column, encoding, data_type } => match DataType::from_str(data_type)? {
+                DataType::Binary => Box::new(DecodeTransform::new(column, *encoding)),
+                DataType::String => Box::new(DecodeTransform::utf8(column, *encoding)),
+                other => return Err(ProcessingError::InvalidArgument(
+                    format!("Cannot decode to {}, only to binary or string", other.name())
+                )),
+            },
+            StepSpec::Scale { columns, method, params } => {
+                let mut transform = ScaleTransform::new(columns.clone(), *method);
+                
+                for (column, params) in params {
+                    transform = transform.with_params(column, *params);
+                }
+                
+                Box::new(transform)
+            },
+            StepSpec::Resample { time_column, interval, aggregations, fill, max_buckets } => {
+                let mut processor = ResampleProcessor::new(time_column, *interval).with_fill(*fill);
+                
+                if let Some(max_buckets) = max_buckets {
+                    processor = processor.with_max_buckets(*max_buckets);
+                }
+                
+                for (column, aggregation) in aggregations {
+                    processor = processor.aggregate(column, *aggregation);
+                }
+                
+                Box::new(processor)
+            },
+            StepSpec::MovingAverage { column, window, output, min_periods, partition_by, order_by } => {
+                let mut transform = MovingAverageTransform::new(column, *window, output).partition_by(partition_by.clone());
+                
+                if let Some(min_periods) = min_periods {
+                    transform = transform.with_min_periods(*min_periods);
+                }
+                
+                if let Some(order_by) = order_by {
+                    transform = transform.order_by(order_by);
+                }
+                
+                Box::new(transform)
+            },
+            StepSpec::Ewma { column, alpha, output, partition_by, order_by } => {
+                let mut transform = EwmaTransform::with_alpha(column, *alpha, output).partition_by(partition_by.clone());
+                
+                if let Some(order_by) = order_by {
+                    transform = transform.order_by(order_by);
+                }
+                
+                Box::new(transform)
+            },
+        })
+    }
+}
+
+/// Build a processor from the configuration returned by `DataProcessor::to_config`
+pub fn processor_from_config(config: JsonValue) -> Result<Box<dyn DataProcessor>, ProcessingError> {
+    StepSpec::from_config(config)?.build()
+}
+
+/// Get a processor configuration as a step
+pub(crate) fn step_of(processor: &dyn DataProcessor) -> Result<StepSpec, ProcessingError> {
+    StepSpec::from_config(processor.to_config()?)
+}
+
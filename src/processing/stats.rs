@@ -1,18 +1,24 @@
 // Statistical operations for data processing
 // Author: Gabriel Demetrios Lafis
 
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
 use crate::data::{DataSet, DataType, Field, Row, Schema, Value};
-use super::{DataProcessor, ProcessingError, ProcessorType};
+use super::{DataProcessor, NanPolicy, ProcessingError, ProcessorType, StepSpec, WarningKind, emit_warning, max_f64, mean_f64, min_f64, sum_f64, variance_f64};
 
 /// Statistical processor for computing statistics on datasets
 pub struct StatsProcessor {
     name: String,
     columns: Vec<String>,
     stats_type: StatsType,
+    quantile: f64,
+    nan_policy: NanPolicy,
 }
 
 /// Type of statistical operation
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum StatsType {
     Mean,
     Median,
@@ -36,9 +42,25 @@ impl StatsProcessor {
             name: name.to_string(),
             columns,
             stats_type,
+            quantile: 0.5,
+            nan_policy: NanPolicy::default(),
         }
     }
     
+    /// Set the quantile computed by a quantile processor, from 0 to 1
+    pub fn with_quantile(mut self, quantile: f64) -> Self {
+        self.quantile = quantile;
+        self
+    }
+    
+    /// Set how NaN and infinite values are handled
+    ///
+    /// With `NanPolicy::Error`, a non-finite result is also an error.
+    pub fn with_nan_policy(mut self, policy: NanPolicy) -> Self {
+        self.nan_policy = policy;
+        self
+    }
+    
     /// Create a mean processor
     pub fn mean(column: &str) -> Self {
         Self::new("mean", vec![column.to_string()], StatsType::Mean)
@@ -91,9 +113,7 @@ impl StatsProcessor {
     
     /// Create a quantile processor
     pub fn quantile(column: &str, quantile: f64) -> Self {
-        let mut processor = Self::new("quantile", vec![column.to_string()], StatsType::Quantile);
-        processor.quantile = quantile;
-        processor
+        Self::new("quantile", vec![column.to_string()], StatsType::Quantile).with_quantile(quantile)
     }
     
     /// Create a correlation processor
@@ -132,24 +152,24 @@ impl StatsProcessor {
         ))?;
         
         // Extract numeric values
+        let mut skipped = 0;
         for row in &input.data {
-            match &row.values[col_idx] {
-                Value::Integer(i) => values.push(*i as f64),
-                Value::Float(f) => values.push(*f),
-                _ => {}, // Ignore non-numeric values
+            match self.nan_policy.apply(&row.values[col_idx], column)? {
+                Some(Value::Integer(i)) => values.push(*i as f64),
+                Some(Value::Float(f)) => values.push(*f),
+                Some(_) => {}, // Ignore non-numeric values
+                None => skipped += 1,
             }
         }
         
+        emit_warning(&self.name, WarningKind::ValuesSkipped, Some(column), skipped, "NaN and infinite values skipped");
+        
         Ok(values)
     }
     
     /// Compute mean of values
     fn compute_mean(&self, values: &[f64]) -> f64 {
-        if values.is_empty() {
-            return 0.0;
-        }
-        
-        values.iter().sum::<f64>() / values.len() as f64
+        mean_f64(values)
     }
     
     /// Compute median of values
@@ -188,40 +208,22 @@ impl StatsProcessor {
     
     /// Compute standard deviation of values
     fn compute_std_dev(&self, values: &[f64]) -> f64 {
-        if values.is_empty() {
-            return 0.0;
-        }
-        
-        let mean = self.compute_mean(values);
-        let variance = values.iter()
-            .map(|&x| (x - mean).powi(2))
-            .sum::<f64>() / values.len() as f64;
-        
-        variance.sqrt()
+        variance_f64(values).sqrt()
     }
     
     /// Compute variance of values
     fn compute_variance(&self, values: &[f64]) -> f64 {
-        if values.is_empty() {
-            return 0.0;
-        }
-        
-        let mean = self.compute_mean(values);
-        values.iter()
-            .map(|&x| (x - mean).powi(2))
-            .sum::<f64>() / values.len() as f64
+        variance_f64(values)
     }
     
     /// Compute minimum of values
     fn compute_min(&self, values: &[f64]) -> f64 {
-        values.iter()
-            .fold(f64::INFINITY, |a, &b| a.min(b))
+        min_f64(values)
     }
     
     /// Compute maximum of values
     fn compute_max(&self, values: &[f64]) -> f64 {
-        values.iter()
-            .fold(f64::NEG_INFINITY, |a, &b| a.max(b))
+        max_f64(values)
     }
     
     /// Compute range of values
@@ -237,7 +239,7 @@ impl StatsProcessor {
     
     /// Compute sum of values
     fn compute_sum(&self, values: &[f64]) -> f64 {
-        values.iter().sum()
+        sum_f64(values)
     }
     
     /// Compute count of values
@@ -392,6 +394,15 @@ impl DataProcessor for StatsProcessor {
             },
         };
         
+        // Finite inputs can still overflow to infinity
+        if let Value::Float(f) = stat_value {
+            if self.nan_policy == NanPolicy::Error && !f.is_finite() {
+                return Err(ProcessingError::InvalidOperation(
+                    format!("{} of '{}' is {}", self.name, self.columns.join("', '"), f)
+                ));
+            }
+        }
+        
         // Create output row
         let output_row = Row::new(vec![stat_value]);
         result.add_row(output_row)?;
@@ -411,5 +422,15 @@ impl DataProcessor for StatsProcessor {
     fn processor_type(&self) -> ProcessorType {
         ProcessorType::Stats
     }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Stats {
+            name: self.name.clone(),
+            columns: self.columns.clone(),
+            stats_type: self.stats_type,
+            quantile: (self.stats_type == StatsType::Quantile).then(|| self.quantile),
+            nan_policy: self.nan_policy,
+        }.to_config())
+    }
 }
 
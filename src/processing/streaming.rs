@@ -0,0 +1,348 @@
+// Streaming execution of pipelines with bounded queues between stages
+// Author: Gabriel Demetrios Lafis
+
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::data::{DataError, DataSet, DataSink};
+use crate::utils::Telemetry;
+use super::{CancellationToken, EventTimeWindow, Pipeline, ProcessingError};
+
+/// Batches each queue between two stages holds by default
+const DEFAULT_QUEUE_CAPACITY: usize = 4;
+
+/// Snapshot of a queue between two stages
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueStats {
+    /// Batches waiting in the queue
+    pub depth: usize,
+    /// Most batches the queue holds before its producer waits
+    pub capacity: usize,
+    /// Most batches the queue has held at once
+    pub high_water: usize,
+    /// Batches passed through the queue
+    pub batches: u64,
+    /// Time the producer spent waiting for room in the queue
+    pub blocked: Duration,
+}
+
+/// Counters of one queue, updated by the stages on either side
+#[derive(Debug)]
+struct QueueMetrics {
+    capacity: usize,
+    depth: AtomicI64,
+    high_water: AtomicI64,
+    batches: AtomicU64,
+    blocked_nanos: AtomicU64,
+}
+
+impl QueueMetrics {
+    fn new(capacity: usize) -> Self {
+        QueueMetrics {
+            capacity,
+            depth: AtomicI64::new(0),
+            high_water: AtomicI64::new(0),
+            batches: AtomicU64::new(0),
+            blocked_nanos: AtomicU64::new(0),
+        }
+    }
+    
+    /// Get the number of batches waiting, never below zero while a batch is in hand-off
+    fn depth(&self) -> usize {
+        self.depth.load(Ordering::Relaxed).max(0) as usize
+    }
+    
+    fn stats(&self) -> QueueStats {
+        QueueStats {
+            depth: self.depth(),
+            capacity: self.capacity,
+            high_water: self.high_water.load(Ordering::Relaxed).max(0) as usize,
+            batches: self.batches.load(Ordering::Relaxed),
+            blocked: Duration::from_nanos(self.blocked_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Queue depths and progress of an executor's runs, readable while one runs
+///
+/// Counters add up over every run of the executor.
+#[derive(Debug)]
+pub struct StreamMetrics {
+    processing: QueueMetrics,
+    sink: QueueMetrics,
+    rows_read: AtomicU64,
+    rows_written: AtomicU64,
+}
+
+impl StreamMetrics {
+    fn new(capacity: usize) -> Self {
+        StreamMetrics {
+            processing: QueueMetrics::new(capacity),
+            sink: QueueMetrics::new(capacity),
+            rows_read: AtomicU64::new(0),
+            rows_written: AtomicU64::new(0),
+        }
+    }
+    
+    /// Get the queue of batches read from the source, waiting to be processed
+    pub fn processing_queue(&self) -> QueueStats {
+        self.processing.stats()
+    }
+    
+    /// Get the queue of processed batches, waiting to be written to the sink
+    pub fn sink_queue(&self) -> QueueStats {
+        self.sink.stats()
+    }
+    
+    /// Get the number of rows read from the source
+    pub fn rows_read(&self) -> u64 {
+        self.rows_read.load(Ordering::Relaxed)
+    }
+    
+    /// Get the number of rows written to the sink
+    pub fn rows_written(&self) -> u64 {
+        self.rows_written.load(Ordering::Relaxed)
+    }
+    
+    /// Report the queue depths as telemetry gauges
+    pub fn observe(self: &Arc<Self>, telemetry: &Telemetry) {
+        let metrics = self.clone();
+        telemetry.observe_gauge("rdpe.stream.processing_queue.depth", "{batch}", move || {
+            metrics.processing.depth() as i64
+        });
+        
+        let metrics = self.clone();
+        telemetry.observe_gauge("rdpe.stream.sink_queue.depth", "{batch}", move || {
+            metrics.sink.depth() as i64
+        });
+    }
+}
+
+/// Outcome of a streaming run
+#[derive(Debug, Clone)]
+pub struct StreamSummary {
+    /// Batches written to the sink
+    pub batches: u64,
+    /// Rows read from the source
+    pub rows_read: u64,
+    /// Rows written to the sink
+    pub rows_written: u64,
+    /// Time the run took
+    pub duration: Duration,
+}
+
+/// Executor running a pipeline over a stream of batches
+///
+/// The source, the pipeline and the sink each run on their own thread,
+/// connected by bounded queues. A stage finding the next queue full waits
+/// for room, so a slow sink throttles the pipeline and through it the
+/// source instead of letting batches pile up in memory: at most
+/// `2 * capacity` batches are queued at any time, plus one in each stage.
+///
+/// With event-time windows, the processing stage aggregates the pipeline's
+/// output into windows and hands on the rows of each window the watermark
+/// closes; windows still open when the source is exhausted are closed then.
+///
+/// A failing stage stops the run; the stages before it stop once they next
+/// hand off a batch, and the error is returned.
+pub struct StreamingExecutor {
+    pipeline: Pipeline,
+    window: Option<EventTimeWindow>,
+    queue_capacity: usize,
+    cancellation: CancellationToken,
+    metrics: Arc<StreamMetrics>,
+}
+
+impl StreamingExecutor {
+    /// Create an executor running a pipeline
+    pub fn new(pipeline: Pipeline) -> Self {
+        StreamingExecutor {
+            pipeline,
+            window: None,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            cancellation: CancellationToken::new(),
+            metrics: Arc::new(StreamMetrics::new(DEFAULT_QUEUE_CAPACITY)),
+        }
+    }
+    
+    /// Set the batches each queue between two stages holds before its producer waits
+    pub fn with_queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = capacity.max(1);
+        self.metrics = Arc::new(StreamMetrics::new(self.queue_capacity));
+        self
+    }
+    
+    /// Aggregate the pipeline's output into event-time windows
+    pub fn with_window(mut self, window: EventTimeWindow) -> Self {
+        self.window = Some(window);
+        self
+    }
+    
+    /// Get the event-time windows, to read their watermark and late rows
+    pub fn window(&self) -> Option<&EventTimeWindow> {
+        self.window.as_ref()
+    }
+    
+    /// Stop the run between batches once the token is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+    
+    /// Get the metrics of the executor's runs, to watch one while it runs
+    pub fn metrics(&self) -> Arc<StreamMetrics> {
+        self.metrics.clone()
+    }
+    
+    /// Run the pipeline over the batches of a source, writing them to a sink
+    ///
+    /// Blocks until the source is exhausted and every batch is written, or a
+    /// stage fails.
+    pub fn run<I, S>(&self, source: I, sink: &S) -> Result<StreamSummary, ProcessingError>
+    where
+        I: Iterator<Item = Result<DataSet, DataError>> + Send,
+        S: DataSink + ?Sized,
+    {
+        let start = Instant::now();
+        let (read_sender, read_receiver) = mpsc::sync_channel(self.queue_capacity);
+        let (processed_sender, processed_receiver) = mpsc::sync_channel(self.queue_capacity);
+        let exhausted = AtomicBool::new(false);
+        
+        let (read, processed, written) = std::thread::scope(|scope| {
+            let exhausted = &exhausted;
+            let reader = scope.spawn(move || self.read(source, read_sender, exhausted));
+            let processor = scope.spawn(move || self.process(read_receiver, processed_sender, exhausted));
+            let written = self.write(processed_receiver, sink);
+            
+            let panicked = || ProcessingError::Other("Streaming stage panicked".to_string());
+            
+            (
+                reader.join().unwrap_or_else(|_| Err(panicked())),
+                processor.join().unwrap_or_else(|_| Err(panicked())),
+                written,
+            )
+        });
+        
+        // Only the stage that failed first reports an error; the others just stop
+        let (batches, rows_written) = written?;
+        processed?;
+        let rows_read = read?;
+        
+        Ok(StreamSummary {
+            batches,
+            rows_read,
+            rows_written,
+            duration: start.elapsed(),
+        })
+    }
+    
+    /// Read batches from the source into the processing queue, returning the rows read
+    ///
+    /// Flags the source as exhausted before the queue closes once every batch is read.
+    fn read<I>(&self, source: I, sender: SyncSender<DataSet>, exhausted: &AtomicBool) -> Result<u64, ProcessingError>
+    where
+        I: Iterator<Item = Result<DataSet, DataError>>,
+    {
+        let mut rows = 0;
+        
+        for batch in source {
+            self.cancellation.check()?;
+            let batch = batch?;
+            
+            rows += batch.len() as u64;
+            self.metrics.rows_read.fetch_add(batch.len() as u64, Ordering::Relaxed);
+            
+            if !send(&sender, batch, &self.metrics.processing) {
+                return Ok(rows);
+            }
+        }
+        
+        exhausted.store(true, Ordering::Release);
+        Ok(rows)
+    }
+    
+    /// Run the pipeline on queued batches, queueing the results for the sink
+    ///
+    /// Windows still open are closed once the source is exhausted, but not
+    /// when it failed.
+    fn process(&self, receiver: Receiver<DataSet>, sender: SyncSender<DataSet>, exhausted: &AtomicBool) -> Result<(), ProcessingError> {
+        while let Some(batch) = receive(&receiver, &self.metrics.processing) {
+            self.cancellation.check()?;
+            let mut batch = self.pipeline.execute_owned(batch)?;
+            
+            if let Some(window) = &self.window {
+                batch = window.push(&batch)?;
+                
+                // Batches closing no window have nothing to write
+                if batch.is_empty() {
+                    continue;
+                }
+            }
+            
+            if !send(&sender, batch, &self.metrics.sink) {
+                return Ok(());
+            }
+        }
+        
+        if let Some(window) = &self.window {
+            if exhausted.load(Ordering::Acquire) {
+                let batch = window.flush()?;
+                
+                if !batch.is_empty() {
+                    send(&sender, batch, &self.metrics.sink);
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Write processed batches to the sink, returning the batches and rows written
+    fn write<S: DataSink + ?Sized>(&self, receiver: Receiver<DataSet>, sink: &S) -> Result<(u64, u64), ProcessingError> {
+        let (mut batches, mut rows) = (0, 0);
+        
+        while let Some(batch) = receive(&receiver, &self.metrics.sink) {
+            self.cancellation.check()?;
+            sink.write(&batch)?;
+            
+            batches += 1;
+            rows += batch.len() as u64;
+            self.metrics.rows_written.fetch_add(batch.len() as u64, Ordering::Relaxed);
+        }
+        
+        Ok((batches, rows))
+    }
+}
+
+/// Queue a batch, waiting for room if the queue is full
+///
+/// Returns false once the consuming stage has stopped.
+fn send(sender: &SyncSender<DataSet>, batch: DataSet, metrics: &QueueMetrics) -> bool {
+    let sent = match sender.try_send(batch) {
+        Ok(()) => true,
+        Err(TrySendError::Disconnected(_)) => false,
+        Err(TrySendError::Full(batch)) => {
+            let waiting = Instant::now();
+            let sent = sender.send(batch).is_ok();
+            metrics.blocked_nanos.fetch_add(waiting.elapsed().as_nanos() as u64, Ordering::Relaxed);
+            sent
+        },
+    };
+    
+    if sent {
+        let depth = metrics.depth.fetch_add(1, Ordering::Relaxed) + 1;
+        metrics.high_water.fetch_max(depth.min(metrics.capacity as i64), Ordering::Relaxed);
+        metrics.batches.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    sent
+}
+
+/// Take the next batch from a queue, waiting for one; None once the producing stage has stopped
+fn receive(receiver: &Receiver<DataSet>, metrics: &QueueMetrics) -> Option<DataSet> {
+    let batch = receiver.recv().ok()?;
+    metrics.depth.fetch_sub(1, Ordering::Relaxed);
+    Some(batch)
+}
@@ -0,0 +1,244 @@
+// Pipeline templates with parameters
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use serde_json::{Map, Value as JsonValue};
+
+use super::{PipelineSpec, ProcessingError};
+
+/// Key of the template's parameter declarations
+const PARAMS_KEY: &str = "params";
+
+/// Pipeline specification with `${name}` parameters, resolved for each run
+///
+/// ```json
+/// {
+///   "name": "daily_${region}",
+///   "params": { "region": "eu", "day": null, "rows": 1000 },
+///   "steps": [
+///     { "type": "filter", "expression": "day == '${day}' and region == '${region}'" },
+///     { "type": "limit", "limit": "${rows}" }
+///   ]
+/// }
+/// ```
+///
+/// Parameters are replaced in every string of the template. A string that
+/// is only a parameter takes the parameter's value as is, so numbers and
+/// booleans can fill numeric and flag fields; within longer strings values
+/// are inserted as text. `params` declares defaults, with `null` marking a
+/// parameter every run must give. `$${` stands for a literal `${`.
+#[derive(Debug, Clone)]
+pub struct PipelineTemplate {
+    config: JsonValue,
+    defaults: BTreeMap<String, JsonValue>,
+}
+
+impl PipelineTemplate {
+    /// Read a pipeline template from a JSON file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ProcessingError> {
+        let text = fs::read_to_string(path.as_ref()).map_err(|e| ProcessingError::InvalidArgument(
+            format!("Cannot read pipeline spec {}: {}", path.as_ref().display(), e)
+        ))?;
+        
+        let config = serde_json::from_str(&text).map_err(|e| ProcessingError::InvalidArgument(
+            format!("Invalid pipeline spec {}: {}", path.as_ref().display(), e)
+        ))?;
+        
+        Self::from_config(config)
+    }
+    
+    /// Read a pipeline template from its JSON configuration
+    ///
+    /// A specification without parameters is a template resolving to itself.
+    pub fn from_config(mut config: JsonValue) -> Result<Self, ProcessingError> {
+        let fields = config.as_object_mut().ok_or_else(|| ProcessingError::InvalidArgument(
+            "Pipeline configuration must be an object".to_string()
+        ))?;
+        
+        let defaults = match fields.remove(PARAMS_KEY) {
+            Some(JsonValue::Object(params)) => params.into_iter().collect(),
+            Some(JsonValue::Null) | None => BTreeMap::new(),
+            Some(_) => return Err(ProcessingError::InvalidArgument(
+                "Pipeline parameters must be an object of defaults".to_string()
+            )),
+        };
+        
+        Ok(PipelineTemplate { config, defaults })
+    }
+    
+    /// Get the names of the parameters used or declared by the template
+    pub fn parameters(&self) -> BTreeSet<String> {
+        let mut names: BTreeSet<String> = self.defaults.keys().cloned().collect();
+        collect_parameters(&self.config, &mut names);
+        names
+    }
+    
+    /// Get the parameters every run must give, having no default
+    pub fn required_parameters(&self) -> BTreeSet<String> {
+        self.parameters().into_iter()
+            .filter(|name| self.defaults.get(name).map_or(true, JsonValue::is_null))
+            .collect()
+    }
+    
+    /// Resolve the template into a pipeline specification
+    ///
+    /// Fails if a parameter without default is missing, or if a parameter
+    /// is given that the template does not know, which is usually a typo.
+    pub fn resolve(&self, params: &BTreeMap<String, JsonValue>) -> Result<PipelineSpec, ProcessingError> {
+        let known = self.parameters();
+        
+        let unknown: Vec<&str> = params.keys().filter(|name| !known.contains(*name)).map(String::as_str).collect();
+        if !unknown.is_empty() {
+            return Err(ProcessingError::InvalidArgument(
+                format!("Unknown pipeline parameters: {}", unknown.join(", "))
+            ));
+        }
+        
+        let mut values = BTreeMap::new();
+        let mut missing = Vec::new();
+        
+        for name in known {
+            match params.get(&name).or_else(|| self.defaults.get(&name)) {
+                Some(value) if !value.is_null() => { values.insert(name, value.clone()); },
+                _ => missing.push(name),
+            }
+        }
+        
+        if !missing.is_empty() {
+            return Err(ProcessingError::InvalidArgument(
+                format!("Missing pipeline parameters: {}", missing.join(", "))
+            ));
+        }
+        
+        PipelineSpec::from_config(substitute(&self.config, &values))
+    }
+}
+
+/// Parse a `name=value` parameter, as given on the command line
+///
+/// Values that parse as JSON, such as numbers and booleans, keep their type;
+/// anything else is a string.
+pub fn parse_parameter(param: &str) -> Result<(String, JsonValue), ProcessingError> {
+    let (name, value) = param.split_once('=').ok_or_else(|| ProcessingError::InvalidArgument(
+        format!("Invalid parameter '{}': expected name=value", param)
+    ))?;
+    
+    let value = serde_json::from_str(value).unwrap_or_else(|_| JsonValue::String(value.to_string()));
+    Ok((name.trim().to_string(), value))
+}
+
+/// Part of a template string
+enum Piece<'a> {
+    Text(&'a str),
+    Parameter(&'a str),
+}
+
+/// Split a template string into text and parameters
+fn pieces(text: &str) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+    
+    while let Some(start) = rest.find('$') {
+        let after = &rest[start + 1..];
+        
+        if after.starts_with("${") {
+            // Escaped: drop one `$` and keep the rest as text
+            pieces.push(Piece::Text(&rest[..start]));
+            pieces.push(Piece::Text("${"));
+            rest = &after[2..];
+            continue;
+        }
+        
+        let name = after.strip_prefix('{')
+            .and_then(|inner| inner.find('}').map(|end| &inner[..end]))
+            .filter(|name| is_parameter_name(name));
+        
+        match name {
+            Some(name) => {
+                pieces.push(Piece::Text(&rest[..start]));
+                pieces.push(Piece::Parameter(name));
+                rest = &after[name.len() + 2..];
+            },
+            None => {
+                pieces.push(Piece::Text(&rest[..start + 1]));
+                rest = after;
+            },
+        }
+    }
+    
+    pieces.push(Piece::Text(rest));
+    pieces.retain(|piece| !matches!(piece, Piece::Text("")));
+    pieces
+}
+
+/// Check if a name can be a parameter: a letter or `_`, then letters, digits or `_`
+fn is_parameter_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Add the parameters used in a configuration to a set
+fn collect_parameters(config: &JsonValue, names: &mut BTreeSet<String>) {
+    match config {
+        JsonValue::String(text) => {
+            for piece in pieces(text) {
+                if let Piece::Parameter(name) = piece {
+                    names.insert(name.to_string());
+                }
+            }
+        },
+        JsonValue::Array(items) => items.iter().for_each(|item| collect_parameters(item, names)),
+        JsonValue::Object(fields) => {
+            for (key, value) in fields {
+                collect_parameters(&JsonValue::String(key.clone()), names);
+                collect_parameters(value, names);
+            }
+        },
+        _ => {},
+    }
+}
+
+/// Replace the parameters of a configuration with their values
+fn substitute(config: &JsonValue, values: &BTreeMap<String, JsonValue>) -> JsonValue {
+    match config {
+        JsonValue::String(text) => {
+            let pieces = pieces(text);
+            
+            // A lone parameter keeps the type of its value
+            if let [Piece::Parameter(name)] = pieces.as_slice() {
+                return values[*name].clone();
+            }
+            
+            JsonValue::String(substitute_text(&pieces, values))
+        },
+        JsonValue::Array(items) => JsonValue::Array(items.iter().map(|item| substitute(item, values)).collect()),
+        JsonValue::Object(fields) => {
+            let mut substituted = Map::new();
+            
+            for (key, value) in fields {
+                substituted.insert(substitute_text(&pieces(key), values), substitute(value, values));
+            }
+            
+            JsonValue::Object(substituted)
+        },
+        other => other.clone(),
+    }
+}
+
+/// Join the pieces of a template string, inserting parameter values as text
+fn substitute_text(pieces: &[Piece], values: &BTreeMap<String, JsonValue>) -> String {
+    pieces.iter()
+        .map(|piece| match piece {
+            Piece::Text(text) => text.to_string(),
+            Piece::Parameter(name) => match &values[*name] {
+                JsonValue::String(value) => value.clone(),
+                value => value.to_string(),
+            },
+        })
+        .collect()
+}
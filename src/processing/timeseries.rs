@@ -0,0 +1,703 @@
+// Time-series operations for data processing
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::data::{DataSet, DataType, Field, Row, Schema, Value};
+use super::{CancellationToken, DataProcessor, ProcessingError, ProcessorType, StepSpec, WarningKind, emit_warning};
+
+/// Aggregation applied to the values falling in one resampling interval
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResampleAggregation {
+    Mean,
+    Sum,
+    Min,
+    Max,
+    Count,
+    First,
+    Last,
+}
+
+impl ResampleAggregation {
+    /// Parse a resampling aggregation from a string
+    pub fn from_str(s: &str) -> Result<Self, ProcessingError> {
+        match s.to_lowercase().as_str() {
+            "mean" | "avg" => Ok(ResampleAggregation::Mean),
+            "sum" => Ok(ResampleAggregation::Sum),
+            "min" => Ok(ResampleAggregation::Min),
+            "max" => Ok(ResampleAggregation::Max),
+            "count" => Ok(ResampleAggregation::Count),
+            "first" => Ok(ResampleAggregation::First),
+            "last" => Ok(ResampleAggregation::Last),
+            _ => Err(ProcessingError::InvalidArgument(
+                format!("Unknown resample aggregation: {}", s)
+            )),
+        }
+    }
+    
+    /// Aggregate the values of one interval
+    fn apply(&self, values: &[f64]) -> Option<f64> {
+        if values.is_empty() {
+            return match self {
+                ResampleAggregation::Count => Some(0.0),
+                _ => None,
+            };
+        }
+        
+        Some(match self {
+            ResampleAggregation::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            ResampleAggregation::Sum => values.iter().sum(),
+            ResampleAggregation::Min => values.iter().cloned().fold(f64::INFINITY, f64::min),
+            ResampleAggregation::Max => values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            ResampleAggregation::Count => values.len() as f64,
+            ResampleAggregation::First => values[0],
+            ResampleAggregation::Last => values[values.len() - 1],
+        })
+    }
+}
+
+/// How to fill intervals that contain no rows
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FillMethod {
+    /// Leave empty intervals as null
+    Null,
+    /// Repeat the last known value
+    ForwardFill,
+    /// Interpolate linearly between the surrounding known values
+    Linear,
+}
+
+impl Default for FillMethod {
+    fn default() -> Self {
+        FillMethod::Null
+    }
+}
+
+impl FillMethod {
+    /// Parse a fill method from a string
+    pub fn from_str(s: &str) -> Result<Self, ProcessingError> {
+        match s.to_lowercase().as_str() {
+            "null" | "none" => Ok(FillMethod::Null),
+            "ffill" | "forward_fill" => Ok(FillMethod::ForwardFill),
+            "linear" | "interpolate" => Ok(FillMethod::Linear),
+            _ => Err(ProcessingError::InvalidArgument(
+                format!("Unknown fill method: {}", s)
+            )),
+        }
+    }
+}
+
+/// Default most intervals a resampled grid may have
+pub const DEFAULT_MAX_BUCKETS: usize = 1_000_000;
+
+/// Resample rows onto a regular time grid
+///
+/// Rows are bucketed by a time column (timestamps, dates, Unix seconds or
+/// ISO 8601 strings) into fixed intervals. Intervals with several rows are aggregated
+/// (downsampling); intervals without rows are filled (upsampling). Grids of
+/// more than `max_buckets` intervals, such as a few far-off times at a fine
+/// interval, are refused rather than filled.
+pub struct ResampleProcessor {
+    time_column: String,
+    interval: i64,
+    max_buckets: usize,
+    aggregations: Vec<(String, ResampleAggregation)>,
+    fill: FillMethod,
+    cancellation: CancellationToken,
+}
+
+impl ResampleProcessor {
+    /// Create a new resample processor with an interval in seconds
+    pub fn new(time_column: &str, interval: i64) -> Self {
+        ResampleProcessor {
+            time_column: time_column.to_string(),
+            interval,
+            max_buckets: DEFAULT_MAX_BUCKETS,
+            aggregations: Vec::new(),
+            fill: FillMethod::Null,
+            cancellation: CancellationToken::new(),
+        }
+    }
+    
+    /// Parse a frequency such as "30s", "5m", "1h", "1d" or "1w" into seconds
+    pub fn parse_frequency(frequency: &str) -> Result<i64, ProcessingError> {
+        let frequency = frequency.trim();
+        let split = frequency.find(|c: char| !c.is_ascii_digit()).unwrap_or(frequency.len());
+        let (count, unit) = frequency.split_at(split);
+        
+        let count: i64 = if count.is_empty() {
+            1
+        } else {
+            count.parse().map_err(|_| ProcessingError::InvalidArgument(
+                format!("Invalid frequency: {}", frequency)
+            ))?
+        };
+        
+        let unit = match unit {
+            "s" | "" => 1,
+            "m" | "min" => 60,
+            "h" => 3_600,
+            "d" => 86_400,
+            "w" => 604_800,
+            _ => return Err(ProcessingError::InvalidArgument(
+                format!("Invalid frequency unit: {}", unit)
+            )),
+        };
+        
+        count.checked_mul(unit).ok_or_else(|| ProcessingError::InvalidArgument(
+            format!("Frequency is too large: {}", frequency)
+        ))
+    }
+    
+    /// Aggregate a column within each interval
+    pub fn aggregate(mut self, column: &str, aggregation: ResampleAggregation) -> Self {
+        self.aggregations.push((column.to_string(), aggregation));
+        self
+    }
+    
+    /// Set how intervals without rows are filled
+    pub fn with_fill(mut self, fill: FillMethod) -> Self {
+        self.fill = fill;
+        self
+    }
+    
+    /// Set the most intervals the grid may have
+    pub fn with_max_buckets(mut self, max_buckets: usize) -> Self {
+        self.max_buckets = max_buckets;
+        self
+    }
+    
+    /// Stop resampling once the token is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+    
+    /// Format Unix seconds in the representation of the time column
+    fn format_time(seconds: i64, data_type: &DataType) -> Value {
+        match data_type {
+            DataType::Timestamp => DateTime::from_timestamp(seconds, 0).map_or(Value::Null, Value::Timestamp),
+            DataType::Date => DateTime::from_timestamp(seconds, 0).map_or(Value::Null, |datetime| Value::Date(datetime.date_naive())),
+            DataType::String => match NaiveDateTime::from_timestamp_opt(seconds, 0) {
+                Some(datetime) => Value::String(datetime.format("%Y-%m-%dT%H:%M:%S").to_string()),
+                None => Value::Null,
+            },
+            _ => Value::Integer(seconds),
+        }
+    }
+    
+    /// Fill gaps in a series of interval values
+    fn fill_gaps(&self, series: &mut [Option<f64>]) {
+        match self.fill {
+            FillMethod::Null => {},
+            FillMethod::ForwardFill => {
+                let mut last = None;
+                for value in series.iter_mut() {
+                    match value {
+                        Some(v) => last = Some(*v),
+                        None => *value = last,
+                    }
+                }
+            },
+            FillMethod::Linear => {
+                let known: Vec<usize> = (0..series.len())
+                    .filter(|&i| series[i].is_some())
+                    .collect();
+                
+                for pair in known.windows(2) {
+                    let (start, end) = (pair[0], pair[1]);
+                    let (from, to) = (series[start].unwrap(), series[end].unwrap());
+                    
+                    for i in start + 1..end {
+                        let t = (i - start) as f64 / (end - start) as f64;
+                        series[i] = Some(from + t * (to - from));
+                    }
+                }
+            },
+        }
+    }
+}
+
+impl DataProcessor for ResampleProcessor {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        if self.interval <= 0 {
+            return Err(ProcessingError::InvalidArgument(
+                "Resample interval must be greater than zero".to_string()
+            ));
+        }
+        
+        // Find column indices
+        let find = |column: &str| input.schema.fields.iter()
+            .position(|field| field.name == column)
+            .ok_or_else(|| ProcessingError::InvalidArgument(
+                format!("Column '{}' not found", column)
+            ));
+        
+        let time_idx = find(&self.time_column)?;
+        
+        // Default to the mean of every numeric column
+        let aggregations: Vec<(String, ResampleAggregation)> = if self.aggregations.is_empty() {
+            input.schema.fields.iter()
+                .enumerate()
+                .filter(|(i, field)| *i != time_idx
+                    && field.data_type.is_numeric())
+                .map(|(_, field)| (field.name.clone(), ResampleAggregation::Mean))
+                .collect()
+        } else {
+            self.aggregations.clone()
+        };
+        
+        let columns = aggregations.iter()
+            .map(|(column, _)| find(column))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        // Bucket rows by interval
+        let mut buckets: BTreeMap<i64, Vec<&Row>> = BTreeMap::new();
+        
+        let mut untimed = 0;
+        for (n, row) in input.data.iter().enumerate() {
+            self.cancellation.checkpoint(n)?;
+            
+            let time = match &row.values[time_idx] {
+                Value::Null => {
+                    untimed += 1;
+                    continue;
+                },
+                value => parse_event_time(value).ok_or_else(|| ProcessingError::InvalidOperation(
+                    format!("Cannot parse {:?} as a timestamp", value)
+                ))?,
+            };
+            
+            buckets.entry(time.div_euclid(self.interval)).or_insert_with(Vec::new).push(row);
+        }
+        
+        emit_warning(self.name(), WarningKind::RowsSkipped, Some(&self.time_column), untimed, "Rows without a time skipped");
+        
+        // Create schema with the time column and one column per aggregation
+        let time_type = input.schema.fields[time_idx].data_type.clone();
+        let mut fields = vec![Field::new(self.time_column.clone(), time_type.clone(), false)];
+        
+        for (column, aggregation) in &aggregations {
+            let data_type = match aggregation {
+                ResampleAggregation::Count => DataType::Integer,
+                _ => DataType::Float,
+            };
+            fields.push(Field::new(column.clone(), data_type, true));
+        }
+        
+        let mut result = DataSet::new(Schema::new(fields));
+        
+        let (first, last) = match (buckets.keys().next(), buckets.keys().next_back()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Ok(result),
+        };
+        
+        let grid = (last as i128 - first as i128 + 1) as u128;
+        if grid > self.max_buckets as u128 {
+            return Err(ProcessingError::InvalidArgument(format!(
+                "Resampling would produce {} intervals, more than the limit of {}; use a coarser frequency",
+                grid, self.max_buckets
+            )));
+        }
+        
+        // Aggregate each column over the full grid, then fill the gaps
+        let series: Vec<Vec<Option<f64>>> = aggregations.iter()
+            .zip(&columns)
+            .map(|((_, aggregation), &col_idx)| {
+                let mut series: Vec<Option<f64>> = (first..=last)
+                    .map(|bucket| {
+                        self.cancellation.checkpoint((bucket - first) as usize)?;
+                        
+                        let values: Vec<f64> = buckets.get(&bucket)
+                            .map(|rows| rows.iter()
+                                .filter_map(|row| match &row.values[col_idx] {
+                                    Value::Integer(i) => Some(*i as f64),
+                                    Value::Float(f) if !f.is_nan() => Some(*f),
+                                    _ => None,
+                                })
+                                .collect())
+                            .unwrap_or_default();
+                        Ok(aggregation.apply(&values))
+                    })
+                    .collect::<Result<_, ProcessingError>>()?;
+                
+                self.fill_gaps(&mut series);
+                Ok(series)
+            })
+            .collect::<Result<_, ProcessingError>>()?;
+        
+        for (i, bucket) in (first..=last).enumerate() {
+            let start = bucket.checked_mul(self.interval);
+            let mut values = vec![start.map_or(Value::Null, |start| Self::format_time(start, &time_type))];
+            
+            for ((_, aggregation), column) in aggregations.iter().zip(&series) {
+                values.push(match (column[i], aggregation) {
+                    (None, _) => Value::Null,
+                    (Some(v), ResampleAggregation::Count) => Value::Integer(v as i64),
+                    (Some(v), _) => Value::Float(v),
+                });
+            }
+            
+            result.add_row(Row::new(values))?;
+        }
+        
+        // Copy metadata
+        for (key, value) in &input.metadata.properties {
+            result.metadata.add(key.clone(), value.clone());
+        }
+        
+        Ok(result)
+    }
+    
+    fn name(&self) -> &str {
+        "resample"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Aggregate
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Resample {
+            time_column: self.time_column.clone(),
+            interval: self.interval,
+            aggregations: self.aggregations.clone(),
+            fill: self.fill,
+            max_buckets: Some(self.max_buckets).filter(|&max_buckets| max_buckets != DEFAULT_MAX_BUCKETS),
+        }.to_config())
+    }
+}
+
+/// Parse a time value into Unix seconds
+///
+/// Times are timestamps, dates, Unix seconds or ISO 8601 strings.
+pub(crate) fn parse_event_time(value: &Value) -> Option<i64> {
+    match value {
+        Value::Timestamp(timestamp) => Some(timestamp.timestamp()),
+        Value::Date(date) => date.and_hms_opt(0, 0, 0).map(|datetime| datetime.and_utc().timestamp()),
+        Value::Integer(i) => Some(*i),
+        Value::Float(f) => Some(*f as i64),
+        Value::String(s) => {
+            if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+                return Some(datetime.timestamp());
+            }
+            
+            ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"].iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+                .or_else(|| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+                    .and_then(|date| date.and_hms_opt(0, 0, 0)))
+                .map(|datetime| datetime.and_utc().timestamp())
+        },
+        _ => None,
+    }
+}
+
+/// Group row indices by partition key and sort each partition by the order column
+fn ordered_partitions(
+    input: &DataSet,
+    partition_by: &[String],
+    order_by: Option<&str>,
+) -> Result<Vec<Vec<usize>>, ProcessingError> {
+    // Find column indices
+    let find = |column: &str| input.schema.fields.iter()
+        .position(|field| field.name == column)
+        .ok_or_else(|| ProcessingError::InvalidArgument(
+            format!("Column '{}' not found", column)
+        ));
+    
+    let partition_indices = partition_by.iter()
+        .map(|column| find(column))
+        .collect::<Result<Vec<_>, _>>()?;
+    
+    let order_idx = match order_by {
+        Some(column) => Some(find(column)?),
+        None => None,
+    };
+    
+    // Keep partitions in order of first appearance
+    let mut positions: HashMap<Vec<&Value>, usize> = HashMap::new();
+    let mut partitions: Vec<Vec<usize>> = Vec::new();
+    
+    for (i, row) in input.data.iter().enumerate() {
+        let key: Vec<&Value> = partition_indices.iter()
+            .map(|&idx| &row.values[idx])
+            .collect();
+        
+        match positions.get(&key) {
+            Some(&p) => partitions[p].push(i),
+            None => {
+                positions.insert(key, partitions.len());
+                partitions.push(vec![i]);
+            },
+        }
+    }
+    
+    if let Some(order_idx) = order_idx {
+        for partition in &mut partitions {
+            // Stable, so rows with equal times keep their order; nulls sort first
+            partition.sort_by(|&a, &b| input.data[a].values[order_idx].cmp(&input.data[b].values[order_idx]));
+        }
+    }
+    
+    Ok(partitions)
+}
+
+/// Append a smoothed column computed over each ordered partition
+fn append_smoothed<F>(
+    input: &DataSet,
+    column: &str,
+    output: &str,
+    partition_by: &[String],
+    order_by: Option<&str>,
+    cancellation: &CancellationToken,
+    smooth: F,
+) -> Result<DataSet, ProcessingError>
+where
+    F: Fn(&[Option<f64>]) -> Vec<Option<f64>>,
+{
+    // Find column index
+    let col_idx = input.schema.fields.iter()
+        .position(|field| field.name == column)
+        .ok_or_else(|| ProcessingError::InvalidArgument(
+            format!("Column '{}' not found", column)
+        ))?;
+    
+    if input.schema.get_field_by_name(output).is_some() {
+        return Err(ProcessingError::InvalidArgument(
+            format!("Column '{}' already exists", output)
+        ));
+    }
+    
+    // Smooth each partition and map results back to the original row positions
+    let mut smoothed = vec![None; input.data.len()];
+    
+    for (n, partition) in ordered_partitions(input, partition_by, order_by)?.into_iter().enumerate() {
+        cancellation.checkpoint(n)?;
+        
+        let series: Vec<Option<f64>> = partition.iter()
+            .map(|&i| match &input.data[i].values[col_idx] {
+                Value::Integer(v) => Some(*v as f64),
+                Value::Float(v) if !v.is_nan() => Some(*v),
+                _ => None,
+            })
+            .collect();
+        
+        for (&i, value) in partition.iter().zip(smooth(&series)) {
+            smoothed[i] = value;
+        }
+    }
+    
+    // Create new schema with added column
+    let mut fields = input.schema.fields.clone();
+    fields.push(Field::new(output.to_string(), DataType::Float, true));
+    
+    let mut result = DataSet::new(Schema::new(fields));
+    
+    for (row, value) in input.data.iter().zip(smoothed) {
+        let mut values = row.values.clone();
+        values.push(value.map(Value::Float).unwrap_or(Value::Null));
+        
+        result.add_row(Row::new(values))?;
+    }
+    
+    // Copy metadata
+    for (key, value) in &input.metadata.properties {
+        result.metadata.add(key.clone(), value.clone());
+    }
+    
+    Ok(result)
+}
+
+/// Append a simple moving average over a trailing window of rows
+pub struct MovingAverageTransform {
+    column: String,
+    window: usize,
+    output: String,
+    min_periods: usize,
+    partition_by: Vec<String>,
+    order_by: Option<String>,
+    cancellation: CancellationToken,
+}
+
+impl MovingAverageTransform {
+    /// Create a new moving average transform
+    pub fn new(column: &str, window: usize, output: &str) -> Self {
+        MovingAverageTransform {
+            column: column.to_string(),
+            window,
+            output: output.to_string(),
+            min_periods: window,
+            partition_by: Vec::new(),
+            order_by: None,
+            cancellation: CancellationToken::new(),
+        }
+    }
+    
+    /// Set the minimum number of non-null values needed to produce an average
+    pub fn with_min_periods(mut self, min_periods: usize) -> Self {
+        self.min_periods = min_periods;
+        self
+    }
+    
+    /// Compute the average separately for each partition
+    pub fn partition_by(mut self, columns: Vec<String>) -> Self {
+        self.partition_by = columns;
+        self
+    }
+    
+    /// Order rows by a column before smoothing
+    pub fn order_by(mut self, column: &str) -> Self {
+        self.order_by = Some(column.to_string());
+        self
+    }
+    
+    /// Stop smoothing once the token is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+}
+
+impl DataProcessor for MovingAverageTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        if self.window == 0 {
+            return Err(ProcessingError::InvalidArgument(
+                "Moving average window must be greater than zero".to_string()
+            ));
+        }
+        
+        let min_periods = self.min_periods.max(1);
+        
+        append_smoothed(input, &self.column, &self.output, &self.partition_by, self.order_by.as_deref(), &self.cancellation, |series| {
+            (0..series.len())
+                .map(|i| {
+                    let start = (i + 1).saturating_sub(self.window);
+                    let values: Vec<f64> = series[start..=i].iter().flatten().cloned().collect();
+                    
+                    if values.len() < min_periods {
+                        None
+                    } else {
+                        Some(values.iter().sum::<f64>() / values.len() as f64)
+                    }
+                })
+                .collect()
+        })
+    }
+    
+    fn name(&self) -> &str {
+        "moving_average"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Window
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::MovingAverage {
+            column: self.column.clone(),
+            window: self.window,
+            output: self.output.clone(),
+            min_periods: Some(self.min_periods),
+            partition_by: self.partition_by.clone(),
+            order_by: self.order_by.clone(),
+        }.to_config())
+    }
+}
+
+/// Append an exponentially weighted moving average
+pub struct EwmaTransform {
+    column: String,
+    alpha: f64,
+    output: String,
+    partition_by: Vec<String>,
+    order_by: Option<String>,
+    cancellation: CancellationToken,
+}
+
+impl EwmaTransform {
+    /// Create a new EWMA transform with a span, giving `alpha = 2 / (span + 1)`
+    pub fn new(column: &str, span: f64, output: &str) -> Self {
+        Self::with_alpha(column, 2.0 / (span + 1.0), output)
+    }
+    
+    /// Create a new EWMA transform with an explicit smoothing factor
+    pub fn with_alpha(column: &str, alpha: f64, output: &str) -> Self {
+        EwmaTransform {
+            column: column.to_string(),
+            alpha,
+            output: output.to_string(),
+            partition_by: Vec::new(),
+            order_by: None,
+            cancellation: CancellationToken::new(),
+        }
+    }
+    
+    /// Compute the average separately for each partition
+    pub fn partition_by(mut self, columns: Vec<String>) -> Self {
+        self.partition_by = columns;
+        self
+    }
+    
+    /// Order rows by a column before smoothing
+    pub fn order_by(mut self, column: &str) -> Self {
+        self.order_by = Some(column.to_string());
+        self
+    }
+    
+    /// Stop smoothing once the token is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+}
+
+impl DataProcessor for EwmaTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        if !(self.alpha > 0.0 && self.alpha <= 1.0) {
+            return Err(ProcessingError::InvalidArgument(
+                format!("EWMA alpha must be in (0, 1], got {}", self.alpha)
+            ));
+        }
+        
+        append_smoothed(input, &self.column, &self.output, &self.partition_by, self.order_by.as_deref(), &self.cancellation, |series| {
+            // Null values carry the previous average forward
+            let mut average: Option<f64> = None;
+            
+            series.iter()
+                .map(|value| {
+                    if let Some(v) = value {
+                        average = Some(match average {
+                            Some(prev) => self.alpha * v + (1.0 - self.alpha) * prev,
+                            None => *v,
+                        });
+                    }
+                    average
+                })
+                .collect()
+        })
+    }
+    
+    fn name(&self) -> &str {
+        "ewma"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Window
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Ewma {
+            column: self.column.clone(),
+            alpha: self.alpha,
+            output: self.output.clone(),
+            partition_by: self.partition_by.clone(),
+            order_by: self.order_by.clone(),
+        }.to_config())
+    }
+}
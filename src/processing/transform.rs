@@ -2,9 +2,18 @@
 // Author: Gabriel Demetrios Lafis
 
 use std::collections::HashSet;
+use std::fmt;
 
-use crate::data::{DataSet, DataType, Field, Row, Schema, Value};
-use super::{DataProcessor, ProcessingError, ProcessorType};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::data::{
+    DataSet, DataType, Decimal, ErrorContext, Field, JsonSink, LineageStep, Metadata, Row, Schema, Value, fit_decimal,
+    format_date, format_time, format_timestamp, format_uuid, generate_uuid_with, parse_date, parse_time,
+    parse_timestamp, parse_uuid,
+};
+use super::{CancellationToken, CastSpec, DataProcessor, InPlaceDataProcessor, OverflowPolicy, ProcessingError, ProcessorType, StepSpec, WarningKind, emit_warning, seeded_rng};
 
 /// Select specific columns from a dataset
 pub struct SelectTransform {
@@ -72,6 +81,10 @@ impl DataProcessor for SelectTransform {
     fn processor_type(&self) -> ProcessorType {
         ProcessorType::Transform
     }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Select { columns: self.columns.clone() }.to_config())
+    }
 }
 
 /// Rename columns in a dataset
@@ -86,10 +99,10 @@ impl RenameTransform {
     }
 }
 
-impl DataProcessor for RenameTransform {
-    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
-        // Create new schema with renamed columns
-        let mut fields = input.schema.fields.clone();
+impl RenameTransform {
+    /// Apply the renames to a list of fields
+    fn rename_fields(&self, fields: &[Field]) -> Result<Vec<Field>, ProcessingError> {
+        let mut fields = fields.to_vec();
         
         for (old_name, new_name) in &self.renames {
             let mut found = false;
@@ -119,6 +132,22 @@ impl DataProcessor for RenameTransform {
             }
         }
         
+        Ok(fields)
+    }
+    
+    /// Carry the lineage of renamed columns over to their new names
+    fn rename_lineage(&self, metadata: &mut Metadata) {
+        for (old_name, new_name) in &self.renames {
+            metadata.rename_lineage(old_name, new_name);
+        }
+    }
+}
+
+impl DataProcessor for RenameTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        // Create new schema with renamed columns
+        let fields = self.rename_fields(&input.schema.fields)?;
+        
         // Create new dataset with renamed schema
         let schema = Schema::new(fields);
         let mut result = DataSet::new(schema);
@@ -133,6 +162,8 @@ impl DataProcessor for RenameTransform {
             result.metadata.add(key.clone(), value.clone());
         }
         
+        self.rename_lineage(&mut result.metadata);
+        
         Ok(result)
     }
     
@@ -143,6 +174,31 @@ impl DataProcessor for RenameTransform {
     fn processor_type(&self) -> ProcessorType {
         ProcessorType::Transform
     }
+    
+    fn as_in_place(&self) -> Option<&dyn InPlaceDataProcessor> {
+        Some(self)
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Rename { renames: self.renames.clone() }.to_config())
+    }
+}
+
+impl InPlaceDataProcessor for RenameTransform {
+    fn process_in_place(&self, input: &mut DataSet) -> Result<(), ProcessingError> {
+        // Only the schema changes, rows are left untouched
+        input.schema = Schema::new(self.rename_fields(&input.schema.fields)?);
+        self.rename_lineage(&mut input.metadata);
+        Ok(())
+    }
+    
+    fn name(&self) -> &str {
+        "rename"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
 }
 
 /// Add a new column to a dataset
@@ -150,26 +206,35 @@ pub struct AddColumnTransform {
     name: String,
     data_type: DataType,
     nullable: bool,
-    generator: Box<dyn Fn(&Row, &DataSet) -> Value>,
+    generator: Box<dyn Fn(&Row, &DataSet) -> Value + Send + Sync>,
+    expression: Option<String>,
 }
 
 impl AddColumnTransform {
     /// Create a new add column transform with a generator function
     pub fn new<F>(name: &str, data_type: DataType, nullable: bool, generator: F) -> Self
     where
-        F: Fn(&Row, &DataSet) -> Value + 'static,
+        F: Fn(&Row, &DataSet) -> Value + Send + Sync + 'static,
     {
         AddColumnTransform {
             name: name.to_string(),
             data_type,
             nullable,
             generator: Box::new(generator),
+            expression: None,
         }
     }
     
     /// Create a new add column transform with a constant value
     pub fn with_constant(name: &str, data_type: DataType, nullable: bool, value: Value) -> Self {
-        Self::new(name, data_type, nullable, move |_, _| value.clone())
+        let expression = JsonSink::value_to_json(&value).to_string();
+        Self::new(name, data_type, nullable, move |_, _| value.clone()).with_expression(&expression)
+    }
+    
+    /// Describe how the column is computed, for its lineage
+    pub fn with_expression(mut self, expression: &str) -> Self {
+        self.expression = Some(expression.to_string());
+        self
     }
 }
 
@@ -205,6 +270,8 @@ impl DataProcessor for AddColumnTransform {
             result.metadata.add(key.clone(), value.clone());
         }
         
+        result.metadata.add_lineage(&self.name, LineageStep::Derived { expression: self.expression.clone() });
+        
         Ok(result)
     }
     
@@ -217,10 +284,95 @@ impl DataProcessor for AddColumnTransform {
     }
 }
 
+/// Fill a UUID column with random (version 4) UUIDs
+///
+/// The column is added if missing; an existing column keeps its values and
+/// only nulls are filled. With a seed, or a global seed, the same input gets
+/// the same UUIDs.
+pub struct GenerateUuidTransform {
+    column: String,
+    seed: Option<u64>,
+}
+
+impl GenerateUuidTransform {
+    /// Create a new UUID generating transform
+    pub fn new(column: &str) -> Self {
+        GenerateUuidTransform {
+            column: column.to_string(),
+            seed: None,
+        }
+    }
+    
+    /// Generate UUIDs from a seed, for reproducible output
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+impl DataProcessor for GenerateUuidTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        let existing = input.schema.fields.iter().position(|f| f.name == self.column);
+        
+        if let Some(index) = existing {
+            if input.schema.fields[index].data_type != DataType::Uuid {
+                return Err(ProcessingError::InvalidArgument(
+                    format!("Column '{}' is not a UUID column", self.column)
+                ));
+            }
+        }
+        
+        // Create new schema with the UUID column
+        let mut fields = input.schema.fields.clone();
+        if existing.is_none() {
+            fields.push(Field::new(self.column.clone(), DataType::Uuid, false));
+        }
+        
+        let schema = Schema::new(fields);
+        let mut result = DataSet::new(schema);
+        let mut rng = seeded_rng(self.seed);
+        
+        for row in &input.data {
+            let mut values = row.values.clone();
+            
+            match existing {
+                Some(index) if matches!(values[index], Value::Null) => {
+                    values[index] = Value::Uuid(generate_uuid_with(&mut rng));
+                },
+                Some(_) => {},
+                None => values.push(Value::Uuid(generate_uuid_with(&mut rng))),
+            }
+            
+            result.add_row(Row::new(values))?;
+        }
+        
+        // Copy metadata
+        for (key, value) in &input.metadata.properties {
+            result.metadata.add(key.clone(), value.clone());
+        }
+        
+        Ok(result)
+    }
+    
+    fn name(&self) -> &str {
+        "generate_uuid"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::GenerateUuid { column: self.column.clone(), seed: self.seed }.to_config())
+    }
+}
+
 /// Cast a column to a different data type
 pub struct CastTransform {
     column: String,
     target_type: DataType,
+    format: CastFormat,
+    cancellation: CancellationToken,
 }
 
 impl CastTransform {
@@ -229,85 +381,44 @@ impl CastTransform {
         CastTransform {
             column: column.to_string(),
             target_type,
+            format: CastFormat::default(),
+            cancellation: CancellationToken::new(),
         }
     }
     
-    /// Cast a value to the target type
-    fn cast_value(&self, value: &Value) -> Result<Value, ProcessingError> {
-        match (value, &self.target_type) {
-            // Null remains null for any type
-            (Value::Null, _) => Ok(Value::Null),
-            
-            // Boolean casts
-            (Value::Boolean(b), DataType::Boolean) => Ok(Value::Boolean(*b)),
-            (Value::Boolean(b), DataType::Integer) => Ok(Value::Integer(if *b { 1 } else { 0 })),
-            (Value::Boolean(b), DataType::Float) => Ok(Value::Float(if *b { 1.0 } else { 0.0 })),
-            (Value::Boolean(b), DataType::String) => Ok(Value::String(b.to_string())),
-            
-            // Integer casts
-            (Value::Integer(i), DataType::Boolean) => Ok(Value::Boolean(*i != 0)),
-            (Value::Integer(i), DataType::Integer) => Ok(Value::Integer(*i)),
-            (Value::Integer(i), DataType::Float) => Ok(Value::Float(*i as f64)),
-            (Value::Integer(i), DataType::String) => Ok(Value::String(i.to_string())),
-            
-            // Float casts
-            (Value::Float(f), DataType::Boolean) => Ok(Value::Boolean(*f != 0.0)),
-            (Value::Float(f), DataType::Integer) => Ok(Value::Integer(*f as i64)),
-            (Value::Float(f), DataType::Float) => Ok(Value::Float(*f)),
-            (Value::Float(f), DataType::String) => Ok(Value::String(f.to_string())),
-            
-            // String casts
-            (Value::String(s), DataType::Boolean) => {
-                let lower = s.to_lowercase();
-                if lower == "true" || lower == "yes" || lower == "1" {
-                    Ok(Value::Boolean(true))
-                } else if lower == "false" || lower == "no" || lower == "0" {
-                    Ok(Value::Boolean(false))
-                } else {
-                    Err(ProcessingError::InvalidOperation(
-                        format!("Cannot cast '{}' to boolean", s)
-                    ))
-                }
-            },
-            (Value::String(s), DataType::Integer) => {
-                s.parse::<i64>()
-                    .map(Value::Integer)
-                    .map_err(|_| ProcessingError::InvalidOperation(
-                        format!("Cannot cast '{}' to integer", s)
-                    ))
-            },
-            (Value::String(s), DataType::Float) => {
-                s.parse::<f64>()
-                    .map(Value::Float)
-                    .map_err(|_| ProcessingError::InvalidOperation(
-                        format!("Cannot cast '{}' to float", s)
-                    ))
-            },
-            (Value::String(s), DataType::String) => Ok(Value::String(s.clone())),
-            
-            // Other casts not supported
-            _ => Err(ProcessingError::NotSupported(
-                format!("Cast from {:?} to {:?} not supported", value, self.target_type)
-            )),
-        }
+    /// Set format hints for parsing string values
+    pub fn with_format(mut self, format: CastFormat) -> Self {
+        self.format = format;
+        self
+    }
+    
+    /// Stop casting once the token is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+    
+    /// Cast the value of a row to the target type
+    fn cast_value(&self, value: &Value, row: usize) -> Result<Value, ProcessingError> {
+        self.cancellation.checkpoint(row)?;
+        cast_value(value, &self.target_type, &self.format)
+            .map_err(|err| err.with_context(ErrorContext::new().column(&self.column).row(row).value(value)))
+    }
+    
+    /// Find the index of the column to cast
+    fn find_column(&self, schema: &Schema) -> Result<usize, ProcessingError> {
+        schema.fields.iter()
+            .position(|field| field.name == self.column)
+            .ok_or_else(|| ProcessingError::InvalidArgument(
+                format!("Column '{}' not found", self.column)
+            ))
     }
 }
 
 impl DataProcessor for CastTransform {
     fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
         // Find column index
-        let mut col_idx = None;
-        
-        for (i, field) in input.schema.fields.iter().enumerate() {
-            if field.name == self.column {
-                col_idx = Some(i);
-                break;
-            }
-        }
-        
-        let col_idx = col_idx.ok_or_else(|| ProcessingError::InvalidArgument(
-            format!("Column '{}' not found", self.column)
-        ))?;
+        let col_idx = self.find_column(&input.schema)?;
         
         // Create new schema with updated data type
         let mut fields = input.schema.fields.clone();
@@ -317,19 +428,34 @@ impl DataProcessor for CastTransform {
         let mut result = DataSet::new(schema);
         
         // Copy data and cast the specified column
-        for row in &input.data {
+        let mut coerced = 0;
+        for (row_idx, row) in input.data.iter().enumerate() {
             let mut values = row.values.clone();
-            values[col_idx] = self.cast_value(&values[col_idx])?;
+            values[col_idx] = self.cast_value(&row.values[col_idx], row_idx)?;
+            
+            if is_coerced(&row.values[col_idx], &values[col_idx]) {
+                coerced += 1;
+            }
             
             let new_row = Row::new(values);
             result.add_row(new_row)?;
         }
         
+        emit_warning(DataProcessor::name(self), WarningKind::ValuesCoerced, Some(&self.column), coerced, COERCED_MESSAGE);
+        
+        result.schema.fields[col_idx].data_type = promoted_type(
+            &self.target_type,
+            result.data.iter().map(|row| &row.values[col_idx]),
+        );
+        
         // Copy metadata
         for (key, value) in &input.metadata.properties {
             result.metadata.add(key.clone(), value.clone());
         }
         
+        let step = LineageStep::cast(&input.schema.fields[col_idx].data_type, &result.schema.fields[col_idx].data_type);
+        result.metadata.add_lineage(&self.column, step);
+        
         Ok(result)
     }
     
@@ -340,6 +466,734 @@ impl DataProcessor for CastTransform {
     fn processor_type(&self) -> ProcessorType {
         ProcessorType::Transform
     }
+    
+    fn as_in_place(&self) -> Option<&dyn InPlaceDataProcessor> {
+        Some(self)
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Cast {
+            column: self.column.clone(),
+            data_type: self.target_type.name(),
+            format: self.format.clone(),
+        }.to_config())
+    }
+}
+
+impl InPlaceDataProcessor for CastTransform {
+    fn process_in_place(&self, input: &mut DataSet) -> Result<(), ProcessingError> {
+        let col_idx = self.find_column(&input.schema)?;
+        
+        // Cast all values before writing any back, so a failed cast leaves the dataset unchanged
+        let cast_values = input.data.iter().enumerate()
+            .map(|(row_idx, row)| self.cast_value(&row.values[col_idx], row_idx))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        let mut coerced = 0;
+        for (row, value) in input.data.iter_mut().zip(cast_values) {
+            if is_coerced(&row.values[col_idx], &value) {
+                coerced += 1;
+            }
+            
+            row.values[col_idx] = value;
+        }
+        
+        emit_warning(InPlaceDataProcessor::name(self), WarningKind::ValuesCoerced, Some(&self.column), coerced, COERCED_MESSAGE);
+        
+        let cast_type = promoted_type(
+            &self.target_type,
+            input.data.iter().map(|row| &row.values[col_idx]),
+        );
+        
+        let step = LineageStep::cast(&input.schema.fields[col_idx].data_type, &cast_type);
+        input.metadata.add_lineage(&self.column, step);
+        input.schema.fields[col_idx].data_type = cast_type;
+        
+        Ok(())
+    }
+    
+    fn name(&self) -> &str {
+        "cast"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+}
+
+/// Warning message of casts changing values to fit their target type
+const COERCED_MESSAGE: &str = "Values truncated or saturated to fit the target type";
+
+/// Check if a cast changed a number beyond converting its type, by truncating or saturating it
+fn is_coerced(original: &Value, cast: &Value) -> bool {
+    match (original, cast) {
+        (Value::Integer(a), Value::Integer(b)) => a != b,
+        (Value::Float(f), Value::Integer(i)) => *f != *i as f64,
+        // Rounding to 32 bits is expected; only saturation changes the magnitude
+        (Value::Float(a), Value::Float(b)) => a.is_finite() && a.abs() > f32::MAX as f64 && a != b,
+        _ => false,
+    }
+}
+
+/// Format hints for parsing strings in casts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CastFormat {
+    /// Thousands separator to strip from numbers, e.g. ',' in "1,234.56"
+    pub thousands_separator: Option<char>,
+    /// Decimal mark used in numbers, e.g. ',' in "1.234,56"
+    pub decimal_mark: char,
+    /// Strip currency symbols and accept accounting negatives like "(12.50)"
+    pub currency: bool,
+    /// Accept percentage strings, converted to fractions when casting to float
+    pub percent: bool,
+    /// chrono format string for parsing dates, e.g. "%d/%m/%Y"
+    pub date_format: Option<String>,
+    /// What to do with numbers that do not fit the target type
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for CastFormat {
+    fn default() -> Self {
+        CastFormat {
+            thousands_separator: None,
+            decimal_mark: '.',
+            currency: false,
+            percent: false,
+            date_format: None,
+            overflow: OverflowPolicy::Error,
+        }
+    }
+}
+
+impl CastFormat {
+    /// Create format hints with plain parsing
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Create format hints for "1.234,56" style numbers
+    pub fn european() -> Self {
+        Self::new().thousands_separator('.').decimal_mark(',')
+    }
+    
+    /// Set the thousands separator
+    pub fn thousands_separator(mut self, separator: char) -> Self {
+        self.thousands_separator = Some(separator);
+        self
+    }
+    
+    /// Set the decimal mark
+    pub fn decimal_mark(mut self, mark: char) -> Self {
+        self.decimal_mark = mark;
+        self
+    }
+    
+    /// Accept currency symbols
+    pub fn currency(mut self) -> Self {
+        self.currency = true;
+        self
+    }
+    
+    /// Accept percentage strings
+    pub fn percent(mut self) -> Self {
+        self.percent = true;
+        self
+    }
+    
+    /// Set the date format
+    pub fn date_format(mut self, format: &str) -> Self {
+        self.date_format = Some(format.to_string());
+        self
+    }
+    
+    /// Set the overflow policy
+    pub fn overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+    
+    /// Normalize a numeric string to plain "1234.56" form
+    ///
+    /// Returns the normalized string and whether it was a percentage.
+    fn normalize_number(&self, s: &str) -> (String, bool) {
+        let mut s = s.trim().to_string();
+        let mut negative = false;
+        let mut is_percent = false;
+        
+        if self.currency {
+            // Accounting style negatives: (12.50)
+            if s.starts_with('(') && s.ends_with(')') {
+                s = s[1..s.len() - 1].to_string();
+                negative = true;
+            }
+            
+            s = s.chars()
+                .filter(|c| !matches!(c, '$' | '€' | '£' | '¥' | '₹' | '₩' | '¢') && !c.is_whitespace())
+                .collect();
+            
+            // Strip a leading or trailing ISO currency code such as "USD"
+            let letters = |c: char| c.is_ascii_alphabetic();
+            s = s.trim_start_matches(letters).trim_end_matches(letters).to_string();
+        }
+        
+        if self.percent && s.ends_with('%') {
+            s.pop();
+            is_percent = true;
+        }
+        
+        if let Some(separator) = self.thousands_separator {
+            s = s.replace(separator, "");
+        }
+        
+        if self.decimal_mark != '.' {
+            s = s.replace(self.decimal_mark, ".");
+        }
+        
+        if negative {
+            s.insert(0, '-');
+        }
+        
+        (s, is_percent)
+    }
+    
+    /// Parse a date or date-time string with the configured date format
+    fn parse_date(&self, s: &str) -> Option<NaiveDateTime> {
+        let format = self.date_format.as_ref()?;
+        
+        NaiveDateTime::parse_from_str(s.trim(), format).ok()
+            .or_else(|| {
+                NaiveDate::parse_from_str(s.trim(), format).ok()
+                    .and_then(|date| date.and_hms_opt(0, 0, 0))
+            })
+    }
+}
+
+/// Cast a value to a target data type
+pub(crate) fn cast_value(value: &Value, target_type: &DataType, format: &CastFormat) -> Result<Value, ProcessingError> {
+    let out_of_range = |value: &dyn fmt::Display| ProcessingError::InvalidOperation(
+        format!("Value {} out of range for {}", value, target_type.name())
+    );
+    
+    // Narrow types cast through their 64-bit type, then must fit
+    match target_type {
+        DataType::Int8 | DataType::Int16 | DataType::Int32 => {
+            let (min, max) = target_type.integer_range().unwrap();
+            
+            return match cast_value(value, &DataType::Integer, format)? {
+                Value::Integer(i) if i < min || i > max => match format.overflow {
+                    OverflowPolicy::Error => Err(out_of_range(&i)),
+                    OverflowPolicy::Saturate => Ok(Value::Integer(i.clamp(min, max))),
+                    OverflowPolicy::Promote => Ok(Value::Integer(i)),
+                },
+                cast => Ok(cast),
+            };
+        },
+        DataType::Float32 => {
+            return match cast_value(value, &DataType::Float, format)? {
+                Value::Float(f) if f.is_finite() && f.abs() > f32::MAX as f64 => match format.overflow {
+                    OverflowPolicy::Error => Err(out_of_range(&f)),
+                    OverflowPolicy::Saturate => Ok(Value::Float((f32::MAX as f64).copysign(f))),
+                    OverflowPolicy::Promote => Ok(Value::Float(f)),
+                },
+                // Round to what a 32-bit float can hold
+                Value::Float(f) => Ok(Value::Float(f as f32 as f64)),
+                cast => Ok(cast),
+            };
+        },
+        _ => {},
+    }
+    
+    match (value, target_type) {
+        // Null remains null for any type
+        (Value::Null, _) => Ok(Value::Null),
+        
+        // Boolean casts
+        (Value::Boolean(b), DataType::Boolean) => Ok(Value::Boolean(*b)),
+        (Value::Boolean(b), DataType::Integer) => Ok(Value::Integer(if *b { 1 } else { 0 })),
+        (Value::Boolean(b), DataType::Float) => Ok(Value::Float(if *b { 1.0 } else { 0.0 })),
+        (Value::Boolean(b), DataType::String) => Ok(Value::String(b.to_string())),
+        
+        // Integer casts
+        (Value::Integer(i), DataType::Boolean) => Ok(Value::Boolean(*i != 0)),
+        (Value::Integer(i), DataType::Integer) => Ok(Value::Integer(*i)),
+        (Value::Integer(i), DataType::Float) => Ok(Value::Float(*i as f64)),
+        (Value::Integer(i), DataType::String) => Ok(Value::String(i.to_string())),
+        
+        // Float casts
+        (Value::Float(f), DataType::Boolean) => Ok(Value::Boolean(*f != 0.0)),
+        (Value::Float(f), DataType::Integer) => {
+            // Fractions are truncated; only values outside the i64 range overflow
+            if f.is_finite() && *f >= i64::MIN as f64 && *f < i64::MAX as f64 {
+                return Ok(Value::Integer(*f as i64));
+            }
+            
+            match format.overflow {
+                OverflowPolicy::Error => Err(out_of_range(f)),
+                OverflowPolicy::Saturate => Ok(Value::Integer(*f as i64)),
+                OverflowPolicy::Promote => Ok(Value::Float(*f)),
+            }
+        },
+        (Value::Float(f), DataType::Float) => Ok(Value::Float(*f)),
+        (Value::Float(f), DataType::String) => Ok(Value::String(f.to_string())),
+        
+        // String casts
+        (Value::String(s), DataType::Boolean) => {
+            let lower = s.to_lowercase();
+            if lower == "true" || lower == "yes" || lower == "1" {
+                Ok(Value::Boolean(true))
+            } else if lower == "false" || lower == "no" || lower == "0" {
+                Ok(Value::Boolean(false))
+            } else {
+                Err(ProcessingError::InvalidOperation(
+                    format!("Cannot cast '{}' to boolean", s)
+                ))
+            }
+        },
+        (Value::String(s), DataType::Integer) => {
+            // Dates cast to integers become Unix timestamps
+            if let Some(datetime) = format.parse_date(s) {
+                return Ok(Value::Integer(datetime.timestamp()));
+            }
+            
+            let (normalized, _) = format.normalize_number(s);
+            normalized.parse::<i64>()
+                .map(Value::Integer)
+                .map_err(|_| ProcessingError::InvalidOperation(
+                    format!("Cannot cast '{}' to integer", s)
+                ))
+        },
+        (Value::String(s), DataType::Float) => {
+            let (normalized, is_percent) = format.normalize_number(s);
+            normalized.parse::<f64>()
+                .map(|f| Value::Float(if is_percent { f / 100.0 } else { f }))
+                .map_err(|_| ProcessingError::InvalidOperation(
+                    format!("Cannot cast '{}' to float", s)
+                ))
+        },
+        (Value::String(s), DataType::String) => {
+            // Reformat dates to ISO 8601 when a date format is given
+            if format.date_format.is_some() {
+                let datetime = format.parse_date(s)
+                    .ok_or_else(|| ProcessingError::InvalidOperation(
+                        format!("Cannot parse '{}' as a date", s)
+                    ))?;
+                
+                if datetime.time() == chrono::NaiveTime::MIN {
+                    return Ok(Value::String(datetime.format("%Y-%m-%d").to_string()));
+                }
+                
+                return Ok(Value::String(datetime.format("%Y-%m-%dT%H:%M:%S").to_string()));
+            }
+            
+            Ok(Value::String(s.clone()))
+        },
+        (Value::String(s), DataType::Point) => {
+            // Points are written as "lat,lon"
+            let invalid = || ProcessingError::InvalidOperation(
+                format!("Cannot cast '{}' to point", s)
+            );
+            
+            let (lat, lon) = s.split_once(',').ok_or_else(invalid)?;
+            let lat = lat.trim().parse::<f64>().map_err(|_| invalid())?;
+            let lon = lon.trim().parse::<f64>().map_err(|_| invalid())?;
+            
+            Ok(Value::Point(lat, lon))
+        },
+        
+        (Value::String(s), DataType::Uuid) => {
+            parse_uuid(s)
+                .map(Value::Uuid)
+                .map_err(|_| ProcessingError::InvalidOperation(
+                    format!("Cannot cast '{}' to UUID", s)
+                ))
+        },
+        
+        // Strings parse with the date format if given, and as ISO 8601 otherwise
+        (Value::String(s), DataType::Date) => {
+            match format.date_format {
+                Some(_) => format.parse_date(s).map(|datetime| datetime.date()),
+                None => parse_date(s).ok(),
+            }
+            .map(Value::Date)
+            .ok_or_else(|| ProcessingError::InvalidOperation(
+                format!("Cannot cast '{}' to date", s)
+            ))
+        },
+        (Value::String(s), DataType::Timestamp) => {
+            match format.date_format {
+                Some(_) => format.parse_date(s).map(|datetime| datetime.and_utc()),
+                None => parse_timestamp(s).ok(),
+            }
+            .map(Value::Timestamp)
+            .ok_or_else(|| ProcessingError::InvalidOperation(
+                format!("Cannot cast '{}' to timestamp", s)
+            ))
+        },
+        (Value::String(s), DataType::Time) => {
+            parse_time(s)
+                .map(Value::Time)
+                .map_err(|_| ProcessingError::InvalidOperation(
+                    format!("Cannot cast '{}' to time", s)
+                ))
+        },
+        
+        // Date, timestamp and time casts; timestamps cast to and from integers as Unix timestamps
+        (Value::Date(date), DataType::Date) => Ok(Value::Date(*date)),
+        (Value::Date(date), DataType::Timestamp) => Ok(Value::Timestamp(date.and_time(chrono::NaiveTime::MIN).and_utc())),
+        (Value::Date(date), DataType::String) => Ok(Value::String(format_date(date))),
+        (Value::Timestamp(timestamp), DataType::Timestamp) => Ok(Value::Timestamp(*timestamp)),
+        (Value::Timestamp(timestamp), DataType::Date) => Ok(Value::Date(timestamp.date_naive())),
+        (Value::Timestamp(timestamp), DataType::Time) => Ok(Value::Time(timestamp.time())),
+        (Value::Timestamp(timestamp), DataType::Integer) => Ok(Value::Integer(timestamp.timestamp())),
+        (Value::Timestamp(timestamp), DataType::String) => Ok(Value::String(format_timestamp(timestamp))),
+        (Value::Integer(i), DataType::Timestamp) => {
+            DateTime::from_timestamp(*i, 0)
+                .map(Value::Timestamp)
+                .ok_or_else(|| out_of_range(i))
+        },
+        (Value::Time(time), DataType::Time) => Ok(Value::Time(*time)),
+        (Value::Time(time), DataType::String) => Ok(Value::String(format_time(time))),
+        
+        // Decimal casts round half to even to the target scale
+        (Value::String(s), DataType::Decimal(precision, scale)) => {
+            let (normalized, is_percent) = format.normalize_number(s);
+            let decimal = Decimal::from_str(&normalized)
+                .map_err(|_| ProcessingError::InvalidOperation(
+                    format!("Cannot cast '{}' to decimal", s)
+                ))?;
+            
+            // Percentages become fractions by moving the point, so no digits are lost
+            let decimal = if is_percent { Decimal::new(decimal.unscaled(), decimal.scale() + 2) } else { decimal };
+            
+            fit_decimal(&decimal, *precision, *scale)
+                .map(Value::Decimal)
+                .map_err(|_| out_of_range(s))
+        },
+        (Value::Integer(i), DataType::Decimal(precision, scale)) => {
+            fit_decimal(&Decimal::from(*i), *precision, *scale)
+                .map(Value::Decimal)
+                .map_err(|_| out_of_range(i))
+        },
+        (Value::Float(f), DataType::Decimal(precision, scale)) => {
+            Decimal::from_f64(*f)
+                .and_then(|decimal| fit_decimal(&decimal, *precision, *scale).ok())
+                .map(Value::Decimal)
+                .ok_or_else(|| out_of_range(f))
+        },
+        (Value::Decimal(d), DataType::Decimal(precision, scale)) => {
+            fit_decimal(d, *precision, *scale)
+                .map(Value::Decimal)
+                .map_err(|_| out_of_range(d))
+        },
+        (Value::Decimal(d), DataType::Integer) => {
+            // Fractions are truncated, like floats
+            let whole = d.unscaled() / 10i128.pow(d.scale().min(38));
+            i64::try_from(whole).map(Value::Integer).map_err(|_| out_of_range(d))
+        },
+        (Value::Decimal(d), DataType::Float) => Ok(Value::Float(d.to_f64())),
+        (Value::Decimal(d), DataType::String) => Ok(Value::String(d.to_string())),
+        
+        // Point casts
+        (Value::Point(lat, lon), DataType::Point) => Ok(Value::Point(*lat, *lon)),
+        (Value::Point(lat, lon), DataType::String) => Ok(Value::String(format!("{},{}", lat, lon))),
+        
+        // UUID casts
+        (Value::Uuid(uuid), DataType::Uuid) => Ok(Value::Uuid(*uuid)),
+        (Value::Uuid(uuid), DataType::String) => Ok(Value::String(format_uuid(*uuid))),
+        (Value::Uuid(uuid), DataType::Binary) => Ok(Value::Binary(uuid.to_be_bytes().to_vec())),
+        (Value::Binary(bytes), DataType::Uuid) => {
+            let bytes: [u8; 16] = bytes.as_slice().try_into()
+                .map_err(|_| ProcessingError::InvalidOperation(
+                    format!("Cannot cast {} bytes to UUID", bytes.len())
+                ))?;
+            
+            Ok(Value::Uuid(u128::from_be_bytes(bytes)))
+        },
+        
+        // Other casts not supported
+        _ => Err(ProcessingError::NotSupported(
+            format!("Cast from {:?} to {:?} not supported", value, target_type)
+        )),
+    }
+}
+
+/// Get the type of a cast column, widened for values promoted past the target type
+pub(crate) fn promoted_type<'a, I: Iterator<Item = &'a Value>>(target_type: &DataType, values: I) -> DataType {
+    values.fold(target_type.clone(), |current, value| {
+        let needed = match (value, &current) {
+            (Value::Float(_), t) if t.is_integer() => DataType::Float,
+            (Value::Float(_), DataType::Decimal(_, _)) => DataType::Float,
+            (Value::Integer(i), t) if t.is_integer() => {
+                let (min, max) = t.integer_range().unwrap();
+                if *i < min || *i > max { DataType::Integer } else { return current; }
+            },
+            (Value::Float(f), DataType::Float32) if f.is_finite() && f.abs() > f32::MAX as f64 => DataType::Float,
+            _ => return current,
+        };
+        
+        current.widen(&needed).unwrap_or(current)
+    })
+}
+
+/// Policy for values that fail to cast
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CastErrorPolicy {
+    /// Fail the whole transform on the first bad value
+    Fail,
+    /// Replace bad values with null
+    Null,
+    /// Replace bad values with the column's default value
+    Default,
+    /// Drop rows containing bad values and collect them in a rejects dataset
+    Reject,
+}
+
+/// Column cast definition for a multi-column cast
+struct ColumnCast {
+    column: String,
+    target_type: DataType,
+    default: Value,
+    /// Date format of this column, overriding the shared one
+    date_format: Option<String>,
+}
+
+/// Cast several columns at once with a shared error policy
+pub struct MultiCastTransform {
+    casts: Vec<ColumnCast>,
+    policy: CastErrorPolicy,
+    format: CastFormat,
+    cancellation: CancellationToken,
+}
+
+impl MultiCastTransform {
+    /// Create a new multi-column cast transform
+    pub fn new(policy: CastErrorPolicy) -> Self {
+        MultiCastTransform {
+            casts: Vec::new(),
+            policy,
+            format: CastFormat::default(),
+            cancellation: CancellationToken::new(),
+        }
+    }
+    
+    /// Set format hints for parsing string values
+    pub fn with_format(mut self, format: CastFormat) -> Self {
+        self.format = format;
+        self
+    }
+    
+    /// Stop casting once the token is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+    
+    /// Add a column to cast
+    pub fn cast(self, column: &str, target_type: DataType) -> Self {
+        self.cast_with_default(column, target_type, Value::Null)
+    }
+    
+    /// Add a column to cast with a default used by the `Default` policy
+    ///
+    /// The default is cast to the target type like the column values; a null
+    /// default makes the column nullable.
+    pub fn cast_with_default(mut self, column: &str, target_type: DataType, default: Value) -> Self {
+        self.casts.push(ColumnCast {
+            column: column.to_string(),
+            target_type,
+            default,
+            date_format: None,
+        });
+        self
+    }
+    
+    /// Parse the strings of a cast column with a date format
+    ///
+    /// Casts to strings reformat the dates to ISO 8601. The shared format's
+    /// date format only applies to casts to dates and timestamps.
+    pub fn with_date_format(mut self, column: &str, format: &str) -> Self {
+        for cast in self.casts.iter_mut().filter(|cast| cast.column == column) {
+            cast.date_format = Some(format.to_string());
+        }
+        self
+    }
+    
+    /// Format hints of each cast, with the date format it parses with
+    fn cast_formats(&self) -> Vec<CastFormat> {
+        self.casts.iter()
+            .map(|cast| {
+                let mut format = self.format.clone();
+                if cast.date_format.is_some() {
+                    format.date_format = cast.date_format.clone();
+                } else if !matches!(cast.target_type, DataType::Date | DataType::Timestamp) {
+                    format.date_format = None;
+                }
+                format
+            })
+            .collect()
+    }
+    
+    /// Cast the default of each column to its target type
+    fn cast_defaults(&self, formats: &[CastFormat]) -> Result<Vec<Value>, ProcessingError> {
+        self.casts.iter().zip(formats)
+            .map(|(cast, format)| cast_value(&cast.default, &cast.target_type, format).map_err(|err| ProcessingError::InvalidArgument(
+                format!("Default for column '{}' does not cast to {}: {}", cast.column, cast.target_type.name(), err)
+            )))
+            .collect()
+    }
+    
+    /// Cast the dataset, returning the cast rows and the rejected rows
+    ///
+    /// Rejected rows keep their original values and gain a `_cast_error` column
+    /// describing the first failure. Only the `Reject` policy produces rejects.
+    pub fn process_with_rejects(&self, input: &DataSet) -> Result<(DataSet, DataSet), ProcessingError> {
+        // Find column indices
+        let mut col_indices = Vec::new();
+        for cast in &self.casts {
+            let col_idx = input.schema.fields.iter()
+                .position(|field| field.name == cast.column)
+                .ok_or_else(|| ProcessingError::InvalidArgument(
+                    format!("Column '{}' not found", cast.column)
+                ))?;
+            
+            col_indices.push(col_idx);
+        }
+        
+        // Defaults are cast once, before any row
+        let formats = self.cast_formats();
+        let defaults = match self.policy {
+            CastErrorPolicy::Default => self.cast_defaults(&formats)?,
+            _ => vec![Value::Null; self.casts.len()],
+        };
+        
+        // Create new schema with updated data types
+        let mut fields = input.schema.fields.clone();
+        for ((cast, &col_idx), default) in self.casts.iter().zip(&col_indices).zip(&defaults) {
+            fields[col_idx].data_type = cast.target_type.clone();
+            
+            match self.policy {
+                CastErrorPolicy::Null => fields[col_idx].nullable = true,
+                CastErrorPolicy::Default if matches!(default, Value::Null) => fields[col_idx].nullable = true,
+                _ => {},
+            }
+        }
+        
+        let mut result = DataSet::new(Schema::new(fields));
+        
+        // Rejects keep the original schema plus an error column
+        let mut reject_fields = input.schema.fields.clone();
+        reject_fields.push(Field::new("_cast_error".to_string(), DataType::String, false));
+        let mut rejects = DataSet::new(Schema::new(reject_fields));
+        
+        // Count the values each policy replaced, per cast
+        let mut coerced = vec![0; self.casts.len()];
+        let mut replaced = vec![0; self.casts.len()];
+        
+        // Cast each row
+        for (row_idx, row) in input.data.iter().enumerate() {
+            self.cancellation.checkpoint(row_idx)?;
+            
+            let mut values = row.values.clone();
+            let mut error = None;
+            
+            for (c, (cast, &col_idx)) in self.casts.iter().zip(&col_indices).enumerate() {
+                match cast_value(&values[col_idx], &cast.target_type, &formats[c]) {
+                    Ok(value) => {
+                        if is_coerced(&values[col_idx], &value) {
+                            coerced[c] += 1;
+                        }
+                        values[col_idx] = value;
+                    },
+                    Err(err) => match self.policy {
+                        CastErrorPolicy::Fail => return Err(err.with_context(
+                            ErrorContext::new().column(&cast.column).row(row_idx).value(&values[col_idx])
+                        )),
+                        CastErrorPolicy::Null => {
+                            values[col_idx] = Value::Null;
+                            replaced[c] += 1;
+                        },
+                        CastErrorPolicy::Default => {
+                            values[col_idx] = defaults[c].clone();
+                            replaced[c] += 1;
+                        },
+                        CastErrorPolicy::Reject => {
+                            error = Some(format!("{}: {}", cast.column, err));
+                            break;
+                        },
+                    },
+                }
+            }
+            
+            if let Some(error) = error {
+                let mut reject_values = row.values.clone();
+                reject_values.push(Value::String(error));
+                rejects.add_row(Row::new(reject_values))?;
+            } else {
+                result.add_row(Row::new(values))?;
+            }
+        }
+        
+        let name = self.name();
+        for (c, cast) in self.casts.iter().enumerate() {
+            emit_warning(name, WarningKind::ValuesCoerced, Some(&cast.column), coerced[c], COERCED_MESSAGE);
+            
+            match self.policy {
+                CastErrorPolicy::Null => emit_warning(
+                    name, WarningKind::NullsIntroduced, Some(&cast.column), replaced[c], "Values that failed to cast replaced with null",
+                ),
+                CastErrorPolicy::Default => emit_warning(
+                    name, WarningKind::ValuesCoerced, Some(&cast.column), replaced[c], "Values that failed to cast replaced with the default",
+                ),
+                _ => {},
+            }
+        }
+        
+        emit_warning(name, WarningKind::RowsSkipped, None, rejects.len(), "Rows that failed to cast rejected");
+        
+        for (cast, &col_idx) in self.casts.iter().zip(&col_indices) {
+            result.schema.fields[col_idx].data_type = promoted_type(
+                &cast.target_type,
+                result.data.iter().map(|row| &row.values[col_idx]),
+            );
+        }
+        
+        // Copy metadata
+        for (key, value) in &input.metadata.properties {
+            result.metadata.add(key.clone(), value.clone());
+            rejects.metadata.add(key.clone(), value.clone());
+        }
+        
+        Ok((result, rejects))
+    }
+}
+
+impl DataProcessor for MultiCastTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        self.process_with_rejects(input).map(|(result, _)| result)
+    }
+    
+    fn name(&self) -> &str {
+        "multi_cast"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        let casts = self.casts.iter()
+            .map(|cast| CastSpec {
+                column: cast.column.clone(),
+                data_type: cast.target_type.name(),
+                default: JsonSink::value_to_json(&cast.default),
+                date_format: cast.date_format.clone(),
+            })
+            .collect();
+        
+        Ok(StepSpec::MultiCast { casts, policy: self.policy, format: self.format.clone() }.to_config())
+    }
 }
 
 /// Drop columns from a dataset
@@ -396,5 +1250,269 @@ impl DataProcessor for DropColumnsTransform {
     fn processor_type(&self) -> ProcessorType {
         ProcessorType::Transform
     }
+    
+    fn as_in_place(&self) -> Option<&dyn InPlaceDataProcessor> {
+        Some(self)
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::DropColumns { columns: self.columns.clone() }.to_config())
+    }
+}
+
+impl InPlaceDataProcessor for DropColumnsTransform {
+    fn process_in_place(&self, input: &mut DataSet) -> Result<(), ProcessingError> {
+        // Mark which columns to keep
+        let keep: Vec<bool> = input.schema.fields.iter()
+            .map(|field| !self.columns.contains(&field.name))
+            .collect();
+        
+        input.schema.fields.retain(|field| !self.columns.contains(&field.name));
+        
+        for row in &mut input.data {
+            let mut keep_iter = keep.iter();
+            row.values.retain(|_| *keep_iter.next().unwrap_or(&true));
+        }
+        
+        Ok(())
+    }
+    
+    fn name(&self) -> &str {
+        "drop_columns"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+}
+
+
+/// Strategy for choosing bin edges
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BinStrategy {
+    /// Split the value range into bins of equal width
+    EqualWidth(usize),
+    /// Split the values into bins holding roughly the same number of rows
+    EqualFrequency(usize),
+    /// Use explicit, ascending bin edges
+    Edges(Vec<f64>),
+}
+
+/// Bucket a numeric column into a categorical column
+///
+/// Computed strategies always produce the requested number of bins, so
+/// labels line up with them even when repeated values leave some bins
+/// empty. NaN and infinite values are left unbinned.
+pub struct BinTransform {
+    column: String,
+    output: String,
+    strategy: BinStrategy,
+    labels: Option<Vec<String>>,
 }
 
+impl BinTransform {
+    /// Create a new bin transform
+    pub fn new(column: &str, strategy: BinStrategy) -> Self {
+        BinTransform {
+            column: column.to_string(),
+            output: format!("{}_bin", column),
+            strategy,
+            labels: None,
+        }
+    }
+    
+    /// Create a bin transform with equal-width bins
+    pub fn equal_width(column: &str, bins: usize) -> Self {
+        Self::new(column, BinStrategy::EqualWidth(bins))
+    }
+    
+    /// Create a bin transform with equal-frequency (quantile) bins
+    pub fn equal_frequency(column: &str, bins: usize) -> Self {
+        Self::new(column, BinStrategy::EqualFrequency(bins))
+    }
+    
+    /// Create a bin transform with custom edges
+    pub fn edges(column: &str, edges: Vec<f64>) -> Self {
+        Self::new(column, BinStrategy::Edges(edges))
+    }
+    
+    /// Set the output column name
+    pub fn with_output(mut self, output: &str) -> Self {
+        self.output = output.to_string();
+        self
+    }
+    
+    /// Set the bin labels, one per bin
+    pub fn with_labels(mut self, labels: Vec<String>) -> Self {
+        self.labels = Some(labels);
+        self
+    }
+    
+    /// Compute the bin edges for the given values
+    fn compute_edges(&self, values: &[f64]) -> Result<Vec<f64>, ProcessingError> {
+        let bins = match &self.strategy {
+            BinStrategy::EqualWidth(bins) | BinStrategy::EqualFrequency(bins) => *bins,
+            BinStrategy::Edges(edges) => edges.len().saturating_sub(1),
+        };
+        
+        if let Some(labels) = &self.labels {
+            if labels.len() != bins {
+                return Err(ProcessingError::InvalidArgument(format!(
+                    "Expected {} bin labels, got {}", bins, labels.len()
+                )));
+            }
+        }
+        
+        let edges = match &self.strategy {
+            BinStrategy::EqualWidth(bins) | BinStrategy::EqualFrequency(bins) if *bins == 0 => {
+                return Err(ProcessingError::InvalidArgument(
+                    "Number of bins must be greater than zero".to_string()
+                ));
+            },
+            BinStrategy::EqualWidth(bins) => {
+                if values.is_empty() {
+                    return Ok(Vec::new());
+                }
+                
+                let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+                let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                
+                let width = (max - min) / *bins as f64;
+                (0..=*bins)
+                    .map(|i| if i == *bins { max } else { min + width * i as f64 })
+                    .collect()
+            },
+            BinStrategy::EqualFrequency(bins) => {
+                if values.is_empty() {
+                    return Ok(Vec::new());
+                }
+                
+                let mut sorted = values.to_vec();
+                sorted.sort_by(|a, b| a.total_cmp(b));
+                
+                // Repeated values give repeated quantiles; the bins between them stay empty
+                (0..=*bins)
+                    .map(|i| {
+                        let pos = i as f64 / *bins as f64 * (sorted.len() - 1) as f64;
+                        let idx = pos.floor() as usize;
+                        let frac = pos - idx as f64;
+                        
+                        if idx + 1 < sorted.len() {
+                            sorted[idx] + frac * (sorted[idx + 1] - sorted[idx])
+                        } else {
+                            sorted[idx]
+                        }
+                    })
+                    .collect()
+            },
+            BinStrategy::Edges(edges) => {
+                if edges.len() < 2 || edges.windows(2).any(|w| w[0] >= w[1]) {
+                    return Err(ProcessingError::InvalidArgument(
+                        "Bin edges must contain at least two strictly ascending values".to_string()
+                    ));
+                }
+                
+                edges.clone()
+            },
+        };
+        
+        Ok(edges)
+    }
+    
+    /// Find the bin label for a value
+    fn bin_label(&self, value: f64, edges: &[f64]) -> Option<String> {
+        let first = *edges.first()?;
+        let last = *edges.last()?;
+        
+        if value < first || value > last {
+            return None;
+        }
+        
+        // Bins are half-open except the last one, which includes its upper edge;
+        // a value on repeated edges falls in the last bin they bound
+        let bin = edges.partition_point(|edge| *edge <= value)
+            .saturating_sub(1)
+            .min(edges.len() - 2);
+        
+        Some(match &self.labels {
+            Some(labels) => labels[bin].clone(),
+            None if bin == edges.len() - 2 => format!("[{}, {}]", edges[bin], edges[bin + 1]),
+            None => format!("[{}, {})", edges[bin], edges[bin + 1]),
+        })
+    }
+}
+
+impl DataProcessor for BinTransform {
+    fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
+        // Find column index
+        let col_idx = input.schema.fields.iter()
+            .position(|field| field.name == self.column)
+            .ok_or_else(|| ProcessingError::InvalidArgument(
+                format!("Column '{}' not found", self.column)
+            ))?;
+        
+        if input.schema.get_field_by_name(&self.output).is_some() {
+            return Err(ProcessingError::InvalidArgument(
+                format!("Column '{}' already exists", self.output)
+            ));
+        }
+        
+        let numeric = |value: &Value| match value {
+            Value::Integer(i) => Some(*i as f64),
+            Value::Float(f) if f.is_finite() => Some(*f),
+            _ => None,
+        };
+        
+        let values: Vec<f64> = input.data.iter()
+            .filter_map(|row| numeric(&row.values[col_idx]))
+            .collect();
+        
+        let non_finite = input.data.iter()
+            .filter(|row| matches!(row.values[col_idx], Value::Float(f) if !f.is_finite()))
+            .count();
+        emit_warning(self.name(), WarningKind::ValuesSkipped, Some(&self.column), non_finite, "NaN and infinite values left unbinned");
+        
+        let edges = self.compute_edges(&values)?;
+        
+        // Create new schema with added column
+        let mut fields = input.schema.fields.clone();
+        fields.push(Field::new(self.output.clone(), DataType::String, true));
+        
+        let mut result = DataSet::new(Schema::new(fields));
+        
+        for row in &input.data {
+            let mut values = row.values.clone();
+            
+            let label = numeric(&row.values[col_idx])
+                .and_then(|value| self.bin_label(value, &edges));
+            values.push(label.map(Value::String).unwrap_or(Value::Null));
+            
+            result.add_row(Row::new(values))?;
+        }
+        
+        // Copy metadata
+        for (key, value) in &input.metadata.properties {
+            result.metadata.add(key.clone(), value.clone());
+        }
+        
+        Ok(result)
+    }
+    
+    fn name(&self) -> &str {
+        "bin"
+    }
+    
+    fn processor_type(&self) -> ProcessorType {
+        ProcessorType::Transform
+    }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        Ok(StepSpec::Bin {
+            column: self.column.clone(),
+            output: Some(self.output.clone()),
+            strategy: self.strategy.clone(),
+            labels: self.labels.clone(),
+        }.to_config())
+    }
+}
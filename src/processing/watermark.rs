@@ -0,0 +1,283 @@
+// Event-time windows over streams, closed by watermarks
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::DateTime;
+
+use crate::data::{DataSet, DataSink, DataType, Field, Row, Schema, Value};
+use super::{
+    AggregateFunction, AggregateState, AvgFunction, CountFunction, DynAggregateFunction, MaxFunction, MinFunction,
+    ProcessingError, SumFunction, WarningKind, emit_warning, parse_event_time, promoted_type,
+};
+
+/// Name of the column holding the start of each window
+pub const WINDOW_START_COLUMN: &str = "window_start";
+
+/// Name of the column holding the end of each window
+pub const WINDOW_END_COLUMN: &str = "window_end";
+
+/// Aggregation states of the groups of one window
+type WindowGroups = BTreeMap<Vec<Value>, Vec<AggregateState>>;
+
+/// Windows still open, with the event times seen so far
+#[derive(Default)]
+struct WindowState {
+    /// Open windows by their start in Unix seconds
+    windows: BTreeMap<i64, WindowGroups>,
+    /// Latest event time seen, in Unix seconds
+    max_event_time: Option<i64>,
+    /// Output schema, known once the first batch arrives
+    schema: Option<Schema>,
+    late_rows: u64,
+}
+
+/// Tumbling event-time windows over a stream of batches
+///
+/// Rows are assigned to fixed windows by the event time in a time column
+/// (timestamps, dates, Unix seconds or ISO 8601 strings), however late they
+/// arrive, and aggregated per window and group. The watermark trails the
+/// latest event time seen by the allowed lateness; once it passes the end of
+/// a window, the window is closed and its rows emitted. Rows of windows that
+/// are already closed are late: they go to the late sink if there is one,
+/// and are dropped otherwise.
+///
+/// The watermark advances between batches, so rows of one batch are never
+/// late relative to each other.
+pub struct EventTimeWindow {
+    time_column: String,
+    size: i64,
+    allowed_lateness: i64,
+    group_by_columns: Vec<String>,
+    aggregations: Vec<(String, String, Box<dyn DynAggregateFunction>)>,
+    late_sink: Option<Arc<dyn DataSink + Send + Sync>>,
+    state: Mutex<WindowState>,
+}
+
+impl EventTimeWindow {
+    /// Create windows of a size in seconds over a time column
+    pub fn new(time_column: &str, size: i64) -> Self {
+        EventTimeWindow {
+            time_column: time_column.to_string(),
+            size,
+            allowed_lateness: 0,
+            group_by_columns: Vec::new(),
+            aggregations: Vec::new(),
+            late_sink: None,
+            state: Mutex::new(WindowState::default()),
+        }
+    }
+    
+    /// Keep windows open for rows arriving up to a number of seconds behind the latest event time
+    pub fn with_allowed_lateness(mut self, seconds: i64) -> Self {
+        self.allowed_lateness = seconds.max(0);
+        self
+    }
+    
+    /// Write late rows to a sink instead of dropping them
+    pub fn with_late_sink(mut self, sink: Arc<dyn DataSink + Send + Sync>) -> Self {
+        self.late_sink = Some(sink);
+        self
+    }
+    
+    /// Add a column to group by within each window
+    pub fn group_by(mut self, column: &str) -> Self {
+        self.group_by_columns.push(column.to_string());
+        self
+    }
+    
+    /// Add an aggregation
+    pub fn aggregate<F: AggregateFunction + 'static>(mut self, output_name: &str, input_column: &str, function: F) -> Self {
+        self.aggregations.push((output_name.to_string(), input_column.to_string(), Box::new(function)));
+        self
+    }
+    
+    /// Add a count aggregation
+    pub fn count(self, output_name: &str, input_column: &str) -> Self {
+        self.aggregate(output_name, input_column, CountFunction)
+    }
+    
+    /// Add a sum aggregation
+    pub fn sum(self, output_name: &str, input_column: &str) -> Self {
+        self.aggregate(output_name, input_column, SumFunction::new())
+    }
+    
+    /// Add an average aggregation
+    pub fn avg(self, output_name: &str, input_column: &str) -> Self {
+        self.aggregate(output_name, input_column, AvgFunction)
+    }
+    
+    /// Add a min aggregation
+    pub fn min(self, output_name: &str, input_column: &str) -> Self {
+        self.aggregate(output_name, input_column, MinFunction)
+    }
+    
+    /// Add a max aggregation
+    pub fn max(self, output_name: &str, input_column: &str) -> Self {
+        self.aggregate(output_name, input_column, MaxFunction)
+    }
+    
+    /// Get the watermark in Unix seconds, before which windows are closed
+    pub fn watermark(&self) -> Option<i64> {
+        self.state.lock().unwrap().max_event_time.map(|time| time - self.allowed_lateness)
+    }
+    
+    /// Get the number of late rows seen
+    pub fn late_rows(&self) -> u64 {
+        self.state.lock().unwrap().late_rows
+    }
+    
+    /// Add a batch to the open windows and advance the watermark, returning
+    /// the rows of the windows it closes
+    pub fn push(&self, batch: &DataSet) -> Result<DataSet, ProcessingError> {
+        if self.size <= 0 {
+            return Err(ProcessingError::InvalidArgument(
+                "Window size must be greater than zero".to_string()
+            ));
+        }
+        
+        let find = |column: &str| batch.schema.fields.iter()
+            .position(|field| field.name == column)
+            .ok_or_else(|| ProcessingError::InvalidArgument(
+                format!("Column '{}' not found", column)
+            ));
+        
+        let time_idx = find(&self.time_column)?;
+        let group_by_indices = self.group_by_columns.iter()
+            .map(|column| find(column))
+            .collect::<Result<Vec<_>, _>>()?;
+        let agg_indices = self.aggregations.iter()
+            .map(|(_, column, _)| find(column))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        let mut state = self.state.lock().unwrap();
+        
+        if state.schema.is_none() {
+            state.schema = Some(self.output_schema(batch, &group_by_indices, &agg_indices));
+        }
+        
+        let watermark = state.max_event_time.map(|time| time - self.allowed_lateness);
+        let mut late = DataSet::new(batch.schema.clone());
+        let mut untimed = 0;
+        
+        for row in &batch.data {
+            let time = match &row.values[time_idx] {
+                Value::Null => {
+                    untimed += 1;
+                    continue;
+                },
+                value => parse_event_time(value).ok_or_else(|| ProcessingError::InvalidOperation(
+                    format!("Cannot parse {:?} as a timestamp", value)
+                ))?,
+            };
+            
+            let start = time.div_euclid(self.size) * self.size;
+            
+            if watermark.map_or(false, |watermark| start + self.size <= watermark) {
+                late.add_row(row.clone())?;
+                continue;
+            }
+            
+            state.max_event_time = Some(state.max_event_time.map_or(time, |max| max.max(time)));
+            
+            let key: Vec<Value> = group_by_indices.iter().map(|&i| row.values[i].clone()).collect();
+            let states = state.windows.entry(start).or_default().entry(key)
+                .or_insert_with(|| self.aggregations.iter().map(|(_, _, function)| function.init()).collect());
+            
+            for (((_, _, function), agg_state), &col_idx) in self.aggregations.iter().zip(states).zip(&agg_indices) {
+                function.update_all(agg_state, &mut std::iter::once(&row.values[col_idx]));
+            }
+        }
+        
+        emit_warning("event_time_window", WarningKind::RowsSkipped, Some(&self.time_column), untimed, "Rows without a time skipped");
+        self.handle_late(&mut state, late)?;
+        
+        let watermark = state.max_event_time.map(|time| time - self.allowed_lateness);
+        let closed = match watermark {
+            Some(watermark) => {
+                // Windows ending after the watermark stay open
+                let open = state.windows.split_off(&(watermark - self.size + 1));
+                std::mem::replace(&mut state.windows, open)
+            },
+            None => BTreeMap::new(),
+        };
+        
+        self.emit(&state, closed)
+    }
+    
+    /// Close every open window, returning their rows, as at the end of a stream
+    pub fn flush(&self) -> Result<DataSet, ProcessingError> {
+        let mut state = self.state.lock().unwrap();
+        let closed = std::mem::take(&mut state.windows);
+        self.emit(&state, closed)
+    }
+    
+    /// Build the output schema from the schema of the first batch
+    fn output_schema(&self, batch: &DataSet, group_by_indices: &[usize], agg_indices: &[usize]) -> Schema {
+        let mut fields = vec![
+            Field::new(WINDOW_START_COLUMN.to_string(), DataType::Timestamp, false),
+            Field::new(WINDOW_END_COLUMN.to_string(), DataType::Timestamp, false),
+        ];
+        
+        fields.extend(group_by_indices.iter().map(|&i| batch.schema.fields[i].clone()));
+        
+        for ((output_name, _, function), &col_idx) in self.aggregations.iter().zip(agg_indices) {
+            let output_type = function.output_type(&batch.schema.fields[col_idx].data_type);
+            fields.push(Field::new(output_name.clone(), output_type, true));
+        }
+        
+        Schema::new(fields)
+    }
+    
+    /// Write late rows to the late sink, or drop them
+    fn handle_late(&self, state: &mut WindowState, late: DataSet) -> Result<(), ProcessingError> {
+        if late.is_empty() {
+            return Ok(());
+        }
+        
+        state.late_rows += late.len() as u64;
+        
+        match &self.late_sink {
+            Some(sink) => sink.write(&late)?,
+            None => emit_warning("event_time_window", WarningKind::RowsSkipped, Some(&self.time_column), late.len(), "Late rows dropped"),
+        }
+        
+        Ok(())
+    }
+    
+    /// Finalize the groups of closed windows into rows, in window and group order
+    fn emit(&self, state: &WindowState, closed: BTreeMap<i64, WindowGroups>) -> Result<DataSet, ProcessingError> {
+        let schema = match &state.schema {
+            Some(schema) => schema.clone(),
+            None => return Ok(DataSet::new(Schema::new(Vec::new()))),
+        };
+        
+        let mut result = DataSet::new(schema);
+        
+        for (start, groups) in closed {
+            let bound = |seconds: i64| DateTime::from_timestamp(seconds, 0).map_or(Value::Null, Value::Timestamp);
+            
+            for (key, states) in groups {
+                let mut values = vec![bound(start), bound(start + self.size)];
+                values.extend(key);
+                
+                for ((_, _, function), agg_state) in self.aggregations.iter().zip(states) {
+                    function.check(&agg_state)?;
+                    values.push(function.finalize(agg_state));
+                }
+                
+                result.add_row(Row::new(values))?;
+            }
+        }
+        
+        // Sums promoted past i64 hold floats
+        let offset = 2 + self.group_by_columns.len();
+        for j in 0..self.aggregations.len() {
+            let field = &mut result.schema.fields[offset + j];
+            field.data_type = promoted_type(&field.data_type, result.data.iter().map(|row| &row.values[offset + j]));
+        }
+        
+        Ok(result)
+    }
+}
@@ -1,30 +1,160 @@
 // Window operations for data processing
 // Author: Gabriel Demetrios Lafis
 
-use crate::data::{DataSet, DataType, Field, Row, Schema, Value};
-use super::{DataProcessor, ProcessingError, ProcessorType};
+use std::collections::HashMap;
+
+use serde_json::Value as JsonValue;
+
+use crate::data::{DataSet, DataType, Dictionary, Field, JsonSink, Row, Schema, Value};
+use super::{
+    AvgFunction, CountFunction, DataProcessor, DynAggregateFunction, MaxFunction, MinFunction, OverflowPolicy,
+    ProcessingError, ProcessorType, RowKeys, StepSpec, SumFunction, promoted_type,
+};
 
 /// Window function type
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy)]
 pub enum WindowFunctionType {
     RowNumber,
     Rank,
     DenseRank,
+    /// Relative rank from 0 to 1: `(rank - 1) / (rows - 1)`
+    PercentRank,
+    /// Fraction of the partition's rows up to and including the row's peers
+    CumeDist,
+    /// Bucket from 1 to `n`, splitting the partition into `n` near-equal groups
+    Ntile,
     Lead,
     Lag,
     FirstValue,
     LastValue,
     NthValue,
+    /// Aggregates of the value column over the whole partition
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Count,
     Custom(fn(&[&Row], usize) -> Value),
 }
 
+/// Custom functions are never equal, as function pointers have no stable address
+impl PartialEq for WindowFunctionType {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (WindowFunctionType::Custom(_), _) | (_, WindowFunctionType::Custom(_)) => false,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl WindowFunctionType {
+    /// Parse a built-in window function from its name
+    pub fn from_str(s: &str) -> Result<Self, ProcessingError> {
+        match s.to_lowercase().as_str() {
+            "row_number" => Ok(WindowFunctionType::RowNumber),
+            "rank" => Ok(WindowFunctionType::Rank),
+            "dense_rank" => Ok(WindowFunctionType::DenseRank),
+            "percent_rank" => Ok(WindowFunctionType::PercentRank),
+            "cume_dist" => Ok(WindowFunctionType::CumeDist),
+            "ntile" => Ok(WindowFunctionType::Ntile),
+            "lead" => Ok(WindowFunctionType::Lead),
+            "lag" => Ok(WindowFunctionType::Lag),
+            "first_value" => Ok(WindowFunctionType::FirstValue),
+            "last_value" => Ok(WindowFunctionType::LastValue),
+            "nth_value" => Ok(WindowFunctionType::NthValue),
+            "sum" => Ok(WindowFunctionType::Sum),
+            "avg" => Ok(WindowFunctionType::Avg),
+            "min" => Ok(WindowFunctionType::Min),
+            "max" => Ok(WindowFunctionType::Max),
+            "count" => Ok(WindowFunctionType::Count),
+            _ => Err(ProcessingError::InvalidArgument(format!("Unknown window function: {}", s))),
+        }
+    }
+    
+    /// Get the name of the function, as used in error messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            WindowFunctionType::RowNumber => "row_number",
+            WindowFunctionType::Rank => "rank",
+            WindowFunctionType::DenseRank => "dense_rank",
+            WindowFunctionType::PercentRank => "percent_rank",
+            WindowFunctionType::CumeDist => "cume_dist",
+            WindowFunctionType::Ntile => "ntile",
+            WindowFunctionType::Lead => "lead",
+            WindowFunctionType::Lag => "lag",
+            WindowFunctionType::FirstValue => "first_value",
+            WindowFunctionType::LastValue => "last_value",
+            WindowFunctionType::NthValue => "nth_value",
+            WindowFunctionType::Sum => "sum",
+            WindowFunctionType::Avg => "avg",
+            WindowFunctionType::Min => "min",
+            WindowFunctionType::Max => "max",
+            WindowFunctionType::Count => "count",
+            WindowFunctionType::Custom(_) => "custom",
+        }
+    }
+    
+    /// Check whether the function reads the values of a value column
+    pub fn needs_value_column(&self) -> bool {
+        matches!(
+            self,
+            WindowFunctionType::Lead
+                | WindowFunctionType::Lag
+                | WindowFunctionType::FirstValue
+                | WindowFunctionType::LastValue
+                | WindowFunctionType::NthValue
+        ) || self.is_aggregate()
+    }
+    
+    /// Check whether the function needs the partition sorted by order by columns
+    pub fn needs_order_by(&self) -> bool {
+        matches!(
+            self,
+            WindowFunctionType::Rank
+                | WindowFunctionType::DenseRank
+                | WindowFunctionType::PercentRank
+                | WindowFunctionType::CumeDist
+        )
+    }
+    
+    /// Check whether the function aggregates the partition
+    pub fn is_aggregate(&self) -> bool {
+        matches!(
+            self,
+            WindowFunctionType::Sum
+                | WindowFunctionType::Avg
+                | WindowFunctionType::Min
+                | WindowFunctionType::Max
+                | WindowFunctionType::Count
+        )
+    }
+}
+
 /// Window processor for window functions
+///
+/// Rows are split into partitions by the partition columns and sorted
+/// within each partition by the order columns, with nulls first. Ranking
+/// functions number the rows of a partition; value functions read the value
+/// column of other rows in it, and their output column takes its type.
+/// Aggregate functions attach the aggregate of the value column over the
+/// whole partition to each of its rows, such as the group total needed for
+/// a percent-of-group column, without a group by and a join back. Rows keep
+/// their input order in the output.
 pub struct WindowProcessor {
     output_column: String,
     function_type: WindowFunctionType,
+    value_column: Option<String>,
     partition_by: Vec<String>,
     order_by: Vec<(String, bool)>, // (column, ascending)
     function_args: Vec<Value>,
+    overflow: OverflowPolicy,
+}
+
+/// Column indices of a window processor, resolved against its input
+struct WindowColumns {
+    partition_by: Vec<usize>,
+    order_by: Vec<(usize, bool)>,
+    value: Option<usize>,
 }
 
 impl WindowProcessor {
@@ -32,6 +162,7 @@ impl WindowProcessor {
     pub fn new(
         output_column: &str,
         function_type: WindowFunctionType,
+        value_column: Option<String>,
         partition_by: Vec<String>,
         order_by: Vec<(String, bool)>,
         function_args: Vec<Value>,
@@ -39,98 +170,104 @@ impl WindowProcessor {
         WindowProcessor {
             output_column: output_column.to_string(),
             function_type,
+            value_column,
             partition_by,
             order_by,
             function_args,
+            overflow: OverflowPolicy::Error,
         }
     }
     
+    /// Create a window function with no value column or arguments
+    fn ranking(output_column: &str, function_type: WindowFunctionType) -> Self {
+        Self::new(output_column, function_type, None, Vec::new(), Vec::new(), Vec::new())
+    }
+    
+    /// Create a window function reading a value column
+    fn reading(output_column: &str, function_type: WindowFunctionType, value_column: &str, args: Vec<Value>) -> Self {
+        Self::new(output_column, function_type, Some(value_column.to_string()), Vec::new(), Vec::new(), args)
+    }
+    
     /// Create a row number window function
     pub fn row_number(output_column: &str) -> Self {
-        Self::new(
-            output_column,
-            WindowFunctionType::RowNumber,
-            Vec::new(),
-            Vec::new(),
-            Vec::new(),
-        )
+        Self::ranking(output_column, WindowFunctionType::RowNumber)
     }
     
     /// Create a rank window function
     pub fn rank(output_column: &str) -> Self {
-        Self::new(
-            output_column,
-            WindowFunctionType::Rank,
-            Vec::new(),
-            Vec::new(),
-            Vec::new(),
-        )
+        Self::ranking(output_column, WindowFunctionType::Rank)
     }
     
     /// Create a dense rank window function
     pub fn dense_rank(output_column: &str) -> Self {
-        Self::new(
-            output_column,
-            WindowFunctionType::DenseRank,
-            Vec::new(),
-            Vec::new(),
-            Vec::new(),
-        )
+        Self::ranking(output_column, WindowFunctionType::DenseRank)
     }
     
-    /// Create a lead window function
-    pub fn lead(output_column: &str, offset: i64) -> Self {
-        Self::new(
-            output_column,
-            WindowFunctionType::Lead,
-            Vec::new(),
-            Vec::new(),
-            vec![Value::Integer(offset)],
-        )
+    /// Create a percent rank window function
+    pub fn percent_rank(output_column: &str) -> Self {
+        Self::ranking(output_column, WindowFunctionType::PercentRank)
     }
     
-    /// Create a lag window function
-    pub fn lag(output_column: &str, offset: i64) -> Self {
-        Self::new(
-            output_column,
-            WindowFunctionType::Lag,
-            Vec::new(),
-            Vec::new(),
-            vec![Value::Integer(offset)],
-        )
+    /// Create a cumulative distribution window function
+    pub fn cume_dist(output_column: &str) -> Self {
+        Self::ranking(output_column, WindowFunctionType::CumeDist)
+    }
+    
+    /// Create an ntile window function, numbering `n` buckets from 1
+    ///
+    /// When the rows do not split evenly, the first buckets get one more row.
+    pub fn ntile(output_column: &str, n: i64) -> Self {
+        Self::new(output_column, WindowFunctionType::Ntile, None, Vec::new(), Vec::new(), vec![Value::Integer(n)])
+    }
+    
+    /// Create a lead window function, reading the value column the given number of rows ahead
+    pub fn lead(output_column: &str, value_column: &str, offset: i64) -> Self {
+        Self::reading(output_column, WindowFunctionType::Lead, value_column, vec![Value::Integer(offset)])
+    }
+    
+    /// Create a lag window function, reading the value column the given number of rows back
+    pub fn lag(output_column: &str, value_column: &str, offset: i64) -> Self {
+        Self::reading(output_column, WindowFunctionType::Lag, value_column, vec![Value::Integer(offset)])
     }
     
     /// Create a first value window function
-    pub fn first_value(output_column: &str) -> Self {
-        Self::new(
-            output_column,
-            WindowFunctionType::FirstValue,
-            Vec::new(),
-            Vec::new(),
-            Vec::new(),
-        )
+    pub fn first_value(output_column: &str, value_column: &str) -> Self {
+        Self::reading(output_column, WindowFunctionType::FirstValue, value_column, Vec::new())
     }
     
-    /// Create a last value window function
-    pub fn last_value(output_column: &str) -> Self {
-        Self::new(
-            output_column,
-            WindowFunctionType::LastValue,
-            Vec::new(),
-            Vec::new(),
-            Vec::new(),
-        )
+    /// Create a last value window function, over the whole partition
+    pub fn last_value(output_column: &str, value_column: &str) -> Self {
+        Self::reading(output_column, WindowFunctionType::LastValue, value_column, Vec::new())
     }
     
-    /// Create a nth value window function
-    pub fn nth_value(output_column: &str, n: i64) -> Self {
-        Self::new(
-            output_column,
-            WindowFunctionType::NthValue,
-            Vec::new(),
-            Vec::new(),
-            vec![Value::Integer(n)],
-        )
+    /// Create a nth value window function, with `n` starting at 1
+    pub fn nth_value(output_column: &str, value_column: &str, n: i64) -> Self {
+        Self::reading(output_column, WindowFunctionType::NthValue, value_column, vec![Value::Integer(n)])
+    }
+    
+    /// Create a sum window function, the partition total of the value column
+    pub fn sum(output_column: &str, value_column: &str) -> Self {
+        Self::reading(output_column, WindowFunctionType::Sum, value_column, Vec::new())
+    }
+    
+    /// Create an average window function
+    pub fn avg(output_column: &str, value_column: &str) -> Self {
+        Self::reading(output_column, WindowFunctionType::Avg, value_column, Vec::new())
+    }
+    
+    /// Create a minimum window function
+    pub fn min(output_column: &str, value_column: &str) -> Self {
+        Self::reading(output_column, WindowFunctionType::Min, value_column, Vec::new())
+    }
+    
+    /// Create a maximum window function
+    pub fn max(output_column: &str, value_column: &str) -> Self {
+        Self::reading(output_column, WindowFunctionType::Max, value_column, Vec::new())
+    }
+    
+    /// Create a count window function, counting the non-null values of the partition
+    pub fn count(output_column: &str, value_column: &str) -> Self {
+        Self::reading(output_column, WindowFunctionType::Count, value_column, Vec::new())
     }
     
     /// Add partition by columns
@@ -145,347 +282,282 @@ impl WindowProcessor {
         self
     }
     
-    /// Apply a window function to a partition
-    fn apply_window_function(&self, partition: &[&Row], row_idx: usize) -> Result<Value, ProcessingError> {
+    /// Set the column read by value functions
+    pub fn value_column(mut self, column: &str) -> Self {
+        self.value_column = Some(column.to_string());
+        self
+    }
+    
+    /// Set the overflow policy of integer sums
+    pub fn with_overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+    
+    /// Get the aggregate function of an aggregate window function
+    fn aggregate_function(&self) -> Option<Box<dyn DynAggregateFunction>> {
         match self.function_type {
-            WindowFunctionType::RowNumber => {
-                Ok(Value::Integer((row_idx + 1) as i64))
+            WindowFunctionType::Sum => Some(Box::new(SumFunction::new().with_overflow(self.overflow))),
+            WindowFunctionType::Avg => Some(Box::new(AvgFunction)),
+            WindowFunctionType::Min => Some(Box::new(MinFunction)),
+            WindowFunctionType::Max => Some(Box::new(MaxFunction)),
+            WindowFunctionType::Count => Some(Box::new(CountFunction)),
+            _ => None,
+        }
+    }
+    
+    /// Resolve the columns of the processor against an input schema
+    fn resolve_columns(&self, schema: &Schema) -> Result<WindowColumns, ProcessingError> {
+        let find = |column: &str, role: &str| {
+            schema.fields.iter().position(|f| f.name == column)
+                .ok_or_else(|| ProcessingError::InvalidArgument(
+                    format!("{} column '{}' not found", role, column)
+                ))
+        };
+        
+        let partition_by = self.partition_by.iter()
+            .map(|col| find(col, "Partition by"))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        let order_by = self.order_by.iter()
+            .map(|(col, ascending)| Ok((find(col, "Order by")?, *ascending)))
+            .collect::<Result<Vec<_>, ProcessingError>>()?;
+        
+        let value = match &self.value_column {
+            Some(col) => Some(find(col, "Value")?),
+            None if self.function_type.needs_value_column() => {
+                return Err(ProcessingError::InvalidArgument(format!(
+                    "Window function {} requires a value column", self.function_type.name()
+                )));
             },
-            WindowFunctionType::Rank => {
-                if self.order_by.is_empty() {
-                    return Err(ProcessingError::InvalidArgument(
-                        "Rank function requires order by columns".to_string()
-                    ));
-                }
-                
-                let mut rank = 1;
-                let current_row = partition[row_idx];
-                
-                for (i, row) in partition.iter().enumerate() {
-                    if i >= row_idx {
-                        break;
-                    }
-                    
-                    let mut equal = true;
-                    
-                    for (col, ascending) in &self.order_by {
-                        let col_idx = self.find_column_index(col)?;
-                        
-                        match self.compare_values(&row.values[col_idx], &current_row.values[col_idx]) {
-                            std::cmp::Ordering::Equal => {},
-                            _ => {
-                                equal = false;
-                                break;
-                            }
-                        }
-                    }
-                    
-                    if !equal {
-                        rank += 1;
-                    }
-                }
-                
-                Ok(Value::Integer(rank))
+            None => None,
+        };
+        
+        if order_by.is_empty() && self.function_type.needs_order_by() {
+            return Err(ProcessingError::InvalidArgument(format!(
+                "Window function {} requires order by columns", self.function_type.name()
+            )));
+        }
+        
+        Ok(WindowColumns { partition_by, order_by, value })
+    }
+    
+    /// Get the integer argument of the function
+    fn integer_arg(&self, arg: &str) -> Result<i64, ProcessingError> {
+        match self.function_args.first() {
+            Some(Value::Integer(i)) => Ok(*i),
+            Some(_) => Err(ProcessingError::InvalidArgument(format!(
+                "Window function {} {} must be an integer", self.function_type.name(), arg
+            ))),
+            None => Err(ProcessingError::InvalidArgument(format!(
+                "Window function {} requires {} argument", self.function_type.name(), arg
+            ))),
+        }
+    }
+    
+    /// Compare two rows by the order by columns
+    fn compare_rows(&self, a: &Row, b: &Row, columns: &WindowColumns) -> std::cmp::Ordering {
+        for &(i, ascending) in &columns.order_by {
+            let cmp = a.values[i].cmp(&b.values[i]);
+            
+            if cmp != std::cmp::Ordering::Equal {
+                return if ascending { cmp } else { cmp.reverse() };
+            }
+        }
+        
+        std::cmp::Ordering::Equal
+    }
+    
+    /// Apply the window function to a sorted partition, giving a value per row
+    fn apply_window_function(&self, partition: &[&Row], columns: &WindowColumns) -> Result<Vec<Value>, ProcessingError> {
+        let len = partition.len();
+        let value_at = |idx: usize| columns.value.map_or(Value::Null, |col| partition[idx].values[col].clone());
+        
+        match self.function_type {
+            WindowFunctionType::RowNumber => {
+                Ok((1..=len).map(|n| Value::Integer(n as i64)).collect())
             },
-            WindowFunctionType::DenseRank => {
-                if self.order_by.is_empty() {
-                    return Err(ProcessingError::InvalidArgument(
-                        "Dense rank function requires order by columns".to_string()
-                    ));
-                }
-                
-                let mut rank = 1;
-                let mut prev_row = None;
-                let current_row = partition[row_idx];
+            WindowFunctionType::Rank
+            | WindowFunctionType::DenseRank
+            | WindowFunctionType::PercentRank
+            | WindowFunctionType::CumeDist => {
+                // Rows equal on the order by columns are peers and share a rank
+                let mut values = Vec::with_capacity(len);
+                let mut group = 0;
+                let mut start = 0;
                 
-                for (i, row) in partition.iter().enumerate() {
-                    if i >= row_idx {
-                        break;
+                while start < len {
+                    let mut end = start + 1;
+                    while end < len && self.compare_rows(partition[end - 1], partition[end], columns) == std::cmp::Ordering::Equal {
+                        end += 1;
                     }
+                    group += 1;
                     
-                    if let Some(prev) = prev_row {
-                        let mut equal = true;
-                        
-                        for (col, ascending) in &self.order_by {
-                            let col_idx = self.find_column_index(col)?;
-                            
-                            match self.compare_values(&prev.values[col_idx], &row.values[col_idx]) {
-                                std::cmp::Ordering::Equal => {},
-                                _ => {
-                                    equal = false;
-                                    break;
-                                }
-                            }
-                        }
-                        
-                        if !equal {
-                            rank += 1;
-                        }
-                    }
+                    let value = match self.function_type {
+                        WindowFunctionType::Rank => Value::Integer(start as i64 + 1),
+                        WindowFunctionType::DenseRank => Value::Integer(group),
+                        WindowFunctionType::PercentRank if len > 1 => Value::Float(start as f64 / (len - 1) as f64),
+                        WindowFunctionType::PercentRank => Value::Float(0.0),
+                        _ => Value::Float(end as f64 / len as f64),
+                    };
                     
-                    prev_row = Some(row);
+                    values.extend(std::iter::repeat(value).take(end - start));
+                    start = end;
                 }
                 
-                Ok(Value::Integer(rank))
+                Ok(values)
             },
-            WindowFunctionType::Lead => {
-                if self.function_args.is_empty() {
+            WindowFunctionType::Ntile => {
+                let n = self.integer_arg("n")?;
+                if n < 1 {
                     return Err(ProcessingError::InvalidArgument(
-                        "Lead function requires offset argument".to_string()
+                        "Window function ntile n must be at least 1".to_string()
                     ));
                 }
                 
-                let offset = match &self.function_args[0] {
-                    Value::Integer(i) => *i as usize,
-                    _ => return Err(ProcessingError::InvalidArgument(
-                        "Lead function offset must be an integer".to_string()
-                    )),
-                };
-                
-                let lead_idx = row_idx + offset;
+                // The first `extra` buckets hold one row more than the rest
+                let n = n as usize;
+                let (size, extra) = (len / n, len % n);
+                let large_rows = extra * (size + 1);
                 
-                if lead_idx < partition.len() {
-                    if self.order_by.is_empty() {
-                        Ok(Value::Integer((lead_idx + 1) as i64))
-                    } else {
-                        let col = &self.order_by[0].0;
-                        let col_idx = self.find_column_index(col)?;
-                        Ok(partition[lead_idx].values[col_idx].clone())
-                    }
-                } else {
-                    Ok(Value::Null)
-                }
+                Ok((0..len)
+                    .map(|i| {
+                        let bucket = if i < large_rows { i / (size + 1) } else { extra + (i - large_rows) / size };
+                        Value::Integer(bucket as i64 + 1)
+                    })
+                    .collect())
             },
-            WindowFunctionType::Lag => {
-                if self.function_args.is_empty() {
-                    return Err(ProcessingError::InvalidArgument(
-                        "Lag function requires offset argument".to_string()
-                    ));
+            WindowFunctionType::Lead | WindowFunctionType::Lag => {
+                let offset = self.integer_arg("offset")?;
+                if offset < 0 {
+                    return Err(ProcessingError::InvalidArgument(format!(
+                        "Window function {} offset must not be negative", self.function_type.name()
+                    )));
                 }
                 
-                let offset = match &self.function_args[0] {
-                    Value::Integer(i) => *i as usize,
-                    _ => return Err(ProcessingError::InvalidArgument(
-                        "Lag function offset must be an integer".to_string()
-                    )),
-                };
+                let offset = offset as usize;
+                let lead = self.function_type == WindowFunctionType::Lead;
                 
-                if offset <= row_idx {
-                    let lag_idx = row_idx - offset;
-                    
-                    if self.order_by.is_empty() {
-                        Ok(Value::Integer((lag_idx + 1) as i64))
-                    } else {
-                        let col = &self.order_by[0].0;
-                        let col_idx = self.find_column_index(col)?;
-                        Ok(partition[lag_idx].values[col_idx].clone())
-                    }
-                } else {
-                    Ok(Value::Null)
-                }
+                Ok((0..len)
+                    .map(|i| {
+                        let target = if lead { i.checked_add(offset) } else { i.checked_sub(offset) };
+                        target.filter(|&t| t < len).map_or(Value::Null, value_at)
+                    })
+                    .collect())
             },
             WindowFunctionType::FirstValue => {
-                if partition.is_empty() {
-                    Ok(Value::Null)
-                } else if self.order_by.is_empty() {
-                    Ok(Value::Integer(1))
-                } else {
-                    let col = &self.order_by[0].0;
-                    let col_idx = self.find_column_index(col)?;
-                    Ok(partition[0].values[col_idx].clone())
-                }
+                Ok(vec![if len > 0 { value_at(0) } else { Value::Null }; len])
             },
             WindowFunctionType::LastValue => {
-                if partition.is_empty() {
-                    Ok(Value::Null)
-                } else if self.order_by.is_empty() {
-                    Ok(Value::Integer(partition.len() as i64))
-                } else {
-                    let col = &self.order_by[0].0;
-                    let col_idx = self.find_column_index(col)?;
-                    Ok(partition[partition.len() - 1].values[col_idx].clone())
-                }
+                Ok(vec![if len > 0 { value_at(len - 1) } else { Value::Null }; len])
             },
             WindowFunctionType::NthValue => {
-                if self.function_args.is_empty() {
+                let n = self.integer_arg("n")?;
+                if n < 1 {
                     return Err(ProcessingError::InvalidArgument(
-                        "Nth value function requires n argument".to_string()
+                        "Window function nth_value n must be at least 1".to_string()
                     ));
                 }
                 
-                let n = match &self.function_args[0] {
-                    Value::Integer(i) => *i as usize,
-                    _ => return Err(ProcessingError::InvalidArgument(
-                        "Nth value function n must be an integer".to_string()
-                    )),
-                };
+                let n = n as usize;
+                Ok(vec![if n <= len { value_at(n - 1) } else { Value::Null }; len])
+            },
+            WindowFunctionType::Sum
+            | WindowFunctionType::Avg
+            | WindowFunctionType::Min
+            | WindowFunctionType::Max
+            | WindowFunctionType::Count => {
+                // Checked when the columns were resolved
+                let function = self.aggregate_function().unwrap();
+                let col = columns.value.unwrap();
                 
-                if n == 0 || n > partition.len() {
-                    Ok(Value::Null)
-                } else if self.order_by.is_empty() {
-                    Ok(Value::Integer(n as i64))
-                } else {
-                    let col = &self.order_by[0].0;
-                    let col_idx = self.find_column_index(col)?;
-                    Ok(partition[n - 1].values[col_idx].clone())
-                }
+                let mut state = function.init();
+                function.update_all(&mut state, &mut partition.iter().map(|row| &row.values[col]));
+                function.check(&state)?;
+                
+                Ok(vec![function.finalize(state); len])
             },
             WindowFunctionType::Custom(f) => {
-                Ok(f(partition, row_idx))
+                Ok((0..len).map(|i| f(partition, i)).collect())
             },
         }
     }
-    
-    /// Find the index of a column
-    fn find_column_index(&self, column: &str) -> Result<usize, ProcessingError> {
-        for (i, field) in self.schema.fields.iter().enumerate() {
-            if field.name == column {
-                return Ok(i);
-            }
-        }
-        
-        Err(ProcessingError::InvalidArgument(
-            format!("Column '{}' not found", column)
-        ))
-    }
-    
-    /// Compare two values
-    fn compare_values(&self, a: &Value, b: &Value) -> std::cmp::Ordering {
-        match (a, b) {
-            (Value::Null, Value::Null) => std::cmp::Ordering::Equal,
-            (Value::Null, _) => std::cmp::Ordering::Less,
-            (_, Value::Null) => std::cmp::Ordering::Greater,
-            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
-            (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
-            (Value::Float(a), Value::Float(b)) => a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal),
-            (Value::String(a), Value::String(b)) => a.cmp(b),
-            _ => std::cmp::Ordering::Equal,
-        }
-    }
 }
 
 impl DataProcessor for WindowProcessor {
     fn process(&self, input: &DataSet) -> Result<DataSet, ProcessingError> {
         // Check if output column already exists
-        for field in &input.schema.fields {
-            if field.name == self.output_column {
-                return Err(ProcessingError::InvalidArgument(
-                    format!("Output column '{}' already exists", self.output_column)
-                ));
-            }
+        if input.schema.fields.iter().any(|field| field.name == self.output_column) {
+            return Err(ProcessingError::InvalidArgument(
+                format!("Output column '{}' already exists", self.output_column)
+            ));
         }
         
-        // Create output schema
-        let mut output_fields = input.schema.fields.clone();
-        output_fields.push(Field::new(
-            self.output_column.clone(),
-            DataType::Integer, // Most window functions return integers
-            true,
-        ));
+        let columns = self.resolve_columns(&input.schema)?;
         
-        let output_schema = Schema::new(output_fields);
-        let mut result = DataSet::new(output_schema);
+        // Value functions return the value column's type, aggregates their
+        // function's type for it, and ranking functions integers, or floats
+        // for relative ranks
+        let data_type = match (columns.value, self.aggregate_function()) {
+            (Some(col), Some(function)) => function.output_type(&input.schema.fields[col].data_type),
+            (Some(col), None) => input.schema.fields[col].data_type.clone(),
+            (None, _) if matches!(self.function_type, WindowFunctionType::PercentRank | WindowFunctionType::CumeDist) => DataType::Float,
+            (None, _) => DataType::Integer,
+        };
         
-        // Store schema for later use
-        self.schema = input.schema.clone();
+        let mut output_fields = input.schema.fields.clone();
+        output_fields.push(Field::new(self.output_column.clone(), data_type, true));
         
-        // Find partition by column indices
-        let mut partition_indices = Vec::new();
-        for col in &self.partition_by {
-            let mut found = false;
-            
-            for (i, field) in input.schema.fields.iter().enumerate() {
-                if &field.name == col {
-                    partition_indices.push(i);
-                    found = true;
-                    break;
-                }
-            }
-            
-            if !found {
-                return Err(ProcessingError::InvalidArgument(
-                    format!("Partition by column '{}' not found", col)
-                ));
-            }
-        }
+        let mut result = DataSet::new(Schema::new(output_fields));
         
-        // Find order by column indices
-        let mut order_indices = Vec::new();
-        for (col, _) in &self.order_by {
-            let mut found = false;
-            
-            for (i, field) in input.schema.fields.iter().enumerate() {
-                if &field.name == col {
-                    order_indices.push(i);
-                    found = true;
-                    break;
-                }
-            }
-            
-            if !found {
-                return Err(ProcessingError::InvalidArgument(
-                    format!("Order by column '{}' not found", col)
-                ));
-            }
-        }
+        // Group row indices by partition, in order of first appearance
+        let mut partitions: Vec<Vec<usize>> = Vec::new();
         
-        // Group rows by partition
-        let mut partitions = Vec::new();
-        
-        if partition_indices.is_empty() {
-            // Single partition with all rows
-            partitions.push(input.data.iter().collect::<Vec<_>>());
+        if columns.partition_by.is_empty() {
+            partitions.push((0..input.data.len()).collect());
         } else {
-            // Group rows by partition key
-            let mut partition_map = std::collections::HashMap::new();
+            let encode = vec![true; columns.partition_by.len()];
+            let keys = RowKeys::new(input, &columns.partition_by, &encode, &mut Dictionary::new());
+            let mut partition_of = HashMap::new();
             
-            for row in &input.data {
-                let key: Vec<Value> = partition_indices.iter()
-                    .map(|&i| row.values[i].clone())
-                    .collect();
-                
-                partition_map.entry(key).or_insert_with(Vec::new).push(row);
-            }
-            
-            partitions.extend(partition_map.values().cloned());
-        }
-        
-        // Sort partitions if order by is specified
-        if !order_indices.is_empty() {
-            for partition in &mut partitions {
-                partition.sort_by(|a, b| {
-                    for (i, (_, ascending)) in order_indices.iter().zip(self.order_by.iter()) {
-                        let cmp = self.compare_values(&a.values[*i], &b.values[*i]);
-                        
-                        if cmp != std::cmp::Ordering::Equal {
-                            return if *ascending { cmp } else { cmp.reverse() };
-                        }
-                    }
-                    
-                    std::cmp::Ordering::Equal
+            for row in 0..input.data.len() {
+                let idx = *partition_of.entry(keys.key(row)).or_insert_with(|| {
+                    partitions.push(Vec::new());
+                    partitions.len() - 1
                 });
+                partitions[idx].push(row);
             }
         }
         
-        // Apply window function to each row
-        let mut window_values = Vec::new();
+        // Apply the window function to each sorted partition
+        let mut window_values = vec![Value::Null; input.data.len()];
         
-        for partition in &partitions {
-            for (i, _) in partition.iter().enumerate() {
-                let value = self.apply_window_function(partition, i)?;
-                window_values.push(value);
+        for partition in &mut partitions {
+            if !columns.order_by.is_empty() {
+                partition.sort_by(|&a, &b| self.compare_rows(&input.data[a], &input.data[b], &columns));
+            }
+            
+            let rows: Vec<&Row> = partition.iter().map(|&i| &input.data[i]).collect();
+            let values = self.apply_window_function(&rows, &columns)?;
+            
+            for (&row, value) in partition.iter().zip(values) {
+                window_values[row] = value;
             }
         }
         
-        // Create output rows
-        let mut value_idx = 0;
-        for row in &input.data {
+        // Create output rows in input order
+        for (row, value) in input.data.iter().zip(window_values) {
             let mut values = row.values.clone();
-            values.push(window_values[value_idx].clone());
-            value_idx += 1;
+            values.push(value);
             
-            let output_row = Row::new(values);
-            result.add_row(output_row)?;
+            result.add_row(Row::new(values))?;
+        }
+        
+        // Sums promoted past i64 hold floats
+        if self.function_type == WindowFunctionType::Sum {
+            let last = result.schema.fields.len() - 1;
+            let data_type = promoted_type(&result.schema.fields[last].data_type, result.data.iter().map(|row| &row.values[last]));
+            result.schema.fields[last].data_type = data_type;
         }
         
         // Copy metadata
@@ -503,5 +575,22 @@ impl DataProcessor for WindowProcessor {
     fn processor_type(&self) -> ProcessorType {
         ProcessorType::Window
     }
+    
+    fn to_config(&self) -> Result<JsonValue, ProcessingError> {
+        if let WindowFunctionType::Custom(_) = self.function_type {
+            return Err(ProcessingError::NotSupported(
+                format!("Window column '{}' uses a custom function and has no configuration", self.output_column)
+            ));
+        }
+        
+        Ok(StepSpec::Window {
+            output: self.output_column.clone(),
+            function: self.function_type.name().to_string(),
+            value_column: self.value_column.clone(),
+            partition_by: self.partition_by.clone(),
+            order_by: self.order_by.clone(),
+            args: self.function_args.iter().map(JsonSink::value_to_json).collect(),
+            overflow: self.overflow,
+        }.to_config())
+    }
 }
-
@@ -0,0 +1,289 @@
+// Backup and restore of whole storages
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::{MsgPackSink, MsgPackSource};
+use super::{DataStorage, StorageError, ViewDefinition};
+
+/// Version of the backup layout written
+const BACKUP_VERSION: u32 = 1;
+
+/// Name of the manifest entry, written after every dataset
+const MANIFEST_ENTRY: &str = "manifest.json";
+
+/// Size of tar headers and of the blocks entries are padded to
+const BLOCK_SIZE: usize = 512;
+
+/// Dataset recorded in a backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupDataset {
+    pub name: String,
+    /// Archive entry holding the schema and rows, as MessagePack
+    pub entry: String,
+    pub rows: usize,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub immutable: bool,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// Contents of a backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub version: u32,
+    /// RFC 3339 time the backup was taken
+    pub created_at: String,
+    pub datasets: Vec<BackupDataset>,
+    /// Aliases and the datasets they point at
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+    /// Views and their definitions, which hold no rows of their own
+    #[serde(default)]
+    pub views: BTreeMap<String, ViewDefinition>,
+}
+
+/// Archive every dataset of a storage into a tar archive
+///
+/// Each dataset is an entry of MessagePack keeping its schema and rows; a
+/// final `manifest.json` entry records their names, tags, metadata and
+/// immutability flags along with the storage's aliases and views. Datasets are read
+/// one at a time, so a backup never holds more than one in memory.
+pub fn backup_storage<W: Write>(storage: &dyn DataStorage, mut writer: W) -> Result<BackupManifest, StorageError> {
+    let mut names = storage.list()?;
+    names.sort();
+    
+    let mut datasets = Vec::with_capacity(names.len());
+    
+    for (i, name) in names.into_iter().enumerate() {
+        let data = storage.load(&name)?;
+        let entry = format!("datasets/{:06}.msgpack", i);
+        
+        write_entry(&mut writer, &entry, &MsgPackSink::to_bytes(&data))?;
+        
+        datasets.push(BackupDataset {
+            rows: data.len(),
+            tags: storage.tags(&name)?,
+            immutable: storage.is_immutable(&name)?,
+            metadata: data.metadata.properties.into_iter().collect(),
+            name,
+            entry,
+        });
+    }
+    
+    let manifest = BackupManifest {
+        version: BACKUP_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        datasets,
+        aliases: storage.aliases()?.into_iter().collect(),
+        views: storage.views()?.into_iter().collect(),
+    };
+    
+    let bytes = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| StorageError::Other(e.to_string()))?;
+    write_entry(&mut writer, MANIFEST_ENTRY, &bytes)?;
+    
+    // The end of a tar archive is marked by two empty blocks
+    writer.write_all(&[0; 2 * BLOCK_SIZE])?;
+    writer.flush()?;
+    
+    Ok(manifest)
+}
+
+/// Restore the datasets of a backup into a storage
+///
+/// Fails before writing anything if a dataset or alias of the backup
+/// already exists, unless `overwrite` is set. Tags, aliases, views and
+/// immutability flags the storage does not support are logged and skipped,
+/// so backups can be restored into simpler backends.
+pub fn restore_storage<R: Read>(storage: &dyn DataStorage, mut reader: R, overwrite: bool) -> Result<BackupManifest, StorageError> {
+    let mut archive = Vec::new();
+    reader.read_to_end(&mut archive)?;
+    
+    let entries = read_entries(&archive)?;
+    let entry = |name: &str| entries.get(name).copied().ok_or_else(|| StorageError::InvalidFormat(
+        format!("Backup has no entry '{}'", name)
+    ));
+    
+    let manifest: BackupManifest = serde_json::from_slice(entry(MANIFEST_ENTRY)?)
+        .map_err(|e| StorageError::InvalidFormat(format!("Invalid backup manifest: {}", e)))?;
+    
+    if manifest.version > BACKUP_VERSION {
+        return Err(StorageError::InvalidFormat(format!(
+            "Backup version {} is newer than the supported version {}", manifest.version, BACKUP_VERSION
+        )));
+    }
+    
+    if !overwrite {
+        let names = manifest.datasets.iter().map(|dataset| &dataset.name).chain(manifest.aliases.keys())
+            .chain(manifest.views.keys());
+        
+        for name in names {
+            if storage.exists(name)? {
+                return Err(StorageError::AlreadyExists(name.clone()));
+            }
+        }
+    }
+    
+    for dataset in &manifest.datasets {
+        let mut data = MsgPackSource::new(&dataset.entry).read_from(entry(&dataset.entry)?)?;
+        data.metadata.properties = dataset.metadata.clone().into_iter().collect();
+        
+        storage.store(&dataset.name, &data)?;
+        
+        if !dataset.tags.is_empty() {
+            if let Err(err) = storage.set_tags(&dataset.name, &dataset.tags) {
+                log::warn!("Cannot restore tags of '{}': {}", dataset.name, err);
+            }
+        }
+    }
+    
+    for (alias, target) in &manifest.aliases {
+        if let Err(err) = storage.create_alias(alias, target) {
+            log::warn!("Cannot restore alias '{}': {}", alias, err);
+        }
+    }
+    
+    // Views may read other views, so retry until no more can be created
+    let mut pending: Vec<(&String, &ViewDefinition)> = manifest.views.iter().collect();
+    
+    loop {
+        let before = pending.len();
+        let mut failed = Vec::new();
+        
+        for (name, view) in pending {
+            if let Err(err) = storage.create_view(name, view) {
+                failed.push((name, view, err));
+            }
+        }
+        
+        if failed.len() == before {
+            for (name, _, err) in failed {
+                log::warn!("Cannot restore view '{}': {}", name, err);
+            }
+            break;
+        }
+        
+        pending = failed.into_iter().map(|(name, view, _)| (name, view)).collect();
+    }
+    
+    // Protect datasets last, once nothing else is written to them
+    for dataset in manifest.datasets.iter().filter(|dataset| dataset.immutable) {
+        if let Err(err) = storage.set_immutable(&dataset.name, true) {
+            log::warn!("Cannot restore immutability of '{}': {}", dataset.name, err);
+        }
+    }
+    
+    Ok(manifest)
+}
+
+/// Write a file entry of a tar archive, padded to whole blocks
+fn write_entry<W: Write>(writer: &mut W, path: &str, bytes: &[u8]) -> Result<(), StorageError> {
+    if path.len() > 100 {
+        return Err(StorageError::Other(format!("Archive entry name too long: {}", path)));
+    }
+    
+    // Sizes are 11 octal digits
+    if bytes.len() as u64 >= 1 << 33 {
+        return Err(StorageError::Other(format!("Archive entry too large: {}", path)));
+    }
+    
+    let mut header = [0u8; BLOCK_SIZE];
+    header[..path.len()].copy_from_slice(path.as_bytes());
+    write_octal(&mut header[100..108], 0o644);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], bytes.len() as u64);
+    write_octal(&mut header[136..148], chrono::Utc::now().timestamp().max(0) as u64);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    
+    // The checksum is computed with its own field taken as spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    
+    writer.write_all(&header)?;
+    writer.write_all(bytes)?;
+    writer.write_all(&vec![0; padding(bytes.len())])?;
+    
+    Ok(())
+}
+
+/// Read the file entries of a tar archive by name
+fn read_entries(archive: &[u8]) -> Result<HashMap<String, &[u8]>, StorageError> {
+    let invalid = |msg: &str| StorageError::InvalidFormat(format!("Invalid backup archive: {}", msg));
+    
+    let mut entries = HashMap::new();
+    let mut pos = 0;
+    
+    while pos + BLOCK_SIZE <= archive.len() {
+        let header = &archive[pos..pos + BLOCK_SIZE];
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        
+        let stored_checksum = parse_octal(&header[148..156]).ok_or_else(|| invalid("bad header checksum"))?;
+        let checksum: u64 = header.iter().enumerate()
+            .map(|(i, &b)| if (148..156).contains(&i) { b' ' as u64 } else { b as u64 })
+            .sum();
+        if checksum != stored_checksum {
+            return Err(invalid("header checksum mismatch"));
+        }
+        
+        let size = parse_octal(&header[124..136]).ok_or_else(|| invalid("bad entry size"))? as usize;
+        let start = pos + BLOCK_SIZE;
+        let end = start.checked_add(size).filter(|&end| end <= archive.len())
+            .ok_or_else(|| invalid("truncated entry"))?;
+        
+        // Keep regular files only, with the ustar prefix of long names
+        if header[156] == b'0' || header[156] == 0 {
+            let mut name = null_terminated(&header[..100]);
+            let prefix = null_terminated(&header[345..500]);
+            if &header[257..262] == b"ustar" && !prefix.is_empty() {
+                name = format!("{}/{}", prefix, name);
+            }
+            
+            entries.insert(name, &archive[start..end]);
+        }
+        
+        pos = end + padding(size);
+    }
+    
+    Ok(entries)
+}
+
+/// Write a number as zero-padded octal digits followed by a NUL
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}\0", value, width = field.len() - 1);
+    field.copy_from_slice(digits.as_bytes());
+}
+
+/// Parse an octal header field, ignoring surrounding spaces and NULs
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let text = std::str::from_utf8(field).ok()?;
+    let text = text.trim_matches(|c: char| c == ' ' || c == '\0');
+    
+    if text.is_empty() {
+        return Some(0);
+    }
+    
+    u64::from_str_radix(text, 8).ok()
+}
+
+/// Get the text of a NUL-terminated header field
+fn null_terminated(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Get the padding bringing an entry of a size to whole blocks
+fn padding(size: usize) -> usize {
+    (BLOCK_SIZE - size % BLOCK_SIZE) % BLOCK_SIZE
+}
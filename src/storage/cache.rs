@@ -5,8 +5,8 @@ use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 
-use crate::data::DataSet;
-use super::{DataStorage, StorageError};
+use crate::data::{ColumnRange, DataSet};
+use super::{CachedDataset, DatasetInfo, DataStorage, MaintenanceReport, StorageError, StorageStatus};
 
 /// Cache entry with expiration
 struct CacheEntry {
@@ -148,9 +148,148 @@ impl DataStorage for CacheStorage {
         Ok(())
     }
     
+    fn copy(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        // Copy in backend; the copy is cached when first loaded
+        self.backend.copy(from, to)
+    }
+    
+    fn rename(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        // Rename in backend
+        self.backend.rename(from, to)?;
+        
+        // Move the cache entry to the new name
+        let mut cache = self.cache.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        if let Some(entry) = cache.remove(from) {
+            cache.insert(to.to_string(), entry);
+        }
+        
+        Ok(())
+    }
+    
     fn list(&self) -> Result<Vec<String>, StorageError> {
         // Just delegate to backend
         self.backend.list()
     }
+    
+    fn load_head(&self, name: &str, rows: usize) -> Result<DataSet, StorageError> {
+        // Serve from cache when the full dataset is already cached
+        if let Ok(cache) = self.cache.read() {
+            if let Some(entry) = cache.get(name) {
+                if entry.expires_at.map_or(true, |expires| expires > Instant::now()) {
+                    let mut data = DataSet::new(entry.data.schema.clone());
+                    data.data = entry.data.data.iter().take(rows).cloned().collect();
+                    data.metadata = entry.data.metadata.clone();
+                    return Ok(data);
+                }
+            }
+        }
+        
+        self.backend.load_head(name, rows)
+    }
+    
+    fn load_range(&self, name: &str, offset: usize, limit: usize) -> Result<DataSet, StorageError> {
+        // Serve from cache when the full dataset is already cached
+        if let Ok(cache) = self.cache.read() {
+            if let Some(entry) = cache.get(name) {
+                if entry.expires_at.map_or(true, |expires| expires > Instant::now()) {
+                    let mut data = DataSet::new(entry.data.schema.clone());
+                    data.data = entry.data.data.iter().skip(offset).take(limit).cloned().collect();
+                    data.metadata = entry.data.metadata.clone();
+                    return Ok(data);
+                }
+            }
+        }
+        
+        self.backend.load_range(name, offset, limit)
+    }
+    
+    fn load_columns(&self, name: &str, columns: &[String]) -> Result<DataSet, StorageError> {
+        // Serve from cache when the full dataset is already cached
+        if let Ok(cache) = self.cache.read() {
+            if let Some(entry) = cache.get(name) {
+                if entry.expires_at.map_or(true, |expires| expires > Instant::now()) {
+                    return super::project_columns(&entry.data, columns);
+                }
+            }
+        }
+        
+        self.backend.load_columns(name, columns)
+    }
+    
+    fn load_pruned(&self, name: &str, ranges: &[ColumnRange]) -> Result<DataSet, StorageError> {
+        // Serve from cache when the full dataset is already cached
+        if let Ok(cache) = self.cache.read() {
+            if let Some(entry) = cache.get(name) {
+                if entry.expires_at.map_or(true, |expires| expires > Instant::now()) {
+                    return Ok(entry.data.clone());
+                }
+            }
+        }
+        
+        self.backend.load_pruned(name, ranges)
+    }
+    
+    fn info(&self, name: &str) -> Result<DatasetInfo, StorageError> {
+        // Delegate to backend so file-backed storage avoids a full load
+        self.backend.info(name)
+    }
+    
+    fn maintain(&self, report: &mut MaintenanceReport) -> Result<(), StorageError> {
+        let before = self.cache.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?.len();
+        
+        self.clear_expired()?;
+        
+        let after = self.cache.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?.len();
+        
+        report.cache_evicted += before.saturating_sub(after);
+        self.backend.maintain(report)
+    }
+    
+    fn describe(&self, status: &mut StorageStatus) -> Result<(), StorageError> {
+        status.layers.push("cache".to_string());
+        
+        {
+            let cache = self.cache.read().map_err(|_| {
+                StorageError::Other("Failed to acquire read lock".to_string())
+            })?;
+            
+            let now = Instant::now();
+            let mut cached: Vec<CachedDataset> = cache.iter()
+                .filter(|(_, entry)| entry.expires_at.map_or(true, |expires| expires > now))
+                .map(|(name, entry)| CachedDataset {
+                    name: name.clone(),
+                    rows: entry.data.len(),
+                    columns: entry.data.schema.fields.len(),
+                    expires_in: entry.expires_at.map(|expires| expires - now),
+                })
+                .collect();
+            cached.sort_by(|a, b| a.name.cmp(&b.name));
+            
+            status.cached.extend(cached);
+        }
+        
+        self.backend.describe(status)
+    }
+    
+    fn clear_cache(&self) -> Result<usize, StorageError> {
+        let cleared = {
+            let mut cache = self.cache.write().map_err(|_| {
+                StorageError::Other("Failed to acquire write lock".to_string())
+            })?;
+            
+            let cleared = cache.len();
+            cache.clear();
+            cleared
+        };
+        
+        Ok(cleared + self.backend.clear_cache()?)
+    }
 }
 
@@ -0,0 +1,715 @@
+// Dataset aliases, views, soft deletion, tags and column lineage
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::{ColumnRange, DataSet, LineageStep};
+use super::{DatasetInfo, DataStorage, MaintenanceReport, StorageError, StorageStatus, ViewDefinition, project_columns};
+
+/// Prefix of backend names holding deleted datasets
+const TRASH_PREFIX: &str = "__trash__.";
+
+/// A deleted dataset that can still be restored
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    pub name: String,
+    pub deleted_at: SystemTime,
+    pub expires_at: Option<SystemTime>,
+}
+
+/// Trash record kept in the catalog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TrashRecord {
+    stored_as: String,
+    deleted_at: u64,
+}
+
+/// Aliases, views, trash, tags and lineage persisted by the catalog
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CatalogState {
+    aliases: BTreeMap<String, String>,
+    #[serde(default)]
+    views: BTreeMap<String, ViewDefinition>,
+    trash: BTreeMap<String, TrashRecord>,
+    #[serde(default)]
+    tags: BTreeMap<String, BTreeSet<String>>,
+    #[serde(default)]
+    lineage: BTreeMap<String, BTreeMap<String, Vec<LineageStep>>>,
+}
+
+/// Storage wrapper adding dataset aliases, views, soft deletion, tags and column lineage
+///
+/// Aliases are stable names resolving to a concrete dataset for reads and
+/// writes; deleting an alias removes only the alias. Views are read-only
+/// names computed from other datasets each time they are read; copying a
+/// view stores its rows as a dataset. Deleting a dataset moves
+/// it to the trash, where it can be restored until the retention period has
+/// passed or it is purged. Tags stay with a dataset through the trash and
+/// renames, and are dropped when it is purged.
+///
+/// The lineage in a stored dataset's metadata is kept like its tags, and is
+/// put back into the metadata of datasets loaded, so pipelines reading a
+/// dataset extend the history of its columns.
+pub struct CatalogStorage {
+    backend: Arc<dyn DataStorage + Send + Sync>,
+    state: RwLock<CatalogState>,
+    catalog_file: Option<PathBuf>,
+    retention: Option<Duration>,
+}
+
+impl CatalogStorage {
+    /// Create a new catalog storage around a backend
+    pub fn new(backend: Arc<dyn DataStorage + Send + Sync>) -> Self {
+        CatalogStorage {
+            backend,
+            state: RwLock::new(CatalogState::default()),
+            catalog_file: None,
+            retention: None,
+        }
+    }
+    
+    /// Purge deleted datasets after the given retention period
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+    
+    /// Persist aliases, views, trash, tags and lineage to a JSON file, loading any existing catalog
+    pub fn with_catalog_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self, StorageError> {
+        let path = path.as_ref().to_path_buf();
+        
+        if path.exists() {
+            let reader = BufReader::new(File::open(&path)?);
+            let state: CatalogState = serde_json::from_reader(reader)
+                .map_err(|e| StorageError::InvalidFormat(e.to_string()))?;
+            
+            self.state = RwLock::new(state);
+        }
+        
+        self.catalog_file = Some(path);
+        Ok(self)
+    }
+    
+    /// Resolve an alias to the dataset it points at
+    fn resolve(&self, name: &str) -> Result<String, StorageError> {
+        let state = self.state.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        Ok(state.aliases.get(name).cloned().unwrap_or_else(|| name.to_string()))
+    }
+    
+    /// Get the definition of a view
+    fn view(&self, name: &str) -> Result<Option<ViewDefinition>, StorageError> {
+        let state = self.state.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        Ok(state.views.get(name).cloned())
+    }
+    
+    /// Compute the rows of a view, reading its inputs through the catalog
+    fn load_view(&self, name: &str, view: &ViewDefinition) -> Result<DataSet, StorageError> {
+        view.evaluate(name, &|input| self.load(input))
+    }
+    
+    /// Put the recorded lineage of a dataset into its metadata
+    fn with_lineage(&self, name: &str, mut data: DataSet) -> Result<DataSet, StorageError> {
+        let state = self.state.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        if let Some(lineage) = state.lineage.get(name) {
+            data.metadata.set_column_lineage(lineage);
+        }
+        
+        Ok(data)
+    }
+    
+    /// Check that a name can be used as a copy or rename target
+    fn check_name(&self, name: &str) -> Result<(), StorageError> {
+        if name.starts_with(TRASH_PREFIX) {
+            return Err(StorageError::Other(
+                format!("Dataset names cannot start with '{}'", TRASH_PREFIX)
+            ));
+        }
+        
+        let state = self.state.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        if state.aliases.contains_key(name) || state.views.contains_key(name) {
+            return Err(StorageError::AlreadyExists(name.to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    /// Get the current time in seconds since the epoch
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+    
+    /// Purge trash entries older than the retention period
+    ///
+    /// Entries that fail to purge are kept and retried on the next call.
+    /// Returns the number of purged datasets and the bytes reclaimed.
+    fn purge_expired(&self, state: &mut CatalogState) -> (usize, u64) {
+        let retention = match self.retention {
+            Some(retention) => retention.as_secs(),
+            None => return (0, 0),
+        };
+        
+        let now = Self::now();
+        let expired: Vec<String> = state.trash.iter()
+            .filter(|(_, record)| record.deleted_at + retention <= now)
+            .map(|(name, _)| name.clone())
+            .collect();
+        
+        let (mut purged, mut bytes) = (0, 0);
+        
+        for name in expired {
+            let stored_as = &state.trash[&name].stored_as;
+            let size = self.backend.info(stored_as).ok().and_then(|info| info.size_bytes);
+            
+            match self.backend.delete(stored_as) {
+                Ok(()) => {
+                    state.trash.remove(&name);
+                    state.tags.remove(&name);
+                    state.lineage.remove(&name);
+                    purged += 1;
+                    bytes += size.unwrap_or(0);
+                },
+                Err(err) => log::warn!("Failed to purge '{}' from trash: {}", name, err),
+            }
+        }
+        
+        (purged, bytes)
+    }
+    
+    /// Write the catalog to the catalog file, if any
+    fn save(&self, state: &CatalogState) -> Result<(), StorageError> {
+        if let Some(path) = &self.catalog_file {
+            let writer = BufWriter::new(File::create(path)?);
+            serde_json::to_writer_pretty(writer, state)
+                .map_err(|e| StorageError::Other(e.to_string()))?;
+        }
+        
+        Ok(())
+    }
+}
+
+impl DataStorage for CatalogStorage {
+    fn store(&self, name: &str, data: &DataSet) -> Result<(), StorageError> {
+        if name.starts_with(TRASH_PREFIX) {
+            return Err(StorageError::Other(
+                format!("Dataset names cannot start with '{}'", TRASH_PREFIX)
+            ));
+        }
+        
+        if self.view(name)?.is_some() {
+            return Err(StorageError::ReadOnly(format!("'{}' is a view", name)));
+        }
+        
+        // Writes through an alias update the dataset it points at
+        let name = self.resolve(name)?;
+        self.backend.store(&name, data)?;
+        
+        let mut state = self.state.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        // Keep the lineage of the columns the dataset still has
+        let lineage = data.metadata.column_lineage(&data.schema);
+        let changed = if lineage.is_empty() {
+            state.lineage.remove(&name).is_some()
+        } else {
+            state.lineage.insert(name, lineage.clone()) != Some(lineage)
+        };
+        
+        if changed {
+            self.save(&state)?;
+        }
+        
+        Ok(())
+    }
+    
+    fn load(&self, name: &str) -> Result<DataSet, StorageError> {
+        if let Some(view) = self.view(name)? {
+            return self.load_view(name, &view);
+        }
+        
+        let name = self.resolve(name)?;
+        self.with_lineage(&name, self.backend.load(&name)?)
+    }
+    
+    fn exists(&self, name: &str) -> Result<bool, StorageError> {
+        if self.view(name)?.is_some() {
+            return Ok(true);
+        }
+        
+        self.backend.exists(&self.resolve(name)?)
+    }
+    
+    fn delete(&self, name: &str) -> Result<(), StorageError> {
+        let mut state = self.state.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        // Deleting an alias or a view leaves its datasets alone
+        if state.aliases.remove(name).is_some() || state.views.remove(name).is_some() {
+            return self.save(&state);
+        }
+        
+        if !self.backend.exists(name)? {
+            return Err(StorageError::NotFound(name.to_string()));
+        }
+        
+        // Move the dataset to the trash, replacing an older deleted copy
+        let stored_as = format!("{}{}", TRASH_PREFIX, name);
+        if state.trash.contains_key(name) {
+            self.backend.delete(&stored_as)?;
+            state.trash.remove(name);
+        }
+        
+        // Renamed rather than rewritten, so files keep their format and are not decoded
+        self.backend.rename(name, &stored_as)?;
+        
+        state.trash.insert(name.to_string(), TrashRecord {
+            stored_as,
+            deleted_at: Self::now(),
+        });
+        
+        self.purge_expired(&mut state);
+        self.save(&state)
+    }
+    
+    fn copy(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.check_name(to)?;
+        
+        // Copies of a view hold its current rows
+        if let Some(view) = self.view(from)? {
+            if self.backend.exists(to)? {
+                return Err(StorageError::AlreadyExists(to.to_string()));
+            }
+            
+            return self.store(to, &self.load_view(from, &view)?);
+        }
+        
+        let from = self.resolve(from)?;
+        self.backend.copy(&from, to)?;
+        
+        let mut state = self.state.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        // Copies have the same columns, so the same lineage
+        match state.lineage.get(&from).cloned() {
+            Some(lineage) => state.lineage.insert(to.to_string(), lineage),
+            None => state.lineage.remove(to),
+        };
+        
+        self.save(&state)
+    }
+    
+    fn rename(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.check_name(to)?;
+        
+        let mut state = self.state.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        // Renaming an alias or a view renames only the alias or view
+        if let Some(target) = state.aliases.remove(from) {
+            state.aliases.insert(to.to_string(), target);
+            return self.save(&state);
+        }
+        
+        if let Some(view) = state.views.remove(from) {
+            state.views.insert(to.to_string(), view);
+            return self.save(&state);
+        }
+        
+        self.backend.rename(from, to)?;
+        
+        if let Some(tags) = state.tags.remove(from) {
+            state.tags.insert(to.to_string(), tags);
+        }
+        
+        if let Some(lineage) = state.lineage.remove(from) {
+            state.lineage.insert(to.to_string(), lineage);
+        }
+        
+        // Keep aliases pointing at the renamed dataset, and views reading it
+        for target in state.aliases.values_mut() {
+            if target == from {
+                *target = to.to_string();
+            }
+        }
+        
+        for view in state.views.values_mut() {
+            let inputs = std::iter::once(&mut view.source).chain(view.join.as_mut().map(|join| &mut join.dataset));
+            
+            for input in inputs.filter(|input| *input == from) {
+                *input = to.to_string();
+            }
+        }
+        
+        self.save(&state)
+    }
+    
+    fn list(&self) -> Result<Vec<String>, StorageError> {
+        Ok(self.backend.list()?
+            .into_iter()
+            .filter(|name| !name.starts_with(TRASH_PREFIX))
+            .collect())
+    }
+    
+    fn load_head(&self, name: &str, rows: usize) -> Result<DataSet, StorageError> {
+        if let Some(view) = self.view(name)? {
+            let mut data = self.load_view(name, &view)?;
+            data.data.truncate(rows);
+            return Ok(data);
+        }
+        
+        let name = self.resolve(name)?;
+        self.with_lineage(&name, self.backend.load_head(&name, rows)?)
+    }
+    
+    fn load_range(&self, name: &str, offset: usize, limit: usize) -> Result<DataSet, StorageError> {
+        if let Some(view) = self.view(name)? {
+            let mut data = self.load_view(name, &view)?;
+            data.data = data.data.into_iter().skip(offset).take(limit).collect();
+            return Ok(data);
+        }
+        
+        let name = self.resolve(name)?;
+        self.with_lineage(&name, self.backend.load_range(&name, offset, limit)?)
+    }
+    
+    fn load_columns(&self, name: &str, columns: &[String]) -> Result<DataSet, StorageError> {
+        if let Some(view) = self.view(name)? {
+            return project_columns(&self.load_view(name, &view)?, columns);
+        }
+        
+        let name = self.resolve(name)?;
+        self.with_lineage(&name, self.backend.load_columns(&name, columns)?)
+    }
+    
+    fn load_pruned(&self, name: &str, ranges: &[ColumnRange]) -> Result<DataSet, StorageError> {
+        if let Some(view) = self.view(name)? {
+            return self.load_view(name, &view);
+        }
+        
+        let name = self.resolve(name)?;
+        self.with_lineage(&name, self.backend.load_pruned(&name, ranges)?)
+    }
+    
+    fn info(&self, name: &str) -> Result<DatasetInfo, StorageError> {
+        if let Some(view) = self.view(name)? {
+            let data = self.load_view(name, &view)?;
+            
+            return Ok(DatasetInfo {
+                rows: data.len(),
+                columns: data.schema.fields.len(),
+                size_bytes: None,
+                format: "view".to_string(),
+                last_modified: None,
+            });
+        }
+        
+        self.backend.info(&self.resolve(name)?)
+    }
+    
+    fn is_immutable(&self, name: &str) -> Result<bool, StorageError> {
+        self.backend.is_immutable(&self.resolve(name)?)
+    }
+    
+    fn set_immutable(&self, name: &str, immutable: bool) -> Result<(), StorageError> {
+        self.backend.set_immutable(&self.resolve(name)?, immutable)
+    }
+    
+    fn aliases(&self) -> Result<Vec<(String, String)>, StorageError> {
+        let state = self.state.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        Ok(state.aliases.iter()
+            .map(|(alias, target)| (alias.clone(), target.clone()))
+            .collect())
+    }
+    
+    fn create_alias(&self, alias: &str, target: &str) -> Result<(), StorageError> {
+        let mut state = self.state.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        if state.aliases.contains_key(target) {
+            return Err(StorageError::Other(
+                format!("Alias target '{}' is itself an alias", target)
+            ));
+        }
+        
+        if !self.backend.exists(target)? {
+            return Err(StorageError::NotFound(target.to_string()));
+        }
+        
+        if self.backend.exists(alias)? || state.views.contains_key(alias) {
+            return Err(StorageError::AlreadyExists(alias.to_string()));
+        }
+        
+        // Creating an existing alias repoints it
+        state.aliases.insert(alias.to_string(), target.to_string());
+        self.save(&state)
+    }
+    
+    fn remove_alias(&self, alias: &str) -> Result<(), StorageError> {
+        let mut state = self.state.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        if state.aliases.remove(alias).is_none() {
+            return Err(StorageError::NotFound(alias.to_string()));
+        }
+        
+        self.save(&state)
+    }
+    
+    fn views(&self) -> Result<Vec<(String, ViewDefinition)>, StorageError> {
+        let state = self.state.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        Ok(state.views.iter()
+            .map(|(name, view)| (name.clone(), view.clone()))
+            .collect())
+    }
+    
+    fn create_view(&self, name: &str, view: &ViewDefinition) -> Result<(), StorageError> {
+        if name.starts_with(TRASH_PREFIX) {
+            return Err(StorageError::Other(
+                format!("Dataset names cannot start with '{}'", TRASH_PREFIX)
+            ));
+        }
+        
+        view.pipeline(name)?;
+        
+        let mut state = self.state.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        if state.aliases.contains_key(name) || self.backend.exists(name)? {
+            return Err(StorageError::AlreadyExists(name.to_string()));
+        }
+        
+        // Views may read other views, but never themselves
+        let mut pending: Vec<String> = view.inputs().into_iter().map(str::to_string).collect();
+        let mut seen = BTreeSet::new();
+        
+        while let Some(input) = pending.pop() {
+            if input == name {
+                return Err(StorageError::InvalidFormat(format!("View '{}' would read itself", name)));
+            }
+            
+            if !seen.insert(input.clone()) {
+                continue;
+            }
+            
+            match state.views.get(&input) {
+                Some(other) => pending.extend(other.inputs().into_iter().map(str::to_string)),
+                None => {
+                    let target = state.aliases.get(&input).unwrap_or(&input);
+                    if !self.backend.exists(target)? {
+                        return Err(StorageError::NotFound(input));
+                    }
+                },
+            }
+        }
+        
+        // Creating an existing view redefines it
+        state.views.insert(name.to_string(), view.clone());
+        self.save(&state)
+    }
+    
+    fn remove_view(&self, name: &str) -> Result<(), StorageError> {
+        let mut state = self.state.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        if state.views.remove(name).is_none() {
+            return Err(StorageError::NotFound(name.to_string()));
+        }
+        
+        self.save(&state)
+    }
+    
+    fn tags(&self, name: &str) -> Result<Vec<String>, StorageError> {
+        let name = self.resolve(name)?;
+        
+        let state = self.state.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        Ok(state.tags.get(&name)
+            .map(|tags| tags.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+    
+    fn set_tags(&self, name: &str, tags: &[String]) -> Result<(), StorageError> {
+        let name = self.resolve(name)?;
+        
+        if !self.backend.exists(&name)? {
+            return Err(StorageError::NotFound(name));
+        }
+        
+        let mut state = self.state.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        let tags: BTreeSet<String> = tags.iter()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        
+        if tags.is_empty() {
+            state.tags.remove(&name);
+        } else {
+            state.tags.insert(name, tags);
+        }
+        
+        self.save(&state)
+    }
+    
+    fn lineage(&self, name: &str) -> Result<BTreeMap<String, Vec<LineageStep>>, StorageError> {
+        let name = self.resolve(name)?;
+        
+        let state = self.state.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        Ok(state.lineage.get(&name).cloned().unwrap_or_default())
+    }
+    
+    fn trash(&self) -> Result<Vec<TrashEntry>, StorageError> {
+        let mut state = self.state.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        self.purge_expired(&mut state);
+        
+        Ok(state.trash.iter()
+            .map(|(name, record)| {
+                let deleted_at = UNIX_EPOCH + Duration::from_secs(record.deleted_at);
+                
+                TrashEntry {
+                    name: name.clone(),
+                    deleted_at,
+                    expires_at: self.retention.map(|retention| deleted_at + retention),
+                }
+            })
+            .collect())
+    }
+    
+    fn restore(&self, name: &str) -> Result<(), StorageError> {
+        let mut state = self.state.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        self.purge_expired(&mut state);
+        
+        let stored_as = match state.trash.get(name) {
+            Some(record) => record.stored_as.clone(),
+            None => return Err(StorageError::NotFound(name.to_string())),
+        };
+        
+        if self.backend.exists(name)? {
+            return Err(StorageError::AlreadyExists(name.to_string()));
+        }
+        
+        self.backend.rename(&stored_as, name)?;
+        
+        state.trash.remove(name);
+        self.save(&state)
+    }
+    
+    fn purge(&self, name: Option<&str>) -> Result<usize, StorageError> {
+        let mut state = self.state.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        let names: Vec<String> = match name {
+            Some(name) if state.trash.contains_key(name) => vec![name.to_string()],
+            Some(name) => return Err(StorageError::NotFound(name.to_string())),
+            None => state.trash.keys().cloned().collect(),
+        };
+        
+        for name in &names {
+            self.backend.delete(&state.trash[name].stored_as)?;
+            state.trash.remove(name);
+            state.tags.remove(name);
+            state.lineage.remove(name);
+        }
+        
+        self.save(&state)?;
+        Ok(names.len())
+    }
+    
+    fn maintain(&self, report: &mut MaintenanceReport) -> Result<(), StorageError> {
+        let mut state = self.state.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        let (purged, bytes) = self.purge_expired(&mut state);
+        report.trash_purged += purged;
+        report.bytes_reclaimed += bytes;
+        
+        // Drop aliases whose dataset is gone and can no longer be restored
+        let mut dangling = Vec::new();
+        for (alias, target) in &state.aliases {
+            if !state.trash.contains_key(target) && !self.backend.exists(target)? {
+                dangling.push(alias.clone());
+            }
+        }
+        
+        for alias in &dangling {
+            state.aliases.remove(alias);
+        }
+        report.aliases_removed += dangling.len();
+        
+        // Drop tags of datasets that were removed outside the catalog
+        let mut untagged = Vec::new();
+        for name in state.tags.keys() {
+            if !state.trash.contains_key(name) && !self.backend.exists(name)? {
+                untagged.push(name.clone());
+            }
+        }
+        
+        for name in &untagged {
+            state.tags.remove(name);
+        }
+        
+        self.save(&state)?;
+        drop(state);
+        
+        self.backend.maintain(report)
+    }
+    
+    fn describe(&self, status: &mut StorageStatus) -> Result<(), StorageError> {
+        status.layers.push("catalog".to_string());
+        self.backend.describe(status)
+    }
+    
+    fn clear_cache(&self) -> Result<usize, StorageError> {
+        self.backend.clear_cache()
+    }
+}
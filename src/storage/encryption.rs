@@ -0,0 +1,319 @@
+// Encryption at rest for stored datasets
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::StorageError;
+
+/// Magic bytes starting every encrypted file
+const MAGIC: &[u8; 8] = b"RDPEENC1";
+
+/// Length of the AES-GCM nonce in bytes
+const NONCE_LEN: usize = 12;
+
+/// A 256-bit AES key with an identifier recorded in every file it encrypts
+#[derive(Clone)]
+pub struct EncryptionKey {
+    id: String,
+    bytes: Vec<u8>,
+}
+
+impl EncryptionKey {
+    /// Create a new key from 32 raw bytes
+    pub fn new<S: Into<String>>(id: S, bytes: Vec<u8>) -> Result<Self, StorageError> {
+        let id = id.into();
+        
+        if bytes.len() != 32 {
+            return Err(StorageError::Other(
+                format!("Encryption key '{}' must be 32 bytes, got {}", id, bytes.len())
+            ));
+        }
+        
+        if id.is_empty() || id.len() > u8::MAX as usize {
+            return Err(StorageError::Other(
+                "Encryption key id must be between 1 and 255 bytes".to_string()
+            ));
+        }
+        
+        Ok(EncryptionKey { id, bytes })
+    }
+    
+    /// Create a new key from base64-encoded bytes
+    pub fn from_base64<S: Into<String>>(id: S, encoded: &str) -> Result<Self, StorageError> {
+        let bytes = base64::decode(encoded.trim())
+            .map_err(|e| StorageError::Other(format!("Invalid base64 key: {}", e)))?;
+        
+        Self::new(id, bytes)
+    }
+    
+    /// Get the key identifier
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // Never print key material
+        f.debug_struct("EncryptionKey").field("id", &self.id).finish()
+    }
+}
+
+/// Source of encryption keys
+///
+/// Implement this to fetch keys from a KMS; `CallbackKeyProvider` adapts a
+/// closure for the common case.
+pub trait KeyProvider: Send + Sync {
+    /// Get the key used for new writes
+    fn current_key(&self) -> Result<EncryptionKey, StorageError>;
+    
+    /// Get a key by id, to read files written before a rotation
+    fn key(&self, id: &str) -> Result<EncryptionKey, StorageError>;
+}
+
+/// Fixed set of keys: one current key plus retired keys kept for reading
+#[derive(Debug, Clone)]
+pub struct KeyRing {
+    current: String,
+    keys: HashMap<String, EncryptionKey>,
+}
+
+impl KeyRing {
+    /// Create a new key ring with a current key
+    pub fn new(current: EncryptionKey) -> Self {
+        let mut keys = HashMap::new();
+        let id = current.id.clone();
+        keys.insert(id.clone(), current);
+        
+        KeyRing { current: id, keys }
+    }
+    
+    /// Keep a retired key to read files it encrypted
+    pub fn with_key(mut self, key: EncryptionKey) -> Self {
+        self.keys.insert(key.id.clone(), key);
+        self
+    }
+    
+    /// Parse a key ring from `id:base64key` entries separated by commas
+    ///
+    /// The first entry is the current key; the others are retired keys.
+    pub fn parse(spec: &str) -> Result<Self, StorageError> {
+        let mut ring: Option<KeyRing> = None;
+        
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (id, encoded) = entry.split_once(':').ok_or_else(|| StorageError::Other(
+                "Key entries must have the form 'id:base64key'".to_string()
+            ))?;
+            let key = EncryptionKey::from_base64(id, encoded)?;
+            
+            ring = Some(match ring {
+                Some(ring) => ring.with_key(key),
+                None => KeyRing::new(key),
+            });
+        }
+        
+        ring.ok_or_else(|| StorageError::Other("No encryption keys given".to_string()))
+    }
+    
+    /// Read a key ring from an environment variable in the `parse` format
+    pub fn from_env(var: &str) -> Result<Self, StorageError> {
+        let spec = std::env::var(var).map_err(|_| StorageError::Other(
+            format!("Environment variable '{}' is not set", var)
+        ))?;
+        
+        Self::parse(&spec)
+    }
+}
+
+impl KeyProvider for KeyRing {
+    fn current_key(&self) -> Result<EncryptionKey, StorageError> {
+        self.key(&self.current)
+    }
+    
+    fn key(&self, id: &str) -> Result<EncryptionKey, StorageError> {
+        self.keys.get(id).cloned().ok_or_else(|| StorageError::Other(
+            format!("Unknown encryption key '{}'", id)
+        ))
+    }
+}
+
+/// Key provider calling a closure, such as a KMS client
+///
+/// The closure receives `None` for the current key and `Some(id)` for a
+/// specific key.
+pub struct CallbackKeyProvider<F>
+where
+    F: Fn(Option<&str>) -> Result<EncryptionKey, StorageError> + Send + Sync,
+{
+    callback: F,
+}
+
+impl<F> CallbackKeyProvider<F>
+where
+    F: Fn(Option<&str>) -> Result<EncryptionKey, StorageError> + Send + Sync,
+{
+    /// Create a new callback key provider
+    pub fn new(callback: F) -> Self {
+        CallbackKeyProvider { callback }
+    }
+}
+
+impl<F> KeyProvider for CallbackKeyProvider<F>
+where
+    F: Fn(Option<&str>) -> Result<EncryptionKey, StorageError> + Send + Sync,
+{
+    fn current_key(&self) -> Result<EncryptionKey, StorageError> {
+        (self.callback)(None)
+    }
+    
+    fn key(&self, id: &str) -> Result<EncryptionKey, StorageError> {
+        (self.callback)(Some(id))
+    }
+}
+
+/// AES-256-GCM encryption of file contents
+///
+/// Encrypted files start with a header holding the key id and nonce, so
+/// files written with older keys stay readable after a rotation.
+#[derive(Clone)]
+pub struct Encryptor {
+    provider: Arc<dyn KeyProvider>,
+}
+
+impl Encryptor {
+    /// Create a new encryptor using keys from a provider
+    pub fn new(provider: Arc<dyn KeyProvider>) -> Self {
+        Encryptor { provider }
+    }
+    
+    /// Check if data starts with the encrypted file header
+    pub fn is_encrypted(data: &[u8]) -> bool {
+        data.starts_with(MAGIC)
+    }
+    
+    /// Check if a file starts with the encrypted file header, reading only the header
+    pub fn is_encrypted_file(path: &Path) -> Result<bool, StorageError> {
+        let mut header = Vec::with_capacity(MAGIC.len());
+        File::open(path)?.take(MAGIC.len() as u64).read_to_end(&mut header)?;
+        
+        Ok(Self::is_encrypted(&header))
+    }
+    
+    /// Get the id of the key that encrypted the data
+    pub fn key_id(data: &[u8]) -> Option<String> {
+        Self::split_header(data).ok().map(|(id, _, _, _)| id.to_string())
+    }
+    
+    /// Get the id of the key used for new writes
+    pub fn current_key_id(&self) -> Result<String, StorageError> {
+        Ok(self.provider.current_key()?.id)
+    }
+    
+    /// Encrypt data with the current key
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, StorageError> {
+        self.encrypt_with_context(plaintext, &[])
+    }
+    
+    /// Encrypt data bound to a context, such as the column it belongs to
+    ///
+    /// The context is authenticated but not stored; decryption fails unless
+    /// the same context is given.
+    pub fn encrypt_with_context(&self, plaintext: &[u8], context: &[u8]) -> Result<Vec<u8>, StorageError> {
+        #[cfg(feature = "encryption")]
+        {
+            use aes_gcm::aead::{Aead, KeyInit, Payload};
+            use aes_gcm::{Aes256Gcm, Key, Nonce};
+            use rand::RngCore;
+            
+            let key = self.provider.current_key()?;
+            
+            let mut nonce = [0u8; NONCE_LEN];
+            rand::thread_rng().fill_bytes(&mut nonce);
+            
+            // The header is authenticated so the key id cannot be swapped
+            let mut output = Vec::with_capacity(MAGIC.len() + 1 + key.id.len() + NONCE_LEN + plaintext.len() + 16);
+            output.extend_from_slice(MAGIC);
+            output.push(key.id.len() as u8);
+            output.extend_from_slice(key.id.as_bytes());
+            
+            let mut aad = output.clone();
+            aad.extend_from_slice(context);
+            
+            let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key.bytes));
+            let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad: &aad })
+                .map_err(|_| StorageError::Other("Encryption failed".to_string()))?;
+            
+            output.extend_from_slice(&nonce);
+            output.extend_from_slice(&ciphertext);
+            
+            Ok(output)
+        }
+        
+        #[cfg(not(feature = "encryption"))]
+        {
+            let _ = (plaintext, context);
+            Err(StorageError::Other("Encryption support not enabled".to_string()))
+        }
+    }
+    
+    /// Decrypt data with the key recorded in its header
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, StorageError> {
+        self.decrypt_with_context(data, &[])
+    }
+    
+    /// Decrypt data encrypted with `encrypt_with_context` for the same context
+    pub fn decrypt_with_context(&self, data: &[u8], context: &[u8]) -> Result<Vec<u8>, StorageError> {
+        #[cfg(feature = "encryption")]
+        {
+            use aes_gcm::aead::{Aead, KeyInit, Payload};
+            use aes_gcm::{Aes256Gcm, Key, Nonce};
+            
+            let (id, header, nonce, ciphertext) = Self::split_header(data)?;
+            let key = self.provider.key(id)?;
+            
+            let mut aad = header.to_vec();
+            aad.extend_from_slice(context);
+            
+            let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key.bytes));
+            cipher.decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: &aad })
+                .map_err(|_| StorageError::InvalidFormat(
+                    "Decryption failed: wrong key or corrupted file".to_string()
+                ))
+        }
+        
+        #[cfg(not(feature = "encryption"))]
+        {
+            let _ = (data, context);
+            Err(StorageError::Other("Encryption support not enabled".to_string()))
+        }
+    }
+    
+    /// Split encrypted data into key id, header, nonce and ciphertext
+    fn split_header(data: &[u8]) -> Result<(&str, &[u8], &[u8], &[u8]), StorageError> {
+        let invalid = || StorageError::InvalidFormat("Invalid encrypted file header".to_string());
+        
+        if !Self::is_encrypted(data) || data.len() <= MAGIC.len() {
+            return Err(invalid());
+        }
+        
+        let id_len = data[MAGIC.len()] as usize;
+        let header_len = MAGIC.len() + 1 + id_len;
+        
+        if data.len() < header_len + NONCE_LEN {
+            return Err(invalid());
+        }
+        
+        let id = std::str::from_utf8(&data[MAGIC.len() + 1..header_len]).map_err(|_| invalid())?;
+        
+        Ok((
+            id,
+            &data[..header_len],
+            &data[header_len..header_len + NONCE_LEN],
+            &data[header_len + NONCE_LEN..],
+        ))
+    }
+}
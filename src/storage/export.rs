@@ -0,0 +1,298 @@
+// Snapshot and incremental export
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::data::{DataSet, DataSink, JsonSink, Value};
+use super::StorageError;
+
+/// Name of the manifest file in an export directory
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Delta file entry in an export manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeltaEntry {
+    pub sequence: u64,
+    pub file: String,
+    pub inserted: usize,
+    pub updated: usize,
+    pub deleted: usize,
+}
+
+/// Manifest describing the snapshot and deltas of an export directory
+///
+/// Consumers load the snapshot once, then apply the deltas in sequence
+/// order. Row fingerprints let the exporter detect changes on the next run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub dataset: String,
+    pub key_columns: Vec<String>,
+    pub snapshot: String,
+    pub deltas: Vec<DeltaEntry>,
+    fingerprints: HashMap<String, u64>,
+}
+
+/// Result of an export run
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportOutcome {
+    /// A full snapshot was written
+    Snapshot { file: String, rows: usize },
+    /// A delta file was written
+    Delta { file: String, inserted: usize, updated: usize, deleted: usize },
+    /// Nothing changed since the last export
+    Unchanged,
+}
+
+/// Export a dataset as a full snapshot followed by incremental delta files
+///
+/// Deltas are JSON arrays of Debezium-style change events (`op` of `c`, `u`
+/// or `d`), so they can be replayed with the change applier.
+pub struct IncrementalExporter {
+    dir: PathBuf,
+    key_columns: Vec<String>,
+}
+
+impl IncrementalExporter {
+    /// Create a new exporter writing to a directory
+    pub fn new<P: AsRef<Path>>(dir: P, key_columns: Vec<String>) -> Result<Self, StorageError> {
+        let dir = dir.as_ref().to_path_buf();
+        
+        // Create directory if it doesn't exist
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+        }
+        
+        Ok(IncrementalExporter { dir, key_columns })
+    }
+    
+    /// Load the manifest of the export directory, if any
+    pub fn manifest(&self) -> Result<Option<ExportManifest>, StorageError> {
+        let path = self.dir.join(MANIFEST_FILE);
+        
+        if !path.exists() {
+            return Ok(None);
+        }
+        
+        let reader = BufReader::new(File::open(path)?);
+        serde_json::from_reader(reader)
+            .map(Some)
+            .map_err(|e| StorageError::InvalidFormat(e.to_string()))
+    }
+    
+    /// Export the current state of a dataset
+    pub fn export(&self, name: &str, data: &DataSet) -> Result<ExportOutcome, StorageError> {
+        let fingerprints = self.fingerprint(data)?;
+        
+        let mut manifest = match self.manifest()? {
+            Some(manifest) if manifest.dataset != name => return Err(StorageError::InvalidFormat(format!(
+                "Directory holds an export of '{}'; start a new export directory", manifest.dataset
+            ))),
+            Some(manifest) if manifest.key_columns == self.key_columns => manifest,
+            Some(_) => return Err(StorageError::InvalidFormat(
+                "Key columns differ from the existing export; start a new export directory".to_string()
+            )),
+            None => {
+                // First run writes a full snapshot
+                let file = "snapshot.json".to_string();
+                JsonSink::new(self.dir.join(&file), false).write(data)?;
+                
+                self.write_manifest(&ExportManifest {
+                    dataset: name.to_string(),
+                    key_columns: self.key_columns.clone(),
+                    snapshot: file.clone(),
+                    deltas: Vec::new(),
+                    fingerprints: fingerprints.into_iter().map(|(key, (hash, _))| (key, hash)).collect(),
+                })?;
+                
+                return Ok(ExportOutcome::Snapshot { file, rows: data.len() });
+            },
+        };
+        
+        // Diff the current rows against the fingerprints of the last export
+        let mut events = Vec::new();
+        let (mut inserted, mut updated, mut deleted) = (0, 0, 0);
+        
+        for (key, (hash, row_idx)) in &fingerprints {
+            let op = match manifest.fingerprints.get(key) {
+                None => {
+                    inserted += 1;
+                    "c"
+                },
+                Some(previous) if previous != hash => {
+                    updated += 1;
+                    "u"
+                },
+                Some(_) => continue,
+            };
+            
+            events.push((*row_idx, Self::change_event(op, "after", &self.row_object(data, *row_idx))));
+        }
+        
+        // Keep events in row order so deltas are deterministic
+        events.sort_by_key(|(row_idx, _)| *row_idx);
+        let mut events: Vec<JsonValue> = events.into_iter().map(|(_, event)| event).collect();
+        
+        let mut removed: Vec<&String> = manifest.fingerprints.keys()
+            .filter(|key| !fingerprints.contains_key(*key))
+            .collect();
+        removed.sort();
+        
+        for key in removed {
+            let before: JsonValue = serde_json::from_str(key)
+                .map_err(|e| StorageError::InvalidFormat(e.to_string()))?;
+            events.push(Self::change_event("d", "before", &before));
+            deleted += 1;
+        }
+        
+        if events.is_empty() {
+            return Ok(ExportOutcome::Unchanged);
+        }
+        
+        let sequence = manifest.deltas.last().map_or(1, |delta| delta.sequence + 1);
+        let file = format!("delta-{:06}.json", sequence);
+        
+        let writer = BufWriter::new(File::create(self.dir.join(&file))?);
+        serde_json::to_writer(writer, &events)
+            .map_err(|e| StorageError::Other(e.to_string()))?;
+        
+        manifest.deltas.push(DeltaEntry {
+            sequence,
+            file: file.clone(),
+            inserted,
+            updated,
+            deleted,
+        });
+        manifest.fingerprints = fingerprints.into_iter().map(|(key, (hash, _))| (key, hash)).collect();
+        self.write_manifest(&manifest)?;
+        
+        Ok(ExportOutcome::Delta { file, inserted, updated, deleted })
+    }
+    
+    /// Compute a key and content fingerprint for every row
+    fn fingerprint(&self, data: &DataSet) -> Result<HashMap<String, (u64, usize)>, StorageError> {
+        // Find key column indices
+        let key_indices = self.key_columns.iter()
+            .map(|column| data.schema.fields.iter()
+                .position(|field| &field.name == column)
+                .ok_or_else(|| StorageError::Other(
+                    format!("Key column '{}' not found", column)
+                )))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        if key_indices.is_empty() {
+            return Err(StorageError::Other(
+                "At least one key column is required".to_string()
+            ));
+        }
+        
+        let mut fingerprints = HashMap::new();
+        
+        for (row_idx, row) in data.data.iter().enumerate() {
+            // Keys are stored as the JSON object of key columns
+            let mut key = Map::new();
+            for (column, &i) in self.key_columns.iter().zip(&key_indices) {
+                key.insert(column.clone(), JsonSink::value_to_json(&row.values[i]));
+            }
+            let key = JsonValue::Object(key).to_string();
+            
+            if fingerprints.insert(key.clone(), (fnv1a(&row.values), row_idx)).is_some() {
+                return Err(StorageError::Other(
+                    format!("Duplicate key {}", key)
+                ));
+            }
+        }
+        
+        Ok(fingerprints)
+    }
+    
+    /// Convert a row to a JSON object
+    fn row_object(&self, data: &DataSet, row_idx: usize) -> JsonValue {
+        let mut obj = Map::new();
+        
+        for (field, value) in data.schema.fields.iter().zip(&data.data[row_idx].values) {
+            obj.insert(field.name.clone(), JsonSink::value_to_json(value));
+        }
+        
+        JsonValue::Object(obj)
+    }
+    
+    /// Build a change event with a single row image
+    fn change_event(op: &str, image: &str, row: &JsonValue) -> JsonValue {
+        let mut event = Map::new();
+        event.insert("op".to_string(), JsonValue::String(op.to_string()));
+        event.insert(image.to_string(), row.clone());
+        JsonValue::Object(event)
+    }
+    
+    /// Write the manifest of the export directory
+    fn write_manifest(&self, manifest: &ExportManifest) -> Result<(), StorageError> {
+        let writer = BufWriter::new(File::create(self.dir.join(MANIFEST_FILE))?);
+        serde_json::to_writer_pretty(writer, manifest)
+            .map_err(|e| StorageError::Other(e.to_string()))
+    }
+}
+
+/// Stable 64-bit FNV-1a hash of row values
+///
+/// Values are hashed in their debug form with map entries sorted by key,
+/// since the entry order of a map differs between processes.
+fn fnv1a(values: &[Value]) -> u64 {
+    let mut text = String::new();
+    write_canonical_list(&mut text, values);
+    
+    let mut hash: u64 = 0xcbf29ce484222325;
+    
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    
+    hash
+}
+
+/// Write values as a debug list, with the entries of maps sorted by key
+fn write_canonical_list(out: &mut String, values: &[Value]) {
+    out.push('[');
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_canonical(out, value);
+    }
+    out.push(']');
+}
+
+/// Write a value in its debug form, with the entries of maps sorted by key
+fn write_canonical(out: &mut String, value: &Value) {
+    match value {
+        Value::Array(values) => {
+            out.push_str("Array(");
+            write_canonical_list(out, values);
+            out.push(')');
+        },
+        Value::Map(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            
+            out.push_str("Map({");
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                let _ = write!(out, "{:?}: ", key);
+                write_canonical(out, &map[key]);
+            }
+            out.push_str("})");
+        },
+        other => {
+            let _ = write!(out, "{:?}", other);
+        },
+    }
+}
@@ -0,0 +1,410 @@
+// Failover storage implementation
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::data::{ColumnRange, DataSet, LineageStep};
+use super::{DatasetInfo, DataStorage, MaintenanceReport, StorageError, StorageStatus, TrashEntry, ViewDefinition};
+
+/// Write made while the primary was failing, kept to be replayed on it
+#[derive(Debug)]
+enum PendingWrite {
+    Store { name: String, data: DataSet },
+    Delete { name: String },
+    Copy { from: String, to: String },
+    Rename { from: String, to: String },
+}
+
+impl PendingWrite {
+    /// Apply the write to a storage
+    fn apply(&self, storage: &dyn DataStorage) -> Result<(), StorageError> {
+        match self {
+            PendingWrite::Store { name, data } => storage.store(name, data),
+            PendingWrite::Delete { name } => storage.delete(name),
+            PendingWrite::Copy { from, to } => storage.copy(from, to),
+            PendingWrite::Rename { from, to } => storage.rename(from, to),
+        }
+    }
+}
+
+/// Health of the primary and the writes it has missed
+struct FailoverState {
+    primary_healthy: bool,
+    checked_at: Option<Instant>,
+    pending: VecDeque<PendingWrite>,
+}
+
+/// Storage wrapper failing over from a primary to a secondary storage
+///
+/// Writes go to both storages. Reads are served by the primary, falling back
+/// to the secondary when it fails with a transient error; the primary is
+/// then marked unhealthy and reads stay on the secondary. Writes made while
+/// the primary is unhealthy are applied to the secondary and queued, and the
+/// primary is health checked at most once per interval. Once it answers,
+/// the queued writes are replayed on it in order and reads return to it.
+///
+/// Writes run one at a time, so both storages apply them in the same order.
+/// At most `max_pending` writes are queued; further writes fail with
+/// `StorageError::Full` until the primary recovers.
+///
+/// Only stores, deletes, copies and renames are queued; changes to aliases,
+/// tags, immutability flags and the trash need the primary.
+pub struct FailoverStorage {
+    primary: Arc<dyn DataStorage + Send + Sync>,
+    secondary: Arc<dyn DataStorage + Send + Sync>,
+    check_interval: Duration,
+    max_pending: usize,
+    state: Mutex<FailoverState>,
+    /// Held while writing or replaying, to keep both storages in step
+    writes: Mutex<()>,
+}
+
+impl FailoverStorage {
+    /// Create a new failover storage over a primary and a secondary storage
+    pub fn new(primary: Arc<dyn DataStorage + Send + Sync>, secondary: Arc<dyn DataStorage + Send + Sync>) -> Self {
+        FailoverStorage {
+            primary,
+            secondary,
+            check_interval: Duration::from_secs(5),
+            max_pending: 10_000,
+            state: Mutex::new(FailoverState {
+                primary_healthy: true,
+                checked_at: None,
+                pending: VecDeque::new(),
+            }),
+            writes: Mutex::new(()),
+        }
+    }
+    
+    /// Set the least time between health checks of a failing primary
+    pub fn with_check_interval(mut self, interval: Duration) -> Self {
+        self.check_interval = interval;
+        self
+    }
+    
+    /// Set the most writes queued for a failing primary
+    pub fn with_max_pending_writes(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending;
+        self
+    }
+    
+    /// Check if reads are served by the primary
+    pub fn is_primary_healthy(&self) -> bool {
+        self.state().map_or(false, |state| state.primary_healthy)
+    }
+    
+    /// Get the number of writes waiting to be replayed on the primary
+    pub fn pending_writes(&self) -> usize {
+        self.state().map_or(0, |state| state.pending.len())
+    }
+    
+    /// Health check the primary now and replay the writes it missed
+    ///
+    /// Returns the number of writes replayed. A write failing again stops
+    /// the replay, leaving it and later writes queued.
+    pub fn recover(&self) -> Result<usize, StorageError> {
+        let writes = self.writes()?;
+        self.replay(&writes)
+    }
+    
+    /// Health check the primary and replay the writes it missed, with the write lock held
+    fn replay(&self, _writes: &MutexGuard<'_, ()>) -> Result<usize, StorageError> {
+        let mut state = self.state()?;
+        state.checked_at = Some(Instant::now());
+        
+        if let Err(err) = self.primary.list() {
+            state.primary_healthy = false;
+            return Err(err);
+        }
+        
+        let mut replayed = 0;
+        
+        while let Some(write) = state.pending.front() {
+            if let Err(err) = write.apply(self.primary.as_ref()) {
+                log::warn!("Replaying write on primary storage failed: {}", err);
+                
+                if err.is_transient() {
+                    state.primary_healthy = false;
+                    return Err(err);
+                }
+                
+                // Writes that no longer apply, such as deleting a dataset the primary never got, are dropped
+            }
+            
+            state.pending.pop_front();
+            replayed += 1;
+        }
+        
+        if !state.primary_healthy {
+            log::info!("Primary storage recovered after replaying {} writes", replayed);
+        }
+        
+        state.primary_healthy = true;
+        Ok(replayed)
+    }
+    
+    /// Lock the failover state
+    fn state(&self) -> Result<MutexGuard<'_, FailoverState>, StorageError> {
+        self.state.lock().map_err(|_| {
+            StorageError::Other("Failed to acquire failover lock".to_string())
+        })
+    }
+    
+    /// Lock out other writes and replays
+    fn writes(&self) -> Result<MutexGuard<'_, ()>, StorageError> {
+        self.writes.lock().map_err(|_| {
+            StorageError::Other("Failed to acquire failover write lock".to_string())
+        })
+    }
+    
+    /// Check if the primary serves requests, health checking it if it failed and is due
+    ///
+    /// Writers pass the write lock they hold; reads only take it for a health check.
+    fn primary_available(&self, writes: Option<&MutexGuard<'_, ()>>) -> Result<bool, StorageError> {
+        let due = {
+            let state = self.state()?;
+            
+            if state.primary_healthy {
+                return Ok(true);
+            }
+            
+            state.checked_at.map_or(true, |checked_at| checked_at.elapsed() >= self.check_interval)
+        };
+        
+        if !due {
+            return Ok(false);
+        }
+        
+        let recovered = match writes {
+            Some(writes) => self.replay(writes),
+            None => self.recover(),
+        };
+        
+        Ok(recovered.is_ok())
+    }
+    
+    /// Mark the primary unhealthy after a failed operation
+    fn fail_primary(&self, err: &StorageError) -> Result<(), StorageError> {
+        let mut state = self.state()?;
+        
+        if state.primary_healthy {
+            log::warn!("Primary storage failed, failing over to secondary: {}", err);
+        }
+        
+        state.primary_healthy = false;
+        state.checked_at = Some(Instant::now());
+        Ok(())
+    }
+    
+    /// Run a read on the primary, falling back to the secondary
+    fn read<T, F>(&self, operation: F) -> Result<T, StorageError>
+    where
+        F: Fn(&dyn DataStorage) -> Result<T, StorageError>,
+    {
+        if self.primary_available(None)? {
+            match operation(self.primary.as_ref()) {
+                Err(err) if err.is_transient() => self.fail_primary(&err)?,
+                result => return result,
+            }
+        }
+        
+        operation(self.secondary.as_ref())
+    }
+    
+    /// Run a write on both storages, queueing it for the primary if that fails
+    ///
+    /// The queued write is only built when needed, so healthy stores do not
+    /// copy their dataset. The write lock is held until both storages have
+    /// the write, so concurrent writes reach them in the same order.
+    fn write<F, W>(&self, operation: F, pending: W) -> Result<(), StorageError>
+    where
+        F: Fn(&dyn DataStorage) -> Result<(), StorageError>,
+        W: FnOnce() -> PendingWrite,
+    {
+        let writes = self.writes()?;
+        
+        if self.primary_available(Some(&writes))? {
+            match operation(self.primary.as_ref()) {
+                Err(err) if err.is_transient() => self.fail_primary(&err)?,
+                Err(err) => return Err(err),
+                Ok(()) => {
+                    // The secondary only has to keep up; a failure here does not fail the write
+                    if let Err(err) = operation(self.secondary.as_ref()) {
+                        log::warn!("Mirroring write to secondary storage failed: {}", err);
+                    }
+                    
+                    return Ok(());
+                },
+            }
+        }
+        
+        // Refuse the write before the secondary gets ahead of what can be replayed
+        if self.state()?.pending.len() >= self.max_pending {
+            return Err(StorageError::Full(format!(
+                "{} writes are waiting for the primary storage to recover", self.max_pending
+            )));
+        }
+        
+        operation(self.secondary.as_ref())?;
+        self.state()?.pending.push_back(pending());
+        Ok(())
+    }
+}
+
+impl DataStorage for FailoverStorage {
+    fn store(&self, name: &str, data: &DataSet) -> Result<(), StorageError> {
+        self.write(
+            |storage| storage.store(name, data),
+            || PendingWrite::Store { name: name.to_string(), data: data.clone() },
+        )
+    }
+    
+    fn load(&self, name: &str) -> Result<DataSet, StorageError> {
+        self.read(|storage| storage.load(name))
+    }
+    
+    fn exists(&self, name: &str) -> Result<bool, StorageError> {
+        self.read(|storage| storage.exists(name))
+    }
+    
+    fn delete(&self, name: &str) -> Result<(), StorageError> {
+        self.write(
+            |storage| storage.delete(name),
+            || PendingWrite::Delete { name: name.to_string() },
+        )
+    }
+    
+    fn list(&self) -> Result<Vec<String>, StorageError> {
+        self.read(|storage| storage.list())
+    }
+    
+    fn copy(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.write(
+            |storage| storage.copy(from, to),
+            || PendingWrite::Copy { from: from.to_string(), to: to.to_string() },
+        )
+    }
+    
+    fn rename(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.write(
+            |storage| storage.rename(from, to),
+            || PendingWrite::Rename { from: from.to_string(), to: to.to_string() },
+        )
+    }
+    
+    fn load_head(&self, name: &str, rows: usize) -> Result<DataSet, StorageError> {
+        self.read(|storage| storage.load_head(name, rows))
+    }
+    
+    fn load_range(&self, name: &str, offset: usize, limit: usize) -> Result<DataSet, StorageError> {
+        self.read(|storage| storage.load_range(name, offset, limit))
+    }
+    
+    fn load_columns(&self, name: &str, columns: &[String]) -> Result<DataSet, StorageError> {
+        self.read(|storage| storage.load_columns(name, columns))
+    }
+    
+    fn load_pruned(&self, name: &str, ranges: &[ColumnRange]) -> Result<DataSet, StorageError> {
+        self.read(|storage| storage.load_pruned(name, ranges))
+    }
+    
+    fn info(&self, name: &str) -> Result<DatasetInfo, StorageError> {
+        self.read(|storage| storage.info(name))
+    }
+    
+    fn is_immutable(&self, name: &str) -> Result<bool, StorageError> {
+        self.read(|storage| storage.is_immutable(name))
+    }
+    
+    fn set_immutable(&self, name: &str, immutable: bool) -> Result<(), StorageError> {
+        self.primary.set_immutable(name, immutable)
+    }
+    
+    fn aliases(&self) -> Result<Vec<(String, String)>, StorageError> {
+        self.read(|storage| storage.aliases())
+    }
+    
+    fn create_alias(&self, alias: &str, target: &str) -> Result<(), StorageError> {
+        self.primary.create_alias(alias, target)
+    }
+    
+    fn remove_alias(&self, alias: &str) -> Result<(), StorageError> {
+        self.primary.remove_alias(alias)
+    }
+    
+    fn views(&self) -> Result<Vec<(String, ViewDefinition)>, StorageError> {
+        self.read(|storage| storage.views())
+    }
+    
+    fn create_view(&self, name: &str, view: &ViewDefinition) -> Result<(), StorageError> {
+        self.primary.create_view(name, view)
+    }
+    
+    fn remove_view(&self, name: &str) -> Result<(), StorageError> {
+        self.primary.remove_view(name)
+    }
+    
+    fn tags(&self, name: &str) -> Result<Vec<String>, StorageError> {
+        self.read(|storage| storage.tags(name))
+    }
+    
+    fn set_tags(&self, name: &str, tags: &[String]) -> Result<(), StorageError> {
+        self.primary.set_tags(name, tags)
+    }
+    
+    fn lineage(&self, name: &str) -> Result<BTreeMap<String, Vec<LineageStep>>, StorageError> {
+        self.read(|storage| storage.lineage(name))
+    }
+    
+    fn trash(&self) -> Result<Vec<TrashEntry>, StorageError> {
+        self.read(|storage| storage.trash())
+    }
+    
+    fn restore(&self, name: &str) -> Result<(), StorageError> {
+        self.primary.restore(name)
+    }
+    
+    fn purge(&self, name: Option<&str>) -> Result<usize, StorageError> {
+        self.primary.purge(name)
+    }
+    
+    fn maintain(&self, report: &mut MaintenanceReport) -> Result<(), StorageError> {
+        // Maintenance is a chance to recover without waiting for a request
+        if !self.is_primary_healthy() {
+            if let Ok(replayed) = self.recover() {
+                report.writes_replayed += replayed;
+            }
+        }
+        
+        if self.is_primary_healthy() {
+            self.primary.maintain(report)
+        } else {
+            self.secondary.maintain(report)
+        }
+    }
+    
+    fn describe(&self, status: &mut StorageStatus) -> Result<(), StorageError> {
+        let state = self.state()?;
+        
+        status.layers.push(if state.primary_healthy {
+            "failover".to_string()
+        } else {
+            format!("failover (on secondary, {} writes pending)", state.pending.len())
+        });
+        
+        let healthy = state.primary_healthy;
+        drop(state);
+        
+        if healthy {
+            self.primary.describe(status)
+        } else {
+            self.secondary.describe(status)
+        }
+    }
+    
+    fn clear_cache(&self) -> Result<usize, StorageError> {
+        Ok(self.primary.clear_cache()? + self.secondary.clear_cache()?)
+    }
+}
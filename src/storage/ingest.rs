@@ -0,0 +1,402 @@
+// Ingestion of files dropped in landing directories
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::data::{ColumnDrift, DataSet, Value};
+use crate::utils::watch_paths;
+use super::{read_data_file, DataStorage, FileFormat, StorageError};
+
+/// Name of the subdirectory ingested files are moved to by default
+const ARCHIVE_DIR: &str = "archive";
+
+/// Name of the subdirectory rejected files are moved to by default
+const QUARANTINE_DIR: &str = "quarantine";
+
+/// How a landed file is combined with the dataset
+#[derive(Debug, Clone, PartialEq)]
+pub enum IngestMode {
+    /// Add the rows of the file to the dataset
+    Append,
+    /// Replace the dataset with the file
+    Replace,
+    /// Replace rows with the same key columns and append the others
+    Merge(Vec<String>),
+}
+
+impl IngestMode {
+    /// Parse an ingestion mode; `merge` takes the key columns
+    pub fn from_str(s: &str, key_columns: &[String]) -> Result<Self, StorageError> {
+        match s.to_lowercase().as_str() {
+            "append" => Ok(IngestMode::Append),
+            "replace" => Ok(IngestMode::Replace),
+            "merge" if key_columns.is_empty() => Err(StorageError::InvalidFormat(
+                "Merge ingestion needs key columns".to_string()
+            )),
+            "merge" => Ok(IngestMode::Merge(key_columns.to_vec())),
+            _ => Err(StorageError::InvalidFormat(format!(
+                "Unknown ingestion mode: {}. Use append, replace or merge", s
+            ))),
+        }
+    }
+}
+
+/// What to do with files whose schema drifts from the dataset's
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftPolicy {
+    /// Quarantine files with any drift
+    Block,
+    /// Ingest files whose drift is not breaking, logging and reporting it
+    Notify,
+}
+
+impl DriftPolicy {
+    /// Parse a drift policy: `block` or `notify`
+    pub fn from_str(s: &str) -> Result<Self, StorageError> {
+        match s.to_lowercase().as_str() {
+            "block" => Ok(DriftPolicy::Block),
+            "notify" => Ok(DriftPolicy::Notify),
+            _ => Err(StorageError::InvalidFormat(format!(
+                "Unknown drift policy: {}. Use block or notify", s
+            ))),
+        }
+    }
+    
+    /// Fail on drift the policy does not let through
+    pub fn check(&self, drift: &[ColumnDrift]) -> Result<(), StorageError> {
+        let blocked: Vec<ColumnDrift> = drift.iter()
+            .filter(|column| *self == DriftPolicy::Block || column.breaking)
+            .cloned()
+            .collect();
+        
+        if blocked.is_empty() {
+            Ok(())
+        } else {
+            Err(StorageError::InvalidFormat(format!("Schema drift: {}", describe_drift(&blocked))))
+        }
+    }
+}
+
+/// Describe the columns that drifted, in words
+pub fn describe_drift(drift: &[ColumnDrift]) -> String {
+    drift.iter()
+        .map(|column| column.describe())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Landing directory feeding a dataset
+#[derive(Debug, Clone)]
+pub struct LandingZone {
+    dataset: String,
+    landing_dir: PathBuf,
+    archive_dir: PathBuf,
+    quarantine_dir: PathBuf,
+    mode: IngestMode,
+    drift_policy: DriftPolicy,
+}
+
+impl LandingZone {
+    /// Create a landing zone appending files to a dataset
+    ///
+    /// Ingested files are moved to `archive/` and rejected files to
+    /// `quarantine/` inside the landing directory.
+    pub fn new<P: AsRef<Path>>(dataset: &str, landing_dir: P) -> Self {
+        let landing_dir = landing_dir.as_ref().to_path_buf();
+        
+        LandingZone {
+            dataset: dataset.to_string(),
+            archive_dir: landing_dir.join(ARCHIVE_DIR),
+            quarantine_dir: landing_dir.join(QUARANTINE_DIR),
+            landing_dir,
+            mode: IngestMode::Append,
+            drift_policy: DriftPolicy::Block,
+        }
+    }
+    
+    /// Move ingested files to another directory
+    pub fn with_archive_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.archive_dir = dir.as_ref().to_path_buf();
+        self
+    }
+    
+    /// Move rejected files to another directory
+    pub fn with_quarantine_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.quarantine_dir = dir.as_ref().to_path_buf();
+        self
+    }
+    
+    /// Set how files are combined with the dataset
+    pub fn with_mode(mut self, mode: IngestMode) -> Self {
+        self.mode = mode;
+        self
+    }
+    
+    /// Set what happens to files whose schema drifts from the dataset's
+    pub fn with_drift_policy(mut self, policy: DriftPolicy) -> Self {
+        self.drift_policy = policy;
+        self
+    }
+    
+    /// Get the dataset fed by the landing zone
+    pub fn dataset(&self) -> &str {
+        &self.dataset
+    }
+    
+    /// Get the landing directory
+    pub fn landing_dir(&self) -> &Path {
+        &self.landing_dir
+    }
+    
+    /// Get the files waiting in the landing directory, in name order
+    ///
+    /// Hidden files and files without a csv, json or parquet extension are
+    /// left alone, so writers can write to a temporary name and rename the
+    /// file once it is complete.
+    pub fn pending_files(&self) -> Result<Vec<PathBuf>, StorageError> {
+        if !self.landing_dir.exists() {
+            return Ok(Vec::new());
+        }
+        
+        let mut files: Vec<PathBuf> = fs::read_dir(&self.landing_dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .filter(|path| {
+                let hidden = path.file_name()
+                    .and_then(|name| name.to_str())
+                    .map_or(true, |name| name.starts_with('.'));
+                let known = path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .map_or(false, |ext| FileFormat::from_extension(ext).is_ok());
+                
+                !hidden && known
+            })
+            .collect();
+        
+        files.sort();
+        Ok(files)
+    }
+}
+
+/// Ingestion results of a landing zone
+#[derive(Debug, Clone, Default)]
+pub struct IngestionStatus {
+    pub files_ingested: u64,
+    pub files_quarantined: u64,
+    pub rows_ingested: u64,
+    pub last_file: Option<String>,
+    pub last_error: Option<String>,
+    pub last_ingested_at: Option<DateTime<Utc>>,
+    /// Drift of the last ingested file from the dataset's schema
+    pub last_drift: Vec<ColumnDrift>,
+}
+
+/// Service ingesting files dropped in landing directories into datasets
+///
+/// Each file is parsed, checked for drift from the dataset's schema, and
+/// combined with it according to the zone's mode. Ingested files are then
+/// archived, while files that fail are quarantined next to a `.error` file
+/// with the reason, so a bad file is never picked up twice.
+pub struct IngestionService {
+    storage: Arc<dyn DataStorage + Send + Sync>,
+    zones: Vec<LandingZone>,
+    status: RwLock<HashMap<String, IngestionStatus>>,
+}
+
+impl IngestionService {
+    /// Create a new ingestion service storing into a storage
+    pub fn new(storage: Arc<dyn DataStorage + Send + Sync>) -> Self {
+        IngestionService {
+            storage,
+            zones: Vec::new(),
+            status: RwLock::new(HashMap::new()),
+        }
+    }
+    
+    /// Add a landing zone
+    pub fn add(mut self, zone: LandingZone) -> Self {
+        self.zones.push(zone);
+        self
+    }
+    
+    /// Get the landing zones
+    pub fn zones(&self) -> &[LandingZone] {
+        &self.zones
+    }
+    
+    /// Get the ingestion results of every landing zone, by dataset
+    pub fn statuses(&self) -> Result<Vec<(String, IngestionStatus)>, StorageError> {
+        let status = self.status.read()
+            .map_err(|_| StorageError::Other("Failed to acquire read lock".to_string()))?;
+        
+        Ok(self.zones.iter()
+            .map(|zone| (zone.dataset.clone(), status.get(&zone.dataset).cloned().unwrap_or_default()))
+            .collect())
+    }
+    
+    /// Ingest every file waiting in the landing zones, returning how many were ingested
+    ///
+    /// A file that fails is quarantined and does not stop the others.
+    pub fn scan(&self) -> Result<usize, StorageError> {
+        let mut ingested = 0;
+        
+        for zone in &self.zones {
+            for file in zone.pending_files()? {
+                let result = self.ingest_file(zone, &file);
+                let file_name = file.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                
+                match &result {
+                    Ok((rows, _)) => {
+                        log::info!("Ingested {} rows from {} into '{}'", rows, file.display(), zone.dataset);
+                        move_file(&file, &zone.archive_dir)?;
+                        ingested += 1;
+                    },
+                    Err(err) => {
+                        log::warn!("Quarantined {} for '{}': {}", file.display(), zone.dataset, err);
+                        let moved = move_file(&file, &zone.quarantine_dir)?;
+                        fs::write(moved.with_extension("error"), err.to_string())?;
+                    },
+                }
+                
+                let mut status = self.status.write()
+                    .map_err(|_| StorageError::Other("Failed to acquire write lock".to_string()))?;
+                let entry = status.entry(zone.dataset.clone()).or_default();
+                
+                entry.last_file = Some(file_name);
+                entry.last_ingested_at = Some(Utc::now());
+                
+                match result {
+                    Ok((rows, drift)) => {
+                        entry.files_ingested += 1;
+                        entry.rows_ingested += rows as u64;
+                        entry.last_error = None;
+                        entry.last_drift = drift;
+                    },
+                    Err(err) => {
+                        entry.files_quarantined += 1;
+                        entry.last_error = Some(err.to_string());
+                    },
+                }
+            }
+        }
+        
+        Ok(ingested)
+    }
+    
+    /// Ingest waiting files now and whenever files arrive, on a background thread
+    pub fn start(self: Arc<Self>, debounce: Duration) -> Result<thread::JoinHandle<()>, StorageError> {
+        for zone in &self.zones {
+            fs::create_dir_all(&zone.landing_dir)?;
+        }
+        
+        let dirs: Vec<PathBuf> = self.zones.iter().map(|zone| zone.landing_dir.clone()).collect();
+        
+        Ok(thread::spawn(move || {
+            if let Err(err) = self.scan() {
+                log::error!("Error ingesting landed files: {}", err);
+            }
+            
+            let result = watch_paths(&dirs, debounce, |_| {
+                if let Err(err) = self.scan() {
+                    log::error!("Error ingesting landed files: {}", err);
+                }
+                
+                true
+            });
+            
+            if let Err(err) = result {
+                log::error!("Error watching landing directories: {}", err);
+            }
+        }))
+    }
+    
+    /// Ingest one file into the dataset of a zone, returning the rows read and
+    /// the drift let through
+    fn ingest_file(&self, zone: &LandingZone, file: &Path) -> Result<(usize, Vec<ColumnDrift>), StorageError> {
+        let data = read_data_file(file)?;
+        let rows = data.len();
+        
+        if zone.mode == IngestMode::Replace || !self.storage.exists(&zone.dataset)? {
+            self.storage.store(&zone.dataset, &data)?;
+            return Ok((rows, Vec::new()));
+        }
+        
+        let mut existing = self.storage.load(&zone.dataset)?;
+        let drift = existing.schema.drift(&data.schema);
+        zone.drift_policy.check(&drift)?;
+        
+        if !drift.is_empty() {
+            log::warn!("Schema drift in {} for '{}': {}", file.display(), zone.dataset, describe_drift(&drift));
+        }
+        
+        let mut aligned = DataSet::new(existing.schema.clone());
+        aligned.data = existing.schema.align_rows(&data.schema, data.data);
+        let data = aligned;
+        
+        match &zone.mode {
+            IngestMode::Merge(key_columns) => merge_rows(&mut existing, data, key_columns)?,
+            // Missing columns are filled from their defaults
+            _ => {
+                for row in data.data {
+                    existing.insert_row(row)?;
+                }
+            },
+        }
+        
+        self.storage.store(&zone.dataset, &existing)?;
+        Ok((rows, drift))
+    }
+}
+
+/// Replace rows of a dataset with the rows of a file with the same keys, appending the rest
+pub(crate) fn merge_rows(existing: &mut DataSet, data: DataSet, key_columns: &[String]) -> Result<(), StorageError> {
+    let indices = key_columns.iter()
+        .map(|column| {
+            existing.schema.fields.iter()
+                .position(|field| &field.name == column)
+                .ok_or_else(|| StorageError::InvalidFormat(format!("Key column '{}' not found", column)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    
+    let key = |values: &[Value]| -> Vec<Value> {
+        indices.iter().map(|&i| values[i].clone()).collect()
+    };
+    
+    let mut positions: HashMap<Vec<Value>, usize> = existing.data.iter().enumerate()
+        .map(|(i, row)| (key(&row.values), i))
+        .collect();
+    
+    for row in data.data {
+        match positions.get(&key(&row.values)) {
+            Some(&i) => existing.data[i] = row,
+            None => {
+                positions.insert(key(&row.values), existing.data.len());
+                existing.data.push(row);
+            },
+        }
+    }
+    
+    Ok(())
+}
+
+/// Move a file into a directory, prefixing its name with a timestamp so files never collide
+fn move_file(file: &Path, dir: &Path) -> Result<PathBuf, StorageError> {
+    fs::create_dir_all(dir)?;
+    
+    let name = file.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let target = dir.join(format!("{}_{}", Utc::now().format("%Y%m%dT%H%M%S%3f"), name));
+    
+    fs::rename(file, &target)?;
+    Ok(target)
+}
@@ -0,0 +1,189 @@
+// Result cache invalidation on dataset writes
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::data::{ColumnRange, DataSet, LineageStep};
+use crate::processing::ResultCache;
+use super::{DatasetInfo, DataStorage, MaintenanceReport, StorageError, StorageStatus, TrashEntry, ViewDefinition};
+
+/// Storage wrapper invalidating cached processing results on writes
+///
+/// Every store, delete, rename, restore, alias or view change made through
+/// this storage drops the cached results computed from the affected
+/// datasets, including results read through aliases pointing at them and
+/// views reading them.
+pub struct InvalidatingStorage {
+    backend: Arc<dyn DataStorage + Send + Sync>,
+    cache: Arc<ResultCache>,
+}
+
+impl InvalidatingStorage {
+    /// Create a new invalidating storage around a backend
+    pub fn new(backend: Arc<dyn DataStorage + Send + Sync>, cache: Arc<ResultCache>) -> Self {
+        InvalidatingStorage { backend, cache }
+    }
+    
+    /// Invalidate a dataset, the aliases pointing at or from it and the views reading it
+    fn invalidate(&self, name: &str) -> Result<(), StorageError> {
+        let mut names = vec![name.to_string()];
+        for (alias, target) in self.backend.aliases()? {
+            if target == name {
+                names.push(alias);
+            } else if alias == name {
+                names.push(target);
+            }
+        }
+        
+        // Views read through other views, so follow them until no more are found
+        let mut views = self.backend.views()?;
+        let reads_changed = |view: &ViewDefinition, names: &[String]| {
+            view.inputs().iter().any(|input| names.iter().any(|name| name == input))
+        };
+        
+        while let Some(i) = views.iter().position(|(_, view)| reads_changed(view, &names)) {
+            names.push(views.swap_remove(i).0);
+        }
+        
+        for name in names {
+            self.cache.invalidate(&name)
+                .map_err(|e| StorageError::Other(e.to_string()))?;
+        }
+        
+        Ok(())
+    }
+}
+
+impl DataStorage for InvalidatingStorage {
+    fn store(&self, name: &str, data: &DataSet) -> Result<(), StorageError> {
+        self.backend.store(name, data)?;
+        self.invalidate(name)
+    }
+    
+    fn load(&self, name: &str) -> Result<DataSet, StorageError> {
+        self.backend.load(name)
+    }
+    
+    fn exists(&self, name: &str) -> Result<bool, StorageError> {
+        self.backend.exists(name)
+    }
+    
+    fn delete(&self, name: &str) -> Result<(), StorageError> {
+        // Aliases are resolved before the delete removes them
+        self.invalidate(name)?;
+        self.backend.delete(name)
+    }
+    
+    fn list(&self) -> Result<Vec<String>, StorageError> {
+        self.backend.list()
+    }
+    
+    fn copy(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.backend.copy(from, to)?;
+        self.invalidate(to)
+    }
+    
+    fn rename(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.invalidate(from)?;
+        self.backend.rename(from, to)?;
+        self.invalidate(to)
+    }
+    
+    fn load_head(&self, name: &str, rows: usize) -> Result<DataSet, StorageError> {
+        self.backend.load_head(name, rows)
+    }
+    
+    fn load_range(&self, name: &str, offset: usize, limit: usize) -> Result<DataSet, StorageError> {
+        self.backend.load_range(name, offset, limit)
+    }
+    
+    fn load_columns(&self, name: &str, columns: &[String]) -> Result<DataSet, StorageError> {
+        self.backend.load_columns(name, columns)
+    }
+    
+    fn load_pruned(&self, name: &str, ranges: &[ColumnRange]) -> Result<DataSet, StorageError> {
+        self.backend.load_pruned(name, ranges)
+    }
+    
+    fn info(&self, name: &str) -> Result<DatasetInfo, StorageError> {
+        self.backend.info(name)
+    }
+    
+    fn is_immutable(&self, name: &str) -> Result<bool, StorageError> {
+        self.backend.is_immutable(name)
+    }
+    
+    fn set_immutable(&self, name: &str, immutable: bool) -> Result<(), StorageError> {
+        self.backend.set_immutable(name, immutable)
+    }
+    
+    fn aliases(&self) -> Result<Vec<(String, String)>, StorageError> {
+        self.backend.aliases()
+    }
+    
+    fn create_alias(&self, alias: &str, target: &str) -> Result<(), StorageError> {
+        self.backend.create_alias(alias, target)?;
+        self.cache.invalidate(alias)
+            .map_err(|e| StorageError::Other(e.to_string()))
+    }
+    
+    fn remove_alias(&self, alias: &str) -> Result<(), StorageError> {
+        self.backend.remove_alias(alias)?;
+        self.cache.invalidate(alias)
+            .map_err(|e| StorageError::Other(e.to_string()))
+    }
+    
+    fn views(&self) -> Result<Vec<(String, ViewDefinition)>, StorageError> {
+        self.backend.views()
+    }
+    
+    fn create_view(&self, name: &str, view: &ViewDefinition) -> Result<(), StorageError> {
+        self.backend.create_view(name, view)?;
+        self.invalidate(name)
+    }
+    
+    fn remove_view(&self, name: &str) -> Result<(), StorageError> {
+        self.invalidate(name)?;
+        self.backend.remove_view(name)
+    }
+    
+    fn tags(&self, name: &str) -> Result<Vec<String>, StorageError> {
+        self.backend.tags(name)
+    }
+    
+    fn set_tags(&self, name: &str, tags: &[String]) -> Result<(), StorageError> {
+        self.backend.set_tags(name, tags)
+    }
+    
+    fn lineage(&self, name: &str) -> Result<BTreeMap<String, Vec<LineageStep>>, StorageError> {
+        self.backend.lineage(name)
+    }
+    
+    fn trash(&self) -> Result<Vec<TrashEntry>, StorageError> {
+        self.backend.trash()
+    }
+    
+    fn restore(&self, name: &str) -> Result<(), StorageError> {
+        self.backend.restore(name)?;
+        self.invalidate(name)
+    }
+    
+    fn purge(&self, name: Option<&str>) -> Result<usize, StorageError> {
+        // Purged datasets are already deleted and invalidated
+        self.backend.purge(name)
+    }
+    
+    fn maintain(&self, report: &mut MaintenanceReport) -> Result<(), StorageError> {
+        self.backend.maintain(report)
+    }
+    
+    fn describe(&self, status: &mut StorageStatus) -> Result<(), StorageError> {
+        status.layers.push("invalidating".to_string());
+        self.backend.describe(status)
+    }
+    
+    fn clear_cache(&self) -> Result<usize, StorageError> {
+        self.backend.clear_cache()
+    }
+}
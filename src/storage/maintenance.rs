@@ -0,0 +1,50 @@
+// Storage maintenance tasks
+// Author: Gabriel Demetrios Lafis
+
+use super::{DataStorage, StorageError};
+
+/// Report of a maintenance run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaintenanceReport {
+    /// Datasets in the storage after maintenance
+    pub datasets: usize,
+    /// Total size of the datasets, where the storage reports sizes
+    pub total_bytes: u64,
+    /// Deleted datasets purged from the trash after their retention period
+    pub trash_purged: usize,
+    /// Aliases removed because their dataset no longer exists
+    pub aliases_removed: usize,
+    /// Expired entries evicted from caches
+    pub cache_evicted: usize,
+    /// Files encrypted with the current key after a key rotation
+    pub files_reencrypted: usize,
+    /// Idle sessions dropped along with their temporary datasets
+    pub sessions_expired: usize,
+    /// Space reclaimed by purging, where the storage reports sizes
+    pub bytes_reclaimed: u64,
+    /// Writes replayed on a recovered primary storage
+    pub writes_replayed: usize,
+}
+
+/// Run maintenance on a storage and refresh its dataset statistics
+///
+/// Each storage layer does its own housekeeping through
+/// `DataStorage::maintain`: wrappers purge expired trash, drop dangling
+/// aliases and evict expired cache entries before delegating to their backend.
+pub fn run_maintenance(storage: &dyn DataStorage) -> Result<MaintenanceReport, StorageError> {
+    let mut report = MaintenanceReport::default();
+    
+    storage.maintain(&mut report)?;
+    
+    // Refresh catalog statistics
+    let names = storage.list()?;
+    report.datasets = names.len();
+    
+    for name in &names {
+        if let Some(size) = storage.info(name)?.size_bytes {
+            report.total_bytes += size;
+        }
+    }
+    
+    Ok(report)
+}
@@ -2,14 +2,78 @@
 // Author: Gabriel Demetrios Lafis
 
 use std::collections::HashMap;
+use std::mem::size_of;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
 
-use crate::data::DataSet;
-use super::{DataStorage, StorageError};
+use crate::data::{DataSet, Row, Value};
+use super::{project_columns, DataStorage, StorageError, StorageStatus};
+
+/// What a memory storage does when a dataset would exceed its memory limit
+pub enum MemoryPolicy {
+    /// Fail the store with `StorageError::Full`
+    Reject,
+    /// Drop the least recently used datasets until the new one fits
+    EvictLru,
+    /// Move the least recently used datasets to another storage, where they
+    /// are still read, renamed and deleted through this storage
+    Spill(Arc<dyn DataStorage + Send + Sync>),
+}
+
+/// Dataset removed from memory to make room for another
+#[derive(Debug, Clone, PartialEq)]
+pub struct Eviction {
+    pub name: String,
+    /// Estimated size of the dataset in memory
+    pub bytes: usize,
+    /// Whether the dataset was moved to the spill storage rather than dropped
+    pub spilled: bool,
+}
+
+/// Memory occupancy of a memory storage
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryUsage {
+    /// Estimated size of the datasets held in memory
+    pub used_bytes: usize,
+    /// Memory limit, if any
+    pub max_bytes: Option<usize>,
+    /// Datasets held in memory
+    pub datasets: usize,
+    /// Datasets dropped to make room since the storage was created
+    pub evicted: u64,
+    /// Datasets moved to the spill storage since the storage was created
+    pub spilled: u64,
+    /// Stores rejected for lack of room since the storage was created
+    pub rejected: u64,
+}
+
+/// Dataset held in memory
+struct Entry {
+    data: DataSet,
+    bytes: usize,
+    last_used: AtomicU64,
+}
+
+/// Soft limit on the memory taken by datasets
+struct MemoryLimit {
+    max_bytes: usize,
+    policy: MemoryPolicy,
+}
 
 /// Memory storage for datasets
+///
+/// With a memory limit, the size of each dataset is estimated when it is
+/// stored, and the policy decides what happens to a store that does not
+/// fit. Eviction hooks are called for every dataset removed to make room,
+/// while the storage is locked, so they must not use the storage.
 pub struct MemoryStorage {
-    datasets: Arc<RwLock<HashMap<String, DataSet>>>,
+    datasets: Arc<RwLock<HashMap<String, Entry>>>,
+    limit: Option<MemoryLimit>,
+    hooks: Vec<Box<dyn Fn(&Eviction) + Send + Sync>>,
+    clock: AtomicU64,
+    evicted: AtomicU64,
+    spilled: AtomicU64,
+    rejected: AtomicU64,
 }
 
 impl MemoryStorage {
@@ -17,8 +81,183 @@ impl MemoryStorage {
     pub fn new() -> Self {
         MemoryStorage {
             datasets: Arc::new(RwLock::new(HashMap::new())),
+            limit: None,
+            hooks: Vec::new(),
+            clock: AtomicU64::new(0),
+            evicted: AtomicU64::new(0),
+            spilled: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+    
+    /// Limit the estimated memory taken by datasets, applying the policy when full
+    pub fn with_memory_limit(mut self, max_bytes: usize, policy: MemoryPolicy) -> Self {
+        self.limit = Some(MemoryLimit { max_bytes, policy });
+        self
+    }
+    
+    /// Call a hook for every dataset evicted or spilled to make room
+    pub fn with_eviction_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Eviction) + Send + Sync + 'static,
+    {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+    
+    /// Get the memory occupancy of the storage
+    pub fn usage(&self) -> Result<MemoryUsage, StorageError> {
+        let datasets = self.datasets.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        Ok(MemoryUsage {
+            used_bytes: datasets.values().map(|entry| entry.bytes).sum(),
+            max_bytes: self.limit.as_ref().map(|limit| limit.max_bytes),
+            datasets: datasets.len(),
+            evicted: self.evicted.load(Ordering::Relaxed),
+            spilled: self.spilled.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+        })
+    }
+    
+    /// Write all datasets to another storage, replacing its previous contents
+    ///
+    /// Used to persist an in-memory server to disk on shutdown. Spilled
+    /// datasets are written too. Returns the number of datasets written.
+    pub fn snapshot(&self, target: &dyn DataStorage) -> Result<usize, StorageError> {
+        let datasets = self.datasets.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        let spilled = match self.spill() {
+            Some(spill) => spill.list()?,
+            None => Vec::new(),
+        };
+        
+        // Remove datasets deleted since the last snapshot
+        for name in target.list()? {
+            if !datasets.contains_key(&name) && !spilled.contains(&name) {
+                target.delete(&name)?;
+            }
+        }
+        
+        for (name, entry) in datasets.iter() {
+            target.store(name, &entry.data)?;
+        }
+        
+        if let Some(spill) = self.spill() {
+            for name in &spilled {
+                target.store(name, &spill.load(name)?)?;
+            }
+        }
+        
+        Ok(datasets.len() + spilled.len())
+    }
+    
+    /// Load all datasets from another storage
+    ///
+    /// Used to warm start an in-memory server from its last snapshot. Datasets
+    /// are stored as usual, so the memory limit applies. Returns the number
+    /// of datasets loaded.
+    pub fn restore(&self, source: &dyn DataStorage) -> Result<usize, StorageError> {
+        let names = source.list()?;
+        
+        for name in &names {
+            self.store(name, &source.load(name)?)?;
+        }
+        
+        Ok(names.len())
+    }
+    
+    /// Get the storage datasets are spilled to, if any
+    fn spill(&self) -> Option<&Arc<dyn DataStorage + Send + Sync>> {
+        match &self.limit {
+            Some(MemoryLimit { policy: MemoryPolicy::Spill(spill), .. }) => Some(spill),
+            _ => None,
+        }
+    }
+    
+    /// Check if a dataset was spilled
+    fn is_spilled(&self, name: &str) -> Result<bool, StorageError> {
+        match self.spill() {
+            Some(spill) => spill.exists(name),
+            None => Ok(false),
+        }
+    }
+    
+    /// Mark a dataset as just used, for least recently used eviction
+    fn touch(&self, entry: &Entry) {
+        entry.last_used.store(self.clock.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+    }
+    
+    /// Report a dataset removed to make room
+    fn report_eviction(&self, name: &str, bytes: usize, spilled: bool) {
+        if spilled {
+            self.spilled.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.evicted.fetch_add(1, Ordering::Relaxed);
+        }
+        
+        log::info!("{} dataset '{}' ({} bytes) to stay within the memory limit",
+            if spilled { "Spilled" } else { "Evicted" }, name, bytes);
+        
+        let eviction = Eviction { name: name.to_string(), bytes, spilled };
+        for hook in &self.hooks {
+            hook(&eviction);
         }
     }
+    
+    /// Remove least recently used datasets other than `name` until `bytes` more fit
+    fn make_room(&self, datasets: &mut HashMap<String, Entry>, name: &str, bytes: usize) -> Result<(), StorageError> {
+        let limit = match &self.limit {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        
+        let mut used: usize = datasets.iter()
+            .filter(|(other, _)| other.as_str() != name)
+            .map(|(_, entry)| entry.bytes)
+            .sum();
+        
+        while used + bytes > limit.max_bytes {
+            let victim = match limit.policy {
+                MemoryPolicy::Reject => None,
+                _ => datasets.iter()
+                    .filter(|(other, _)| other.as_str() != name)
+                    .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+                    .map(|(other, _)| other.clone()),
+            };
+            
+            let victim = match victim {
+                Some(victim) => victim,
+                None => {
+                    self.rejected.fetch_add(1, Ordering::Relaxed);
+                    return Err(StorageError::Full(format!(
+                        "Dataset '{}' of {} bytes does not fit in the memory limit of {} bytes",
+                        name, bytes, limit.max_bytes
+                    )));
+                },
+            };
+            
+            let entry = match datasets.remove(&victim) {
+                Some(entry) => entry,
+                None => break,
+            };
+            
+            if let MemoryPolicy::Spill(spill) = &limit.policy {
+                if let Err(err) = spill.store(&victim, &entry.data) {
+                    datasets.insert(victim, entry);
+                    return Err(err);
+                }
+            }
+            
+            used -= entry.bytes;
+            self.report_eviction(&victim, entry.bytes, self.spill().is_some());
+        }
+        
+        Ok(())
+    }
 }
 
 impl Default for MemoryStorage {
@@ -29,11 +268,35 @@ impl Default for MemoryStorage {
 
 impl DataStorage for MemoryStorage {
     fn store(&self, name: &str, data: &DataSet) -> Result<(), StorageError> {
+        let bytes = dataset_bytes(data);
+        
         let mut datasets = self.datasets.write().map_err(|_| {
             StorageError::Other("Failed to acquire write lock".to_string())
         })?;
         
-        datasets.insert(name.to_string(), data.clone());
+        // A dataset larger than the whole limit goes straight to the spill storage
+        if let (Some(limit), Some(spill)) = (&self.limit, self.spill()) {
+            if bytes > limit.max_bytes {
+                spill.store(name, data)?;
+                datasets.remove(name);
+                self.report_eviction(name, bytes, true);
+                return Ok(());
+            }
+        }
+        
+        self.make_room(&mut datasets, name, bytes)?;
+        
+        let entry = Entry { data: data.clone(), bytes, last_used: AtomicU64::new(0) };
+        self.touch(&entry);
+        datasets.insert(name.to_string(), entry);
+        
+        // Drop an older spilled copy, which would otherwise come back after a delete
+        if self.is_spilled(name)? {
+            if let Some(spill) = self.spill() {
+                spill.delete(name)?;
+            }
+        }
+        
         Ok(())
     }
     
@@ -42,9 +305,14 @@ impl DataStorage for MemoryStorage {
             StorageError::Other("Failed to acquire read lock".to_string())
         })?;
         
-        datasets.get(name)
-            .cloned()
-            .ok_or_else(|| StorageError::NotFound(name.to_string()))
+        match (datasets.get(name), self.spill()) {
+            (Some(entry), _) => {
+                self.touch(entry);
+                Ok(entry.data.clone())
+            },
+            (None, Some(spill)) => spill.load(name),
+            (None, None) => Err(StorageError::NotFound(name.to_string())),
+        }
     }
     
     fn exists(&self, name: &str) -> Result<bool, StorageError> {
@@ -52,7 +320,7 @@ impl DataStorage for MemoryStorage {
             StorageError::Other("Failed to acquire read lock".to_string())
         })?;
         
-        Ok(datasets.contains_key(name))
+        Ok(datasets.contains_key(name) || self.is_spilled(name)?)
     }
     
     fn delete(&self, name: &str) -> Result<(), StorageError> {
@@ -61,18 +329,127 @@ impl DataStorage for MemoryStorage {
         })?;
         
         if datasets.remove(name).is_none() {
-            return Err(StorageError::NotFound(name.to_string()));
+            return match self.spill() {
+                Some(spill) => spill.delete(name),
+                None => Err(StorageError::NotFound(name.to_string())),
+            };
         }
         
         Ok(())
     }
     
+    fn copy(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        let datasets = self.datasets.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        if datasets.contains_key(to) || self.is_spilled(to)? {
+            return Err(StorageError::AlreadyExists(to.to_string()));
+        }
+        
+        let data = match (datasets.get(from), self.spill()) {
+            (Some(entry), _) => entry.data.clone(),
+            (None, Some(spill)) => spill.load(from)?,
+            (None, None) => return Err(StorageError::NotFound(from.to_string())),
+        };
+        
+        // The copy takes memory of its own, so it is stored under the limit
+        drop(datasets);
+        self.store(to, &data)
+    }
+    
+    fn rename(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        let mut datasets = self.datasets.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        if datasets.contains_key(to) || self.is_spilled(to)? {
+            return Err(StorageError::AlreadyExists(to.to_string()));
+        }
+        
+        // Move the dataset without cloning it, wherever it is
+        match (datasets.remove(from), self.spill()) {
+            (Some(entry), _) => {
+                datasets.insert(to.to_string(), entry);
+                Ok(())
+            },
+            (None, Some(spill)) => spill.rename(from, to),
+            (None, None) => Err(StorageError::NotFound(from.to_string())),
+        }
+    }
+    
     fn list(&self) -> Result<Vec<String>, StorageError> {
         let datasets = self.datasets.read().map_err(|_| {
             StorageError::Other("Failed to acquire read lock".to_string())
         })?;
         
-        Ok(datasets.keys().cloned().collect())
+        let mut names: Vec<String> = datasets.keys().cloned().collect();
+        
+        if let Some(spill) = self.spill() {
+            names.extend(spill.list()?.into_iter().filter(|name| !datasets.contains_key(name)));
+        }
+        
+        Ok(names)
+    }
+    
+    fn load_range(&self, name: &str, offset: usize, limit: usize) -> Result<DataSet, StorageError> {
+        let datasets = self.datasets.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        let entry = match (datasets.get(name), self.spill()) {
+            (Some(entry), _) => entry,
+            (None, Some(spill)) => return spill.load_range(name, offset, limit),
+            (None, None) => return Err(StorageError::NotFound(name.to_string())),
+        };
+        
+        self.touch(entry);
+        let data = &entry.data;
+        
+        // Clone only the requested rows
+        let mut result = DataSet::new(data.schema.clone());
+        result.data = data.data.iter().skip(offset).take(limit).cloned().collect();
+        result.metadata = data.metadata.clone();
+        
+        Ok(result)
     }
+    
+    fn load_columns(&self, name: &str, columns: &[String]) -> Result<DataSet, StorageError> {
+        let datasets = self.datasets.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        match (datasets.get(name), self.spill()) {
+            (Some(entry), _) => {
+                self.touch(entry);
+                project_columns(&entry.data, columns)
+            },
+            (None, Some(spill)) => spill.load_columns(name, columns),
+            (None, None) => Err(StorageError::NotFound(name.to_string())),
+        }
+    }
+    
+    fn describe(&self, status: &mut StorageStatus) -> Result<(), StorageError> {
+        status.backend = "memory".to_string();
+        status.memory = Some(self.usage()?);
+        Ok(())
+    }
+}
+
+/// Estimate the memory taken by the rows of a dataset
+fn dataset_bytes(data: &DataSet) -> usize {
+    data.data.iter()
+        .map(|row| size_of::<Row>() + row.values.iter().map(value_bytes).sum::<usize>())
+        .sum()
 }
 
+/// Estimate the memory taken by a value, including what it points to
+fn value_bytes(value: &Value) -> usize {
+    size_of::<Value>() + match value {
+        Value::String(s) => s.capacity(),
+        Value::Binary(bytes) => bytes.capacity(),
+        Value::Array(values) => values.iter().map(value_bytes).sum(),
+        Value::Map(entries) => entries.iter().map(|(key, value)| key.capacity() + value_bytes(value)).sum(),
+        _ => 0,
+    }
+}
@@ -4,15 +4,45 @@
 mod file;
 mod memory;
 mod cache;
+mod export;
+mod retry;
+mod failover;
+mod protect;
+mod catalog;
+mod maintenance;
+mod encryption;
+mod invalidate;
+mod session;
+mod ingest;
+mod backup;
+mod status;
+mod streaming;
+mod view;
 
 pub use file::*;
 pub use memory::*;
 pub use cache::*;
+pub use export::*;
+pub use retry::*;
+pub use failover::*;
+pub use protect::*;
+pub use catalog::*;
+pub use maintenance::*;
+pub use encryption::*;
+pub use invalidate::*;
+pub use session::*;
+pub use ingest::*;
+pub use backup::*;
+pub use status::*;
+pub use streaming::*;
+pub use view::*;
 
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fmt;
+use std::time::SystemTime;
 
-use crate::data::{DataError, DataSet};
+use crate::data::{ColumnRange, DataError, DataSet, LineageStep, Row, Schema};
 
 /// Represents a data storage
 pub trait DataStorage {
@@ -30,6 +60,227 @@ pub trait DataStorage {
     
     /// List all datasets
     fn list(&self) -> Result<Vec<String>, StorageError>;
+    
+    /// Copy a dataset to a new name
+    ///
+    /// The default implementation loads and stores the dataset.
+    fn copy(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        if self.exists(to)? {
+            return Err(StorageError::AlreadyExists(to.to_string()));
+        }
+        
+        self.store(to, &self.load(from)?)
+    }
+    
+    /// Rename a dataset
+    ///
+    /// The default implementation copies the dataset and deletes the original.
+    fn rename(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.copy(from, to)?;
+        self.delete(from)
+    }
+    
+    /// Load the first rows of a dataset
+    ///
+    /// The default implementation loads the full dataset; backends with
+    /// streaming readers should override it.
+    fn load_head(&self, name: &str, rows: usize) -> Result<DataSet, StorageError> {
+        let mut data = self.load(name)?;
+        data.data.truncate(rows);
+        Ok(data)
+    }
+    
+    /// Load a range of rows of a dataset
+    ///
+    /// The default implementation loads the full dataset.
+    fn load_range(&self, name: &str, offset: usize, limit: usize) -> Result<DataSet, StorageError> {
+        let data = self.load(name)?;
+        
+        let mut result = DataSet::new(data.schema);
+        result.data = data.data.into_iter().skip(offset).take(limit).collect();
+        result.metadata = data.metadata;
+        
+        Ok(result)
+    }
+    
+    /// Load only the given columns of a dataset
+    ///
+    /// The default implementation loads the full dataset.
+    fn load_columns(&self, name: &str, columns: &[String]) -> Result<DataSet, StorageError> {
+        project_columns(&self.load(name)?, columns)
+    }
+    
+    /// Load a dataset, skipping blocks of rows with no values in the given ranges
+    ///
+    /// Rows outside the ranges may still be returned, so callers apply the
+    /// filter the ranges come from as usual. The default implementation
+    /// loads the full dataset; backends keeping column statistics, such as
+    /// Parquet files, should override it.
+    fn load_pruned(&self, name: &str, _ranges: &[ColumnRange]) -> Result<DataSet, StorageError> {
+        self.load(name)
+    }
+    
+    /// Get summary information about a dataset
+    ///
+    /// The default implementation loads the dataset; backends that can read
+    /// counts from file headers or footers should override it.
+    fn info(&self, name: &str) -> Result<DatasetInfo, StorageError> {
+        let data = self.load(name)?;
+        
+        Ok(DatasetInfo {
+            rows: data.len(),
+            columns: data.schema.fields.len(),
+            size_bytes: None,
+            format: "memory".to_string(),
+            last_modified: None,
+        })
+    }
+    
+    /// Check if a dataset is marked immutable
+    fn is_immutable(&self, _name: &str) -> Result<bool, StorageError> {
+        Ok(false)
+    }
+    
+    /// Mark a dataset immutable, or unlock it again
+    ///
+    /// Only storages that track immutability flags support this.
+    fn set_immutable(&self, _name: &str, _immutable: bool) -> Result<(), StorageError> {
+        Err(StorageError::Unsupported(
+            "Storage does not support immutable datasets".to_string()
+        ))
+    }
+    
+    /// List aliases and the datasets they point at
+    fn aliases(&self) -> Result<Vec<(String, String)>, StorageError> {
+        Ok(Vec::new())
+    }
+    
+    /// Create or repoint an alias to a dataset
+    fn create_alias(&self, _alias: &str, _target: &str) -> Result<(), StorageError> {
+        Err(StorageError::Unsupported(
+            "Storage does not support aliases".to_string()
+        ))
+    }
+    
+    /// Remove an alias, leaving its dataset alone
+    fn remove_alias(&self, _alias: &str) -> Result<(), StorageError> {
+        Err(StorageError::Unsupported(
+            "Storage does not support aliases".to_string()
+        ))
+    }
+    
+    /// List views and their definitions
+    fn views(&self) -> Result<Vec<(String, ViewDefinition)>, StorageError> {
+        Ok(Vec::new())
+    }
+    
+    /// Create or redefine a view, read like a dataset of the same name
+    fn create_view(&self, _name: &str, _view: &ViewDefinition) -> Result<(), StorageError> {
+        Err(StorageError::Unsupported(
+            "Storage does not support views".to_string()
+        ))
+    }
+    
+    /// Remove a view, leaving the datasets it reads alone
+    fn remove_view(&self, _name: &str) -> Result<(), StorageError> {
+        Err(StorageError::Unsupported(
+            "Storage does not support views".to_string()
+        ))
+    }
+    
+    /// Get the tags of a dataset
+    fn tags(&self, _name: &str) -> Result<Vec<String>, StorageError> {
+        Ok(Vec::new())
+    }
+    
+    /// Replace the tags of a dataset
+    fn set_tags(&self, _name: &str, _tags: &[String]) -> Result<(), StorageError> {
+        Err(StorageError::Unsupported(
+            "Storage does not support tags".to_string()
+        ))
+    }
+    
+    /// Get the history of each column of a dataset, as recorded when it was stored
+    fn lineage(&self, _name: &str) -> Result<BTreeMap<String, Vec<LineageStep>>, StorageError> {
+        Ok(BTreeMap::new())
+    }
+    
+    /// List deleted datasets that can still be restored
+    fn trash(&self) -> Result<Vec<TrashEntry>, StorageError> {
+        Ok(Vec::new())
+    }
+    
+    /// Restore a deleted dataset from the trash
+    fn restore(&self, _name: &str) -> Result<(), StorageError> {
+        Err(StorageError::Unsupported(
+            "Storage does not support soft deletion".to_string()
+        ))
+    }
+    
+    /// Permanently remove a dataset from the trash, or all of them
+    fn purge(&self, _name: Option<&str>) -> Result<usize, StorageError> {
+        Err(StorageError::Unsupported(
+            "Storage does not support soft deletion".to_string()
+        ))
+    }
+    
+    /// Run housekeeping tasks, adding their results to a report
+    ///
+    /// Wrapping storages do their own work and then delegate to their
+    /// backend; storages without housekeeping keep the default.
+    fn maintain(&self, _report: &mut MaintenanceReport) -> Result<(), StorageError> {
+        Ok(())
+    }
+    
+    /// Describe the storage, adding its layer or backend to a status
+    ///
+    /// Wrapping storages add their layer and then delegate to their backend;
+    /// backends name themselves.
+    fn describe(&self, _status: &mut StorageStatus) -> Result<(), StorageError> {
+        Ok(())
+    }
+    
+    /// Drop every cached dataset, returning how many were dropped
+    ///
+    /// Wrapping storages clear their own cache, if any, and then delegate to
+    /// their backend.
+    fn clear_cache(&self) -> Result<usize, StorageError> {
+        Ok(0)
+    }
+}
+
+/// Project a dataset onto the given columns, in the given order
+pub(crate) fn project_columns(data: &DataSet, columns: &[String]) -> Result<DataSet, StorageError> {
+    // Find column indices
+    let indices = columns.iter()
+        .map(|column| data.schema.fields.iter()
+            .position(|field| &field.name == column)
+            .ok_or_else(|| StorageError::Other(
+                format!("Column '{}' not found", column)
+            )))
+        .collect::<Result<Vec<_>, _>>()?;
+    
+    let fields = indices.iter()
+        .map(|&i| data.schema.fields[i].clone())
+        .collect();
+    
+    let mut result = DataSet::new(Schema::new(fields));
+    result.data = data.data.iter()
+        .map(|row| Row::new(indices.iter().map(|&i| row.values[i].clone()).collect()))
+        .collect();
+    result.metadata = data.metadata.clone();
+    
+    Ok(result)
+}
+
+/// Summary information about a stored dataset
+#[derive(Debug, Clone)]
+pub struct DatasetInfo {
+    pub rows: usize,
+    pub columns: usize,
+    pub size_bytes: Option<u64>,
+    pub format: String,
+    pub last_modified: Option<SystemTime>,
 }
 
 /// Represents an error in the storage module
@@ -40,6 +291,13 @@ pub enum StorageError {
     NotFound(String),
     AlreadyExists(String),
     InvalidFormat(String),
+    ReadOnly(String),
+    /// The storage has no room for the data
+    Full(String),
+    /// The backend cannot serve requests for now, such as behind an open circuit
+    Unavailable(String),
+    /// The storage does not support the operation
+    Unsupported(String),
     Other(String),
 }
 
@@ -51,11 +309,25 @@ impl fmt::Display for StorageError {
             StorageError::NotFound(name) => write!(f, "Dataset '{}' not found", name),
             StorageError::AlreadyExists(name) => write!(f, "Dataset '{}' already exists", name),
             StorageError::InvalidFormat(msg) => write!(f, "Invalid format: {}", msg),
+            StorageError::ReadOnly(msg) => write!(f, "Read-only: {}", msg),
+            StorageError::Full(msg) => write!(f, "Storage full: {}", msg),
+            StorageError::Unavailable(msg) => write!(f, "Unavailable: {}", msg),
+            StorageError::Unsupported(msg) => write!(f, "Not supported: {}", msg),
             StorageError::Other(msg) => write!(f, "Error: {}", msg),
         }
     }
 }
 
+impl StorageError {
+    /// Check if an error is transient (I/O errors and unavailable backends) and worth retrying
+    ///
+    /// Other errors, such as a missing column or an unknown key, fail the
+    /// same way on every attempt.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, StorageError::IoError(_) | StorageError::Unavailable(_))
+    }
+}
+
 impl Error for StorageError {}
 
 impl From<DataError> for StorageError {
@@ -0,0 +1,263 @@
+// Read-only and immutable dataset protection
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::data::{ColumnRange, DataSet, LineageStep};
+use super::{DatasetInfo, DataStorage, MaintenanceReport, StorageError, StorageStatus, TrashEntry, ViewDefinition};
+
+/// Storage wrapper rejecting mutations of protected data
+///
+/// In read-only mode every store and delete is rejected. Otherwise only
+/// datasets marked immutable ("published") are protected; they can be read
+/// but not overwritten or deleted until they are unlocked again.
+pub struct ProtectedStorage {
+    backend: Arc<dyn DataStorage + Send + Sync>,
+    read_only: bool,
+    immutable: RwLock<BTreeSet<String>>,
+    lock_file: Option<PathBuf>,
+}
+
+impl ProtectedStorage {
+    /// Create a new protected storage around a backend
+    pub fn new(backend: Arc<dyn DataStorage + Send + Sync>) -> Self {
+        ProtectedStorage {
+            backend,
+            read_only: false,
+            immutable: RwLock::new(BTreeSet::new()),
+            lock_file: None,
+        }
+    }
+    
+    /// Reject all mutations
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+    
+    /// Persist immutability flags to a JSON file, loading any existing flags
+    pub fn with_lock_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self, StorageError> {
+        let path = path.as_ref().to_path_buf();
+        
+        if path.exists() {
+            let reader = BufReader::new(File::open(&path)?);
+            let names: BTreeSet<String> = serde_json::from_reader(reader)
+                .map_err(|e| StorageError::InvalidFormat(e.to_string()))?;
+            
+            self.immutable = RwLock::new(names);
+        }
+        
+        self.lock_file = Some(path);
+        Ok(self)
+    }
+    
+    /// Check that the storage can be modified
+    fn check_read_only(&self) -> Result<(), StorageError> {
+        if self.read_only {
+            return Err(StorageError::ReadOnly("Storage is in read-only mode".to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    /// Check that a dataset can be modified
+    fn check_writable(&self, name: &str) -> Result<(), StorageError> {
+        self.check_read_only()?;
+        
+        if self.is_immutable(name)? {
+            return Err(StorageError::ReadOnly(format!(
+                "Dataset '{}' is immutable; unlock it before modifying", name
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    /// Resolve an alias of the backend to the dataset it points at
+    fn resolve(&self, name: &str) -> Result<String, StorageError> {
+        Ok(self.backend.aliases()?
+            .into_iter()
+            .find(|(alias, _)| alias == name)
+            .map_or_else(|| name.to_string(), |(_, target)| target))
+    }
+    
+    /// Write the immutability flags to the lock file, if any
+    fn save_locks(&self, names: &BTreeSet<String>) -> Result<(), StorageError> {
+        if let Some(path) = &self.lock_file {
+            let writer = BufWriter::new(File::create(path)?);
+            serde_json::to_writer_pretty(writer, names)
+                .map_err(|e| StorageError::Other(e.to_string()))?;
+        }
+        
+        Ok(())
+    }
+}
+
+impl DataStorage for ProtectedStorage {
+    fn store(&self, name: &str, data: &DataSet) -> Result<(), StorageError> {
+        self.check_writable(name)?;
+        self.backend.store(name, data)
+    }
+    
+    fn load(&self, name: &str) -> Result<DataSet, StorageError> {
+        self.backend.load(name)
+    }
+    
+    fn exists(&self, name: &str) -> Result<bool, StorageError> {
+        self.backend.exists(name)
+    }
+    
+    fn delete(&self, name: &str) -> Result<(), StorageError> {
+        // Deleting an alias leaves its dataset alone
+        if self.backend.aliases()?.iter().any(|(alias, _)| alias == name) {
+            self.check_read_only()?;
+        } else {
+            self.check_writable(name)?;
+        }
+        
+        
+        self.backend.delete(name)
+    }
+    
+    fn list(&self) -> Result<Vec<String>, StorageError> {
+        self.backend.list()
+    }
+    
+    fn copy(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.check_writable(to)?;
+        self.backend.copy(from, to)
+    }
+    
+    fn rename(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.check_writable(from)?;
+        self.check_writable(to)?;
+        self.backend.rename(from, to)
+    }
+    
+    fn load_head(&self, name: &str, rows: usize) -> Result<DataSet, StorageError> {
+        self.backend.load_head(name, rows)
+    }
+    
+    fn load_range(&self, name: &str, offset: usize, limit: usize) -> Result<DataSet, StorageError> {
+        self.backend.load_range(name, offset, limit)
+    }
+    
+    fn load_columns(&self, name: &str, columns: &[String]) -> Result<DataSet, StorageError> {
+        self.backend.load_columns(name, columns)
+    }
+    
+    fn load_pruned(&self, name: &str, ranges: &[ColumnRange]) -> Result<DataSet, StorageError> {
+        self.backend.load_pruned(name, ranges)
+    }
+    
+    fn info(&self, name: &str) -> Result<DatasetInfo, StorageError> {
+        self.backend.info(name)
+    }
+    
+    fn is_immutable(&self, name: &str) -> Result<bool, StorageError> {
+        let immutable = self.immutable.read().map_err(|_| {
+            StorageError::Other("Failed to acquire read lock".to_string())
+        })?;
+        
+        Ok(immutable.contains(&self.resolve(name)?))
+    }
+    
+    fn set_immutable(&self, name: &str, immutable: bool) -> Result<(), StorageError> {
+        self.check_read_only()?;
+        
+        let name = self.resolve(name)?;
+        if !self.backend.exists(&name)? {
+            return Err(StorageError::NotFound(name.to_string()));
+        }
+        
+        let mut names = self.immutable.write().map_err(|_| {
+            StorageError::Other("Failed to acquire write lock".to_string())
+        })?;
+        
+        if immutable {
+            names.insert(name);
+        } else {
+            names.remove(&name);
+        }
+        
+        self.save_locks(&names)
+    }
+    
+    fn aliases(&self) -> Result<Vec<(String, String)>, StorageError> {
+        self.backend.aliases()
+    }
+    
+    fn create_alias(&self, alias: &str, target: &str) -> Result<(), StorageError> {
+        self.check_read_only()?;
+        self.backend.create_alias(alias, target)
+    }
+    
+    fn remove_alias(&self, alias: &str) -> Result<(), StorageError> {
+        self.check_read_only()?;
+        self.backend.remove_alias(alias)
+    }
+    
+    fn views(&self) -> Result<Vec<(String, ViewDefinition)>, StorageError> {
+        self.backend.views()
+    }
+    
+    fn create_view(&self, name: &str, view: &ViewDefinition) -> Result<(), StorageError> {
+        self.check_read_only()?;
+        self.backend.create_view(name, view)
+    }
+    
+    fn remove_view(&self, name: &str) -> Result<(), StorageError> {
+        self.check_read_only()?;
+        self.backend.remove_view(name)
+    }
+    
+    fn tags(&self, name: &str) -> Result<Vec<String>, StorageError> {
+        self.backend.tags(name)
+    }
+    
+    fn set_tags(&self, name: &str, tags: &[String]) -> Result<(), StorageError> {
+        // Tags describe a dataset without changing it, so published datasets can be tagged
+        self.check_read_only()?;
+        self.backend.set_tags(name, tags)
+    }
+    
+    fn lineage(&self, name: &str) -> Result<BTreeMap<String, Vec<LineageStep>>, StorageError> {
+        self.backend.lineage(name)
+    }
+    
+    fn trash(&self) -> Result<Vec<TrashEntry>, StorageError> {
+        self.backend.trash()
+    }
+    
+    fn restore(&self, name: &str) -> Result<(), StorageError> {
+        self.check_read_only()?;
+        self.backend.restore(name)
+    }
+    
+    fn purge(&self, name: Option<&str>) -> Result<usize, StorageError> {
+        self.check_read_only()?;
+        self.backend.purge(name)
+    }
+    
+    fn maintain(&self, report: &mut MaintenanceReport) -> Result<(), StorageError> {
+        // Housekeeping mutates the storage, so only statistics are refreshed
+        if self.read_only {
+            return Ok(());
+        }
+        
+        self.backend.maintain(report)
+    }
+    
+    fn describe(&self, status: &mut StorageStatus) -> Result<(), StorageError> {
+        status.layers.push("protected".to_string());
+        self.backend.describe(status)
+    }
+    
+    fn clear_cache(&self) -> Result<usize, StorageError> {
+        self.backend.clear_cache()
+    }
+}
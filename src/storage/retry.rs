@@ -0,0 +1,267 @@
+// Retry and circuit breaking storage implementation
+// Author: Gabriel Demetrios Lafis
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use crate::data::{ColumnRange, DataSet};
+use super::{DatasetInfo, DataStorage, MaintenanceReport, StorageError, StorageStatus};
+
+/// Retry policy with exponential backoff and jitter
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    pub jitter: bool,
+    /// Stop retrying once an operation has been running this long
+    pub timeout: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: true,
+            timeout: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Get the delay before the given retry attempt (starting at 0)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let base = self.initial_backoff.as_secs_f64() * self.multiplier.powi(attempt as i32);
+        let capped = base.min(self.max_backoff.as_secs_f64());
+        
+        // Full jitter spreads retries from many clients over the whole interval
+        let delay = if self.jitter {
+            rand::thread_rng().gen_range(0.0..=capped)
+        } else {
+            capped
+        };
+        
+        Duration::from_secs_f64(delay)
+    }
+}
+
+/// Circuit breaker state shared by all operations of a storage
+struct CircuitState {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// Whether the trial call of a half-open circuit is running
+    probing: bool,
+}
+
+/// Storage wrapper adding retries with backoff and a circuit breaker
+///
+/// Only transient errors (I/O errors and unavailable backends) are retried;
+/// missing datasets, format errors and other errors fail immediately. After
+/// `failure_threshold` consecutive failed operations the circuit opens and
+/// calls fail fast until `reset_timeout` has passed, when a single trial call
+/// is let through while the others keep failing fast.
+///
+/// Backoff sleeps the calling thread, so calls made from async runtime
+/// threads, such as API request handlers, are not retried; their failures
+/// still count towards the circuit breaker. The wrapped backend manages its
+/// own connections; this layer does not pool them.
+pub struct RetryStorage {
+    backend: Arc<dyn DataStorage + Send + Sync>,
+    policy: RetryPolicy,
+    failure_threshold: Option<u32>,
+    reset_timeout: Duration,
+    circuit: Mutex<CircuitState>,
+}
+
+impl RetryStorage {
+    /// Create a new retry storage around a backend
+    pub fn new(backend: Arc<dyn DataStorage + Send + Sync>, policy: RetryPolicy) -> Self {
+        RetryStorage {
+            backend,
+            policy,
+            failure_threshold: None,
+            reset_timeout: Duration::from_secs(30),
+            circuit: Mutex::new(CircuitState {
+                consecutive_failures: 0,
+                opened_at: None,
+                probing: false,
+            }),
+        }
+    }
+    
+    /// Open the circuit after the given number of consecutive failed operations
+    pub fn with_circuit_breaker(mut self, failure_threshold: u32, reset_timeout: Duration) -> Self {
+        self.failure_threshold = Some(failure_threshold);
+        self.reset_timeout = reset_timeout;
+        self
+    }
+    
+    /// Run an operation with retries, honoring the circuit breaker
+    fn call<T, F>(&self, operation: F) -> Result<T, StorageError>
+    where
+        F: Fn(&dyn DataStorage) -> Result<T, StorageError>,
+    {
+        let trial = self.admit()?;
+        
+        // Sleeping on a runtime thread would stall every request it serves
+        let max_retries = if actix_web::rt::System::try_current().is_some() {
+            0
+        } else {
+            self.policy.max_retries
+        };
+        
+        let started = Instant::now();
+        let mut attempt = 0;
+        
+        let result = loop {
+            match operation(self.backend.as_ref()) {
+                Err(err) if err.is_transient() && attempt < max_retries => {
+                    let delay = self.policy.backoff(attempt);
+                    
+                    if let Some(timeout) = self.policy.timeout {
+                        if started.elapsed() + delay > timeout {
+                            break Err(err);
+                        }
+                    }
+                    
+                    log::warn!("Storage operation failed (attempt {}): {}", attempt + 1, err);
+                    thread::sleep(delay);
+                    attempt += 1;
+                },
+                result => break result,
+            }
+        };
+        
+        self.record(&result, trial);
+        result
+    }
+    
+    /// Check that the circuit lets a call through, returning whether it is the trial call
+    ///
+    /// Once the reset timeout of an open circuit has passed, the first call
+    /// becomes the trial call and the others fail fast until it finishes.
+    fn admit(&self) -> Result<bool, StorageError> {
+        let threshold = match self.failure_threshold {
+            Some(threshold) => threshold,
+            None => return Ok(false),
+        };
+        
+        let mut circuit = self.circuit.lock().map_err(|_| {
+            StorageError::Other("Failed to acquire circuit lock".to_string())
+        })?;
+        
+        let open = match circuit.opened_at {
+            Some(opened_at) if circuit.consecutive_failures >= threshold => {
+                opened_at.elapsed() < self.reset_timeout || circuit.probing
+            },
+            _ => false,
+        };
+        
+        if open {
+            return Err(StorageError::Unavailable(
+                "Circuit open: storage backend is failing".to_string()
+            ));
+        }
+        
+        let trial = circuit.consecutive_failures >= threshold;
+        if trial {
+            circuit.probing = true;
+        }
+        
+        Ok(trial)
+    }
+    
+    /// Record the outcome of an operation in the circuit breaker
+    fn record<T>(&self, result: &Result<T, StorageError>, trial: bool) {
+        if self.failure_threshold.is_none() {
+            return;
+        }
+        
+        if let Ok(mut circuit) = self.circuit.lock() {
+            if trial {
+                circuit.probing = false;
+            }
+            
+            match result {
+                Err(err) if err.is_transient() => {
+                    circuit.consecutive_failures += 1;
+                    circuit.opened_at = Some(Instant::now());
+                },
+                _ => {
+                    circuit.consecutive_failures = 0;
+                    circuit.opened_at = None;
+                },
+            }
+        }
+    }
+}
+
+impl DataStorage for RetryStorage {
+    fn store(&self, name: &str, data: &DataSet) -> Result<(), StorageError> {
+        self.call(|backend| backend.store(name, data))
+    }
+    
+    fn load(&self, name: &str) -> Result<DataSet, StorageError> {
+        self.call(|backend| backend.load(name))
+    }
+    
+    fn exists(&self, name: &str) -> Result<bool, StorageError> {
+        self.call(|backend| backend.exists(name))
+    }
+    
+    fn delete(&self, name: &str) -> Result<(), StorageError> {
+        self.call(|backend| backend.delete(name))
+    }
+    
+    fn list(&self) -> Result<Vec<String>, StorageError> {
+        self.call(|backend| backend.list())
+    }
+    
+    fn copy(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.call(|backend| backend.copy(from, to))
+    }
+    
+    fn rename(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.call(|backend| backend.rename(from, to))
+    }
+    
+    fn load_head(&self, name: &str, rows: usize) -> Result<DataSet, StorageError> {
+        self.call(|backend| backend.load_head(name, rows))
+    }
+    
+    fn load_range(&self, name: &str, offset: usize, limit: usize) -> Result<DataSet, StorageError> {
+        self.call(|backend| backend.load_range(name, offset, limit))
+    }
+    
+    fn load_columns(&self, name: &str, columns: &[String]) -> Result<DataSet, StorageError> {
+        self.call(|backend| backend.load_columns(name, columns))
+    }
+    
+    fn load_pruned(&self, name: &str, ranges: &[ColumnRange]) -> Result<DataSet, StorageError> {
+        self.call(|backend| backend.load_pruned(name, ranges))
+    }
+    
+    fn info(&self, name: &str) -> Result<DatasetInfo, StorageError> {
+        self.call(|backend| backend.info(name))
+    }
+    
+    fn maintain(&self, report: &mut MaintenanceReport) -> Result<(), StorageError> {
+        self.backend.maintain(report)
+    }
+    
+    fn describe(&self, status: &mut StorageStatus) -> Result<(), StorageError> {
+        status.layers.push("retry".to_string());
+        self.backend.describe(status)
+    }
+    
+    fn clear_cache(&self) -> Result<usize, StorageError> {
+        self.backend.clear_cache()
+    }
+}
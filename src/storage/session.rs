@@ -0,0 +1,353 @@
+// Session-scoped temporary datasets
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use rand::RngCore;
+
+use crate::data::{ColumnRange, DataSet, LineageStep};
+use super::{project_columns, DatasetInfo, DataStorage, MaintenanceReport, StorageError, StorageStatus, TrashEntry, ViewDefinition};
+
+/// First character of temporary dataset names
+pub const SESSION_PREFIX: char = '~';
+
+/// Temporary datasets of a session
+struct Session {
+    expires_at: Instant,
+    datasets: HashMap<String, DataSet>,
+    next_id: u64,
+}
+
+/// Storage wrapper keeping session-scoped temporary datasets
+///
+/// Datasets named `~<session>.<name>` are kept in memory instead of in the
+/// backend, are left out of listings, and are dropped with their session
+/// once it has been idle for longer than the TTL. Every other name goes to
+/// the backend unchanged.
+pub struct SessionStorage {
+    backend: Arc<dyn DataStorage + Send + Sync>,
+    sessions: RwLock<HashMap<String, Session>>,
+    ttl: Duration,
+}
+
+impl SessionStorage {
+    /// Create a new session storage around a backend
+    pub fn new(backend: Arc<dyn DataStorage + Send + Sync>, ttl: Duration) -> Self {
+        SessionStorage {
+            backend,
+            sessions: RwLock::new(HashMap::new()),
+            ttl,
+        }
+    }
+    
+    /// Get the time a session may stay idle before it expires
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+    
+    /// Start a new session, returning its id
+    pub fn create_session(&self) -> Result<String, StorageError> {
+        // Drop expired sessions first, so abandoned ones do not pile up
+        self.expire()?;
+        
+        let mut bytes = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let id: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        
+        let mut sessions = self.sessions.write()
+            .map_err(|_| StorageError::Other("Failed to acquire write lock".to_string()))?;
+        
+        sessions.insert(id.clone(), Session {
+            expires_at: Instant::now() + self.ttl,
+            datasets: HashMap::new(),
+            next_id: 1,
+        });
+        
+        Ok(id)
+    }
+    
+    /// End a session, dropping its temporary datasets
+    pub fn end_session(&self, session: &str) -> Result<(), StorageError> {
+        // Check the session is live before removing it
+        self.with_session(session, |_| Ok(()))?;
+        
+        let mut sessions = self.sessions.write()
+            .map_err(|_| StorageError::Other("Failed to acquire write lock".to_string()))?;
+        
+        sessions.remove(session);
+        Ok(())
+    }
+    
+    /// Get the names of the temporary datasets of a session
+    pub fn session_datasets(&self, session: &str) -> Result<Vec<String>, StorageError> {
+        self.with_session(session, |state| {
+            let mut names: Vec<String> = state.datasets.keys()
+                .map(|name| temporary_name(session, name))
+                .collect();
+            
+            names.sort();
+            Ok(names)
+        })
+    }
+    
+    /// Reserve a new automatic name for a temporary dataset of a session
+    pub fn next_temporary_name(&self, session: &str) -> Result<String, StorageError> {
+        self.with_session(session, |state| {
+            let name = temporary_name(session, &format!("result_{}", state.next_id));
+            state.next_id += 1;
+            Ok(name)
+        })
+    }
+    
+    /// Drop sessions idle for longer than the TTL, returning how many were dropped
+    pub fn expire(&self) -> Result<usize, StorageError> {
+        let mut sessions = self.sessions.write()
+            .map_err(|_| StorageError::Other("Failed to acquire write lock".to_string()))?;
+        
+        let now = Instant::now();
+        let before = sessions.len();
+        sessions.retain(|_, state| state.expires_at > now);
+        
+        Ok(before - sessions.len())
+    }
+    
+    /// Run a function on a live session, extending its life
+    ///
+    /// Fails with `StorageError::NotFound` naming the session if it does
+    /// not exist or has expired.
+    fn with_session<T, F>(&self, session: &str, f: F) -> Result<T, StorageError>
+    where
+        F: FnOnce(&mut Session) -> Result<T, StorageError>,
+    {
+        let mut sessions = self.sessions.write()
+            .map_err(|_| StorageError::Other("Failed to acquire write lock".to_string()))?;
+        
+        let now = Instant::now();
+        match sessions.get_mut(session) {
+            Some(state) if state.expires_at > now => {
+                state.expires_at = now + self.ttl;
+                f(state)
+            },
+            Some(_) => {
+                sessions.remove(session);
+                Err(StorageError::NotFound(session.to_string()))
+            },
+            None => Err(StorageError::NotFound(session.to_string())),
+        }
+    }
+    
+    /// Run a function on the temporary dataset with the given name
+    fn with_dataset<T, F>(&self, name: &str, session: &str, local: &str, f: F) -> Result<T, StorageError>
+    where
+        F: FnOnce(&DataSet) -> T,
+    {
+        self.with_session(session, |state| {
+            state.datasets.get(local)
+                .map(f)
+                .ok_or_else(|| StorageError::NotFound(name.to_string()))
+        })
+        .map_err(|err| match err {
+            StorageError::NotFound(_) => StorageError::NotFound(name.to_string()),
+            err => err,
+        })
+    }
+}
+
+/// Get the name of a temporary dataset of a session
+pub fn temporary_name(session: &str, name: &str) -> String {
+    format!("{}{}.{}", SESSION_PREFIX, session, name)
+}
+
+/// Split a temporary dataset name into its session and name within it
+pub fn parse_temporary_name(name: &str) -> Option<(&str, &str)> {
+    name.strip_prefix(SESSION_PREFIX)?.split_once('.')
+}
+
+impl DataStorage for SessionStorage {
+    fn store(&self, name: &str, data: &DataSet) -> Result<(), StorageError> {
+        match parse_temporary_name(name) {
+            Some((session, local)) => self.with_session(session, |state| {
+                state.datasets.insert(local.to_string(), data.clone());
+                Ok(())
+            }),
+            None => self.backend.store(name, data),
+        }
+    }
+    
+    fn load(&self, name: &str) -> Result<DataSet, StorageError> {
+        match parse_temporary_name(name) {
+            Some((session, local)) => self.with_dataset(name, session, local, DataSet::clone),
+            None => self.backend.load(name),
+        }
+    }
+    
+    fn exists(&self, name: &str) -> Result<bool, StorageError> {
+        match parse_temporary_name(name) {
+            Some((session, local)) => match self.with_session(session, |state| Ok(state.datasets.contains_key(local))) {
+                Err(StorageError::NotFound(_)) => Ok(false),
+                result => result,
+            },
+            None => self.backend.exists(name),
+        }
+    }
+    
+    fn delete(&self, name: &str) -> Result<(), StorageError> {
+        match parse_temporary_name(name) {
+            Some((session, local)) => self.with_session(session, |state| {
+                state.datasets.remove(local)
+                    .map(|_| ())
+                    .ok_or_else(|| StorageError::NotFound(name.to_string()))
+            })
+            .map_err(|_| StorageError::NotFound(name.to_string())),
+            None => self.backend.delete(name),
+        }
+    }
+    
+    fn list(&self) -> Result<Vec<String>, StorageError> {
+        // Temporary datasets are not part of the catalog
+        self.backend.list()
+    }
+    
+    fn copy(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        if parse_temporary_name(from).is_none() && parse_temporary_name(to).is_none() {
+            return self.backend.copy(from, to);
+        }
+        
+        // Copying a temporary dataset to a regular name keeps it past its session
+        if self.exists(to)? {
+            return Err(StorageError::AlreadyExists(to.to_string()));
+        }
+        
+        self.store(to, &self.load(from)?)
+    }
+    
+    fn rename(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        if parse_temporary_name(from).is_none() && parse_temporary_name(to).is_none() {
+            return self.backend.rename(from, to);
+        }
+        
+        self.copy(from, to)?;
+        self.delete(from)
+    }
+    
+    fn load_head(&self, name: &str, rows: usize) -> Result<DataSet, StorageError> {
+        match parse_temporary_name(name) {
+            Some(_) => {
+                let mut data = self.load(name)?;
+                data.data.truncate(rows);
+                Ok(data)
+            },
+            None => self.backend.load_head(name, rows),
+        }
+    }
+    
+    fn load_range(&self, name: &str, offset: usize, limit: usize) -> Result<DataSet, StorageError> {
+        match parse_temporary_name(name) {
+            Some(_) => {
+                let mut data = self.load(name)?;
+                data.data = data.data.into_iter().skip(offset).take(limit).collect();
+                Ok(data)
+            },
+            None => self.backend.load_range(name, offset, limit),
+        }
+    }
+    
+    fn load_columns(&self, name: &str, columns: &[String]) -> Result<DataSet, StorageError> {
+        match parse_temporary_name(name) {
+            Some((session, local)) => self.with_dataset(name, session, local, |data| project_columns(data, columns))?,
+            None => self.backend.load_columns(name, columns),
+        }
+    }
+    
+    fn load_pruned(&self, name: &str, ranges: &[ColumnRange]) -> Result<DataSet, StorageError> {
+        match parse_temporary_name(name) {
+            Some((session, local)) => self.with_dataset(name, session, local, DataSet::clone),
+            None => self.backend.load_pruned(name, ranges),
+        }
+    }
+    
+    fn info(&self, name: &str) -> Result<DatasetInfo, StorageError> {
+        match parse_temporary_name(name) {
+            Some((session, local)) => self.with_dataset(name, session, local, |data| DatasetInfo {
+                rows: data.len(),
+                columns: data.schema.fields.len(),
+                size_bytes: None,
+                format: "memory".to_string(),
+                last_modified: None,
+            }),
+            None => self.backend.info(name),
+        }
+    }
+    
+    fn is_immutable(&self, name: &str) -> Result<bool, StorageError> {
+        self.backend.is_immutable(name)
+    }
+    
+    fn set_immutable(&self, name: &str, immutable: bool) -> Result<(), StorageError> {
+        self.backend.set_immutable(name, immutable)
+    }
+    
+    fn aliases(&self) -> Result<Vec<(String, String)>, StorageError> {
+        self.backend.aliases()
+    }
+    
+    fn create_alias(&self, alias: &str, target: &str) -> Result<(), StorageError> {
+        self.backend.create_alias(alias, target)
+    }
+    
+    fn remove_alias(&self, alias: &str) -> Result<(), StorageError> {
+        self.backend.remove_alias(alias)
+    }
+    
+    fn views(&self) -> Result<Vec<(String, ViewDefinition)>, StorageError> {
+        self.backend.views()
+    }
+    
+    fn create_view(&self, name: &str, view: &ViewDefinition) -> Result<(), StorageError> {
+        self.backend.create_view(name, view)
+    }
+    
+    fn remove_view(&self, name: &str) -> Result<(), StorageError> {
+        self.backend.remove_view(name)
+    }
+    
+    fn tags(&self, name: &str) -> Result<Vec<String>, StorageError> {
+        self.backend.tags(name)
+    }
+    
+    fn set_tags(&self, name: &str, tags: &[String]) -> Result<(), StorageError> {
+        self.backend.set_tags(name, tags)
+    }
+    
+    fn lineage(&self, name: &str) -> Result<BTreeMap<String, Vec<LineageStep>>, StorageError> {
+        self.backend.lineage(name)
+    }
+    
+    fn trash(&self) -> Result<Vec<TrashEntry>, StorageError> {
+        self.backend.trash()
+    }
+    
+    fn restore(&self, name: &str) -> Result<(), StorageError> {
+        self.backend.restore(name)
+    }
+    
+    fn purge(&self, name: Option<&str>) -> Result<usize, StorageError> {
+        self.backend.purge(name)
+    }
+    
+    fn maintain(&self, report: &mut MaintenanceReport) -> Result<(), StorageError> {
+        report.sessions_expired += self.expire()?;
+        self.backend.maintain(report)
+    }
+    
+    fn describe(&self, status: &mut StorageStatus) -> Result<(), StorageError> {
+        status.layers.push("sessions".to_string());
+        self.backend.describe(status)
+    }
+    
+    fn clear_cache(&self) -> Result<usize, StorageError> {
+        self.backend.clear_cache()
+    }
+}
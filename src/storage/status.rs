@@ -0,0 +1,71 @@
+// Storage status for diagnostics
+// Author: Gabriel Demetrios Lafis
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::{DataStorage, MemoryUsage};
+
+/// Dataset held by a cache layer of a storage
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedDataset {
+    pub name: String,
+    pub rows: usize,
+    pub columns: usize,
+    /// Time left before the entry expires, if it expires
+    pub expires_in: Option<Duration>,
+}
+
+/// Description of a storage, its layers and its health
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StorageStatus {
+    /// Backend at the bottom of the layers, such as `file` or `memory`
+    pub backend: String,
+    /// Wrapping layers, outermost first
+    pub layers: Vec<String>,
+    /// Directory of a file-backed storage
+    pub location: Option<PathBuf>,
+    /// Default format of new datasets, for file-backed storages
+    pub format: Option<String>,
+    /// Datasets in the storage
+    pub datasets: usize,
+    /// Total size of the datasets, where the storage reports sizes
+    pub total_bytes: u64,
+    /// Datasets held by cache layers
+    pub cached: Vec<CachedDataset>,
+    /// Memory occupancy of a memory storage
+    pub memory: Option<MemoryUsage>,
+    /// Whether the storage could be described and listed
+    pub healthy: bool,
+    /// Error that made the storage unhealthy
+    pub error: Option<String>,
+}
+
+/// Describe a storage and check that it can list its datasets
+///
+/// Each layer adds itself through `DataStorage::describe`. Failures are
+/// reported in the status rather than returned, since a failing storage is
+/// exactly what the status is asked for.
+pub fn storage_status(storage: &dyn DataStorage) -> StorageStatus {
+    let mut status = StorageStatus::default();
+    
+    let result = storage.describe(&mut status).and_then(|_| {
+        let names = storage.list()?;
+        status.datasets = names.len();
+        
+        for name in &names {
+            if let Some(size) = storage.info(name)?.size_bytes {
+                status.total_bytes += size;
+            }
+        }
+        
+        Ok(())
+    });
+    
+    match result {
+        Ok(()) => status.healthy = true,
+        Err(err) => status.error = Some(err.to_string()),
+    }
+    
+    status
+}
@@ -0,0 +1,309 @@
+// Checkpointed, exactly-once writes of streaming batches
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::DataSet;
+use super::{merge_rows, DataStorage, StorageError};
+
+/// Position of a streaming source: the offset of the next record to read
+/// in each partition
+pub type Offsets = BTreeMap<String, u64>;
+
+/// Write of a batch that was started but not yet committed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingCommit {
+    offsets: Offsets,
+    rows_before: usize,
+    rows_after: usize,
+}
+
+/// Progress of a streaming job
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Offsets up to which batches have been written
+    pub offsets: Offsets,
+    /// Batches written
+    pub batches: u64,
+    /// Rows written
+    pub rows: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pending: Option<PendingCommit>,
+}
+
+impl Checkpoint {
+    /// Check if a batch ending at the given offsets has already been written
+    ///
+    /// Batches without offsets are never considered written.
+    pub fn covers(&self, offsets: &Offsets) -> bool {
+        !offsets.is_empty() && offsets.iter().all(|(partition, offset)| {
+            self.offsets.get(partition).map_or(false, |committed| offset <= committed)
+        })
+    }
+}
+
+/// Checkpoints of streaming jobs, by job name
+///
+/// Checkpoints are kept in memory, or in a JSON file that is replaced
+/// atomically on every change so a crash never leaves it half written.
+pub struct CheckpointStore {
+    path: Option<PathBuf>,
+    checkpoints: Mutex<BTreeMap<String, Checkpoint>>,
+}
+
+impl CheckpointStore {
+    /// Create a store keeping checkpoints in memory
+    pub fn new() -> Self {
+        CheckpointStore {
+            path: None,
+            checkpoints: Mutex::new(BTreeMap::new()),
+        }
+    }
+    
+    /// Open a store persisting checkpoints to a JSON file, loading any existing checkpoints
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, StorageError> {
+        let path = path.as_ref().to_path_buf();
+        let mut checkpoints = BTreeMap::new();
+        
+        if path.exists() {
+            let reader = BufReader::new(File::open(&path)?);
+            checkpoints = serde_json::from_reader(reader)
+                .map_err(|e| StorageError::InvalidFormat(e.to_string()))?;
+        }
+        
+        Ok(CheckpointStore {
+            path: Some(path),
+            checkpoints: Mutex::new(checkpoints),
+        })
+    }
+    
+    /// Get the checkpoint of a job, empty if it has none
+    pub fn checkpoint(&self, job: &str) -> Result<Checkpoint, StorageError> {
+        Ok(self.lock()?.get(job).cloned().unwrap_or_default())
+    }
+    
+    /// Forget the checkpoint of a job, so it starts over
+    pub fn reset(&self, job: &str) -> Result<(), StorageError> {
+        self.update(job, |checkpoint| *checkpoint = Checkpoint::default())
+    }
+    
+    /// Change the checkpoint of a job and persist it
+    fn update<F>(&self, job: &str, change: F) -> Result<(), StorageError>
+    where
+        F: FnOnce(&mut Checkpoint),
+    {
+        let mut checkpoints = self.lock()?;
+        let mut updated = checkpoints.clone();
+        change(updated.entry(job.to_string()).or_default());
+        
+        // Only take the change once it is persisted
+        self.save(&updated)?;
+        *checkpoints = updated;
+        Ok(())
+    }
+    
+    /// Lock the checkpoints
+    fn lock(&self) -> Result<MutexGuard<'_, BTreeMap<String, Checkpoint>>, StorageError> {
+        self.checkpoints.lock().map_err(|_| {
+            StorageError::Other("Failed to acquire checkpoint lock".to_string())
+        })
+    }
+    
+    /// Write the checkpoints to the file, if any, through a temporary file
+    fn save(&self, checkpoints: &BTreeMap<String, Checkpoint>) -> Result<(), StorageError> {
+        if let Some(path) = &self.path {
+            let json = serde_json::to_vec_pretty(checkpoints)
+                .map_err(|e| StorageError::Other(e.to_string()))?;
+            
+            let mut tmp_path = path.as_os_str().to_owned();
+            tmp_path.push(".tmp");
+            
+            fs::write(&tmp_path, json)?;
+            fs::rename(&tmp_path, path)?;
+        }
+        
+        Ok(())
+    }
+}
+
+impl Default for CheckpointStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sink writing batches of a streaming job to a dataset exactly once
+///
+/// Each batch is written together with the source offsets it ends at. The
+/// write is recorded in the job's checkpoint before the dataset is stored
+/// and committed after, so a restarted job can tell whether a batch
+/// interrupted by a crash landed: it is then committed, and otherwise
+/// dropped so the source delivers it again. Batches at or before the
+/// committed offsets are skipped, so replays after a restart are not
+/// written twice. Jobs resume reading from `recover`'s offsets.
+///
+/// Sources without offsets, such as MQTT, get exactly-once writes through
+/// key columns instead: rows replace the rows with the same keys, so
+/// messages delivered again overwrite their earlier copies. The sink must
+/// be the only writer of its dataset.
+pub struct TransactionalSink {
+    storage: Arc<dyn DataStorage + Send + Sync>,
+    checkpoints: Arc<CheckpointStore>,
+    dataset: String,
+    job: String,
+    key_columns: Vec<String>,
+    lock: Mutex<()>,
+}
+
+impl TransactionalSink {
+    /// Create a sink appending the batches of a job to a dataset
+    pub fn new(storage: Arc<dyn DataStorage + Send + Sync>, checkpoints: Arc<CheckpointStore>, dataset: &str, job: &str) -> Self {
+        TransactionalSink {
+            storage,
+            checkpoints,
+            dataset: dataset.to_string(),
+            job: job.to_string(),
+            key_columns: Vec::new(),
+            lock: Mutex::new(()),
+        }
+    }
+    
+    /// Replace rows with the same key columns instead of appending them
+    pub fn with_key_columns(mut self, key_columns: &[String]) -> Self {
+        self.key_columns = key_columns.to_vec();
+        self
+    }
+    
+    /// Get the dataset written to
+    pub fn dataset(&self) -> &str {
+        &self.dataset
+    }
+    
+    /// Settle a write interrupted by a crash and get the offsets to resume from
+    pub fn recover(&self) -> Result<Offsets, StorageError> {
+        let _guard = self.guard()?;
+        self.settle()?;
+        Ok(self.checkpoints.checkpoint(&self.job)?.offsets)
+    }
+    
+    /// Write a batch ending at the given source offsets, returning the rows written
+    ///
+    /// Returns 0 without writing when the batch was already written.
+    pub fn commit(&self, batch: &DataSet, offsets: &Offsets) -> Result<usize, StorageError> {
+        let _guard = self.guard()?;
+        self.settle()?;
+        
+        if self.checkpoints.checkpoint(&self.job)?.covers(offsets) {
+            log::info!("Skipping batch of '{}' already written to '{}'", self.job, self.dataset);
+            return Ok(0);
+        }
+        
+        let existing = if self.storage.exists(&self.dataset)? {
+            Some(self.storage.load(&self.dataset)?)
+        } else {
+            None
+        };
+        let rows_before = existing.as_ref().map_or(0, |existing| existing.len());
+        let data = self.combine(existing, batch)?;
+        
+        let pending = PendingCommit { offsets: offsets.clone(), rows_before, rows_after: data.len() };
+        self.checkpoints.update(&self.job, |checkpoint| checkpoint.pending = Some(pending))?;
+        
+        if let Err(err) = self.storage.store(&self.dataset, &data) {
+            // The dataset is unchanged, so the batch can simply be tried again
+            self.checkpoints.update(&self.job, |checkpoint| checkpoint.pending = None)?;
+            return Err(err);
+        }
+        
+        self.checkpoints.update(&self.job, |checkpoint| {
+            checkpoint.pending = None;
+            advance(checkpoint, offsets, batch.len());
+        })?;
+        
+        Ok(batch.len())
+    }
+    
+    /// Lock the sink for one write at a time
+    fn guard(&self) -> Result<MutexGuard<'_, ()>, StorageError> {
+        self.lock.lock().map_err(|_| {
+            StorageError::Other("Failed to acquire sink lock".to_string())
+        })
+    }
+    
+    /// Commit or drop a write the job's checkpoint records as started
+    fn settle(&self) -> Result<(), StorageError> {
+        let checkpoint = self.checkpoints.checkpoint(&self.job)?;
+        let pending = match checkpoint.pending {
+            Some(pending) => pending,
+            None => return Ok(()),
+        };
+        
+        let rows = if self.storage.exists(&self.dataset)? {
+            self.storage.info(&self.dataset)?.rows
+        } else {
+            0
+        };
+        
+        let landed = rows == pending.rows_after && rows != pending.rows_before;
+        
+        // Keyed writes can always be replayed, so when unsure the batch is written again
+        if !landed && rows != pending.rows_before && self.key_columns.is_empty() {
+            return Err(StorageError::Other(format!(
+                "Dataset '{}' has {} rows, expected {} or {}; it was changed outside streaming job '{}'",
+                self.dataset, rows, pending.rows_before, pending.rows_after, self.job
+            )));
+        }
+        
+        log::info!(
+            "Recovered interrupted write of '{}' to '{}': {}",
+            self.job, self.dataset, if landed { "committed" } else { "dropped" }
+        );
+        
+        self.checkpoints.update(&self.job, |checkpoint| {
+            checkpoint.pending = None;
+            
+            if landed {
+                advance(checkpoint, &pending.offsets, pending.rows_after.saturating_sub(pending.rows_before));
+            }
+        })
+    }
+    
+    /// Combine a batch with the dataset's rows
+    fn combine(&self, existing: Option<DataSet>, batch: &DataSet) -> Result<DataSet, StorageError> {
+        let mut existing = match existing {
+            Some(existing) => existing,
+            None => return Ok(batch.clone()),
+        };
+        
+        let mut aligned = DataSet::new(existing.schema.clone());
+        aligned.data = existing.schema.align_rows(&batch.schema, batch.data.clone());
+        
+        if self.key_columns.is_empty() {
+            // Missing columns are filled from their defaults
+            for row in aligned.data {
+                existing.insert_row(row)?;
+            }
+        } else {
+            merge_rows(&mut existing, aligned, &self.key_columns)?;
+        }
+        
+        Ok(existing)
+    }
+}
+
+/// Move a checkpoint past a written batch
+fn advance(checkpoint: &mut Checkpoint, offsets: &Offsets, rows: usize) {
+    for (partition, offset) in offsets {
+        let committed = checkpoint.offsets.entry(partition.clone()).or_insert(0);
+        *committed = (*committed).max(*offset);
+    }
+    
+    checkpoint.batches += 1;
+    checkpoint.rows += rows as u64;
+}
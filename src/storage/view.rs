@@ -0,0 +1,103 @@
+// Logical views over stored datasets
+// Author: Gabriel Demetrios Lafis
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::DataSet;
+use crate::processing::{JoinProcessor, JoinType, Pipeline, PipelineSpec, StepSpec};
+use super::StorageError;
+
+/// Dataset joined to the source of a view
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewJoin {
+    /// Dataset joined on the right, which may be another view
+    pub dataset: String,
+    pub join_type: JoinType,
+    #[serde(default)]
+    pub left_columns: Vec<String>,
+    #[serde(default)]
+    pub right_columns: Vec<String>,
+}
+
+/// Logical view: a pipeline over other datasets, run whenever the view is read
+///
+/// Views hold no rows of their own. Reading one loads its source, joins the
+/// joined dataset if any, and runs the pipeline steps on the result, so
+/// projections and filters of a dataset need not be stored twice. Sources
+/// may be views themselves, as long as no view reads itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViewDefinition {
+    /// Dataset the view reads, which may be another view
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub join: Option<ViewJoin>,
+    /// Steps run on the source, as in a pipeline specification
+    #[serde(default)]
+    pub steps: Vec<StepSpec>,
+}
+
+impl ViewDefinition {
+    /// Create a view of a dataset
+    pub fn new(source: &str) -> Self {
+        ViewDefinition {
+            source: source.to_string(),
+            join: None,
+            steps: Vec::new(),
+        }
+    }
+    
+    /// Join another dataset to the source
+    pub fn with_join(mut self, dataset: &str, join_type: JoinType, left_columns: Vec<String>, right_columns: Vec<String>) -> Self {
+        self.join = Some(ViewJoin {
+            dataset: dataset.to_string(),
+            join_type,
+            left_columns,
+            right_columns,
+        });
+        self
+    }
+    
+    /// Add a step run on the source
+    pub fn step(mut self, step: StepSpec) -> Self {
+        self.steps.push(step);
+        self
+    }
+    
+    /// Get the datasets the view reads
+    pub fn inputs(&self) -> Vec<&str> {
+        std::iter::once(self.source.as_str())
+            .chain(self.join.as_ref().map(|join| join.dataset.as_str()))
+            .collect()
+    }
+    
+    /// Build the pipeline of the view, checking every step
+    pub fn pipeline(&self, name: &str) -> Result<Pipeline, StorageError> {
+        PipelineSpec {
+            name: name.to_string(),
+            seed: None,
+            steps: self.steps.clone(),
+        }
+        .build()
+        .map_err(|e| StorageError::InvalidFormat(format!("Invalid view '{}': {}", name, e)))
+    }
+    
+    /// Compute the rows of the view, loading its inputs with a function
+    pub fn evaluate(&self, name: &str, load: &dyn Fn(&str) -> Result<DataSet, StorageError>) -> Result<DataSet, StorageError> {
+        let failed = |e| StorageError::Other(format!("Cannot read view '{}': {}", name, e));
+        let source = load(&self.source)?;
+        
+        let source = match &self.join {
+            Some(join) => {
+                let processor = match join.join_type {
+                    JoinType::Cross => JoinProcessor::cross(),
+                    join_type => JoinProcessor::new(join_type, join.left_columns.clone(), join.right_columns.clone()),
+                };
+                
+                processor.process_join(&source, &load(&join.dataset)?).map_err(failed)?
+            },
+            None => source,
+        };
+        
+        self.pipeline(name)?.execute_owned(source).map_err(failed)
+    }
+}
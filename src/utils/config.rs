@@ -1,6 +1,7 @@
 // Configuration utilities
 // Author: Gabriel Demetrios Lafis
 
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
@@ -13,6 +14,18 @@ pub struct Config {
     pub server: ServerConfig,
     pub storage: StorageConfig,
     pub logging: LoggingConfig,
+    /// Landing directories ingested into datasets by the server
+    #[serde(default)]
+    pub ingestion: Vec<IngestionConfig>,
+    /// SFTP and FTP servers files are fetched from, by name
+    #[serde(default)]
+    pub sftp: HashMap<String, SftpConfig>,
+    /// Global seed of sampling and generated values, for reproducible runs
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// OpenTelemetry collector traces and metrics are exported to; disabled if unset
+    #[serde(default)]
+    pub telemetry: Option<TelemetryConfig>,
 }
 
 /// Server configuration
@@ -22,6 +35,54 @@ pub struct ServerConfig {
     pub port: u16,
     pub workers: Option<usize>,
     pub enable_cors: bool,
+    pub security: Option<SecurityConfig>,
+    /// Milliseconds a processing request may run before it is cancelled; unlimited if unset
+    pub processing_timeout_ms: Option<u64>,
+    /// Most rows returned inline by dataset and processing responses; unlimited if unset
+    pub max_response_rows: Option<usize>,
+    /// Largest response body in bytes for dataset and processing responses; unlimited if unset
+    pub max_response_bytes: Option<usize>,
+    /// Truncation and redaction of values returned by dataset reads, by dataset name or `*` for the rest
+    #[serde(default)]
+    pub response_policies: HashMap<String, ResponsePolicyConfig>,
+    /// Directory export requests write under; exports are disabled if unset
+    pub export_root: Option<String>,
+}
+
+/// Truncation and redaction of the values of a dataset in responses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponsePolicyConfig {
+    /// Characters strings are cut to; unlimited if unset
+    pub max_string_length: Option<usize>,
+    /// Columns whose values are replaced
+    #[serde(default)]
+    pub redact: Vec<String>,
+    /// Hide binary values rather than sending them base64 encoded
+    #[serde(default = "default_hide_binary")]
+    pub hide_binary: bool,
+}
+
+fn default_hide_binary() -> bool {
+    true
+}
+
+/// API key and row-level security settings
+///
+/// When set, every request must carry an `X-API-Key` header naming a
+/// configured key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityConfig {
+    /// Roles by API key
+    pub api_keys: HashMap<String, String>,
+    /// Roles that manage policies and see all rows
+    #[serde(default = "default_admin_roles")]
+    pub admin_roles: Vec<String>,
+    /// File where row-level security policies are kept
+    pub policy_file: Option<String>,
+}
+
+fn default_admin_roles() -> Vec<String> {
+    vec!["admin".to_string()]
 }
 
 /// Storage configuration
@@ -31,6 +92,221 @@ pub struct StorageConfig {
     pub path: Option<String>,
     pub format: Option<String>,
     pub cache_ttl: Option<u64>,
+    /// Directory where memory storage is snapshotted on shutdown and reloaded on startup
+    pub snapshot_path: Option<String>,
+    /// Budget of memory storage; unlimited if unset
+    pub memory_limit: Option<MemoryLimitConfig>,
+    /// Serve data but reject all mutations
+    #[serde(default)]
+    pub read_only: bool,
+    /// File where immutable ("published") dataset flags are kept
+    pub lock_file: Option<String>,
+    /// File where dataset aliases and the trash are kept
+    pub catalog_file: Option<String>,
+    /// Seconds a deleted dataset stays restorable; kept until purged if unset
+    pub trash_retention: Option<u64>,
+    /// Seconds between background maintenance runs; disabled if unset
+    pub maintenance_interval: Option<u64>,
+    /// Number of processing results kept for repeated requests on unchanged inputs; disabled if unset
+    pub result_cache_entries: Option<usize>,
+    /// Seconds an idle session keeps its temporary datasets; sessions are disabled if unset
+    pub session_ttl: Option<u64>,
+    /// Read file storage datasets through memory maps; needs the `mmap` feature
+    #[serde(default)]
+    pub mmap: bool,
+    /// Write Parquet datasets with each column in the narrowest type holding its values
+    #[serde(default)]
+    pub downcast: bool,
+    pub encryption: Option<EncryptionConfig>,
+    pub retry: Option<RetryConfig>,
+    /// Directory of a secondary file storage serving reads while the primary fails
+    pub failover: Option<FailoverConfig>,
+}
+
+/// Soft limit on the memory taken by memory storage datasets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryLimitConfig {
+    /// Estimated bytes the datasets may take
+    pub max_bytes: usize,
+    /// `reject` (default) fails new stores when full, `evict` drops the least
+    /// recently used datasets and `spill` moves them to `spill_path`
+    #[serde(default = "default_memory_policy")]
+    pub on_full: String,
+    /// Directory of the file storage spilled datasets are moved to
+    pub spill_path: Option<String>,
+}
+
+fn default_memory_policy() -> String {
+    "reject".to_string()
+}
+
+/// Encryption at rest settings for file storage
+///
+/// Keys are `id:base64key` entries separated by commas; the first is used for
+/// new writes and the others only to read files written before a rotation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    /// Keys given inline in the configuration
+    pub keys: Option<String>,
+    /// Environment variable holding the keys, used when no inline keys are given
+    #[serde(default = "default_key_env")]
+    pub key_env: String,
+}
+
+fn default_key_env() -> String {
+    "RDPE_ENCRYPTION_KEYS".to_string()
+}
+
+/// Retry, backoff and circuit breaker settings for storage operations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryConfig {
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    #[serde(default = "default_jitter")]
+    pub jitter: bool,
+    pub timeout_ms: Option<u64>,
+    pub failure_threshold: Option<u32>,
+    #[serde(default = "default_reset_timeout_ms")]
+    pub reset_timeout_ms: u64,
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    100
+}
+
+fn default_max_backoff_ms() -> u64 {
+    5_000
+}
+
+fn default_jitter() -> bool {
+    true
+}
+
+fn default_reset_timeout_ms() -> u64 {
+    30_000
+}
+
+/// Secondary storage mirroring the primary storage
+///
+/// Writes made while the primary fails are kept and replayed on it once a
+/// health check passes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailoverConfig {
+    pub path: String,
+    /// `csv` (default), `json`, `parquet` or `orc`
+    pub format: Option<String>,
+    /// Least time between health checks of a failing primary
+    #[serde(default = "default_check_interval_ms")]
+    pub check_interval_ms: u64,
+    /// Most writes queued for a failing primary before writes are refused
+    #[serde(default = "default_max_pending_writes")]
+    pub max_pending_writes: usize,
+}
+
+fn default_check_interval_ms() -> u64 {
+    5_000
+}
+
+fn default_max_pending_writes() -> usize {
+    10_000
+}
+
+/// Landing directory feeding a dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestionConfig {
+    pub dataset: String,
+    pub landing_dir: String,
+    /// Where ingested files go; `archive/` in the landing directory if unset
+    pub archive_dir: Option<String>,
+    /// Where rejected files go; `quarantine/` in the landing directory if unset
+    pub quarantine_dir: Option<String>,
+    /// `append` (default), `replace` or `merge`
+    #[serde(default = "default_ingest_mode")]
+    pub mode: String,
+    /// Columns matching rows on for `merge`
+    #[serde(default)]
+    pub key_columns: Vec<String>,
+    /// `block` (default) quarantines files whose schema drifts from the
+    /// dataset's, `notify` ingests them unless the drift is breaking
+    #[serde(default = "default_drift_policy")]
+    pub on_drift: String,
+}
+
+fn default_ingest_mode() -> String {
+    "append".to_string()
+}
+
+fn default_drift_policy() -> String {
+    "block".to_string()
+}
+
+/// SFTP or FTP server and credentials
+///
+/// Secrets are read from environment variables so they stay out of config files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SftpConfig {
+    /// `sftp` (default) or `ftp`; FTP sends the password in clear text
+    #[serde(default = "default_remote_protocol")]
+    pub protocol: String,
+    pub host: String,
+    /// Port of the server; 22 for SFTP and 21 for FTP if unset
+    pub port: Option<u16>,
+    pub username: String,
+    /// Environment variable holding the password
+    pub password_env: Option<String>,
+    /// Private key file; the SSH agent is used if neither a password nor a key is set
+    pub private_key: Option<String>,
+    /// Environment variable holding the passphrase of the private key
+    pub passphrase_env: Option<String>,
+    /// known_hosts file the SFTP server's key is checked against; `~/.ssh/known_hosts` if unset
+    pub known_hosts: Option<String>,
+    /// SHA-256 fingerprint the SFTP server's key must have, checked instead of a known_hosts file
+    pub host_fingerprint: Option<String>,
+}
+
+fn default_remote_protocol() -> String {
+    "sftp".to_string()
+}
+
+/// OpenTelemetry export settings
+///
+/// Traces and metrics are sent with OTLP over HTTP/JSON; needs the `otlp` feature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    /// Base URL of the collector, such as `http://localhost:4318`
+    pub endpoint: String,
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+    #[serde(default = "default_true")]
+    pub traces: bool,
+    #[serde(default = "default_true")]
+    pub metrics: bool,
+    /// Milliseconds between exports
+    #[serde(default = "default_export_interval_ms")]
+    pub export_interval_ms: u64,
+    /// Headers sent with every export, for example to authenticate
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+fn default_service_name() -> String {
+    "rust-data-processing-engine".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_export_interval_ms() -> u64 {
+    10_000
 }
 
 /// Logging configuration
@@ -48,17 +324,41 @@ impl Default for Config {
                 port: 8080,
                 workers: None,
                 enable_cors: false,
+                security: None,
+                processing_timeout_ms: None,
+                max_response_rows: None,
+                max_response_bytes: None,
+                response_policies: HashMap::new(),
+                export_root: None,
             },
             storage: StorageConfig {
                 type_: "memory".to_string(),
                 path: None,
                 format: None,
                 cache_ttl: None,
+                snapshot_path: None,
+                memory_limit: None,
+                read_only: false,
+                lock_file: None,
+                catalog_file: None,
+                trash_retention: None,
+                maintenance_interval: None,
+                result_cache_entries: None,
+                session_ttl: None,
+                mmap: false,
+                downcast: false,
+                encryption: None,
+                retry: None,
+                failover: None,
             },
             logging: LoggingConfig {
                 level: "info".to_string(),
                 file: None,
             },
+            ingestion: Vec::new(),
+            sftp: HashMap::new(),
+            seed: None,
+            telemetry: None,
         }
     }
 }
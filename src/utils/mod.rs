@@ -5,9 +5,19 @@ mod logging;
 mod config;
 mod error;
 mod validation;
+mod report;
+mod output;
+mod watch;
+mod testing;
+mod telemetry;
 
 pub use logging::*;
 pub use config::*;
 pub use error::*;
 pub use validation::*;
+pub use report::*;
+pub use output::*;
+pub use watch::*;
+pub use testing::*;
+pub use telemetry::*;
 
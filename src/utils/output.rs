@@ -0,0 +1,125 @@
+// Output formats for printing datasets
+// Author: Gabriel Demetrios Lafis
+
+use std::io::Write;
+
+use crate::data::{CsvSink, DataError, DataSet, JsonSink, Value, format_date, format_time, format_timestamp, format_uuid};
+
+/// Format of datasets printed by the command line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// Aligned plain text columns
+    Table,
+    Csv,
+    Json,
+    /// Markdown table, for docs and issue comments
+    Markdown,
+}
+
+impl OutputFormat {
+    /// Parse an output format from a string
+    pub fn from_str(s: &str) -> Result<Self, DataError> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "csv" => Ok(OutputFormat::Csv),
+            "json" => Ok(OutputFormat::Json),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            _ => Err(DataError::ParseError(format!(
+                "Unknown output format: {}. Use table, csv, json or markdown", s
+            ))),
+        }
+    }
+    
+    /// Write a dataset in this format
+    pub fn write<W: Write>(&self, data: &DataSet, mut writer: W) -> Result<(), DataError> {
+        match self {
+            OutputFormat::Csv => CsvSink::new("", ',').write_to(data, writer),
+            OutputFormat::Json => {
+                JsonSink::new("", true).write_to(data, &mut writer)?;
+                writeln!(writer).map_err(DataError::IoError)
+            },
+            OutputFormat::Table | OutputFormat::Markdown => {
+                let markdown = *self == OutputFormat::Markdown;
+                let headers: Vec<String> = data.schema.fields.iter()
+                    .map(|field| cell(&field.name, markdown))
+                    .collect();
+                let rows: Vec<Vec<String>> = data.data.iter()
+                    .map(|row| row.values.iter().map(|value| cell(&text(value), markdown)).collect())
+                    .collect();
+                
+                // Pad every column to its widest cell
+                let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+                for row in &rows {
+                    for (width, value) in widths.iter_mut().zip(row) {
+                        *width = (*width).max(value.chars().count());
+                    }
+                }
+                
+                let line = |cells: &[String]| -> String {
+                    let padded: Vec<String> = cells.iter().zip(&widths)
+                        .map(|(value, width)| format!("{:<width$}", value, width = width))
+                        .collect();
+                    
+                    if markdown {
+                        format!("| {} |", padded.join(" | "))
+                    } else {
+                        padded.join("  ").trim_end().to_string()
+                    }
+                };
+                
+                let rule: Vec<String> = widths.iter()
+                    .map(|width| if markdown { "-".repeat((*width).max(3)) } else { "-".repeat(*width) })
+                    .collect();
+                
+                let mut out = String::new();
+                out.push_str(&line(&headers));
+                out.push('\n');
+                
+                if markdown {
+                    out.push_str(&format!("| {} |\n", rule.join(" | ")));
+                } else {
+                    out.push_str(&rule.join("  "));
+                    out.push('\n');
+                }
+                
+                for row in &rows {
+                    out.push_str(&line(row));
+                    out.push('\n');
+                }
+                
+                writer.write_all(out.as_bytes()).map_err(DataError::IoError)
+            },
+        }
+    }
+}
+
+/// Get the printed text of a value
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Float(f) => f.to_string(),
+        Value::Decimal(d) => d.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Binary(b) => base64::encode(b),
+        Value::Point(lat, lon) => format!("{},{}", lat, lon),
+        Value::Uuid(uuid) => format_uuid(*uuid),
+        Value::Date(date) => format_date(date),
+        Value::Timestamp(timestamp) => format_timestamp(timestamp),
+        Value::Time(time) => format_time(time),
+        Value::Array(_) => "[array]".to_string(),
+        Value::Map(_) => "[map]".to_string(),
+    }
+}
+
+/// Keep a cell on one line, escaping pipes in Markdown tables
+fn cell(text: &str, markdown: bool) -> String {
+    let text = text.replace('\n', " ");
+    
+    if markdown {
+        text.replace('|', "\\|")
+    } else {
+        text
+    }
+}
@@ -0,0 +1,119 @@
+// HTML reports of dataset profiles
+// Author: Gabriel Demetrios Lafis
+
+use std::fmt::Write;
+
+use crate::data::{DataSet, Value};
+use crate::processing::{ColumnProfile, DatasetProfile};
+
+/// Styles of the report, inlined so the file stands alone
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:1.5em}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:left;font-size:13px}\
+th{background:#f0f0f0}\
+.bar{background:#4e79a7;height:12px}\
+.column{margin-bottom:2em}";
+
+/// Render a dataset profile and sample rows as a standalone HTML page
+pub fn render_html_report(name: &str, profile: &DatasetProfile, sample: &DataSet) -> String {
+    let mut html = String::new();
+    let title = escape(name);
+    
+    let _ = write!(
+        html,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Profile of {t}</title><style>{s}</style></head><body>",
+        t = title, s = STYLE,
+    );
+    let _ = write!(
+        html,
+        "<h1>Profile of {}</h1><p>{} rows, {} columns</p>",
+        title, profile.rows, profile.columns.len(),
+    );
+    
+    // Schema with the summary of each column
+    html.push_str("<h2>Schema</h2><table><tr><th>Column</th><th>Type</th><th>Nulls</th><th>Distinct</th>\
+        <th>Min</th><th>Max</th><th>Mean</th><th>Std dev</th></tr>");
+    
+    for column in &profile.columns {
+        let stats = match &column.numeric {
+            Some(n) => format!("<td>{}</td><td>{}</td><td>{:.4}</td><td>{:.4}</td>", n.min, n.max, n.mean, n.std_dev),
+            None => "<td></td><td></td><td></td><td></td>".to_string(),
+        };
+        
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td>{:?}</td><td>{}</td><td>{}</td>{}</tr>",
+            escape(&column.name), column.data_type, column.nulls, column.distinct, stats,
+        );
+    }
+    html.push_str("</table>");
+    
+    html.push_str("<h2>Columns</h2>");
+    for column in &profile.columns {
+        render_column(&mut html, column);
+    }
+    
+    // Sample rows
+    let _ = write!(html, "<h2>Sample rows</h2><p>First {} rows</p><table><tr>", sample.len());
+    for field in &sample.schema.fields {
+        let _ = write!(html, "<th>{}</th>", escape(&field.name));
+    }
+    html.push_str("</tr>");
+    
+    for row in &sample.data {
+        html.push_str("<tr>");
+        for value in &row.values {
+            let _ = write!(html, "<td>{}</td>", escape(&label(value)));
+        }
+        html.push_str("</tr>");
+    }
+    
+    html.push_str("</table></body></html>");
+    html
+}
+
+/// Render the histogram and most frequent values of a column
+fn render_column(html: &mut String, column: &ColumnProfile) {
+    let _ = write!(html, "<div class=\"column\"><h3>{}</h3>", escape(&column.name));
+    
+    if let Some(numeric) = &column.numeric {
+        let largest = numeric.histogram.iter().map(|bin| bin.count).max().unwrap_or(0).max(1);
+        
+        html.push_str("<table><tr><th>Range</th><th>Count</th><th></th></tr>");
+        for bin in &numeric.histogram {
+            let _ = write!(
+                html,
+                "<tr><td>{:.4} &ndash; {:.4}</td><td>{}</td><td style=\"width:300px\"><div class=\"bar\" style=\"width:{:.1}%\"></div></td></tr>",
+                bin.start, bin.end, bin.count, 100.0 * bin.count as f64 / largest as f64,
+            );
+        }
+        html.push_str("</table>");
+    }
+    
+    if !column.top_values.is_empty() {
+        html.push_str("<table><tr><th>Value</th><th>Count</th></tr>");
+        for (value, count) in &column.top_values {
+            let _ = write!(html, "<tr><td>{}</td><td>{}</td></tr>", escape(&label(value)), count);
+        }
+        html.push_str("</table>");
+    }
+    
+    html.push_str("</div>");
+}
+
+/// Get the text of a value in the report
+fn label(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Float(f) => f.to_string(),
+        Value::String(s) => s.clone(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Escape text for HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,551 @@
+// OpenTelemetry export of traces and metrics
+// Author: Gabriel Demetrios Lafis
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rand::Rng;
+use serde_json::{json, Value as JsonValue};
+
+use super::AppError;
+
+/// Spans kept for the next export; later spans are dropped until then
+const MAX_PENDING_SPANS: usize = 2048;
+
+/// Bucket bounds of duration histograms, in seconds
+pub const DURATION_BUCKETS: [f64; 14] = [0.005, 0.01, 0.025, 0.05, 0.075, 0.1, 0.25, 0.5, 0.75, 1.0, 2.5, 5.0, 7.5, 10.0];
+
+/// Role of a span in a trace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    Internal,
+    Server,
+    Client,
+}
+
+impl SpanKind {
+    /// Get the OTLP code of the kind
+    fn code(self) -> u8 {
+        match self {
+            SpanKind::Internal => 1,
+            SpanKind::Server => 2,
+            SpanKind::Client => 3,
+        }
+    }
+}
+
+/// Value of a span attribute
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    String(String),
+    Int(i64),
+    Double(f64),
+    Bool(bool),
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        AttributeValue::String(value.to_string())
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        AttributeValue::String(value)
+    }
+}
+
+impl From<i64> for AttributeValue {
+    fn from(value: i64) -> Self {
+        AttributeValue::Int(value)
+    }
+}
+
+impl From<f64> for AttributeValue {
+    fn from(value: f64) -> Self {
+        AttributeValue::Double(value)
+    }
+}
+
+impl From<bool> for AttributeValue {
+    fn from(value: bool) -> Self {
+        AttributeValue::Bool(value)
+    }
+}
+
+impl AttributeValue {
+    /// Get the OTLP JSON form of the value
+    fn to_otlp(&self) -> JsonValue {
+        match self {
+            AttributeValue::String(value) => json!({ "stringValue": value }),
+            // 64-bit integers are strings in OTLP JSON
+            AttributeValue::Int(value) => json!({ "intValue": value.to_string() }),
+            AttributeValue::Double(value) => json!({ "doubleValue": value }),
+            AttributeValue::Bool(value) => json!({ "boolValue": value }),
+        }
+    }
+}
+
+/// Timed operation of a trace
+#[derive(Debug, Clone)]
+pub struct Span {
+    trace_id: u128,
+    span_id: u64,
+    parent_span_id: Option<u64>,
+    name: String,
+    kind: SpanKind,
+    start: SystemTime,
+    end: Option<SystemTime>,
+    attributes: Vec<(String, AttributeValue)>,
+    error: Option<String>,
+}
+
+impl Span {
+    /// Start a span of a new trace
+    pub fn start(name: &str, kind: SpanKind) -> Self {
+        let mut rng = rand::thread_rng();
+        
+        Span {
+            trace_id: rng.gen_range(1..=u128::MAX),
+            span_id: rng.gen_range(1..=u64::MAX),
+            parent_span_id: None,
+            name: name.to_string(),
+            kind,
+            start: SystemTime::now(),
+            end: None,
+            attributes: Vec::new(),
+            error: None,
+        }
+    }
+    
+    /// Make the span a child of a span, joining its trace
+    pub fn with_parent(mut self, trace_id: u128, parent_span_id: u64) -> Self {
+        self.trace_id = trace_id;
+        self.parent_span_id = Some(parent_span_id);
+        self
+    }
+    
+    /// Start a child span of this span
+    pub fn child(&self, name: &str, kind: SpanKind) -> Span {
+        Span::start(name, kind).with_parent(self.trace_id, self.span_id)
+    }
+    
+    /// Set an attribute of the span
+    pub fn set_attribute<V: Into<AttributeValue>>(&mut self, key: &str, value: V) {
+        let value = value.into();
+        
+        match self.attributes.iter_mut().find(|(k, _)| k == key) {
+            Some((_, existing)) => *existing = value,
+            None => self.attributes.push((key.to_string(), value)),
+        }
+    }
+    
+    /// Rename the span, for example once the route of a request is known
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+    
+    /// Mark the span as failed
+    pub fn set_error(&mut self, message: &str) {
+        self.error = Some(message.to_string());
+    }
+    
+    /// End the span now, unless it has already ended
+    pub fn end(&mut self) {
+        if self.end.is_none() {
+            self.end = Some(SystemTime::now());
+        }
+    }
+    
+    /// Get the ID of the span's trace
+    pub fn trace_id(&self) -> u128 {
+        self.trace_id
+    }
+    
+    /// Get the ID of the span
+    pub fn span_id(&self) -> u64 {
+        self.span_id
+    }
+    
+    /// Get the W3C `traceparent` header value propagating this span
+    pub fn traceparent(&self) -> String {
+        format!("00-{:032x}-{:016x}-01", self.trace_id, self.span_id)
+    }
+    
+    /// Get the OTLP JSON form of the span
+    fn to_otlp(&self) -> JsonValue {
+        let mut span = json!({
+            "traceId": format!("{:032x}", self.trace_id),
+            "spanId": format!("{:016x}", self.span_id),
+            "name": self.name,
+            "kind": self.kind.code(),
+            "startTimeUnixNano": unix_nanos(self.start),
+            "endTimeUnixNano": unix_nanos(self.end.unwrap_or_else(SystemTime::now)),
+            "attributes": otlp_attributes(self.attributes.iter().map(|(k, v)| (k.as_str(), v.to_otlp()))),
+        });
+        
+        if let Some(parent) = self.parent_span_id {
+            span["parentSpanId"] = json!(format!("{:016x}", parent));
+        }
+        
+        if let Some(message) = &self.error {
+            span["status"] = json!({ "code": 2, "message": message });
+        }
+        
+        span
+    }
+}
+
+/// Parse a W3C `traceparent` header into its trace and parent span IDs
+///
+/// Returns `None` for unsupported versions and malformed or all-zero IDs.
+pub fn parse_traceparent(value: &str) -> Option<(u128, u64)> {
+    let mut parts = value.trim().split('-');
+    let (version, trace_id, span_id, _flags) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    
+    if version != "00" || trace_id.len() != 32 || span_id.len() != 16 {
+        return None;
+    }
+    
+    let trace_id = u128::from_str_radix(trace_id, 16).ok().filter(|&id| id != 0)?;
+    let span_id = u64::from_str_radix(span_id, 16).ok().filter(|&id| id != 0)?;
+    
+    Some((trace_id, span_id))
+}
+
+/// Attributes identifying a metric series
+type SeriesKey = Vec<(String, String)>;
+
+/// Histogram of recorded values with fixed bucket bounds
+#[derive(Debug, Clone)]
+struct Histogram {
+    bounds: Vec<f64>,
+    counts: Vec<u64>,
+    count: u64,
+    sum: f64,
+}
+
+/// Metric with its series by attributes
+#[derive(Debug, Clone)]
+enum Metric {
+    Counter { unit: String, series: BTreeMap<SeriesKey, u64> },
+    Histogram { unit: String, series: BTreeMap<SeriesKey, Histogram> },
+    Gauge { unit: String, value: i64 },
+}
+
+/// Gauge read by a callback at each export
+struct ObservedGauge {
+    name: String,
+    unit: String,
+    observe: Box<dyn Fn() -> i64 + Send + Sync>,
+}
+
+/// Exporter of traces and metrics to an OpenTelemetry collector
+///
+/// Spans and metrics are buffered in memory and sent with OTLP over
+/// HTTP/JSON to the collector's `/v1/traces` and `/v1/metrics` endpoints,
+/// either periodically by `start` or on demand by `export`. Counters and
+/// histograms are cumulative since the telemetry was created; gauges are
+/// read when exported. Sending requires the `otlp` feature; without it
+/// telemetry is still collected but exports fail.
+pub struct Telemetry {
+    endpoint: String,
+    service_name: String,
+    headers: Vec<(String, String)>,
+    traces: bool,
+    metrics: bool,
+    started: SystemTime,
+    spans: Mutex<Vec<Span>>,
+    dropped_spans: Mutex<u64>,
+    series: Mutex<BTreeMap<String, Metric>>,
+    gauges: Mutex<Vec<ObservedGauge>>,
+}
+
+impl Telemetry {
+    /// Create telemetry exported to the collector at a base URL, such as `http://localhost:4318`
+    pub fn new(endpoint: &str, service_name: &str) -> Self {
+        Telemetry {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            service_name: service_name.to_string(),
+            headers: Vec::new(),
+            traces: true,
+            metrics: true,
+            started: SystemTime::now(),
+            spans: Mutex::new(Vec::new()),
+            dropped_spans: Mutex::new(0),
+            series: Mutex::new(BTreeMap::new()),
+            gauges: Mutex::new(Vec::new()),
+        }
+    }
+    
+    /// Send a header with every export, for example to authenticate
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+    
+    /// Enable or disable the export of traces
+    pub fn with_traces(mut self, enabled: bool) -> Self {
+        self.traces = enabled;
+        self
+    }
+    
+    /// Enable or disable the export of metrics
+    pub fn with_metrics(mut self, enabled: bool) -> Self {
+        self.metrics = enabled;
+        self
+    }
+    
+    /// Keep an ended span for the next export
+    pub fn record_span(&self, mut span: Span) {
+        if !self.traces {
+            return;
+        }
+        
+        span.end();
+        
+        let mut spans = self.spans.lock().unwrap_or_else(|e| e.into_inner());
+        if spans.len() < MAX_PENDING_SPANS {
+            spans.push(span);
+        } else {
+            *self.dropped_spans.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+        }
+    }
+    
+    /// Add to a counter
+    pub fn add_counter(&self, name: &str, unit: &str, attributes: &[(&str, &str)], value: u64) {
+        if !self.metrics {
+            return;
+        }
+        
+        let mut series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        let metric = series.entry(name.to_string()).or_insert_with(|| Metric::Counter {
+            unit: unit.to_string(),
+            series: BTreeMap::new(),
+        });
+        
+        if let Metric::Counter { series, .. } = metric {
+            *series.entry(series_key(attributes)).or_insert(0) += value;
+        }
+    }
+    
+    /// Record a value in a histogram with the given bucket bounds
+    pub fn record_histogram(&self, name: &str, unit: &str, bounds: &[f64], attributes: &[(&str, &str)], value: f64) {
+        if !self.metrics {
+            return;
+        }
+        
+        let mut series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        let metric = series.entry(name.to_string()).or_insert_with(|| Metric::Histogram {
+            unit: unit.to_string(),
+            series: BTreeMap::new(),
+        });
+        
+        if let Metric::Histogram { series, .. } = metric {
+            let histogram = series.entry(series_key(attributes)).or_insert_with(|| Histogram {
+                bounds: bounds.to_vec(),
+                counts: vec![0; bounds.len() + 1],
+                count: 0,
+                sum: 0.0,
+            });
+            
+            let bucket = histogram.bounds.iter().position(|&bound| value <= bound).unwrap_or(histogram.bounds.len());
+            histogram.counts[bucket] += 1;
+            histogram.count += 1;
+            histogram.sum += value;
+        }
+    }
+    
+    /// Report a gauge, such as an occupancy, read by a callback at each export
+    pub fn observe_gauge<F>(&self, name: &str, unit: &str, observe: F)
+    where
+        F: Fn() -> i64 + Send + Sync + 'static,
+    {
+        if !self.metrics {
+            return;
+        }
+        
+        self.gauges.lock().unwrap_or_else(|e| e.into_inner()).push(ObservedGauge {
+            name: name.to_string(),
+            unit: unit.to_string(),
+            observe: Box::new(observe),
+        });
+    }
+    
+    /// Send the pending spans and the current metrics to the collector
+    ///
+    /// Spans are dropped once sent, or if the collector rejects them, so a
+    /// collector outage does not grow the buffer without bound.
+    pub fn export(&self) -> Result<(), AppError> {
+        let dropped = std::mem::take(&mut *self.dropped_spans.lock().unwrap_or_else(|e| e.into_inner()));
+        if dropped > 0 {
+            log::warn!("Dropped {} spans while the export buffer was full", dropped);
+        }
+        
+        let spans = std::mem::take(&mut *self.spans.lock().unwrap_or_else(|e| e.into_inner()));
+        if !spans.is_empty() {
+            self.send("v1/traces", &self.traces_body(&spans))?;
+        }
+        
+        let mut metrics = self.series.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        for gauge in self.gauges.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            metrics.insert(gauge.name.clone(), Metric::Gauge { unit: gauge.unit.clone(), value: (gauge.observe)() });
+        }
+        
+        if !metrics.is_empty() {
+            self.send("v1/metrics", &self.metrics_body(&metrics))?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Export on a background thread at a fixed interval
+    pub fn start(self: Arc<Self>, interval: Duration) {
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            
+            if let Err(err) = self.export() {
+                log::error!("Error exporting telemetry: {}", err);
+            }
+        });
+    }
+    
+    /// Get the OTLP resource describing this service
+    fn resource(&self) -> JsonValue {
+        json!({
+            "attributes": otlp_attributes([
+                ("service.name", json!({ "stringValue": self.service_name })),
+                ("service.version", json!({ "stringValue": env!("CARGO_PKG_VERSION") })),
+            ]),
+        })
+    }
+    
+    /// Get the OTLP instrumentation scope of the engine
+    fn scope() -> JsonValue {
+        json!({ "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") })
+    }
+    
+    /// Build the body of a trace export
+    fn traces_body(&self, spans: &[Span]) -> JsonValue {
+        json!({
+            "resourceSpans": [{
+                "resource": self.resource(),
+                "scopeSpans": [{
+                    "scope": Self::scope(),
+                    "spans": spans.iter().map(Span::to_otlp).collect::<Vec<_>>(),
+                }],
+            }],
+        })
+    }
+    
+    /// Build the body of a metrics export
+    fn metrics_body(&self, metrics: &BTreeMap<String, Metric>) -> JsonValue {
+        let start = unix_nanos(self.started);
+        let now = unix_nanos(SystemTime::now());
+        
+        let metrics: Vec<JsonValue> = metrics.iter().map(|(name, metric)| match metric {
+            Metric::Counter { unit, series } => json!({
+                "name": name,
+                "unit": unit,
+                "sum": {
+                    // Cumulative temporality
+                    "aggregationTemporality": 2,
+                    "isMonotonic": true,
+                    "dataPoints": series.iter().map(|(key, value)| json!({
+                        "attributes": series_attributes(key),
+                        "startTimeUnixNano": start,
+                        "timeUnixNano": now,
+                        "asInt": value.to_string(),
+                    })).collect::<Vec<_>>(),
+                },
+            }),
+            Metric::Histogram { unit, series } => json!({
+                "name": name,
+                "unit": unit,
+                "histogram": {
+                    "aggregationTemporality": 2,
+                    "dataPoints": series.iter().map(|(key, histogram)| json!({
+                        "attributes": series_attributes(key),
+                        "startTimeUnixNano": start,
+                        "timeUnixNano": now,
+                        "count": histogram.count.to_string(),
+                        "sum": histogram.sum,
+                        "bucketCounts": histogram.counts.iter().map(u64::to_string).collect::<Vec<_>>(),
+                        "explicitBounds": histogram.bounds,
+                    })).collect::<Vec<_>>(),
+                },
+            }),
+            Metric::Gauge { unit, value } => json!({
+                "name": name,
+                "unit": unit,
+                "gauge": {
+                    "dataPoints": [{
+                        "timeUnixNano": now,
+                        "asInt": value.to_string(),
+                    }],
+                },
+            }),
+        }).collect();
+        
+        json!({
+            "resourceMetrics": [{
+                "resource": self.resource(),
+                "scopeMetrics": [{
+                    "scope": Self::scope(),
+                    "metrics": metrics,
+                }],
+            }],
+        })
+    }
+    
+    /// Post an export body to a path of the collector
+    #[cfg(feature = "otlp")]
+    fn send(&self, path: &str, body: &JsonValue) -> Result<(), AppError> {
+        let mut request = ureq::post(&format!("{}/{}", self.endpoint, path))
+            .set("Content-Type", "application/json");
+        
+        for (name, value) in &self.headers {
+            request = request.set(name, value);
+        }
+        
+        match request.send_string(&body.to_string()) {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(status, response)) => Err(AppError::Other(format!(
+                "Collector rejected export to {} with status {}: {}",
+                path, status, response.into_string().unwrap_or_default()
+            ))),
+            Err(err) => Err(AppError::Other(format!("Cannot reach collector: {}", err))),
+        }
+    }
+    
+    #[cfg(not(feature = "otlp"))]
+    fn send(&self, _path: &str, _body: &JsonValue) -> Result<(), AppError> {
+        Err(AppError::Config("OTLP export support not enabled".to_string()))
+    }
+}
+
+/// Build the attributes of a metric series, sorted so equal sets match
+fn series_key(attributes: &[(&str, &str)]) -> SeriesKey {
+    let mut key: SeriesKey = attributes.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    key.sort();
+    key
+}
+
+/// Get the OTLP JSON attributes of a metric series
+fn series_attributes(key: &SeriesKey) -> JsonValue {
+    otlp_attributes(key.iter().map(|(k, v)| (k.as_str(), json!({ "stringValue": v }))))
+}
+
+/// Build an OTLP JSON attribute list
+fn otlp_attributes<'a, I: IntoIterator<Item = (&'a str, JsonValue)>>(attributes: I) -> JsonValue {
+    JsonValue::Array(attributes.into_iter()
+        .map(|(key, value)| json!({ "key": key, "value": value }))
+        .collect())
+}
+
+/// Get a time as nanoseconds since the epoch, as a string like OTLP JSON expects
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0).to_string()
+}
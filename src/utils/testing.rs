@@ -0,0 +1,301 @@
+// Test support for comparing datasets and golden files
+// Author: Gabriel Demetrios Lafis
+
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Value as JsonValue};
+
+use crate::data::{DataSet, Value, format_date, format_time, format_timestamp, format_uuid};
+
+/// Environment variable that makes snapshot assertions rewrite their files
+pub const UPDATE_SNAPSHOTS_ENV: &str = "UPDATE_SNAPSHOTS";
+
+/// Unchanged lines kept around each change in a diff
+const DIFF_CONTEXT: usize = 3;
+
+/// Order of rows in a canonical rendering
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RowOrder {
+    /// Rows in dataset order, for results whose order matters
+    AsIs,
+    /// Rows sorted by their rendering, for results with no defined order
+    /// such as groups and joins
+    Sorted,
+}
+
+/// Format of a snapshot file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapshotFormat {
+    Csv,
+    Json,
+}
+
+impl SnapshotFormat {
+    /// Get the format of a snapshot file from its extension, defaulting to CSV
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("json") => SnapshotFormat::Json,
+            _ => SnapshotFormat::Csv,
+        }
+    }
+    
+    /// Render a dataset in this format
+    pub fn render(&self, data: &DataSet, order: RowOrder) -> String {
+        match self {
+            SnapshotFormat::Csv => canonical_csv(data, order),
+            SnapshotFormat::Json => canonical_json(data, order),
+        }
+    }
+}
+
+/// Render a dataset as canonical CSV
+///
+/// The header names each column with its type, and a `?` when it is
+/// nullable, such as `age:integer?`. Nulls are written as `null`, floats
+/// always keep their decimal point, and nested values are written as JSON,
+/// so equal datasets always render the same text.
+pub fn canonical_csv(data: &DataSet, order: RowOrder) -> String {
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::Any(b'\n'))
+        .from_writer(Vec::new());
+    
+    let header: Vec<String> = data.schema.fields.iter()
+        .map(|field| format!("{}:{}{}", field.name, field.data_type.name(), if field.nullable { "?" } else { "" }))
+        .collect();
+    // Writing to a vector cannot fail
+    writer.write_record(&header).unwrap();
+    
+    for row in ordered_rows(data, order) {
+        let cells: Vec<String> = row.iter()
+            .map(|value| match value {
+                JsonValue::String(s) => s.clone(),
+                value => value.to_string(),
+            })
+            .collect();
+        writer.write_record(&cells).unwrap();
+    }
+    
+    String::from_utf8(writer.into_inner().unwrap()).unwrap()
+}
+
+/// Render a dataset as canonical, pretty-printed JSON
+///
+/// The schema is listed first, then each row as an array of values. Map
+/// keys are sorted, and non-finite floats are written as strings.
+pub fn canonical_json(data: &DataSet, order: RowOrder) -> String {
+    let schema: Vec<JsonValue> = data.schema.fields.iter()
+        .map(|field| json!({
+            "name": field.name,
+            "type": field.data_type.name(),
+            "nullable": field.nullable,
+        }))
+        .collect();
+    
+    let rows: Vec<JsonValue> = ordered_rows(data, order).into_iter()
+        .map(JsonValue::Array)
+        .collect();
+    
+    let mut text = serde_json::to_string_pretty(&json!({
+        "schema": schema,
+        "rows": rows,
+    })).unwrap();
+    text.push('\n');
+    
+    text
+}
+
+/// Assert that two datasets have the same schema and rows in the same order
+///
+/// On a mismatch, panics with a diff of their canonical CSV renderings.
+pub fn assert_dataset_eq(actual: &DataSet, expected: &DataSet) {
+    compare_datasets(actual, expected, RowOrder::AsIs);
+}
+
+/// Assert that two datasets have the same schema and rows in any order
+pub fn assert_dataset_eq_unordered(actual: &DataSet, expected: &DataSet) {
+    compare_datasets(actual, expected, RowOrder::Sorted);
+}
+
+/// Assert that a dataset matches a golden file
+///
+/// The file format follows its extension: `.json` for canonical JSON and
+/// anything else for canonical CSV. A missing file is written from the
+/// dataset, as is every file when `UPDATE_SNAPSHOTS` is set, so a changed
+/// result is accepted by re-running the tests with it and reviewing the
+/// file diff. Otherwise panics with a diff of the file and the dataset.
+pub fn assert_snapshot<P: AsRef<Path>>(path: P, actual: &DataSet, order: RowOrder) {
+    let path = path.as_ref();
+    let rendered = SnapshotFormat::from_path(path).render(actual, order);
+    
+    let update = std::env::var_os(UPDATE_SNAPSHOTS_ENV).map_or(false, |value| value != "0");
+    
+    if update || !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .unwrap_or_else(|e| panic!("Cannot create snapshot directory {}: {}", parent.display(), e));
+        }
+        
+        fs::write(path, &rendered)
+            .unwrap_or_else(|e| panic!("Cannot write snapshot {}: {}", path.display(), e));
+        return;
+    }
+    
+    let expected = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Cannot read snapshot {}: {}", path.display(), e));
+    
+    // Snapshots checked out on Windows may have CRLF line endings
+    let expected = expected.replace("\r\n", "\n");
+    
+    if expected != rendered {
+        panic!(
+            "Dataset does not match snapshot {} (set {}=1 to update it)\n{}",
+            path.display(), UPDATE_SNAPSHOTS_ENV, diff_lines(&expected, &rendered)
+        );
+    }
+}
+
+/// Diff two texts line by line, marking removed lines with `-` and added
+/// lines with `+`, with unchanged lines far from any change left out
+pub fn diff_lines(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+    
+    // Only the middle between common leading and trailing lines is compared
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+    
+    let mut lines: Vec<(char, &str)> = old[..prefix].iter().map(|line| (' ', *line)).collect();
+    lines.extend(diff_middle(old_mid, new_mid));
+    lines.extend(old[old.len() - suffix..].iter().map(|line| (' ', *line)));
+    
+    // Keep only unchanged lines near a change
+    let changed: Vec<usize> = lines.iter().enumerate()
+        .filter(|(_, (mark, _))| *mark != ' ')
+        .map(|(idx, _)| idx)
+        .collect();
+    let near_change = |idx: usize| changed.iter().any(|&c| idx + DIFF_CONTEXT >= c && idx <= c + DIFF_CONTEXT);
+    
+    let mut out = String::new();
+    let mut skipped = false;
+    for (idx, (mark, line)) in lines.iter().enumerate() {
+        if *mark == ' ' && !near_change(idx) {
+            if !skipped {
+                out.push_str("  ...\n");
+                skipped = true;
+            }
+            continue;
+        }
+        
+        skipped = false;
+        out.push_str(&format!("{} {}\n", mark, line));
+    }
+    
+    out
+}
+
+/// Diff lines with a longest common subsequence, or as a block replacement
+/// when they are too many to compare pairwise
+fn diff_middle<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(char, &'a str)> {
+    if old.len().saturating_mul(new.len()) > 1_000_000 {
+        return old.iter().map(|line| ('-', *line))
+            .chain(new.iter().map(|line| ('+', *line)))
+            .collect();
+    }
+    
+    // lcs[i][j] is the common subsequence length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Removed lines come before the lines replacing them
+            lines.push(('-', old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
+    }
+    
+    lines
+}
+
+/// Compare datasets, panicking with a diff when they differ
+fn compare_datasets(actual: &DataSet, expected: &DataSet, order: RowOrder) {
+    let schema_matches = actual.schema.fields.len() == expected.schema.fields.len()
+        && actual.schema.fields.iter().zip(&expected.schema.fields).all(|(a, e)| {
+            a.name == e.name && a.data_type == e.data_type && a.nullable == e.nullable
+        });
+    
+    let rows_match = match order {
+        RowOrder::AsIs => actual.data.len() == expected.data.len()
+            && actual.data.iter().zip(&expected.data).all(|(a, e)| a.values == e.values),
+        // Sorted renderings are equal exactly when the row multisets are
+        RowOrder::Sorted => ordered_rows(actual, order) == ordered_rows(expected, order),
+    };
+    
+    if !(schema_matches && rows_match) {
+        panic!(
+            "Datasets differ (- expected, + actual)\n{}",
+            diff_lines(&canonical_csv(expected, order), &canonical_csv(actual, order))
+        );
+    }
+}
+
+/// Get the canonical values of each row, in the given order
+fn ordered_rows(data: &DataSet, order: RowOrder) -> Vec<Vec<JsonValue>> {
+    let mut rows: Vec<Vec<JsonValue>> = data.data.iter()
+        .map(|row| row.values.iter().map(canonical_value).collect())
+        .collect();
+    
+    if order == RowOrder::Sorted {
+        rows.sort_by_cached_key(|row| JsonValue::Array(row.clone()).to_string());
+    }
+    
+    rows
+}
+
+/// Convert a value to the JSON used by canonical renderings
+fn canonical_value(value: &Value) -> JsonValue {
+    match value {
+        Value::Null => JsonValue::Null,
+        Value::Boolean(b) => JsonValue::Bool(*b),
+        Value::Integer(i) => JsonValue::from(*i),
+        Value::Float(f) => match serde_json::Number::from_f64(*f) {
+            Some(n) => JsonValue::Number(n),
+            None if f.is_nan() => JsonValue::String("NaN".to_string()),
+            None if *f > 0.0 => JsonValue::String("inf".to_string()),
+            None => JsonValue::String("-inf".to_string()),
+        },
+        Value::Decimal(d) => JsonValue::String(d.to_string()),
+        Value::String(s) => JsonValue::String(s.clone()),
+        Value::Binary(b) => JsonValue::String(base64::encode(b)),
+        Value::Point(lat, lon) => json!([canonical_value(&Value::Float(*lat)), canonical_value(&Value::Float(*lon))]),
+        Value::Uuid(uuid) => JsonValue::String(format_uuid(*uuid)),
+        Value::Date(date) => JsonValue::String(format_date(date)),
+        Value::Timestamp(timestamp) => JsonValue::String(format_timestamp(timestamp)),
+        Value::Time(time) => JsonValue::String(format_time(time)),
+        Value::Array(values) => JsonValue::Array(values.iter().map(canonical_value).collect()),
+        // Without order preservation, JSON objects keep their keys sorted
+        Value::Map(map) => JsonValue::Object(map.iter().map(|(k, v)| (k.clone(), canonical_value(v))).collect()),
+    }
+}
@@ -0,0 +1,59 @@
+// File watching for the command line
+// Author: Gabriel Demetrios Lafis
+
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+/// Watch files and directories, calling back after each burst of changes
+///
+/// Changes arriving within the debounce time of each other are reported
+/// together, so an editor saving through a temporary file triggers a single
+/// call. Directories are watched recursively. Watching stops when the
+/// callback returns false.
+pub fn watch_paths<F>(paths: &[PathBuf], debounce: Duration, mut on_change: F) -> Result<(), notify::Error>
+where
+    F: FnMut(&[PathBuf]) -> bool,
+{
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    
+    for path in paths {
+        watcher.watch(path, RecursiveMode::Recursive)?;
+    }
+    
+    // The watcher stops sending when dropped, which ends the loop
+    while let Ok(event) = rx.recv() {
+        let mut changed = Vec::new();
+        collect_paths(event?, &mut changed);
+        
+        loop {
+            match rx.recv_timeout(debounce) {
+                Ok(event) => collect_paths(event?, &mut changed),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+        
+        if !changed.is_empty() && !on_change(&changed) {
+            break;
+        }
+    }
+    
+    Ok(())
+}
+
+/// Add the paths of an event that changes content to a list of changed paths
+fn collect_paths(event: Event, changed: &mut Vec<PathBuf>) {
+    if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+        return;
+    }
+    
+    for path in event.paths {
+        if !changed.contains(&path) {
+            changed.push(path);
+        }
+    }
+}
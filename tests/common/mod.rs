@@ -0,0 +1,181 @@
+// API integration test utilities
+// Author: Gabriel Demetrios Lafis
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use actix_web::dev::ServerHandle;
+use serde_json::{json, Value as JsonValue};
+
+use rust_data_processing_engine::{
+    api::{Server, ServerConfig, API_KEY_HEADER},
+    storage::{CatalogStorage, DataStorage, MemoryStorage},
+};
+
+/// API server running on a random local port for the length of a test
+pub struct TestServer {
+    base_url: String,
+    handle: ServerHandle,
+    http: reqwest::Client,
+    api_key: Option<String>,
+}
+
+impl TestServer {
+    /// Start a server over empty in-memory storage
+    pub async fn start() -> Self {
+        Self::start_with(|server| server).await
+    }
+    
+    /// Start a server over empty in-memory storage, configured by a closure
+    ///
+    /// The storage keeps a catalog, so tags, aliases, views and the trash
+    /// work as they do in a deployed server.
+    pub async fn start_with<F: FnOnce(Server) -> Server>(configure: F) -> Self {
+        Self::start_over(CatalogStorage::new(Arc::new(MemoryStorage::new())), configure).await
+    }
+    
+    /// Start a server over the given storage, configured by a closure
+    pub async fn start_over<S, F>(storage: S, configure: F) -> Self
+    where
+        S: DataStorage + Send + Sync + 'static,
+        F: FnOnce(Server) -> Server,
+    {
+        let config = ServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            workers: 1,
+            enable_cors: false,
+        };
+        
+        let server = configure(Server::new(storage, config));
+        let (server, addr) = server.bind().expect("failed to bind test server");
+        
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+        
+        TestServer {
+            base_url: format!("http://{}/api/v1", addr),
+            handle,
+            http: reqwest::Client::new(),
+            api_key: None,
+        }
+    }
+    
+    /// Send the given API key with every later request, or none
+    pub fn use_api_key(&mut self, key: Option<&str>) {
+        self.api_key = key.map(str::to_string);
+    }
+    
+    /// Get the URL of an API path, such as `/datasets`
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+    
+    /// Send a GET request, returning the status and JSON body
+    pub async fn get(&self, path: &str) -> (u16, JsonValue) {
+        Self::response(self.request(reqwest::Method::GET, path).send().await).await
+    }
+    
+    /// Send a POST request with a JSON body
+    pub async fn post(&self, path: &str, body: &JsonValue) -> (u16, JsonValue) {
+        Self::response(self.request(reqwest::Method::POST, path).json(body).send().await).await
+    }
+    
+    /// Send a PUT request with a JSON body
+    pub async fn put(&self, path: &str, body: &JsonValue) -> (u16, JsonValue) {
+        Self::response(self.request(reqwest::Method::PUT, path).json(body).send().await).await
+    }
+    
+    /// Send a DELETE request
+    pub async fn delete(&self, path: &str) -> (u16, JsonValue) {
+        Self::response(self.request(reqwest::Method::DELETE, path).send().await).await
+    }
+    
+    /// Send a GET request with an extra header, also returning the response headers
+    pub async fn get_with_header(&self, path: &str, name: &str, value: &str) -> (u16, reqwest::header::HeaderMap, JsonValue) {
+        let response = self.request(reqwest::Method::GET, path).header(name, value).send().await
+            .expect("request to test server failed");
+        let headers = response.headers().clone();
+        let (status, body) = Self::response(Ok(response)).await;
+        
+        (status, headers, body)
+    }
+    
+    /// Send a HEAD request, returning the status and response headers
+    pub async fn head(&self, path: &str) -> (u16, reqwest::header::HeaderMap) {
+        let response = self.request(reqwest::Method::HEAD, path).send().await.expect("request to test server failed");
+        
+        (response.status().as_u16(), response.headers().clone())
+    }
+    
+    /// Send a GET request, returning the status and raw body
+    pub async fn get_bytes(&self, path: &str) -> (u16, Vec<u8>) {
+        let response = self.request(reqwest::Method::GET, path).send().await.expect("request to test server failed");
+        let status = response.status().as_u16();
+        let body = response.bytes().await.expect("failed to read response body");
+        
+        (status, body.to_vec())
+    }
+    
+    /// Send a GET request with an extra header, returning the status, response headers and raw body
+    pub async fn get_bytes_with_header(&self, path: &str, name: &str, value: &str) -> (u16, reqwest::header::HeaderMap, Vec<u8>) {
+        let response = self.request(reqwest::Method::GET, path).header(name, value).send().await
+            .expect("request to test server failed");
+        let status = response.status().as_u16();
+        let headers = response.headers().clone();
+        let body = response.bytes().await.expect("failed to read response body");
+        
+        (status, headers, body.to_vec())
+    }
+    
+    /// Send a POST request with a raw body
+    pub async fn post_bytes(&self, path: &str, content_type: &str, body: Vec<u8>) -> (u16, JsonValue) {
+        Self::response(self.request(reqwest::Method::POST, path).header("Content-Type", content_type).body(body).send().await).await
+    }
+    
+    /// Create the `people` dataset used by most tests
+    pub async fn create_people(&self) {
+        let (status, _) = self.post("/datasets", &json!({
+            "name": "people",
+            "schema": [
+                {"name": "id", "data_type": "integer", "nullable": false},
+                {"name": "name", "data_type": "string"},
+                {"name": "age", "data_type": "integer"},
+                {"name": "city", "data_type": "string"},
+            ],
+            "data": [
+                [1, "Alice", 30, "London"],
+                [2, "Bob", 25, "Paris"],
+                [3, "Charlie", 35, "London"],
+                [4, "Diana", 28, "Berlin"],
+            ],
+        })).await;
+        
+        assert_eq!(status, 201, "failed to create the people dataset");
+    }
+    
+    /// Stop the server
+    pub async fn stop(self) {
+        self.handle.stop(true).await;
+    }
+    
+    /// Build a request to an API path, with the API key if one is set
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.http.request(method, self.url(path));
+        
+        match &self.api_key {
+            Some(key) => request.header(API_KEY_HEADER, key),
+            None => request,
+        }
+    }
+    
+    /// Read the status and JSON body of a response, with non-JSON bodies as null
+    async fn response(response: reqwest::Result<reqwest::Response>) -> (u16, JsonValue) {
+        let response = response.expect("request to test server failed");
+        let status = response.status().as_u16();
+        let body = response.text().await.expect("failed to read response body");
+        
+        (status, serde_json::from_str(&body).unwrap_or(JsonValue::Null))
+    }
+}
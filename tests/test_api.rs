@@ -0,0 +1,1508 @@
+// API integration tests
+// Author: Gabriel Demetrios Lafis
+
+mod common;
+
+use std::sync::Arc;
+
+use rust_data_processing_engine::api::{AccessControl, ResponseLimit, ResponsePolicies, ResponsePolicy};
+use rust_data_processing_engine::processing::ResultCache;
+use rust_data_processing_engine::storage::{CatalogStorage, IngestionService, InvalidatingStorage, LandingZone, MemoryStorage, ProtectedStorage, SessionStorage};
+use serde_json::json;
+
+use common::TestServer;
+
+#[actix_web::test]
+async fn test_health() {
+    let server = TestServer::start().await;
+    
+    let (status, body) = server.get("/health").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["status"], "ok");
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_request_ids() {
+    let server = TestServer::start().await;
+    
+    // A client's ID is echoed in the header and in error bodies
+    let (status, headers, body) = server.get_with_header("/datasets/missing", "X-Request-Id", "trace-123").await;
+    assert_eq!(status, 404);
+    assert_eq!(headers["x-request-id"], "trace-123");
+    assert_eq!(body["request_id"], "trace-123");
+    assert_eq!(body["error"], "not_found");
+    
+    // Unusable IDs are replaced with generated ones
+    let (status, headers, _) = server.get_with_header("/health", "X-Request-Id", "not valid").await;
+    assert_eq!(status, 200);
+    let id = headers["x-request-id"].to_str().unwrap();
+    assert_ne!(id, "not valid");
+    assert_eq!(id.len(), 36);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_dataset_crud() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    // Creating the same dataset again conflicts
+    let (status, _) = server.post("/datasets", &json!({
+        "name": "people",
+        "schema": [{"name": "id", "data_type": "integer"}],
+    })).await;
+    assert_eq!(status, 409);
+    
+    // List
+    let (status, body) = server.get("/datasets").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["datasets"][0]["name"], "people");
+    assert_eq!(body["datasets"][0]["rows"], 4);
+    
+    // Read
+    let (status, body) = server.get("/datasets/people").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 4);
+    assert_eq!(body["schema"].as_array().unwrap().len(), 4);
+    assert_eq!(body["data"][0], json!([1, "Alice", 30, "London"]));
+    
+    // Read a page
+    let (status, body) = server.get("/datasets/people?offset=1&limit=2").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 2);
+    assert_eq!(body["data"][0][1], "Bob");
+    assert_eq!(body["next_offset"], 3);
+    
+    // Append
+    let (status, body) = server.post("/datasets/people/rows", &json!({
+        "data": [[5, "Eve", 41, "Madrid"]],
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["appended"], 1);
+    assert_eq!(body["rows"], 5);
+    
+    // Values must match their column's type
+    let (status, body) = server.post("/datasets/people/rows", &json!({
+        "data": [[6, "Frank", "forty", "Oslo"]],
+    })).await;
+    assert_eq!(status, 400);
+    assert!(body["message"].as_str().unwrap().contains("'age'"));
+    
+    // Tags and info
+    let (status, _) = server.put("/datasets/people/tags", &json!({"tags": ["hr"]})).await;
+    assert_eq!(status, 200);
+    
+    let (status, body) = server.get("/datasets/people/info").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 5);
+    assert_eq!(body["columns"], 4);
+    assert_eq!(body["tags"], json!(["hr"]));
+    
+    // Replace
+    let (status, body) = server.put("/datasets/people", &json!({
+        "data": [[1, "Alice", 31, "London"]],
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 1);
+    
+    // Delete
+    let (status, _) = server.delete("/datasets/people").await;
+    assert_eq!(status, 204);
+    
+    let (status, _) = server.get("/datasets/people").await;
+    assert_eq!(status, 404);
+    
+    server.stop().await;
+    
+    // Storages without a catalog report tags as not implemented
+    let server = TestServer::start_over(MemoryStorage::new(), |server| server).await;
+    server.create_people().await;
+    
+    let (status, body) = server.put("/datasets/people/tags", &json!({"tags": ["hr"]})).await;
+    assert_eq!(status, 501);
+    assert_eq!(body["error"], "storage_error");
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_transform_endpoint() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    let (status, body) = server.post("/process/transform", &json!({
+        "source": "people",
+        "transform_type": "select",
+        "params": {"columns": ["name", "age"]},
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 4);
+    assert_eq!(body["data"][0], json!(["Alice", 30]));
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_multi_cast_rejects() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    let cast = |policy: &str| json!({
+        "source": "people",
+        "transform_type": "multi_cast",
+        "params": {
+            "casts": [{"column": "city", "target_type": "integer"}],
+            "on_error": policy,
+            "rejects_target": "bad_cities",
+        },
+    });
+    
+    // Only the reject policy has rejected rows to store
+    let (status, _) = server.post("/process/transform", &cast("null")).await;
+    assert_eq!(status, 400);
+    
+    let (status, body) = server.post("/process/transform", &cast("reject")).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 0);
+    
+    let (_, body) = server.get("/datasets/bad_cities").await;
+    assert_eq!(body["rows"], 4);
+    
+    // An existing dataset is only replaced when asked
+    let (status, _) = server.post("/process/transform", &cast("reject")).await;
+    assert_eq!(status, 409);
+    
+    let mut request = cast("reject");
+    request["params"]["overwrite_rejects"] = json!(true);
+    let (status, _) = server.post("/process/transform", &request).await;
+    assert_eq!(status, 200);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_filter_endpoint() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    let (status, body) = server.post("/process/filter", &json!({
+        "source": "people",
+        "target": "older",
+        "filter_type": "greater_than",
+        "params": {"column": "age", "value": 28},
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["target"], "older");
+    assert_eq!(body["rows"], 2);
+    
+    // The result is stored in the target
+    let (status, body) = server.get("/datasets/older").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 2);
+    
+    // Unknown sources are reported as missing
+    let (status, _) = server.post("/process/filter", &json!({
+        "source": "missing",
+        "filter_type": "not_null",
+        "params": {"column": "age"},
+    })).await;
+    assert_eq!(status, 404);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_pipeline_endpoint() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    let pipeline = json!({
+        "name": "people_in_${city}",
+        "params": {"city": null},
+        "steps": [
+            {"type": "filter", "expression": "city == '${city}'"},
+            {"type": "select", "columns": ["name"]},
+        ],
+    });
+    
+    let (status, body) = server.post("/process/pipeline", &json!({
+        "source": "people",
+        "pipeline": pipeline,
+        "params": {"city": "London"},
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 2);
+    
+    // Missing parameters are rejected before running
+    let (status, body) = server.post("/process/pipeline", &json!({
+        "source": "people",
+        "pipeline": pipeline,
+    })).await;
+    assert_eq!(status, 400);
+    assert!(body["message"].as_str().unwrap().contains("city"));
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_dry_run() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    // Pipelines report their plan without storing the target
+    let (status, body) = server.post("/process/pipeline?dry_run=true", &json!({
+        "source": "people",
+        "target": "londoners",
+        "pipeline": {
+            "name": "londoners",
+            "steps": [
+                {"type": "filter", "expression": "city == 'London'"},
+                {"type": "select", "columns": ["name"]},
+            ],
+        },
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["dry_run"], true);
+    assert_eq!(body["input_rows"], 4);
+    assert_eq!(body["estimated_rows"], 2);
+    assert_eq!(body["exact"], true);
+    assert_eq!(body["overwrites_target"], false);
+    assert_eq!(body["steps"].as_array().unwrap().len(), 2);
+    assert_eq!(body["schema"][0]["name"], "name");
+    
+    let (status, _) = server.get("/datasets/londoners/info").await;
+    assert_eq!(status, 404);
+    
+    // Unknown columns fail as in a real run
+    let (status, _) = server.post("/process/transform?dry_run=true", &json!({
+        "source": "people",
+        "transform_type": "select",
+        "params": {"columns": ["country"]},
+    })).await;
+    assert_eq!(status, 400);
+    
+    // Mutating dataset endpoints check the request and leave the dataset alone
+    let (status, body) = server.post("/datasets/people/rows?dry_run=true", &json!({
+        "data": [[5, "Eve", 41, "Rome"]],
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 5);
+    
+    let (status, body) = server.post("/datasets/people/rows?dry_run=true", &json!({
+        "data": [["five", "Eve", 41, "Rome"]],
+    })).await;
+    assert_eq!(status, 400);
+    assert!(body["message"].as_str().unwrap().contains("'id'"));
+    
+    let (status, body) = server.delete("/datasets/people?dry_run=true").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 4);
+    
+    let (status, body) = server.get("/datasets/people/info").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 4);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_schema_drift() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    // Reordered columns are not drift; a missing nullable column and a new one are
+    let (status, body) = server.post("/datasets/people/drift", &json!({
+        "schema": [
+            {"name": "name", "data_type": "string"},
+            {"name": "id", "data_type": "int32"},
+            {"name": "age", "data_type": "string"},
+            {"name": "country", "data_type": "string"},
+        ],
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["breaking"], true);
+    
+    let drift: Vec<_> = body["drift"].as_array().unwrap().iter()
+        .map(|column| (column["column"].as_str().unwrap(), column["kind"].as_str().unwrap(), column["breaking"].as_bool().unwrap()))
+        .collect();
+    assert_eq!(drift, vec![
+        ("id", "retyped", false),
+        ("age", "retyped", true),
+        ("city", "removed", false),
+        ("country", "added", true),
+    ]);
+    
+    // Appends block on drift unless asked to let non-breaking drift through
+    let schema = json!([
+        {"name": "name", "data_type": "string"},
+        {"name": "id", "data_type": "integer"},
+    ]);
+    
+    let (status, _) = server.post("/datasets/people/rows", &json!({
+        "data": [["Eve", 5]],
+        "schema": schema,
+    })).await;
+    assert_eq!(status, 400);
+    
+    let (status, body) = server.post("/datasets/people/rows", &json!({
+        "data": [["Eve", 5]],
+        "schema": schema,
+        "on_drift": "notify",
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 5);
+    assert_eq!(body["drift"][0]["column"], "age");
+    
+    let (_, body) = server.get("/datasets/people").await;
+    assert_eq!(body["data"][4], json!([5, "Eve", null, null]));
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_response_policies() {
+    let policies = ResponsePolicies::new()
+        .with_policy("people", ResponsePolicy::new().with_redacted_column("age").with_max_string_length(3));
+    let server = TestServer::start_with(|server| server.with_response_policies(policies)).await;
+    server.create_people().await;
+    
+    let (status, body) = server.get("/datasets/people").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["schema"][2]["data_type"], "string");
+    assert_eq!(body["data"][0], json!([1, "Ali…", "[redacted]", "Lon…"]));
+    assert_eq!(body["data"][1][1], "Bob");
+    
+    // Redacted values cannot be found by searching for them
+    let (status, body) = server.get("/datasets/people/search?q=30&columns=age").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["total"], 0);
+    
+    let (_, body) = server.get("/datasets/people/columns/age").await;
+    assert_eq!(body["values"][0], "[redacted]");
+    assert!(body["stats"].get("mean").is_none());
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_aggregate_endpoint() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    let (status, body) = server.post("/process/aggregate", &json!({
+        "source": "people",
+        "group_by": ["city"],
+        "aggregations": [
+            {"function": "count", "input_column": "id", "output_name": "people"},
+            {"function": "sum", "input_column": "age", "output_name": "total_age"},
+        ],
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 3);
+    
+    let london = body["data"].as_array().unwrap().iter()
+        .find(|row| row[0] == "London")
+        .expect("no London group");
+    assert_eq!(london[1], 2);
+    assert_eq!(london[2], 65);
+    
+    // Groups are filtered on their aggregates
+    let (status, body) = server.post("/process/aggregate", &json!({
+        "source": "people",
+        "group_by": ["city"],
+        "aggregations": [{"function": "count", "input_column": "id", "output_name": "people"}],
+        "having": "people > 1",
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"], json!([["London", 2]]));
+    
+    // Unknown functions are rejected
+    let (status, _) = server.post("/process/aggregate", &json!({
+        "source": "people",
+        "aggregations": [{"function": "mystery", "input_column": "age", "output_name": "x"}],
+    })).await;
+    assert_eq!(status, 400);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_join_endpoint() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    let (status, _) = server.post("/datasets", &json!({
+        "name": "cities",
+        "schema": [
+            {"name": "city_name", "data_type": "string"},
+            {"name": "country", "data_type": "string"},
+        ],
+        "data": [["London", "UK"], ["Paris", "France"]],
+    })).await;
+    assert_eq!(status, 201);
+    
+    let (status, body) = server.post("/process/join", &json!({
+        "left": "people",
+        "right": "cities",
+        "join_type": "inner",
+        "left_columns": ["city"],
+        "right_columns": ["city_name"],
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 3);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_stats_endpoint() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    let (status, body) = server.post("/process/stats", &json!({
+        "source": "people",
+        "stats_type": "mean",
+        "columns": ["age"],
+        "output_name": "mean_age",
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["name"], "mean_age");
+    assert_eq!(body["value"].as_f64(), Some(29.5));
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_response_limits() {
+    let limit = ResponseLimit::new().with_max_rows(2).with_max_bytes(1024);
+    let server = TestServer::start_with(|server| server.with_response_limit(limit)).await;
+    server.create_people().await;
+    
+    // Datasets are paged, and larger results must go to a target
+    let (status, body) = server.get("/datasets/people").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+    assert_eq!(body["next_offset"], 2);
+    
+    let select = json!({
+        "source": "people",
+        "transform_type": "select",
+        "params": {"columns": ["name"]},
+    });
+    let (status, _) = server.post("/process/transform", &select).await;
+    assert_eq!(status, 400);
+    
+    let mut stored = select.clone();
+    stored["target"] = json!("names");
+    let (status, body) = server.post("/process/transform", &stored).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 4);
+    
+    // Statistics are held to the byte cap
+    let stats = |output_name: &str| json!({
+        "source": "people",
+        "stats_type": "mean",
+        "columns": ["age"],
+        "output_name": output_name,
+    });
+    let (status, _) = server.post("/process/stats", &stats("mean_age")).await;
+    assert_eq!(status, 200);
+    
+    let (status, body) = server.post("/process/stats", &stats(&"x".repeat(2000))).await;
+    assert_eq!(status, 400);
+    assert!(body["message"].as_str().unwrap().contains("1024"));
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_backup_and_restore() {
+    let source = TestServer::start().await;
+    source.create_people().await;
+    
+    let (status, _) = source.put("/datasets/people/tags", &json!({"tags": ["hr"]})).await;
+    assert_eq!(status, 200);
+    
+    let (_, original) = source.get("/datasets/people").await;
+    let (status, archive) = source.get_bytes("/backup").await;
+    assert_eq!(status, 200);
+    source.stop().await;
+    
+    // Restore into a fresh server
+    let target = TestServer::start().await;
+    
+    let (status, body) = target.post_bytes("/restore", "application/x-tar", archive.clone()).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["datasets"], json!(["people"]));
+    
+    let (status, body) = target.get("/datasets/people/info").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 4);
+    assert_eq!(body["tags"], json!(["hr"]));
+    
+    // Schema and rows come back as they were
+    let (_, restored) = target.get("/datasets/people").await;
+    assert_eq!(restored["schema"], original["schema"]);
+    assert_eq!(restored["data"], original["data"]);
+    
+    // Existing datasets are only replaced when asked to
+    let (status, _) = target.post_bytes("/restore", "application/x-tar", archive.clone()).await;
+    assert_eq!(status, 409);
+    
+    let (status, _) = target.post_bytes("/restore?overwrite=true", "application/x-tar", archive).await;
+    assert_eq!(status, 200);
+    
+    target.stop().await;
+}
+
+#[actix_web::test]
+async fn test_admin_endpoints() {
+    let server = TestServer::start_with(|server| server.with_result_cache(Arc::new(ResultCache::new(8)))).await;
+    server.create_people().await;
+    
+    let (status, body) = server.get("/admin/storage").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["backend"], "memory");
+    assert_eq!(body["datasets"], 1);
+    assert_eq!(body["healthy"], true);
+    
+    // The same request twice is served from the result cache the second time
+    let request = json!({
+        "source": "people",
+        "filter_type": "greater_than",
+        "params": {"column": "age", "value": 28},
+    });
+    for _ in 0..2 {
+        let (status, _) = server.post("/process/filter", &request).await;
+        assert_eq!(status, 200);
+    }
+    
+    let (status, body) = server.get("/admin/cache").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["results"]["capacity"], 8);
+    assert_eq!(body["results"]["hits"], 1);
+    assert_eq!(body["results"]["entries"][0]["inputs"], json!(["people"]));
+    assert_eq!(body["results"]["entries"][0]["rows"], 2);
+    
+    let (status, body) = server.delete("/admin/cache").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["results_cleared"], 1);
+    
+    let (_, body) = server.get("/admin/cache").await;
+    assert_eq!(body["results"]["entries"], json!([]));
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_result_cache_invalidation() {
+    let cache = Arc::new(ResultCache::new(16));
+    let access = AccessControl::new()
+        .with_api_key("admin-key", "admin")
+        .with_admin_role("admin")
+        .with_api_key("analyst-key", "analyst");
+    let storage = InvalidatingStorage::new(Arc::new(CatalogStorage::new(Arc::new(MemoryStorage::new()))), cache.clone());
+    let mut server = TestServer::start_over(storage, |server| server
+        .with_result_cache(cache.clone())
+        .with_access_control(access)).await;
+    
+    server.use_api_key(Some("admin-key"));
+    server.create_people().await;
+    let (status, _) = server.put("/aliases/folks", &json!({"target": "people"})).await;
+    assert_eq!(status, 200);
+    let (status, _) = server.put("/views/names", &json!({
+        "source": "people",
+        "steps": [{"type": "select", "columns": ["name", "age"]}],
+    })).await;
+    assert_eq!(status, 200);
+    
+    let filter = |source: &str| json!({
+        "source": source,
+        "filter_type": "greater_than",
+        "params": {"column": "age", "value": 28},
+    });
+    
+    // Unchanged inputs are served from the cache the second time
+    for source in ["people", "folks", "names"] {
+        for _ in 0..2 {
+            let (status, body) = server.post("/process/filter", &filter(source)).await;
+            assert_eq!(status, 200);
+            assert_eq!(body["rows"], 2);
+        }
+    }
+    assert_eq!(cache.stats().unwrap().hits, 3);
+    assert_eq!(cache.stats().unwrap().entries, 3);
+    
+    // A write drops results read directly, through the alias and through the view
+    let (status, _) = server.post("/datasets/people/rows", &json!({"data": [[5, "Eve", 40, "Paris"]]})).await;
+    assert_eq!(status, 200);
+    assert_eq!(cache.stats().unwrap().entries, 0);
+    
+    for source in ["people", "folks", "names"] {
+        let (_, body) = server.post("/process/filter", &filter(source)).await;
+        assert_eq!(body["rows"], 3, "stale result for {}", source);
+    }
+    assert_eq!(cache.stats().unwrap().hits, 3);
+    
+    // Roles with different row policies never share a result
+    let (status, _) = server.put("/policies/people/analyst", &json!({"filter": "city == 'London'"})).await;
+    assert_eq!(status, 200);
+    
+    server.use_api_key(Some("analyst-key"));
+    let (_, body) = server.post("/process/filter", &filter("people")).await;
+    assert_eq!(body["rows"], 2);
+    
+    server.use_api_key(Some("admin-key"));
+    let (_, body) = server.post("/process/filter", &filter("people")).await;
+    assert_eq!(body["rows"], 3);
+    assert_eq!(cache.stats().unwrap().hits, 3);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_export_directories() {
+    let root = tempfile::tempdir().unwrap();
+    let export_root = root.path().to_path_buf();
+    let server = TestServer::start_with(move |server| server.with_export_root(export_root)).await;
+    server.create_people().await;
+    
+    // Directories are resolved under the export root
+    let (status, body) = server.post("/datasets/people/export", &json!({
+        "directory": "people/daily",
+        "key_columns": ["id"],
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["mode"], "snapshot");
+    assert!(root.path().join("people/daily/snapshot.json").exists());
+    
+    // Paths that leave the root are rejected
+    for directory in ["/tmp/people", "../people", "people/../../people", ""] {
+        let (status, _) = server.post("/datasets/people/export", &json!({
+            "directory": directory,
+            "key_columns": ["id"],
+        })).await;
+        assert_eq!(status, 400, "directory {:?} was accepted", directory);
+    }
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_row_policies() {
+    let access = AccessControl::new()
+        .with_api_key("admin-key", "admin")
+        .with_admin_role("admin")
+        .with_api_key("analyst-key", "analyst");
+    let storage = CatalogStorage::new(Arc::new(MemoryStorage::new()));
+    let mut server = TestServer::start_over(storage, |server| server.with_access_control(access)).await;
+    
+    // Requests without a valid key are refused
+    let (status, _) = server.get("/datasets").await;
+    assert_eq!(status, 401);
+    let (status, _) = server.post("/sessions", &json!({})).await;
+    assert_eq!(status, 401);
+    let (status, _) = server.delete("/sessions/0123456789abcdef").await;
+    assert_eq!(status, 401);
+    let (status, _) = server.get("/ingestion").await;
+    assert_eq!(status, 401);
+    
+    server.use_api_key(Some("admin-key"));
+    server.create_people().await;
+    
+    let (status, _) = server.put("/policies/people/analyst", &json!({"filter": "city == 'London'"})).await;
+    assert_eq!(status, 200);
+    let (status, _) = server.put("/aliases/folks", &json!({"target": "people"})).await;
+    assert_eq!(status, 200);
+    let (status, _) = server.put("/views/names", &json!({
+        "source": "people",
+        "steps": [{"type": "select", "columns": ["name"]}],
+    })).await;
+    assert_eq!(status, 200);
+    
+    server.use_api_key(Some("analyst-key"));
+    
+    // Every kind of read only returns the rows the role's policy lets through
+    let (_, body) = server.get("/datasets/people").await;
+    assert_eq!(body["rows"], 2);
+    
+    let (_, body) = server.get("/datasets/people?offset=1&limit=1").await;
+    assert_eq!(body["data"], json!([[3, "Charlie", 35, "London"]]));
+    
+    let (_, body) = server.get("/datasets/people/preview?rows=1").await;
+    assert_eq!(body["data"], json!([[1, "Alice", 30, "London"]]));
+    assert_eq!(body["total_rows"], 2);
+    
+    let (_, body) = server.get("/datasets/people/columns/name").await;
+    assert_eq!(body["values"], json!(["Alice", "Charlie"]));
+    
+    // Aliases and views of the restricted dataset are filtered the same way
+    let (_, body) = server.get("/datasets/folks").await;
+    assert_eq!(body["rows"], 2);
+    
+    let (_, body) = server.get("/datasets/names").await;
+    assert_eq!(body["data"], json!([["Alice"], ["Charlie"]]));
+    
+    // Writing back the filtered rows would drop the others
+    let (status, _) = server.post("/datasets/people/rows", &json!({"data": [[5, "Eve", 40, "London"]]})).await;
+    assert_eq!(status, 403);
+    let (status, _) = server.delete("/datasets/folks").await;
+    assert_eq!(status, 403);
+    
+    // Storage-wide actions are left to administrators
+    for path in ["/maintenance", "/datasets/people/unlock"] {
+        let (status, _) = server.post(path, &json!({})).await;
+        assert_eq!(status, 403, "{} was allowed", path);
+    }
+    let (status, _) = server.delete("/trash").await;
+    assert_eq!(status, 403);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_column_endpoint() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    // Values are paged, statistics cover the whole column
+    let (status, body) = server.get("/datasets/people/columns/age?offset=1&limit=2").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["values"], json!([25, 35]));
+    assert_eq!(body["total"], 4);
+    assert_eq!(body["stats"]["count"], 4);
+    assert_eq!(body["stats"]["min"].as_f64(), Some(25.0));
+    assert_eq!(body["stats"]["max"].as_f64(), Some(35.0));
+    assert_eq!(body["stats"]["mean"].as_f64(), Some(29.5));
+    
+    // Only numeric columns get numeric statistics
+    let (_, body) = server.get("/datasets/people/columns/city").await;
+    assert_eq!(body["values"], json!(["London", "Paris", "London", "Berlin"]));
+    assert!(body["stats"].get("mean").is_none());
+    
+    let (status, body) = server.get("/datasets/people/columns/salary").await;
+    assert_eq!(status, 404);
+    assert!(body["message"].as_str().unwrap().contains("salary"));
+    
+    let (status, _) = server.get("/datasets/nobody/columns/age").await;
+    assert_eq!(status, 404);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_search_endpoint() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    // Substrings match case-insensitively across string columns
+    let (status, body) = server.get("/datasets/people/search?q=LON&limit=1").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["data"], json!([{"row": 0, "values": [1, "Alice", 30, "London"]}]));
+    
+    let (_, body) = server.get("/datasets/people/search?q=%5E%5BAB%5D&regex=true&columns=name").await;
+    let rows: Vec<_> = body["data"].as_array().unwrap().iter().map(|m| m["row"].clone()).collect();
+    assert_eq!(rows, vec![json!(0), json!(1)]);
+    
+    // Patterns are escaped unless regex is asked for
+    let (_, body) = server.get("/datasets/people/search?q=.*").await;
+    assert_eq!(body["total"], 0);
+    
+    let (status, _) = server.get("/datasets/people/search?q=(&regex=true").await;
+    assert_eq!(status, 400);
+    let (status, _) = server.get("/datasets/people/search?q=a&columns=country").await;
+    assert_eq!(status, 400);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_preview_and_head() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    // Previews hold the first rows and count all of them
+    let (status, body) = server.get("/datasets/people/preview?rows=2").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"], json!([[1, "Alice", 30, "London"], [2, "Bob", 25, "Paris"]]));
+    assert_eq!(body["rows"], 2);
+    assert_eq!(body["total_rows"], 4);
+    
+    // HEAD reports the shape without a body
+    let (status, headers) = server.head("/datasets/people").await;
+    assert_eq!(status, 200);
+    assert_eq!(headers["x-dataset-rows"], "4");
+    assert_eq!(headers["x-dataset-columns"], "4");
+    
+    let (status, _) = server.head("/datasets/nobody").await;
+    assert_eq!(status, 404);
+    let (status, _) = server.get("/datasets/nobody/preview").await;
+    assert_eq!(status, 404);
+    let (status, _) = server.get("/datasets/people/preview?rows=many").await;
+    assert_eq!(status, 400);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_publish_and_read_only() {
+    let storage = ProtectedStorage::new(Arc::new(CatalogStorage::new(Arc::new(MemoryStorage::new()))));
+    let server = TestServer::start_over(storage, |server| server).await;
+    server.create_people().await;
+    
+    let (status, body) = server.post("/datasets/people/publish", &json!({})).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["immutable"], true);
+    
+    let (_, body) = server.get("/datasets/people/info").await;
+    assert_eq!(body["immutable"], true);
+    
+    // Published datasets can be read but not deleted until unlocked
+    let (status, _) = server.delete("/datasets/people").await;
+    assert_eq!(status, 403);
+    let (status, _) = server.post("/datasets/missing/publish", &json!({})).await;
+    assert_eq!(status, 404);
+    
+    let (status, body) = server.post("/datasets/people/unlock", &json!({})).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["immutable"], false);
+    let (status, _) = server.delete("/datasets/people").await;
+    assert_eq!(status, 204);
+    
+    server.stop().await;
+    
+    // In read-only mode nothing can be created
+    let backend = Arc::new(MemoryStorage::new());
+    let storage = ProtectedStorage::new(backend).with_read_only(true);
+    let server = TestServer::start_over(storage, |server| server).await;
+    
+    let (status, _) = server.post("/datasets", &json!({
+        "name": "people",
+        "schema": [{"name": "id", "data_type": "integer"}],
+        "data": [[1]],
+    })).await;
+    assert_eq!(status, 403);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_aliases_and_trash() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    let (status, _) = server.put("/aliases/folks", &json!({"target": "people"})).await;
+    assert_eq!(status, 200);
+    let (status, _) = server.put("/aliases/nobody", &json!({"target": "missing"})).await;
+    assert_eq!(status, 404);
+    
+    let (_, body) = server.get("/aliases").await;
+    assert_eq!(body["aliases"], json!([{"alias": "folks", "target": "people"}]));
+    let (_, body) = server.get("/datasets/folks").await;
+    assert_eq!(body["rows"], 4);
+    
+    // Removing an alias leaves its dataset alone
+    let (status, _) = server.delete("/aliases/folks").await;
+    assert_eq!(status, 204);
+    let (status, _) = server.delete("/aliases/folks").await;
+    assert_eq!(status, 404);
+    let (status, _) = server.get("/datasets/people").await;
+    assert_eq!(status, 200);
+    
+    // Deleted datasets go to the trash until restored or purged
+    let (status, _) = server.delete("/datasets/people").await;
+    assert_eq!(status, 204);
+    let (status, _) = server.get("/datasets/people").await;
+    assert_eq!(status, 404);
+    let (_, body) = server.get("/trash").await;
+    assert_eq!(body["trash"][0]["name"], "people");
+    
+    let (status, _) = server.post("/trash/people/restore", &json!({})).await;
+    assert_eq!(status, 200);
+    let (_, body) = server.get("/datasets/people").await;
+    assert_eq!(body["rows"], 4);
+    let (status, _) = server.post("/trash/people/restore", &json!({})).await;
+    assert_eq!(status, 404);
+    
+    let (status, _) = server.delete("/datasets/people").await;
+    assert_eq!(status, 204);
+    let (status, _) = server.delete("/trash/people").await;
+    assert_eq!(status, 204);
+    let (status, _) = server.delete("/trash/people").await;
+    assert_eq!(status, 404);
+    let (_, body) = server.get("/trash").await;
+    assert_eq!(body["trash"], json!([]));
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_copy_rename_and_move() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    let (status, body) = server.post("/datasets/people/copy", &json!({"target": "raw.eu.people"})).await;
+    assert_eq!(status, 201);
+    assert_eq!(body["name"], "raw.eu.people");
+    let (status, _) = server.post("/datasets/people/copy", &json!({"target": "raw.eu.people"})).await;
+    assert_eq!(status, 409);
+    let (status, _) = server.post("/datasets/missing/copy", &json!({"target": "other"})).await;
+    assert_eq!(status, 404);
+    
+    // Moving keeps only the last segment of the name
+    let (status, body) = server.post("/datasets/raw.eu.people/move", &json!({"namespace": "curated"})).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["name"], "curated.people");
+    let (_, body) = server.get("/datasets/curated.people").await;
+    assert_eq!(body["rows"], 4);
+    let (status, _) = server.get("/datasets/raw.eu.people").await;
+    assert_eq!(status, 404);
+    
+    // Moving to the top level would clash with the original
+    let (status, _) = server.post("/datasets/curated.people/move", &json!({"namespace": ""})).await;
+    assert_eq!(status, 409);
+    
+    let (status, body) = server.post("/datasets/curated.people/rename", &json!({"target": "folks"})).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["name"], "folks");
+    let (status, _) = server.post("/datasets/curated.people/rename", &json!({"target": "others"})).await;
+    assert_eq!(status, 404);
+    let (status, _) = server.post("/datasets/folks/rename", &json!({"target": "people"})).await;
+    assert_eq!(status, 409);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_dataset_listing() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    server.post("/datasets/people/copy", &json!({"target": "raw.people"})).await;
+    let (status, _) = server.post("/datasets", &json!({
+        "name": "raw.cities",
+        "schema": [{"name": "city", "data_type": "string"}],
+        "data": [["London"]],
+    })).await;
+    assert_eq!(status, 201);
+    server.put("/datasets/raw.cities/tags", &json!({"tags": ["geo"]})).await;
+    
+    let (_, body) = server.get("/datasets?prefix=raw.").await;
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["datasets"][0]["name"], "raw.cities");
+    assert_eq!(body["datasets"][0]["rows"], 1);
+    assert_eq!(body["datasets"][0]["tags"], json!(["geo"]));
+    
+    let (_, body) = server.get("/datasets?tag=geo").await;
+    assert_eq!(body["total"], 1);
+    
+    // Sorting, then paging over the sorted list
+    let (_, body) = server.get("/datasets?sort=rows&order=desc&offset=1&limit=1").await;
+    assert_eq!(body["total"], 3);
+    assert_eq!(body["datasets"].as_array().unwrap().len(), 1);
+    assert_eq!(body["datasets"][0]["name"], "people");
+    
+    let (status, _) = server.get("/datasets?sort=color").await;
+    assert_eq!(status, 400);
+    let (status, _) = server.get("/datasets?order=sideways").await;
+    assert_eq!(status, 400);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_create_from_csv_and_ndjson() {
+    let server = TestServer::start().await;
+    
+    let csv = "id;name;score\n1;Alice;9.5\n2;Bob;7\n";
+    let (status, body) = server.post_bytes("/datasets?name=scores&delimiter=;&tags=exam,final", "text/csv", csv.into()).await;
+    assert_eq!(status, 201);
+    assert_eq!(body["rows"], 2);
+    assert_eq!(body["tags"], json!(["exam", "final"]));
+    
+    // Column types of CSV bodies are inferred from their values
+    let (_, body) = server.get("/datasets/scores").await;
+    assert_eq!(body["data"][0], json!([1, "Alice", 9.5]));
+    
+    let ndjson = "{\"id\": 1, \"city\": \"London\"}\n{\"id\": 2, \"city\": \"Paris\"}\n";
+    let (status, body) = server.post_bytes("/datasets?name=cities", "application/x-ndjson", ndjson.into()).await;
+    assert_eq!(status, 201);
+    assert_eq!(body["rows"], 2);
+    
+    // Bodies without a name, of other types, or for existing datasets are rejected
+    let (status, _) = server.post_bytes("/datasets", "text/csv", csv.into()).await;
+    assert_eq!(status, 400);
+    let (status, _) = server.post_bytes("/datasets?name=other", "application/xml", b"<rows/>".to_vec()).await;
+    assert_eq!(status, 400);
+    let (status, _) = server.post_bytes("/datasets?name=scores", "text/csv", csv.into()).await;
+    assert_eq!(status, 409);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_schema_only_dataset() {
+    let server = TestServer::start().await;
+    
+    let (status, body) = server.post("/datasets", &json!({
+        "name": "events",
+        "schema": [
+            {"name": "id", "data_type": "integer", "nullable": false},
+            {"name": "kind", "data_type": "string"},
+        ],
+    })).await;
+    assert_eq!(status, 201);
+    assert_eq!(body["rows"], 0);
+    
+    let (_, body) = server.get("/datasets/events").await;
+    assert_eq!(body["schema"].as_array().unwrap().len(), 2);
+    
+    let (status, body) = server.post("/datasets/events/rows", &json!({"data": [[1, "click"], [2, null]]})).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 2);
+    
+    // Rows must fit the schema, and the dataset must exist
+    let (status, body) = server.post("/datasets/events/rows", &json!({"data": [[null, "view"]]})).await;
+    assert_eq!(status, 400);
+    assert!(body["message"].as_str().unwrap().contains("'id'"));
+    let (status, _) = server.post("/datasets/events/rows", &json!({"data": [[3, "view", "extra"]]})).await;
+    assert_eq!(status, 400);
+    let (status, _) = server.post("/datasets/missing/rows", &json!({"data": [[1, "click"]]})).await;
+    assert_eq!(status, 404);
+    
+    let (_, body) = server.get("/datasets/events").await;
+    assert_eq!(body["rows"], 2);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_processing_timeout() {
+    let filter = json!({
+        "source": "people",
+        "filter_type": "greater_than",
+        "params": {"column": "age", "value": 28},
+    });
+    
+    let server = TestServer::start_with(|server| server.with_processing_timeout(std::time::Duration::from_secs(60))).await;
+    server.create_people().await;
+    
+    let (status, body) = server.post("/process/filter", &filter).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["rows"], 2);
+    let (_, body) = server.get("/health").await;
+    assert_eq!(body["processing_timeouts"], 0);
+    
+    server.stop().await;
+    
+    // Requests that run out of time fail and are counted
+    let server = TestServer::start_with(|server| server.with_processing_timeout(std::time::Duration::ZERO)).await;
+    server.create_people().await;
+    
+    let (status, body) = server.post("/process/filter", &filter).await;
+    assert_eq!(status, 503);
+    assert_eq!(body["error"], "timeout");
+    let (status, _) = server.post("/process/stats", &json!({
+        "source": "people",
+        "stats_type": "mean",
+        "columns": ["age"],
+        "output_name": "mean_age",
+    })).await;
+    assert_eq!(status, 503);
+    
+    let (_, body) = server.get("/health").await;
+    assert_eq!(body["processing_timeouts"], 2);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_sessions() {
+    let sessions = Arc::new(SessionStorage::new(
+        Arc::new(CatalogStorage::new(Arc::new(MemoryStorage::new()))),
+        std::time::Duration::from_secs(600),
+    ));
+    let storage = InvalidatingStorage::new(sessions.clone(), Arc::new(ResultCache::new(16)));
+    let server = TestServer::start_over(storage, |server| server.with_sessions(sessions.clone())).await;
+    server.create_people().await;
+    
+    let (status, body) = server.post("/sessions", &json!({})).await;
+    assert_eq!(status, 201);
+    assert_eq!(body["ttl_secs"], 600);
+    let session = body["session"].as_str().unwrap().to_string();
+    
+    // Results of requests naming the session go to its temporary datasets
+    let (status, body) = server.post("/process/filter", &json!({
+        "source": "people",
+        "filter_type": "greater_than",
+        "params": {"column": "age", "value": 28},
+        "session": session,
+    })).await;
+    assert_eq!(status, 200);
+    let target = body["target"].as_str().unwrap().to_string();
+    
+    let (_, body) = server.get(&format!("/sessions/{}", session)).await;
+    assert_eq!(body["datasets"].as_array().unwrap().len(), 1);
+    let (_, body) = server.get(&format!("/datasets/{}", target)).await;
+    assert_eq!(body["rows"], 2);
+    let (_, body) = server.get("/datasets").await;
+    assert_eq!(body["total"], 1);
+    
+    // Ending the session drops its datasets
+    let (status, _) = server.delete(&format!("/sessions/{}", session)).await;
+    assert_eq!(status, 204);
+    let (status, _) = server.get(&format!("/sessions/{}", session)).await;
+    assert_eq!(status, 404);
+    let (status, _) = server.get(&format!("/datasets/{}", target)).await;
+    assert_eq!(status, 404);
+    let (status, _) = server.post("/process/filter", &json!({
+        "source": "people",
+        "filter_type": "greater_than",
+        "params": {"column": "age", "value": 28},
+        "session": session,
+    })).await;
+    assert_eq!(status, 404);
+    
+    server.stop().await;
+    
+    // Servers without sessions refuse to open one
+    let server = TestServer::start().await;
+    let (status, _) = server.post("/sessions", &json!({})).await;
+    assert_eq!(status, 400);
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_chart_endpoint() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    
+    let (status, body) = server.post("/datasets/people/chart", &json!({"x": "city", "y": "age", "aggregation": "avg"})).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["x"], json!(["Berlin", "London", "Paris"]));
+    assert_eq!(body["series"][0]["name"], "avg_age");
+    assert_eq!(body["series"][0]["values"], json!([28.0, 32.5, 25.0]));
+    
+    // Rows are counted without a y column, one series per group value
+    let (_, body) = server.post("/datasets/people/chart", &json!({"x": "city", "group": "name"})).await;
+    assert_eq!(body["series"].as_array().unwrap().len(), 4);
+    assert_eq!(body["series"][0]["name"], "Diana");
+    assert_eq!(body["series"][0]["values"], json!([1, null, null]));
+    
+    let (status, _) = server.post("/datasets/people/chart", &json!({"x": "city", "format": "svg", "kind": "line"})).await;
+    assert_eq!(status, 200);
+    
+    let (status, _) = server.post("/datasets/people/chart", &json!({"x": "city", "aggregation": "median_of_means"})).await;
+    assert_eq!(status, 400);
+    let (status, _) = server.post("/datasets/people/chart", &json!({"x": "city", "format": "png"})).await;
+    assert_eq!(status, 400);
+    let (status, _) = server.post("/datasets/missing/chart", &json!({"x": "city"})).await;
+    assert_eq!(status, 404);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_html_report() {
+    let server = TestServer::start().await;
+    server.create_people().await;
+    server.post("/datasets/people/rows", &json!({"data": [[5, "<script>", 41, "Oslo"]]})).await;
+    
+    let (status, body) = server.get_bytes("/datasets/people/report?sample=5&bins=4").await;
+    assert_eq!(status, 200);
+    let html = String::from_utf8(body).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("Profile of people"));
+    assert!(html.contains("5 rows, 4 columns"));
+    
+    // Values are escaped rather than injected into the page
+    assert!(html.contains("&lt;script&gt;"));
+    assert!(!html.contains("<script>"));
+    
+    // Only the requested number of sample rows is shown
+    let (_, body) = server.get_bytes("/datasets/people/report?sample=1").await;
+    let html = String::from_utf8(body).unwrap();
+    assert!(html.contains("<p>First 1 rows</p>"));
+    assert!(html.contains("<td>1</td><td>Alice</td><td>30</td><td>London</td>"));
+    assert!(!html.contains("<td>2</td><td>Bob</td>"));
+    
+    let (status, _) = server.get_bytes("/datasets/missing/report").await;
+    assert_eq!(status, 404);
+    let (status, _) = server.get_bytes("/datasets/people/report?sample=many").await;
+    assert_eq!(status, 400);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_ingestion() {
+    let landing = tempfile::tempdir().unwrap();
+    let catalog = Arc::new(CatalogStorage::new(Arc::new(MemoryStorage::new())));
+    let ingestion = Arc::new(IngestionService::new(catalog.clone())
+        .add(LandingZone::new("people", landing.path())));
+    let storage = InvalidatingStorage::new(catalog, Arc::new(ResultCache::new(16)));
+    let server = TestServer::start_over(storage, |server| server.with_ingestion(ingestion.clone())).await;
+    server.create_people().await;
+    
+    // Moved files keep their name behind a timestamp
+    let moved = |dir: &str| -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(landing.path().join(dir)).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().split_once('_').unwrap().1.to_string())
+            .collect();
+        names.sort();
+        names
+    };
+    
+    // Matching files are appended and archived; others are left alone
+    std::fs::write(landing.path().join("a.csv"), "id,name,age,city\n5,Eve,41,Rome\n6,Frank,33,Paris\n").unwrap();
+    std::fs::write(landing.path().join("notes.txt"), "not data").unwrap();
+    
+    let (_, body) = server.get("/ingestion").await;
+    assert_eq!(body["zones"][0]["dataset"], "people");
+    assert_eq!(body["zones"][0]["pending_files"], 1);
+    
+    let (status, body) = server.post("/ingestion/scan", &json!({})).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["ingested"], 1);
+    let (_, body) = server.get("/datasets/people").await;
+    assert_eq!(body["rows"], 6);
+    assert_eq!(moved("archive"), vec!["a.csv"]);
+    assert!(landing.path().join("notes.txt").exists());
+    
+    // Files not matching the dataset's columns are quarantined with the reason
+    std::fs::write(landing.path().join("b.csv"), "id,name,age,city\n7,Gina,old,Oslo\n").unwrap();
+    
+    let (_, body) = server.post("/ingestion/scan", &json!({})).await;
+    assert_eq!(body["ingested"], 0);
+    assert_eq!(moved("quarantine"), vec!["b.csv", "b.error"]);
+    
+    let (_, body) = server.get("/ingestion").await;
+    let zone = &body["zones"][0];
+    assert_eq!(zone["files_ingested"], 1);
+    assert_eq!(zone["files_quarantined"], 1);
+    assert_eq!(zone["rows_ingested"], 2);
+    assert_eq!(zone["last_file"], "b.csv");
+    assert!(zone["last_error"].as_str().unwrap().contains("'age'"));
+    let (_, body) = server.get("/datasets/people").await;
+    assert_eq!(body["rows"], 6);
+    
+    server.stop().await;
+    
+    // Without landing directories the endpoints are rejected
+    let server = TestServer::start().await;
+    let (status, _) = server.get("/ingestion").await;
+    assert_eq!(status, 400);
+    let (status, _) = server.post("/ingestion/scan", &json!({})).await;
+    assert_eq!(status, 400);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_msgpack_datasets() {
+    use rust_data_processing_engine::data::{DataType, DataSet, Field, MsgPackSink, MsgPackSource, Row, Schema, Value};
+    
+    let server = TestServer::start_with(|server| server.with_response_limit(ResponseLimit::new().with_max_bytes(1024))).await;
+    
+    let mut dataset = DataSet::new(Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("name".to_string(), DataType::String, true),
+        Field::new("score".to_string(), DataType::Float, true),
+    ]));
+    for (id, name) in [(1, Some("Alice")), (300, None), (-70000, Some("Charlie"))] {
+        dataset.add_row(Row::new(vec![
+            Value::Integer(id),
+            name.map_or(Value::Null, |name| Value::String(name.to_string())),
+            Value::Float(id as f64 / 4.0),
+        ])).unwrap();
+    }
+    
+    // MessagePack bodies create datasets like JSON ones
+    let (status, _) = server.post_bytes("/datasets?name=scores&tags=binary", "application/msgpack", MsgPackSink::to_bytes(&dataset)).await;
+    assert_eq!(status, 201);
+    
+    let (_, body) = server.get("/datasets/scores").await;
+    assert_eq!(body["rows"], 3);
+    assert_eq!(body["data"][1], json!([300, null, 75.0]));
+    assert_eq!(body["data"][2][0], -70000);
+    
+    // Clients accepting MessagePack get the rows encoded, with the next page in a header
+    let (status, headers, bytes) = server.get_bytes_with_header("/datasets/scores", "Accept", "application/json;q=0.5, application/msgpack").await;
+    assert_eq!(status, 200);
+    assert_eq!(headers["content-type"], "application/msgpack");
+    let decoded = MsgPackSource::new("scores").read_from(bytes.as_slice()).unwrap();
+    assert_eq!(decoded.schema.fields[1].name, "name");
+    assert_eq!(decoded.len(), 3);
+    for (decoded, row) in decoded.data.iter().zip(&dataset.data) {
+        assert_eq!(decoded.values, row.values);
+    }
+    
+    let (_, headers, bytes) = server.get_bytes_with_header("/datasets/scores?limit=2", "Accept", "application/x-msgpack").await;
+    assert_eq!(headers["x-next-offset"], "2");
+    assert_eq!(MsgPackSource::new("scores").read_from(bytes.as_slice()).unwrap().len(), 2);
+    
+    // Encoded bodies are held to the response size limit
+    let mut large = DataSet::new(dataset.schema.clone());
+    for id in 0..100 {
+        large.add_row(Row::new(vec![Value::Integer(id), Value::String("x".repeat(20)), Value::Null])).unwrap();
+    }
+    server.post_bytes("/datasets?name=large", "application/msgpack", MsgPackSink::to_bytes(&large)).await;
+    let (status, _, _) = server.get_bytes_with_header("/datasets/large", "Accept", "application/msgpack").await;
+    assert_eq!(status, 400);
+    
+    // Malformed bodies and missing names are rejected
+    let (status, _) = server.post_bytes("/datasets?name=broken", "application/msgpack", vec![0x82, 0xa6]).await;
+    assert_eq!(status, 400);
+    let mut trailing = MsgPackSink::to_bytes(&dataset);
+    trailing.push(0xc0);
+    let (status, _) = server.post_bytes("/datasets?name=broken", "application/msgpack", trailing).await;
+    assert_eq!(status, 400);
+    let (status, _) = server.post_bytes("/datasets", "application/msgpack", MsgPackSink::to_bytes(&dataset)).await;
+    assert_eq!(status, 400);
+    let (status, _) = server.get("/datasets/broken").await;
+    assert_eq!(status, 404);
+    
+    server.stop().await;
+}
+
+#[cfg(feature = "client")]
+#[actix_web::test]
+async fn test_engine_client() {
+    use rust_data_processing_engine::api::FilterRequest;
+    use rust_data_processing_engine::client::{ClientError, EngineClient, ProcessResult};
+    use rust_data_processing_engine::data::{DataType, DataSet, Field, Row, Schema, Value};
+    
+    let server = TestServer::start_with(|server| server.with_response_limit(ResponseLimit::new().with_max_rows(2))).await;
+    let client = EngineClient::new(server.url("").trim_end_matches("/api/v1"));
+    
+    assert_eq!(client.health().await.unwrap().status, "ok");
+    
+    let mut dataset = DataSet::new(Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("score".to_string(), DataType::Float, true),
+    ]));
+    for id in 1..=5 {
+        dataset.add_row(Row::new(vec![Value::Integer(id), Value::Float(id as f64 * 10.0)])).unwrap();
+    }
+    
+    // Datasets round trip, with downloads following pages
+    let created = client.upload_dataset("scores", &dataset, &["team".to_string()]).await.unwrap();
+    assert_eq!((created.rows, created.tags), (5, vec!["team".to_string()]));
+    
+    let downloaded = client.get_dataset("scores").await.unwrap();
+    assert_eq!(downloaded.len(), 5);
+    assert_eq!(downloaded.data[4].values, vec![Value::Integer(5), Value::Float(50.0)]);
+    assert_eq!(client.dataset_info("scores").await.unwrap().rows, 5);
+    
+    // Processing results are stored, or returned when no target is given
+    let filter = |target: Option<&str>| FilterRequest {
+        source: "scores".to_string(),
+        target: target.map(str::to_string),
+        session: None,
+        filter_type: "greater_than".to_string(),
+        params: json!({"column": "score", "value": 25.0}),
+    };
+    match client.filter(&filter(Some("high"))).await.unwrap() {
+        ProcessResult::Stored { target, rows, .. } => assert_eq!((target.as_str(), rows), ("high", 3)),
+        other => panic!("expected a stored result, got {:?}", other),
+    }
+    assert_eq!(client.get_dataset("high").await.unwrap().len(), 3);
+    
+    // Error responses keep their status, message and request ID
+    let err = client.get_dataset("missing").await.unwrap_err();
+    assert!(err.is_not_found());
+    assert!(err.request_id().is_some());
+    assert!(matches!(err, ClientError::Api { ref message, .. } if !message.is_empty()));
+    
+    client.delete_dataset("high").await.unwrap();
+    assert!(client.dataset_info("high").await.unwrap_err().is_not_found());
+    
+    let err = EngineClient::new("not a url").health().await.unwrap_err();
+    assert!(matches!(err, ClientError::InvalidUrl(_)));
+    assert_eq!(err.status(), None);
+    
+    server.stop().await;
+}
+
+#[actix_web::test]
+async fn test_request_telemetry() {
+    use rust_data_processing_engine::utils::Telemetry;
+    
+    #[cfg(feature = "otlp")]
+    let (collector, exports) = {
+        use std::sync::Mutex;
+        use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+        
+        // Collector keeping the path and body of every export
+        let exports = Arc::new(Mutex::new(Vec::new()));
+        let received = exports.clone();
+        let server = HttpServer::new(move || {
+            let received = received.clone();
+            App::new().default_service(web::to(move |req: HttpRequest, body: web::Bytes| {
+                let received = received.clone();
+                async move {
+                    let export: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                    received.lock().unwrap().push((req.path().to_string(), export));
+                    HttpResponse::Ok().json(json!({}))
+                }
+            }))
+        }).workers(1).bind("127.0.0.1:0").unwrap();
+        
+        let collector = format!("http://{}", server.addrs()[0]);
+        actix_web::rt::spawn(server.run());
+        (collector, exports)
+    };
+    #[cfg(not(feature = "otlp"))]
+    let collector = "http://127.0.0.1:9".to_string();
+    
+    let telemetry = Arc::new(Telemetry::new(&collector, "engine"));
+    let server = TestServer::start_with(|server| server.with_telemetry(telemetry.clone())).await;
+    
+    // Requests are answered as usual
+    let (status, _, _) = server.get_with_header(
+        "/datasets/missing", "traceparent", "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+    ).await;
+    assert_eq!(status, 404);
+    let (status, _) = server.get("/health").await;
+    assert_eq!(status, 200);
+    
+    // Export happens on a blocking client, off the runtime serving the collector
+    let exporter = telemetry.clone();
+    let exported = actix_web::rt::task::spawn_blocking(move || exporter.export()).await.unwrap();
+    
+    #[cfg(not(feature = "otlp"))]
+    assert!(matches!(exported, Err(rust_data_processing_engine::utils::AppError::Config(_))));
+    
+    #[cfg(feature = "otlp")]
+    {
+        exported.unwrap();
+        let exports = exports.lock().unwrap().clone();
+        assert_eq!(exports.iter().map(|(path, _)| path.as_str()).collect::<Vec<_>>(), vec!["/v1/traces", "/v1/metrics"]);
+        
+        // Spans are named by route and join the caller's trace
+        let spans = exports[0].1["resourceSpans"][0]["scopeSpans"][0]["spans"].as_array().unwrap().clone();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0]["name"], "GET /api/v1/datasets/{name}");
+        assert_eq!(spans[0]["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(spans[0]["parentSpanId"], "00f067aa0ba902b7");
+        assert!(spans[0].get("status").is_none());
+        assert_eq!(spans[1]["name"], "GET /api/v1/health");
+        assert!(spans[1].get("parentSpanId").is_none());
+        
+        let attribute = |span: &serde_json::Value, key: &str| span["attributes"].as_array().unwrap().iter()
+            .find(|attribute| attribute["key"] == key)
+            .map(|attribute| attribute["value"].clone());
+        assert_eq!(attribute(&spans[0], "http.response.status_code"), Some(json!({ "intValue": "404" })));
+        assert_eq!(attribute(&spans[0], "url.path"), Some(json!({ "stringValue": "/api/v1/datasets/missing" })));
+        assert!(attribute(&spans[0], "request.id").is_some());
+        
+        // Each route and status is its own duration series
+        let metric = &exports[1].1["resourceMetrics"][0]["scopeMetrics"][0]["metrics"][0];
+        assert_eq!(metric["name"], "http.server.request.duration");
+        assert_eq!(metric["histogram"]["dataPoints"].as_array().unwrap().len(), 2);
+    }
+}
@@ -1,11 +1,31 @@
 // Pipeline tests
 // Author: Gabriel Demetrios Lafis
 
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use rust_data_processing_engine::{
-    data::{DataSet, DataType, Field, Row, Schema, Value},
+    data::{
+        AvroSchema, ColumnStatistics, Compatibility, CsvSource, DataError, DataSet, DataSink, DataType, Decimal,
+        ErrorContext, ExtraFields, Field, FieldDefault, LineageStep, Row, RowGroupStatistics, Schema, SchemaRegistry, SinkType, Value,
+    },
     processing::{
         FilterProcessor, Pipeline, SelectTransform, AddColumnTransform,
-        GroupByProcessor, JoinProcessor, JoinType,
+        GroupByProcessor, JoinProcessor, JoinType, RenameTransform,
+        DropColumnsTransform, LimitProcessor, CastTransform, CastFormat,
+        GeohashTransform, haversine_distance, DataProcessor, WindowProcessor,
+        AggregateFunction, AggregateOptions, processor_from_config, register_aggregate,
+        MultiCastTransform, CastErrorPolicy, NanPolicy, WarningKind, PipelineTemplate,
+        PipelineSpec, StepSpec, StreamingExecutor, EventTimeWindow, SortKey, SortProcessor, TableEstimate, BuildSide,
+        FilterExpression, MovingAverageTransform, ResampleProcessor, ChangeApplier, ChangeEvent,
+        FlattenTransform, ExplodeTransform, WindowFunctionType, JsonPathTransform, EncodeTransform, DecodeTransform, Encoding,
+        CurrencyConversionTransform, BinTransform, ScaleTransform, ScaleParams, FillMethod, ResampleAggregation,
+        EwmaTransform, Orchestrator, PipelineStep, StepStatus, ProcessingError,
+    },
+    storage::{
+        CatalogStorage, CheckpointStore, DataStorage, ExportOutcome, FailoverStorage, IncrementalExporter, MemoryPolicy,
+        MemoryStorage, Offsets, RetryPolicy, RetryStorage, StorageError, TransactionalSink, ViewDefinition,
     },
 };
 
@@ -226,3 +246,3696 @@ fn test_join_pipeline() {
     assert_eq!(result.data[1].values[2], Value::Null);
 }
 
+#[test]
+fn test_in_place_pipeline() {
+    // Create a schema
+    let schema = Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("name".to_string(), DataType::String, false),
+        Field::new("age".to_string(), DataType::Integer, true),
+    ]);
+    
+    // Create a dataset
+    let mut dataset = DataSet::new(schema);
+    
+    // Add rows
+    for (id, name, age) in [(1, "Alice", 30), (2, "Bob", 25), (3, "Charlie", 35)] {
+        dataset.add_row(Row::new(vec![
+            Value::Integer(id),
+            Value::String(name.to_string()),
+            Value::Integer(age),
+        ])).unwrap();
+    }
+    
+    // Create a pipeline made of in-place capable processors
+    let pipeline = Pipeline::new("test")
+        .add(FilterProcessor::greater_than("age", Value::Integer(28)))
+        .add(RenameTransform::new(vec![("name".to_string(), "full_name".to_string())]))
+        .add(DropColumnsTransform::new(vec!["id".to_string()]))
+        .add(LimitProcessor::new(1));
+    
+    // Process the dataset without cloning it
+    let result = pipeline.execute_owned(dataset).unwrap();
+    
+    // Check result
+    assert_eq!(result.len(), 1);
+    assert_eq!(result.schema.fields.len(), 2);
+    assert_eq!(result.schema.fields[0].name, "full_name");
+    assert_eq!(result.data[0].values[0], Value::String("Alice".to_string()));
+    assert_eq!(result.data[0].values[1], Value::Integer(30));
+}
+
+#[test]
+fn test_cast_with_format_hints() {
+    // Create a dataset of formatted amounts
+    let schema = Schema::new(vec![
+        Field::new("amount".to_string(), DataType::String, true),
+    ]);
+    
+    let mut dataset = DataSet::new(schema);
+    
+    for amount in ["€1.234,50", "(12,00)", "7"] {
+        dataset.add_row(Row::new(vec![Value::String(amount.to_string())])).unwrap();
+    }
+    
+    // Cast using European number formatting
+    let pipeline = Pipeline::new("test")
+        .add(CastTransform::new("amount", DataType::Float)
+            .with_format(CastFormat::european().currency()));
+    
+    let result = pipeline.process(&dataset).unwrap();
+    
+    // Check result
+    assert_eq!(result.schema.fields[0].data_type, DataType::Float);
+    assert_eq!(result.data[0].values[0], Value::Float(1234.5));
+    assert_eq!(result.data[1].values[0], Value::Float(-12.0));
+    assert_eq!(result.data[2].values[0], Value::Float(7.0));
+}
+
+#[test]
+fn test_geo_radius_and_geohash() {
+    // Create a dataset of city locations
+    let schema = Schema::new(vec![
+        Field::new("city".to_string(), DataType::String, false),
+        Field::new("location".to_string(), DataType::Point, true),
+    ]);
+    
+    let mut dataset = DataSet::new(schema);
+    
+    for (city, lat, lon) in [("Paris", 48.8566, 2.3522), ("London", 51.5074, -0.1278), ("Tokyo", 35.6762, 139.6503)] {
+        dataset.add_row(Row::new(vec![
+            Value::String(city.to_string()),
+            Value::Point(lat, lon),
+        ])).unwrap();
+    }
+    
+    // Paris to London is roughly 344 km
+    let distance = haversine_distance((48.8566, 2.3522), (51.5074, -0.1278));
+    assert!((distance - 344.0).abs() < 2.0);
+    
+    // Keep cities within 500 km of Paris and bucket them by geohash
+    let pipeline = Pipeline::new("test")
+        .add(FilterProcessor::within_radius("location", 48.8566, 2.3522, 500.0))
+        .add(GeohashTransform::new("location", 5, "cell"));
+    
+    let result = pipeline.process(&dataset).unwrap();
+    
+    // Check result
+    assert_eq!(result.len(), 2);
+    assert_eq!(result.data[0].values[2], Value::String("u09tv".to_string()));
+    assert_eq!(result.data[1].values[2], Value::String("gcpvj".to_string()));
+}
+
+#[test]
+fn test_pipeline_config_round_trip() {
+    let schema = Schema::new(vec![
+        Field::new("category".to_string(), DataType::String, false),
+        Field::new("amount".to_string(), DataType::String, false),
+    ]);
+    
+    let mut dataset = DataSet::new(schema);
+    
+    for (category, amount) in [("A", "1,000"), ("B", "250"), ("A", "-5"), ("B", "40")] {
+        dataset.add_row(Row::new(vec![
+            Value::String(category.to_string()),
+            Value::String(amount.to_string()),
+        ])).unwrap();
+    }
+    
+    let pipeline = Pipeline::new("totals")
+        .add(CastTransform::new("amount", DataType::Integer).with_format(CastFormat::new().thousands_separator(',')))
+        .add(FilterProcessor::expression("amount > 0").unwrap())
+        .add(WindowProcessor::sum("category_total", "amount").partition_by(vec!["category".to_string()]))
+        .add(LimitProcessor::new(10));
+    
+    // Rebuild the pipeline from its configuration
+    let config = pipeline.to_config().unwrap();
+    let rebuilt = Pipeline::from_config(config.clone()).unwrap();
+    
+    assert_eq!(config["steps"][0]["type"], "cast");
+    assert_eq!(config["steps"][1]["expression"], "amount > 0");
+    assert_eq!(rebuilt.to_config().unwrap(), config);
+    
+    let expected = pipeline.process(&dataset).unwrap();
+    let actual = rebuilt.process(&dataset).unwrap();
+    assert_eq!(actual.len(), 3);
+    assert_eq!(actual.schema.fields.len(), expected.schema.fields.len());
+    for (a, e) in actual.data.iter().zip(&expected.data) {
+        assert_eq!(a.values, e.values);
+    }
+    
+    // Predicate filters have no configuration
+    let custom = Pipeline::new("custom").add(FilterProcessor::new("positive", |_, _| true));
+    assert!(custom.to_config().is_err());
+}
+
+#[test]
+fn test_pipeline_template() {
+    let template = PipelineTemplate::from_config(serde_json::json!({
+        "name": "city_${city}",
+        "params": {"city": null, "rows": 10},
+        "steps": [
+            {"type": "filter", "expression": "city == '${city}' and note != '$${literal}'"},
+            {"type": "limit", "limit": "${rows}"},
+        ],
+    })).unwrap();
+    
+    assert_eq!(template.parameters().into_iter().collect::<Vec<_>>(), vec!["city", "rows"]);
+    assert_eq!(template.required_parameters().into_iter().collect::<Vec<_>>(), vec!["city"]);
+    
+    // Lone parameters keep the type of their value; embedded ones become text
+    let params = [("city".to_string(), serde_json::json!("London")), ("rows".to_string(), serde_json::json!(1))]
+        .into_iter().collect();
+    let spec = template.resolve(&params).unwrap();
+    let config = serde_json::to_value(&spec).unwrap();
+    
+    assert_eq!(config["name"], "city_London");
+    assert_eq!(config["steps"][0]["expression"], "city == 'London' and note != '${literal}'");
+    assert_eq!(config["steps"][1]["limit"], 1);
+    
+    // Required parameters must be given, and unknown ones are rejected
+    assert!(template.resolve(&Default::default()).is_err());
+    
+    let typo = [("cty".to_string(), serde_json::json!("Paris"))].into_iter().collect();
+    assert!(template.resolve(&typo).is_err());
+}
+
+#[test]
+fn test_pipeline_dry_run() {
+    let schema = Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("age".to_string(), DataType::Integer, true),
+    ]);
+    
+    let mut dataset = DataSet::new(schema);
+    for id in 0..10 {
+        dataset.add_row(Row::new(vec![Value::Integer(id), Value::Integer(20 + id)])).unwrap();
+    }
+    
+    let pipeline = Pipeline::new("adults")
+        .add(FilterProcessor::greater_than("age", Value::Integer(21)))
+        .add(SelectTransform::new(vec!["id".to_string()]));
+    
+    // The whole input fits the sample, so the plan is exact
+    let plan = pipeline.dry_run(&dataset, dataset.len()).unwrap();
+    assert!(plan.exact);
+    assert_eq!(plan.steps.len(), 2);
+    assert_eq!(plan.steps[0].estimated_rows, 8);
+    assert_eq!(plan.estimated_rows, 8);
+    assert_eq!(plan.schema.fields.len(), 1);
+    assert_eq!(plan.schema.fields[0].name, "id");
+    
+    // Estimates scale from a sample to the whole input
+    let mut sample = dataset.clone();
+    sample.data.truncate(4);
+    let plan = pipeline.dry_run(&sample, 1000).unwrap();
+    assert!(!plan.exact);
+    assert_eq!(plan.sample_rows, 4);
+    assert_eq!(plan.estimated_rows, 500);
+    
+    // Unknown columns fail as they would in a full run
+    let broken = Pipeline::new("broken").add(SelectTransform::new(vec!["missing".to_string()]));
+    assert!(broken.dry_run(&sample, 1000).is_err());
+}
+
+#[test]
+fn test_filter_pushdown_ranges() {
+    let spec = PipelineSpec::from_config(serde_json::json!({
+        "name": "recent",
+        "steps": [
+            {"type": "filter", "expression": "age >= 30 and age < 40 and city != 'Paris'"},
+            {"type": "select", "columns": ["age"]},
+            {"type": "filter", "expression": "age > 100"},
+        ],
+    })).unwrap();
+    
+    // Only the leading filter's comparisons joined by `and` bound the input
+    let ranges = spec.input_ranges().unwrap();
+    assert_eq!(ranges.len(), 2);
+    assert!(ranges.iter().all(|range| range.column == "age"));
+    
+    let row_group = |min: i64, max: i64| RowGroupStatistics {
+        rows: 100,
+        columns: vec![ColumnStatistics {
+            column: "age".to_string(),
+            min: Some(Value::Integer(min)),
+            max: Some(Value::Integer(max)),
+            nulls: Some(0),
+        }],
+    };
+    
+    assert!(row_group(20, 30).may_match(&ranges));
+    assert!(row_group(35, 90).may_match(&ranges));
+    assert!(!row_group(0, 29).may_match(&ranges));
+    assert!(!row_group(40, 90).may_match(&ranges));
+    
+    // Row groups without statistics of the column are always read
+    let unknown = RowGroupStatistics { rows: 100, columns: Vec::new() };
+    assert!(unknown.may_match(&ranges));
+}
+
+/// Product of the integer values of a group, for the registry test
+struct ProductFunction;
+
+impl AggregateFunction for ProductFunction {
+    type State = i64;
+    
+    fn name(&self) -> &str {
+        "product"
+    }
+    
+    fn output_type(&self, _input_type: &DataType) -> DataType {
+        DataType::Integer
+    }
+    
+    fn init(&self) -> i64 {
+        1
+    }
+    
+    fn update(&self, product: &mut i64, value: &Value) {
+        if let Value::Integer(i) = value {
+            *product *= i;
+        }
+    }
+    
+    fn merge(&self, product: &mut i64, other: i64) {
+        *product *= other;
+    }
+    
+    fn finalize(&self, product: i64) -> Value {
+        Value::Integer(product)
+    }
+}
+
+#[test]
+fn test_registered_aggregate_function() {
+    register_aggregate("product", |_| Box::new(ProductFunction)).unwrap();
+    
+    // Names are taken once, and built-in names never
+    assert!(register_aggregate("Product", |_| Box::new(ProductFunction)).is_err());
+    assert!(register_aggregate("sum", |_| Box::new(ProductFunction)).is_err());
+    
+    let schema = Schema::new(vec![
+        Field::new("category".to_string(), DataType::String, false),
+        Field::new("amount".to_string(), DataType::Integer, false),
+    ]);
+    
+    let mut dataset = DataSet::new(schema);
+    
+    for (category, amount) in [("A", 2), ("B", 5), ("A", 3)] {
+        dataset.add_row(Row::new(vec![
+            Value::String(category.to_string()),
+            Value::Integer(amount),
+        ])).unwrap();
+    }
+    
+    let group_by = GroupByProcessor::new()
+        .group_by("category")
+        .aggregate_named("product", "amount", "product", &AggregateOptions::default())
+        .unwrap();
+    
+    // The function is also usable from configurations
+    let rebuilt = processor_from_config(group_by.to_config().unwrap()).unwrap();
+    
+    for result in [group_by.process(&dataset).unwrap(), rebuilt.process(&dataset).unwrap()] {
+        assert_eq!(result.len(), 2);
+        assert_eq!(result.data[0].values, vec![Value::String("A".to_string()), Value::Integer(6)]);
+        assert_eq!(result.data[1].values, vec![Value::String("B".to_string()), Value::Integer(5)]);
+    }
+    
+    assert!(GroupByProcessor::new().aggregate_named("total", "amount", "median", &AggregateOptions::default()).is_err());
+}
+
+#[test]
+fn test_cast_error_context() {
+    let schema = Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("amount".to_string(), DataType::String, false),
+    ]);
+    
+    let mut dataset = DataSet::new(schema);
+    
+    for (id, amount) in [(1, "10"), (2, "20"), (3, "abc")] {
+        dataset.add_row(Row::new(vec![
+            Value::Integer(id),
+            Value::String(amount.to_string()),
+        ])).unwrap();
+    }
+    
+    let err = CastTransform::new("amount", DataType::Integer)
+        .process(&dataset)
+        .unwrap_err();
+    
+    let expected = ErrorContext::new().column("amount").row(2).value(&Value::String("abc".to_string()));
+    assert_eq!(err.context(), Some(&expected));
+    assert!(err.to_string().ends_with("(column 'amount', row 2, value 'abc')"));
+    
+    // Outer layers only fill in what is still missing
+    let err = err.with_context(ErrorContext::new().dataset("sales").row(0));
+    let context = err.context().unwrap();
+    assert_eq!(context.dataset.as_deref(), Some("sales"));
+    assert_eq!(context.row, Some(2));
+}
+
+#[test]
+fn test_pipeline_warnings() {
+    let schema = Schema::new(vec![
+        Field::new("category".to_string(), DataType::String, false),
+        Field::new("quantity".to_string(), DataType::String, false),
+        Field::new("price".to_string(), DataType::Float, false),
+    ]);
+    
+    let mut dataset = DataSet::new(schema);
+    
+    for (category, quantity, price) in [("A", "2", 1.5), ("A", "two", f64::NAN), ("B", "3", 4.0), ("B", "?", 2.0)] {
+        dataset.add_row(Row::new(vec![
+            Value::String(category.to_string()),
+            Value::String(quantity.to_string()),
+            Value::Float(price),
+        ])).unwrap();
+    }
+    
+    let pipeline = Pipeline::new("warnings")
+        .add(MultiCastTransform::new(CastErrorPolicy::Null).cast("quantity", DataType::Integer))
+        .add(GroupByProcessor::new()
+            .group_by("category")
+            .avg("price", "price")
+            .with_nan_policy(NanPolicy::Skip))
+        .add(CastTransform::new("price", DataType::Integer));
+    
+    let (result, warnings) = pipeline.execute_with_warnings(&dataset).unwrap();
+    assert_eq!(result.len(), 2);
+    
+    let summary: Vec<_> = warnings.iter()
+        .map(|w| (w.kind, w.processor.as_str(), w.column.as_deref(), w.count))
+        .collect();
+    
+    // The average price of A is 1.5 once NaN is skipped, truncated by the cast
+    assert_eq!(summary, vec![
+        (WarningKind::NullsIntroduced, "multi_cast", Some("quantity"), 2),
+        (WarningKind::ValuesSkipped, "group_by", Some("price"), 1),
+        (WarningKind::ValuesCoerced, "cast", Some("price"), 1),
+    ]);
+    
+    // Without collection the same pipeline runs silently
+    assert_eq!(pipeline.execute(&dataset).unwrap().len(), 2);
+}
+
+#[test]
+fn test_records() {
+    let schema = Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("name".to_string(), DataType::String, true),
+    ]);
+    
+    // Missing fields are null, and unknown fields are rejected by default
+    let mut dataset = DataSet::new(schema);
+    dataset.push_record([("id", Value::from(1)), ("name", Value::from("Alice"))]).unwrap();
+    dataset.push_record([("id", 2)]).unwrap();
+    assert!(dataset.push_record([("id", 3), ("age", 30)]).is_err());
+    assert!(dataset.push_record([("name", "Carol")]).is_err());
+    
+    let records: Vec<_> = dataset.records().collect();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["name"], Value::String("Alice".to_string()));
+    assert_eq!(records[1]["name"], Value::Null);
+    
+    // Extending builds the schema from the records, backfilling earlier rows
+    let dataset = DataSet::try_from_records(Schema::new(vec![]), vec![
+        vec![("id", Value::from(1))],
+        vec![("id", Value::from(2)), ("score", Value::from(9.5))],
+    ], ExtraFields::Extend).unwrap();
+    
+    assert_eq!(dataset.schema.fields.len(), 2);
+    assert_eq!(dataset.schema.fields[1].data_type, DataType::Float);
+    assert_eq!(dataset.data[0].values[1], Value::Null);
+    assert_eq!(dataset.data[1].as_record(&dataset.schema)["score"], Value::Float(9.5));
+}
+
+#[test]
+fn test_column_lineage() {
+    let schema = Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("amount".to_string(), DataType::String, false),
+    ]);
+    
+    let mut dataset = DataSet::new(schema);
+    dataset.add_row(Row::new(vec![Value::Integer(1), Value::String("12".to_string())])).unwrap();
+    
+    let pipeline = Pipeline::new("lineage")
+        .add(CastTransform::new("amount", DataType::Integer))
+        .add(RenameTransform::new(vec![("amount".to_string(), "total".to_string())]))
+        .add(AddColumnTransform::new("double", DataType::Integer, false, |row, _| match row.values[1] {
+            Value::Integer(n) => Value::Integer(n * 2),
+            _ => Value::Null,
+        }).with_expression("total * 2"));
+    
+    let result = pipeline.execute(&dataset).unwrap();
+    
+    assert_eq!(result.metadata.lineage("total"), vec![
+        LineageStep::Cast { from: "string".to_string(), to: "integer".to_string() },
+        LineageStep::Renamed { from: "amount".to_string() },
+    ]);
+    assert_eq!(result.metadata.lineage("double"), vec![
+        LineageStep::Derived { expression: Some("total * 2".to_string()) },
+    ]);
+    assert!(result.metadata.lineage("amount").is_empty());
+    
+    // The catalog keeps the lineage of stored datasets and restores it on load
+    let storage = CatalogStorage::new(Arc::new(MemoryStorage::new()));
+    storage.store("orders", &result).unwrap();
+    assert_eq!(storage.lineage("orders").unwrap().len(), 2);
+    assert_eq!(storage.load("orders").unwrap().metadata.lineage("total").len(), 2);
+}
+
+/// Memory storage that fails every operation while it is down, for the failover and retry tests
+struct FlakyStorage {
+    backend: MemoryStorage,
+    down: Arc<AtomicBool>,
+}
+
+impl FlakyStorage {
+    fn check(&self) -> Result<(), StorageError> {
+        if self.down.load(Ordering::SeqCst) {
+            Err(StorageError::Unavailable("storage unavailable".to_string()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl DataStorage for FlakyStorage {
+    fn store(&self, name: &str, data: &DataSet) -> Result<(), StorageError> {
+        self.check()?;
+        self.backend.store(name, data)
+    }
+    
+    fn load(&self, name: &str) -> Result<DataSet, StorageError> {
+        self.check()?;
+        self.backend.load(name)
+    }
+    
+    fn exists(&self, name: &str) -> Result<bool, StorageError> {
+        self.check()?;
+        self.backend.exists(name)
+    }
+    
+    fn delete(&self, name: &str) -> Result<(), StorageError> {
+        self.check()?;
+        self.backend.delete(name)
+    }
+    
+    fn list(&self) -> Result<Vec<String>, StorageError> {
+        self.check()?;
+        self.backend.list()
+    }
+}
+
+#[test]
+fn test_failover_storage() {
+    let down = Arc::new(AtomicBool::new(false));
+    let primary = Arc::new(FlakyStorage { backend: MemoryStorage::new(), down: down.clone() });
+    let secondary = Arc::new(MemoryStorage::new());
+    let storage = FailoverStorage::new(primary.clone(), secondary.clone())
+        .with_check_interval(Duration::from_secs(3600))
+        .with_max_pending_writes(2);
+    
+    let schema = Schema::new(vec![Field::new("id".to_string(), DataType::Integer, false)]);
+    let mut dataset = DataSet::new(schema);
+    dataset.add_row(Row::new(vec![Value::Integer(1)])).unwrap();
+    
+    // Writes are mirrored while the primary is up
+    storage.store("a", &dataset).unwrap();
+    assert!(secondary.exists("a").unwrap());
+    
+    // Reads and writes fall back to the secondary while it is down
+    down.store(true, Ordering::SeqCst);
+    assert_eq!(storage.load("a").unwrap().len(), 1);
+    assert!(!storage.is_primary_healthy());
+    
+    storage.store("b", &dataset).unwrap();
+    storage.delete("a").unwrap();
+    assert_eq!(storage.list().unwrap(), vec!["b".to_string()]);
+    assert_eq!(storage.pending_writes(), 2);
+    
+    // A full queue refuses writes before the secondary gets them
+    assert!(matches!(storage.store("c", &dataset), Err(StorageError::Full(_))));
+    assert!(!secondary.exists("c").unwrap());
+    
+    // Nothing is replayed until the primary answers again
+    assert!(storage.recover().is_err());
+    
+    down.store(false, Ordering::SeqCst);
+    assert_eq!(storage.recover().unwrap(), 2);
+    assert!(storage.is_primary_healthy());
+    assert_eq!(primary.list().unwrap(), vec!["b".to_string()]);
+}
+
+#[test]
+fn test_retry_circuit_breaker() {
+    let down = Arc::new(AtomicBool::new(false));
+    let policy = RetryPolicy {
+        max_retries: 2,
+        initial_backoff: Duration::from_millis(1),
+        jitter: false,
+        ..RetryPolicy::default()
+    };
+    let storage = RetryStorage::new(Arc::new(FlakyStorage { backend: MemoryStorage::new(), down: down.clone() }), policy)
+        .with_circuit_breaker(2, Duration::from_millis(50));
+    
+    let schema = Schema::new(vec![Field::new("id".to_string(), DataType::Integer, false)]);
+    storage.store("a", &DataSet::new(schema)).unwrap();
+    
+    // Errors that fail the same way every time do not open the circuit
+    for _ in 0..3 {
+        assert!(!storage.load_columns("a", &["missing".to_string()]).unwrap_err().is_transient());
+    }
+    assert!(storage.load("a").is_ok());
+    
+    // Repeated transient failures open it, and calls then fail fast
+    down.store(true, Ordering::SeqCst);
+    assert!(storage.load("a").is_err());
+    assert!(storage.load("a").is_err());
+    down.store(false, Ordering::SeqCst);
+    assert!(storage.load("a").unwrap_err().to_string().contains("Circuit open"));
+    
+    // A trial call after the reset timeout closes it again
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(storage.load("a").is_ok());
+    assert!(storage.load("a").is_ok());
+}
+
+#[test]
+fn test_memory_limit() {
+    let schema = Schema::new(vec![Field::new("text".to_string(), DataType::String, false)]);
+    let mut dataset = DataSet::new(schema);
+    dataset.add_row(Row::new(vec![Value::String("x".repeat(1000))])).unwrap();
+    
+    // Room for two datasets of a row each
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+    let hook_evicted = evicted.clone();
+    let storage = MemoryStorage::new()
+        .with_memory_limit(2500, MemoryPolicy::EvictLru)
+        .with_eviction_hook(move |eviction| hook_evicted.lock().unwrap().push(eviction.name.clone()));
+    
+    storage.store("a", &dataset).unwrap();
+    storage.store("b", &dataset).unwrap();
+    storage.load("a").unwrap();
+    storage.store("c", &dataset).unwrap();
+    
+    assert_eq!(*evicted.lock().unwrap(), vec!["b".to_string()]);
+    assert!(storage.exists("a").unwrap() && !storage.exists("b").unwrap());
+    
+    let usage = storage.usage().unwrap();
+    assert_eq!((usage.datasets, usage.evicted, usage.max_bytes), (2, 1, Some(2500)));
+    assert!(usage.used_bytes <= 2500);
+    
+    // Rejecting keeps what is stored
+    let storage = MemoryStorage::new().with_memory_limit(2500, MemoryPolicy::Reject);
+    storage.store("a", &dataset).unwrap();
+    storage.store("b", &dataset).unwrap();
+    assert!(matches!(storage.store("c", &dataset), Err(StorageError::Full(_))));
+    
+    // Spilled datasets are still read through the storage
+    let spill = Arc::new(MemoryStorage::new());
+    let storage = MemoryStorage::new().with_memory_limit(2500, MemoryPolicy::Spill(spill.clone()));
+    storage.store("a", &dataset).unwrap();
+    storage.store("b", &dataset).unwrap();
+    storage.store("c", &dataset).unwrap();
+    
+    assert_eq!(spill.list().unwrap(), vec!["a".to_string()]);
+    assert_eq!(storage.load("a").unwrap().len(), 1);
+    assert_eq!(storage.list().unwrap().len(), 3);
+    
+    storage.delete("a").unwrap();
+    assert!(!spill.exists("a").unwrap());
+}
+
+#[test]
+fn test_schema_registry() {
+    let reading = r#"{"type": "record", "name": "Reading", "fields": [
+        {"name": "sensor", "type": "string"},
+        {"name": "value", "type": "double"},
+        {"name": "unit", "type": ["null", "string"], "default": null}
+    ]}"#;
+    let with_battery = r#"{"type": "record", "name": "Reading", "fields": [
+        {"name": "sensor", "type": "string"},
+        {"name": "value", "type": "double"},
+        {"name": "unit", "type": ["null", "string"], "default": null},
+        {"name": "battery", "type": ["null", "int"], "default": null}
+    ]}"#;
+    let with_location = r#"{"type": "record", "name": "Reading", "fields": [
+        {"name": "sensor", "type": "string"},
+        {"name": "location", "type": "string"}
+    ]}"#;
+    
+    let registry = SchemaRegistry::new("http://localhost:8081")
+        .with_schema(1, AvroSchema::parse(reading).unwrap())
+        .with_schema(2, AvroSchema::parse(with_battery).unwrap())
+        .with_schema(3, AvroSchema::parse(with_location).unwrap());
+    
+    // Wire format: magic byte, schema id, then the record with zigzag lengths and union indices
+    let message = |id: u8, body: &[u8]| [&[0, 0, 0, 0, id][..], body].concat();
+    let first = message(1, &[&[4][..], b"s1", &21.5f64.to_le_bytes(), &[2, 2], b"C"].concat());
+    let second = message(2, &[&[4][..], b"s2", &19.0f64.to_le_bytes(), &[0, 2, 0xa0, 0x01]].concat());
+    
+    let mut dataset = DataSet::new(registry.schema(1).unwrap().schema().clone());
+    registry.decode_into(&first, &mut dataset, Compatibility::Backward).unwrap();
+    assert_eq!(dataset.data[0].values, vec![
+        Value::String("s1".to_string()), Value::Float(21.5), Value::String("C".to_string()),
+    ]);
+    
+    // A new nullable column evolves the dataset
+    registry.decode_into(&second, &mut dataset, Compatibility::Backward).unwrap();
+    assert_eq!(dataset.schema.fields.len(), 4);
+    assert_eq!(dataset.schema.fields[3].data_type, DataType::Int32);
+    assert_eq!(dataset.data[0].values[3], Value::Null);
+    assert_eq!(dataset.data[1].values, vec![
+        Value::String("s2".to_string()), Value::Float(19.0), Value::Null, Value::Integer(80),
+    ]);
+    
+    // A required column breaks backward compatibility and leaves the dataset as it was
+    assert!(registry.decode_into(&message(3, &[]), &mut dataset, Compatibility::Backward).is_err());
+    assert_eq!((dataset.len(), dataset.schema.fields.len()), (2, 4));
+    
+    assert_eq!(Compatibility::from_str("FULL_TRANSITIVE").unwrap(), Compatibility::Full);
+    assert!(registry.decode_into(&[1, 2, 3], &mut dataset, Compatibility::None).is_err());
+}
+
+#[test]
+fn test_transactional_sink() {
+    let schema = Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("reading".to_string(), DataType::Float, false),
+    ]);
+    let batch = |ids: &[i64]| {
+        let mut batch = DataSet::new(schema.clone());
+        for &id in ids {
+            batch.add_row(Row::new(vec![Value::Integer(id), Value::Float(id as f64 / 2.0)])).unwrap();
+        }
+        batch
+    };
+    let offsets = |offset: u64| Offsets::from([("p0".to_string(), offset)]);
+    
+    let storage = Arc::new(MemoryStorage::new());
+    let checkpoints = Arc::new(CheckpointStore::new());
+    
+    let sink = TransactionalSink::new(storage.clone(), checkpoints.clone(), "readings", "ingest");
+    assert_eq!(sink.commit(&batch(&[1, 2]), &offsets(2)).unwrap(), 2);
+    assert_eq!(sink.commit(&batch(&[3]), &offsets(3)).unwrap(), 1);
+    
+    // A restarted job resumes from the checkpoint and skips batches it already wrote
+    let sink = TransactionalSink::new(storage.clone(), checkpoints.clone(), "readings", "ingest");
+    assert_eq!(sink.recover().unwrap(), offsets(3));
+    assert_eq!(sink.commit(&batch(&[3]), &offsets(3)).unwrap(), 0);
+    assert_eq!(storage.load("readings").unwrap().len(), 3);
+    
+    let checkpoint = checkpoints.checkpoint("ingest").unwrap();
+    assert_eq!((checkpoint.batches, checkpoint.rows), (2, 3));
+    
+    // Without offsets, key columns keep redelivered rows from being duplicated
+    let sink = TransactionalSink::new(storage.clone(), checkpoints, "keyed", "mqtt")
+        .with_key_columns(&["id".to_string()]);
+    sink.commit(&batch(&[1, 2]), &Offsets::new()).unwrap();
+    sink.commit(&batch(&[2, 3]), &Offsets::new()).unwrap();
+    assert_eq!(storage.load("keyed").unwrap().len(), 3);
+}
+
+#[test]
+fn test_temporal_types() {
+    // Read a CSV file with date and timestamp columns
+    let csv = "day,logged_at\n2024-02-28,2024-02-28T23:30:00Z\n2024-03-01,2024-03-01T08:15:00+02:00\n";
+    let dataset = CsvSource::new("events.csv", true, ',')
+        .with_type_inference()
+        .read_from(csv.as_bytes())
+        .unwrap();
+    
+    assert_eq!(dataset.schema.fields[0].data_type, DataType::Date);
+    assert_eq!(dataset.schema.fields[1].data_type, DataType::Timestamp);
+    
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    assert_eq!(dataset.data[1].values[0], Value::Date(date));
+    
+    // Timestamps with an offset are converted to UTC
+    let timestamp = date.and_hms_opt(6, 15, 0).unwrap().and_utc();
+    assert_eq!(dataset.data[1].values[1], Value::Timestamp(timestamp));
+    
+    // Filter against a string literal and cast timestamps to dates
+    let pipeline = Pipeline::new("test")
+        .add(FilterProcessor::greater_than("day", Value::String("2024-02-29".to_string())))
+        .add(CastTransform::new("logged_at", DataType::Date));
+    
+    let result = pipeline.process(&dataset).unwrap();
+    
+    assert_eq!(result.len(), 1);
+    assert_eq!(result.schema.fields[1].data_type, DataType::Date);
+    assert_eq!(result.data[0].values[1], Value::Date(date));
+}
+
+#[test]
+fn test_decimal_sum() {
+    let schema = Schema::new(vec![
+        Field::new("category".to_string(), DataType::String, false),
+        Field::new("amount".to_string(), DataType::String, false),
+    ]);
+    
+    let mut dataset = DataSet::new(schema);
+    for (category, amount) in [("A", "0.10"), ("A", "0.20"), ("B", "1,234.50"), ("B", "0.05")] {
+        dataset.add_row(Row::new(vec![
+            Value::String(category.to_string()),
+            Value::String(amount.to_string()),
+        ])).unwrap();
+    }
+    
+    let pipeline = Pipeline::new("money")
+        .add(CastTransform::new("amount", DataType::Decimal(10, 2)).with_format(CastFormat::new().thousands_separator(',')))
+        .add(GroupByProcessor::new()
+            .group_by("category")
+            .sum("total", "amount")
+            .avg("average", "amount")
+            .max("largest", "amount"));
+    
+    let result = pipeline.process(&dataset).unwrap();
+    assert_eq!(result.schema.fields[1].data_type, DataType::Decimal(20, 2));
+    assert_eq!(result.schema.fields[2].data_type, DataType::Decimal(14, 6));
+    
+    let decimal = |s: &str| Value::Decimal(Decimal::from_str(s).unwrap());
+    
+    // Sums of decimals are exact, where 0.1 + 0.2 as floats is not 0.3
+    for row in &result.data {
+        if row.values[0] == Value::String("A".to_string()) {
+            assert_eq!(row.values[1], decimal("0.30"));
+            assert_eq!(row.values[2], decimal("0.150000"));
+            assert_eq!(row.values[3], decimal("0.20"));
+        } else {
+            assert_eq!(row.values[1], decimal("1234.55"));
+            assert_eq!(row.values[2], decimal("617.275000"));
+            assert_eq!(row.values[3], decimal("1234.50"));
+        }
+    }
+    
+    // Values with more digits than the column holds do not cast
+    assert!(CastTransform::new("amount", DataType::Decimal(4, 2))
+        .with_format(CastFormat::new().thousands_separator(','))
+        .process(&dataset)
+        .is_err());
+}
+
+/// Sink taking a while to write each batch
+struct SlowSink {
+    rows: Mutex<Vec<Row>>,
+}
+
+impl DataSink for SlowSink {
+    fn write(&self, data: &DataSet) -> Result<(), DataError> {
+        std::thread::sleep(Duration::from_millis(5));
+        self.rows.lock().unwrap().extend(data.data.iter().cloned());
+        Ok(())
+    }
+    
+    fn name(&self) -> &str {
+        "slow"
+    }
+    
+    fn sink_type(&self) -> SinkType {
+        SinkType::Custom("slow".to_string())
+    }
+}
+
+#[test]
+fn test_streaming_backpressure() {
+    let schema = Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+    ]);
+    
+    let executor = StreamingExecutor::new(Pipeline::new("stream")
+        .add(FilterProcessor::greater_than("id", Value::Integer(0))))
+        .with_queue_capacity(2);
+    let metrics = executor.metrics();
+    
+    // The source can only run a few batches ahead of the slow sink
+    let mut lead = 0;
+    let source = (0..20).map(|i| {
+        lead = lead.max(i - metrics.rows_written() as i64);
+        
+        let mut batch = DataSet::new(schema.clone());
+        batch.add_row(Row::new(vec![Value::Integer(i)])).unwrap();
+        Ok(batch)
+    });
+    
+    let sink = SlowSink { rows: Mutex::new(Vec::new()) };
+    let summary = executor.run(source, &sink).unwrap();
+    
+    assert_eq!(summary.rows_read, 20);
+    assert_eq!(summary.rows_written, 19);
+    assert!(lead <= 8, "source ran {} batches ahead", lead);
+    assert_eq!(sink.rows.lock().unwrap().len(), 19);
+    
+    let queue = executor.metrics().sink_queue();
+    assert_eq!(queue.high_water, 2);
+    assert_eq!(queue.depth, 0);
+    assert!(queue.blocked > Duration::from_millis(0));
+}
+
+#[test]
+fn test_event_time_watermarks() {
+    let schema = Schema::new(vec![
+        Field::new("time".to_string(), DataType::Integer, false),
+        Field::new("value".to_string(), DataType::Integer, false),
+    ]);
+    
+    let late = Arc::new(SlowSink { rows: Mutex::new(Vec::new()) });
+    let executor = StreamingExecutor::new(Pipeline::new("stream"))
+        .with_window(EventTimeWindow::new("time", 10)
+            .with_allowed_lateness(5)
+            .with_late_sink(late.clone())
+            .sum("total", "value"));
+    
+    // Time 3 arrives out of order but before the watermark closes its window;
+    // time 8 arrives after the watermark reached 20 and is late
+    let batches = vec![vec![1, 12], vec![3, 25], vec![8, 27]];
+    let source = batches.into_iter().map(|times| {
+        let mut batch = DataSet::new(schema.clone());
+        for time in times {
+            batch.add_row(Row::new(vec![Value::Integer(time), Value::Integer(time)])).unwrap();
+        }
+        Ok(batch)
+    });
+    
+    let sink = SlowSink { rows: Mutex::new(Vec::new()) };
+    executor.run(source, &sink).unwrap();
+    
+    // Windows are written as the watermark closes them, the last at the end of the stream
+    let totals: Vec<Value> = sink.rows.lock().unwrap().iter().map(|row| row.values[2].clone()).collect();
+    assert_eq!(totals, vec![Value::Integer(4), Value::Integer(12), Value::Integer(52)]);
+    
+    let start = sink.rows.lock().unwrap()[1].values[0].clone();
+    assert_eq!(start, Value::Timestamp(chrono::DateTime::from_timestamp(10, 0).unwrap()));
+    
+    let window = executor.window().unwrap();
+    assert_eq!(window.watermark(), Some(22));
+    assert_eq!(window.late_rows(), 1);
+    assert_eq!(late.rows.lock().unwrap()[0].values[0], Value::Integer(8));
+}
+
+#[test]
+fn test_views() {
+    let storage = CatalogStorage::new(Arc::new(MemoryStorage::new()));
+    
+    let schema = Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("amount".to_string(), DataType::Integer, false),
+    ]);
+    let mut sales = DataSet::new(schema);
+    for (id, amount) in [(1, 5), (2, 20), (3, 30)] {
+        sales.add_row(Row::new(vec![Value::Integer(id), Value::Integer(amount)])).unwrap();
+    }
+    storage.store("sales", &sales).unwrap();
+    
+    let view = ViewDefinition::new("sales")
+        .step(StepSpec::Filter { expression: "amount > 10".to_string() })
+        .step(StepSpec::Select { columns: vec!["id".to_string()] });
+    storage.create_view("big_sales", &view).unwrap();
+    
+    // Views are computed on read from the current rows of their source
+    assert!(storage.exists("big_sales").unwrap());
+    assert_eq!(storage.load("big_sales").unwrap().len(), 2);
+    
+    sales.add_row(Row::new(vec![Value::Integer(4), Value::Integer(40)])).unwrap();
+    storage.store("sales", &sales).unwrap();
+    
+    let result = storage.load("big_sales").unwrap();
+    assert_eq!(result.len(), 3);
+    assert_eq!(result.schema.fields.len(), 1);
+    
+    // Views hold no rows of their own and cannot read themselves
+    assert!(matches!(storage.store("big_sales", &sales), Err(StorageError::ReadOnly(_))));
+    assert!(!storage.list().unwrap().contains(&"big_sales".to_string()));
+    assert!(storage.create_view("sales_loop", &ViewDefinition::new("sales_loop")).is_err());
+    assert!(matches!(storage.create_view("other", &ViewDefinition::new("missing")), Err(StorageError::NotFound(_))));
+}
+
+#[test]
+fn test_sort_processor() {
+    let schema = Schema::new(vec![
+        Field::new("region".to_string(), DataType::String, true),
+        Field::new("amount".to_string(), DataType::Integer, true),
+    ]);
+    let mut dataset = DataSet::new(schema);
+    
+    let rows = [(Some("b"), Some(1)), (None, Some(2)), (Some("a"), None), (Some("a"), Some(3)), (Some("b"), Some(4))];
+    for (region, amount) in rows {
+        dataset.add_row(Row::new(vec![
+            region.map_or(Value::Null, |r| Value::String(r.to_string())),
+            amount.map_or(Value::Null, Value::Integer),
+        ])).unwrap();
+    }
+    
+    let sort = SortProcessor::new(vec![SortKey::asc("region"), SortKey::desc("amount").nulls_first()]);
+    let result = sort.process(&dataset).unwrap();
+    
+    let amounts = |data: &DataSet| data.data.iter().map(|row| row.values[1].clone()).collect::<Vec<_>>();
+    assert_eq!(amounts(&result), vec![Value::Null, Value::Integer(3), Value::Integer(4), Value::Integer(1), Value::Integer(2)]);
+    
+    // The sort round-trips through a pipeline specification
+    let pipeline = Pipeline::new("sorted").add(sort);
+    let rebuilt = pipeline.to_spec().unwrap().build().unwrap();
+    assert_eq!(amounts(&rebuilt.execute(&dataset).unwrap()), amounts(&result));
+    
+    assert!(SortProcessor::new(vec![SortKey::asc("missing")]).process(&dataset).is_err());
+}
+
+#[test]
+fn test_cardinality_estimates() {
+    let schema = Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("region".to_string(), DataType::String, false),
+    ]);
+    let mut sales = DataSet::new(schema.clone());
+    for i in 0..100 {
+        let region = ["EU", "US", "APAC", "LATAM"][i % 4];
+        sales.add_row(Row::new(vec![Value::Integer(i as i64), Value::String(region.to_string())])).unwrap();
+    }
+    
+    let statistics = TableEstimate::compute(&sales);
+    assert_eq!(statistics.rows, 100);
+    assert_eq!(statistics.column("region").unwrap().distinct, 4);
+    
+    // Equalities keep one distinct value and ranges their share of the bounds
+    let equality = FilterExpression::parse("region == 'EU'").unwrap();
+    assert!((statistics.selectivity(&equality) - 0.25).abs() < 1e-9);
+    assert_eq!(statistics.filter(&FilterExpression::parse("id < 33").unwrap()).rows, 33);
+    assert_eq!(statistics.filter(&FilterExpression::parse("id > 1000").unwrap()).rows, 0);
+    
+    // Aggregations are estimated from distinct values rather than scaled from the sample
+    let pipeline = Pipeline::new("totals").add(GroupByProcessor::new().group_by("region").count("n", "id"));
+    let mut sample = DataSet::new(schema);
+    for row in sales.data.iter().take(10) {
+        sample.add_row(row.clone()).unwrap();
+    }
+    
+    let plan = pipeline.dry_run_with_statistics(&sample, &statistics).unwrap();
+    assert_eq!(plan.estimated_rows, 4);
+    
+    // Joins divide the row pairs by the distinct keys
+    let mut regions = DataSet::new(Schema::new(vec![Field::new("region".to_string(), DataType::String, false)]));
+    for region in ["EU", "US", "APAC", "LATAM"] {
+        regions.add_row(Row::new(vec![Value::String(region.to_string())])).unwrap();
+    }
+    
+    let join = JoinProcessor::inner(vec!["region".to_string()], vec!["region".to_string()]);
+    assert_eq!(join.estimate_rows(&statistics, &TableEstimate::compute(&regions)), 100);
+    
+    // Inner joins hash the smaller input, with the same output
+    assert_eq!(join.build_side(regions.len(), sales.len()), BuildSide::Left);
+    let joined = join.process_join(&regions, &sales).unwrap();
+    assert_eq!(joined.len(), 100);
+    assert_eq!(joined.data[0].values, vec![Value::String("EU".to_string()), Value::Integer(0)]);
+    assert_eq!(joined.data[1].values, vec![Value::String("EU".to_string()), Value::Integer(4)]);
+    
+    // The most selective filters run first
+    let pipeline = Pipeline::new("filters")
+        .add(FilterProcessor::expression("id >= 10").unwrap())
+        .add(FilterProcessor::expression("region == 'EU'").unwrap())
+        .order_filters(&statistics);
+    match &pipeline.to_spec().unwrap().steps[0] {
+        StepSpec::Filter { expression } => assert_eq!(expression, &equality.to_string()),
+        step => panic!("unexpected step {:?}", step),
+    }
+    assert_eq!(pipeline.execute(&sales).unwrap().len(), 22);
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn test_encryption_at_rest() {
+    use rust_data_processing_engine::storage::{EncryptionKey, Encryptor, FileFormat, FileStorage, KeyRing, MaintenanceReport};
+    
+    let dir = tempfile::tempdir().unwrap();
+    let v1 = EncryptionKey::new("v1", vec![1; 32]).unwrap();
+    let v2 = EncryptionKey::new("v2", vec![2; 32]).unwrap();
+    
+    let mut dataset = DataSet::new(Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("name".to_string(), DataType::String, false),
+    ]));
+    dataset.add_row(Row::new(vec![Value::Integer(1), Value::String("Alice".to_string())])).unwrap();
+    dataset.add_row(Row::new(vec![Value::Integer(2), Value::String("Bob".to_string())])).unwrap();
+    
+    // Files written before encryption was enabled stay readable
+    std::fs::write(dir.path().join("legacy.csv"), "id,name\n3,Charlie\n").unwrap();
+    
+    let storage = FileStorage::new(dir.path(), FileFormat::Csv).unwrap()
+        .with_encryption(Arc::new(KeyRing::new(v1.clone()))).unwrap();
+    storage.store("people", &dataset).unwrap();
+    
+    let bytes = std::fs::read(dir.path().join("people.csv")).unwrap();
+    assert_eq!(Encryptor::key_id(&bytes).as_deref(), Some("v1"));
+    assert!(!String::from_utf8_lossy(&bytes).contains("Alice"));
+    
+    let loaded = storage.load("people").unwrap();
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(loaded.data[0].values[1], Value::String("Alice".to_string()));
+    assert_eq!(storage.load("legacy").unwrap().len(), 1);
+    
+    // Batches are read from encrypted and plaintext files alike
+    assert!(Encryptor::is_encrypted_file(&dir.path().join("people.csv")).unwrap());
+    assert!(!Encryptor::is_encrypted_file(&dir.path().join("legacy.csv")).unwrap());
+    
+    let batches: Vec<_> = storage.load_batches("people", 1).unwrap().map(|batch| batch.unwrap().len()).collect();
+    assert_eq!(batches, vec![1, 1]);
+    assert_eq!(storage.load_batches("legacy", 10).unwrap().count(), 1);
+    assert!(storage.load_batches("missing", 10).is_err());
+    
+    // Parquet and ORC would be written in plaintext
+    assert!(matches!(storage.store_as("people", &dataset, FileFormat::Parquet), Err(StorageError::InvalidFormat(_))));
+    assert!(matches!(storage.store_as("orc", &dataset, FileFormat::Orc), Err(StorageError::InvalidFormat(_))));
+    
+    // After a rotation, maintenance re-encrypts plaintext files and those of retired keys
+    let storage = FileStorage::new(dir.path(), FileFormat::Csv).unwrap()
+        .with_encryption(Arc::new(KeyRing::new(v2.clone()).with_key(v1))).unwrap();
+    assert_eq!(storage.load("people").unwrap().len(), 2);
+    
+    let mut report = MaintenanceReport::default();
+    storage.maintain(&mut report).unwrap();
+    assert_eq!(report.files_reencrypted, 2);
+    
+    for name in ["people", "legacy"] {
+        let bytes = std::fs::read(dir.path().join(format!("{}.csv", name))).unwrap();
+        assert_eq!(Encryptor::key_id(&bytes).as_deref(), Some("v2"));
+    }
+    
+    // Retired keys are no longer needed
+    let storage = FileStorage::new(dir.path(), FileFormat::Csv).unwrap()
+        .with_encryption(Arc::new(KeyRing::new(v2))).unwrap();
+    assert_eq!(storage.load("people").unwrap().data[1].values[1], Value::String("Bob".to_string()));
+    assert_eq!(storage.load("legacy").unwrap().len(), 1);
+    
+    let mut report = MaintenanceReport::default();
+    storage.maintain(&mut report).unwrap();
+    assert_eq!(report.files_reencrypted, 0);
+}
+
+#[test]
+fn test_moving_average_by_timestamp() {
+    let schema = Schema::new(vec![
+        Field::new("sensor".to_string(), DataType::String, false),
+        Field::new("time".to_string(), DataType::Timestamp, false),
+        Field::new("value".to_string(), DataType::Integer, false),
+    ]);
+    let mut readings = DataSet::new(schema);
+    
+    // Rows arrive out of time order
+    for (sensor, seconds, value) in [("a", 30, 30), ("a", 10, 10), ("a", 20, 20), ("b", 5, 100)] {
+        readings.add_row(Row::new(vec![
+            Value::String(sensor.to_string()),
+            Value::Timestamp(chrono::DateTime::from_timestamp(seconds, 0).unwrap()),
+            Value::Integer(value),
+        ])).unwrap();
+    }
+    
+    let transform = MovingAverageTransform::new("value", 2, "avg")
+        .with_min_periods(1)
+        .partition_by(vec!["sensor".to_string()])
+        .order_by("time");
+    let result = transform.process(&readings).unwrap();
+    
+    let averages: Vec<Value> = result.data.iter().map(|row| row.values[3].clone()).collect();
+    assert_eq!(averages, vec![Value::Float(25.0), Value::Float(10.0), Value::Float(15.0), Value::Float(100.0)]);
+}
+
+#[test]
+fn test_smoothing_with_nulls() {
+    let mut readings = DataSet::new(Schema::new(vec![Field::new("value".to_string(), DataType::Integer, true)]));
+    for value in [Value::Integer(10), Value::Null, Value::Integer(20), Value::Integer(40)] {
+        readings.add_row(Row::new(vec![value])).unwrap();
+    }
+    
+    let smoothed = |transform: &dyn DataProcessor| transform.process(&readings).unwrap().data.iter()
+        .map(|row| row.values[1].clone())
+        .collect::<Vec<_>>();
+    let floats = |values: &[Option<f64>]| values.iter()
+        .map(|value| value.map_or(Value::Null, Value::Float))
+        .collect::<Vec<_>>();
+    
+    // Windows need enough non-null values, and nulls carry the average forward
+    assert_eq!(smoothed(&MovingAverageTransform::new("value", 2, "avg")),
+        floats(&[None, None, None, Some(30.0)]));
+    assert_eq!(smoothed(&MovingAverageTransform::new("value", 2, "avg").with_min_periods(1)),
+        floats(&[Some(10.0), Some(10.0), Some(20.0), Some(30.0)]));
+    assert_eq!(smoothed(&EwmaTransform::new("value", 3.0, "ewma")),
+        floats(&[Some(10.0), Some(10.0), Some(15.0), Some(27.5)]));
+    
+    // Empty windows, smoothing factors outside (0, 1] and unknown columns are rejected
+    assert!(MovingAverageTransform::new("value", 0, "avg").process(&readings).is_err());
+    assert!(EwmaTransform::with_alpha("value", 0.0, "ewma").process(&readings).is_err());
+    assert!(EwmaTransform::with_alpha("value", 1.5, "ewma").process(&readings).is_err());
+    assert!(EwmaTransform::new("reading", 3.0, "ewma").process(&readings).is_err());
+}
+
+#[test]
+fn test_resample_limits() {
+    assert_eq!(ResampleProcessor::parse_frequency("15m").unwrap(), 900);
+    assert!(ResampleProcessor::parse_frequency("99999999999999999w").is_err());
+    
+    let mut readings = DataSet::new(Schema::new(vec![
+        Field::new("time".to_string(), DataType::Integer, false),
+        Field::new("value".to_string(), DataType::Float, true),
+    ]));
+    for time in [0, 10, 1_000_000_000] {
+        readings.add_row(Row::new(vec![Value::Integer(time), Value::Float(1.0)])).unwrap();
+    }
+    
+    // Two readings decades apart would fill billions of one-second intervals
+    assert!(ResampleProcessor::new("time", 1).process(&readings).is_err());
+    assert!(ResampleProcessor::new("time", 60).with_max_buckets(10).process(&readings).is_err());
+    assert_eq!(ResampleProcessor::new("time", 100_000_000).process(&readings).unwrap().len(), 11);
+}
+
+#[test]
+fn test_resample_fill() {
+    let mut readings = DataSet::new(Schema::new(vec![
+        Field::new("time".to_string(), DataType::Integer, false),
+        Field::new("value".to_string(), DataType::Integer, true),
+    ]));
+    for (time, value) in [(0, 1), (5, 3), (30, 9)] {
+        readings.add_row(Row::new(vec![Value::Integer(time), Value::Integer(value)])).unwrap();
+    }
+    
+    let resample = |fill: FillMethod| ResampleProcessor::new("time", 10)
+        .aggregate("value", ResampleAggregation::Mean)
+        .with_fill(fill)
+        .process(&readings)
+        .unwrap();
+    let means = |result: &DataSet| result.data.iter()
+        .map(|row| match row.values[1] {
+            Value::Float(f) => Some((f * 1000.0).round() / 1000.0),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    
+    // Rows in one interval are aggregated, and empty intervals filled
+    let result = resample(FillMethod::Null);
+    let times: Vec<Value> = result.data.iter().map(|row| row.values[0].clone()).collect();
+    assert_eq!(times, vec![Value::Integer(0), Value::Integer(10), Value::Integer(20), Value::Integer(30)]);
+    assert_eq!(means(&result), vec![Some(2.0), None, None, Some(9.0)]);
+    
+    assert_eq!(means(&resample(FillMethod::ForwardFill)), vec![Some(2.0), Some(2.0), Some(2.0), Some(9.0)]);
+    assert_eq!(means(&resample(FillMethod::Linear)), vec![Some(2.0), Some(4.333), Some(6.667), Some(9.0)]);
+    
+    // Unknown fills, zero intervals and unparsable times are rejected
+    assert!(FillMethod::from_str("cubic").is_err());
+    assert!(ResampleProcessor::new("time", 0).process(&readings).is_err());
+    
+    let mut untimed = readings.clone();
+    untimed.schema.fields[0].data_type = DataType::String;
+    untimed.data[0].values[0] = Value::String("soon".to_string());
+    assert!(ResampleProcessor::new("time", 10).process(&untimed).is_err());
+}
+
+#[test]
+fn test_change_events_match_typed_keys() {
+    let mut customers = DataSet::new(Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("name".to_string(), DataType::String, true),
+    ]));
+    for (id, name) in [(1, "Alice"), (2, "Bob")] {
+        customers.add_row(Row::new(vec![Value::Integer(id), Value::String(name.to_string())])).unwrap();
+    }
+    
+    // Keys arrive as strings and floats rather than integers
+    let events = [
+        serde_json::json!({"op": "u", "before": {"id": "1"}, "after": {"id": "1", "name": "Alicia"}}),
+        serde_json::json!({"op": "d", "before": {"id": 2.0}}),
+        serde_json::json!({"op": "c", "after": {"id": "3", "name": "Carol"}}),
+    ];
+    let events: Vec<ChangeEvent> = events.iter().map(|event| ChangeEvent::from_json(event).unwrap()).collect();
+    
+    let summary = ChangeApplier::new(vec!["id".to_string()]).apply(&mut customers, &events).unwrap();
+    assert_eq!((summary.inserted, summary.updated, summary.deleted), (1, 1, 1));
+    assert_eq!(customers.data[0].values, vec![Value::Integer(1), Value::String("Alicia".to_string())]);
+    assert_eq!(customers.data[1].values, vec![Value::Integer(3), Value::String("Carol".to_string())]);
+    
+    // Enveloped events parse like bare ones; unknown operations and missing images do not
+    let enveloped = serde_json::json!({"payload": {"op": "r", "after": {"id": 4, "name": "Dan"}}});
+    assert!(ChangeEvent::from_json(&enveloped).is_ok());
+    assert!(ChangeEvent::from_json(&serde_json::json!({"op": "t", "after": {"id": 1}})).is_err());
+    assert!(ChangeEvent::from_json(&serde_json::json!({"op": "u", "before": {"id": 1}})).is_err());
+    assert!(ChangeEvent::from_json(&serde_json::json!({"after": {"id": 1}})).is_err());
+    
+    // Keys must be columns of the target
+    assert!(ChangeApplier::new(vec!["email".to_string()]).apply(&mut customers, &events).is_err());
+    assert!(ChangeApplier::new(Vec::new()).apply(&mut customers, &events).is_err());
+}
+
+#[test]
+fn test_flatten_round_trip() {
+    let map = |entries: Vec<(&str, Value)>| Value::Map(
+        entries.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+    );
+    
+    let mut customers = DataSet::new(Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("address".to_string(), DataType::Map(Box::new(DataType::String)), true),
+    ]));
+    
+    // One key contains the separator, and "zip" mixes integers and strings
+    let addresses = vec![
+        map(vec![
+            ("city", Value::String("Lisbon".to_string())),
+            ("geo", map(vec![("lat.deg", Value::Float(38.7)), ("lon", Value::Float(-9.1))])),
+            ("zip", Value::Integer(1100)),
+        ]),
+        map(vec![
+            ("city", Value::String("Porto".to_string())),
+            ("geo", map(vec![("lat.deg", Value::Float(41.1)), ("lon", Value::Float(-8.6))])),
+            ("zip", Value::String("4000-001".to_string())),
+        ]),
+    ];
+    for (id, address) in addresses.iter().enumerate() {
+        customers.add_row(Row::new(vec![Value::Integer(id as i64), address.clone()])).unwrap();
+    }
+    
+    let flat = FlattenTransform::new().process(&customers).unwrap();
+    let names: Vec<&str> = flat.schema.fields.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["id", "address.city", "address.geo.lat.deg", "address.geo.lon", "address.zip"]);
+    assert_eq!(flat.schema.fields[4].data_type, DataType::String);
+    assert_eq!(flat.data[0].values[4], Value::String("1100".to_string()));
+    
+    // Rebuilding the maps from the leaf columns gives back the original rows
+    let leaves = [
+        (1, vec!["city"]),
+        (2, vec!["geo", "lat.deg"]),
+        (3, vec!["geo", "lon"]),
+        (4, vec!["zip"]),
+    ];
+    for (row, original) in flat.data.iter().zip(&addresses) {
+        let mut rebuilt = std::collections::HashMap::new();
+        
+        for (col_idx, path) in &leaves {
+            let mut node = &mut rebuilt;
+            for key in &path[..path.len() - 1] {
+                node = match node.entry(key.to_string()).or_insert_with(|| Value::Map(Default::default())) {
+                    Value::Map(nested) => nested,
+                    _ => unreachable!(),
+                };
+            }
+            node.insert(path[path.len() - 1].to_string(), row.values[*col_idx].clone());
+        }
+        
+        let expected = match original {
+            Value::Map(entries) => {
+                let mut entries = entries.clone();
+                if let Some(Value::Integer(zip)) = entries.get("zip") {
+                    entries.insert("zip".to_string(), Value::String(zip.to_string()));
+                }
+                Value::Map(entries)
+            },
+            _ => unreachable!(),
+        };
+        assert_eq!(Value::Map(rebuilt), expected);
+    }
+    
+    // Exploding an array of mixed elements keeps one row per element as strings
+    let mut orders = DataSet::new(Schema::new(vec![
+        Field::new("tags".to_string(), DataType::Array(Box::new(DataType::String)), true),
+    ]));
+    orders.add_row(Row::new(vec![Value::Array(vec![Value::Integer(7), Value::String("new".to_string())])])).unwrap();
+    
+    let exploded = ExplodeTransform::new("tags").process(&orders).unwrap();
+    assert_eq!(exploded.schema.fields[0].data_type, DataType::String);
+    assert_eq!(exploded.data.iter().map(|row| row.values[0].clone()).collect::<Vec<_>>(),
+        vec![Value::String("7".to_string()), Value::String("new".to_string())]);
+    
+    // Named columns must exist
+    assert!(FlattenTransform::columns(vec!["contact".to_string()]).process(&customers).is_err());
+    assert!(ExplodeTransform::new("items").process(&orders).is_err());
+}
+
+#[test]
+fn test_parallel_partitions_match_single_partition() {
+    // Enough rows for four partitions of at least 10,000 rows each
+    let mut sales = DataSet::new(Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("store".to_string(), DataType::Integer, false),
+        Field::new("amount".to_string(), DataType::Integer, false),
+    ]));
+    for id in 0..40_000i64 {
+        sales.add_row(Row::new(vec![
+            Value::Integer(id),
+            Value::Integer((id * 7919) % 997),
+            Value::Integer(id % 100),
+        ])).unwrap();
+    }
+    
+    let group_by = |partitions| GroupByProcessor::new()
+        .group_by("store")
+        .sum("total", "amount")
+        .count("sales", "id")
+        .with_partitions(partitions);
+    
+    let rows = |dataset: &DataSet| dataset.data.iter().map(|row| row.values.clone()).collect::<Vec<_>>();
+    
+    let single = group_by(1).process(&sales).unwrap();
+    let parallel = group_by(4).process(&sales).unwrap();
+    assert_eq!(single.len(), 997);
+    assert_eq!(rows(&parallel), rows(&single));
+    
+    // Stores, with some missing and some listed twice
+    let mut stores = DataSet::new(Schema::new(vec![
+        Field::new("store".to_string(), DataType::Integer, false),
+        Field::new("region".to_string(), DataType::String, false),
+    ]));
+    for store in (0..997i64).filter(|store| store % 7 != 0) {
+        stores.add_row(Row::new(vec![Value::Integer(store), Value::String("north".to_string())])).unwrap();
+        
+        if store % 3 == 0 {
+            stores.add_row(Row::new(vec![Value::Integer(store), Value::String("south".to_string())])).unwrap();
+        }
+    }
+    
+    for join_type in [JoinType::Inner, JoinType::Left] {
+        let join = |partitions| JoinProcessor::new(join_type, vec!["store".to_string()], vec!["store".to_string()])
+            .with_partitions(partitions);
+        
+        let single = join(1).process_join(&sales, &stores).unwrap();
+        let parallel = join(4).process_join(&sales, &stores).unwrap();
+        assert_eq!(rows(&parallel), rows(&single), "{:?} join order differs", join_type);
+    }
+}
+
+#[test]
+fn test_partitioned_window_functions() {
+    let mut sales = DataSet::new(Schema::new(vec![
+        Field::new("dept".to_string(), DataType::String, false),
+        Field::new("day".to_string(), DataType::Integer, false),
+        Field::new("amount".to_string(), DataType::Integer, false),
+    ]));
+    for (dept, day, amount) in [("a", 3, 20), ("b", 1, 50), ("a", 1, 10), ("a", 2, 20), ("b", 2, 40), ("a", 4, 30)] {
+        sales.add_row(Row::new(vec![Value::String(dept.to_string()), Value::Integer(day), Value::Integer(amount)])).unwrap();
+    }
+    
+    let window = |processor: WindowProcessor| {
+        let result = processor.partition_by(vec!["dept".to_string()]).process(&sales).unwrap();
+        result.data.iter().map(|row| row.values[3].clone()).collect::<Vec<_>>()
+    };
+    let integers = |values: &[Option<i64>]| values.iter()
+        .map(|value| value.map_or(Value::Null, Value::Integer))
+        .collect::<Vec<_>>();
+    
+    // Ties share a rank and the next rank follows without a gap
+    let ranks = window(WindowProcessor::dense_rank("rank").order_by(vec![("amount".to_string(), true)]));
+    assert_eq!(ranks, integers(&[Some(2), Some(2), Some(1), Some(2), Some(1), Some(3)]));
+    
+    // Lead and lag read the value column, not the order column
+    let by_day = vec![("day".to_string(), true)];
+    let next = window(WindowProcessor::lead("next", "amount", 1).order_by(by_day.clone()));
+    assert_eq!(next, integers(&[Some(30), Some(40), Some(20), Some(20), None, None]));
+    
+    let previous = window(WindowProcessor::lag("previous", "amount", 1).order_by(by_day));
+    assert_eq!(previous, integers(&[Some(20), None, None, Some(10), Some(50), Some(20)]));
+    
+    // Built-in functions compare by kind, custom functions never compare equal
+    assert_eq!(WindowFunctionType::from_str("LEAD").unwrap(), WindowFunctionType::Lead);
+    assert!(WindowFunctionType::from_str("median").is_err());
+    
+    fn constant(_: &[&Row], _: usize) -> Value {
+        Value::Integer(1)
+    }
+    assert_ne!(WindowFunctionType::Custom(constant), WindowFunctionType::Custom(constant));
+}
+
+#[test]
+fn test_insert_row_defaults_and_types() {
+    let mut orders = DataSet::new(Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false).with_default(FieldDefault::AutoIncrement),
+        Field::new("item".to_string(), DataType::String, false),
+        Field::new("status".to_string(), DataType::String, false)
+            .with_default(FieldDefault::Value(Value::String("new".to_string()))),
+    ]));
+    
+    // Missing and null values are generated from the defaults
+    orders.insert_row(Row::new(vec![Value::Null, Value::String("pen".to_string())])).unwrap();
+    orders.insert_row(Row::new(vec![Value::Null, Value::String("ink".to_string()), Value::Null])).unwrap();
+    assert_eq!(orders.data[1].values, vec![
+        Value::Integer(2), Value::String("ink".to_string()), Value::String("new".to_string()),
+    ]);
+    
+    // Nulls without a default and values of the wrong type are rejected
+    assert!(orders.insert_row(Row::new(vec![Value::Integer(3), Value::Null])).is_err());
+    
+    let err = orders.insert_row(Row::new(vec![Value::String("four".to_string()), Value::String("cap".to_string())]))
+        .unwrap_err();
+    assert_eq!(err.context().and_then(|context| context.column.as_deref()), Some("id"));
+    assert_eq!(orders.len(), 2);
+}
+
+#[test]
+fn test_incremental_export() {
+    let dir = tempfile::tempdir().unwrap();
+    let exporter = IncrementalExporter::new(dir.path(), vec!["id".to_string()]).unwrap();
+    
+    // Maps are rebuilt for every export, so their entry order changes
+    let devices = |firmware: &str| {
+        let mut devices = DataSet::new(Schema::new(vec![
+            Field::new("id".to_string(), DataType::Integer, false),
+            Field::new("attributes".to_string(), DataType::Map(Box::new(DataType::String)), true),
+        ]));
+        for id in 0..3 {
+            let mut attributes: std::collections::HashMap<String, Value> = (0..16)
+                .map(|i| (format!("key{}", i), Value::String(i.to_string())))
+                .collect();
+            if id == 0 {
+                attributes.insert("firmware".to_string(), Value::String(firmware.to_string()));
+            }
+            devices.add_row(Row::new(vec![Value::Integer(id), Value::Map(attributes)])).unwrap();
+        }
+        devices
+    };
+    
+    assert_eq!(exporter.export("devices", &devices("1.0")).unwrap(),
+        ExportOutcome::Snapshot { file: "snapshot.json".to_string(), rows: 3 });
+    assert_eq!(exporter.export("devices", &devices("1.0")).unwrap(), ExportOutcome::Unchanged);
+    assert_eq!(exporter.export("devices", &devices("1.1")).unwrap(), ExportOutcome::Delta {
+        file: "delta-000001.json".to_string(), inserted: 0, updated: 1, deleted: 0,
+    });
+    
+    // The directory belongs to the dataset first exported into it
+    assert!(matches!(exporter.export("sensors", &devices("1.1")), Err(StorageError::InvalidFormat(_))));
+}
+
+#[test]
+fn test_multi_cast_defaults() {
+    let mut readings = DataSet::new(Schema::new(vec![
+        Field::new("sensor".to_string(), DataType::String, false),
+        Field::new("value".to_string(), DataType::String, false),
+    ]));
+    
+    for (sensor, value) in [("a", "12"), ("b", "n/a")] {
+        readings.add_row(Row::new(vec![
+            Value::String(sensor.to_string()),
+            Value::String(value.to_string()),
+        ])).unwrap();
+    }
+    
+    // Defaults are cast to the target type like the values they replace
+    let result = MultiCastTransform::new(CastErrorPolicy::Default)
+        .cast_with_default("value", DataType::Integer, Value::String("0".to_string()))
+        .process(&readings)
+        .unwrap();
+    assert_eq!(result.data[1].values[1], Value::Integer(0));
+    assert!(!result.schema.fields[1].nullable);
+    
+    // A null default makes the column nullable
+    let result = MultiCastTransform::new(CastErrorPolicy::Default)
+        .cast("value", DataType::Integer)
+        .process(&readings)
+        .unwrap();
+    assert_eq!(result.data[1].values[1], Value::Null);
+    assert!(result.schema.fields[1].nullable);
+    
+    // A default that does not cast fails before any row is cast
+    let err = MultiCastTransform::new(CastErrorPolicy::Default)
+        .cast_with_default("value", DataType::Integer, Value::String("none".to_string()))
+        .process(&readings)
+        .unwrap_err();
+    assert!(err.to_string().contains("'value'"));
+}
+
+#[test]
+fn test_multi_cast_date_formats() {
+    let mut orders = DataSet::new(Schema::new(vec![
+        Field::new("code".to_string(), DataType::String, false),
+        Field::new("ordered".to_string(), DataType::String, false),
+        Field::new("shipped".to_string(), DataType::String, false),
+    ]));
+    
+    orders.add_row(Row::new(vec![
+        Value::String("A-1".to_string()),
+        Value::String("31/01/2024".to_string()),
+        Value::String("02/01/2024".to_string()),
+    ])).unwrap();
+    
+    // The shared date format only parses the date casts, and a cast's own
+    // format reformats its strings
+    let transform = MultiCastTransform::new(CastErrorPolicy::Fail)
+        .with_format(CastFormat::new().date_format("%d/%m/%Y"))
+        .cast("code", DataType::String)
+        .cast("ordered", DataType::Date)
+        .cast("shipped", DataType::String)
+        .with_date_format("shipped", "%m/%d/%Y");
+    
+    let result = transform.process(&orders).unwrap();
+    assert_eq!(result.data[0].values[0], Value::String("A-1".to_string()));
+    assert!(matches!(result.data[0].values[1], Value::Date(_)));
+    assert_eq!(result.data[0].values[2], Value::String("2024-02-01".to_string()));
+    
+    // Per-cast formats survive a round trip through the config
+    let rebuilt = processor_from_config(transform.to_config().unwrap()).unwrap();
+    assert_eq!(rebuilt.process(&orders).unwrap().data[0].values, result.data[0].values);
+    
+    // Strings that do not match a cast's own format still fail
+    let err = MultiCastTransform::new(CastErrorPolicy::Fail)
+        .cast("ordered", DataType::String)
+        .with_date_format("ordered", "%Y-%m-%d")
+        .process(&orders)
+        .unwrap_err();
+    assert_eq!(err.context().and_then(|context| context.column.as_deref()), Some("ordered"));
+}
+
+#[test]
+fn test_json_path_extraction() {
+    let mut events = DataSet::new(Schema::new(vec![
+        Field::new("payload".to_string(), DataType::String, false),
+    ]));
+    events.add_row(Row::new(vec![Value::String(
+        r#"{"user": {"id": 7, "full name": "Ann"}, "items": [{"sku": "a", "qty": 2}, {"sku": "b", "qty": 1}]}"#.to_string()
+    )])).unwrap();
+    
+    let transform = JsonPathTransform::new("payload")
+        .extract("$.user.id", "user_id", DataType::Integer)
+        .extract("$.user['full name']", "user_name", DataType::String)
+        .extract("$.items[1].sku", "second_sku", DataType::String)
+        .extract("$.items[*].qty", "quantities", DataType::Array(Box::new(DataType::Integer)))
+        .extract("$.coupon", "coupon", DataType::String);
+    
+    let result = transform.process(&events).unwrap();
+    assert_eq!(result.data[0].values[1..], [
+        Value::Integer(7),
+        Value::String("Ann".to_string()),
+        Value::String("b".to_string()),
+        Value::Array(vec![Value::Integer(2), Value::Integer(1)]),
+        Value::Null,
+    ]);
+    
+    // Paths must start at the root, and outputs must be new columns
+    assert!(JsonPathTransform::new("payload").extract("user.id", "id", DataType::Integer).process(&events).is_err());
+    assert!(JsonPathTransform::new("payload").extract("$.user.id", "payload", DataType::Integer).process(&events).is_err());
+    
+    // Strings that are not JSON fail the transform
+    events.add_row(Row::new(vec![Value::String("{not json".to_string())])).unwrap();
+    assert!(transform.process(&events).is_err());
+}
+
+#[test]
+fn test_encoding_round_trip() {
+    let mut files = DataSet::new(Schema::new(vec![
+        Field::new("content".to_string(), DataType::Binary, true),
+        Field::new("path".to_string(), DataType::String, false),
+    ]));
+    files.add_row(Row::new(vec![Value::Binary(vec![0xff, 0x00, 0x10]), Value::String("a b&c/é".to_string())])).unwrap();
+    files.add_row(Row::new(vec![Value::Null, Value::String("plain".to_string())])).unwrap();
+    
+    let column = |dataset: &DataSet, col_idx: usize| dataset.data.iter()
+        .map(|row| row.values[col_idx].clone())
+        .collect::<Vec<_>>();
+    
+    for (encoding, encoded) in [(Encoding::Base64, "/wAQ"), (Encoding::Hex, "ff0010"), (Encoding::Url, "%FF%00%10")] {
+        let result = EncodeTransform::new("content", encoding).process(&files).unwrap();
+        assert_eq!(result.schema.fields[0].data_type, DataType::String);
+        assert_eq!(column(&result, 0), vec![Value::String(encoded.to_string()), Value::Null]);
+        
+        let decoded = DecodeTransform::new("content", encoding).process(&result).unwrap();
+        assert_eq!(column(&decoded, 0), column(&files, 0));
+    }
+    
+    // Strings round trip through their UTF-8 bytes
+    let encoded = EncodeTransform::new("path", Encoding::Url).process(&files).unwrap();
+    assert_eq!(encoded.data[0].values[1], Value::String("a%20b%26c%2F%C3%A9".to_string()));
+    let decoded = DecodeTransform::utf8("path", Encoding::Url).process(&encoded).unwrap();
+    assert_eq!(column(&decoded, 1), column(&files, 1));
+    
+    // Malformed text, invalid UTF-8 and non-text columns are rejected
+    assert!(DecodeTransform::new("path", Encoding::Hex).process(&files).is_err());
+    let hex = EncodeTransform::new("content", Encoding::Hex).process(&files).unwrap();
+    assert!(DecodeTransform::utf8("content", Encoding::Hex).process(&hex).is_err());
+    assert!(DecodeTransform::new("content", Encoding::Base64).process(&files).is_err());
+    assert!(Encoding::from_str("rot13").is_err());
+}
+
+#[test]
+fn test_currency_conversion() {
+    let mut rates = DataSet::new(Schema::new(vec![
+        Field::new("currency".to_string(), DataType::String, false),
+        Field::new("rate".to_string(), DataType::Float, false),
+        Field::new("day".to_string(), DataType::String, true),
+    ]));
+    for (currency, rate, day) in [("USD", 1.0, "2024-01-01"), ("EUR", 1.1, "2024-01-01"), ("EUR", 1.2, "2024-02-01")] {
+        rates.add_row(Row::new(vec![
+            Value::String(currency.to_string()),
+            Value::Float(rate),
+            Value::String(day.to_string()),
+        ])).unwrap();
+    }
+    
+    let sales = |rows: &[(f64, &str, &str)]| {
+        let mut sales = DataSet::new(Schema::new(vec![
+            Field::new("amount".to_string(), DataType::Float, false),
+            Field::new("currency".to_string(), DataType::String, false),
+            Field::new("day".to_string(), DataType::String, false),
+        ]));
+        for (amount, currency, day) in rows {
+            sales.add_row(Row::new(vec![
+                Value::Float(*amount),
+                Value::String(currency.to_string()),
+                Value::String(day.to_string()),
+            ])).unwrap();
+        }
+        sales
+    };
+    let converter = || CurrencyConversionTransform::new("amount", "currency", "usd", rates.clone(), "amount_usd")
+        .with_dates("day", "day");
+    
+    // Each row uses the latest rate on or before its date, and codes ignore case
+    let result = converter()
+        .process(&sales(&[(100.0, "eur", "2024-01-15"), (100.0, "EUR", "2024-02-10"), (50.0, "USD", "2023-06-01")]))
+        .unwrap();
+    let converted: Vec<f64> = result.data.iter()
+        .map(|row| match row.values[3] {
+            Value::Float(f) => f,
+            ref other => panic!("unexpected {:?}", other),
+        })
+        .collect();
+    assert!((converted[0] - 110.0).abs() < 1e-9);
+    assert!((converted[1] - 120.0).abs() < 1e-9);
+    assert_eq!(converted[2], 50.0);
+    
+    // Missing rates fail the conversion unless they may be null
+    let unknown = sales(&[(10.0, "JPY", "2024-01-15"), (10.0, "EUR", "2023-12-31")]);
+    assert!(converter().process(&unknown).is_err());
+    let result = converter().with_missing_as_null().process(&unknown).unwrap();
+    assert_eq!(result.data[0].values[3], Value::Null);
+    assert_eq!(result.data[1].values[3], Value::Null);
+    
+    // Rates must be positive, and the output must be a new column
+    let mut invalid_rates = rates.clone();
+    invalid_rates.add_row(Row::new(vec![Value::String("GBP".to_string()), Value::Float(0.0), Value::Null])).unwrap();
+    assert!(CurrencyConversionTransform::new("amount", "currency", "EUR", invalid_rates, "amount_eur")
+        .process(&sales(&[(1.0, "USD", "2024-01-01")])).is_err());
+    assert!(CurrencyConversionTransform::new("amount", "currency", "USD", DataSet::new(Schema::new(vec![])), "amount")
+        .process(&sales(&[])).is_err());
+}
+
+#[test]
+fn test_binning() {
+    let mut ages = DataSet::new(Schema::new(vec![Field::new("age".to_string(), DataType::Integer, true)]));
+    for age in [Value::Integer(0), Value::Integer(5), Value::Integer(10), Value::Integer(20), Value::Null] {
+        ages.add_row(Row::new(vec![age])).unwrap();
+    }
+    
+    let bins = |transform: BinTransform| transform.process(&ages).unwrap().data.iter()
+        .map(|row| row.values[1].clone())
+        .collect::<Vec<_>>();
+    let labels = |labels: &[Option<&str>]| labels.iter()
+        .map(|label| label.map_or(Value::Null, |label| Value::String(label.to_string())))
+        .collect::<Vec<_>>();
+    
+    // Bins are half-open except the last, and nulls stay unbinned
+    assert_eq!(bins(BinTransform::equal_width("age", 2)),
+        labels(&[Some("[0, 10)"), Some("[0, 10)"), Some("[10, 20]"), Some("[10, 20]"), None]));
+    assert_eq!(bins(BinTransform::equal_frequency("age", 2)),
+        labels(&[Some("[0, 7.5)"), Some("[0, 7.5)"), Some("[7.5, 20]"), Some("[7.5, 20]"), None]));
+    
+    // Values outside explicit edges are left unbinned
+    let named = BinTransform::edges("age", vec![1.0, 10.0, 15.0])
+        .with_labels(vec!["child".to_string(), "teen".to_string()])
+        .with_output("group");
+    assert_eq!(bins(named), labels(&[None, Some("child"), Some("teen"), None, None]));
+    
+    // Labels must match the bins, edges must ascend, and there must be a bin
+    assert!(BinTransform::equal_width("age", 3).with_labels(vec!["a".to_string()]).process(&ages).is_err());
+    assert!(BinTransform::edges("age", vec![10.0, 5.0]).process(&ages).is_err());
+    assert!(BinTransform::equal_frequency("age", 0).process(&ages).is_err());
+    assert!(BinTransform::equal_width("height", 2).process(&ages).is_err());
+}
+
+#[test]
+fn test_scaling() {
+    let readings = |values: &[Value]| {
+        let mut readings = DataSet::new(Schema::new(vec![
+            Field::new("x".to_string(), DataType::Integer, true),
+            Field::new("flat".to_string(), DataType::Integer, true),
+        ]));
+        for value in values {
+            readings.add_row(Row::new(vec![value.clone(), Value::Integer(3)])).unwrap();
+        }
+        readings
+    };
+    let column = |dataset: &DataSet, col_idx: usize| dataset.data.iter()
+        .map(|row| match row.values[col_idx] {
+            Value::Float(f) => Some(f),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let columns = vec!["x".to_string(), "flat".to_string()];
+    let train = readings(&[Value::Integer(2), Value::Integer(4), Value::Null, Value::Integer(8)]);
+    
+    // Nulls are skipped when fitting, and constant columns scale to zero
+    let scaled = ScaleTransform::min_max(columns.clone()).process(&train).unwrap();
+    assert_eq!(column(&scaled, 0), vec![Some(0.0), Some(1.0 / 3.0), None, Some(1.0)]);
+    assert_eq!(column(&scaled, 1), vec![Some(0.0); 4]);
+    
+    let scaled = ScaleTransform::z_score(columns.clone()).process(&train).unwrap();
+    let z = column(&scaled, 0);
+    assert!((z[0].unwrap() + 1.0690449676).abs() < 1e-9);
+    
+    // Fitted parameters travel in the metadata and are reapplied to new data
+    let reapplied = ScaleTransform::from_metadata(&ScaleTransform::min_max(columns.clone()).process(&train).unwrap().metadata)
+        .unwrap()
+        .process(&readings(&[Value::Integer(14)]))
+        .unwrap();
+    assert_eq!(column(&reapplied, 0), vec![Some(2.0)]);
+    
+    let fixed = ScaleTransform::robust(vec!["x".to_string()])
+        .with_params("x", ScaleParams { center: 4.0, scale: 2.0 })
+        .process(&train)
+        .unwrap();
+    assert_eq!(column(&fixed, 0)[3], Some(2.0));
+    
+    // Non-numeric values, unknown columns and missing parameters are rejected
+    let mut mixed = readings(&[]);
+    mixed.data.push(Row::new(vec![Value::String("high".to_string()), Value::Integer(3)]));
+    assert!(ScaleTransform::min_max(columns.clone()).process(&mixed).is_err());
+    assert!(ScaleTransform::z_score(vec!["y".to_string()]).process(&train).is_err());
+    assert!(ScaleTransform::from_metadata(&train.metadata).is_err());
+}
+
+#[test]
+fn test_dataset_info_from_files() {
+    use rust_data_processing_engine::storage::{FileFormat, FileStorage};
+    
+    let dir = tempfile::tempdir().unwrap();
+    let storage = FileStorage::new(dir.path(), FileFormat::Csv).unwrap();
+    
+    // Quoted line breaks belong to their row
+    std::fs::write(dir.path().join("notes.csv"), "id,text\n1,\"two\nlines\"\n2,short\n3,end\n").unwrap();
+    let info = storage.info("notes").unwrap();
+    assert_eq!((info.rows, info.columns), (3, 2));
+    assert_eq!(info.format, "csv");
+    assert_eq!(info.size_bytes, Some(std::fs::metadata(dir.path().join("notes.csv")).unwrap().len()));
+    assert!(info.last_modified.is_some());
+    
+    let mut dataset = DataSet::new(Schema::new(vec![Field::new("id".to_string(), DataType::Integer, false)]));
+    dataset.add_row(Row::new(vec![Value::Integer(1)])).unwrap();
+    storage.store_as("ids", &dataset, FileFormat::Json).unwrap();
+    let info = storage.info("ids").unwrap();
+    assert_eq!((info.rows, info.columns, info.format.as_str()), (1, 1, "json"));
+    
+    // Unreadable files and missing datasets are errors
+    std::fs::write(dir.path().join("broken.json"), "[{\"id\": 1},").unwrap();
+    assert!(storage.info("broken").is_err());
+    assert!(matches!(storage.info("missing"), Err(StorageError::NotFound(_))));
+}
+
+#[test]
+fn test_partial_reads() {
+    use rust_data_processing_engine::storage::{FileFormat, FileStorage};
+    
+    let mut dataset = DataSet::new(Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("name".to_string(), DataType::String, false),
+        Field::new("score".to_string(), DataType::Float, false),
+    ]));
+    for id in 0..10 {
+        dataset.add_row(Row::new(vec![Value::Integer(id), Value::String(format!("n{}", id)), Value::Float(id as f64 / 2.0)])).unwrap();
+    }
+    
+    let dir = tempfile::tempdir().unwrap();
+    let memory = MemoryStorage::new();
+    let csv = FileStorage::new(dir.path().join("csv"), FileFormat::Csv).unwrap();
+    let json = FileStorage::new(dir.path().join("json"), FileFormat::Json).unwrap();
+    let backends: [&dyn DataStorage; 3] = [&memory, &csv, &json];
+    
+    for storage in backends {
+        storage.store("scores", &dataset).unwrap();
+        
+        // Partial reads match the same slice of a full load, whatever the
+        // types the backend reads back
+        let full = storage.load("scores").unwrap();
+        let values = |data: &DataSet| data.data.iter().map(|row| row.values.clone()).collect::<Vec<_>>();
+        
+        // Ranges past the end are cut short
+        assert_eq!(values(&storage.load_range("scores", 8, 5).unwrap()), values(&full)[8..]);
+        assert_eq!(storage.load_range("scores", 20, 5).unwrap().len(), 0);
+        
+        // Columns come back in the requested order
+        let columns = storage.load_columns("scores", &["score".to_string(), "id".to_string()]).unwrap();
+        let names: Vec<&str> = columns.schema.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["score", "id"]);
+        assert_eq!(columns.data[3].values, vec![full.data[3].values[2].clone(), full.data[3].values[0].clone()]);
+        
+        assert!(storage.load_columns("scores", &["rank".to_string()]).is_err());
+        assert!(matches!(storage.load_range("missing", 0, 1), Err(StorageError::NotFound(_))));
+    }
+}
+
+#[test]
+fn test_memory_snapshot_and_warm_start() {
+    use rust_data_processing_engine::storage::{FileFormat, FileStorage};
+    
+    let mut dataset = DataSet::new(Schema::new(vec![Field::new("text".to_string(), DataType::String, false)]));
+    dataset.add_row(Row::new(vec![Value::String("x".repeat(1000))])).unwrap();
+    
+    let dir = tempfile::tempdir().unwrap();
+    let disk = FileStorage::new(dir.path(), FileFormat::Json).unwrap();
+    let memory = MemoryStorage::new();
+    memory.store("a", &dataset).unwrap();
+    memory.store("b", &dataset).unwrap();
+    assert_eq!(memory.snapshot(&disk).unwrap(), 2);
+    
+    // Datasets deleted since the last snapshot are removed from it
+    memory.delete("b").unwrap();
+    assert_eq!(memory.snapshot(&disk).unwrap(), 1);
+    assert_eq!(disk.list().unwrap(), vec!["a".to_string()]);
+    
+    let restarted = MemoryStorage::new();
+    assert_eq!(restarted.restore(&disk).unwrap(), 1);
+    assert_eq!(restarted.load("a").unwrap().data[0].values, dataset.data[0].values);
+    
+    // Warm starts are held to the memory limit
+    memory.store("c", &dataset).unwrap();
+    memory.snapshot(&disk).unwrap();
+    let small = MemoryStorage::new().with_memory_limit(1500, MemoryPolicy::Reject);
+    assert!(matches!(small.restore(&disk), Err(StorageError::Full(_))));
+}
+
+#[test]
+fn test_storage_maintenance() {
+    use rust_data_processing_engine::storage::{run_maintenance, ProtectedStorage};
+    
+    let mut dataset = DataSet::new(Schema::new(vec![Field::new("id".to_string(), DataType::Integer, false)]));
+    dataset.add_row(Row::new(vec![Value::Integer(1)])).unwrap();
+    
+    let backend = Arc::new(MemoryStorage::new());
+    let storage = Arc::new(CatalogStorage::new(backend.clone()));
+    for name in ["kept", "gone"] {
+        storage.store(name, &dataset).unwrap();
+    }
+    storage.create_alias("latest", "gone").unwrap();
+    
+    // Removed behind the catalog's back, leaving its alias dangling
+    backend.delete("gone").unwrap();
+    
+    // Read-only storage skips the housekeeping
+    let protected = ProtectedStorage::new(storage.clone()).with_read_only(true);
+    let report = run_maintenance(&protected).unwrap();
+    assert_eq!(report.aliases_removed, 0);
+    assert_eq!(storage.aliases().unwrap().len(), 1);
+    
+    let report = run_maintenance(storage.as_ref()).unwrap();
+    assert_eq!(report.datasets, 1);
+    assert_eq!(report.aliases_removed, 1);
+    assert!(storage.aliases().unwrap().is_empty());
+    
+    // A second run has nothing left to do
+    let report = run_maintenance(storage.as_ref()).unwrap();
+    assert_eq!(report.aliases_removed, 0);
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn test_column_encryption() {
+    use rust_data_processing_engine::processing::{DecryptColumnTransform, EncryptColumnTransform};
+    use rust_data_processing_engine::storage::{EncryptionKey, KeyRing};
+    
+    let v1 = EncryptionKey::new("v1", vec![1; 32]).unwrap();
+    let v2 = EncryptionKey::new("v2", vec![2; 32]).unwrap();
+    
+    let mut dataset = DataSet::new(Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("ssn".to_string(), DataType::String, true),
+        Field::new("salary".to_string(), DataType::Integer, true),
+    ]));
+    dataset.add_row(Row::new(vec![Value::Integer(1), Value::String("123-45-6789".to_string()), Value::Integer(5000)])).unwrap();
+    dataset.add_row(Row::new(vec![Value::Integer(2), Value::Null, Value::Null])).unwrap();
+    
+    let columns = vec!["ssn".to_string(), "salary".to_string()];
+    let encrypted = EncryptColumnTransform::with_key(columns.clone(), v1.clone()).as_text().process(&dataset).unwrap();
+    assert_eq!(encrypted.schema.fields[2].data_type, DataType::String);
+    assert!(matches!(&encrypted.data[0].values[1], Value::String(text) if !text.contains("6789")));
+    assert_eq!(encrypted.data[1].values[1], Value::Null);
+    
+    // Columns get their types back, and rotated keys still decrypt older values
+    let keys = KeyRing::new(v2.clone()).with_key(v1);
+    let decrypted = DecryptColumnTransform::new(columns.clone(), Arc::new(keys)).process(&encrypted).unwrap();
+    assert_eq!(decrypted.schema.fields[2].data_type, DataType::Integer);
+    assert_eq!(decrypted.data[0].values, dataset.data[0].values);
+    assert_eq!(decrypted.data[1].values, dataset.data[1].values);
+    
+    // Without the key the values cannot be read
+    let result = DecryptColumnTransform::with_key(columns.clone(), v2).process(&encrypted);
+    assert!(matches!(result, Err(ProcessingError::InvalidOperation(_))));
+    
+    // Ciphertexts are bound to their column
+    let mut swapped = encrypted.clone();
+    swapped.data[0].values.swap(1, 2);
+    let v1 = EncryptionKey::new("v1", vec![1; 32]).unwrap();
+    assert!(DecryptColumnTransform::with_key(columns.clone(), v1.clone()).process(&swapped).is_err());
+    
+    assert!(EncryptColumnTransform::with_key(vec!["missing".to_string()], v1).process(&dataset).is_err());
+}
+
+#[test]
+fn test_orchestrated_pipelines() {
+    let mut people = DataSet::new(Schema::new(vec![
+        Field::new("name".to_string(), DataType::String, false),
+        Field::new("age".to_string(), DataType::Integer, false),
+    ]));
+    for (name, age) in [("Alice", 30), ("Bob", 25), ("Charlie", 35)] {
+        people.add_row(Row::new(vec![Value::String(name.to_string()), Value::Integer(age)])).unwrap();
+    }
+    
+    let storage = MemoryStorage::new();
+    storage.store("people", &people).unwrap();
+    
+    let adults = Pipeline::new("adults").add(FilterProcessor::greater_than("age", Value::Integer(28)));
+    let names = Pipeline::new("names").add(SelectTransform::new(vec!["name".to_string()]));
+    let broken = Pipeline::new("broken").add(SelectTransform::new(vec!["missing".to_string()]));
+    let after_broken = Pipeline::new("after_broken").add(LimitProcessor::new(1));
+    
+    // Steps are added out of order; each depends on the step writing its input
+    let orchestrator = Orchestrator::new()
+        .add(PipelineStep::new("names", "adults", "adult_names", names))
+        .add(PipelineStep::new("after_broken", "broken", "after_broken", after_broken))
+        .add(PipelineStep::new("adults", "people", "adults", adults))
+        .add(PipelineStep::new("broken", "people", "broken", broken))
+        .with_parallelism(2);
+    
+    let plan = orchestrator.plan().unwrap();
+    let position = |name: &str| plan.iter().position(|step| *step == name).unwrap();
+    assert!(position("adults") < position("names"));
+    assert!(position("broken") < position("after_broken"));
+    
+    // A failure skips its downstream steps while other branches carry on
+    let summary = orchestrator.run(&storage).unwrap();
+    assert_eq!(summary.steps[0].status, StepStatus::Succeeded(2));
+    assert_eq!(summary.steps[1].status, StepStatus::Skipped);
+    assert!(matches!(summary.steps[3].status, StepStatus::Failed(_)));
+    assert_eq!((summary.succeeded(), summary.failed(), summary.skipped()), (2, 1, 1));
+    assert!(!summary.is_success());
+    assert_eq!(storage.load("adult_names").unwrap().schema.fields.len(), 1);
+    assert!(!storage.exists("after_broken").unwrap());
+    
+    // Cycles and outputs written by two steps are rejected before running
+    let cycle = Orchestrator::new()
+        .add(PipelineStep::new("a", "y", "x", Pipeline::new("a")))
+        .add(PipelineStep::new("b", "x", "y", Pipeline::new("b")));
+    assert!(cycle.run(&storage).is_err());
+    
+    let clash = Orchestrator::new()
+        .add(PipelineStep::new("a", "people", "x", Pipeline::new("a")))
+        .add(PipelineStep::new("b", "people", "x", Pipeline::new("b")));
+    assert!(clash.plan().is_err());
+}
+
+#[test]
+fn test_orchestrator_retries_and_dead_letters() {
+    let down = Arc::new(AtomicBool::new(false));
+    let storage = FlakyStorage { backend: MemoryStorage::new(), down: down.clone() };
+    let policy = RetryPolicy {
+        max_retries: 2,
+        initial_backoff: Duration::from_millis(1),
+        jitter: false,
+        ..RetryPolicy::default()
+    };
+    
+    let mut input = DataSet::new(Schema::new(vec![Field::new("id".to_string(), DataType::Integer, false)]));
+    input.add_row(Row::new(vec![Value::Integer(1)])).unwrap();
+    input.add_row(Row::new(vec![Value::Integer(2)])).unwrap();
+    storage.store("raw", &input).unwrap();
+    
+    // Transient storage failures are retried up to the policy's limit
+    let copy = Orchestrator::new()
+        .add(PipelineStep::new("copy", "raw", "copied", Pipeline::new("copy")).with_retry(policy.clone()));
+    down.store(true, Ordering::SeqCst);
+    let summary = copy.run(&storage).unwrap();
+    assert!(matches!(summary.steps[0].status, StepStatus::Failed(_)));
+    assert_eq!(summary.steps[0].attempts, 3);
+    
+    down.store(false, Ordering::SeqCst);
+    let summary = copy.run(&storage).unwrap();
+    assert_eq!(summary.steps[0].status, StepStatus::Succeeded(2));
+    assert_eq!(summary.steps[0].attempts, 1);
+    
+    // Pipeline failures are not retried, and their input is quarantined with context
+    let broken = || Orchestrator::new().add(
+        PipelineStep::new("broken", "raw", "out", Pipeline::new("broken").add(SelectTransform::new(vec!["missing".to_string()])))
+            .with_retry(policy.clone())
+            .with_dead_letter("dead_letters")
+    );
+    for _ in 0..2 {
+        let summary = broken().run(&storage).unwrap();
+        assert_eq!(summary.steps[0].attempts, 1);
+        assert_eq!(summary.dead_lettered(), 1);
+    }
+    
+    let quarantined = storage.load("dead_letters").unwrap();
+    assert_eq!(quarantined.len(), 4);
+    let names: Vec<&str> = quarantined.schema.fields.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["id", "_step", "_error", "_attempts", "_failed_at"]);
+    assert_eq!(quarantined.data[0].values[1], Value::String("broken".to_string()));
+    
+    // A dead-letter dataset with other columns is left alone
+    storage.store("dead_letters", &DataSet::new(Schema::new(vec![Field::new("other".to_string(), DataType::String, false)]))).unwrap();
+    let summary = broken().run(&storage).unwrap();
+    assert_eq!(summary.failed(), 1);
+    assert_eq!(summary.dead_lettered(), 0);
+    assert_eq!(storage.load("dead_letters").unwrap().len(), 0);
+}
+
+#[test]
+fn test_uuid_values() {
+    use rust_data_processing_engine::data::{format_uuid, parse_uuid};
+    use rust_data_processing_engine::processing::GenerateUuidTransform;
+    
+    let text = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+    let uuid = parse_uuid(text).unwrap();
+    assert_eq!(format_uuid(uuid), text);
+    assert_eq!(parse_uuid("{67E5504410B1426F9247BB680E5FE0C8}").unwrap(), uuid);
+    for invalid in ["67e55044-10b1-426f-9247", "67e5504410b1-426f-9247-bb680e5fe0c8-", "zze55044-10b1-426f-9247-bb680e5fe0c8"] {
+        assert!(parse_uuid(invalid).is_err(), "{} was parsed", invalid);
+    }
+    
+    let mut dataset = DataSet::new(Schema::new(vec![Field::new("id".to_string(), DataType::String, true)]));
+    dataset.add_row(Row::new(vec![Value::String(text.to_string())])).unwrap();
+    dataset.add_row(Row::new(vec![Value::Null])).unwrap();
+    
+    let cast = CastTransform::new("id", DataType::Uuid).process(&dataset).unwrap();
+    assert_eq!(cast.data[0].values[0], Value::Uuid(uuid));
+    
+    // Existing UUIDs are kept and only nulls are filled, with version 4 UUIDs
+    let filled = GenerateUuidTransform::new("id").process(&cast).unwrap();
+    assert_eq!(filled.data[0].values[0], Value::Uuid(uuid));
+    match filled.data[1].values[0] {
+        Value::Uuid(generated) => assert_eq!(format_uuid(generated).as_bytes()[14], b'4'),
+        ref other => panic!("expected a UUID, got {:?}", other),
+    }
+    
+    let added = GenerateUuidTransform::new("key").process(&dataset).unwrap();
+    assert_eq!(added.schema.fields[1].data_type, DataType::Uuid);
+    assert_ne!(added.data[0].values[1], added.data[1].values[1]);
+    
+    // Text that is not a UUID does not cast, and other columns are not filled
+    dataset.data[0].values[0] = Value::String("not-a-uuid".to_string());
+    assert!(CastTransform::new("id", DataType::Uuid).process(&dataset).is_err());
+    assert!(GenerateUuidTransform::new("id").process(&dataset).is_err());
+}
+
+#[test]
+fn test_narrow_numeric_types() {
+    assert_eq!(DataType::from_str("int16").unwrap(), DataType::Int16);
+    assert_eq!(DataType::Int8.widen(&DataType::Int32), Some(DataType::Int32));
+    assert_eq!(DataType::Int16.widen(&DataType::Float32), Some(DataType::Float32));
+    assert_eq!(DataType::Int32.widen(&DataType::Float32), Some(DataType::Float));
+    assert_eq!(DataType::Int8.widen(&DataType::String), None);
+    
+    let mut dataset = DataSet::new(Schema::new(vec![Field::new("value".to_string(), DataType::String, false)]));
+    for value in ["127", "-128", "0.1"] {
+        dataset.add_row(Row::new(vec![Value::String(value.to_string())])).unwrap();
+    }
+    
+    // Float32 rounds to what 32 bits hold
+    let floats = CastTransform::new("value", DataType::Float32).process(&dataset).unwrap();
+    assert_eq!(floats.schema.fields[0].data_type, DataType::Float32);
+    assert_eq!(floats.data[2].values[0], Value::Float(0.1f32 as f64));
+    
+    dataset.data.pop();
+    let bytes = CastTransform::new("value", DataType::Int8).process(&dataset).unwrap();
+    assert_eq!(bytes.data[1].values[0], Value::Integer(-128));
+    
+    // Values out of range neither cast nor validate
+    dataset.data[0].values[0] = Value::String("128".to_string());
+    assert!(CastTransform::new("value", DataType::Int8).process(&dataset).is_err());
+    assert!(CastTransform::new("value", DataType::Int16).process(&dataset).is_ok());
+    
+    let mut narrow = DataSet::new(Schema::new(vec![Field::new("small".to_string(), DataType::Int8, false)]));
+    assert!(narrow.insert_row(Row::new(vec![Value::Integer(100)])).is_ok());
+    assert!(narrow.insert_row(Row::new(vec![Value::Integer(300)])).is_err());
+    
+    let mut single = DataSet::new(Schema::new(vec![Field::new("f".to_string(), DataType::Float32, false)]));
+    assert!(single.insert_row(Row::new(vec![Value::Float(1e39)])).is_err());
+}
+
+#[test]
+fn test_overflow_policies() {
+    use rust_data_processing_engine::processing::{OverflowPolicy, SumFunction};
+    
+    let mut dataset = DataSet::new(Schema::new(vec![
+        Field::new("group".to_string(), DataType::String, false),
+        Field::new("value".to_string(), DataType::Integer, false),
+    ]));
+    for value in [i64::MAX, 1, 100] {
+        dataset.add_row(Row::new(vec![Value::String("a".to_string()), Value::Integer(value)])).unwrap();
+    }
+    
+    let sum = |policy: OverflowPolicy| GroupByProcessor::new()
+        .group_by("group")
+        .aggregate("total", "value", SumFunction::new().with_overflow(policy))
+        .process(&dataset);
+    
+    assert!(sum(OverflowPolicy::Error).is_err());
+    assert_eq!(sum(OverflowPolicy::Saturate).unwrap().data[0].values[1], Value::Integer(i64::MAX));
+    let promoted = sum(OverflowPolicy::Promote).unwrap();
+    assert!(matches!(promoted.data[0].values[1], Value::Float(f) if f >= i64::MAX as f64));
+    
+    // Casts clamp or widen the column instead of failing
+    let cast = |policy: OverflowPolicy| CastTransform::new("value", DataType::Int8)
+        .with_format(CastFormat::new().overflow(policy))
+        .process(&dataset);
+    
+    assert!(cast(OverflowPolicy::Error).is_err());
+    let saturated = cast(OverflowPolicy::Saturate).unwrap();
+    assert_eq!(saturated.schema.fields[1].data_type, DataType::Int8);
+    assert_eq!(saturated.data[0].values[1], Value::Integer(127));
+    assert_eq!(saturated.data[1].values[1], Value::Integer(1));
+    let promoted = cast(OverflowPolicy::Promote).unwrap();
+    assert_eq!(promoted.schema.fields[1].data_type, DataType::Integer);
+    assert_eq!(promoted.data[0].values[1], Value::Integer(i64::MAX));
+    
+    assert!(OverflowPolicy::from_str("wrap").is_err());
+}
+
+#[test]
+fn test_nan_policies() {
+    use rust_data_processing_engine::data::{JsonSink, NonFiniteFloats};
+    
+    let mut dataset = DataSet::new(Schema::new(vec![
+        Field::new("group".to_string(), DataType::String, false),
+        Field::new("price".to_string(), DataType::Float, false),
+    ]));
+    for price in [1.0, f64::NAN, 3.0, f64::INFINITY] {
+        dataset.add_row(Row::new(vec![Value::String("a".to_string()), Value::Float(price)])).unwrap();
+    }
+    
+    let average = |policy: NanPolicy| GroupByProcessor::new()
+        .group_by("group")
+        .avg("price", "price")
+        .with_nan_policy(policy)
+        .process(&dataset);
+    
+    assert_eq!(average(NanPolicy::Skip).unwrap().data[0].values[1], Value::Float(2.0));
+    assert!(matches!(average(NanPolicy::Propagate).unwrap().data[0].values[1], Value::Float(f) if f.is_nan()));
+    assert!(matches!(average(NanPolicy::Error), Err(ProcessingError::InvalidOperation(_))));
+    assert!(NanPolicy::from_str("ignore").is_err());
+    
+    // JSON numbers cannot hold NaN or infinities
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("prices.json");
+    
+    JsonSink::new(&path, false).write(&dataset).unwrap();
+    let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(written[1]["price"], serde_json::Value::Null);
+    
+    JsonSink::new(&path, false).with_non_finite(NonFiniteFloats::String).write(&dataset).unwrap();
+    let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(written[1]["price"], "NaN");
+    assert_eq!(written[3]["price"], "Infinity");
+    
+    assert!(JsonSink::new(&path, false).with_non_finite(NonFiniteFloats::Error).write(&dataset).is_err());
+}
+
+#[test]
+fn test_deterministic_output_order() {
+    let mut sales = DataSet::new(Schema::new(vec![
+        Field::new("store".to_string(), DataType::String, true),
+        Field::new("amount".to_string(), DataType::Integer, false),
+    ]));
+    for (store, amount) in [(Some("b"), 1), (Some("c"), 2), (None, 3), (Some("a"), 4), (Some("b"), 5)] {
+        sales.add_row(Row::new(vec![store.map_or(Value::Null, Value::from), Value::Integer(amount)])).unwrap();
+    }
+    
+    // Groups come out in key order, nulls first
+    let totals = GroupByProcessor::new().group_by("store").sum("total", "amount").process(&sales).unwrap();
+    let keys: Vec<Value> = totals.data.iter().map(|row| row.values[0].clone()).collect();
+    assert_eq!(keys, vec![Value::Null, Value::from("a"), Value::from("b"), Value::from("c")]);
+    assert_eq!(totals.data[2].values[1], Value::Integer(6));
+    
+    let mut stores = DataSet::new(Schema::new(vec![
+        Field::new("name".to_string(), DataType::String, false),
+        Field::new("city".to_string(), DataType::String, false),
+    ]));
+    for (name, city) in [("d", "Rome"), ("b", "Paris"), ("a", "Oslo"), ("b", "Lyon")] {
+        stores.add_row(Row::new(vec![Value::from(name), Value::from(city)])).unwrap();
+    }
+    
+    // Joins keep the left order with matches in right order, unmatched right rows last
+    let joined = JoinProcessor::new(JoinType::Full, vec!["store".to_string()], vec!["name".to_string()])
+        .process_join(&sales, &stores)
+        .unwrap();
+    let cities: Vec<Value> = joined.data.iter().map(|row| row.values[2].clone()).collect();
+    assert_eq!(cities, vec![
+        Value::from("Paris"), Value::from("Lyon"), Value::Null, Value::Null, Value::from("Oslo"),
+        Value::from("Paris"), Value::from("Lyon"), Value::from("Rome"),
+    ]);
+    
+    assert!(GroupByProcessor::new().group_by("region").sum("total", "amount").process(&sales).is_err());
+    assert!(JoinProcessor::new(JoinType::Full, vec!["region".to_string()], vec!["name".to_string()])
+        .process_join(&sales, &stores)
+        .is_err());
+}
+
+#[test]
+fn test_vectorized_kernels() {
+    use rust_data_processing_engine::processing::{compare_f64, compare_i64, max_f64, mean_f64, min_f64, sum_f64, variance_f64, CompareOp, StatsProcessor};
+    
+    // Lengths that do not fill the last step of lanes
+    let values: Vec<f64> = (1..=21).map(|i| i as f64 * 0.5).collect();
+    assert_eq!(sum_f64(&values), values.iter().sum::<f64>());
+    assert_eq!(mean_f64(&values), 5.5);
+    let variance = values.iter().map(|v| (v - 5.5).powi(2)).sum::<f64>() / 21.0;
+    assert!((variance_f64(&values) - variance).abs() < 1e-12);
+    
+    let with_nan = [3.0, f64::NAN, -1.0, 7.5, f64::NAN];
+    assert_eq!(min_f64(&with_nan), -1.0);
+    assert_eq!(max_f64(&with_nan), 7.5);
+    assert_eq!(min_f64(&[]), f64::INFINITY);
+    assert_eq!(mean_f64(&[]), 0.0);
+    
+    assert_eq!(compare_f64(&with_nan, CompareOp::Gt, 2.0), vec![true, false, false, true, false]);
+    let ints: Vec<i64> = (0..10).collect();
+    assert_eq!(compare_i64(&ints, CompareOp::Le, 2).iter().filter(|m| **m).count(), 3);
+    
+    // Filters use the kernels and keep the row predicates' semantics
+    let mut dataset = DataSet::new(Schema::new(vec![Field::new("n".to_string(), DataType::Integer, true)]));
+    for n in [Value::Integer(5), Value::Null, Value::Integer(12), Value::Integer(30)] {
+        dataset.add_row(Row::new(vec![n])).unwrap();
+    }
+    
+    let result = FilterProcessor::greater_than("n", Value::Integer(10)).process(&dataset).unwrap();
+    assert_eq!(result.len(), 2);
+    assert_eq!(result.data[0].values[0], Value::Integer(12));
+    assert_eq!(FilterProcessor::greater_than("n", Value::Float(10.0)).process(&dataset).unwrap().len(), 0);
+    assert_eq!(FilterProcessor::greater_than("missing", Value::Integer(10)).process(&dataset).unwrap().len(), 0);
+    
+    // Statistics read the column through the same kernels, skipping nulls
+    let sum = StatsProcessor::sum("n").process(&dataset).unwrap();
+    assert_eq!(sum.data[0].values[0], Value::Float(47.0));
+    assert!(StatsProcessor::sum("missing").process(&dataset).is_err());
+}
+
+#[test]
+fn test_dictionary_encoded_keys() {
+    use rust_data_processing_engine::data::DictionaryColumn;
+    
+    let mut dataset = DataSet::new(Schema::new(vec![
+        Field::new("city".to_string(), DataType::String, true),
+        Field::new("sales".to_string(), DataType::Integer, false),
+    ]));
+    for (city, sales) in [(Some("London"), 1), (Some("Paris"), 2), (None, 3), (Some("London"), 4), (Some("Lyon"), 5)] {
+        dataset.add_row(Row::new(vec![city.map_or(Value::Null, Value::from), Value::Integer(sales)])).unwrap();
+    }
+    
+    let column = DictionaryColumn::encode(&dataset, "city").unwrap();
+    assert_eq!(column.dictionary().values(), &["London".to_string(), "Paris".to_string(), "Lyon".to_string()]);
+    assert_eq!(column.ids(), &[Some(0), Some(1), None, Some(0), Some(2)]);
+    assert_eq!(column.get(3), Some("London"));
+    assert_eq!(column.decode(), dataset.data.iter().map(|row| row.values[0].clone()).collect::<Vec<_>>());
+    
+    // Only columns of strings and nulls can be encoded
+    assert!(DictionaryColumn::encode(&dataset, "sales").is_err());
+    assert!(DictionaryColumn::encode(&dataset, "missing").is_err());
+    
+    // Grouping, joining and filtering on encoded keys match the string values
+    let totals = GroupByProcessor::new().group_by("city").sum("total", "sales").process(&dataset).unwrap();
+    assert_eq!(totals.len(), 4);
+    assert_eq!(totals.data[1].values, vec![Value::from("London"), Value::Integer(5)]);
+    
+    let joined = JoinProcessor::new(JoinType::Inner, vec!["city".to_string()], vec!["city".to_string()])
+        .process_join(&dataset, &dataset)
+        .unwrap();
+    // London matches twice on each side, and null keys match as they do unencoded
+    assert_eq!(joined.len(), 7);
+    
+    let filtered = FilterProcessor::contains("city", "L").process(&dataset).unwrap();
+    assert_eq!(filtered.len(), 3);
+}
+
+#[test]
+fn test_output_formats() {
+    use rust_data_processing_engine::utils::OutputFormat;
+    
+    let mut dataset = DataSet::new(Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("note".to_string(), DataType::String, true),
+    ]));
+    dataset.add_row(Row::new(vec![Value::Integer(1), Value::String("a|b".to_string())])).unwrap();
+    dataset.add_row(Row::new(vec![Value::Integer(22), Value::Null])).unwrap();
+    
+    let render = |format: &str| {
+        let mut out = Vec::new();
+        OutputFormat::from_str(format).unwrap().write(&dataset, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    
+    assert_eq!(render("table"), "id  note\n--  ----\n1   a|b\n22  null\n");
+    
+    // Pipes are escaped so they do not split Markdown cells
+    assert_eq!(render("md"), "| id | note |\n| --- | ---- |\n| 1  | a\\|b |\n| 22 | null |\n");
+    assert!(render("csv").starts_with("id,note\n1,a|b\n"));
+    
+    let json: serde_json::Value = serde_json::from_str(&render("json")).unwrap();
+    assert_eq!(json[1]["id"], 22);
+    
+    assert!(OutputFormat::from_str("yaml").is_err());
+}
+
+#[test]
+fn test_spec_files_and_watching() {
+    use std::sync::mpsc;
+    use rust_data_processing_engine::utils::watch_paths;
+    
+    let dir = tempfile::tempdir().unwrap();
+    let spec_path = dir.path().join("totals.json");
+    std::fs::write(&spec_path, r#"{
+        "name": "totals",
+        "steps": [
+            {"type": "filter", "expression": "amount > 0"},
+            {"type": "aggregate", "group_by": ["day"],
+             "aggregations": [{"function": "sum", "input_column": "amount", "output_name": "total"}]}
+        ]
+    }"#).unwrap();
+    
+    let mut sales = DataSet::new(Schema::new(vec![
+        Field::new("day".to_string(), DataType::String, false),
+        Field::new("amount".to_string(), DataType::Integer, false),
+    ]));
+    for (day, amount) in [("mon", 5), ("mon", -2), ("tue", 3)] {
+        sales.add_row(Row::new(vec![Value::from(day), Value::Integer(amount)])).unwrap();
+    }
+    
+    let totals = PipelineSpec::from_file(&spec_path).unwrap().build().unwrap().execute(&sales).unwrap();
+    assert_eq!(totals.data[0].values, vec![Value::from("mon"), Value::Integer(5)]);
+    
+    // Broken specs fail to read or to build
+    std::fs::write(&spec_path, r#"{"name": "totals", "steps": [{"type": "sort"}]}"#).unwrap();
+    assert!(PipelineSpec::from_file(&spec_path).is_err());
+    assert!(PipelineSpec::from_file(dir.path().join("missing.json")).is_err());
+    std::fs::write(&spec_path, r#"{"name": "totals", "steps": [{"type": "aggregate",
+        "aggregations": [{"function": "median", "input_column": "amount", "output_name": "m"}]}]}"#).unwrap();
+    assert!(PipelineSpec::from_file(&spec_path).unwrap().build().is_err());
+    
+    // A burst of changes is reported once, and watching stops when asked
+    let (sender, receiver) = mpsc::channel();
+    let watched = vec![dir.path().to_path_buf()];
+    std::thread::spawn(move || {
+        watch_paths(&watched, Duration::from_millis(200), |changed| {
+            let _ = sender.send(changed.to_vec());
+            false
+        })
+    });
+    
+    std::thread::sleep(Duration::from_millis(200));
+    std::fs::write(dir.path().join("a.csv"), "x\n1\n").unwrap();
+    std::fs::write(dir.path().join("b.csv"), "x\n2\n").unwrap();
+    
+    let changed = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(changed.iter().any(|path| path.ends_with("a.csv")));
+    assert!(changed.iter().any(|path| path.ends_with("b.csv")));
+}
+
+/// Minimal FTP server serving files from memory with passive transfers, for the FTP source test
+///
+/// Each file has a name, content and MDTM time. Only the password `secret` is accepted.
+fn serve_ftp(files: Vec<(&'static str, &'static str, &'static str)>) -> u16 {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    
+    std::thread::spawn(move || {
+        for control in listener.incoming() {
+            let mut control = control.unwrap();
+            let mut reader = BufReader::new(control.try_clone().unwrap());
+            let mut passive: Option<TcpListener> = None;
+            control.write_all(b"220 ready\r\n").unwrap();
+            
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap_or(0) > 0 {
+                let (command, arg) = line.trim_end().split_once(' ').unwrap_or((line.trim_end(), ""));
+                let file = files.iter().find(|(name, _, _)| *name == arg);
+                let mut transfer = |control: &mut std::net::TcpStream, bytes: &[u8]| {
+                    control.write_all(b"150 opening\r\n").unwrap();
+                    let (mut data, _) = passive.take().unwrap().accept().unwrap();
+                    data.write_all(bytes).unwrap();
+                    drop(data);
+                    control.write_all(b"226 done\r\n").unwrap();
+                };
+                
+                match (command, file) {
+                    ("USER", _) => control.write_all(b"331 password\r\n").unwrap(),
+                    ("PASS", _) if arg == "secret" => control.write_all(b"230 in\r\n").unwrap(),
+                    ("PASS", _) => control.write_all(b"530 denied\r\n").unwrap(),
+                    ("TYPE", _) => control.write_all(b"200 binary\r\n").unwrap(),
+                    ("PASV", _) => {
+                        let data = TcpListener::bind("127.0.0.1:0").unwrap();
+                        let port = data.local_addr().unwrap().port();
+                        passive = Some(data);
+                        write!(control, "227 Entering Passive Mode (10,0,0,1,{},{})\r\n", port / 256, port % 256).unwrap();
+                    },
+                    ("NLST", _) => {
+                        let names: String = files.iter().map(|(name, _, _)| format!("{}\r\n", name)).collect();
+                        transfer(&mut control, names.as_bytes());
+                    },
+                    ("SIZE", Some((_, content, _))) => write!(control, "213 {}\r\n", content.len()).unwrap(),
+                    ("MDTM", Some((_, _, modified))) => write!(control, "213 {}\r\n", modified).unwrap(),
+                    ("RETR", Some((_, content, _))) => transfer(&mut control, content.as_bytes()),
+                    ("QUIT", _) => {
+                        control.write_all(b"221 bye\r\n").unwrap();
+                        break;
+                    },
+                    _ => control.write_all(b"550 no such file\r\n").unwrap(),
+                }
+                
+                line.clear();
+            }
+        }
+    });
+    
+    port
+}
+
+#[test]
+fn test_remote_sources() {
+    use rust_data_processing_engine::data::{DataSource, FtpSource};
+    
+    let port = serve_ftp(vec![
+        ("orders_2.csv", "id,item\n3,pen\n", "20240301120000"),
+        ("orders_1.csv", "id,item\n1,cup\n2,mug\n", "20240101120000"),
+        ("stock.csv", "sku,count\nA,4\n", "20240201120000"),
+        ("notes.txt", "not data", "20240201120000"),
+    ]);
+    let source = |pattern: &str| FtpSource::new("127.0.0.1", "partner", "secret").with_port(port).with_pattern(pattern);
+    
+    // Matching files are listed and combined in name order, over the control host
+    let files = source("orders_*.csv").list().unwrap();
+    let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(paths, vec!["orders_1.csv", "orders_2.csv"]);
+    assert_eq!(files[1].size, Some(14));
+    assert_eq!(files[0].modified.unwrap().to_rfc3339(), "2024-01-01T12:00:00+00:00");
+    
+    let data = source("orders_*.csv").read().unwrap();
+    assert_eq!(data.len(), 3);
+    assert_eq!(data.data[2].values[1], Value::String("pen".to_string()));
+    
+    let single = source("orders_*.csv").read_file(&files[1]).unwrap();
+    assert_eq!(single.len(), 1);
+    
+    // Only files modified after the given time are read
+    let since = chrono::DateTime::parse_from_rfc3339("2024-02-01T00:00:00Z").unwrap().with_timezone(&chrono::Utc);
+    let data = source("orders_*.csv").with_modified_since(since).read().unwrap();
+    assert_eq!(data.len(), 1);
+    
+    // Files with other columns, unknown formats and empty matches fail
+    assert!(matches!(source("*.csv").read(), Err(DataError::ValidationError(_))));
+    assert!(matches!(source("*.txt").read(), Err(DataError::NotSupported(_))));
+    assert!(source("invoices_*.csv").read().is_err());
+    
+    // A wrong password is rejected at login
+    let denied = FtpSource::new("127.0.0.1", "partner", "guess").with_port(port).with_pattern("*.csv");
+    let err = denied.list().unwrap_err();
+    assert!(err.to_string().contains("530"));
+    
+    // SFTP needs the sftp feature
+    #[cfg(not(feature = "sftp"))]
+    {
+        use rust_data_processing_engine::data::{SftpAuth, SftpSource};
+        
+        let sftp = SftpSource::new("127.0.0.1", "partner", SftpAuth::Password("secret".to_string()));
+        assert!(matches!(sftp.list(), Err(DataError::NotSupported(_))));
+        assert!(matches!(sftp.read(), Err(DataError::NotSupported(_))));
+    }
+}
+
+/// Minimal HTTP server answering requests with canned JSON responses in order, for the Elasticsearch sink and OTLP tests
+///
+/// Returns the port and the method, path and body of every request received.
+#[cfg(any(feature = "elasticsearch", feature = "otlp"))]
+fn serve_http(responses: Vec<(u16, serde_json::Value)>) -> (u16, Arc<Mutex<Vec<(String, String)>>>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let received = requests.clone();
+    let responses = Arc::new(Mutex::new(std::collections::VecDeque::from(responses)));
+    
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let responses = responses.clone();
+            let received = received.clone();
+            
+            // Clients may keep the connection alive across requests
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                
+                loop {
+                    let mut request_line = String::new();
+                    if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+                        break;
+                    }
+                    
+                    let mut length = 0;
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).unwrap() > 2 {
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                length = value.trim().parse().unwrap();
+                            }
+                        }
+                        line.clear();
+                    }
+                    
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).unwrap();
+                    let mut parts = request_line.split_whitespace();
+                    let target = format!("{} {}", parts.next().unwrap(), parts.next().unwrap());
+                    received.lock().unwrap().push((target, String::from_utf8(body).unwrap()));
+                    
+                    let (status, response) = responses.lock().unwrap().pop_front().unwrap_or((500, serde_json::json!({})));
+                    let response = response.to_string();
+                    write!(stream, "HTTP/1.1 {} OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                        status, response.len(), response).unwrap();
+                }
+            });
+        }
+    });
+    
+    (port, requests)
+}
+
+#[test]
+fn test_elasticsearch_sink() {
+    use rust_data_processing_engine::data::ElasticsearchSink;
+    use serde_json::json;
+    
+    let schema = Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("name".to_string(), DataType::String, true),
+        Field::new("price".to_string(), DataType::Decimal(10, 2), true),
+        Field::new("location".to_string(), DataType::Point, true),
+    ]);
+    
+    // The mapping keeps exact matches on strings and geo queries on points
+    let mapping = ElasticsearchSink::mapping(&schema);
+    let properties = &mapping["mappings"]["properties"];
+    assert_eq!(properties["id"]["type"], "long");
+    assert_eq!(properties["name"]["type"], "text");
+    assert_eq!(properties["name"]["fields"]["keyword"]["type"], "keyword");
+    assert_eq!(properties["price"], json!({"type": "scaled_float", "scaling_factor": 100.0}));
+    assert_eq!(properties["location"]["type"], "geo_point");
+    
+    let mut data = DataSet::new(schema);
+    for (id, name) in [(1, "cup"), (2, "mug"), (3, "pen")] {
+        data.add_row(Row::new(vec![
+            Value::Integer(id),
+            Value::String(name.to_string()),
+            Value::Null,
+            Value::Point(51.5, -0.1),
+        ])).unwrap();
+    }
+    
+    // The id column must exist
+    let sink = ElasticsearchSink::new("http://127.0.0.1:1", "products").with_id_column("sku");
+    assert!(matches!(sink.write(&data), Err(DataError::ValidationError(_))));
+    
+    #[cfg(not(feature = "elasticsearch"))]
+    {
+        let sink = ElasticsearchSink::new("http://127.0.0.1:1", "products");
+        assert!(matches!(sink.write(&data), Err(DataError::NotSupported(_))));
+    }
+    
+    #[cfg(feature = "elasticsearch")]
+    {
+        let retry = RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            jitter: false,
+            ..RetryPolicy::default()
+        };
+        
+        // An existing index is reused, and rows rejected with 429 are sent again
+        let (port, requests) = serve_http(vec![
+            (400, json!({"error": {"type": "resource_already_exists_exception"}})),
+            (200, json!({"errors": true, "items": [{"index": {"status": 201}}, {"index": {"status": 429}}]})),
+            (200, json!({"errors": false, "items": [{"index": {"status": 201}}]})),
+            (200, json!({"errors": false, "items": [{"index": {"status": 201}}]})),
+        ]);
+        let sink = ElasticsearchSink::new(&format!("http://127.0.0.1:{}/", port), "products")
+            .with_id_column("id")
+            .with_batch_size(2)
+            .with_retry(retry.clone());
+        sink.write(&data).unwrap();
+        
+        let requests = requests.lock().unwrap().clone();
+        let targets: Vec<&str> = requests.iter().map(|(target, _)| target.as_str()).collect();
+        assert_eq!(targets, vec!["PUT /products", "POST /_bulk", "POST /_bulk", "POST /_bulk"]);
+        assert_eq!(requests[0].1, ElasticsearchSink::mapping(&data.schema).to_string());
+        
+        let lines: Vec<serde_json::Value> = requests[2].1.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], json!({"index": {"_index": "products", "_id": "2"}}));
+        assert_eq!(lines[1]["location"], json!({"lat": 51.5, "lon": -0.1}));
+        assert!(requests[3].1.contains("\"_id\":\"3\""));
+        
+        // Other row rejections fail the write without retrying
+        let (port, requests) = serve_http(vec![
+            (200, json!({})),
+            (200, json!({"errors": true, "items": [{"index": {"status": 400, "error": {"type": "mapper_parsing_exception"}}}]})),
+        ]);
+        let sink = ElasticsearchSink::new(&format!("http://127.0.0.1:{}", port), "products").with_retry(retry);
+        let err = sink.write(&data).unwrap_err();
+        assert!(err.to_string().contains("mapper_parsing_exception"));
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+}
+
+/// Minimal MQTT 3.1.1 broker publishing messages with QoS 1 once a client subscribes, for the MQTT source test
+///
+/// Returns the port and the packet ids the client acknowledged.
+#[cfg(feature = "mqtt")]
+fn serve_mqtt(messages: Vec<&'static str>) -> (u16, Arc<Mutex<Vec<u16>>>) {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let acks = Arc::new(Mutex::new(Vec::new()));
+    let acked = acks.clone();
+    
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        
+        loop {
+            let mut header = [0u8; 1];
+            if stream.read_exact(&mut header).is_err() {
+                break;
+            }
+            
+            let (mut length, mut shift) = (0usize, 0);
+            loop {
+                let mut byte = [0u8; 1];
+                stream.read_exact(&mut byte).unwrap();
+                length |= ((byte[0] & 0x7f) as usize) << shift;
+                shift += 7;
+                if byte[0] & 0x80 == 0 {
+                    break;
+                }
+            }
+            let mut body = vec![0u8; length];
+            stream.read_exact(&mut body).unwrap();
+            
+            match header[0] >> 4 {
+                // CONNECT
+                1 => stream.write_all(&[0x20, 0x02, 0x00, 0x00]).unwrap(),
+                // SUBSCRIBE, answered with a grant of QoS 1 and the messages
+                8 => {
+                    stream.write_all(&[0x90, 0x03, body[0], body[1], 0x01]).unwrap();
+                    
+                    for (i, message) in messages.iter().enumerate() {
+                        let topic = b"sensors/a";
+                        let mut packet = vec![0x32, (2 + topic.len() + 2 + message.len()) as u8, 0x00, topic.len() as u8];
+                        packet.extend_from_slice(topic);
+                        packet.extend_from_slice(&(i as u16 + 1).to_be_bytes());
+                        packet.extend_from_slice(message.as_bytes());
+                        stream.write_all(&packet).unwrap();
+                    }
+                },
+                // PUBACK
+                4 => acked.lock().unwrap().push(u16::from_be_bytes([body[0], body[1]])),
+                // PINGREQ
+                12 => stream.write_all(&[0xd0, 0x00]).unwrap(),
+                _ => {},
+            }
+        }
+    });
+    
+    (port, acks)
+}
+
+#[test]
+fn test_mqtt_source() {
+    use rust_data_processing_engine::data::MqttSource;
+    
+    let schema = Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("temp".to_string(), DataType::Float, true),
+    ]);
+    
+    // A source needs topics to subscribe to
+    let source = MqttSource::new("127.0.0.1", 1, "engine", schema.clone());
+    assert!(matches!(source.subscribe(), Err(DataError::ValidationError(_))));
+    
+    #[cfg(not(feature = "mqtt"))]
+    {
+        let source = MqttSource::new("127.0.0.1", 1, "engine", schema).with_topic("sensors/+");
+        assert!(matches!(source.subscribe(), Err(DataError::NotSupported(_))));
+    }
+    
+    #[cfg(feature = "mqtt")]
+    {
+        let (port, acks) = serve_mqtt(vec![
+            r#"{"id": 1, "temp": 20.5}"#,
+            "not json",
+            r#"{"temp": 19.0}"#,
+            r#"{"id": 2, "temp": 21}"#,
+        ]);
+        let mut stream = MqttSource::new("127.0.0.1", port, "engine", schema)
+            .with_topic("sensors/+")
+            .with_batch_size(3)
+            .with_batch_timeout(Duration::from_millis(200))
+            .subscribe()
+            .unwrap();
+        
+        // Batches close when full; messages that do not fit are counted and skipped
+        let batch = stream.next_batch().unwrap();
+        assert_eq!(batch.data.len(), 1);
+        assert_eq!(batch.rejected, 2);
+        assert_eq!(batch.data.data[0].values, vec![Value::Integer(1), Value::Float(20.5)]);
+        assert!(acks.lock().unwrap().is_empty());
+        stream.commit(batch).unwrap();
+        
+        // Or after their timeout, acknowledging the committed batch on the way
+        let batch = stream.next_batch().unwrap();
+        assert_eq!(batch.data.len(), 1);
+        assert_eq!(batch.rejected, 0);
+        assert_eq!(batch.data.data[0].values, vec![Value::Integer(2), Value::Float(21.0)]);
+        
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while acks.lock().unwrap().len() < 3 && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(*acks.lock().unwrap(), vec![1, 2, 3]);
+    }
+}
+
+#[test]
+fn test_delta_tables() {
+    use rust_data_processing_engine::data::{DataSource, DeltaTable, DeltaWriteMode};
+    use serde_json::json;
+    
+    let dir = tempfile::tempdir().unwrap();
+    let write_log = |table: &str, version: u64, actions: Vec<serde_json::Value>| {
+        let log = dir.path().join(table).join("_delta_log");
+        std::fs::create_dir_all(&log).unwrap();
+        let body: String = actions.iter().map(|action| format!("{}\n", action)).collect();
+        std::fs::write(log.join(format!("{:020}.json", version)), body).unwrap();
+    };
+    let schema_string = json!({"type": "struct", "fields": [
+        {"name": "id", "type": "long", "nullable": false, "metadata": {}},
+        {"name": "region", "type": "string", "nullable": true, "metadata": {}},
+    ]}).to_string();
+    
+    // Commits written by another engine are replayed up to each version
+    write_log("sales", 0, vec![
+        json!({"protocol": {"minReaderVersion": 1, "minWriterVersion": 2}}),
+        json!({"metaData": {"schemaString": schema_string, "partitionColumns": ["region"]}}),
+        json!({"add": {"path": "region=eu/a.parquet", "partitionValues": {"region": "eu"}, "size": 10}}),
+        json!({"add": {"path": "region=us/b.parquet", "partitionValues": {"region": "us"}, "size": 20}}),
+    ]);
+    write_log("sales", 1, vec![
+        json!({"commitInfo": {"operation": "DELETE"}}),
+        json!({"remove": {"path": "region=eu/a.parquet"}}),
+        json!({"add": {"path": "region=__HIVE_DEFAULT_PARTITION__/c.parquet", "partitionValues": {"region": null}, "size": 30}}),
+    ]);
+    
+    let sales = DeltaTable::open(dir.path().join("sales"));
+    assert_eq!(sales.latest_version().unwrap(), Some(1));
+    
+    let snapshot = sales.snapshot(None).unwrap();
+    assert_eq!(snapshot.version, 1);
+    assert_eq!(snapshot.partition_columns, vec!["region"]);
+    assert_eq!(snapshot.schema.fields[0].data_type, DataType::Integer);
+    assert!(!snapshot.schema.fields[0].nullable);
+    let paths: Vec<&str> = snapshot.files.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(paths, vec!["region=us/b.parquet", "region=__HIVE_DEFAULT_PARTITION__/c.parquet"]);
+    assert_eq!(snapshot.files[1].partition_values["region"], None);
+    
+    let snapshot = sales.snapshot(Some(0)).unwrap();
+    assert_eq!(snapshot.files.len(), 2);
+    assert_eq!(snapshot.files[0].size, 10);
+    
+    // A version without files reads as an empty table
+    write_log("sales", 2, vec![
+        json!({"remove": {"path": "region=us/b.parquet"}}),
+        json!({"remove": {"path": "region=__HIVE_DEFAULT_PARTITION__/c.parquet"}}),
+    ]);
+    let data = DeltaTable::open(dir.path().join("sales")).read().unwrap();
+    assert_eq!(data.len(), 0);
+    assert_eq!(data.schema.fields.len(), 2);
+    assert_eq!(data.metadata.get("delta_version").map(String::as_str), Some("2"));
+    
+    // Unknown versions, partitioned writes and mismatched appends are rejected
+    assert!(matches!(sales.snapshot(Some(5)), Err(DataError::ValidationError(_))));
+    assert!(matches!(DeltaTable::open(dir.path().join("sales")).with_version(7).read(), Err(DataError::ValidationError(_))));
+    assert!(matches!(sales.commit(&data, DeltaWriteMode::Append), Err(DataError::NotSupported(_))));
+    
+    write_log("events", 0, vec![
+        json!({"protocol": {"minReaderVersion": 1, "minWriterVersion": 2}}),
+        json!({"metaData": {"schemaString": schema_string, "partitionColumns": []}}),
+    ]);
+    let mut other = DataSet::new(Schema::new(vec![Field::new("id".to_string(), DataType::Integer, false)]));
+    other.add_row(Row::new(vec![Value::Integer(1)])).unwrap();
+    let events = DeltaTable::open(dir.path().join("events"));
+    assert!(matches!(events.commit(&other, DeltaWriteMode::Append), Err(DataError::ValidationError(_))));
+    assert_eq!(events.latest_version().unwrap(), Some(0));
+    
+    // Columns without a Delta type are rejected before anything is written
+    let mut times = DataSet::new(Schema::new(vec![Field::new("at".to_string(), DataType::Time, true)]));
+    times.add_row(Row::new(vec![Value::Time(chrono::NaiveTime::from_hms_opt(9, 30, 0).unwrap())])).unwrap();
+    let fresh = DeltaTable::open(dir.path().join("fresh"));
+    assert!(matches!(fresh.commit(&times, DeltaWriteMode::Append), Err(DataError::NotSupported(_))));
+    assert_eq!(fresh.latest_version().unwrap(), None);
+    assert!(fresh.read().is_err());
+    
+    // Newer reader protocols and gaps in the log are not supported
+    write_log("deletions", 0, vec![
+        json!({"protocol": {"minReaderVersion": 3, "minWriterVersion": 7}}),
+        json!({"metaData": {"schemaString": schema_string, "partitionColumns": []}}),
+    ]);
+    assert!(matches!(DeltaTable::open(dir.path().join("deletions")).snapshot(None), Err(DataError::NotSupported(_))));
+    
+    write_log("checkpointed", 10, vec![json!({"remove": {"path": "a.parquet"}})]);
+    assert!(matches!(DeltaTable::open(dir.path().join("checkpointed")).snapshot(None), Err(DataError::NotSupported(_))));
+    
+    // Writes commit the next version, which reads back as of each version
+    #[cfg(feature = "parquet")]
+    {
+        let table = DeltaTable::open(dir.path().join("written"));
+        assert_eq!(table.commit(&other, DeltaWriteMode::Append).unwrap(), 0);
+        assert_eq!(table.commit(&other, DeltaWriteMode::Append).unwrap(), 1);
+        assert_eq!(table.read().unwrap().len(), 2);
+        assert_eq!(table.commit(&other, DeltaWriteMode::Overwrite).unwrap(), 2);
+        assert_eq!(table.read().unwrap().len(), 1);
+        assert_eq!(table.read_version(Some(1)).unwrap().len(), 2);
+    }
+}
+
+#[test]
+fn test_orc_format() {
+    use rust_data_processing_engine::data::{DataSource, OrcSink, OrcSource};
+    use rust_data_processing_engine::storage::{FileFormat, FileStorage};
+    
+    // ORC is a storage format like the others
+    assert_eq!(FileFormat::from_extension("orc").unwrap(), FileFormat::Orc);
+    assert_eq!(FileFormat::Orc.extension(), "orc");
+    
+    let mut dataset = DataSet::new(Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("name".to_string(), DataType::String, true),
+        Field::new("score".to_string(), DataType::Float, true),
+        Field::new("day".to_string(), DataType::Date, true),
+    ]));
+    for (id, name) in [(1, Some("a")), (2, None), (3, Some("c"))] {
+        dataset.add_row(Row::new(vec![
+            Value::Integer(id),
+            name.map_or(Value::Null, |name| Value::String(name.to_string())),
+            Value::Float(id as f64 * 1.5),
+            Value::Date(chrono::NaiveDate::from_ymd_opt(2024, 1, id as u32).unwrap()),
+        ])).unwrap();
+    }
+    
+    let dir = tempfile::tempdir().unwrap();
+    let storage = FileStorage::new(dir.path(), FileFormat::Orc).unwrap();
+    
+    #[cfg(not(feature = "orc"))]
+    {
+        assert!(matches!(storage.store("scores", &dataset), Err(StorageError::DataError(DataError::NotSupported(_)))));
+        assert!(matches!(OrcSource::new(dir.path().join("scores.orc")).read(), Err(DataError::NotSupported(_))));
+        assert!(matches!(OrcSink::new(dir.path().join("scores.orc")).write(&dataset), Err(DataError::NotSupported(_))));
+    }
+    
+    #[cfg(feature = "orc")]
+    {
+        // Rows round trip, with dates read back as text
+        storage.store("scores", &dataset).unwrap();
+        assert!(dir.path().join("scores.orc").exists());
+        
+        let loaded = storage.load("scores").unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded.data[1].values[1], Value::Null);
+        assert_eq!(loaded.data[2].values[2], Value::Float(4.5));
+        assert_eq!(loaded.data[0].values[3], Value::String("2024-01-01".to_string()));
+        
+        let limited = OrcSource::new(dir.path().join("scores.orc")).with_limit(2).read().unwrap();
+        assert_eq!(limited.len(), 2);
+        
+        // Nested columns have no ORC type here
+        let mut nested = DataSet::new(Schema::new(vec![
+            Field::new("tags".to_string(), DataType::Array(Box::new(DataType::String)), true),
+        ]));
+        nested.add_row(Row::new(vec![Value::Array(vec![Value::String("x".to_string())])])).unwrap();
+        assert!(matches!(OrcSink::new(dir.path().join("nested.orc")).write(&nested), Err(DataError::NotSupported(_))));
+        assert!(OrcSource::new(dir.path().join("missing.orc")).read().is_err());
+    }
+    
+    // ORC files cannot be encrypted at rest
+    #[cfg(feature = "encryption")]
+    {
+        use rust_data_processing_engine::storage::{EncryptionKey, KeyRing};
+        
+        let keys = Arc::new(KeyRing::new(EncryptionKey::new("v1", vec![1; 32]).unwrap()));
+        assert!(matches!(FileStorage::new(dir.path(), FileFormat::Orc).unwrap().with_encryption(keys), Err(StorageError::InvalidFormat(_))));
+    }
+}
+
+#[test]
+fn test_fixed_width_source() {
+    use rust_data_processing_engine::data::{DataSource, FixedWidthColumn, FixedWidthSource};
+    
+    let text = [
+        "ACCOUNT   BALANCE   OPENED",
+        "A-001       12.50 2024-01-05",
+        "",
+        "Bé-02        -3.25",
+        "  C03         0.00 2023-12-31",
+    ].join("\n");
+    let source = FixedWidthSource::new("accounts.txt")
+        .with_column(FixedWidthColumn::new("account", 0, 6, DataType::String))
+        .with_column(FixedWidthColumn::new("balance", 6, 12, DataType::Float))
+        .with_column(FixedWidthColumn::new("opened", 18, 10, DataType::Date))
+        .with_column(FixedWidthColumn::new("raw", 0, 6, DataType::String).untrimmed())
+        .with_skip_lines(1);
+    
+    // Columns are cut by character, short lines leave nulls and blank lines are skipped
+    let data = source.read_from(text.as_bytes()).unwrap();
+    assert_eq!(data.len(), 3);
+    assert_eq!(data.data[0].values[0], Value::String("A-001".to_string()));
+    assert_eq!(data.data[0].values[1], Value::Float(12.5));
+    assert_eq!(data.data[0].values[2], Value::Date(chrono::NaiveDate::from_ymd_opt(2024, 1, 5).unwrap()));
+    assert_eq!(data.data[1].values[0], Value::String("Bé-02".to_string()));
+    assert_eq!(data.data[1].values[1], Value::Float(-3.25));
+    assert_eq!(data.data[1].values[2], Value::Null);
+    assert_eq!(data.data[2].values[0], Value::String("C03".to_string()));
+    assert_eq!(data.data[2].values[3], Value::String("  C03 ".to_string()));
+    
+    assert_eq!(source.with_limit(1).read_from(text.as_bytes()).unwrap().len(), 1);
+    
+    // Layouts can come from a spec file
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("accounts.txt"), &text).unwrap();
+    std::fs::write(dir.path().join("layout.json"), r#"{
+        "skip_lines": 1,
+        "columns": [
+            {"name": "account", "start": 0, "length": 6},
+            {"name": "balance", "start": 6, "length": 12, "type": "float"}
+        ]
+    }"#).unwrap();
+    let source = FixedWidthSource::from_spec_file(dir.path().join("accounts.txt"), dir.path().join("layout.json")).unwrap();
+    let data = source.read().unwrap();
+    assert_eq!(data.schema.fields[1].data_type, DataType::Float);
+    assert_eq!(data.data[2].values[1], Value::Float(0.0));
+    
+    // Values that do not parse fail the read with their line
+    let err = FixedWidthSource::new("bad.txt")
+        .with_column(FixedWidthColumn::new("balance", 0, 5, DataType::Integer))
+        .read_from("   12\n  abc\n".as_bytes())
+        .unwrap_err();
+    assert!(err.to_string().contains("Line 2"));
+    
+    // Layouts without columns, with empty or unsupported columns, and bad spec files are rejected
+    assert!(matches!(FixedWidthSource::new("x.txt").read_from("a\n".as_bytes()), Err(DataError::ValidationError(_))));
+    let empty = FixedWidthSource::new("x.txt").with_column(FixedWidthColumn::new("a", 0, 0, DataType::String));
+    assert!(matches!(empty.schema(), Err(DataError::ValidationError(_))));
+    let binary = FixedWidthSource::new("x.txt").with_column(FixedWidthColumn::new("a", 0, 4, DataType::Binary));
+    assert!(matches!(binary.schema(), Err(DataError::NotSupported(_))));
+    
+    std::fs::write(dir.path().join("broken.json"), r#"{"columns": [{"name": "a"}]}"#).unwrap();
+    assert!(matches!(
+        FixedWidthSource::from_spec_file(dir.path().join("accounts.txt"), dir.path().join("broken.json")),
+        Err(DataError::ParseError(_))
+    ));
+    assert!(FixedWidthSource::from_spec_file(dir.path().join("accounts.txt"), dir.path().join("missing.json")).is_err());
+}
+
+#[test]
+fn test_xml_source() {
+    use rust_data_processing_engine::data::{DataSource, XmlSource};
+    
+    let xml = r#"<?xml version="1.0"?>
+        <feed xmlns:x="urn:example">
+            <orders>
+                <order id="1" status="open">
+                    <code>00123</code>
+                    <total>19.5</total>
+                    <paid>true</paid>
+                    <customer id="7"><name>Alice</name><address><city>London</city></address></customer>
+                    <item>cup</item>
+                    <item>mug</item>
+                </order>
+                <order id="2" status="closed">
+                    <code>00124</code>
+                    <total>5</total>
+                    <paid>false</paid>
+                    <customer id="8"><name>Bob &amp; Co</name><address><city>Paris</city></address></customer>
+                    <item>pen</item>
+                    <item>ink</item>
+                </order>
+            </orders>
+            <archive><order id="3"/></archive>
+        </feed>"#;
+    
+    #[cfg(not(feature = "xml"))]
+    {
+        assert!(matches!(XmlSource::new("orders.xml", "orders/order").read_from(xml.as_bytes()), Err(DataError::NotSupported(_))));
+    }
+    
+    #[cfg(feature = "xml")]
+    {
+        // Attributes, nested and repeated elements become columns of each record
+        let data = XmlSource::new("orders.xml", "orders/order").read_from(xml.as_bytes()).unwrap();
+        assert_eq!(data.len(), 2);
+        let column = |row: usize, name: &str| {
+            let index = data.schema.fields.iter().position(|field| field.name == name).unwrap();
+            data.data[row].values[index].clone()
+        };
+        assert_eq!(column(0, "id"), Value::Integer(1));
+        assert_eq!(column(1, "status"), Value::String("closed".to_string()));
+        assert_eq!(column(0, "code"), Value::String("00123".to_string()));
+        assert_eq!(column(1, "paid"), Value::Boolean(false));
+        assert_eq!(column(0, "customer.id"), Value::Integer(7));
+        assert_eq!(column(1, "customer.name"), Value::String("Bob & Co".to_string()));
+        assert_eq!(column(0, "customer.address.city"), Value::String("London".to_string()));
+        assert_eq!(column(1, "item"), Value::Array(vec![Value::String("pen".to_string()), Value::String("ink".to_string())]));
+        
+        // Paths match at any depth unless anchored at the root
+        assert_eq!(XmlSource::new("orders.xml", "order").read_from(xml.as_bytes()).unwrap().len(), 3);
+        assert_eq!(XmlSource::new("orders.xml", "/feed/archive/order").read_from(xml.as_bytes()).unwrap().len(), 1);
+        
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("orders.xml"), xml).unwrap();
+        assert_eq!(XmlSource::new(dir.path().join("orders.xml"), "orders/order").read().unwrap().len(), 2);
+        
+        // Paths without elements and malformed documents fail
+        assert!(matches!(XmlSource::new("orders.xml", "/orders/order").read_from(xml.as_bytes()), Err(DataError::ParseError(_))));
+        assert!(matches!(XmlSource::new("bad.xml", "a").read_from("<a><b></a>".as_bytes()), Err(DataError::ParseError(_))));
+    }
+    
+    assert!(XmlSource::new("missing.xml", "a").read().is_err());
+}
+
+#[test]
+fn test_dataset_snapshots() {
+    use rust_data_processing_engine::utils::{
+        assert_dataset_eq, assert_dataset_eq_unordered, assert_snapshot, canonical_csv, canonical_json, diff_lines, RowOrder,
+    };
+    
+    let schema = Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("score".to_string(), DataType::Float, true),
+        Field::new("tags".to_string(), DataType::Array(Box::new(DataType::String)), true),
+    ]);
+    let dataset = |ids: &[i64]| {
+        let mut data = DataSet::new(schema.clone());
+        for &id in ids {
+            data.add_row(Row::new(vec![
+                Value::Integer(id),
+                if id == 2 { Value::Null } else { Value::Float(id as f64) },
+                Value::Array(vec![Value::String(format!("t{}", id))]),
+            ])).unwrap();
+        }
+        data
+    };
+    
+    // Renderings are canonical: typed headers, nulls, floats with a point and nested JSON
+    assert_eq!(
+        canonical_csv(&dataset(&[2, 1]), RowOrder::AsIs),
+        "id:integer,score:float?,tags:array<string>?\n2,null,\"[\"\"t2\"\"]\"\n1,1.0,\"[\"\"t1\"\"]\"\n"
+    );
+    assert_eq!(canonical_csv(&dataset(&[2, 1]), RowOrder::Sorted), canonical_csv(&dataset(&[1, 2]), RowOrder::AsIs));
+    let json: serde_json::Value = serde_json::from_str(&canonical_json(&dataset(&[1]), RowOrder::AsIs)).unwrap();
+    assert_eq!(json["schema"][1], serde_json::json!({"name": "score", "type": "float", "nullable": true}));
+    assert_eq!(json["rows"][0], serde_json::json!([1, 1.0, ["t1"]]));
+    
+    assert_dataset_eq(&dataset(&[1, 2]), &dataset(&[1, 2]));
+    assert_dataset_eq_unordered(&dataset(&[2, 1]), &dataset(&[1, 2]));
+    
+    // Mismatches panic with a diff of the renderings
+    let panic_message = |f: Box<dyn FnOnce() + std::panic::UnwindSafe>| {
+        let payload = std::panic::catch_unwind(f).unwrap_err();
+        payload.downcast_ref::<String>().cloned().unwrap_or_default()
+    };
+    let message = panic_message(Box::new(move || assert_dataset_eq(&dataset(&[2, 1]), &dataset(&[1, 2]))));
+    assert!(message.contains("- 1,1.0"));
+    assert!(message.contains("+ 1,1.0"));
+    
+    assert_eq!(diff_lines("a\nb\nc\n", "a\nx\nc\n"), "  a\n- b\n+ x\n  c\n");
+    let long: String = (0..20).map(|i| format!("{}\n", i)).collect();
+    let diff = diff_lines(&long, &long.replace("10\n", "ten\n"));
+    assert!(diff.starts_with("  ...\n  7\n"));
+    assert!(diff.ends_with("  13\n  ...\n"));
+    
+    // Missing snapshots are written, then later results are compared against them
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("golden").join("scores.json");
+    let mut data = DataSet::new(schema.clone());
+    data.add_row(Row::new(vec![Value::Integer(3), Value::Float(0.5), Value::Null])).unwrap();
+    assert_snapshot(&path, &data, RowOrder::AsIs);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), canonical_json(&data, RowOrder::AsIs));
+    assert_snapshot(&path, &data, RowOrder::AsIs);
+    
+    let csv_path = dir.path().join("scores.csv");
+    std::fs::write(&csv_path, canonical_csv(&data, RowOrder::AsIs).replace('\n', "\r\n")).unwrap();
+    assert_snapshot(&csv_path, &data, RowOrder::AsIs);
+    
+    data.data[0].values[1] = Value::Float(0.75);
+    let message = panic_message(Box::new(move || assert_snapshot(&path, &data, RowOrder::AsIs)));
+    assert!(message.contains("UPDATE_SNAPSHOTS=1"));
+    assert!(message.contains("+       0.75,"));
+}
+
+#[test]
+fn test_aggregate_window_functions() {
+    use rust_data_processing_engine::processing::OverflowPolicy;
+    
+    let mut sales = DataSet::new(Schema::new(vec![
+        Field::new("dept".to_string(), DataType::String, false),
+        Field::new("amount".to_string(), DataType::Integer, true),
+    ]));
+    for (dept, amount) in [("a", Some(20)), ("b", Some(50)), ("a", Some(10)), ("a", None), ("b", Some(40)), ("a", Some(30))] {
+        sales.add_row(Row::new(vec![Value::String(dept.to_string()), amount.map_or(Value::Null, Value::Integer)])).unwrap();
+    }
+    
+    let window = |processor: WindowProcessor| processor.partition_by(vec!["dept".to_string()]).process(&sales).unwrap();
+    let values = |result: &DataSet| result.data.iter().map(|row| row.values[2].clone()).collect::<Vec<_>>();
+    let integers = |values: &[i64]| values.iter().map(|&v| Value::Integer(v)).collect::<Vec<_>>();
+    
+    // Every row gets its partition's aggregate, in input order
+    let totals = window(WindowProcessor::sum("total", "amount"));
+    assert_eq!(values(&totals), integers(&[60, 90, 60, 60, 90, 60]));
+    assert_eq!(totals.schema.fields[2].data_type, DataType::Integer);
+    
+    let averages = window(WindowProcessor::avg("average", "amount"));
+    assert_eq!(averages.schema.fields[2].data_type, DataType::Float);
+    assert_eq!(values(&averages)[0], Value::Float(20.0));
+    assert_eq!(values(&averages)[1], Value::Float(45.0));
+    
+    assert_eq!(values(&window(WindowProcessor::min("low", "amount"))), integers(&[10, 40, 10, 10, 40, 10]));
+    assert_eq!(values(&window(WindowProcessor::max("high", "amount"))), integers(&[30, 50, 30, 30, 50, 30]));
+    // Nulls are not counted
+    assert_eq!(values(&window(WindowProcessor::count("n", "amount"))), integers(&[3, 2, 3, 3, 2, 3]));
+    
+    // Without partitions the aggregate covers the whole dataset
+    let all = WindowProcessor::sum("total", "amount").process(&sales).unwrap();
+    assert_eq!(all.data[3].values[2], Value::Integer(150));
+    
+    assert_eq!(WindowFunctionType::from_str("avg").unwrap(), WindowFunctionType::Avg);
+    assert!(WindowFunctionType::Count.is_aggregate());
+    
+    // Aggregates need a value column that exists
+    let missing_value = WindowProcessor::new("total", WindowFunctionType::Sum, None, Vec::new(), Vec::new(), Vec::new());
+    assert!(missing_value.process(&sales).is_err());
+    assert!(WindowProcessor::sum("total", "price").process(&sales).is_err());
+    
+    // Integer sums follow the overflow policy, widening the column when promoted
+    let mut large = DataSet::new(Schema::new(vec![
+        Field::new("dept".to_string(), DataType::String, false),
+        Field::new("amount".to_string(), DataType::Integer, false),
+    ]));
+    for amount in [i64::MAX, 1] {
+        large.add_row(Row::new(vec![Value::String("a".to_string()), Value::Integer(amount)])).unwrap();
+    }
+    let sum = |policy: OverflowPolicy| WindowProcessor::sum("total", "amount").with_overflow(policy).process(&large);
+    
+    assert!(sum(OverflowPolicy::Error).is_err());
+    assert_eq!(sum(OverflowPolicy::Saturate).unwrap().data[1].values[2], Value::Integer(i64::MAX));
+    let promoted = sum(OverflowPolicy::Promote).unwrap();
+    assert_eq!(promoted.schema.fields[2].data_type, DataType::Float);
+    assert!(matches!(promoted.data[0].values[2], Value::Float(f) if f >= i64::MAX as f64));
+}
+
+#[test]
+fn test_distribution_window_functions() {
+    let mut scores = DataSet::new(Schema::new(vec![
+        Field::new("team".to_string(), DataType::String, false),
+        Field::new("score".to_string(), DataType::Integer, false),
+    ]));
+    for (team, score) in [("a", 20), ("a", 40), ("b", 70), ("a", 10), ("a", 20)] {
+        scores.add_row(Row::new(vec![Value::String(team.to_string()), Value::Integer(score)])).unwrap();
+    }
+    
+    let by_score = vec![("score".to_string(), true)];
+    let window = |processor: WindowProcessor| processor
+        .partition_by(vec!["team".to_string()])
+        .order_by(by_score.clone())
+        .process(&scores);
+    let values = |result: DataSet| result.data.iter().map(|row| row.values[2].clone()).collect::<Vec<_>>();
+    let floats = |values: &[f64]| values.iter().map(|&v| Value::Float(v)).collect::<Vec<_>>();
+    
+    // Peers share a relative rank; a partition of one row ranks at 0
+    let ranks = window(WindowProcessor::percent_rank("pct")).unwrap();
+    assert_eq!(ranks.schema.fields[2].data_type, DataType::Float);
+    assert_eq!(values(ranks), floats(&[1.0 / 3.0, 1.0, 0.0, 0.0, 1.0 / 3.0]));
+    
+    // Cumulative distribution counts every peer of the row
+    assert_eq!(values(window(WindowProcessor::cume_dist("dist")).unwrap()), floats(&[0.75, 1.0, 1.0, 0.25, 0.75]));
+    
+    // The first buckets take the extra rows, and extra buckets stay empty
+    let integers = |values: &[i64]| values.iter().map(|&v| Value::Integer(v)).collect::<Vec<_>>();
+    assert_eq!(values(window(WindowProcessor::ntile("bucket", 3)).unwrap()), integers(&[1, 3, 1, 1, 2]));
+    assert_eq!(values(window(WindowProcessor::ntile("bucket", 10)).unwrap()), integers(&[2, 4, 1, 1, 3]));
+    
+    assert_eq!(WindowFunctionType::from_str("cume_dist").unwrap(), WindowFunctionType::CumeDist);
+    assert_eq!(WindowFunctionType::Ntile.name(), "ntile");
+    
+    // Relative ranks need an order, and ntile a positive bucket count
+    let unordered = WindowProcessor::percent_rank("pct").process(&scores);
+    assert!(matches!(unordered, Err(ProcessingError::InvalidArgument(_))));
+    assert!(matches!(window(WindowProcessor::ntile("bucket", 0)), Err(ProcessingError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_merged_aggregation_states() {
+    use rust_data_processing_engine::processing::{AvgFunction, CountFunction, MaxFunction, MinFunction, OverflowPolicy, SumFunction};
+    
+    /// Aggregate parts of a group separately, merge their states and finalize
+    fn merged<F: AggregateFunction>(function: &F, parts: &[&[Value]]) -> Result<Value, ProcessingError> {
+        let mut state = function.init();
+        
+        for part in parts {
+            let mut partial = function.init();
+            for value in part.iter() {
+                function.update(&mut partial, value);
+            }
+            function.merge(&mut state, partial);
+        }
+        
+        function.check(&state)?;
+        Ok(function.finalize(state))
+    }
+    
+    let values = [Value::Integer(4), Value::Null, Value::Integer(-2), Value::Float(1.5), Value::Integer(9)];
+    let parts: [&[Value]; 3] = [&values[..2], &values[2..2], &values[2..]];
+    
+    // Merged states finalize as one state updated with every value would
+    assert_eq!(merged(&CountFunction, &parts).unwrap(), merged(&CountFunction, &[&values]).unwrap());
+    assert_eq!(merged(&CountFunction, &parts).unwrap(), Value::Integer(4));
+    assert_eq!(merged(&SumFunction::new(), &parts).unwrap(), Value::Float(12.5));
+    assert_eq!(merged(&AvgFunction, &parts).unwrap(), Value::Float(12.5 / 4.0));
+    assert_eq!(merged(&MinFunction, &parts).unwrap(), Value::Integer(-2));
+    assert_eq!(merged(&MaxFunction, &parts).unwrap(), Value::Integer(9));
+    assert_eq!(merged(&ProductFunction, &[&values[..1], &values[2..3]]).unwrap(), Value::Integer(-8));
+    
+    let decimals = [Value::Decimal(Decimal::from_str("1.25").unwrap()), Value::Decimal(Decimal::from_str("2.50").unwrap())];
+    assert_eq!(merged(&SumFunction::new(), &[&decimals[..1], &decimals[1..]]).unwrap(), Value::Decimal(Decimal::from_str("3.75").unwrap()));
+    
+    // Empty groups merge into empty results
+    assert_eq!(merged(&MaxFunction, &[&[], &[Value::Null]]).unwrap(), Value::Null);
+    assert_eq!(merged(&CountFunction, &[&[], &[]]).unwrap(), Value::Integer(0));
+    
+    // Partial sums that overflow when merged follow the overflow policy
+    let halves: [&[Value]; 2] = [&[Value::Integer(i64::MAX)], &[Value::Integer(1)]];
+    assert!(matches!(merged(&SumFunction::new(), &halves), Err(ProcessingError::InvalidOperation(_))));
+    assert_eq!(merged(&SumFunction::new().with_overflow(OverflowPolicy::Saturate), &halves).unwrap(), Value::Integer(i64::MAX));
+    assert!(matches!(merged(&SumFunction::new().with_overflow(OverflowPolicy::Promote), &halves).unwrap(), Value::Float(f) if f >= i64::MAX as f64));
+    
+    // An overflow inside one part is carried into the merged state
+    let overflowing: [&[Value]; 2] = [&[Value::Integer(i64::MAX), Value::Integer(1)], &[Value::Integer(-5)]];
+    assert!(merged(&SumFunction::new(), &overflowing).is_err());
+}
+
+#[test]
+fn test_type_erased_aggregation_states() {
+    use rust_data_processing_engine::processing::{AvgFunction, CountFunction, DynAggregateFunction, SumFunction};
+    
+    // Functions with different state types are held and driven together
+    let functions: Vec<Box<dyn DynAggregateFunction>> = vec![
+        Box::new(CountFunction),
+        Box::new(SumFunction::new()),
+        Box::new(AvgFunction),
+        Box::new(ProductFunction),
+    ];
+    let names: Vec<&str> = functions.iter().map(|function| function.name()).collect();
+    assert_eq!(names, vec!["count", "sum", "avg", "product"]);
+    assert_eq!(functions[2].output_type(&DataType::Integer), DataType::Float);
+    
+    let first = [Value::Integer(2), Value::Integer(3)];
+    let second = [Value::Null, Value::Integer(5)];
+    
+    let results: Vec<Value> = functions.iter()
+        .map(|function| {
+            let mut state = function.init();
+            function.update_all(&mut state, &mut first.iter());
+            
+            let mut partial = function.init();
+            function.update_all(&mut partial, &mut second.iter());
+            function.merge(&mut state, partial);
+            
+            function.check(&state).unwrap();
+            function.finalize(state)
+        })
+        .collect();
+    assert_eq!(results, vec![Value::Integer(3), Value::Integer(10), Value::Float(10.0 / 3.0), Value::Integer(30)]);
+    
+    // Errors in erased states are still reported before finalizing
+    let sum: Box<dyn DynAggregateFunction> = Box::new(SumFunction::new());
+    let mut state = sum.init();
+    sum.update_all(&mut state, &mut [Value::Integer(i64::MAX), Value::Integer(1)].iter());
+    assert!(matches!(sum.check(&state), Err(ProcessingError::InvalidOperation(_))));
+    
+    // A state only works with the kind of function that created it
+    let count_state = functions[0].init();
+    let payload = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| functions[2].finalize(count_state))).unwrap_err();
+    let message = payload.downcast_ref::<String>().cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap_or_default();
+    assert!(message.contains("created by another aggregation function"));
+}
+
+#[test]
+fn test_dataset_formats() {
+    use rust_data_processing_engine::storage::{FileFormat, FileStorage};
+    
+    let mut dataset = DataSet::new(Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("name".to_string(), DataType::String, false),
+    ]));
+    dataset.add_row(Row::new(vec![Value::Integer(1), Value::String("Alice".to_string())])).unwrap();
+    dataset.add_row(Row::new(vec![Value::Integer(2), Value::String("Bob".to_string())])).unwrap();
+    
+    let dir = tempfile::tempdir().unwrap();
+    let storage = FileStorage::new(dir.path(), FileFormat::Csv).unwrap();
+    storage.store("default", &dataset).unwrap();
+    storage.store_as("people", &dataset, FileFormat::Json).unwrap();
+    assert_eq!(storage.dataset_format("default").unwrap(), FileFormat::Csv);
+    assert_eq!(storage.dataset_format("people").unwrap(), FileFormat::Json);
+    
+    // Overwrites keep the dataset's own format
+    storage.store("people", &dataset).unwrap();
+    assert!(dir.path().join("people.json").exists());
+    assert!(!dir.path().join("people.csv").exists());
+    
+    // A storage configured for another format still reads the dataset
+    let reopened = FileStorage::new(dir.path(), FileFormat::Csv).unwrap();
+    let loaded = reopened.load("people").unwrap();
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(loaded.data[1].values, vec![Value::Integer(2), Value::String("Bob".to_string())]);
+    assert_eq!(reopened.info("people").unwrap().format, "json");
+    
+    // The manifest is hidden from the datasets listed
+    assert!(dir.path().join(".formats.json").exists());
+    let mut names = reopened.list().unwrap();
+    names.sort();
+    assert_eq!(names, vec!["default", "people"]);
+    
+    // Storing in a new format replaces the previous file
+    reopened.store_as("people", &dataset, FileFormat::Csv).unwrap();
+    assert!(dir.path().join("people.csv").exists());
+    assert!(!dir.path().join("people.json").exists());
+    assert_eq!(reopened.dataset_format("people").unwrap(), FileFormat::Csv);
+    
+    // Copies and renames carry the format along
+    reopened.store_as("scores", &dataset, FileFormat::Json).unwrap();
+    reopened.copy("scores", "copied").unwrap();
+    reopened.rename("scores", "renamed").unwrap();
+    assert_eq!(reopened.dataset_format("copied").unwrap(), FileFormat::Json);
+    assert_eq!(reopened.dataset_format("renamed").unwrap(), FileFormat::Json);
+    assert!(dir.path().join("renamed.json").exists());
+    assert!(matches!(reopened.dataset_format("scores"), Err(StorageError::NotFound(_))));
+    
+    // Files missing from the manifest are found by extension
+    std::fs::write(dir.path().join("dropped.json"), "[{\"id\": 7, \"name\": \"Eve\"}]").unwrap();
+    assert_eq!(reopened.dataset_format("dropped").unwrap(), FileFormat::Json);
+    assert_eq!(reopened.load("dropped").unwrap().len(), 1);
+    
+    // Deleting forgets the format
+    reopened.delete("renamed").unwrap();
+    assert!(!reopened.exists("renamed").unwrap());
+    assert!(matches!(reopened.load("renamed"), Err(StorageError::NotFound(_))));
+    
+    // Renames onto an existing dataset and corrupt manifests are errors
+    assert!(matches!(reopened.rename("copied", "people"), Err(StorageError::AlreadyExists(_))));
+    std::fs::write(dir.path().join(".formats.json"), "{\"people\": \"xlsx\"}").unwrap();
+    assert!(matches!(FileStorage::new(dir.path(), FileFormat::Csv), Err(StorageError::InvalidFormat(_))));
+}
+
+#[test]
+fn test_telemetry_export() {
+    use rust_data_processing_engine::utils::{parse_traceparent, AppError, Span, SpanKind, Telemetry};
+    
+    // Trace context round trips through the traceparent header
+    let root = Span::start("pipeline", SpanKind::Internal);
+    let child = root.child("filter", SpanKind::Internal);
+    assert_eq!(child.trace_id(), root.trace_id());
+    assert_ne!(child.span_id(), root.span_id());
+    assert_eq!(parse_traceparent(&child.traceparent()), Some((child.trace_id(), child.span_id())));
+    assert_eq!(
+        parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+        Some((0x4bf92f3577b34da6a3ce929d0e0e4736, 0x00f067aa0ba902b7))
+    );
+    
+    // Unsupported versions and malformed or all-zero IDs are ignored
+    for header in [
+        "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+        "00-4bf92f3577b34da6-00f067aa0ba902b7-01",
+        "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-000000000000000g-01",
+    ] {
+        assert_eq!(parse_traceparent(header), None, "{}", header);
+    }
+    
+    // Nothing is sent while nothing was recorded or collection is disabled
+    let disabled = Telemetry::new("http://127.0.0.1:9", "engine").with_traces(false).with_metrics(false);
+    disabled.record_span(Span::start("ignored", SpanKind::Server));
+    disabled.add_counter("rows", "1", &[], 1);
+    assert!(disabled.export().is_ok());
+    
+    #[cfg(not(feature = "otlp"))]
+    {
+        let telemetry = Telemetry::new("http://127.0.0.1:9", "engine");
+        telemetry.add_counter("rows", "1", &[], 1);
+        assert!(matches!(telemetry.export(), Err(AppError::Config(_))));
+    }
+    
+    #[cfg(feature = "otlp")]
+    {
+        use rust_data_processing_engine::utils::DURATION_BUCKETS;
+        
+        let (port, requests) = serve_http(vec![(200, serde_json::json!({})), (200, serde_json::json!({})), (400, serde_json::json!({}))]);
+        let telemetry = Telemetry::new(&format!("http://127.0.0.1:{}/", port), "engine");
+        
+        let mut span = Span::start("GET /datasets", SpanKind::Server)
+            .with_parent(0x4bf92f3577b34da6a3ce929d0e0e4736, 0x00f067aa0ba902b7);
+        span.set_attribute("http.response.status_code", 500i64);
+        span.set_error("Internal Server Error");
+        telemetry.record_span(span);
+        
+        telemetry.add_counter("rows", "1", &[("dataset", "people")], 2);
+        telemetry.add_counter("rows", "1", &[("dataset", "people")], 3);
+        telemetry.record_histogram("duration", "s", &DURATION_BUCKETS, &[], 0.02);
+        telemetry.record_histogram("duration", "s", &DURATION_BUCKETS, &[], 20.0);
+        telemetry.observe_gauge("queued", "1", || 7);
+        telemetry.export().unwrap();
+        
+        let received = requests.lock().unwrap().clone();
+        assert_eq!(received.iter().map(|(target, _)| target.as_str()).collect::<Vec<_>>(), vec!["POST /v1/traces", "POST /v1/metrics"]);
+        
+        let traces: serde_json::Value = serde_json::from_str(&received[0].1).unwrap();
+        let exported = &traces["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(exported["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(exported["parentSpanId"], "00f067aa0ba902b7");
+        assert_eq!(exported["kind"], 2);
+        assert_eq!(exported["status"]["code"], 2);
+        assert_eq!(exported["attributes"][0]["value"]["intValue"], "500");
+        assert_eq!(traces["resourceSpans"][0]["resource"]["attributes"][0]["value"]["stringValue"], "engine");
+        
+        // Metrics are sorted by name and cumulative
+        let metrics: serde_json::Value = serde_json::from_str(&received[1].1).unwrap();
+        let metrics = &metrics["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+        let histogram = &metrics[0]["histogram"]["dataPoints"][0];
+        assert_eq!((histogram["count"].as_str(), histogram["sum"].as_f64()), (Some("2"), Some(20.02)));
+        assert_eq!(histogram["bucketCounts"][2], "1");
+        assert_eq!(histogram["bucketCounts"][DURATION_BUCKETS.len()], "1");
+        assert_eq!(metrics[1]["gauge"]["dataPoints"][0]["asInt"], "7");
+        assert_eq!(metrics[2]["sum"]["dataPoints"][0]["asInt"], "5");
+        
+        // Sent spans are dropped, and a rejected export is an error
+        assert!(matches!(telemetry.export(), Err(AppError::Other(_))));
+        assert_eq!(requests.lock().unwrap().last().unwrap().0, "POST /v1/metrics");
+    }
+}
+
+#[test]
+fn test_parquet_downcasting() {
+    use rust_data_processing_engine::data::{ColumnEncoding, DataSource, ParquetCompression, ParquetSink, ParquetSource};
+    use rust_data_processing_engine::storage::{FileFormat, FileStorage};
+    
+    let mut dataset = DataSet::new(Schema::new(vec![
+        Field::new("small".to_string(), DataType::Integer, true),
+        Field::new("medium".to_string(), DataType::Int32, false),
+        Field::new("ratio".to_string(), DataType::Float, false),
+        Field::new("precise".to_string(), DataType::Float, false),
+        Field::new("city".to_string(), DataType::String, false),
+        Field::new("name".to_string(), DataType::String, false),
+        Field::new("active".to_string(), DataType::Boolean, false),
+    ]));
+    for (i, (city, name)) in [("Lisbon", "Ana"), ("Lisbon", "Bia"), ("Porto", "Caio"), ("Porto", "Dani")].into_iter().enumerate() {
+        dataset.add_row(Row::new(vec![
+            if i == 0 { Value::Null } else { Value::Integer(i as i64 - 100) },
+            Value::Integer(i as i64 * 1000),
+            Value::Float(i as f64 * 0.25),
+            Value::Float(i as f64 * 0.1),
+            Value::String(city.to_string()),
+            Value::String(name.to_string()),
+            Value::Boolean(i % 2 == 0),
+        ])).unwrap();
+    }
+    
+    let encoding = |column| ColumnEncoding::analyze(&dataset, column);
+    let narrowed = |physical_type, dictionary| ColumnEncoding { physical_type, dictionary };
+    
+    // Integers take the narrowest width holding their range, ignoring nulls
+    assert_eq!(encoding(0), narrowed(DataType::Int8, true));
+    assert_eq!(encoding(1), narrowed(DataType::Int16, true));
+    
+    // Floats narrow only when every value survives the round trip
+    assert_eq!(encoding(2), narrowed(DataType::Float32, true));
+    assert_eq!(encoding(3), narrowed(DataType::Float, true));
+    
+    // Only strings repeating enough are dictionary encoded
+    assert_eq!(encoding(4), narrowed(DataType::String, true));
+    assert_eq!(encoding(5), narrowed(DataType::String, false));
+    assert_eq!(encoding(6), narrowed(DataType::Boolean, true));
+    
+    // Columns holding values of another type keep their own type
+    let mut mixed = DataSet::new(Schema::new(vec![Field::new("id".to_string(), DataType::Integer, false)]));
+    mixed.data.push(Row::new(vec![Value::Integer(1)]));
+    mixed.data.push(Row::new(vec![Value::String("2".to_string())]));
+    assert_eq!(ColumnEncoding::analyze(&mixed, 0), narrowed(DataType::Integer, true));
+    
+    let dir = tempfile::tempdir().unwrap();
+    let storage = FileStorage::new(dir.path(), FileFormat::Parquet).unwrap().with_downcasting();
+    
+    #[cfg(not(feature = "parquet"))]
+    {
+        assert!(matches!(storage.store("people", &dataset), Err(StorageError::DataError(DataError::NotSupported(_)))));
+        let sink = ParquetSink::new(dir.path().join("direct.parquet"), ParquetCompression::Snappy).with_downcasting();
+        assert!(matches!(sink.write(&dataset), Err(DataError::NotSupported(_))));
+        assert!(matches!(ParquetSource::new(dir.path().join("direct.parquet")).read(), Err(DataError::NotSupported(_))));
+    }
+    
+    // Downcast files read back with the dataset's own schema and values
+    #[cfg(feature = "parquet")]
+    {
+        storage.store("people", &dataset).unwrap();
+        let loaded = storage.load("people").unwrap();
+        assert_eq!(loaded.schema, dataset.schema);
+        for (loaded, original) in loaded.data.iter().zip(&dataset.data) {
+            assert_eq!(loaded.values, original.values);
+        }
+        
+        let sink = ParquetSink::new(dir.path().join("direct.parquet"), ParquetCompression::Snappy).with_downcasting();
+        sink.write(&dataset).unwrap();
+        assert_eq!(ParquetSource::new(dir.path().join("direct.parquet")).read().unwrap().schema, dataset.schema);
+        
+        // Values of another type are still rejected
+        let sink = ParquetSink::new(dir.path().join("mixed.parquet"), ParquetCompression::Snappy).with_downcasting();
+        assert!(sink.write(&mixed).is_err());
+    }
+}
+
+#[test]
+fn test_parquet_nested_types() {
+    use rust_data_processing_engine::data::{DataSource, ParquetCompression, ParquetSink, ParquetSource};
+    
+    let schema = Schema::new(vec![
+        Field::new("id".to_string(), DataType::Integer, false),
+        Field::new("scores".to_string(), DataType::Array(Box::new(DataType::Integer)), true),
+        Field::new("prices".to_string(), DataType::Map(Box::new(DataType::Float)), true),
+        Field::new("payload".to_string(), DataType::Binary, true),
+        Field::new("location".to_string(), DataType::Point, true),
+    ]);
+    let mut dataset = DataSet::new(schema.clone());
+    dataset.add_row(Row::new(vec![
+        Value::Integer(1),
+        Value::Array(vec![Value::Integer(3), Value::Null, Value::Integer(5)]),
+        Value::Map([("eur".to_string(), Value::Float(9.5)), ("usd".to_string(), Value::Float(10.25))].into_iter().collect()),
+        Value::Binary(vec![0, 159, 146, 150]),
+        Value::Point(38.72, -9.14),
+    ])).unwrap();
+    dataset.add_row(Row::new(vec![Value::Integer(2), Value::Array(Vec::new()), Value::Null, Value::Null, Value::Null])).unwrap();
+    
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested.parquet");
+    
+    #[cfg(not(feature = "parquet"))]
+    {
+        assert!(matches!(ParquetSink::new(&path, ParquetCompression::Snappy).write(&dataset), Err(DataError::NotSupported(_))));
+        assert!(matches!(ParquetSource::new(&path).read(), Err(DataError::NotSupported(_))));
+    }
+    
+    // Nested and logical types read back as themselves rather than placeholders
+    #[cfg(feature = "parquet")]
+    {
+        ParquetSink::new(&path, ParquetCompression::Snappy).write(&dataset).unwrap();
+        let loaded = ParquetSource::new(&path).read().unwrap();
+        assert_eq!(loaded.schema, schema);
+        for (loaded, original) in loaded.data.iter().zip(&dataset.data) {
+            assert_eq!(loaded.values, original.values);
+        }
+        
+        // Values that do not match their column's type are rejected
+        let mut mismatched = DataSet::new(schema.clone());
+        mismatched.data.push(Row::new(vec![
+            Value::Integer(3), Value::String("[1]".to_string()), Value::Null, Value::Null, Value::Null,
+        ]));
+        let sink = ParquetSink::new(dir.path().join("mismatched.parquet"), ParquetCompression::Snappy);
+        assert!(matches!(sink.write(&mismatched), Err(DataError::ValidationError(_))));
+    }
+}
+
+#[test]
+fn test_parquet_footer_reads() {
+    use rust_data_processing_engine::data::ParquetSource;
+    use rust_data_processing_engine::storage::{FileFormat, FileStorage};
+    
+    let dir = tempfile::tempdir().unwrap();
+    let storage = FileStorage::new(dir.path(), FileFormat::Parquet).unwrap();
+    
+    #[cfg(not(feature = "parquet"))]
+    {
+        std::fs::write(dir.path().join("empty.parquet"), b"PAR1").unwrap();
+        let source = ParquetSource::new(dir.path().join("empty.parquet"));
+        assert!(matches!(source.schema(), Err(DataError::NotSupported(_))));
+        assert!(matches!(source.count(), Err(DataError::NotSupported(_))));
+        assert!(matches!(storage.load_head("empty", 0), Err(StorageError::DataError(DataError::NotSupported(_)))));
+    }
+    
+    #[cfg(feature = "parquet")]
+    {
+        use rust_data_processing_engine::data::DataSource;
+        
+        let schema = Schema::new(vec![
+            Field::new("id".to_string(), DataType::Integer, false),
+            Field::new("tags".to_string(), DataType::Array(Box::new(DataType::String)), true),
+            Field::new("prices".to_string(), DataType::Map(Box::new(DataType::Float)), true),
+        ]);
+        
+        // Empty datasets keep their schema
+        storage.store("empty", &DataSet::new(schema.clone())).unwrap();
+        let source = ParquetSource::new(dir.path().join("empty.parquet"));
+        assert_eq!(source.schema().unwrap(), schema);
+        let loaded = source.read().unwrap();
+        assert_eq!((loaded.len(), &loaded.schema), (0, &schema));
+        
+        // Schemas alone are read from the footer, and nested columns count once
+        let mut dataset = DataSet::new(schema.clone());
+        dataset.add_row(Row::new(vec![
+            Value::Integer(1),
+            Value::Array(vec![Value::String("new".to_string())]),
+            Value::Map([("eur".to_string(), Value::Float(2.5))].into_iter().collect()),
+        ])).unwrap();
+        storage.store("tagged", &dataset).unwrap();
+        let head = storage.load_head("tagged", 0).unwrap();
+        assert_eq!((head.len(), &head.schema), (0, &schema));
+        let info = storage.info("tagged").unwrap();
+        assert_eq!((info.rows, info.columns), (1, 3));
+        
+        // Missing projected columns and files that are not Parquet are errors
+        let projected = ParquetSource::new(dir.path().join("tagged.parquet")).with_columns(vec!["missing".to_string()]);
+        assert!(matches!(projected.schema(), Err(DataError::ValidationError(_))));
+        std::fs::write(dir.path().join("broken.parquet"), b"PAR1").unwrap();
+        assert!(ParquetSource::new(dir.path().join("broken.parquet")).schema().is_err());
+    }
+}